@@ -0,0 +1,8 @@
+//! HTTP API types and helpers shared by the bot, the payment service and
+//! the registration proxy.
+
+mod auth;
+mod page;
+
+pub use auth::constant_time_eq;
+pub use page::{PageParams, DEFAULT_PAGE_SIZE, MAX_PAGE_SIZE};
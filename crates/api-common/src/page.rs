@@ -0,0 +1,81 @@
+//! Pagination query parameters.
+
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+
+/// Default number of items per page.
+pub const DEFAULT_PAGE_SIZE: usize = 50;
+
+/// Largest page a client may request.
+pub const MAX_PAGE_SIZE: usize = 500;
+
+/// Pagination query parameters (`?limit=&offset=&since=`).
+#[derive(Debug, Default, Clone, Deserialize)]
+pub struct PageParams {
+    /// Items per page (default 50, capped at 500).
+    pub limit: Option<usize>,
+    /// Number of items to skip.
+    #[serde(default)]
+    pub offset: usize,
+    /// Only include items at or after this time (RFC 3339).
+    #[serde(default)]
+    pub since: Option<DateTime<Utc>>,
+}
+
+impl PageParams {
+    /// Effective page size.
+    pub fn limit(&self) -> usize {
+        self.limit.unwrap_or(DEFAULT_PAGE_SIZE).min(MAX_PAGE_SIZE)
+    }
+
+    /// Whether the client asked for pagination at all.
+    ///
+    /// Endpoints that returned everything before they were paginated use
+    /// this to keep answering unpaginated requests the old way.
+    pub fn is_requested(&self) -> bool {
+        self.limit.is_some() || self.offset > 0 || self.since.is_some()
+    }
+
+    /// Slice one page out of `items`.
+    pub fn apply<T>(&self, items: impl IntoIterator<Item = T>) -> Vec<T> {
+        items.into_iter().skip(self.offset).take(self.limit()).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apply() {
+        let items: Vec<u32> = (0..10).collect();
+
+        let page = PageParams { limit: Some(3), offset: 4, since: None };
+        assert_eq!(page.apply(items.clone()), vec![4, 5, 6]);
+
+        let past_end = PageParams { limit: Some(3), offset: 20, since: None };
+        assert!(past_end.apply(items.clone()).is_empty());
+
+        let default = PageParams::default();
+        assert_eq!(default.limit(), DEFAULT_PAGE_SIZE);
+        assert_eq!(default.apply(items).len(), 10);
+
+        let huge = PageParams { limit: Some(1_000_000), offset: 0, since: None };
+        assert_eq!(huge.limit(), MAX_PAGE_SIZE);
+    }
+
+    #[test]
+    fn test_is_requested() {
+        assert!(!PageParams::default().is_requested());
+        assert!(PageParams { limit: Some(10), ..Default::default() }.is_requested());
+        assert!(PageParams { offset: 5, ..Default::default() }.is_requested());
+    }
+
+    #[test]
+    fn test_deserialize_since() {
+        let page: PageParams =
+            serde_json::from_str(r#"{"since":"2026-01-01T00:00:00Z"}"#).unwrap();
+        assert_eq!(page.since.unwrap().to_rfc3339(), "2026-01-01T00:00:00+00:00");
+        assert_eq!(page.offset, 0);
+    }
+}
@@ -6,4 +6,7 @@ use thiserror::Error;
 pub enum ConversationError {
     #[error("Serialization error: {0}")]
     Serialization(#[from] serde_json::Error),
+
+    #[error("Already {0} pinned messages, the most allowed")]
+    TooManyPins(usize),
 }
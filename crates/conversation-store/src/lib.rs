@@ -8,7 +8,7 @@ mod store;
 mod types;
 
 pub use error::ConversationError;
-pub use store::ConversationStore;
+pub use store::{ConversationStore, MAX_SEARCH_RESULTS};
 pub use types::*;
 
 #[cfg(test)]
@@ -118,6 +118,158 @@ mod tests {
         assert_eq!(conv.messages[0].content, Some("Message 1".into()));
     }
 
+    #[test]
+    fn test_conversation_replace_oldest_with_summary() {
+        let mut conv = Conversation::new("user123", None);
+        conv.add_message("user", "Message 1");
+        conv.messages.push(StoredMessage::with_tool_calls(
+            "assistant",
+            None,
+            vec![StoredToolCall { id: "call-1".into(), name: "calculate".into(), arguments: "{}".into() }],
+        ));
+        conv.messages.push(StoredMessage::tool_result("call-1", "4"));
+        conv.add_message("assistant", "Reply 1");
+        conv.add_message("user", "Message 2");
+
+        // The tool result goes with the call it answers
+        assert_eq!(conv.replace_oldest_with_summary(2, "asked for 2+2"), 3);
+        assert_eq!(conv.messages.len(), 3);
+        assert_eq!(conv.messages[0].role, "system");
+        assert_eq!(
+            conv.messages[0].content.as_deref(),
+            Some("Summary of the earlier conversation: asked for 2+2")
+        );
+        assert_eq!(conv.messages[1].content, Some("Reply 1".into()));
+
+        // An earlier summary is folded into the next one
+        assert_eq!(conv.replace_oldest_with_summary(2, "asked for 2+2, got 4"), 2);
+        assert_eq!(conv.messages.len(), 2);
+        assert_eq!(conv.messages[1].content, Some("Message 2".into()));
+
+        // The newest message is the one being answered, so it stays
+        assert_eq!(conv.replace_oldest_with_summary(10, "all"), 1);
+        assert_eq!(conv.messages.len(), 2);
+        assert_eq!(conv.messages[1].content, Some("Message 2".into()));
+        assert_eq!(Conversation::new("user456", None).replace_oldest_with_summary(5, "none"), 0);
+    }
+
+    #[test]
+    fn test_conversation_summary_keeps_newest_message() {
+        let mut conv = Conversation::new("user123", None);
+        for i in 1..=3 {
+            conv.add_message("user", &format!("Message {}", i));
+        }
+
+        // A batch larger than the conversation still leaves the newest
+        assert_eq!(conv.summary_end(20), 2);
+        assert_eq!(conv.replace_oldest_with_summary(20, "summary"), 2);
+        assert_eq!(conv.messages[1].content, Some("Message 3".into()));
+
+        // When the newest is a tool result, its call stays with it
+        let mut conv = Conversation::new("user123", None);
+        conv.add_message("user", "Message 1");
+        conv.messages.push(StoredMessage::with_tool_calls(
+            "assistant",
+            None,
+            vec![StoredToolCall { id: "call-1".into(), name: "calculate".into(), arguments: "{}".into() }],
+        ));
+        conv.messages.push(StoredMessage::tool_result("call-1", "4"));
+        assert_eq!(conv.summary_end(20), 1);
+        assert_eq!(conv.summary_end(0), 0);
+        assert_eq!(Conversation::new("user456", None).summary_end(5), 0);
+    }
+
+    #[test]
+    fn test_conversation_trim_keeps_pinned_messages() {
+        let mut conv = Conversation::new("user123", None);
+        for i in 1..=8 {
+            conv.add_message("user", &format!("Message {}", i));
+        }
+        conv.messages[1].pinned = true;
+        conv.messages[4].pinned = true;
+
+        conv.trim(4);
+
+        // Oldest unpinned go first; pinned ones keep their place
+        let contents: Vec<_> = conv.messages.iter().filter_map(|m| m.content.as_deref()).collect();
+        assert_eq!(contents, ["Message 2", "Message 5", "Message 7", "Message 8"]);
+    }
+
+    #[test]
+    fn test_conversation_trim_drops_tool_results_with_their_call() {
+        let mut conv = Conversation::new("user123", None);
+        conv.add_message("user", "What's 2+2?");
+        conv.messages.push(StoredMessage::with_tool_calls(
+            "assistant",
+            None,
+            vec![StoredToolCall { id: "call-1".into(), name: "calculate".into(), arguments: "{}".into() }],
+        ));
+        conv.messages.push(StoredMessage::tool_result("call-1", "4"));
+        conv.add_message("assistant", "4");
+        conv.add_message("user", "Thanks");
+
+        // Dropping two takes the call, so its result goes too
+        conv.trim(3);
+        let roles: Vec<&str> = conv.messages.iter().map(|m| m.role.as_str()).collect();
+        assert_eq!(roles, ["assistant", "user"]);
+        assert_eq!(conv.messages[0].content.as_deref(), Some("4"));
+
+        // A call that stays keeps its results
+        let mut conv = Conversation::new("user123", None);
+        conv.add_message("user", "Hi");
+        conv.add_message("user", "What's 2+2?");
+        conv.messages.push(StoredMessage::with_tool_calls(
+            "assistant",
+            None,
+            vec![StoredToolCall { id: "call-1".into(), name: "calculate".into(), arguments: "{}".into() }],
+        ));
+        conv.messages.push(StoredMessage::tool_result("call-1", "4"));
+        conv.trim(3);
+        let roles: Vec<&str> = conv.messages.iter().map(|m| m.role.as_str()).collect();
+        assert_eq!(roles, ["user", "assistant", "tool"]);
+    }
+
+    #[test]
+    fn test_conversation_trim_all_pinned_over_limit() {
+        let mut conv = Conversation::new("user123", None);
+        for i in 1..=4 {
+            conv.add_message("user", &format!("Message {}", i));
+        }
+        conv.messages[0].pinned = true;
+        conv.messages[2].pinned = true;
+        conv.messages[3].pinned = true;
+
+        conv.trim(2);
+
+        // Only the unpinned message can go, leaving the conversation over the limit
+        let contents: Vec<_> = conv.messages.iter().filter_map(|m| m.content.as_deref()).collect();
+        assert_eq!(contents, ["Message 1", "Message 3", "Message 4"]);
+    }
+
+    #[test]
+    fn test_conversation_summary_keeps_pinned_messages() {
+        let mut conv = Conversation::new("user123", None);
+        for i in 1..=4 {
+            conv.add_message("user", &format!("Message {}", i));
+        }
+        conv.messages[1].pinned = true;
+
+        assert_eq!(conv.replace_oldest_with_summary(3, "summary"), 2);
+        let contents: Vec<_> = conv.messages.iter().filter_map(|m| m.content.as_deref()).collect();
+        assert_eq!(
+            contents,
+            ["Summary of the earlier conversation: summary", "Message 2", "Message 4"]
+        );
+
+        // Nothing but pins to summarize
+        let mut conv = Conversation::new("user123", None);
+        conv.add_message("user", "Message 1");
+        conv.messages[0].pinned = true;
+        assert_eq!(conv.replace_oldest_with_summary(1, "summary"), 0);
+        assert_eq!(conv.messages.len(), 1);
+        assert!(conv.messages[0].pinned);
+    }
+
     #[test]
     fn test_conversation_serialization() {
         let mut conv = Conversation::new("user123", Some("System prompt".into()));
@@ -251,6 +403,299 @@ mod tests {
         assert_eq!(count, 3);
     }
 
+    #[tokio::test]
+    async fn test_store_stats() {
+        let store = ConversationStore::new(100, Duration::from_secs(3600));
+        assert_eq!(store.stats().await, ConversationStats::default());
+
+        store.add_message("user1", "user", "Hello", None).await.unwrap();
+        store.add_message("user1", "assistant", "Hi!", None).await.unwrap();
+        store.add_message("user1", "user", "Bye", None).await.unwrap();
+        store.add_message("user2", "user", "Hey", None).await.unwrap();
+
+        let stats = store.stats().await;
+        assert_eq!(stats.conversations, 2);
+        assert_eq!(stats.active_last_hour, 2);
+        assert_eq!(stats.total_messages, 4);
+        assert_eq!(stats.avg_messages_per_conversation, 2.0);
+        assert_eq!(stats.prompt_overrides, 0);
+    }
+
+    #[tokio::test]
+    async fn test_store_reap_expired() {
+        let store = ConversationStore::new(100, Duration::from_millis(50));
+        store.add_message("user1", "user", "Hello", None).await.unwrap();
+        assert_eq!(store.reap_expired().await, 0);
+
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        store.add_message("user2", "user", "Hi", None).await.unwrap();
+
+        // Only the idle conversation is evicted
+        assert_eq!(store.reap_expired().await, 1);
+        assert_eq!(store.reap_expired().await, 0);
+        assert!(store.get("user2").await.unwrap().is_some());
+        assert_eq!(store.stats().await.reaped_total, 1);
+    }
+
+    #[tokio::test]
+    async fn test_store_prompt_override() {
+        let store = ConversationStore::new(100, Duration::from_secs(3600));
+        assert!(store.prompt_override("user1").await.is_none());
+
+        store.set_prompt_override("user1", "Talk like a pirate").await;
+        store.add_message("user1", "user", "Hello", None).await.unwrap();
+        assert_eq!(store.stats().await.prompt_overrides, 1);
+
+        // Clearing the history keeps the override
+        store.clear("user1").await.unwrap();
+        assert_eq!(store.prompt_override("user1").await.as_deref(), Some("Talk like a pirate"));
+
+        assert!(store.clear_prompt_override("user1").await);
+        assert!(!store.clear_prompt_override("user1").await);
+        assert!(store.prompt_override("user1").await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_prompt_override_expires_with_conversation() {
+        let store = ConversationStore::new(100, Duration::from_millis(100));
+
+        store.set_prompt_override("user1", "Talk like a pirate").await;
+        store.set_prompt_override("user2", "Be brief").await;
+        tokio::time::sleep(Duration::from_millis(60)).await;
+
+        // Activity keeps user1's override alive past its original expiry
+        store.add_message("user1", "user", "Hello", None).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(60)).await;
+
+        assert!(store.prompt_override("user1").await.is_some());
+        assert!(store.prompt_override("user2").await.is_none());
+        assert!(!store.clear_prompt_override("user2").await);
+
+        store.reap_expired().await;
+        assert_eq!(store.stats().await.prompt_overrides, 1);
+    }
+
+    #[tokio::test]
+    async fn test_system_prompt_override_expires_with_conversation() {
+        let store = ConversationStore::new(100, Duration::from_millis(100));
+
+        store.set_system_prompt_override("user1", "Be terse").await;
+        store.set_system_prompt_override("user2", "Be verbose").await;
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        store.add_message("user1", "user", "Hello", None).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(60)).await;
+
+        assert_eq!(store.system_prompt_override("user1").await.as_deref(), Some("Be terse"));
+        assert!(store.system_prompt_override("user2").await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_model_override_expires_with_conversation() {
+        let store = ConversationStore::new(100, Duration::from_millis(100));
+
+        store.set_model_override("user1", "model-a").await;
+        store.set_model_override("user2", "model-b").await;
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        store.add_message("user1", "user", "Hello", None).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(60)).await;
+
+        assert_eq!(store.model_override("user1").await.as_deref(), Some("model-a"));
+        assert!(store.model_override("user2").await.is_none());
+        assert!(!store.clear_model_override("user2").await);
+    }
+
+    #[tokio::test]
+    async fn test_store_system_prompt_override() {
+        let store = ConversationStore::new(100, Duration::from_secs(3600));
+        assert!(store.system_prompt_override("user1").await.is_none());
+
+        store.set_system_prompt_override("user1", "You are a terse assistant").await;
+        store.set_prompt_override("user1", "Talk like a pirate").await;
+        store.clear("user1").await.unwrap();
+        assert_eq!(
+            store.system_prompt_override("user1").await.as_deref(),
+            Some("You are a terse assistant")
+        );
+
+        // The two overrides are independent
+        assert!(store.clear_system_prompt_override("user1").await);
+        assert!(!store.clear_system_prompt_override("user1").await);
+        assert!(store.system_prompt_override("user1").await.is_none());
+        assert!(store.prompt_override("user1").await.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_store_model_override() {
+        let store = ConversationStore::new(100, Duration::from_secs(3600));
+        assert!(store.model_override("user1").await.is_none());
+
+        store.set_model_override("user1", "zai-org/GLM-4.6").await;
+        store.add_message("user1", "user", "Hello", None).await.unwrap();
+        store.clear("user1").await.unwrap();
+        assert_eq!(store.model_override("user1").await.as_deref(), Some("zai-org/GLM-4.6"));
+        assert!(store.model_override("user2").await.is_none());
+
+        assert!(store.clear_model_override("user1").await);
+        assert!(!store.clear_model_override("user1").await);
+        assert!(store.model_override("user1").await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_store_retention_hard_cap() {
+        let store =
+            ConversationStore::new_with_retention(100, Duration::from_secs(3600), Duration::from_millis(100));
+
+        store.add_signal_message("user1", "Old", 1000, None).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        store.add_message("user1", "user", "New", None).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(60)).await;
+
+        // Activity kept the conversation alive, but not its oldest message
+        let conv = store.get("user1").await.unwrap().unwrap();
+        assert_eq!(conv.messages.len(), 1);
+        assert_eq!(conv.messages[0].content, Some("New".into()));
+        assert!(!store.edit_message("user1", 1000, "Edited").await.unwrap());
+        assert_eq!(store.stats().await.total_messages, 1);
+
+        // Once every message is past the cap the conversation is gone
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        assert!(store.get("user1").await.unwrap().is_none());
+        assert!(store.to_openai_messages("user1", None).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_store_retention_drops_orphaned_tool_results() {
+        let store =
+            ConversationStore::new_with_retention(100, Duration::from_secs(3600), Duration::from_millis(100));
+
+        let call = StoredToolCall {
+            id: "call-1".into(),
+            name: "calculate".into(),
+            arguments: "{}".into(),
+        };
+        store.add_assistant_with_tools("user1", None, &[call]).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        store.add_tool_result("user1", "call-1", "4").await.unwrap();
+        store.add_message("user1", "assistant", "It's 4", None).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(60)).await;
+
+        let conv = store.get("user1").await.unwrap().unwrap();
+        assert_eq!(conv.messages.len(), 1);
+        assert_eq!(conv.messages[0].role, "assistant");
+    }
+
+    #[tokio::test]
+    async fn test_store_replace_oldest_with_summary() {
+        let store = ConversationStore::new(100, Duration::from_secs(3600));
+        for i in 1..=5 {
+            store.add_message("user1", "user", &format!("Message {}", i), None).await.unwrap();
+        }
+
+        let oldest = store.get("user1").await.unwrap().unwrap().messages[0].timestamp;
+
+        // Trimmed since the summarized messages were read: nothing replaced
+        let moved_on = oldest - chrono::Duration::seconds(1);
+        assert_eq!(store.replace_oldest_with_summary("user1", 3, "stale", moved_on).await.unwrap(), 0);
+        assert_eq!(store.message_count("user1").await.unwrap(), 5);
+
+        assert_eq!(store.replace_oldest_with_summary("user1", 3, "three messages", oldest).await.unwrap(), 3);
+        // The summary is folded into the system prompt
+        let messages = store.to_openai_messages("user1", Some("prompt")).await.unwrap();
+        let roles: Vec<&str> = messages.iter().map(|m| m.role.as_str()).collect();
+        assert_eq!(roles, ["system", "user", "user"]);
+        assert_eq!(
+            messages[0].content.as_deref(),
+            Some("prompt\n\nSummary of the earlier conversation: three messages")
+        );
+        assert_eq!(messages[1].content, Some("Message 4".into()));
+
+        let messages = store.to_openai_messages("user1", None).await.unwrap();
+        assert_eq!(messages[0].content.as_deref(), Some("Summary of the earlier conversation: three messages"));
+
+        assert_eq!(store.replace_oldest_with_summary("nobody", 3, "none", oldest).await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_store_search() {
+        let store = ConversationStore::new(100, Duration::from_secs(3600));
+        store.add_message("user1", "user", "My project is a Rust compiler for robots", None).await.unwrap();
+        store.add_message("user1", "assistant", "Sounds fun! What targets does it support?", None).await.unwrap();
+        store.add_message("user1", "user", "ARM only for now", None).await.unwrap();
+        store.add_tool_result("user1", "call-1", "project robots").await.unwrap();
+        store.add_message("user2", "user", "My project is secret", None).await.unwrap();
+
+        // Substring, ignoring case
+        let hits = store.search("user1", "PROJECT").await;
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].content.as_deref(), Some("My project is a Rust compiler for robots"));
+
+        // Every word, in any order
+        assert_eq!(store.search("user1", "robots rust").await.len(), 1);
+        assert_eq!(store.search("user1", "arm targets").await.len(), 0);
+
+        assert!(store.search("user1", "python").await.is_empty());
+        assert!(store.search("user1", "   ").await.is_empty());
+        assert!(store.search("nobody", "project").await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_store_search_keeps_most_recent_matches() {
+        let store = ConversationStore::new(100, Duration::from_secs(3600));
+        for i in 0..MAX_SEARCH_RESULTS + 5 {
+            store.add_message("user1", "user", &format!("note {}", i), None).await.unwrap();
+        }
+
+        let hits = store.search("user1", "note").await;
+        assert_eq!(hits.len(), MAX_SEARCH_RESULTS);
+        assert_eq!(hits[0].content.as_deref(), Some("note 5"));
+        assert_eq!(hits.last().unwrap().content.as_deref(), Some(&*format!("note {}", MAX_SEARCH_RESULTS + 4)));
+    }
+
+    #[tokio::test]
+    async fn test_store_pinned_message_survives_trimming() {
+        let store = ConversationStore::new(3, Duration::from_secs(3600));
+        store.add_message("user1", "user", "I'm vegetarian", None).await.unwrap();
+        store.add_message("user1", "assistant", "Noted!", None).await.unwrap();
+
+        let pinned = store.pin_last_user_message("user1").await.unwrap().unwrap();
+        assert_eq!(pinned.content.as_deref(), Some("I'm vegetarian"));
+
+        for i in 1..=4 {
+            store.add_message("user1", "user", &format!("Message {}", i), None).await.unwrap();
+        }
+        let conv = store.get("user1").await.unwrap().unwrap();
+        let contents: Vec<_> = conv.messages.iter().filter_map(|m| m.content.as_deref()).collect();
+        assert_eq!(contents, ["I'm vegetarian", "Message 3", "Message 4"]);
+
+        // Unpinning lets it be trimmed
+        assert_eq!(store.unpin_all("user1").await.unwrap(), 1);
+        store.add_message("user1", "user", "Message 5", None).await.unwrap();
+        let conv = store.get("user1").await.unwrap().unwrap();
+        assert_eq!(conv.messages[0].content.as_deref(), Some("Message 3"));
+
+        assert!(store.pin_last_user_message("nobody").await.unwrap().is_none());
+        assert_eq!(store.unpin_all("nobody").await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_store_caps_pins() {
+        let store = ConversationStore::new(4, Duration::from_secs(3600));
+        assert_eq!(store.max_pins(), 2);
+
+        for i in 1..=2 {
+            store.add_message("user1", "user", &format!("Message {}", i), None).await.unwrap();
+            store.pin_last_user_message("user1").await.unwrap();
+        }
+        // Re-pinning an already pinned message is fine at the cap
+        store.pin_last_user_message("user1").await.unwrap().unwrap();
+
+        store.add_message("user1", "user", "Message 3", None).await.unwrap();
+        let err = store.pin_last_user_message("user1").await.unwrap_err();
+        assert!(matches!(err, ConversationError::TooManyPins(2)));
+        let conv = store.get("user1").await.unwrap().unwrap();
+        assert!(!conv.messages[2].pinned);
+    }
+
     #[tokio::test]
     async fn test_store_health_check() {
         let store = ConversationStore::new(100, Duration::from_secs(3600));
@@ -455,4 +900,21 @@ mod tests {
         assert_eq!(messages[3].role, "tool");
         assert_eq!(messages[3].tool_call_id, Some("call-1".into()));
     }
+
+    #[tokio::test]
+    async fn test_store_edit_signal_message() {
+        let store = ConversationStore::new(100, Duration::from_secs(3600));
+
+        store.add_signal_message("user1", "Waht is 2+2?", 1000, None).await.unwrap();
+        store.add_message("user1", "assistant", "4", None).await.unwrap();
+
+        assert!(store.edit_message("user1", 1000, "What is 2+2?").await.unwrap());
+        assert!(!store.edit_message("user1", 2000, "Unknown").await.unwrap());
+        assert!(!store.edit_message("user2", 1000, "Unknown").await.unwrap());
+
+        let conv = store.get("user1").await.unwrap().unwrap();
+        assert_eq!(conv.messages.len(), 2);
+        assert_eq!(conv.messages[0].content, Some("What is 2+2?".into()));
+        assert_eq!(conv.messages[0].source_timestamp, Some(1000));
+    }
 }
@@ -2,12 +2,20 @@
 
 use crate::error::ConversationError;
 use crate::types::*;
+use chrono::{DateTime, Utc};
 use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 use std::time::Duration;
 use tokio::sync::RwLock;
 use tracing::{debug, info, instrument};
 
+/// Longest gap between cleanup passes.
+const CLEANUP_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Most messages [`ConversationStore::search`] returns.
+pub const MAX_SEARCH_RESULTS: usize = 10;
+
 /// Entry in the conversation store with expiration tracking.
 struct ConversationEntry {
     conversation: Conversation,
@@ -23,6 +31,18 @@ pub struct ConversationStore {
     conversations: Arc<RwLock<HashMap<String, ConversationEntry>>>,
     max_messages: usize,
     ttl: Duration,
+    /// Hard cap on a message's age, regardless of conversation activity.
+    max_message_age: Option<Duration>,
+    /// User-set system prompts, by conversation. Kept apart from the
+    /// history so they survive `clear`, but expire with the conversation.
+    prompt_overrides: Overrides,
+    /// User-set replacements for the base system prompt, by conversation,
+    /// kept like `prompt_overrides`.
+    system_prompt_overrides: Overrides,
+    /// User-chosen models, by conversation, kept like `prompt_overrides`.
+    model_overrides: Overrides,
+    /// Conversations evicted by `reap_expired` since startup.
+    reaped_total: Arc<AtomicU64>,
 }
 
 impl ConversationStore {
@@ -30,10 +50,28 @@ impl ConversationStore {
     ///
     /// Spawns a background task to periodically clean up expired conversations.
     pub fn new(max_messages: usize, ttl: Duration) -> Self {
+        Self::build(max_messages, ttl, None)
+    }
+
+    /// Create a store that also never retains a message longer than `hard_cap`.
+    ///
+    /// The sliding `ttl` still expires idle conversations, but activity no
+    /// longer keeps old messages alive: each message is dropped `hard_cap`
+    /// after it was stored, and a conversation left empty is evicted.
+    pub fn new_with_retention(max_messages: usize, ttl: Duration, hard_cap: Duration) -> Self {
+        Self::build(max_messages, ttl, Some(hard_cap))
+    }
+
+    fn build(max_messages: usize, ttl: Duration, max_message_age: Option<Duration>) -> Self {
         let store = Self {
             conversations: Arc::new(RwLock::new(HashMap::new())),
             max_messages,
             ttl,
+            max_message_age,
+            prompt_overrides: Overrides::default(),
+            system_prompt_overrides: Overrides::default(),
+            model_overrides: Overrides::default(),
+            reaped_total: Arc::new(AtomicU64::new(0)),
         };
 
         // Spawn cleanup task
@@ -43,8 +81,8 @@ impl ConversationStore {
         });
 
         info!(
-            "In-memory conversation store initialized (max_messages={}, ttl={:?})",
-            max_messages, ttl
+            "In-memory conversation store initialized (max_messages={}, ttl={:?}, max_message_age={:?})",
+            max_messages, ttl, max_message_age
         );
 
         store
@@ -52,22 +90,53 @@ impl ConversationStore {
 
     /// Background task that periodically removes expired conversations.
     async fn cleanup_loop(&self) {
-        let cleanup_interval = Duration::from_secs(60); // Check every minute
+        // Check every minute, or often enough to honor the message age cap
+        let cleanup_interval = self
+            .max_message_age
+            .map_or(CLEANUP_INTERVAL, |age| age.clamp(Duration::from_secs(1), CLEANUP_INTERVAL));
 
         loop {
             tokio::time::sleep(cleanup_interval).await;
+            self.reap_expired().await;
+        }
+    }
 
-            let now = std::time::Instant::now();
-            let mut conversations = self.conversations.write().await;
-            let before_count = conversations.len();
-
-            conversations.retain(|_, entry| entry.expires_at > now);
-
-            let removed = before_count - conversations.len();
-            if removed > 0 {
-                debug!("Cleaned up {} expired conversations", removed);
+    /// Evict conversations past their TTL (or emptied by the message age
+    /// cap), returning how many were removed.
+    ///
+    /// Reads already skip expired entries; this frees their memory. The
+    /// background cleanup task calls it periodically.
+    pub async fn reap_expired(&self) -> usize {
+        let now = std::time::Instant::now();
+        let cutoff = self.retention_cutoff();
+        let mut conversations = self.conversations.write().await;
+        let before_count = conversations.len();
+
+        conversations.retain(|_, entry| {
+            if let Some(cutoff) = cutoff {
+                prune_before(&mut entry.conversation, cutoff);
+                if entry.conversation.messages.is_empty() {
+                    return false;
+                }
             }
+            entry.expires_at > now
+        });
+
+        let removed = before_count - conversations.len();
+        if removed > 0 {
+            self.reaped_total.fetch_add(removed as u64, Ordering::Relaxed);
+            debug!(
+                "Reaped {} expired conversations ({} remaining)",
+                removed,
+                conversations.len()
+            );
         }
+        drop(conversations);
+
+        self.prompt_overrides.reap(now).await;
+        self.system_prompt_overrides.reap(now).await;
+        self.model_overrides.reap(now).await;
+        removed
     }
 
     /// Get conversation for a user.
@@ -76,10 +145,28 @@ impl ConversationStore {
         let conversations = self.conversations.read().await;
         let now = std::time::Instant::now();
 
-        Ok(conversations
+        let Some(mut conversation) = conversations
             .get(user_id)
             .filter(|entry| entry.expires_at > now)
-            .map(|entry| entry.conversation.clone()))
+            .map(|entry| entry.conversation.clone())
+        else {
+            return Ok(None);
+        };
+
+        if let Some(cutoff) = self.retention_cutoff() {
+            prune_before(&mut conversation, cutoff);
+            if conversation.messages.is_empty() {
+                return Ok(None);
+            }
+        }
+
+        Ok(Some(conversation))
+    }
+
+    /// Oldest creation time a message may have and still be kept.
+    fn retention_cutoff(&self) -> Option<DateTime<Utc>> {
+        let age = chrono::Duration::from_std(self.max_message_age?).ok()?;
+        Utc::now().checked_sub_signed(age)
     }
 
     /// Add a message to a conversation, creating if needed.
@@ -91,38 +178,184 @@ impl ConversationStore {
         content: &str,
         system_prompt: Option<&str>,
     ) -> Result<Conversation, ConversationError> {
+        self.add_message_internal(user_id, StoredMessage::new(role, content), system_prompt)
+            .await
+    }
+
+    /// Add a user message received from Signal, remembering its timestamp
+    /// so a later edit of that message can replace it.
+    #[instrument(skip(self, content))]
+    pub async fn add_signal_message(
+        &self,
+        user_id: &str,
+        content: &str,
+        source_timestamp: i64,
+        system_prompt: Option<&str>,
+    ) -> Result<Conversation, ConversationError> {
+        let message = StoredMessage::new("user", content).with_source_timestamp(source_timestamp);
+        self.add_message_internal(user_id, message, system_prompt).await
+    }
+
+    /// Replace the content of the message received with `source_timestamp`.
+    ///
+    /// Returns `false` if no such message is stored (e.g. it was trimmed or
+    /// the conversation expired).
+    #[instrument(skip(self, content))]
+    pub async fn edit_message(
+        &self,
+        user_id: &str,
+        source_timestamp: i64,
+        content: &str,
+    ) -> Result<bool, ConversationError> {
         let mut conversations = self.conversations.write().await;
         let now = std::time::Instant::now();
-        let expires_at = now + self.ttl;
 
-        let entry = conversations
-            .entry(user_id.to_string())
-            .or_insert_with(|| ConversationEntry {
-                conversation: Conversation::new(user_id, system_prompt.map(String::from)),
-                expires_at,
-            });
+        let Some(entry) = conversations
+            .get_mut(user_id)
+            .filter(|entry| entry.expires_at > now)
+        else {
+            return Ok(false);
+        };
+        if let Some(cutoff) = self.retention_cutoff() {
+            prune_before(&mut entry.conversation, cutoff);
+        }
 
-        // Update expiration on activity
-        entry.expires_at = expires_at;
+        let Some(message) = entry
+            .conversation
+            .messages
+            .iter_mut()
+            .find(|m| m.source_timestamp == Some(source_timestamp))
+        else {
+            return Ok(false);
+        };
 
-        // Update system prompt if provided
-        if let Some(prompt) = system_prompt {
-            entry.conversation.system_prompt = Some(prompt.to_string());
+        message.content = Some(content.to_string());
+        entry.conversation.updated_at = chrono::Utc::now();
+        entry.expires_at = now + self.ttl;
+
+        debug!("Edited message {} for {}", source_timestamp, user_id);
+        Ok(true)
+    }
+
+    /// Most messages a conversation may have pinned: half the message limit
+    /// (at least one), so pins can't crowd out the rest of the history.
+    pub fn max_pins(&self) -> usize {
+        (self.max_messages / 2).max(1)
+    }
+
+    /// Pin the most recent user message so trimming keeps it.
+    ///
+    /// Returns the pinned message, or `None` if the conversation has no user
+    /// message. Fails with [`ConversationError::TooManyPins`] when
+    /// [`ConversationStore::max_pins`] messages are already pinned.
+    #[instrument(skip(self))]
+    pub async fn pin_last_user_message(&self, user_id: &str) -> Result<Option<StoredMessage>, ConversationError> {
+        let mut conversations = self.conversations.write().await;
+        let now = std::time::Instant::now();
+
+        let Some(entry) = conversations
+            .get_mut(user_id)
+            .filter(|entry| entry.expires_at > now)
+        else {
+            return Ok(None);
+        };
+        if let Some(cutoff) = self.retention_cutoff() {
+            prune_before(&mut entry.conversation, cutoff);
         }
 
-        // Add the message
-        entry.conversation.add_message(role, content);
+        let Some(message) = entry
+            .conversation
+            .messages
+            .iter_mut()
+            .rev()
+            .find(|m| m.role == "user")
+        else {
+            return Ok(None);
+        };
+        if message.pinned {
+            return Ok(Some(message.clone()));
+        }
+        let pinned = entry.conversation.messages.iter().filter(|m| m.pinned).count();
+        if pinned >= self.max_pins() {
+            return Err(ConversationError::TooManyPins(pinned));
+        }
 
-        // Trim old messages
+        let Some(message) = entry
+            .conversation
+            .messages
+            .iter_mut()
+            .rev()
+            .find(|m| m.role == "user")
+        else {
+            return Ok(None);
+        };
+        message.pinned = true;
+
+        debug!("Pinned a message for {}", user_id);
+        Ok(Some(message.clone()))
+    }
+
+    /// Unpin every message in a conversation, returning how many were pinned.
+    #[instrument(skip(self))]
+    pub async fn unpin_all(&self, user_id: &str) -> Result<usize, ConversationError> {
+        let mut conversations = self.conversations.write().await;
+        let now = std::time::Instant::now();
+
+        let Some(entry) = conversations
+            .get_mut(user_id)
+            .filter(|entry| entry.expires_at > now)
+        else {
+            return Ok(0);
+        };
+
+        let mut unpinned = 0;
+        for message in entry.conversation.messages.iter_mut().filter(|m| m.pinned) {
+            message.pinned = false;
+            unpinned += 1;
+        }
+        // Trimming was held back by the pins
         entry.conversation.trim(self.max_messages);
 
-        debug!(
-            "Added message for {} (total: {})",
-            user_id,
-            entry.conversation.messages.len()
-        );
+        debug!("Unpinned {} messages for {}", unpinned, user_id);
+        Ok(unpinned)
+    }
 
-        Ok(entry.conversation.clone())
+    /// Replace the oldest `n` messages of a conversation with `summary`.
+    ///
+    /// `oldest` is the timestamp of the first message when the summarized
+    /// messages were read. If the history has been trimmed, cleared or
+    /// pruned since, the summary no longer covers the oldest messages and
+    /// nothing is replaced. Returns how many messages were replaced (0 if the
+    /// conversation has expired or changed). See
+    /// [`Conversation::replace_oldest_with_summary`].
+    #[instrument(skip(self, summary))]
+    pub async fn replace_oldest_with_summary(
+        &self,
+        user_id: &str,
+        n: usize,
+        summary: &str,
+        oldest: DateTime<Utc>,
+    ) -> Result<usize, ConversationError> {
+        let mut conversations = self.conversations.write().await;
+        let now = std::time::Instant::now();
+
+        let Some(entry) = conversations
+            .get_mut(user_id)
+            .filter(|entry| entry.expires_at > now)
+        else {
+            return Ok(0);
+        };
+        if let Some(cutoff) = self.retention_cutoff() {
+            prune_before(&mut entry.conversation, cutoff);
+        }
+        if entry.conversation.messages.first().map(|m| m.timestamp) != Some(oldest) {
+            debug!("History of {} changed while it was summarized", user_id);
+            return Ok(0);
+        }
+
+        let replaced = entry.conversation.replace_oldest_with_summary(n, summary);
+        debug!("Summarized {} messages for {}", replaced, user_id);
+        Ok(replaced)
     }
 
     /// Clear a user's conversation.
@@ -138,6 +371,66 @@ impl ConversationStore {
         Ok(removed)
     }
 
+    /// Set the system prompt override for a conversation.
+    ///
+    /// The override lasts for the store's TTL after it was set or the
+    /// conversation was last active, whichever is later.
+    #[instrument(skip(self, prompt))]
+    pub async fn set_prompt_override(&self, user_id: &str, prompt: &str) {
+        self.prompt_overrides.set(user_id, prompt, self.ttl).await;
+        info!("Set system prompt override for {}", user_id);
+    }
+
+    /// Remove a conversation's system prompt override, returning whether it had one.
+    #[instrument(skip(self))]
+    pub async fn clear_prompt_override(&self, user_id: &str) -> bool {
+        self.prompt_overrides.remove(user_id).await
+    }
+
+    /// System prompt override set for a conversation, if any.
+    pub async fn prompt_override(&self, user_id: &str) -> Option<String> {
+        self.prompt_overrides.get(user_id).await
+    }
+
+    /// Replace the base system prompt for a conversation.
+    ///
+    /// Expires like [`set_prompt_override`](Self::set_prompt_override).
+    #[instrument(skip(self, prompt))]
+    pub async fn set_system_prompt_override(&self, user_id: &str, prompt: &str) {
+        self.system_prompt_overrides.set(user_id, prompt, self.ttl).await;
+        info!("Set base system prompt override for {}", user_id);
+    }
+
+    /// Remove a conversation's base system prompt replacement, returning
+    /// whether it had one.
+    #[instrument(skip(self))]
+    pub async fn clear_system_prompt_override(&self, user_id: &str) -> bool {
+        self.system_prompt_overrides.remove(user_id).await
+    }
+
+    /// Base system prompt replacement set for a conversation, if any.
+    pub async fn system_prompt_override(&self, user_id: &str) -> Option<String> {
+        self.system_prompt_overrides.get(user_id).await
+    }
+
+    /// Set the model used for a conversation.
+    #[instrument(skip(self))]
+    pub async fn set_model_override(&self, user_id: &str, model: &str) {
+        self.model_overrides.set(user_id, model, self.ttl).await;
+        info!("Set model override for {}", user_id);
+    }
+
+    /// Remove a conversation's model override, returning whether it had one.
+    #[instrument(skip(self))]
+    pub async fn clear_model_override(&self, user_id: &str) -> bool {
+        self.model_overrides.remove(user_id).await
+    }
+
+    /// Model override set for a conversation, if any.
+    pub async fn model_override(&self, user_id: &str) -> Option<String> {
+        self.model_overrides.get(user_id).await
+    }
+
     /// Convert conversation to OpenAI messages format.
     pub async fn to_openai_messages(
         &self,
@@ -147,10 +440,18 @@ impl ConversationStore {
         let conv = self.get(user_id).await?;
         let mut messages = Vec::new();
 
-        // Add system prompt
+        // Add system prompt, with any summary of earlier history folded in
         let prompt = system_prompt
             .map(String::from)
             .or_else(|| conv.as_ref().and_then(|c| c.system_prompt.clone()));
+        let summary = conv
+            .as_ref()
+            .and_then(|c| c.messages.iter().find(|m| m.is_summary()))
+            .and_then(|m| m.content.clone());
+        let prompt = match (prompt, summary) {
+            (Some(prompt), Some(summary)) => Some(format!("{}\n\n{}", prompt, summary)),
+            (prompt, summary) => prompt.or(summary),
+        };
 
         if let Some(p) = prompt {
             messages.push(OpenAiMessage {
@@ -163,7 +464,7 @@ impl ConversationStore {
 
         // Add conversation history
         if let Some(conv) = conv {
-            for msg in conv.messages {
+            for msg in conv.messages.into_iter().filter(|m| !m.is_summary()) {
                 messages.push(OpenAiMessage {
                     role: msg.role,
                     content: msg.content,
@@ -176,6 +477,37 @@ impl ConversationStore {
         Ok(messages)
     }
 
+    /// Find a conversation's user and assistant messages matching `query`.
+    ///
+    /// Matching ignores case: a message matches if it contains the whole
+    /// query or every word of it. Returns the most recent
+    /// [`MAX_SEARCH_RESULTS`] matches, oldest first.
+    pub async fn search(&self, user_id: &str, query: &str) -> Vec<StoredMessage> {
+        let query = query.trim().to_lowercase();
+        let words: Vec<&str> = query.split_whitespace().collect();
+        if words.is_empty() {
+            return Vec::new();
+        }
+        let Ok(Some(conversation)) = self.get(user_id).await else {
+            return Vec::new();
+        };
+
+        let mut matches: Vec<StoredMessage> = conversation
+            .messages
+            .into_iter()
+            .filter(|m| m.role == "user" || m.role == "assistant")
+            .filter(|m| {
+                m.content.as_deref().is_some_and(|content| {
+                    let content = content.to_lowercase();
+                    content.contains(&query) || words.iter().all(|word| content.contains(word))
+                })
+            })
+            .collect();
+        let skip = matches.len().saturating_sub(MAX_SEARCH_RESULTS);
+        matches.drain(..skip);
+        matches
+    }
+
     /// Get message count for a user.
     pub async fn message_count(&self, user_id: &str) -> Result<usize, ConversationError> {
         Ok(self
@@ -195,6 +527,41 @@ impl ConversationStore {
             .count()
     }
 
+    /// Aggregate statistics over all unexpired conversations.
+    pub async fn stats(&self) -> ConversationStats {
+        let conversations = self.conversations.read().await;
+        let now = std::time::Instant::now();
+        let hour_ago = chrono::Utc::now() - chrono::Duration::hours(1);
+
+        let cutoff = self.retention_cutoff();
+
+        let mut stats = ConversationStats::default();
+        for entry in conversations.values().filter(|entry| entry.expires_at > now) {
+            let retained = entry
+                .conversation
+                .messages
+                .iter()
+                .filter(|m| cutoff.is_none_or(|cutoff| m.timestamp >= cutoff))
+                .count();
+            if retained == 0 {
+                continue;
+            }
+            stats.conversations += 1;
+            stats.total_messages += retained;
+            if entry.conversation.updated_at > hour_ago {
+                stats.active_last_hour += 1;
+            }
+        }
+        stats.prompt_overrides = self.prompt_overrides.len().await;
+        stats.reaped_total = self.reaped_total.load(Ordering::Relaxed);
+        if stats.conversations > 0 {
+            stats.avg_messages_per_conversation =
+                stats.total_messages as f64 / stats.conversations as f64;
+        }
+
+        stats
+    }
+
     /// Health check - always returns true for in-memory store.
     pub async fn health_check(&self) -> bool {
         true
@@ -214,7 +581,7 @@ impl ConversationStore {
             tool_calls.to_vec(),
         );
 
-        self.add_message_internal(user_id, message).await
+        self.add_message_internal(user_id, message, None).await
     }
 
     /// Add a tool result message.
@@ -226,7 +593,7 @@ impl ConversationStore {
         content: &str,
     ) -> Result<Conversation, ConversationError> {
         let message = StoredMessage::tool_result(tool_call_id, content);
-        self.add_message_internal(user_id, message).await
+        self.add_message_internal(user_id, message, None).await
     }
 
     /// Internal method to add a pre-constructed message.
@@ -234,6 +601,7 @@ impl ConversationStore {
         &self,
         user_id: &str,
         message: StoredMessage,
+        system_prompt: Option<&str>,
     ) -> Result<Conversation, ConversationError> {
         let mut conversations = self.conversations.write().await;
         let now = std::time::Instant::now();
@@ -242,13 +610,23 @@ impl ConversationStore {
         let entry = conversations
             .entry(user_id.to_string())
             .or_insert_with(|| ConversationEntry {
-                conversation: Conversation::new(user_id, None),
+                conversation: Conversation::new(user_id, system_prompt.map(String::from)),
                 expires_at,
             });
 
         // Update expiration on activity
         entry.expires_at = expires_at;
 
+        // Activity doesn't extend the life of messages past the age cap
+        if let Some(cutoff) = self.retention_cutoff() {
+            prune_before(&mut entry.conversation, cutoff);
+        }
+
+        // Update system prompt if provided
+        if let Some(prompt) = system_prompt {
+            entry.conversation.system_prompt = Some(prompt.to_string());
+        }
+
         // Add the message
         entry.conversation.messages.push(message);
         entry.conversation.updated_at = chrono::Utc::now();
@@ -262,6 +640,91 @@ impl ConversationStore {
             entry.conversation.messages.len()
         );
 
-        Ok(entry.conversation.clone())
+        let conversation = entry.conversation.clone();
+        drop(conversations);
+
+        // Overrides live as long as the conversation is in use
+        self.prompt_overrides.touch(user_id, expires_at).await;
+        self.system_prompt_overrides.touch(user_id, expires_at).await;
+        self.model_overrides.touch(user_id, expires_at).await;
+
+        Ok(conversation)
+    }
+}
+
+/// Per-conversation settings that survive `clear` but expire like the
+/// conversation: each lasts for the TTL after it was set or the
+/// conversation was last active.
+#[derive(Clone, Default)]
+struct Overrides(Arc<RwLock<HashMap<String, OverrideEntry>>>);
+
+struct OverrideEntry {
+    value: String,
+    expires_at: std::time::Instant,
+}
+
+impl Overrides {
+    async fn set(&self, user_id: &str, value: &str, ttl: Duration) {
+        let entry = OverrideEntry {
+            value: value.to_string(),
+            expires_at: std::time::Instant::now() + ttl,
+        };
+        self.0.write().await.insert(user_id.to_string(), entry);
     }
+
+    /// Remove an override, returning whether an unexpired one was set.
+    async fn remove(&self, user_id: &str) -> bool {
+        let now = std::time::Instant::now();
+        self.0
+            .write()
+            .await
+            .remove(user_id)
+            .is_some_and(|entry| entry.expires_at > now)
+    }
+
+    async fn get(&self, user_id: &str) -> Option<String> {
+        let now = std::time::Instant::now();
+        self.0
+            .read()
+            .await
+            .get(user_id)
+            .filter(|entry| entry.expires_at > now)
+            .map(|entry| entry.value.clone())
+    }
+
+    /// Extend an existing override to at least `expires_at`.
+    async fn touch(&self, user_id: &str, expires_at: std::time::Instant) {
+        if let Some(entry) = self.0.write().await.get_mut(user_id) {
+            entry.expires_at = entry.expires_at.max(expires_at);
+        }
+    }
+
+    async fn reap(&self, now: std::time::Instant) {
+        self.0.write().await.retain(|_, entry| entry.expires_at > now);
+    }
+
+    /// Number of unexpired overrides.
+    async fn len(&self) -> usize {
+        let now = std::time::Instant::now();
+        self.0
+            .read()
+            .await
+            .values()
+            .filter(|entry| entry.expires_at > now)
+            .count()
+    }
+}
+
+/// Drop messages stored before `cutoff`.
+///
+/// Tool results whose assistant message was dropped are removed too, since
+/// the API rejects a tool message without the call it answers.
+fn prune_before(conversation: &mut Conversation, cutoff: DateTime<Utc>) {
+    conversation.messages.retain(|m| m.timestamp >= cutoff);
+    let orphaned = conversation
+        .messages
+        .iter()
+        .take_while(|m| m.role == "tool")
+        .count();
+    conversation.messages.drain(..orphaned);
 }
@@ -2,6 +2,7 @@
 
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use tracing::warn;
 
 /// Stored tool call info.
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -22,6 +23,12 @@ pub struct StoredMessage {
     pub tool_calls: Option<Vec<StoredToolCall>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub tool_call_id: Option<String>,
+    /// Timestamp of the Signal message this was stored from, used to apply edits.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_timestamp: Option<i64>,
+    /// Pinned by the user with `!pin`, so trimming and summarizing keep it.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub pinned: bool,
 }
 
 impl StoredMessage {
@@ -32,9 +39,17 @@ impl StoredMessage {
             timestamp: Utc::now(),
             tool_calls: None,
             tool_call_id: None,
+            source_timestamp: None,
+            pinned: false,
         }
     }
 
+    /// Tag the message with the Signal timestamp it was received with.
+    pub fn with_source_timestamp(mut self, source_timestamp: i64) -> Self {
+        self.source_timestamp = Some(source_timestamp);
+        self
+    }
+
     pub fn with_tool_calls(role: impl Into<String>, content: Option<String>, tool_calls: Vec<StoredToolCall>) -> Self {
         Self {
             role: role.into(),
@@ -42,9 +57,17 @@ impl StoredMessage {
             timestamp: Utc::now(),
             tool_calls: Some(tool_calls),
             tool_call_id: None,
+            source_timestamp: None,
+            pinned: false,
         }
     }
 
+    /// Whether this stands in for summarized history (see
+    /// [`Conversation::replace_oldest_with_summary`]).
+    pub fn is_summary(&self) -> bool {
+        self.role == "system" && self.content.as_deref().is_some_and(|c| c.starts_with(SUMMARY_PREFIX))
+    }
+
     pub fn tool_result(tool_call_id: impl Into<String>, content: impl Into<String>) -> Self {
         Self {
             role: "tool".into(),
@@ -52,10 +75,15 @@ impl StoredMessage {
             timestamp: Utc::now(),
             tool_calls: None,
             tool_call_id: Some(tool_call_id.into()),
+            source_timestamp: None,
+            pinned: false,
         }
     }
 }
 
+/// Start of the system message that stands in for summarized history.
+pub const SUMMARY_PREFIX: &str = "Summary of the earlier conversation: ";
+
 /// A conversation with a user.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Conversation {
@@ -84,15 +112,105 @@ impl Conversation {
         self.updated_at = Utc::now();
     }
 
-    /// Trim to max messages, keeping most recent.
+    /// Replace the oldest `n` messages with one system message carrying
+    /// `summary`, returning how many were replaced. The summary is sent to
+    /// the model as part of the system prompt rather than as a message.
+    ///
+    /// Tool results answering a replaced call are replaced with it, since the
+    /// API rejects a tool message without the call it answers. Pinned
+    /// messages in that range are kept, right after the summary, and the
+    /// newest message is never replaced (see [`Self::summary_end`]).
+    pub fn replace_oldest_with_summary(&mut self, n: usize, summary: &str) -> usize {
+        let end = self.summary_end(n);
+        let (pinned, replaced): (Vec<_>, Vec<_>) = self.messages.drain(..end).partition(|m| m.pinned);
+        let Some(newest) = replaced.last() else {
+            self.messages.splice(..0, pinned);
+            return 0;
+        };
+
+        let mut message = StoredMessage::new("system", format!("{}{}", SUMMARY_PREFIX, summary));
+        // Date the summary like the newest message it covers, so a message
+        // age cap doesn't keep it longer than what it replaced
+        message.timestamp = newest.timestamp;
+        self.messages.splice(..0, std::iter::once(message).chain(pinned));
+        self.updated_at = Utc::now();
+        replaced.len()
+    }
+
+    /// End of the range [`Self::replace_oldest_with_summary`] replaces: the
+    /// oldest `n` messages plus the results of a tool call among them, but
+    /// never the newest message, which is the one being answered.
+    pub fn summary_end(&self, n: usize) -> usize {
+        let is_tool = |i: usize| self.messages.get(i).is_some_and(|m| m.role == "tool");
+        let last = self.messages.len().saturating_sub(1);
+        let mut end = n.min(last);
+        while is_tool(end) {
+            end += 1;
+        }
+        if end > last {
+            // The newest message is a tool result, so its call stays too
+            end = n.min(last);
+            while end > 0 && is_tool(end) {
+                end -= 1;
+            }
+        }
+        end
+    }
+
+    /// Trim to max messages, dropping the oldest unpinned ones.
+    ///
+    /// Pinned messages are never dropped, even if they alone are over the limit.
+    /// Tool results go with the call they answer, since the API rejects a
+    /// tool message without it, so a trim may drop a few more than needed.
     pub fn trim(&mut self, max_messages: usize) {
-        if self.messages.len() > max_messages {
-            let start = self.messages.len() - max_messages;
-            self.messages = self.messages[start..].to_vec();
+        let mut excess = self.messages.len().saturating_sub(max_messages);
+        if excess == 0 {
+            return;
+        }
+        // Whether the last message kept was a tool call, whose results stay
+        let mut keep_results = false;
+        self.messages.retain(|m| {
+            if m.role == "tool" {
+                if !keep_results {
+                    excess = excess.saturating_sub(1);
+                }
+                return keep_results;
+            }
+            let keep = excess == 0 || m.pinned;
+            if !keep {
+                excess -= 1;
+            }
+            keep_results = keep && m.tool_calls.is_some();
+            keep
+        });
+        if excess > 0 {
+            warn!(
+                "Conversation {} keeps {} pinned messages, over the limit of {}",
+                self.user_id,
+                self.messages.len(),
+                max_messages
+            );
         }
     }
 }
 
+/// Aggregate usage across live conversations.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct ConversationStats {
+    /// Conversations that haven't expired.
+    pub conversations: usize,
+    /// Conversations with a message in the last hour.
+    pub active_last_hour: usize,
+    /// Messages stored across all conversations.
+    pub total_messages: usize,
+    /// Mean messages per conversation (0 when there are none).
+    pub avg_messages_per_conversation: f64,
+    /// Conversations with a user-set system prompt.
+    pub prompt_overrides: usize,
+    /// Expired conversations evicted from memory since startup.
+    pub reaped_total: u64,
+}
+
 /// OpenAI-compatible message format.
 #[derive(Debug, Clone, Serialize)]
 pub struct OpenAiMessage {
@@ -0,0 +1,65 @@
+//! Dstack guest agent abstraction.
+
+use crate::client::DstackClient;
+use crate::error::DstackError;
+use crate::types::*;
+use async_trait::async_trait;
+
+/// Operations exposed by the Dstack guest agent.
+///
+/// Consumers that need TEE keys or attestation should depend on this trait
+/// rather than on [`DstackClient`] directly, so they can be exercised in tests
+/// without a running TEE (see `MockDstackClient` behind the `mock` feature).
+#[async_trait]
+pub trait DstackApi: Send + Sync {
+    /// Check if running inside a TEE.
+    async fn is_in_tee(&self) -> bool;
+
+    /// Get application information.
+    async fn get_app_info(&self) -> Result<AppInfo, DstackError>;
+
+    /// Get TCB info (RTMRs and event log).
+    ///
+    /// Parsed from the `tcb_info` field of [`DstackApi::get_app_info`] unless
+    /// overridden.
+    async fn get_tcb_info(&self) -> Result<TcbInfo, DstackError> {
+        self.get_app_info().await?.tcb_info()
+    }
+
+    /// Generate TDX attestation quote.
+    async fn get_quote(&self, report_data: &[u8]) -> Result<Quote, DstackError>;
+
+    /// Derive a key from TEE root of trust.
+    async fn derive_key(&self, path: &str, subject: Option<&str>)
+        -> Result<Vec<u8>, DstackError>;
+
+    /// Get RA-TLS certificate.
+    async fn get_ra_tls_cert(&self) -> Result<Vec<u8>, DstackError>;
+}
+
+#[async_trait]
+impl DstackApi for DstackClient {
+    async fn is_in_tee(&self) -> bool {
+        DstackClient::is_in_tee(self).await
+    }
+
+    async fn get_app_info(&self) -> Result<AppInfo, DstackError> {
+        DstackClient::get_app_info(self).await
+    }
+
+    async fn get_quote(&self, report_data: &[u8]) -> Result<Quote, DstackError> {
+        DstackClient::get_quote(self, report_data).await
+    }
+
+    async fn derive_key(
+        &self,
+        path: &str,
+        subject: Option<&str>,
+    ) -> Result<Vec<u8>, DstackError> {
+        DstackClient::derive_key(self, path, subject).await
+    }
+
+    async fn get_ra_tls_cert(&self) -> Result<Vec<u8>, DstackError> {
+        DstackClient::get_ra_tls_cert(self).await
+    }
+}
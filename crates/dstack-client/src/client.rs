@@ -1,30 +1,191 @@
 //! Dstack guest agent client implementation.
 
 use crate::error::DstackError;
+use crate::quote::QuoteVerdict;
 use crate::types::*;
+use async_trait::async_trait;
 use hyper::{Body, Client, Method, Request};
 use hyperlocal::{UnixClientExt, Uri};
-use std::path::Path;
+use reqwest::Url;
+use std::fmt;
+use std::net::IpAddr;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 use tracing::{debug, instrument, warn};
 
+/// How long app info is reused before asking the guest agent again.
+pub const DEFAULT_CACHE_TTL: Duration = Duration::from_secs(60);
+
+/// Timeout for requests to a guest agent reached over HTTP.
+const HTTP_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// How the guest agent is reached.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DstackTransport {
+    /// The guest agent's unix socket (the default in a CVM).
+    UnixSocket(PathBuf),
+    /// A localhost HTTP port proxying the guest agent.
+    Http(Url),
+}
+
+impl DstackTransport {
+    /// The guest agent at `url` if set, otherwise the unix socket at
+    /// `socket_path`.
+    ///
+    /// Plain `http://` URLs must point at a loopback address: the guest
+    /// agent hands out keys, so it must never be reached unencrypted over a
+    /// network.
+    pub fn from_config(socket_path: &str, url: Option<&str>) -> Result<Self, DstackError> {
+        let Some(url) = url else {
+            return Ok(Self::UnixSocket(socket_path.into()));
+        };
+        let parsed: Url = url
+            .parse()
+            .map_err(|e| DstackError::InvalidUrl(format!("{}: {}", url, e)))?;
+        match parsed.scheme() {
+            "https" => {}
+            "http" if is_loopback(&parsed) => {}
+            "http" => {
+                return Err(DstackError::InvalidUrl(format!(
+                    "{}: plain HTTP is only allowed to a loopback address",
+                    url
+                )))
+            }
+            scheme => {
+                return Err(DstackError::InvalidUrl(format!(
+                    "{}: unsupported scheme {}",
+                    url, scheme
+                )))
+            }
+        }
+        Ok(Self::Http(parsed))
+    }
+}
+
+/// Whether `url` points at this host.
+fn is_loopback(url: &Url) -> bool {
+    url.host_str().is_some_and(|host| {
+        host.eq_ignore_ascii_case("localhost")
+            || host
+                .trim_matches(['[', ']'])
+                .parse::<IpAddr>()
+                .is_ok_and(|ip| ip.is_loopback())
+    })
+}
+
+/// Sends requests to the guest agent.
+#[async_trait]
+pub(crate) trait Transport: Send + Sync {
+    async fn request(
+        &self,
+        method: Method,
+        path: &str,
+        body: Option<Vec<u8>>,
+    ) -> Result<Vec<u8>, DstackError>;
+}
+
+/// Transport over the guest agent's unix socket.
+struct UnixSocketTransport {
+    socket_path: PathBuf,
+}
+
+/// Transport over HTTP to a proxied guest agent.
+struct HttpTransport {
+    client: reqwest::Client,
+    base_url: Url,
+}
+
+/// A cached value and when it was fetched.
+type Cached<T> = Option<(Instant, T)>;
+
+/// Responses reused within the cache TTL, shared between clones.
+///
+/// Derived keys are deliberately not cached: they would linger in memory
+/// long after their callers have dropped them.
+#[derive(Default)]
+struct ResponseCache {
+    app_info: Cached<AppInfo>,
+}
+
 /// Client for Dstack guest agent.
-#[derive(Clone, Debug)]
+///
+/// `get_app_info` responses are cached for a short TTL ([`DEFAULT_CACHE_TTL`]
+/// unless set with [`DstackClient::with_cache_ttl`]), so hot paths such as
+/// `!verify` don't round-trip the socket every time.
+#[derive(Clone)]
 pub struct DstackClient {
-    socket_path: String,
+    endpoint: DstackTransport,
+    transport: Arc<dyn Transport>,
+    cache_ttl: Duration,
+    cache: Arc<Mutex<ResponseCache>>,
+}
+
+impl fmt::Debug for DstackClient {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DstackClient")
+            .field("endpoint", &self.endpoint)
+            .field("cache_ttl", &self.cache_ttl)
+            .finish_non_exhaustive()
+    }
 }
 
 impl DstackClient {
-    /// Create a new Dstack client.
+    /// Create a new Dstack client talking to the unix socket at `socket_path`.
     pub fn new(socket_path: impl Into<String>) -> Self {
+        Self::new_with_transport(DstackTransport::UnixSocket(socket_path.into().into()))
+    }
+
+    /// Create a client for the guest agent reached through `endpoint`.
+    pub fn new_with_transport(endpoint: DstackTransport) -> Self {
+        let transport: Arc<dyn Transport> = match &endpoint {
+            DstackTransport::UnixSocket(path) => Arc::new(UnixSocketTransport {
+                socket_path: path.clone(),
+            }),
+            DstackTransport::Http(url) => Arc::new(HttpTransport {
+                client: reqwest::Client::builder()
+                    .timeout(HTTP_TIMEOUT)
+                    .build()
+                    .unwrap_or_default(),
+                base_url: url.clone(),
+            }),
+        };
+        Self::from_transport(endpoint, transport)
+    }
+
+    pub(crate) fn from_transport(endpoint: DstackTransport, transport: Arc<dyn Transport>) -> Self {
         Self {
-            socket_path: socket_path.into(),
+            endpoint,
+            transport,
+            cache_ttl: DEFAULT_CACHE_TTL,
+            cache: Arc::default(),
         }
     }
 
+    /// Set how long app info is cached. Zero disables caching.
+    pub fn with_cache_ttl(mut self, ttl: Duration) -> Self {
+        self.cache_ttl = ttl;
+        self
+    }
+
+    /// Drop all cached responses, so the next call asks the guest agent.
+    pub fn clear_cache(&self) {
+        *self.cache.lock().unwrap() = ResponseCache::default();
+    }
+
+    fn is_fresh(&self, fetched_at: Instant) -> bool {
+        fetched_at.elapsed() < self.cache_ttl
+    }
+
     /// Check if running inside a TEE.
+    ///
+    /// Over HTTP there is no socket to look for, so this probes the info
+    /// endpoint.
     pub async fn is_in_tee(&self) -> bool {
-        if !Path::new(&self.socket_path).exists() {
-            return false;
+        if let DstackTransport::UnixSocket(path) = &self.endpoint {
+            if !path.exists() {
+                return false;
+            }
         }
         self.get_app_info().await.is_ok()
     }
@@ -32,9 +193,27 @@ impl DstackClient {
     /// Get application information.
     #[instrument(skip(self))]
     pub async fn get_app_info(&self) -> Result<AppInfo, DstackError> {
+        if let Some((fetched_at, info)) = &self.cache.lock().unwrap().app_info {
+            if self.is_fresh(*fetched_at) {
+                return Ok(info.clone());
+            }
+        }
+
         let response = self.request(Method::GET, "/Info", None).await?;
         let info: AppInfo = serde_json::from_slice(&response)?;
         debug!("Got app info: {:?}", info);
+        self.cache.lock().unwrap().app_info = Some((Instant::now(), info.clone()));
+        Ok(info)
+    }
+
+    /// Get TCB info: the RTMR values and the event log behind them.
+    ///
+    /// The guest agent has no endpoint of its own for this; it is the
+    /// `tcb_info` field of `/Info`.
+    #[instrument(skip(self))]
+    pub async fn get_tcb_info(&self) -> Result<TcbInfo, DstackError> {
+        let info = self.get_app_info().await?.tcb_info()?;
+        debug!("Got TCB info with {} events", info.event_log.len());
         Ok(info)
     }
 
@@ -56,6 +235,16 @@ impl DstackClient {
         Ok(quote)
     }
 
+    /// Parse a raw TDX quote and check that it embeds `expected_report_data`.
+    ///
+    /// Structural only: the quote signature is not verified.
+    pub fn verify_quote(
+        quote: &[u8],
+        expected_report_data: &[u8],
+    ) -> Result<QuoteVerdict, DstackError> {
+        crate::quote::verify_quote(quote, expected_report_data)
+    }
+
     /// Derive a key from TEE root of trust.
     #[instrument(skip(self))]
     pub async fn derive_key(
@@ -87,6 +276,19 @@ impl DstackClient {
             .map_err(|e| DstackError::QuoteGeneration(e.to_string()))
     }
 
+    /// Make HTTP request to the guest agent.
+    async fn request(
+        &self,
+        method: Method,
+        path: &str,
+        body: Option<Vec<u8>>,
+    ) -> Result<Vec<u8>, DstackError> {
+        self.transport.request(method, path, body).await
+    }
+}
+
+#[async_trait]
+impl Transport for UnixSocketTransport {
     /// Make HTTP request to Dstack socket.
     async fn request(
         &self,
@@ -94,8 +296,10 @@ impl DstackClient {
         path: &str,
         body: Option<Vec<u8>>,
     ) -> Result<Vec<u8>, DstackError> {
-        if !Path::new(&self.socket_path).exists() {
-            return Err(DstackError::SocketNotFound(self.socket_path.clone()));
+        if !self.socket_path.exists() {
+            return Err(DstackError::SocketNotFound(
+                self.socket_path.display().to_string(),
+            ));
         }
 
         let client = Client::unix();
@@ -132,3 +336,211 @@ impl DstackClient {
         Ok(body.to_vec())
     }
 }
+
+#[async_trait]
+impl Transport for HttpTransport {
+    /// Make HTTP request to the proxied guest agent.
+    async fn request(
+        &self,
+        method: Method,
+        path: &str,
+        body: Option<Vec<u8>>,
+    ) -> Result<Vec<u8>, DstackError> {
+        // Append rather than join, so a base path such as /dstack is kept
+        let url = format!("{}{}", self.base_url.as_str().trim_end_matches('/'), path);
+
+        let mut request = self
+            .client
+            .request(method, url)
+            .header("Content-Type", "application/json");
+        if let Some(body) = body {
+            request = request.body(body);
+        }
+
+        let response = request.send().await?;
+        let status = response.status();
+        let body = response.bytes().await?;
+
+        if !status.is_success() {
+            let msg = String::from_utf8_lossy(&body);
+            warn!("Dstack request failed: {} - {}", status, msg);
+            return Err(DstackError::QuoteGeneration(format!(
+                "HTTP {}: {}",
+                status, msg
+            )));
+        }
+
+        Ok(body.to_vec())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Answers every request with a fixed body and counts the calls.
+    #[derive(Default)]
+    struct CountingTransport {
+        calls: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl Transport for CountingTransport {
+        async fn request(
+            &self,
+            _method: Method,
+            path: &str,
+            _body: Option<Vec<u8>>,
+        ) -> Result<Vec<u8>, DstackError> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            let body = match path {
+                "/Info" => r#"{"app_id": "test-app"}"#,
+                "/DeriveKey" => r#"{"key": "deadbeef"}"#,
+                _ => return Err(DstackError::QuoteGeneration(format!("unexpected {}", path))),
+            };
+            Ok(body.as_bytes().to_vec())
+        }
+    }
+
+    fn counting_client() -> (DstackClient, Arc<CountingTransport>) {
+        let transport = Arc::new(CountingTransport::default());
+        let client = DstackClient::from_transport(
+            DstackTransport::UnixSocket("/test.sock".into()),
+            transport.clone(),
+        );
+        (client, transport)
+    }
+
+    #[tokio::test]
+    async fn test_app_info_cached_within_ttl() {
+        let (client, transport) = counting_client();
+
+        let first = client.get_app_info().await.unwrap();
+        let second = client.clone().get_app_info().await.unwrap();
+        assert_eq!(first.app_id.as_deref(), Some("test-app"));
+        assert_eq!(second.app_id, first.app_id);
+        assert_eq!(transport.calls.load(Ordering::SeqCst), 1);
+
+        client.clear_cache();
+        client.get_app_info().await.unwrap();
+        assert_eq!(transport.calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_derived_keys_not_cached() {
+        let (client, transport) = counting_client();
+
+        let key = client.derive_key("/a", None).await.unwrap();
+        assert_eq!(key, vec![0xde, 0xad, 0xbe, 0xef]);
+        client.derive_key("/a", None).await.unwrap();
+        assert_eq!(transport.calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_zero_ttl_disables_cache() {
+        let (client, transport) = counting_client();
+        let client = client.with_cache_ttl(Duration::ZERO);
+
+        client.get_app_info().await.unwrap();
+        client.get_app_info().await.unwrap();
+        assert_eq!(transport.calls.load(Ordering::SeqCst), 2);
+    }
+
+    fn http_client(server: &wiremock::MockServer) -> DstackClient {
+        DstackClient::new_with_transport(DstackTransport::Http(
+            format!("{}/dstack/", server.uri()).parse().unwrap(),
+        ))
+    }
+
+    #[tokio::test]
+    async fn test_http_transport() {
+        use wiremock::matchers::{body_json, method, path, query_param};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/dstack/Info"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "app_id": "http-app",
+                "compose_hash": "abc123",
+                "tcb_info": r#"{"mrtd": "aa", "rtmr3": "33", "event_log": []}"#
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/dstack/GetQuote"))
+            .and(query_param("report_data", hex::encode([b"nonce".as_slice(), &[0u8; 59]].concat())))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "quote": "cXVvdGU="
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/dstack/DeriveKey"))
+            .and(body_json(serde_json::json!({ "path": "/app/key" })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "key": "deadbeef"
+            })))
+            .mount(&server)
+            .await;
+
+        let client = http_client(&server);
+        assert!(client.is_in_tee().await);
+        assert_eq!(client.get_app_info().await.unwrap().app_id.as_deref(), Some("http-app"));
+        let tcb = crate::DstackApi::get_tcb_info(&client).await.unwrap();
+        assert_eq!(tcb.rtmrs(), vec![("RTMR3", "33")]);
+        assert_eq!(client.get_quote(b"nonce").await.unwrap().quote, "cXVvdGU=");
+        assert_eq!(
+            client.derive_key("/app/key", None).await.unwrap(),
+            vec![0xde, 0xad, 0xbe, 0xef]
+        );
+    }
+
+    #[test]
+    fn test_transport_from_config() {
+        assert_eq!(
+            DstackTransport::from_config("/var/run/dstack.sock", None).unwrap(),
+            DstackTransport::UnixSocket("/var/run/dstack.sock".into())
+        );
+        for url in ["http://127.0.0.1:8090", "http://localhost:8090/dstack", "http://[::1]:8090"] {
+            assert!(
+                matches!(DstackTransport::from_config("", Some(url)), Ok(DstackTransport::Http(_))),
+                "{}",
+                url
+            );
+        }
+        assert!(DstackTransport::from_config("", Some("https://agent.internal")).is_ok());
+
+        for url in ["http://10.0.0.5:8090", "http://agent.internal", "ftp://127.0.0.1", "not a url"] {
+            assert!(
+                matches!(DstackTransport::from_config("", Some(url)), Err(DstackError::InvalidUrl(_))),
+                "{}",
+                url
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_http_transport_errors() {
+        use wiremock::matchers::method;
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(503).set_body_string("agent down"))
+            .mount(&server)
+            .await;
+
+        let client = http_client(&server);
+        assert!(!client.is_in_tee().await);
+        let err = client.get_app_info().await.unwrap_err();
+        assert!(err.to_string().contains("503"));
+
+        let unreachable = DstackClient::new_with_transport(DstackTransport::Http(
+            "http://127.0.0.1:9".parse().unwrap(),
+        ));
+        assert!(matches!(unreachable.get_app_info().await, Err(DstackError::Http(_))));
+    }
+}
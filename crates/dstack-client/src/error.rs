@@ -19,9 +19,18 @@ pub enum DstackError {
     #[error("Key derivation failed: {0}")]
     KeyDerivation(String),
 
+    #[error("Invalid quote: {0}")]
+    InvalidQuote(String),
+
+    #[error("TCB info unavailable: {0}")]
+    TcbInfo(String),
+
     #[error("Not running in TEE")]
     NotInTee,
 
     #[error("Socket not found: {0}")]
     SocketNotFound(String),
+
+    #[error("Invalid guest agent URL: {0}")]
+    InvalidUrl(String),
 }
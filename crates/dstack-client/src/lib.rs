@@ -1,11 +1,19 @@
 //! Dstack TEE guest agent client.
 
+mod api;
 mod client;
 mod error;
+#[cfg(any(test, feature = "mock"))]
+mod mock;
+mod quote;
 mod types;
 
-pub use client::DstackClient;
+pub use api::DstackApi;
+pub use client::{DstackClient, DstackTransport, DEFAULT_CACHE_TTL};
 pub use error::DstackError;
+#[cfg(any(test, feature = "mock"))]
+pub use mock::MockDstackClient;
+pub use quote::{QuoteVerdict, TEE_TYPE_TDX};
 pub use types::*;
 
 #[cfg(test)]
@@ -26,6 +34,14 @@ mod tests {
         assert!(matches!(result, Err(DstackError::SocketNotFound(_))));
     }
 
+    #[tokio::test]
+    async fn test_get_tcb_info_when_socket_not_exists() {
+        let client = DstackClient::new("/nonexistent/socket/path");
+        let result = client.get_tcb_info().await;
+        assert!(result.is_err());
+        assert!(matches!(result, Err(DstackError::SocketNotFound(_))));
+    }
+
     #[tokio::test]
     async fn test_get_quote_when_socket_not_exists() {
         let client = DstackClient::new("/nonexistent/socket/path");
@@ -50,6 +66,72 @@ mod tests {
         assert!(matches!(result, Err(DstackError::SocketNotFound(_))));
     }
 
+    #[tokio::test]
+    async fn test_mock_derive_key_is_deterministic() {
+        let mock = MockDstackClient::new();
+
+        let a = mock.derive_key("x402-payments/credit-store", None).await.unwrap();
+        let b = mock.derive_key("x402-payments/credit-store", None).await.unwrap();
+        let other = mock.derive_key("x402-payments/near-deposit-wallet", None).await.unwrap();
+        let with_subject = mock
+            .derive_key("x402-payments/credit-store", Some("user"))
+            .await
+            .unwrap();
+
+        assert_eq!(a.len(), 32);
+        assert_eq!(a, b);
+        assert_ne!(a, other);
+        assert_ne!(a, with_subject);
+
+        let reseeded = MockDstackClient::new().with_seed("other");
+        assert_ne!(a, reseeded.derive_key("x402-payments/credit-store", None).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_mock_quote_embeds_padded_report_data() {
+        let mock = MockDstackClient::new();
+        let quote = mock.get_quote(b"test").await.unwrap();
+
+        let report_data = quote.report_data.unwrap();
+        assert_eq!(report_data.len(), 128);
+        assert!(report_data.starts_with("74657374"));
+    }
+
+    #[tokio::test]
+    async fn test_verify_mock_quote() {
+        use base64::{engine::general_purpose::STANDARD, Engine};
+
+        let mock = MockDstackClient::new();
+        let quote = STANDARD.decode(mock.get_quote(b"test").await.unwrap().quote).unwrap();
+        let tcb = mock.get_tcb_info().await.unwrap();
+
+        let verdict = DstackClient::verify_quote(&quote, b"test").unwrap();
+        assert!(verdict.report_data_matches);
+        assert_eq!(verdict.tee_type, TEE_TYPE_TDX);
+        assert_eq!(Some(&verdict.rtmrs[3]), tcb.rtmr3.as_ref());
+        assert_eq!(Some(&verdict.mrtd), tcb.mrtd.as_ref());
+
+        assert!(!DstackClient::verify_quote(&quote, b"other").unwrap().report_data_matches);
+    }
+
+    #[tokio::test]
+    async fn test_mock_not_in_tee() {
+        let mock = MockDstackClient::not_in_tee();
+
+        assert!(!mock.is_in_tee().await);
+        assert!(matches!(
+            mock.get_app_info().await,
+            Err(DstackError::SocketNotFound(_))
+        ));
+        assert!(mock.derive_key("/test/path", None).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_client_usable_as_trait_object() {
+        let client: Box<dyn DstackApi> = Box::new(DstackClient::new("/nonexistent/socket/path"));
+        assert!(!client.is_in_tee().await);
+    }
+
     #[test]
     fn test_app_info_deserialization() {
         let json = r#"{
@@ -75,6 +157,44 @@ mod tests {
         assert!(info.instance_id.is_none());
     }
 
+    #[test]
+    fn test_tcb_info_deserialization() {
+        let json = r#"{
+            "mrtd": "aa",
+            "rtmr0": "00",
+            "rtmr1": "11",
+            "rtmr3": "33",
+            "event_log": [
+                {"imr": 3, "event_type": 134217729, "digest": "ff", "event": "compose-hash", "event_payload": "abcd"}
+            ]
+        }"#;
+
+        let info: TcbInfo = serde_json::from_str(json).unwrap();
+        assert_eq!(info.mrtd.as_deref(), Some("aa"));
+        assert_eq!(info.rtmrs(), vec![("RTMR0", "00"), ("RTMR1", "11"), ("RTMR3", "33")]);
+        assert_eq!(info.event_log.len(), 1);
+        assert_eq!(info.event_log[0].imr, 3);
+        assert_eq!(info.event_log[0].event, "compose-hash");
+    }
+
+    #[test]
+    fn test_tcb_info_from_app_info() {
+        let json = r#"{
+            "app_id": "test-app",
+            "tcb_info": "{\"mrtd\": \"aa\", \"rtmr0\": \"00\", \"event_log\": []}"
+        }"#;
+        let info: AppInfo = serde_json::from_str(json).unwrap();
+        let tcb = info.tcb_info().unwrap();
+        assert_eq!(tcb.mrtd.as_deref(), Some("aa"));
+        assert_eq!(tcb.rtmrs(), vec![("RTMR0", "00")]);
+
+        let inline: AppInfo = serde_json::from_str(r#"{"tcb_info": {"rtmr1": "11"}}"#).unwrap();
+        assert_eq!(inline.tcb_info().unwrap().rtmrs(), vec![("RTMR1", "11")]);
+
+        let missing: AppInfo = serde_json::from_str("{}").unwrap();
+        assert!(matches!(missing.tcb_info(), Err(DstackError::TcbInfo(_))));
+    }
+
     #[test]
     fn test_quote_deserialization() {
         let json = r#"{
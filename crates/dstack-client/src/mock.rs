@@ -0,0 +1,155 @@
+//! Deterministic Dstack mock for tests.
+//!
+//! Enabled with the `mock` feature. Every response is derived from a fixed
+//! seed, so the same path always yields the same key and wallets derived from
+//! it are stable across test runs.
+
+use crate::api::DstackApi;
+use crate::error::DstackError;
+use crate::quote::build_quote;
+use crate::types::*;
+use async_trait::async_trait;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use sha2::{Digest, Sha256};
+
+/// Default seed used by [`MockDstackClient::new`].
+const DEFAULT_SEED: &[u8] = b"dstack-mock-seed";
+
+/// In-process stand-in for the Dstack guest agent.
+#[derive(Clone, Debug)]
+pub struct MockDstackClient {
+    in_tee: bool,
+    seed: Vec<u8>,
+    app_info: AppInfo,
+}
+
+impl MockDstackClient {
+    /// Create a mock that reports running inside a TEE.
+    pub fn new() -> Self {
+        Self {
+            in_tee: true,
+            seed: DEFAULT_SEED.to_vec(),
+            app_info: AppInfo {
+                app_id: Some("mock-app-id".into()),
+                compose_hash: Some("mock-compose-hash".into()),
+                instance_id: Some("mock-instance-id".into()),
+                extra: serde_json::Value::Object(Default::default()),
+            },
+        }
+    }
+
+    /// Create a mock that behaves like a missing guest agent socket.
+    pub fn not_in_tee() -> Self {
+        Self {
+            in_tee: false,
+            ..Self::new()
+        }
+    }
+
+    /// Use a different seed for key derivation.
+    pub fn with_seed(mut self, seed: impl Into<Vec<u8>>) -> Self {
+        self.seed = seed.into();
+        self
+    }
+
+    /// Override the returned application info.
+    pub fn with_app_info(mut self, app_info: AppInfo) -> Self {
+        self.app_info = app_info;
+        self
+    }
+
+    /// A 48-byte measurement derived from the seed.
+    fn measurement(&self, label: &[u8]) -> [u8; 48] {
+        let first = Sha256::new().chain_update(&self.seed).chain_update(label).finalize();
+        let second = Sha256::digest(first);
+        let mut out = [0u8; 48];
+        out[..32].copy_from_slice(&first);
+        out[32..].copy_from_slice(&second[..16]);
+        out
+    }
+
+    fn rtmrs(&self) -> [[u8; 48]; 4] {
+        [0u8, 1, 2, 3].map(|index| self.measurement(&[b'r', index]))
+    }
+
+    fn ensure_in_tee(&self) -> Result<(), DstackError> {
+        if self.in_tee {
+            Ok(())
+        } else {
+            Err(DstackError::SocketNotFound("mock".into()))
+        }
+    }
+}
+
+impl Default for MockDstackClient {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl DstackApi for MockDstackClient {
+    async fn is_in_tee(&self) -> bool {
+        self.in_tee
+    }
+
+    async fn get_app_info(&self) -> Result<AppInfo, DstackError> {
+        self.ensure_in_tee()?;
+        Ok(self.app_info.clone())
+    }
+
+    async fn get_tcb_info(&self) -> Result<TcbInfo, DstackError> {
+        self.ensure_in_tee()?;
+
+        // Registers derived from the seed, matching the ones in quotes
+        let [rtmr0, rtmr1, rtmr2, rtmr3] = self.rtmrs().map(|r| Some(hex::encode(r)));
+        Ok(TcbInfo {
+            mrtd: Some(hex::encode(self.measurement(b"mrtd"))),
+            rtmr0,
+            rtmr1,
+            rtmr2,
+            rtmr3,
+            event_log: vec![],
+            extra: serde_json::Value::Object(Default::default()),
+        })
+    }
+
+    async fn get_quote(&self, report_data: &[u8]) -> Result<Quote, DstackError> {
+        self.ensure_in_tee()?;
+
+        // Same padding as the real client
+        let mut data = [0u8; 64];
+        let len = report_data.len().min(64);
+        data[..len].copy_from_slice(&report_data[..len]);
+
+        // Unsigned but structurally valid, so verify_quote can parse it
+        let quote = build_quote(self.measurement(b"mrtd"), self.rtmrs(), data);
+
+        Ok(Quote {
+            quote: STANDARD.encode(quote),
+            report_data: Some(hex::encode(data)),
+        })
+    }
+
+    async fn derive_key(
+        &self,
+        path: &str,
+        subject: Option<&str>,
+    ) -> Result<Vec<u8>, DstackError> {
+        self.ensure_in_tee()?;
+
+        let mut hasher = Sha256::new();
+        hasher.update(&self.seed);
+        hasher.update(path.as_bytes());
+        if let Some(subject) = subject {
+            hasher.update(b"/");
+            hasher.update(subject.as_bytes());
+        }
+        Ok(hasher.finalize().to_vec())
+    }
+
+    async fn get_ra_tls_cert(&self) -> Result<Vec<u8>, DstackError> {
+        self.ensure_in_tee()?;
+        Ok(b"mock-ra-tls-cert".to_vec())
+    }
+}
@@ -0,0 +1,155 @@
+//! Structural parsing of TDX quotes.
+//!
+//! Only the v4 quote header and TD report body are read. The signature
+//! section is not checked, so a verdict says what the quote claims, not that
+//! Intel vouches for it; proof.t16z.com remains the way to check signatures.
+
+use crate::error::DstackError;
+
+/// Length of the quote header.
+const HEADER_LEN: usize = 48;
+
+/// Length of the TD report body that follows the header.
+const BODY_LEN: usize = 584;
+
+/// TEE type of TDX in the quote header.
+pub const TEE_TYPE_TDX: u32 = 0x81;
+
+/// Offsets of fields within the TD report body.
+const MRTD: usize = 136;
+const RTMR0: usize = 328;
+const REPORT_DATA: usize = 520;
+const MEASUREMENT_LEN: usize = 48;
+const REPORT_DATA_LEN: usize = 64;
+
+/// What a quote claims, and whether it carries the expected report data.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QuoteVerdict {
+    /// Quote format version (4 for TDX).
+    pub version: u16,
+    /// TEE type from the header (0x81 for TDX).
+    pub tee_type: u32,
+    /// Hex-encoded MRTD.
+    pub mrtd: String,
+    /// Hex-encoded RTMR0 through RTMR3.
+    pub rtmrs: [String; 4],
+    /// Hex-encoded report data embedded in the quote.
+    pub report_data: String,
+    /// Whether the embedded report data equals the expected value.
+    pub report_data_matches: bool,
+}
+
+/// Parse `quote` and compare its report data with `expected_report_data`,
+/// padded to 64 bytes the same way `get_quote` pads it.
+pub(crate) fn verify_quote(
+    quote: &[u8],
+    expected_report_data: &[u8],
+) -> Result<QuoteVerdict, DstackError> {
+    if quote.len() < HEADER_LEN + BODY_LEN {
+        return Err(DstackError::InvalidQuote(format!(
+            "{} bytes is shorter than a TDX quote header and report",
+            quote.len()
+        )));
+    }
+    if expected_report_data.len() > REPORT_DATA_LEN {
+        return Err(DstackError::InvalidQuote(format!(
+            "expected report data is {} bytes, more than {}",
+            expected_report_data.len(),
+            REPORT_DATA_LEN
+        )));
+    }
+
+    let version = u16::from_le_bytes([quote[0], quote[1]]);
+    let tee_type = u32::from_le_bytes([quote[4], quote[5], quote[6], quote[7]]);
+    if version != 4 || tee_type != TEE_TYPE_TDX {
+        return Err(DstackError::InvalidQuote(format!(
+            "unsupported quote version {} with TEE type {:#x}",
+            version, tee_type
+        )));
+    }
+
+    let body = &quote[HEADER_LEN..HEADER_LEN + BODY_LEN];
+    let measurement = |offset: usize| hex::encode(&body[offset..offset + MEASUREMENT_LEN]);
+    let report_data = &body[REPORT_DATA..REPORT_DATA + REPORT_DATA_LEN];
+
+    let mut expected = [0u8; REPORT_DATA_LEN];
+    expected[..expected_report_data.len()].copy_from_slice(expected_report_data);
+
+    Ok(QuoteVerdict {
+        version,
+        tee_type,
+        mrtd: measurement(MRTD),
+        rtmrs: [0, 1, 2, 3].map(|i| measurement(RTMR0 + i * MEASUREMENT_LEN)),
+        report_data: hex::encode(report_data),
+        report_data_matches: report_data == expected,
+    })
+}
+
+/// Build an unsigned v4 TDX quote with the given measurements, for tests
+/// and the mock guest agent.
+#[cfg(any(test, feature = "mock"))]
+pub(crate) fn build_quote(mrtd: [u8; 48], rtmrs: [[u8; 48]; 4], report_data: [u8; 64]) -> Vec<u8> {
+    let mut quote = vec![0u8; HEADER_LEN + BODY_LEN];
+    quote[0..2].copy_from_slice(&4u16.to_le_bytes());
+    quote[2..4].copy_from_slice(&2u16.to_le_bytes()); // ECDSA-256 attestation key
+    quote[4..8].copy_from_slice(&TEE_TYPE_TDX.to_le_bytes());
+
+    let body = &mut quote[HEADER_LEN..];
+    body[MRTD..MRTD + MEASUREMENT_LEN].copy_from_slice(&mrtd);
+    for (i, rtmr) in rtmrs.iter().enumerate() {
+        let offset = RTMR0 + i * MEASUREMENT_LEN;
+        body[offset..offset + MEASUREMENT_LEN].copy_from_slice(rtmr);
+    }
+    body[REPORT_DATA..REPORT_DATA + REPORT_DATA_LEN].copy_from_slice(&report_data);
+
+    // Signature data follows the body in a real quote
+    quote.extend_from_slice(&[0u8; 4]);
+    quote
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_quote(report_data: &[u8]) -> Vec<u8> {
+        let mut data = [0u8; 64];
+        data[..report_data.len()].copy_from_slice(report_data);
+        build_quote([0xaa; 48], [[0x00; 48], [0x11; 48], [0x22; 48], [0x33; 48]], data)
+    }
+
+    #[test]
+    fn test_verify_quote_matching_report_data() {
+        let verdict = verify_quote(&sample_quote(b"my-nonce"), b"my-nonce").unwrap();
+
+        assert_eq!(verdict.version, 4);
+        assert_eq!(verdict.tee_type, TEE_TYPE_TDX);
+        assert!(verdict.report_data_matches);
+        assert_eq!(verdict.mrtd, "aa".repeat(48));
+        assert_eq!(verdict.rtmrs[1], "11".repeat(48));
+        assert_eq!(verdict.rtmrs[3], "33".repeat(48));
+        assert!(verdict.report_data.starts_with(&hex::encode("my-nonce")));
+    }
+
+    #[test]
+    fn test_verify_quote_mismatched_report_data() {
+        let verdict = verify_quote(&sample_quote(b"my-nonce"), b"other").unwrap();
+        assert!(!verdict.report_data_matches);
+    }
+
+    #[test]
+    fn test_verify_quote_rejects_malformed_quotes() {
+        assert!(matches!(
+            verify_quote(b"mock-tdx-quote", b""),
+            Err(DstackError::InvalidQuote(_))
+        ));
+
+        let mut sgx = sample_quote(b"");
+        sgx[4..8].copy_from_slice(&0u32.to_le_bytes());
+        assert!(matches!(verify_quote(&sgx, b""), Err(DstackError::InvalidQuote(_))));
+
+        assert!(matches!(
+            verify_quote(&sample_quote(b""), &[0u8; 65]),
+            Err(DstackError::InvalidQuote(_))
+        ));
+    }
+}
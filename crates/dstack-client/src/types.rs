@@ -1,5 +1,6 @@
 //! Dstack API types.
 
+use crate::error::DstackError;
 use serde::{Deserialize, Serialize};
 
 /// Application information from Dstack.
@@ -19,6 +20,76 @@ pub struct AppInfo {
     pub extra: serde_json::Value,
 }
 
+impl AppInfo {
+    /// The TCB info the guest agent reports in `tcb_info`, either as a
+    /// JSON-encoded string (as dstack sends it) or an inline object.
+    pub fn tcb_info(&self) -> Result<TcbInfo, DstackError> {
+        match self.extra.get("tcb_info") {
+            Some(serde_json::Value::String(json)) => Ok(serde_json::from_str(json)?),
+            Some(value) => Ok(TcbInfo::deserialize(value)?),
+            None => Err(DstackError::TcbInfo("missing from app info".into())),
+        }
+    }
+}
+
+/// TCB measurements of the running CVM.
+#[derive(Debug, Clone, Deserialize)]
+pub struct TcbInfo {
+    /// Measurement of the TD (firmware)
+    pub mrtd: Option<String>,
+
+    /// Runtime measurement registers, hex-encoded
+    pub rtmr0: Option<String>,
+    pub rtmr1: Option<String>,
+    pub rtmr2: Option<String>,
+    pub rtmr3: Option<String>,
+
+    /// Events extended into the RTMRs, in order
+    #[serde(default)]
+    pub event_log: Vec<EventLogEntry>,
+
+    /// Additional fields
+    #[serde(flatten)]
+    pub extra: serde_json::Value,
+}
+
+impl TcbInfo {
+    /// The RTMRs that are present, as `(name, value)` pairs in register order.
+    pub fn rtmrs(&self) -> Vec<(&'static str, &str)> {
+        [
+            ("RTMR0", &self.rtmr0),
+            ("RTMR1", &self.rtmr1),
+            ("RTMR2", &self.rtmr2),
+            ("RTMR3", &self.rtmr3),
+        ]
+        .into_iter()
+        .filter_map(|(name, value)| value.as_deref().map(|v| (name, v)))
+        .collect()
+    }
+}
+
+/// One measured event from the TCB event log.
+#[derive(Debug, Clone, Deserialize)]
+pub struct EventLogEntry {
+    /// Index of the RTMR the event was extended into
+    pub imr: u32,
+
+    /// TCG event type
+    #[serde(default)]
+    pub event_type: u32,
+
+    /// Hex-encoded digest extended into the register
+    pub digest: String,
+
+    /// Event name, for events recorded by dstack
+    #[serde(default)]
+    pub event: String,
+
+    /// Hex-encoded event payload
+    #[serde(default)]
+    pub event_payload: String,
+}
+
 /// TDX attestation quote.
 #[derive(Debug, Clone, Deserialize)]
 pub struct Quote {
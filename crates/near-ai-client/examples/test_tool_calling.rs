@@ -1,7 +1,7 @@
 //! Test script for debugging NEAR AI tool calling
 //! Run with: cargo run -p near-ai-client --example test_tool_calling
 
-use near_ai_client::{Message, NearAiClient, Role, ToolDefinition, FunctionDefinitionApi};
+use near_ai_client::{ChatParams, Message, NearAiClient, Role, ToolDefinition, FunctionDefinitionApi};
 use std::time::Duration;
 
 #[tokio::main]
@@ -52,7 +52,7 @@ async fn main() -> anyhow::Result<()> {
         Message::user("What's the latest news about Bitcoin?"),
     ];
 
-    let response = client.chat_with_tools(messages.clone(), Some(0.7), None, Some(&tools)).await?;
+    let response = client.chat_with_tools(messages.clone(), ChatParams::default().with_temperature(0.7), None, Some(&tools), None).await?;
     println!("Response content: {:?}", response.content);
     println!("Tool calls: {:?}", response.tool_calls);
     println!("Finish reason: {}", response.finish_reason);
@@ -100,7 +100,7 @@ async fn main() -> anyhow::Result<()> {
 
         // KEY FIX: Don't offer tools in the follow-up call - force model to respond
         println!("\n=== Sending to NEAR AI (WITHOUT tools to force response) ===");
-        let response2 = client.chat_with_tools(messages_with_result, Some(0.7), None, None).await?;
+        let response2 = client.chat_with_tools(messages_with_result, ChatParams::default().with_temperature(0.7), None, None, None).await?;
         println!("\nResponse 2 content: {:?}", response2.content);
         println!("Response 2 tool calls: {:?}", response2.tool_calls);
         println!("Response 2 finish reason: {}", response2.finish_reason);
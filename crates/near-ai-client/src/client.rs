@@ -8,7 +8,7 @@ use secrecy::{ExposeSecret, SecretString};
 use std::time::Duration;
 use tokio::time::sleep;
 use tokio_stream::Stream;
-use tracing::{debug, instrument, warn};
+use tracing::{debug, info, instrument, warn};
 
 /// Default retry configuration
 const DEFAULT_MAX_RETRIES: u32 = 3;
@@ -52,18 +52,31 @@ impl NearAiClient {
         &self.model
     }
 
+    /// Clone this client with a different default model.
+    ///
+    /// The underlying HTTP connection pool is shared.
+    pub fn with_model(&self, model: impl Into<String>) -> Self {
+        Self {
+            model: model.into(),
+            ..self.clone()
+        }
+    }
+
     /// Send a chat completion request.
+    ///
+    /// `timeout` overrides the client's default timeout for this call.
     #[instrument(skip(self, messages), fields(message_count = messages.len()))]
     pub async fn chat(
         &self,
         messages: Vec<Message>,
-        temperature: Option<f32>,
+        params: ChatParams,
         max_tokens: Option<u32>,
+        timeout: Option<Duration>,
     ) -> Result<String, NearAiError> {
         let request = ChatRequest {
             model: self.model.clone(),
             messages,
-            temperature,
+            params,
             max_tokens,
             stream: Some(false),
             tools: None,
@@ -71,8 +84,7 @@ impl NearAiClient {
         };
 
         let response = self
-            .client
-            .post(format!("{}/chat/completions", self.base_url))
+            .with_timeout(self.client.post(format!("{}/chat/completions", self.base_url)), timeout)
             .header("Authorization", format!("Bearer {}", self.api_key.expose_secret()))
             .header("Content-Type", "application/json")
             .json(&request)
@@ -92,18 +104,21 @@ impl NearAiClient {
     }
 
     /// Send a chat completion request with tool support.
+    ///
+    /// `timeout` overrides the client's default timeout for this call.
     #[instrument(skip(self, messages, tools), fields(message_count = messages.len()))]
     pub async fn chat_with_tools(
         &self,
         messages: Vec<Message>,
-        temperature: Option<f32>,
+        params: ChatParams,
         max_tokens: Option<u32>,
         tools: Option<&[ToolDefinition]>,
+        timeout: Option<Duration>,
     ) -> Result<ChatResponseWithTools, NearAiError> {
         let request = ChatRequest {
             model: self.model.clone(),
             messages,
-            temperature,
+            params,
             max_tokens,
             stream: Some(false),
             tools: tools.map(|t| t.to_vec()),
@@ -114,8 +129,7 @@ impl NearAiClient {
         debug!("Sending chat_with_tools request to {}", url);
 
         let response = match self
-            .client
-            .post(&url)
+            .with_timeout(self.client.post(&url), timeout)
             .header("Authorization", format!("Bearer {}", self.api_key.expose_secret()))
             .header("Content-Type", "application/json")
             .json(&request)
@@ -150,18 +164,63 @@ impl NearAiClient {
         })
     }
 
+    /// Send a chat completion request, falling back through `models` in order.
+    ///
+    /// Moves on to the next model only on retryable errors (rate limits,
+    /// timeouts, 5xx); any other error is returned immediately. With no
+    /// models given, the client's own model is used.
+    #[instrument(skip(self, messages, tools), fields(message_count = messages.len()))]
+    pub async fn chat_with_fallback(
+        &self,
+        messages: Vec<Message>,
+        models: &[&str],
+        params: ChatParams,
+        max_tokens: Option<u32>,
+        tools: Option<&[ToolDefinition]>,
+        timeout: Option<Duration>,
+    ) -> Result<ChatResponseWithTools, NearAiError> {
+        let default_model = [self.model.as_str()];
+        let models = if models.is_empty() { &default_model[..] } else { models };
+        let mut last_error = None;
+
+        for (i, model) in models.iter().enumerate() {
+            let result = self
+                .with_model(*model)
+                .chat_with_tools(messages.clone(), params, max_tokens, tools, timeout)
+                .await;
+
+            match result {
+                Ok(response) => {
+                    if i > 0 {
+                        info!("Request served by fallback model {}", model);
+                    } else {
+                        debug!("Request served by model {}", model);
+                    }
+                    return Ok(response);
+                }
+                Err(e) if e.is_retryable() => {
+                    warn!("Model {} unavailable: {}", model, e);
+                    last_error = Some(e);
+                }
+                Err(e) => return Err(e),
+            }
+        }
+
+        Err(last_error.unwrap_or(NearAiError::EmptyResponse))
+    }
+
     /// Send a streaming chat completion request.
     #[instrument(skip(self, messages), fields(message_count = messages.len()))]
     pub async fn chat_stream(
         &self,
         messages: Vec<Message>,
-        temperature: Option<f32>,
+        params: ChatParams,
         max_tokens: Option<u32>,
     ) -> Result<impl Stream<Item = Result<String, NearAiError>>, NearAiError> {
         let request = ChatRequest {
             model: self.model.clone(),
             messages,
-            temperature,
+            params,
             max_tokens,
             stream: Some(true),
             tools: None,
@@ -184,7 +243,7 @@ impl NearAiClient {
         let stream = response.bytes_stream().map(|result| {
             result
                 .map_err(NearAiError::from)
-                .and_then(|bytes| {
+                .map(|bytes| {
                     // Parse SSE data
                     let text = String::from_utf8_lossy(&bytes);
                     let mut content = String::new();
@@ -206,13 +265,46 @@ impl NearAiClient {
                         }
                     }
 
-                    Ok(content)
+                    content
                 })
         });
 
         Ok(stream)
     }
 
+    /// Generate embeddings for each input, in input order.
+    #[instrument(skip(self, inputs), fields(input_count = inputs.len()))]
+    pub async fn embed(&self, inputs: Vec<String>, model: &str) -> Result<Vec<Vec<f32>>, NearAiError> {
+        if inputs.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let expected = inputs.len();
+        let request = EmbeddingsRequest {
+            model: model.to_string(),
+            input: inputs,
+        };
+
+        let response = self
+            .client
+            .post(format!("{}/embeddings", self.base_url))
+            .header("Authorization", format!("Bearer {}", self.api_key.expose_secret()))
+            .header("Content-Type", "application/json")
+            .json(&request)
+            .send()
+            .await?;
+
+        let mut embeddings = self.handle_response::<EmbeddingsResponse>(response).await?.data;
+        if embeddings.len() != expected {
+            warn!("Expected {} embeddings, got {}", expected, embeddings.len());
+            return Err(NearAiError::EmptyResponse);
+        }
+
+        // The API may return items out of order; `index` ties them to inputs
+        embeddings.sort_by_key(|e| e.index);
+        Ok(embeddings.into_iter().map(|e| e.embedding).collect())
+    }
+
     /// List available models.
     /// Note: NEAR AI Cloud doesn't have a /models endpoint, so we return known models.
     #[instrument(skip(self))]
@@ -248,7 +340,7 @@ impl NearAiClient {
     pub async fn chat_with_retry(
         &self,
         messages: Vec<Message>,
-        temperature: Option<f32>,
+        params: ChatParams,
         max_tokens: Option<u32>,
         max_retries: Option<u32>,
     ) -> Result<String, NearAiError> {
@@ -264,7 +356,7 @@ impl NearAiClient {
                 backoff_ms = (backoff_ms * 2).min(DEFAULT_MAX_BACKOFF_MS);
             }
 
-            match self.chat(messages.clone(), temperature, max_tokens).await {
+            match self.chat(messages.clone(), params, max_tokens, None).await {
                 Ok(response) => return Ok(response),
                 Err(NearAiError::Unauthorized) => return Err(NearAiError::Unauthorized),
                 Err(NearAiError::EmptyResponse) => return Err(NearAiError::EmptyResponse),
@@ -282,12 +374,13 @@ impl NearAiClient {
     }
 
     /// Health check - returns true if API is reachable.
-    /// Tests connectivity by sending a minimal chat completion request.
-    pub async fn health_check(&self) -> bool {
+    /// Tests connectivity and credentials by sending a minimal (one-token)
+    /// chat completion to the configured endpoint, giving up after `timeout`.
+    pub async fn health_check(&self, timeout: Duration) -> bool {
         let request = ChatRequest {
             model: self.model.clone(),
             messages: vec![Message::user("ping")],
-            temperature: None,
+            params: ChatParams::default(),
             max_tokens: Some(1),
             stream: Some(false),
             tools: None,
@@ -297,6 +390,7 @@ impl NearAiClient {
         match self
             .client
             .post(format!("{}/chat/completions", self.base_url))
+            .timeout(timeout)
             .header("Authorization", format!("Bearer {}", self.api_key.expose_secret()))
             .header("Content-Type", "application/json")
             .json(&request)
@@ -318,6 +412,50 @@ impl NearAiClient {
         }
     }
 
+    /// Cheap reachability probe that costs no tokens.
+    ///
+    /// Sends an authenticated `GET /models`, giving up after `timeout`. The
+    /// API is up unless the request fails, the key is rejected (401/403) or
+    /// the server errors (5xx); a 404 still proves the endpoint answers, since
+    /// NEAR AI Cloud doesn't list models.
+    pub async fn ping(&self, timeout: Duration) -> bool {
+        match self
+            .client
+            .get(format!("{}/models", self.base_url))
+            .timeout(timeout)
+            .header("Authorization", format!("Bearer {}", self.api_key.expose_secret()))
+            .send()
+            .await
+        {
+            Ok(response) => {
+                let status = response.status();
+                let up = !(status.is_server_error()
+                    || status == StatusCode::UNAUTHORIZED
+                    || status == StatusCode::FORBIDDEN);
+                if !up {
+                    warn!("NEAR AI ping failed: HTTP {}", status);
+                }
+                up
+            }
+            Err(e) => {
+                warn!("NEAR AI ping failed: {}", e);
+                false
+            }
+        }
+    }
+
+    /// Apply a per-call timeout override to a request.
+    fn with_timeout(
+        &self,
+        builder: reqwest::RequestBuilder,
+        timeout: Option<Duration>,
+    ) -> reqwest::RequestBuilder {
+        match timeout {
+            Some(timeout) => builder.timeout(timeout),
+            None => builder,
+        }
+    }
+
     /// Handle HTTP response, converting errors appropriately.
     async fn handle_response<T: serde::de::DeserializeOwned>(
         &self,
@@ -5,7 +5,10 @@ use thiserror::Error;
 #[derive(Error, Debug)]
 pub enum NearAiError {
     #[error("HTTP error: {0}")]
-    Http(#[from] reqwest::Error),
+    Http(reqwest::Error),
+
+    #[error("Request timed out")]
+    Timeout,
 
     #[error("Rate limit exceeded")]
     RateLimit,
@@ -25,3 +28,27 @@ pub enum NearAiError {
     #[error("Empty response from AI service")]
     EmptyResponse,
 }
+
+impl NearAiError {
+    /// Whether another attempt (or another model) might succeed.
+    ///
+    /// Rate limits, timeouts, connection failures and 5xx responses are
+    /// transient; auth failures, bad requests and empty answers are not.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            NearAiError::RateLimit | NearAiError::Timeout | NearAiError::Http(_) => true,
+            NearAiError::Api { status, .. } => *status >= 500,
+            _ => false,
+        }
+    }
+}
+
+impl From<reqwest::Error> for NearAiError {
+    fn from(e: reqwest::Error) -> Self {
+        if e.is_timeout() {
+            NearAiError::Timeout
+        } else {
+            NearAiError::Http(e)
+        }
+    }
+}
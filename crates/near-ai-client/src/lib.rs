@@ -12,7 +12,7 @@ pub use types::*;
 mod tests {
     use super::*;
     use std::time::Duration;
-    use wiremock::matchers::{header, method, path};
+    use wiremock::matchers::{body_json, body_partial_json, header, method, path};
     use wiremock::{Mock, MockServer, ResponseTemplate};
 
     async fn create_test_client(mock_server: &MockServer) -> NearAiClient {
@@ -59,11 +59,71 @@ mod tests {
         let client = create_test_client(&mock_server).await;
         let messages = vec![Message::user("Hello")];
 
-        let result = client.chat(messages, Some(0.7), None).await;
+        let result = client.chat(messages, ChatParams::default().with_temperature(0.7), None, None).await;
         assert!(result.is_ok());
         assert_eq!(result.unwrap(), "Hello! How can I help you?");
     }
 
+    #[test]
+    fn test_unset_chat_params_are_omitted() {
+        let request = ChatRequest {
+            model: "test-model".into(),
+            messages: vec![Message::user("Hello")],
+            params: ChatParams {
+                top_p: Some(0.9),
+                ..Default::default()
+            },
+            max_tokens: None,
+            stream: None,
+            tools: None,
+            tool_choice: None,
+        };
+
+        let json = serde_json::to_value(&request).unwrap();
+        assert_eq!(json["top_p"], serde_json::json!(0.9f32));
+        for key in ["temperature", "frequency_penalty", "presence_penalty", "max_tokens"] {
+            assert!(json.get(key).is_none(), "{} should be omitted", key);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_chat_with_tools_sends_sampling_params() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .and(body_partial_json(serde_json::json!({
+                "temperature": 0.5,
+                "presence_penalty": 1.0
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "id": "chatcmpl-123",
+                "object": "chat.completion",
+                "created": 1677652288,
+                "model": "test-model",
+                "choices": [{
+                    "index": 0,
+                    "message": { "role": "assistant", "content": "Tuned" },
+                    "finish_reason": "stop"
+                }]
+            })))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let client = create_test_client(&mock_server).await;
+        let params = ChatParams {
+            presence_penalty: Some(1.0),
+            ..ChatParams::default().with_temperature(0.5)
+        };
+        let response = client
+            .chat_with_tools(vec![Message::user("Hello")], params, None, None, None)
+            .await
+            .unwrap();
+
+        assert_eq!(response.content.as_deref(), Some("Tuned"));
+    }
+
     #[tokio::test]
     async fn test_chat_empty_response() {
         let mock_server = MockServer::start().await;
@@ -86,7 +146,7 @@ mod tests {
         let client = create_test_client(&mock_server).await;
         let messages = vec![Message::user("Hello")];
 
-        let result = client.chat(messages, Some(0.7), None).await;
+        let result = client.chat(messages, ChatParams::default().with_temperature(0.7), None, None).await;
         assert!(matches!(result, Err(NearAiError::EmptyResponse)));
     }
 
@@ -103,7 +163,7 @@ mod tests {
         let client = create_test_client(&mock_server).await;
         let messages = vec![Message::user("Hello")];
 
-        let result = client.chat(messages, Some(0.7), None).await;
+        let result = client.chat(messages, ChatParams::default().with_temperature(0.7), None, None).await;
         assert!(matches!(result, Err(NearAiError::RateLimit)));
     }
 
@@ -120,7 +180,117 @@ mod tests {
         let client = create_test_client(&mock_server).await;
         let messages = vec![Message::user("Hello")];
 
-        let result = client.chat(messages, Some(0.7), None).await;
+        let result = client.chat(messages, ChatParams::default().with_temperature(0.7), None, None).await;
+        assert!(matches!(result, Err(NearAiError::Unauthorized)));
+    }
+
+    #[tokio::test]
+    async fn test_chat_per_call_timeout() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(serde_json::json!({
+                        "id": "chatcmpl-123",
+                        "object": "chat.completion",
+                        "created": 1677652288,
+                        "model": "test-model",
+                        "choices": [{
+                            "index": 0,
+                            "message": { "role": "assistant", "content": "Too slow" },
+                            "finish_reason": "stop"
+                        }]
+                    }))
+                    .set_delay(Duration::from_millis(500)),
+            )
+            .mount(&mock_server)
+            .await;
+
+        // Client default (30s) would wait; the per-call override doesn't
+        let client = create_test_client(&mock_server).await;
+        let messages = vec![Message::user("Hello")];
+
+        let result = client
+            .chat(messages.clone(), ChatParams::default(), None, Some(Duration::from_millis(50)))
+            .await;
+        assert!(matches!(result, Err(NearAiError::Timeout)));
+
+        let result = client
+            .chat_with_tools(messages, ChatParams::default(), None, None, Some(Duration::from_millis(50)))
+            .await;
+        assert!(matches!(result, Err(NearAiError::Timeout)));
+    }
+
+    #[tokio::test]
+    async fn test_chat_falls_back_to_next_model() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .and(body_partial_json(serde_json::json!({ "model": "primary-model" })))
+            .respond_with(ResponseTemplate::new(429))
+            .mount(&mock_server)
+            .await;
+
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .and(body_partial_json(serde_json::json!({ "model": "backup-model" })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "id": "chatcmpl-123",
+                "object": "chat.completion",
+                "created": 1677652288,
+                "model": "backup-model",
+                "choices": [{
+                    "index": 0,
+                    "message": { "role": "assistant", "content": "From the backup" },
+                    "finish_reason": "stop"
+                }]
+            })))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let client = create_test_client(&mock_server).await;
+        let response = client
+            .chat_with_fallback(
+                vec![Message::user("Hello")],
+                &["primary-model", "backup-model"],
+                ChatParams::default(),
+                None,
+                None,
+                None,
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.content.as_deref(), Some("From the backup"));
+    }
+
+    #[tokio::test]
+    async fn test_fallback_stops_on_unauthorized() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .respond_with(ResponseTemplate::new(401))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let client = create_test_client(&mock_server).await;
+        let result = client
+            .chat_with_fallback(
+                vec![Message::user("Hello")],
+                &["primary-model", "backup-model"],
+                ChatParams::default(),
+                None,
+                None,
+                None,
+            )
+            .await;
+
         assert!(matches!(result, Err(NearAiError::Unauthorized)));
     }
 
@@ -149,7 +319,72 @@ mod tests {
             .await;
 
         let client = create_test_client(&mock_server).await;
-        assert!(client.health_check().await);
+        assert!(client.health_check(Duration::from_secs(5)).await);
+    }
+
+    #[tokio::test]
+    async fn test_ping() {
+        let mock_server = MockServer::start().await;
+
+        // Never charged a completion
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(0)
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/models"))
+            .and(header("Authorization", "Bearer test-api-key"))
+            .respond_with(ResponseTemplate::new(404))
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/models"))
+            .respond_with(ResponseTemplate::new(401))
+            .mount(&mock_server)
+            .await;
+
+        let client = create_test_client(&mock_server).await;
+        assert!(client.ping(Duration::from_secs(5)).await);
+
+        let client = NearAiClient::new("wrong-key", mock_server.uri(), "test-model", Duration::from_secs(30))
+            .unwrap();
+        assert!(!client.ping(Duration::from_secs(5)).await);
+
+        let client = NearAiClient::new("test-api-key", "http://127.0.0.1:9", "test-model", Duration::from_secs(30))
+            .unwrap();
+        assert!(!client.ping(Duration::from_secs(5)).await);
+    }
+
+    #[tokio::test]
+    async fn test_health_check_failures() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .and(header("Authorization", "Bearer test-api-key"))
+            .respond_with(ResponseTemplate::new(200).set_delay(Duration::from_millis(500)))
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/chat/completions"))
+            .respond_with(ResponseTemplate::new(401))
+            .mount(&mock_server)
+            .await;
+
+        // Bad credentials
+        let client = NearAiClient::new("wrong-key", mock_server.uri(), "test-model", Duration::from_secs(30))
+            .unwrap();
+        assert!(!client.health_check(Duration::from_secs(5)).await);
+
+        // Slower than the health check timeout
+        let client = create_test_client(&mock_server).await;
+        assert!(!client.health_check(Duration::from_millis(50)).await);
+
+        // Nothing listening
+        let client = NearAiClient::new("test-api-key", "http://127.0.0.1:9", "test-model", Duration::from_secs(30))
+            .unwrap();
+        assert!(!client.health_check(Duration::from_secs(5)).await);
     }
 
     #[tokio::test]
@@ -181,11 +416,61 @@ mod tests {
         let client = create_test_client(&mock_server).await;
         let messages = vec![Message::user("Hello")];
 
-        let result = client.chat_with_retry(messages, None, None, Some(3)).await;
+        let result = client.chat_with_retry(messages, ChatParams::default(), None, Some(3)).await;
         assert!(result.is_ok());
         assert_eq!(result.unwrap(), "Success on first try");
     }
 
+    #[tokio::test]
+    async fn test_embed_success() {
+        let mock_server = MockServer::start().await;
+
+        let response_body = serde_json::json!({
+            "object": "list",
+            "model": "test-embedding-model",
+            "data": [
+                { "object": "embedding", "index": 1, "embedding": [0.4, 0.5] },
+                { "object": "embedding", "index": 0, "embedding": [0.1, 0.2] }
+            ],
+            "usage": { "prompt_tokens": 4, "total_tokens": 4 }
+        });
+
+        Mock::given(method("POST"))
+            .and(path("/embeddings"))
+            .and(header("Authorization", "Bearer test-api-key"))
+            .and(body_json(serde_json::json!({
+                "model": "test-embedding-model",
+                "input": ["first", "second"]
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(&response_body))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let client = create_test_client(&mock_server).await;
+        let embeddings = client
+            .embed(vec!["first".into(), "second".into()], "test-embedding-model")
+            .await
+            .unwrap();
+
+        assert_eq!(embeddings, vec![vec![0.1, 0.2], vec![0.4, 0.5]]);
+    }
+
+    #[tokio::test]
+    async fn test_embed_rate_limit() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("POST"))
+            .and(path("/embeddings"))
+            .respond_with(ResponseTemplate::new(429))
+            .mount(&mock_server)
+            .await;
+
+        let client = create_test_client(&mock_server).await;
+        let result = client.embed(vec!["hello".into()], "test-embedding-model").await;
+        assert!(matches!(result, Err(NearAiError::RateLimit)));
+    }
+
     #[tokio::test]
     async fn test_message_constructors() {
         let system = Message::system("You are a helpful assistant");
@@ -210,5 +495,7 @@ mod tests {
         let mock_server = MockServer::start().await;
         let client = create_test_client(&mock_server).await;
         assert_eq!(client.model(), "test-model");
+        assert_eq!(client.with_model("other-model").model(), "other-model");
+        assert_eq!(client.model(), "test-model");
     }
 }
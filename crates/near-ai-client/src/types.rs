@@ -103,13 +103,36 @@ pub struct FunctionDefinitionApi {
     pub parameters: serde_json::Value,
 }
 
+/// Optional sampling parameters for a chat completion.
+///
+/// Unset fields are omitted from the request so the API's defaults apply.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct ChatParams {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub top_p: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub frequency_penalty: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub presence_penalty: Option<f32>,
+}
+
+impl ChatParams {
+    /// Set the sampling temperature.
+    pub fn with_temperature(mut self, temperature: f32) -> Self {
+        self.temperature = Some(temperature);
+        self
+    }
+}
+
 /// Chat completion request.
 #[derive(Debug, Clone, Serialize)]
 pub struct ChatRequest {
     pub model: String,
     pub messages: Vec<Message>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub temperature: Option<f32>,
+    #[serde(flatten)]
+    pub params: ChatParams,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub max_tokens: Option<u32>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -176,6 +199,35 @@ pub struct Delta {
     pub content: Option<String>,
 }
 
+/// Embeddings request.
+#[derive(Debug, Clone, Serialize)]
+pub struct EmbeddingsRequest {
+    pub model: String,
+    pub input: Vec<String>,
+}
+
+/// Embeddings response.
+#[derive(Debug, Clone, Deserialize)]
+pub struct EmbeddingsResponse {
+    pub data: Vec<EmbeddingData>,
+    pub model: Option<String>,
+    pub usage: Option<EmbeddingsUsage>,
+}
+
+/// A single embedding vector.
+#[derive(Debug, Clone, Deserialize)]
+pub struct EmbeddingData {
+    /// Position of the corresponding input.
+    pub index: usize,
+    pub embedding: Vec<f32>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct EmbeddingsUsage {
+    pub prompt_tokens: u32,
+    pub total_tokens: u32,
+}
+
 /// Model information.
 #[derive(Debug, Clone, Deserialize)]
 pub struct Model {
@@ -0,0 +1,49 @@
+//! Operator alerts delivered over Signal.
+
+use anyhow::Context;
+use async_trait::async_trait;
+use signal_client::SignalClient;
+use std::sync::Arc;
+use x402_payments::NotificationChannel;
+
+/// Sends operator alerts as Signal messages to the admin number.
+pub struct SignalAlertChannel {
+    signal: Arc<SignalClient>,
+    admin_number: String,
+    /// Bot account the alerts are sent from (first registered one if unset).
+    from_account: Option<String>,
+}
+
+impl SignalAlertChannel {
+    pub fn new(signal: Arc<SignalClient>, admin_number: impl Into<String>) -> Self {
+        Self {
+            signal,
+            admin_number: admin_number.into(),
+            from_account: None,
+        }
+    }
+
+    /// Send alerts from a specific bot account.
+    pub fn with_from_account(mut self, account: impl Into<String>) -> Self {
+        self.from_account = Some(account.into());
+        self
+    }
+}
+
+#[async_trait]
+impl NotificationChannel for SignalAlertChannel {
+    async fn send(&self, message: &str) -> anyhow::Result<()> {
+        let from = match &self.from_account {
+            Some(account) => account.clone(),
+            None => self
+                .signal
+                .list_accounts()
+                .await?
+                .into_iter()
+                .next()
+                .context("No registered Signal account to send alerts from")?,
+        };
+        self.signal.send(&from, &self.admin_number, message).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,266 @@
+//! OpenAI-compatible HTTP API.
+//!
+//! Serves `POST /v1/chat/completions` so other applications can use the
+//! bot's TEE-hosted NEAR AI access, tools and conversation memory. Requests
+//! run through the same [`ChatHandler`] pipeline as Signal messages.
+
+use crate::commands::ChatHandler;
+use crate::config::ApiConfig;
+use crate::error::AppResult;
+use anyhow::Context;
+use api_common::constant_time_eq;
+use axum::{
+    extract::State,
+    http::{header::AUTHORIZATION, HeaderMap, StatusCode},
+    routing::post,
+    Json, Router,
+};
+use conversation_store::ConversationStore;
+use secrecy::{ExposeSecret, SecretString};
+use serde::{Deserialize, Serialize};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tokio::net::TcpListener;
+use tracing::{error, info, warn};
+
+/// Shared state for API handlers.
+pub struct ApiState {
+    /// Chat pipeline used to answer requests.
+    pub chat: Arc<ChatHandler>,
+    /// Conversation store backing the chat pipeline.
+    pub conversations: Arc<ConversationStore>,
+    /// Model name reported in responses.
+    pub model: String,
+    /// Token clients must present as `Authorization: Bearer <token>`.
+    pub bearer_token: SecretString,
+}
+
+/// A chat message in OpenAI format.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChatMessage {
+    pub role: String,
+    #[serde(default)]
+    pub content: Option<String>,
+}
+
+/// OpenAI chat completion request.
+///
+/// When `user` is set, the bot keeps that user's history server-side and
+/// only the latest user message is read from `messages`. Without it the
+/// request is stateless and the full `messages` history is used.
+#[derive(Debug, Deserialize)]
+pub struct ChatCompletionRequest {
+    /// Requested model (ignored; the bot's configured model is used).
+    #[serde(default)]
+    pub model: Option<String>,
+    pub messages: Vec<ChatMessage>,
+    #[serde(default)]
+    pub user: Option<String>,
+}
+
+/// OpenAI chat completion response.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ChatCompletionResponse {
+    pub id: String,
+    pub object: String,
+    pub created: i64,
+    pub model: String,
+    pub choices: Vec<ChatChoice>,
+    pub usage: ChatUsage,
+}
+
+/// A completion choice.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ChatChoice {
+    pub index: u32,
+    pub message: ChatMessage,
+    pub finish_reason: String,
+}
+
+/// Token usage for a completion.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ChatUsage {
+    pub prompt_tokens: u32,
+    pub completion_tokens: u32,
+    pub total_tokens: u32,
+}
+
+/// OpenAI-style error body.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ErrorResponse {
+    pub error: ErrorDetail,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ErrorDetail {
+    pub message: String,
+    #[serde(rename = "type")]
+    pub error_type: String,
+}
+
+type ApiError = (StatusCode, Json<ErrorResponse>);
+
+fn api_error(status: StatusCode, message: impl Into<String>, error_type: &str) -> ApiError {
+    (
+        status,
+        Json(ErrorResponse {
+            error: ErrorDetail {
+                message: message.into(),
+                error_type: error_type.to_string(),
+            },
+        }),
+    )
+}
+
+/// Create the API router.
+pub fn create_router(state: Arc<ApiState>) -> Router {
+    Router::new()
+        .route("/v1/chat/completions", post(chat_completions))
+        .with_state(state)
+}
+
+/// Bind the API server and run it as a background task.
+///
+/// Returns `None` when the API is disabled or no bearer token is configured.
+pub async fn spawn_api_server(
+    config: &ApiConfig,
+    chat: Arc<ChatHandler>,
+    conversations: Arc<ConversationStore>,
+    model: String,
+) -> AppResult<Option<tokio::task::JoinHandle<()>>> {
+    if !config.enabled {
+        return Ok(None);
+    }
+
+    let Some(bearer_token) = config.bearer_token.clone() else {
+        warn!("API enabled but API__BEARER_TOKEN not set - not starting");
+        return Ok(None);
+    };
+
+    let state = Arc::new(ApiState {
+        chat,
+        conversations,
+        model,
+        bearer_token,
+    });
+    let router = create_router(state);
+
+    let addr = SocketAddr::from(([0, 0, 0, 0], config.port));
+    let listener = TcpListener::bind(addr)
+        .await
+        .with_context(|| format!("Failed to bind API server to {}", addr))?;
+
+    info!("Chat completions API listening on {}", addr);
+
+    let handle = tokio::spawn(async move {
+        if let Err(e) = axum::serve(listener, router).await {
+            error!("API server error: {}", e);
+        }
+    });
+
+    Ok(Some(handle))
+}
+
+/// `POST /v1/chat/completions`
+async fn chat_completions(
+    State(state): State<Arc<ApiState>>,
+    headers: HeaderMap,
+    Json(request): Json<ChatCompletionRequest>,
+) -> Result<Json<ChatCompletionResponse>, ApiError> {
+    if !is_authorized(&state.bearer_token, &headers) {
+        return Err(api_error(
+            StatusCode::UNAUTHORIZED,
+            "Invalid or missing bearer token",
+            "invalid_request_error",
+        ));
+    }
+
+    let Some(last_user) = request
+        .messages
+        .iter()
+        .rposition(|m| m.role == "user" && m.content.as_deref().is_some_and(|c| !c.is_empty()))
+    else {
+        return Err(api_error(
+            StatusCode::BAD_REQUEST,
+            "messages must contain a user message",
+            "invalid_request_error",
+        ));
+    };
+    let text = request.messages[last_user].content.clone().unwrap_or_default();
+
+    // Keyed conversations keep history server-side; otherwise use a
+    // throwaway conversation seeded from the request's history.
+    let stateless = request.user.is_none();
+    let conversation_id = match request.user.as_deref() {
+        Some(user) => format!("api:{}", user),
+        None => format!("api:{}", uuid::Uuid::new_v4()),
+    };
+
+    if stateless {
+        for message in &request.messages[..last_user] {
+            let Some(content) = message.content.as_deref() else {
+                continue;
+            };
+            if message.role == "user" || message.role == "assistant" {
+                state
+                    .conversations
+                    .add_message(&conversation_id, &message.role, content, None)
+                    .await
+                    .map_err(|e| internal_error(&e))?;
+            }
+        }
+    }
+
+    let result = state.chat.respond(&conversation_id, &text, None).await;
+
+    if stateless {
+        let _ = state.conversations.clear(&conversation_id).await;
+    }
+
+    let reply = result.map_err(|e| internal_error(&e))?;
+    let Some(usage) = reply.usage else {
+        return Err(api_error(StatusCode::BAD_GATEWAY, reply.content, "upstream_error"));
+    };
+
+    Ok(Json(ChatCompletionResponse {
+        id: format!("chatcmpl-{}", uuid::Uuid::new_v4().simple()),
+        object: "chat.completion".to_string(),
+        created: chrono::Utc::now().timestamp(),
+        model: state.model.clone(),
+        choices: vec![ChatChoice {
+            index: 0,
+            message: ChatMessage {
+                role: "assistant".to_string(),
+                content: Some(reply.content),
+            },
+            finish_reason: "stop".to_string(),
+        }],
+        usage: ChatUsage {
+            prompt_tokens: usage.prompt_tokens,
+            completion_tokens: usage.completion_tokens,
+            total_tokens: usage.prompt_tokens + usage.completion_tokens,
+        },
+    }))
+}
+
+fn internal_error(e: &dyn std::fmt::Display) -> ApiError {
+    error!("Chat completion failed: {}", e);
+    api_error(
+        StatusCode::INTERNAL_SERVER_ERROR,
+        "Internal error",
+        "server_error",
+    )
+}
+
+/// Check the request's bearer token against the configured one.
+fn is_authorized(expected: &SecretString, headers: &HeaderMap) -> bool {
+    let provided = headers
+        .get(AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "));
+
+    match provided {
+        Some(token) => constant_time_eq(token.as_bytes(), expected.expose_secret().as_bytes()),
+        None => false,
+    }
+}
@@ -6,7 +6,7 @@ use async_trait::async_trait;
 use signal_client::BotMessage;
 use std::sync::Arc;
 use tracing::info;
-use x402_payments::{CreditStore, PricingCalculator};
+use x402_payments::{CreditBalance, CreditStore, PricingCalculator};
 
 pub struct BalanceHandler {
     credit_store: Arc<CreditStore>,
@@ -18,42 +18,71 @@ impl BalanceHandler {
     }
 }
 
+/// Render a balance. Deposits are only recorded once confirmed on chain,
+/// so everything shown here is spendable.
+fn format_balance(balance: &CreditBalance) -> String {
+    if balance.credits_remaining == 0 && balance.total_deposited == 0 && balance.total_received == 0 {
+        return "**Your Balance**\n\n\
+                You have no credits yet.\n\n\
+                Use `!deposit` to get deposit addresses and add credits."
+            .to_string();
+    }
+
+    format!(
+        "**Your Balance**\n\n\
+         Credits: {} ({})\n\
+         Total Deposited: {}\n\
+         Total Used: {}\n\n\
+         Use `!deposit` to add more credits.",
+        balance.credits_remaining,
+        PricingCalculator::format_usdc(balance.credits_remaining),
+        PricingCalculator::format_usdc(balance.total_deposited),
+        PricingCalculator::format_usdc(balance.total_consumed),
+    )
+}
+
 #[async_trait]
 impl CommandHandler for BalanceHandler {
     fn trigger(&self) -> Option<&str> {
         Some("!balance")
     }
 
+    fn description(&self) -> Option<&str> {
+        Some("Check your credit balance")
+    }
+
     async fn execute(&self, message: &BotMessage) -> AppResult<String> {
         let user_id = &message.source;
         let balance = self.credit_store.get_balance(user_id).await;
 
         info!("Balance check for {}: {} credits", user_id, balance.credits_remaining);
 
-        let usdc_balance = PricingCalculator::format_usdc(balance.credits_remaining);
-        let usdc_deposited = PricingCalculator::format_usdc(balance.total_deposited);
-        let usdc_consumed = PricingCalculator::format_usdc(balance.total_consumed);
-
-        let response = if balance.credits_remaining == 0 && balance.total_deposited == 0 {
-            format!(
-                "**Your Balance**\n\n\
-                 You have no credits yet.\n\n\
-                 Use `!deposit` to get deposit addresses and add credits."
-            )
-        } else {
-            format!(
-                "**Your Balance**\n\n\
-                 Credits: {} ({})\n\
-                 Total Deposited: {}\n\
-                 Total Used: {}\n\n\
-                 Use `!deposit` to add more credits.",
-                balance.credits_remaining,
-                usdc_balance,
-                usdc_deposited,
-                usdc_consumed,
-            )
-        };
-
-        Ok(response)
+        Ok(format_balance(&balance))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn balance(credits_remaining: u64, total_deposited: u64) -> CreditBalance {
+        CreditBalance {
+            credits_remaining,
+            total_deposited,
+            ..CreditBalance::new("+14155551234".to_string())
+        }
+    }
+
+    #[test]
+    fn test_format_balance() {
+        let response = format_balance(&balance(2_000_000, 3_000_000));
+
+        assert!(response.contains("Credits: 2000000 ($2.000000)"));
+        assert!(response.contains("Total Deposited: $3.000000"));
+    }
+
+    #[test]
+    fn test_format_empty_balance() {
+        assert!(format_balance(&balance(0, 0)).contains("no credits yet"));
     }
 }
@@ -1,21 +1,101 @@
 //! Chat command - proxies messages to NEAR AI.
 
-use crate::commands::CommandHandler;
+use crate::commands::{conversation_key, CommandHandler};
+use crate::config::GroupPolicy;
 use crate::error::AppResult;
+use crate::personas::{Persona, PersonaRegistry};
 use async_trait::async_trait;
 use conversation_store::{ConversationStore, StoredToolCall};
 use near_ai_client::{
-    FunctionDefinitionApi, Message, NearAiClient, NearAiError, Role,
+    ChatParams, FunctionDefinitionApi, Message, NearAiClient, NearAiError, Role,
     ToolDefinition as NearToolDefinition,
 };
+use chrono::{DateTime, Utc};
 use signal_client::{BotMessage, SignalClient};
-use std::sync::Arc;
-use tools::{FunctionCall as ToolsFunctionCall, ToolCall as ToolsToolCall, ToolExecutor, ToolRegistry};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio_stream::StreamExt;
+use tools::{FunctionCall as ToolsFunctionCall, ToolCall as ToolsToolCall, ToolContext, ToolExecutor, ToolRegistry};
 use tracing::{debug, error, info, instrument, warn};
 use x402_payments::{
-    calculate_credits, estimate_credits, CreditStore, PricingConfig, TokenUsage, UsageRecord,
+    calculate_credits, estimate_credits, max_completion_tokens, CreditBalance, CreditStore,
+    PricingConfig, TokenUsage, UsageRecord,
 };
 
+/// Placeholder sent before a streamed reply's first tokens arrive.
+const STREAM_PLACEHOLDER: &str = "…";
+
+/// Signal only accepts a handful of edits per message; stop updating a
+/// streamed reply after this many so the final text can still be applied.
+const MAX_STREAM_EDITS: usize = 8;
+
+/// Instructions for summarizing the oldest part of a conversation.
+const SUMMARY_PROMPT: &str = "Summarize this excerpt of a conversation between a user and an assistant \
+in a short paragraph. Keep facts, names, numbers, decisions and open questions the assistant \
+will need later; drop pleasantries.";
+
+/// Longest summary requested, in tokens.
+const SUMMARY_MAX_TOKENS: u32 = 400;
+
+/// Reply when the model still has no answer after the tool-call limit.
+const TOOL_LIMIT_MESSAGE: &str =
+    "I couldn't complete that after several tool attempts. Please try rephrasing your request.";
+
+/// Outcome of running one user message through the model and tools.
+#[derive(Debug, Clone)]
+pub struct ChatReply {
+    /// Text to return to the user.
+    pub content: String,
+    /// Tokens used across all model calls, or `None` if the model never
+    /// produced a final answer (`content` is then a user-facing error notice).
+    pub usage: Option<TokenUsage>,
+    /// Timestamp of the Signal message the reply was streamed into, which
+    /// should be edited to show the final `content`.
+    pub streamed_to: Option<i64>,
+}
+
+impl ChatReply {
+    fn failed(content: impl Into<String>) -> Self {
+        Self {
+            content: content.into(),
+            usage: None,
+            streamed_to: None,
+        }
+    }
+}
+
+/// Remembers which users were told their balance is running low, so each
+/// is warned once per top-up.
+#[derive(Debug, Default)]
+struct LowBalanceWarnings {
+    warned_at: Mutex<HashMap<String, DateTime<Utc>>>,
+}
+
+impl LowBalanceWarnings {
+    /// Whether a charge of `charged` credits that left `balance` should warn
+    /// the user, recording the warning if so.
+    ///
+    /// The user is warned when the balance covers fewer than `messages` more
+    /// charges of that size, unless they already were since their last deposit.
+    fn should_warn(&self, balance: &CreditBalance, charged: u64, messages: u64) -> bool {
+        let threshold = charged.saturating_mul(messages);
+        if threshold == 0 || balance.credits_remaining >= threshold {
+            return false;
+        }
+
+        let mut warned_at = self.warned_at.lock().unwrap();
+        let already_warned = warned_at
+            .get(&balance.user_id)
+            .is_some_and(|at| balance.last_deposit_at.is_none_or(|deposit| deposit <= *at));
+        if already_warned {
+            return false;
+        }
+        warned_at.insert(balance.user_id.clone(), Utc::now());
+        true
+    }
+}
+
 pub struct ChatHandler {
     near_ai: Arc<NearAiClient>,
     conversations: Arc<ConversationStore>,
@@ -32,9 +112,30 @@ pub struct ChatHandler {
     credit_store: Option<Arc<CreditStore>>,
     /// Pricing configuration.
     pricing_config: PricingConfig,
+    /// Per-account model and system prompt overrides.
+    personas: Option<Arc<PersonaRegistry>>,
+    /// Timeout override for requests that offer tools.
+    tool_timeout: Option<Duration>,
+    /// Backup model tried when the primary is rate limited or unavailable.
+    fallback_model: Option<String>,
+    /// When to answer group messages.
+    group_policy: GroupPolicy,
+    /// Minimum time between edits of a streamed Signal reply (`None`
+    /// disables streaming).
+    stream_interval: Option<Duration>,
+    /// Sampling parameters sent with every chat completion.
+    sampling: ChatParams,
+    /// Users already told their balance is low.
+    low_balance_warnings: LowBalanceWarnings,
+    /// Message count above which the oldest messages are summarized
+    /// (`None` leaves them to be trimmed).
+    summarize_after: Option<usize>,
+    /// Oldest messages replaced by each summary.
+    summarize_batch: usize,
 }
 
 impl ChatHandler {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         near_ai: Arc<NearAiClient>,
         conversations: Arc<ConversationStore>,
@@ -57,10 +158,20 @@ impl ChatHandler {
             github_repo,
             credit_store: None,
             pricing_config: PricingConfig::default(),
+            personas: None,
+            tool_timeout: None,
+            fallback_model: None,
+            group_policy: GroupPolicy::default(),
+            stream_interval: None,
+            sampling: ChatParams::default().with_temperature(0.7),
+            low_balance_warnings: LowBalanceWarnings::default(),
+            summarize_after: None,
+            summarize_batch: 0,
         }
     }
 
     /// Create a new ChatHandler with payment integration.
+    #[allow(clippy::too_many_arguments)]
     pub fn with_payments(
         near_ai: Arc<NearAiClient>,
         conversations: Arc<ConversationStore>,
@@ -85,9 +196,79 @@ impl ChatHandler {
             github_repo,
             credit_store: Some(credit_store),
             pricing_config,
+            personas: None,
+            tool_timeout: None,
+            fallback_model: None,
+            group_policy: GroupPolicy::default(),
+            stream_interval: None,
+            sampling: ChatParams::default().with_temperature(0.7),
+            low_balance_warnings: LowBalanceWarnings::default(),
+            summarize_after: None,
+            summarize_batch: 0,
         }
     }
 
+    /// Answer each bot account with its own persona.
+    ///
+    /// Conversation history is then kept per receiving account, so personas
+    /// hosted on different numbers don't share context.
+    pub fn with_personas(mut self, personas: Arc<PersonaRegistry>) -> Self {
+        self.personas = Some(personas);
+        self
+    }
+
+    /// Use a longer NEAR AI timeout for requests that offer tools.
+    pub fn with_tool_timeout(mut self, timeout: Duration) -> Self {
+        self.tool_timeout = Some(timeout);
+        self
+    }
+
+    /// Cut tool results longer than `max_chars` characters before they are
+    /// stored and sent to the model.
+    pub fn with_max_tool_result_len(mut self, max_chars: usize) -> Self {
+        self.tool_executor = Arc::new(
+            ToolExecutor::new(self.tool_registry.clone()).with_max_response_len(max_chars),
+        );
+        self
+    }
+
+    /// Fall back to `model` when the primary model is rate limited or unavailable.
+    pub fn with_fallback_model(mut self, model: impl Into<String>) -> Self {
+        self.fallback_model = Some(model.into());
+        self
+    }
+
+    /// Decide which group messages get an answer.
+    pub fn with_group_policy(mut self, policy: GroupPolicy) -> Self {
+        self.group_policy = policy;
+        self
+    }
+
+    /// Stream answers into a Signal message that is edited as tokens arrive,
+    /// at most once per `interval`.
+    ///
+    /// Only answers produced without offering tools are streamed; the rest
+    /// are sent in one message as before.
+    pub fn with_streaming(mut self, interval: Duration) -> Self {
+        self.stream_interval = Some(interval);
+        self
+    }
+
+    /// Override the sampling parameters (temperature 0.7 by default).
+    pub fn with_sampling(mut self, sampling: ChatParams) -> Self {
+        self.sampling = sampling;
+        self
+    }
+
+    /// Once a conversation holds more than `after` messages, have the model
+    /// summarize the oldest `batch` into one message rather than letting them
+    /// be trimmed. `after` should be below the store's message limit.
+    pub fn with_summarization(mut self, after: usize, batch: usize) -> Self {
+        self.summarize_after = Some(after);
+        self.summarize_batch = batch;
+        self
+    }
+
     /// Format credits as USDC for display.
     fn format_credits(credits: u64) -> String {
         let usdc = credits as f64 / 1_000_000.0;
@@ -99,17 +280,39 @@ impl ChatHandler {
     }
 
     /// Build system prompt with identity information and current timestamp.
-    fn build_system_prompt(&self) -> String {
-        crate::config::build_system_prompt_with_identity(
-            &self.system_prompt,
+    ///
+    /// A conversation's `!system` prompt replaces the base prompt, and its
+    /// `!persona` override is placed before either.
+    fn build_system_prompt(
+        &self,
+        base_prompt: &str,
+        system_override: Option<&str>,
+        prompt_override: Option<&str>,
+    ) -> String {
+        let prompt = crate::config::build_system_prompt_with_identity(
+            system_override.unwrap_or(base_prompt),
             self.signal_username.as_deref(),
             self.github_repo.as_deref(),
-        )
+        );
+        match prompt_override {
+            Some(custom) => format!("{}\n\n{}", custom, prompt),
+            None => prompt,
+        }
     }
 
     /// Build messages for NEAR AI request from conversation store.
-    async fn build_messages(&self, conversation_id: &str) -> AppResult<Vec<Message>> {
-        let system_prompt = self.build_system_prompt();
+    async fn build_messages(
+        &self,
+        conversation_id: &str,
+        base_prompt: &str,
+    ) -> AppResult<Vec<Message>> {
+        let system_override = self.conversations.system_prompt_override(conversation_id).await;
+        let prompt_override = self.conversations.prompt_override(conversation_id).await;
+        let system_prompt = self.build_system_prompt(
+            base_prompt,
+            system_override.as_deref(),
+            prompt_override.as_deref(),
+        );
         let stored_messages = self
             .conversations
             .to_openai_messages(conversation_id, Some(&system_prompt))
@@ -151,6 +354,83 @@ impl ChatHandler {
         Ok(messages)
     }
 
+    /// Fold the oldest messages into a model-written summary once the
+    /// conversation is over the summarization threshold.
+    ///
+    /// The summary is paid for out of the message's credit `budget`, so it's
+    /// skipped when its prompt and longest reply wouldn't fit. Returns the
+    /// summary call's token usage. A failed or skipped call leaves the
+    /// history to be trimmed as usual.
+    async fn compact_history(
+        &self,
+        conversation_id: &str,
+        near_ai: &NearAiClient,
+        budget: Option<u64>,
+        pricing: &PricingConfig,
+    ) -> AppResult<Option<TokenUsage>> {
+        let Some(after) = self.summarize_after else {
+            return Ok(None);
+        };
+        let Some(conversation) = self.conversations.get(conversation_id).await? else {
+            return Ok(None);
+        };
+        let Some(oldest) = conversation.messages.first().map(|m| m.timestamp) else {
+            return Ok(None);
+        };
+        if conversation.messages.len() <= after {
+            return Ok(None);
+        }
+
+        // The same messages the store replaces: tool results go with their
+        // call, pinned messages stay as they are and the newest is kept
+        let end = conversation.summary_end(self.summarize_batch);
+        let transcript = conversation.messages[..end]
+            .iter()
+            .filter(|m| !m.pinned)
+            .filter_map(|m| m.content.as_deref().map(|content| format!("{}: {}", m.role, content)))
+            .collect::<Vec<_>>()
+            .join("\n\n");
+        if transcript.is_empty() {
+            return Ok(None);
+        }
+
+        // Same ~4 chars per token estimate as the answer's budget
+        if let Some(budget) = budget {
+            let prompt_tokens = u32::try_from((SUMMARY_PROMPT.len() + transcript.len()) / 4).unwrap_or(u32::MAX);
+            if max_completion_tokens(prompt_tokens, budget, pricing).is_none_or(|tokens| tokens < SUMMARY_MAX_TOKENS) {
+                info!("Summary for {} doesn't fit the credit budget of {}", conversation_id, budget);
+                return Ok(None);
+            }
+        }
+        let messages = vec![Message::system(SUMMARY_PROMPT), Message::user(transcript)];
+
+        let response = match near_ai
+            .chat_with_tools(messages, self.sampling, Some(SUMMARY_MAX_TOKENS), None, None)
+            .await
+        {
+            Ok(response) => response,
+            Err(e) => {
+                warn!("Failed to summarize history for {}: {}", conversation_id, e);
+                return Ok(None);
+            }
+        };
+        let usage = response
+            .usage
+            .map(|usage| TokenUsage::new(usage.prompt_tokens, usage.completion_tokens));
+
+        match response.content.as_deref().map(str::trim).filter(|s| !s.is_empty()) {
+            Some(summary) => {
+                let replaced = self
+                    .conversations
+                    .replace_oldest_with_summary(conversation_id, self.summarize_batch, summary, oldest)
+                    .await?;
+                info!("Summarized {} old messages for {}", replaced, conversation_id);
+            }
+            None => warn!("Model returned an empty history summary for {}", conversation_id),
+        }
+        Ok(usage)
+    }
+
     /// Finalize and store the response.
     async fn finalize_response(
         &self,
@@ -163,58 +443,175 @@ impl ChatHandler {
             .await?;
         Ok(response)
     }
-}
 
-#[async_trait]
-impl CommandHandler for ChatHandler {
-    fn is_default(&self) -> bool {
-        true
+    /// Stream a completion into a Signal reply to `message`, editing it as
+    /// tokens arrive.
+    ///
+    /// Returns `None` without sending anything if the stream can't be
+    /// started, so the caller can fall back to a regular request. Token usage
+    /// is estimated from text length (~4 chars per token), since streamed
+    /// responses don't report it.
+    async fn stream_reply(
+        &self,
+        near_ai: &NearAiClient,
+        conversation_id: &str,
+        messages: Vec<Message>,
+        max_tokens: Option<u32>,
+        message: &BotMessage,
+        interval: Duration,
+    ) -> AppResult<Option<ChatReply>> {
+        let prompt_chars: usize = messages
+            .iter()
+            .filter_map(|m| m.content.as_deref())
+            .map(str::len)
+            .sum();
+        let stream = match near_ai.chat_stream(messages, self.sampling, max_tokens).await {
+            Ok(stream) => stream,
+            Err(e) => {
+                warn!("Streaming unavailable, falling back to a single reply: {}", e);
+                return Ok(None);
+            }
+        };
+        let mut stream = Box::pin(stream);
+
+        let (from, to) = (&message.receiving_account, message.reply_target());
+        let mut target = match self.signal_client.send(from, to, STREAM_PLACEHOLDER).await {
+            Ok(Some(timestamp)) => Some(timestamp),
+            Ok(None) => {
+                warn!("Signal didn't report the placeholder's timestamp; reply won't be edited");
+                None
+            }
+            Err(e) => {
+                warn!("Failed to send streaming placeholder: {}", e);
+                None
+            }
+        };
+
+        let mut content = String::new();
+        let mut shown_len = 0;
+        let mut edits = 0;
+        let mut last_edit = Instant::now();
+        while let Some(chunk) = stream.next().await {
+            match chunk {
+                Ok(text) => content.push_str(&text),
+                Err(e) if content.trim().is_empty() => {
+                    error!("NEAR AI stream failed: {}", e);
+                    return Ok(Some(ChatReply {
+                        streamed_to: target,
+                        ..ChatReply::failed(
+                            "Sorry, I encountered an error connecting to the AI service. Please try again.",
+                        )
+                    }));
+                }
+                Err(e) => {
+                    warn!("NEAR AI stream ended early, keeping partial answer: {}", e);
+                    break;
+                }
+            }
+
+            let Some(timestamp) = target else { continue };
+            if edits >= MAX_STREAM_EDITS || content.len() == shown_len || last_edit.elapsed() < interval {
+                continue;
+            }
+            let partial = format!("{} {}", content.trim_end(), STREAM_PLACEHOLDER);
+            match self.signal_client.edit_message(from, to, timestamp, &partial).await {
+                Ok(_) => {
+                    shown_len = content.len();
+                    edits += 1;
+                    last_edit = Instant::now();
+                }
+                Err(e) => {
+                    warn!("Failed to edit streamed reply, sending it whole instead: {}", e);
+                    target = None;
+                }
+            }
+        }
+
+        let content = self
+            .finalize_response(conversation_id, Some(content).filter(|c| !c.trim().is_empty()))
+            .await?;
+        let usage = TokenUsage::new(
+            u32::try_from(prompt_chars / 4).unwrap_or(u32::MAX),
+            u32::try_from(content.len() / 4).unwrap_or(u32::MAX),
+        );
+
+        Ok(Some(ChatReply {
+            content,
+            usage: Some(usage),
+            streamed_to: target,
+        }))
     }
 
-    #[instrument(skip(self, message), fields(user = %message.source, is_group = %message.is_group))]
-    async fn execute(&self, message: &BotMessage) -> AppResult<String> {
-        // Use reply_target as conversation key:
-        // - For DMs: sender's phone number
-        // - For groups: group_id (shared context for all members)
-        let conversation_id = message.reply_target();
-        // For credits, use the sender's phone number (not group ID)
-        let user_id = &message.source;
+    /// Run a user message through the model, executing tools as requested.
+    ///
+    /// Stores the exchange in `conversation_id`'s history. When answering a
+    /// Signal message `progress_to`, tool progress notices are sent to it and
+    /// the stored user message keeps its timestamp so later edits can find
+    /// it. Credits are not checked or charged here; that's up to the caller.
+    pub async fn respond(
+        &self,
+        conversation_id: &str,
+        text: &str,
+        progress_to: Option<&BotMessage>,
+    ) -> AppResult<ChatReply> {
+        self.respond_as(conversation_id, text, progress_to, None, None).await
+    }
 
-        if message.is_group {
-            info!(
-                "Group chat from {} in {}: {}...",
-                &message.source[..message.source.len().min(8)],
-                &conversation_id[..conversation_id.len().min(12)],
-                &message.text[..message.text.len().min(50)]
-            );
-        } else {
-            info!(
-                "Chat from {}: {}...",
-                &conversation_id[..conversation_id.len().min(8)],
-                &message.text[..message.text.len().min(50)]
-            );
+    /// Model answering in `conversation_id`, or `None` for the default. A
+    /// model picked with `!model` wins over the persona's.
+    async fn selected_model(&self, conversation_id: &str, persona: Option<&Persona>) -> Option<String> {
+        match self.conversations.model_override(conversation_id).await {
+            Some(model) => Some(model),
+            None => persona.and_then(|p| p.model.clone()),
         }
+    }
 
-        // Pre-flight credit check (if payments enabled)
-        if let Some(ref credit_store) = self.credit_store {
-            let estimated_credits = estimate_credits(message.text.len(), &self.pricing_config);
-            if !credit_store.has_credits(user_id, estimated_credits).await {
-                let balance = credit_store.get_balance(user_id).await;
-                return Ok(format!(
-                    "Insufficient credits. You have {} remaining.\n\n\
-                     Use `!deposit` to add USDC and get more credits.",
-                    Self::format_credits(balance.credits_remaining)
-                ));
-            }
+    /// Like [`ChatHandler::respond`], answering with `persona`'s model and
+    /// system prompt where set.
+    ///
+    /// With a credit `budget`, each model call's `max_tokens` is capped so the
+    /// whole exchange stays within it.
+    async fn respond_as(
+        &self,
+        conversation_id: &str,
+        text: &str,
+        progress_to: Option<&BotMessage>,
+        persona: Option<&Persona>,
+        budget: Option<u64>,
+    ) -> AppResult<ChatReply> {
+        let base_prompt = persona
+            .and_then(|p| p.system_prompt.as_deref())
+            .unwrap_or(&self.system_prompt);
+        let model_client = self
+            .selected_model(conversation_id, persona)
+            .await
+            .map(|model| self.near_ai.with_model(&model));
+        let near_ai = model_client.as_ref().unwrap_or(&self.near_ai);
+        let pricing = self.pricing_config.for_model(near_ai.model());
+        let mut models = vec![near_ai.model()];
+        if let Some(fallback) = self.fallback_model.as_deref().filter(|m| *m != near_ai.model()) {
+            models.push(fallback);
         }
 
         // Add user message to history
-        self.conversations
-            .add_message(conversation_id, "user", &message.text, Some(&self.system_prompt))
-            .await?;
+        match progress_to {
+            Some(message) => {
+                self.conversations
+                    .add_signal_message(conversation_id, text, message.timestamp, Some(base_prompt))
+                    .await?;
+            }
+            None => {
+                self.conversations
+                    .add_message(conversation_id, "user", text, Some(base_prompt))
+                    .await?;
+            }
+        }
 
         // Get tool definitions and convert to NEAR AI format
-        let tool_defs = self.tool_registry.get_definitions();
+        // Offer only the tools this caller may use; API requests have no
+        // sender, so they're matched on their conversation ID
+        let caller = progress_to.map_or(conversation_id, |m| m.source.as_str());
+        let tool_defs = self.tool_registry.get_definitions_for(caller);
         let near_tools: Vec<NearToolDefinition> = tool_defs
             .into_iter()
             .map(|d| NearToolDefinition {
@@ -227,57 +624,141 @@ impl CommandHandler for ChatHandler {
             })
             .collect();
 
+        // Tools that read history only ever see this conversation
+        let tool_context = ToolContext::for_conversation(conversation_id).with_user(caller);
+
+        // Summarize old history before it would be trimmed away
+        let summary_usage = self
+            .compact_history(conversation_id, near_ai, budget, &pricing)
+            .await?;
+
         // Tool execution loop - only offer tools on first iteration
         let mut tools_executed = false;
         // Track total token usage across all iterations (for credit deduction)
-        let mut total_prompt_tokens: u32 = 0;
-        let mut total_completion_tokens: u32 = 0;
+        let mut total_prompt_tokens: u32 = summary_usage.as_ref().map_or(0, |u| u.prompt_tokens);
+        let mut total_completion_tokens: u32 = summary_usage.map_or(0, |u| u.completion_tokens);
 
-        for iteration in 0..self.max_tool_iterations {
+        // After max_tool_iterations rounds, one last call is made without
+        // tools and whatever it returns is the answer.
+        let mut iteration = 0;
+        loop {
+            let final_call = iteration >= self.max_tool_iterations;
             debug!("Tool execution loop iteration {}, tools_executed={}", iteration, tools_executed);
+            iteration += 1;
+            if final_call {
+                warn!(
+                    "Max tool iterations ({}) reached for {}, asking for a final answer",
+                    self.max_tool_iterations, conversation_id
+                );
+            }
 
             // Build messages from conversation store
-            let messages = self.build_messages(conversation_id).await?;
+            let messages = self.build_messages(conversation_id, base_prompt).await?;
 
             // Only offer tools if we haven't executed any yet
             // After tools execute once, force the model to give a text response
-            let tools_to_offer = if !tools_executed && !near_tools.is_empty() {
+            let tools_to_offer = if !tools_executed && !final_call && !near_tools.is_empty() {
                 Some(&near_tools[..])
             } else {
                 None
             };
 
+            // Leave room in the budget for this call's prompt (~4 chars per token)
+            let max_tokens = match budget {
+                Some(budget) => {
+                    let usage = TokenUsage::new(total_prompt_tokens, total_completion_tokens);
+                    let spent = if usage.total_tokens() == 0 {
+                        0
+                    } else {
+                        calculate_credits(&usage, &pricing)
+                    };
+                    let prompt_chars: usize = messages
+                        .iter()
+                        .filter_map(|m| m.content.as_deref())
+                        .map(str::len)
+                        .sum();
+                    let prompt_tokens = u32::try_from(prompt_chars / 4).unwrap_or(u32::MAX);
+                    match max_completion_tokens(
+                        prompt_tokens,
+                        budget.saturating_sub(spent),
+                        &pricing,
+                    ) {
+                        Some(tokens) => Some(tokens),
+                        None if iteration == 1 => {
+                            info!("Prompt for {} exceeds the credit budget of {}", conversation_id, budget);
+                            return Ok(ChatReply::failed(format!(
+                                "This conversation is too long for your credit limit of {} per message. \
+                                 Use `!clear` to start over, or `!deposit` to add credits.",
+                                Self::format_credits(budget)
+                            )));
+                        }
+                        None => {
+                            warn!("Credit budget of {} used up by tool calls for {}", budget, conversation_id);
+                            return Ok(ChatReply {
+                                content: "I reached the credit limit for this message while using tools. \
+                                          Please try a simpler request."
+                                    .into(),
+                                usage: Some(usage),
+                                streamed_to: None,
+                            });
+                        }
+                    }
+                }
+                None => None,
+            };
+
+            // Stream plain answers to Signal users when enabled
+            if let (Some(interval), Some(message), None) =
+                (self.stream_interval, progress_to, tools_to_offer)
+            {
+                let streamed = self
+                    .stream_reply(near_ai, conversation_id, messages.clone(), max_tokens, message, interval)
+                    .await?;
+                if let Some(mut reply) = streamed {
+                    if let Some(ref mut usage) = reply.usage {
+                        usage.prompt_tokens = usage.prompt_tokens.saturating_add(total_prompt_tokens);
+                        usage.completion_tokens =
+                            usage.completion_tokens.saturating_add(total_completion_tokens);
+                    }
+                    return Ok(reply);
+                }
+            }
+
             // Call NEAR AI with tools (or without if already executed)
-            let response = match self
-                .near_ai
-                .chat_with_tools(
+            let response = match near_ai
+                .chat_with_fallback(
                     messages,
-                    Some(0.7),
-                    None,
+                    &models,
+                    self.sampling,
+                    max_tokens,
                     tools_to_offer,
+                    tools_to_offer.and(self.tool_timeout),
                 )
                 .await
             {
                 Ok(r) => r,
                 Err(NearAiError::RateLimit) => {
-                    return Ok(
-                        "I'm receiving too many requests. Please wait a moment and try again."
-                            .into(),
-                    );
+                    return Ok(ChatReply::failed(
+                        "I'm receiving too many requests. Please wait a moment and try again.",
+                    ));
+                }
+                Err(NearAiError::Timeout) => {
+                    warn!("NEAR AI request timed out");
+                    return Ok(ChatReply::failed(
+                        "The AI service took too long to respond. Please try again.",
+                    ));
                 }
                 Err(NearAiError::EmptyResponse) => {
                     error!("NEAR AI returned empty response");
-                    return Ok(
-                        "The AI service returned an empty response. Please try rephrasing your message."
-                            .into(),
-                    );
+                    return Ok(ChatReply::failed(
+                        "The AI service returned an empty response. Please try rephrasing your message.",
+                    ));
                 }
                 Err(e) => {
                     error!("NEAR AI error: {}", e);
-                    return Ok(
-                        "Sorry, I encountered an error connecting to the AI service. Please try again."
-                            .into(),
-                    );
+                    return Ok(ChatReply::failed(
+                        "Sorry, I encountered an error connecting to the AI service. Please try again.",
+                    ));
                 }
             };
 
@@ -291,8 +772,10 @@ impl CommandHandler for ChatHandler {
                 );
             }
 
-            // Check if response has tool calls (must be non-empty)
-            if let Some(tool_calls) = response.tool_calls {
+            // Check if response has tool calls (must be non-empty). Any the
+            // model still requests on the final call are not run.
+            let tool_calls = if final_call { None } else { response.tool_calls };
+            if let Some(tool_calls) = tool_calls {
                 if tool_calls.is_empty() {
                     // Empty tool_calls array - treat as final response
                     debug!("LLM returned empty tool_calls array, treating as final response");
@@ -316,13 +799,15 @@ impl CommandHandler for ChatHandler {
                 // Execute each tool call
                 for tool_call in tool_calls {
                     // Send progress message
-                    let progress_msg = format!("🔧 Using {}...", tool_call.function.name);
-                    if let Err(e) = self
-                        .signal_client
-                        .send(&message.receiving_account, message.reply_target(), &progress_msg)
-                        .await
-                    {
-                        warn!("Failed to send progress message: {}", e);
+                    if let Some(message) = progress_to {
+                        let progress_msg = format!("🔧 Using {}...", tool_call.function.name);
+                        if let Err(e) = self
+                            .signal_client
+                            .send(&message.receiving_account, message.reply_target(), &progress_msg)
+                            .await
+                        {
+                            warn!("Failed to send progress message: {}", e);
+                        }
                     }
 
                     // Convert to tools crate format and execute
@@ -335,9 +820,12 @@ impl CommandHandler for ChatHandler {
                         },
                     };
 
-                    let result = self.tool_executor.execute(&tools_call).await;
+                    let result = self
+                        .tool_executor
+                        .execute_with_context(&tools_call, &tool_context)
+                        .await;
                     let result_content = if result.success {
-                        debug!("Tool {} succeeded: {}...", tool_call.function.name, &result.content[..result.content.len().min(100)]);
+                        debug!("Tool {} succeeded: {}...", tool_call.function.name, preview(&result.content, 100));
                         result.content
                     } else {
                         warn!("Tool {} failed: {}", tool_call.function.name, result.content);
@@ -359,59 +847,261 @@ impl CommandHandler for ChatHandler {
             }  // close if let Some(tool_calls)
 
             // No tool calls (or empty array) - this is the final response
-            let mut final_response = self.finalize_response(conversation_id, response.content).await?;
-
-            // Deduct credits if payments enabled
-            if let Some(ref credit_store) = self.credit_store {
-                let token_usage = TokenUsage::new(total_prompt_tokens, total_completion_tokens);
-                let credits_used = calculate_credits(&token_usage, &self.pricing_config);
-
-                // Create usage record
-                let usage_record = UsageRecord::new(
-                    user_id.to_string(),
-                    conversation_id.to_string(),
-                    total_prompt_tokens,
-                    total_completion_tokens,
-                    credits_used,
-                );
+            let content = match response.content {
+                Some(content) if !content.trim().is_empty() => Some(content),
+                _ if final_call => Some(TOOL_LIMIT_MESSAGE.to_string()),
+                content => content,
+            };
+            let content = self.finalize_response(conversation_id, content).await?;
 
-                // Deduct credits (if this fails, still return response - better UX)
-                match credit_store.deduct_credits(user_id, credits_used, usage_record).await {
-                    Ok(new_balance) => {
-                        // Append cost info to response
-                        let cost_info = format!(
-                            "\n\n_Cost: {} ({} tokens) | Balance: {}_",
-                            Self::format_credits(credits_used),
-                            total_prompt_tokens + total_completion_tokens,
-                            Self::format_credits(new_balance.credits_remaining)
-                        );
-                        final_response.push_str(&cost_info);
-                        info!(
-                            "Charged {} credits ({} tokens) to {}, remaining: {}",
-                            credits_used,
-                            total_prompt_tokens + total_completion_tokens,
-                            &user_id[..user_id.len().min(8)],
-                            new_balance.credits_remaining
-                        );
-                    }
-                    Err(e) => {
-                        // Log but don't fail the response
-                        error!("Failed to deduct credits for {}: {}", user_id, e);
-                    }
-                }
+            return Ok(ChatReply {
+                content,
+                usage: Some(TokenUsage::new(total_prompt_tokens, total_completion_tokens)),
+                streamed_to: None,
+            });
+        }
+    }
+}
+
+#[async_trait]
+impl CommandHandler for ChatHandler {
+    fn is_default(&self) -> bool {
+        true
+    }
+
+    fn handles_edits(&self) -> bool {
+        true
+    }
+
+    fn matches(&self, message: &BotMessage) -> bool {
+        self.handles_kind(&message.kind)
+            && !message.text.starts_with('!')
+            && self.group_policy.allows(message)
+    }
+
+    #[instrument(skip(self, message), fields(user = %message.source, is_group = %message.is_group))]
+    async fn execute(&self, message: &BotMessage) -> AppResult<String> {
+        // Use reply_target as conversation key:
+        // - For DMs: sender's phone number
+        // - For groups: group_id (shared context for all members)
+        // Scoped to the receiving account when hosting several personas.
+        let conversation_id = &conversation_key(message, self.personas.is_some());
+        // For credits, use the sender's phone number (not group ID)
+        let user_id = &message.source;
+
+        // An edit fixes the stored message in place; it isn't answered or
+        // charged again
+        if let Some(target) = message.edit_target {
+            if self
+                .conversations
+                .edit_message(conversation_id, target, &message.text)
+                .await?
+            {
+                info!("Applied edit to message {} in conversation history", target);
+            } else {
+                debug!("Edited message {} is no longer in conversation history", target);
             }
+            return Ok(String::new());
+        }
 
+        if message.is_group {
             info!(
-                "Response to {}: {} chars",
+                "Group chat from {} in {}: {}...",
+                &message.source[..message.source.len().min(8)],
                 &conversation_id[..conversation_id.len().min(12)],
-                final_response.len()
+                preview(&message.text, 50)
+            );
+        } else {
+            info!(
+                "Chat from {}: {}...",
+                &conversation_id[..conversation_id.len().min(8)],
+                preview(&message.text, 50)
             );
+        }
+
+        let persona = match self.personas {
+            Some(ref personas) => personas.get(&message.receiving_account).await,
+            None => None,
+        };
+        // Charged at the rates of the model that will answer
+        let model = self.selected_model(conversation_id, persona.as_ref()).await;
+        let pricing = self
+            .pricing_config
+            .for_model(model.as_deref().unwrap_or(self.near_ai.model()));
 
-            return Ok(final_response);
+        // Pre-flight credit check (if payments enabled)
+        let mut budget = None;
+        if let Some(ref credit_store) = self.credit_store {
+            let estimated_credits = estimate_credits(message.text.len(), &pricing);
+            if !credit_store.has_credits(user_id, estimated_credits).await {
+                let balance = credit_store.get_balance(user_id).await;
+                return Ok(format!(
+                    "Insufficient credits. You have {} remaining.\n\n\
+                     Use `!deposit` to add USDC and get more credits.",
+                    Self::format_credits(balance.credits_remaining)
+                ));
+            }
+
+            // Never spend more than the per-message cap (or the balance) on one turn
+            if let Some(cap) = self.pricing_config.max_credits_per_message {
+                if estimated_credits > cap {
+                    return Ok(format!(
+                        "This message would cost more than the {} per-message limit. \
+                         Please send a shorter message.",
+                        Self::format_credits(cap)
+                    ));
+                }
+                let balance = credit_store.get_balance(user_id).await;
+                budget = Some(cap.min(balance.credits_remaining));
+            }
         }
 
-        // Max iterations reached
-        warn!("Max tool iterations ({}) reached for {}", self.max_tool_iterations, conversation_id);
-        Ok("I've reached my maximum number of tool uses for this request. Please start a new conversation.".into())
+        let reply = self
+            .respond_as(conversation_id, &message.text, Some(message), persona.as_ref(), budget)
+            .await?;
+        let streamed_to = reply.streamed_to;
+        let mut final_response = reply.content;
+
+        // Deduct credits if payments enabled (only when the model actually answered)
+        if let (Some(credit_store), Some(token_usage)) = (&self.credit_store, reply.usage) {
+            let total_prompt_tokens = token_usage.prompt_tokens;
+            let total_completion_tokens = token_usage.completion_tokens;
+            // Prompt sizes are estimated, so the final bill is clamped to the budget
+            let credits_used = calculate_credits(&token_usage, &pricing)
+                .min(budget.unwrap_or(u64::MAX));
+
+            // Create usage record
+            let usage_record = UsageRecord::new(
+                user_id.to_string(),
+                conversation_id.to_string(),
+                total_prompt_tokens,
+                total_completion_tokens,
+                credits_used,
+            )
+            .with_message_timestamp(message.timestamp);
+
+            // Deduct credits (if this fails, still return response - better UX)
+            match credit_store.deduct_credits(user_id, credits_used, usage_record).await {
+                Ok(new_balance) => {
+                    // Append cost info to response
+                    let cost_info = format!(
+                        "\n\n_Cost: {} ({} tokens) | Balance: {}_",
+                        Self::format_credits(credits_used),
+                        total_prompt_tokens + total_completion_tokens,
+                        Self::format_credits(new_balance.credits_remaining)
+                    );
+                    final_response.push_str(&cost_info);
+                    if self.low_balance_warnings.should_warn(
+                        &new_balance,
+                        credits_used,
+                        self.pricing_config.low_balance_messages,
+                    ) {
+                        final_response.push_str(&format!(
+                            "\n\nYour balance is running low ({} left, enough for only a few more \
+                             messages like this one). Use `!deposit` to add USDC and keep chatting.",
+                            Self::format_credits(new_balance.credits_remaining)
+                        ));
+                    }
+                    info!(
+                        conversation_id = %conversation_id,
+                        message_timestamp = message.timestamp,
+                        credits = credits_used,
+                        "Charged {} credits ({} tokens) to {}, remaining: {}",
+                        credits_used,
+                        total_prompt_tokens + total_completion_tokens,
+                        &user_id[..user_id.len().min(8)],
+                        new_balance.credits_remaining
+                    );
+                }
+                Err(e) => {
+                    // Log but don't fail the response
+                    error!(
+                        conversation_id = %conversation_id,
+                        message_timestamp = message.timestamp,
+                        "Failed to deduct credits for {}: {}",
+                        user_id,
+                        e
+                    );
+                }
+            }
+        }
+
+        info!(
+            "Response to {}: {} chars",
+            &conversation_id[..conversation_id.len().min(12)],
+            final_response.len()
+        );
+
+        // A streamed reply is already on screen; show its final text in place
+        if let Some(timestamp) = streamed_to {
+            match self
+                .signal_client
+                .edit_message(&message.receiving_account, message.reply_target(), timestamp, &final_response)
+                .await
+            {
+                Ok(_) => return Ok(String::new()),
+                Err(e) => warn!("Failed to finish streamed reply, sending it instead: {}", e),
+            }
+        }
+
+        Ok(final_response)
+    }
+}
+
+/// The first `max_chars` characters of `text`, for logging.
+fn preview(text: &str, max_chars: usize) -> &str {
+    match text.char_indices().nth(max_chars) {
+        Some((cut, _)) => &text[..cut],
+        None => text,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_preview_cuts_on_char_boundary() {
+        assert_eq!(preview("héllo wörld", 5), "héllo");
+        assert_eq!(preview("🦀🦀🦀", 2), "🦀🦀");
+        assert_eq!(preview("short", 100), "short");
+    }
+
+    fn balance(credits_remaining: u64) -> CreditBalance {
+        CreditBalance {
+            credits_remaining,
+            last_deposit_at: Some(Utc::now() - chrono::Duration::hours(1)),
+            ..CreditBalance::new("+14155551234".to_string())
+        }
+    }
+
+    #[test]
+    fn test_low_balance_warns_once_below_threshold() {
+        let warnings = LowBalanceWarnings::default();
+
+        // 1,000 left covers 10 more 100-credit messages
+        assert!(!warnings.should_warn(&balance(1_000), 100, 5));
+        // 400 left covers only 4
+        assert!(warnings.should_warn(&balance(400), 100, 5));
+        assert!(!warnings.should_warn(&balance(300), 100, 5), "warned already");
+    }
+
+    #[test]
+    fn test_low_balance_warning_resets_after_deposit() {
+        let warnings = LowBalanceWarnings::default();
+        assert!(warnings.should_warn(&balance(400), 100, 5));
+
+        let topped_up = CreditBalance {
+            last_deposit_at: Some(Utc::now() + chrono::Duration::seconds(1)),
+            ..balance(300)
+        };
+        assert!(warnings.should_warn(&topped_up, 100, 5));
+    }
+
+    #[test]
+    fn test_low_balance_warning_disabled() {
+        let warnings = LowBalanceWarnings::default();
+        assert!(!warnings.should_warn(&balance(0), 100, 0));
+        assert!(!warnings.should_warn(&balance(0), 0, 5));
     }
 }
@@ -1,6 +1,6 @@
 //! Clear command - resets conversation history.
 
-use crate::commands::CommandHandler;
+use crate::commands::{conversation_key, CommandHandler};
 use crate::error::AppResult;
 use async_trait::async_trait;
 use conversation_store::ConversationStore;
@@ -10,11 +10,23 @@ use tracing::info;
 
 pub struct ClearHandler {
     conversations: Arc<ConversationStore>,
+    /// Whether histories are kept per receiving account (multi-persona mode).
+    per_account: bool,
 }
 
 impl ClearHandler {
     pub fn new(conversations: Arc<ConversationStore>) -> Self {
-        Self { conversations }
+        Self {
+            conversations,
+            per_account: false,
+        }
+    }
+
+    /// Clear the history kept for the receiving account only, matching a
+    /// `ChatHandler` configured with personas.
+    pub fn per_account(mut self) -> Self {
+        self.per_account = true;
+        self
     }
 }
 
@@ -24,9 +36,13 @@ impl CommandHandler for ClearHandler {
         Some("!clear")
     }
 
+    fn description(&self) -> Option<&str> {
+        Some("Clear conversation history")
+    }
+
     async fn execute(&self, message: &BotMessage) -> AppResult<String> {
         // Use reply_target: clears group conversation in groups, personal in DMs
-        let conversation_id = message.reply_target();
+        let conversation_id = &conversation_key(message, self.per_account);
         let cleared = self.conversations.clear(conversation_id).await?;
 
         if cleared {
@@ -0,0 +1,74 @@
+//! Cost command - estimates what a message would cost before sending it.
+
+use crate::commands::CommandHandler;
+use crate::error::AppResult;
+use async_trait::async_trait;
+use signal_client::BotMessage;
+use x402_payments::{estimate_credits, PricingCalculator, PricingConfig};
+
+pub struct CostHandler {
+    pricing_config: PricingConfig,
+}
+
+impl CostHandler {
+    pub fn new(pricing_config: PricingConfig) -> Self {
+        Self { pricing_config }
+    }
+}
+
+/// Rough token count of `text` (~4 characters per token).
+fn approx_tokens(text: &str) -> usize {
+    (text.chars().count() / 4).max(1)
+}
+
+#[async_trait]
+impl CommandHandler for CostHandler {
+    fn trigger(&self) -> Option<&str> {
+        Some("!cost")
+    }
+
+    fn description(&self) -> Option<&str> {
+        Some("Estimate what a message would cost")
+    }
+
+    fn usage(&self) -> Option<&str> {
+        Some("<text>")
+    }
+
+    async fn execute(&self, message: &BotMessage) -> AppResult<String> {
+        let text = message.text.trim_start_matches("!cost").trim();
+        if text.is_empty() {
+            return Ok("Usage: `!cost <message>` estimates what sending that message would cost.".into());
+        }
+
+        let credits = estimate_credits(text.chars().count(), &self.pricing_config);
+        Ok(format!(
+            "**Estimated cost:** {} ({} credits)\n\n\
+             Based on ~{} tokens for your message and a typical reply. \
+             Earlier messages in the conversation and tool use add to the actual cost.",
+            PricingCalculator::format_usdc(credits),
+            credits,
+            approx_tokens(text)
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::message;
+
+    #[tokio::test]
+    async fn test_cost_estimate() {
+        let handler = CostHandler::new(PricingConfig::default());
+        let text = "x".repeat(40_000);
+
+        // 10k prompt tokens and a 20k-token reply at default prices
+        let reply = handler.execute(&message(&format!("!cost {}", text))).await.unwrap();
+        assert!(reply.contains("$0.007000 (7000 credits)"));
+        assert!(reply.contains("~10000 tokens"));
+
+        let reply = handler.execute(&message("!cost")).await.unwrap();
+        assert!(reply.starts_with("Usage:"));
+    }
+}
@@ -23,6 +23,10 @@ impl CommandHandler for DepositHandler {
         Some("!deposit")
     }
 
+    fn description(&self) -> Option<&str> {
+        Some("Get deposit addresses for USDC")
+    }
+
     async fn execute(&self, message: &BotMessage) -> AppResult<String> {
         info!("Deposit info requested by {}", message.source);
 
@@ -0,0 +1,143 @@
+//! Ephemeral command - sets Signal's disappearing-message timer.
+
+use crate::commands::CommandHandler;
+use crate::error::AppResult;
+use async_trait::async_trait;
+use signal_client::{BotMessage, SignalClient, SignalError};
+use std::sync::Arc;
+use tracing::info;
+
+/// Longest timer Signal clients offer: 4 weeks, in minutes.
+const MAX_EPHEMERAL_MINUTES: u32 = 4 * 7 * 24 * 60;
+
+pub struct EphemeralHandler {
+    signal: Arc<SignalClient>,
+}
+
+impl EphemeralHandler {
+    pub fn new(signal: Arc<SignalClient>) -> Self {
+        Self { signal }
+    }
+}
+
+#[async_trait]
+impl CommandHandler for EphemeralHandler {
+    fn trigger(&self) -> Option<&str> {
+        Some("!ephemeral")
+    }
+
+    fn description(&self) -> Option<&str> {
+        Some("Make messages in this chat disappear (0 turns it off)")
+    }
+
+    fn usage(&self) -> Option<&str> {
+        Some("<minutes>")
+    }
+
+    async fn execute(&self, message: &BotMessage) -> AppResult<String> {
+        if message.is_group {
+            return Ok("Disappearing messages can only be set from direct messages.".into());
+        }
+
+        let arg = message.text.trim().strip_prefix("!ephemeral").unwrap_or("").trim();
+        let minutes = match arg.parse::<u32>() {
+            Ok(minutes) if minutes <= MAX_EPHEMERAL_MINUTES => minutes,
+            _ => {
+                return Ok(format!(
+                    "Usage: `!ephemeral <minutes>` with 0 to {} (4 weeks); 0 turns disappearing messages off.",
+                    MAX_EPHEMERAL_MINUTES
+                ))
+            }
+        };
+
+        match self
+            .signal
+            .set_expiration(&message.receiving_account, &message.source, minutes * 60)
+            .await
+        {
+            Ok(()) => {}
+            Err(SignalError::Unsupported(_)) => {
+                return Ok("Disappearing messages aren't supported by this bot's Signal server.".into())
+            }
+            Err(e) => return Err(e.into()),
+        }
+
+        info!(
+            "Set disappearing messages to {} minutes for {}",
+            minutes,
+            &message.source[..8.min(message.source.len())]
+        );
+        if minutes == 0 {
+            Ok("Disappearing messages turned off.".into())
+        } else {
+            Ok(format!(
+                "Messages in this chat will now disappear after {} minute{}.",
+                minutes,
+                if minutes == 1 { "" } else { "s" }
+            ))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::message;
+    use wiremock::matchers::{body_partial_json, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn handler(server: &MockServer) -> EphemeralHandler {
+        EphemeralHandler::new(Arc::new(SignalClient::new(server.uri()).unwrap()))
+    }
+
+    #[tokio::test]
+    async fn test_sets_timer_in_seconds() {
+        let server = MockServer::start().await;
+        Mock::given(method("PUT"))
+            .and(path("/v1/contacts/%2B15555555555"))
+            .and(body_partial_json(serde_json::json!({
+                "recipient": "+14155551234",
+                "expiration_in_seconds": 300
+            })))
+            .respond_with(ResponseTemplate::new(204))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let reply = handler(&server).execute(&message("!ephemeral 5")).await.unwrap();
+        assert!(reply.contains("after 5 minutes"));
+    }
+
+    #[tokio::test]
+    async fn test_rejects_bad_input_and_groups() {
+        let server = MockServer::start().await;
+        let handler = handler(&server);
+
+        assert!(handler.execute(&message("!ephemeral")).await.unwrap().starts_with("Usage"));
+        assert!(handler.execute(&message("!ephemeral -1")).await.unwrap().starts_with("Usage"));
+        assert!(handler
+            .execute(&message("!ephemeral 999999"))
+            .await
+            .unwrap()
+            .starts_with("Usage"));
+
+        let mut group = message("!ephemeral 5");
+        group.is_group = true;
+        group.group_id = Some("group-id".into());
+        assert!(handler.execute(&group).await.unwrap().contains("direct messages"));
+
+        assert!(server.received_requests().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_unsupported_server() {
+        let server = MockServer::start().await;
+        Mock::given(method("PUT"))
+            .respond_with(ResponseTemplate::new(404))
+            .mount(&server)
+            .await;
+
+        let reply = handler(&server).execute(&message("!ephemeral 0")).await.unwrap();
+        assert!(reply.contains("aren't supported"));
+    }
+}
@@ -5,49 +5,108 @@ use crate::error::AppResult;
 use async_trait::async_trait;
 use signal_client::BotMessage;
 
-pub struct HelpHandler;
+pub struct HelpHandler {
+    /// One `- !trigger <usage> - description` line per command.
+    commands: Vec<String>,
+    /// Whether payment commands are registered.
+    payments: bool,
+}
 
 impl HelpHandler {
-    pub fn new() -> Self {
-        Self
+    /// Build help for `handlers`, listing each one with a trigger and a
+    /// description in order, followed by `!help` itself.
+    pub fn new(handlers: &[Box<dyn CommandHandler>]) -> Self {
+        let mut commands: Vec<String> = handlers
+            .iter()
+            .filter_map(|h| Some(command_line(h.trigger()?, h.usage(), h.description()?)))
+            .collect();
+        commands.push(command_line(HELP_TRIGGER, None, HELP_DESCRIPTION));
+
+        Self {
+            payments: handlers.iter().any(|h| h.trigger() == Some("!balance")),
+            commands,
+        }
     }
 }
 
-impl Default for HelpHandler {
-    fn default() -> Self {
-        Self::new()
+const HELP_TRIGGER: &str = "!help";
+const HELP_DESCRIPTION: &str = "Show this message";
+
+fn command_line(trigger: &str, usage: Option<&str>, description: &str) -> String {
+    match usage {
+        Some(usage) => format!("- {} {} - {}", trigger, usage, description),
+        None => format!("- {} - {}", trigger, description),
     }
 }
 
 #[async_trait]
 impl CommandHandler for HelpHandler {
     fn trigger(&self) -> Option<&str> {
-        Some("!help")
+        Some(HELP_TRIGGER)
     }
 
-    async fn execute(&self, _message: &BotMessage) -> AppResult<String> {
-        Ok(r#"**Signal AI** (Private & Verifiable)
-
-Just send a message to chat with AI.
+    fn description(&self) -> Option<&str> {
+        Some(HELP_DESCRIPTION)
+    }
 
-**Commands:**
-- !verify <challenge> - Get TEE attestation with your challenge
-- !clear - Clear conversation history
-- !models - List available AI models
-- !balance - Check your credit balance
-- !deposit - Get deposit addresses for USDC
-- !help - Show this message
+    async fn execute(&self, _message: &BotMessage) -> AppResult<String> {
+        let mut help = format!(
+            "**Signal AI** (Private & Verifiable)\n\n\
+             Just send a message to chat with AI.\n\n\
+             **Commands:**\n{}\n\n",
+            self.commands.join("\n")
+        );
+        help.push_str(
+            "**Verification:**\n\
+             Use `!verify my-random-text` to get cryptographic proof this bot runs in a TEE. Your challenge is embedded in the TDX quote, proving the attestation was generated fresh for you.\n\n",
+        );
+        if self.payments {
+            help.push_str(
+                "**Payments:**\n\
+                 This bot uses prepaid credits. Deposit USDC on Base, NEAR, or Solana to add credits. Use `!balance` to check your balance and `!deposit` for deposit addresses.\n\n",
+            );
+        }
+        help.push_str(
+            "**Privacy:**\n\
+             Your messages are end-to-end encrypted via Signal, processed in a verified TEE (Intel TDX), and sent to NEAR AI Cloud's private inference (NVIDIA GPU TEE).\n\n\
+             Neither the bot operator nor NEAR AI can read your messages.",
+        );
+        Ok(help)
+    }
+}
 
-**Verification:**
-Use `!verify my-random-text` to get cryptographic proof this bot runs in a TEE. Your challenge is embedded in the TDX quote, proving the attestation was generated fresh for you.
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::message;
+    use crate::commands::{ClearHandler, CostHandler, ModelsHandler, PersonaHandler};
+    use x402_payments::PricingConfig;
+    use conversation_store::ConversationStore;
+    use near_ai_client::NearAiClient;
+    use std::sync::Arc;
+    use std::time::Duration;
 
-**Payments:**
-This bot uses prepaid credits. Deposit USDC on Base, NEAR, or Solana to add credits. Use `!balance` to check your balance and `!deposit` for deposit addresses.
+    #[tokio::test]
+    async fn test_help_lists_registered_commands() {
+        let conversations = Arc::new(ConversationStore::new(100, Duration::from_secs(3600)));
+        let near_ai = NearAiClient::new("key", "http://localhost", "model", Duration::from_secs(5)).unwrap();
+        let handlers: Vec<Box<dyn CommandHandler>> = vec![
+            Box::new(ClearHandler::new(conversations.clone())),
+            Box::new(PersonaHandler::new(conversations)),
+            Box::new(ModelsHandler::new(Arc::new(near_ai))),
+            Box::new(CostHandler::new(PricingConfig::default())),
+        ];
 
-**Privacy:**
-Your messages are end-to-end encrypted via Signal, processed in a verified TEE (Intel TDX), and sent to NEAR AI Cloud's private inference (NVIDIA GPU TEE).
+        let help = HelpHandler::new(&handlers).execute(&message("!help")).await.unwrap();
+        for handler in &handlers {
+            assert!(help.contains(handler.trigger().unwrap()), "{:?} missing", handler.trigger());
+        }
+        assert!(help.contains("- !persona <text> - Set a custom persona"));
+        assert!(help.contains("- !clear - Clear conversation history"));
+        assert!(help.contains("- !help - Show this message"));
 
-Neither the bot operator nor NEAR AI can read your messages."#
-            .into())
+        // Commands that aren't registered aren't advertised
+        assert!(!help.contains("!deposit"));
+        assert!(!help.contains("!balance"));
     }
 }
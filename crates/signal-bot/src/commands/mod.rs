@@ -3,22 +3,77 @@
 mod balance;
 mod chat;
 mod clear;
+mod cost;
 mod deposit;
+mod ephemeral;
 mod help;
+mod model;
 mod models;
+mod persona;
+mod pin;
+mod search;
+mod system;
+mod tools;
+mod usage;
 mod verify;
 
 pub use balance::BalanceHandler;
-pub use chat::ChatHandler;
+pub use chat::{ChatHandler, ChatReply};
 pub use clear::ClearHandler;
+pub use cost::CostHandler;
 pub use deposit::DepositHandler;
+pub use ephemeral::EphemeralHandler;
 pub use help::HelpHandler;
+pub use model::ModelHandler;
 pub use models::ModelsHandler;
-pub use verify::VerifyHandler;
+pub use persona::{PersonaHandler, DEFAULT_MAX_PERSONA_LENGTH};
+pub use pin::{PinHandler, UnpinHandler};
+pub use search::SearchHandler;
+pub use system::SystemPromptHandler;
+pub use tools::ToolsHandler;
+pub use usage::UsageHandler;
+pub use verify::{AttestHandler, AttestationBundle, VerifyHandler, VerifyOptions};
 
 use crate::error::AppResult;
 use async_trait::async_trait;
-use signal_client::BotMessage;
+use signal_client::{BotMessage, MessageKind};
+
+/// Conversation history key for a message.
+///
+/// This is the reply target (sender for DMs, group ID for groups). With
+/// `per_account`, the receiving bot account is prepended so personas on
+/// different numbers keep separate histories.
+pub fn conversation_key(message: &BotMessage, per_account: bool) -> String {
+    if per_account {
+        format!("{}:{}", message.receiving_account, message.reply_target())
+    } else {
+        message.reply_target().to_string()
+    }
+}
+
+/// Arguments after `trigger` when `text` invokes it as a whole word, so
+/// `!persona` doesn't also answer `!personality`.
+pub(crate) fn command_args<'a>(text: &'a str, trigger: &str) -> Option<&'a str> {
+    text.strip_prefix(trigger)
+        .filter(|rest| rest.is_empty() || rest.starts_with(char::is_whitespace))
+        .map(str::trim)
+}
+
+/// A direct text message from `+14155551234` to the bot, for handler tests.
+#[cfg(test)]
+pub(crate) fn message(text: &str) -> BotMessage {
+    BotMessage {
+        source: "+14155551234".to_string(),
+        text: text.to_string(),
+        timestamp: 1,
+        is_group: false,
+        group_id: None,
+        receiving_account: "+15555555555".to_string(),
+        edit_target: None,
+        mentions: vec![],
+        kind: MessageKind::Text,
+    }
+}
 
 /// Command handler trait.
 #[async_trait]
@@ -28,13 +83,43 @@ pub trait CommandHandler: Send + Sync {
         None
     }
 
+    /// One-line summary listed by `!help` (commands without one are left out).
+    fn description(&self) -> Option<&str> {
+        None
+    }
+
+    /// Arguments shown after the trigger in `!help`, e.g. `<challenge>`.
+    fn usage(&self) -> Option<&str> {
+        None
+    }
+
     /// Whether this is the default handler for non-command messages.
     fn is_default(&self) -> bool {
         false
     }
 
+    /// Whether this handler processes edits of earlier messages.
+    ///
+    /// Edits are otherwise ignored so a corrected command isn't run twice.
+    fn handles_edits(&self) -> bool {
+        false
+    }
+
+    /// Whether this handler processes messages of the given kind.
+    ///
+    /// Only text is handled by default; reactions and stickers are ignored.
+    fn handles_kind(&self, kind: &MessageKind) -> bool {
+        matches!(kind, MessageKind::Text)
+    }
+
     /// Check if this handler matches the message.
     fn matches(&self, message: &BotMessage) -> bool {
+        if message.is_edit() && !self.handles_edits() {
+            return false;
+        }
+        if !self.handles_kind(&message.kind) {
+            return false;
+        }
         if let Some(trigger) = self.trigger() {
             message.text.starts_with(trigger)
         } else {
@@ -43,5 +128,7 @@ pub trait CommandHandler: Send + Sync {
     }
 
     /// Execute the command.
+    ///
+    /// An empty response means there is nothing to send back.
     async fn execute(&self, message: &BotMessage) -> AppResult<String>;
 }
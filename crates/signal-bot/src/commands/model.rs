@@ -0,0 +1,176 @@
+//! Model command - picks the AI model for the conversation.
+
+use crate::commands::{conversation_key, CommandHandler};
+use crate::error::AppResult;
+use async_trait::async_trait;
+use conversation_store::ConversationStore;
+use near_ai_client::NearAiClient;
+use signal_client::{BotMessage, MessageKind};
+use std::sync::Arc;
+use tracing::{error, info};
+
+pub struct ModelHandler {
+    near_ai: Arc<NearAiClient>,
+    conversations: Arc<ConversationStore>,
+    /// Whether histories are kept per receiving account (multi-persona mode).
+    per_account: bool,
+}
+
+impl ModelHandler {
+    pub fn new(near_ai: Arc<NearAiClient>, conversations: Arc<ConversationStore>) -> Self {
+        Self {
+            near_ai,
+            conversations,
+            per_account: false,
+        }
+    }
+
+    /// Keep model choices per receiving account, matching a `ChatHandler`
+    /// configured with personas.
+    pub fn per_account(mut self) -> Self {
+        self.per_account = true;
+        self
+    }
+}
+
+#[async_trait]
+impl CommandHandler for ModelHandler {
+    fn trigger(&self) -> Option<&str> {
+        Some("!model")
+    }
+
+    fn description(&self) -> Option<&str> {
+        Some("Use a different model for this chat (`reset` for the default)")
+    }
+
+    fn usage(&self) -> Option<&str> {
+        Some("<id>")
+    }
+
+    // `!models` shares the prefix, so only the whole word triggers this
+    fn matches(&self, message: &BotMessage) -> bool {
+        if message.is_edit() || !matches!(message.kind, MessageKind::Text) {
+            return false;
+        }
+        message
+            .text
+            .strip_prefix("!model")
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with(char::is_whitespace))
+    }
+
+    async fn execute(&self, message: &BotMessage) -> AppResult<String> {
+        // Like `!persona`, one member shouldn't change the model for a group
+        if message.is_group {
+            return Ok("Choosing a model is only available in direct messages.".into());
+        }
+
+        let models = match self.near_ai.list_models().await {
+            Ok(models) => models,
+            Err(e) => {
+                error!("Failed to list models: {}", e);
+                return Ok("Could not fetch model list.".into());
+            }
+        };
+        let model_list = models
+            .iter()
+            .map(|m| format!("- {}", m.id))
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        let conversation_id = &conversation_key(message, self.per_account);
+        let args = message.text.trim_start_matches("!model").trim();
+
+        match args {
+            "" => {
+                let current = self.conversations.model_override(conversation_id).await;
+                Ok(format!(
+                    "**Current model:** {}{}\n\n**Available Models:**\n{}\n\n\
+                     Use `!model <id>` to switch, or `!model reset` for the default.",
+                    current.as_deref().unwrap_or(self.near_ai.model()),
+                    if current.is_some() { "" } else { " (default)" },
+                    model_list
+                ))
+            }
+            "reset" => {
+                if self.conversations.clear_model_override(conversation_id).await {
+                    Ok(format!("Model reset to the default ({}).", self.near_ai.model()))
+                } else {
+                    Ok(format!("Already using the default model ({}).", self.near_ai.model()))
+                }
+            }
+            id => {
+                if !models.iter().any(|m| m.id == id) {
+                    return Ok(format!(
+                        "Unknown model `{}`. Choose one of:\n{}",
+                        id, model_list
+                    ));
+                }
+                self.conversations.set_model_override(conversation_id, id).await;
+                info!("Set model {} for {}", id, &conversation_id[..8.min(conversation_id.len())]);
+                Ok(format!(
+                    "Now using {}. Use `!model reset` to go back to the default.",
+                    id
+                ))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::message;
+    use std::time::Duration;
+
+    fn handler() -> (ModelHandler, Arc<ConversationStore>) {
+        let near_ai = NearAiClient::new("key", "http://localhost", "openai/gpt-oss-120b", Duration::from_secs(5)).unwrap();
+        let conversations = Arc::new(ConversationStore::new(100, Duration::from_secs(3600)));
+        (ModelHandler::new(Arc::new(near_ai), conversations.clone()), conversations)
+    }
+
+    #[tokio::test]
+    async fn test_set_model() {
+        let (handler, conversations) = handler();
+
+        let reply = handler.execute(&message("!model zai-org/GLM-4.6")).await.unwrap();
+        assert!(reply.starts_with("Now using zai-org/GLM-4.6"));
+        assert_eq!(
+            conversations.model_override("+14155551234").await.as_deref(),
+            Some("zai-org/GLM-4.6")
+        );
+
+        handler.execute(&message("!model reset")).await.unwrap();
+        assert!(conversations.model_override("+14155551234").await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_unknown_model_is_rejected() {
+        let (handler, conversations) = handler();
+
+        let reply = handler.execute(&message("!model gpt-17")).await.unwrap();
+        assert!(reply.starts_with("Unknown model `gpt-17`"));
+        assert!(reply.contains("- deepseek-ai/DeepSeek-V3.1"));
+        assert!(conversations.model_override("+14155551234").await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_query_model() {
+        let (handler, _) = handler();
+
+        let reply = handler.execute(&message("!model")).await.unwrap();
+        assert!(reply.contains("**Current model:** openai/gpt-oss-120b (default)"));
+        assert!(reply.contains("- zai-org/GLM-4.6"));
+
+        handler.execute(&message("!model zai-org/GLM-4.6")).await.unwrap();
+        let reply = handler.execute(&message("!model")).await.unwrap();
+        assert!(reply.contains("**Current model:** zai-org/GLM-4.6\n"));
+    }
+
+    #[tokio::test]
+    async fn test_does_not_match_models_command() {
+        let (handler, _) = handler();
+        assert!(handler.matches(&message("!model")));
+        assert!(handler.matches(&message("!model zai-org/GLM-4.6")));
+        assert!(!handler.matches(&message("!models")));
+    }
+}
@@ -24,6 +24,10 @@ impl CommandHandler for ModelsHandler {
         Some("!models")
     }
 
+    fn description(&self) -> Option<&str> {
+        Some("List available AI models")
+    }
+
     async fn execute(&self, _message: &BotMessage) -> AppResult<String> {
         match self.near_ai.list_models().await {
             Ok(models) => {
@@ -0,0 +1,105 @@
+//! Persona command - sets a custom system prompt for the conversation.
+
+use crate::commands::{command_args, conversation_key, CommandHandler};
+use crate::error::AppResult;
+use async_trait::async_trait;
+use conversation_store::ConversationStore;
+use signal_client::{BotMessage, MessageKind};
+use std::sync::Arc;
+use tracing::info;
+
+/// Default limit on a custom system prompt, in characters.
+pub const DEFAULT_MAX_PERSONA_LENGTH: usize = 500;
+
+pub struct PersonaHandler {
+    conversations: Arc<ConversationStore>,
+    max_length: usize,
+    /// Whether histories are kept per receiving account (multi-persona mode).
+    per_account: bool,
+}
+
+impl PersonaHandler {
+    pub fn new(conversations: Arc<ConversationStore>) -> Self {
+        Self {
+            conversations,
+            max_length: DEFAULT_MAX_PERSONA_LENGTH,
+            per_account: false,
+        }
+    }
+
+    /// Set the longest custom prompt accepted, in characters.
+    pub fn with_max_length(mut self, max_length: usize) -> Self {
+        self.max_length = max_length;
+        self
+    }
+
+    /// Keep overrides per receiving account, matching a `ChatHandler`
+    /// configured with personas.
+    pub fn per_account(mut self) -> Self {
+        self.per_account = true;
+        self
+    }
+}
+
+#[async_trait]
+impl CommandHandler for PersonaHandler {
+    fn trigger(&self) -> Option<&str> {
+        Some("!persona")
+    }
+
+    fn description(&self) -> Option<&str> {
+        Some("Set a custom persona for this chat (`reset` to clear)")
+    }
+
+    fn usage(&self) -> Option<&str> {
+        Some("<text>")
+    }
+
+    fn matches(&self, message: &BotMessage) -> bool {
+        !message.is_edit()
+            && matches!(message.kind, MessageKind::Text)
+            && command_args(&message.text, "!persona").is_some()
+    }
+
+    async fn execute(&self, message: &BotMessage) -> AppResult<String> {
+        // Group members can't be checked for admin rights, so groups keep
+        // the operator's prompt
+        if message.is_group {
+            return Ok("Custom personas are only available in direct messages.".into());
+        }
+
+        let conversation_id = &conversation_key(message, self.per_account);
+        let args = command_args(&message.text, "!persona").unwrap_or_default();
+
+        match args {
+            "" => Ok(match self.conversations.prompt_override(conversation_id).await {
+                Some(prompt) => format!(
+                    "**Current persona:**\n{}\n\nUse `!persona reset` to go back to the default.",
+                    prompt
+                ),
+                None => "No custom persona is set.\n\nUse `!persona <instructions>` to set one, \
+                         e.g. `!persona Answer like a pirate.`"
+                    .into(),
+            }),
+            "reset" => {
+                if self.conversations.clear_prompt_override(conversation_id).await {
+                    info!("Cleared persona for {}", &conversation_id[..8.min(conversation_id.len())]);
+                    Ok("Persona reset to the default.".into())
+                } else {
+                    Ok("No custom persona is set.".into())
+                }
+            }
+            prompt => {
+                let length = prompt.chars().count();
+                if length > self.max_length {
+                    return Ok(format!(
+                        "That persona is too long ({} characters). The limit is {}.",
+                        length, self.max_length
+                    ));
+                }
+                self.conversations.set_prompt_override(conversation_id, prompt).await;
+                Ok("Persona set. It applies to this conversation until you use `!persona reset`.".into())
+            }
+        }
+    }
+}
@@ -0,0 +1,158 @@
+//! Pin commands - keep messages from being trimmed.
+
+use crate::commands::{conversation_key, CommandHandler};
+use crate::error::AppResult;
+use async_trait::async_trait;
+use conversation_store::{ConversationError, ConversationStore};
+use signal_client::BotMessage;
+use std::sync::Arc;
+use tracing::info;
+
+/// Longest excerpt of the pinned message echoed back, in characters.
+const MAX_EXCERPT_CHARS: usize = 100;
+
+pub struct PinHandler {
+    conversations: Arc<ConversationStore>,
+    /// Whether histories are kept per receiving account (multi-persona mode).
+    per_account: bool,
+}
+
+impl PinHandler {
+    pub fn new(conversations: Arc<ConversationStore>) -> Self {
+        Self {
+            conversations,
+            per_account: false,
+        }
+    }
+
+    /// Pin in the history kept for the receiving account only, matching a
+    /// `ChatHandler` configured with personas.
+    pub fn per_account(mut self) -> Self {
+        self.per_account = true;
+        self
+    }
+}
+
+#[async_trait]
+impl CommandHandler for PinHandler {
+    fn trigger(&self) -> Option<&str> {
+        Some("!pin")
+    }
+
+    fn description(&self) -> Option<&str> {
+        Some("Keep your last message when older history is trimmed")
+    }
+
+    async fn execute(&self, message: &BotMessage) -> AppResult<String> {
+        let conversation_id = conversation_key(message, self.per_account);
+        let pinned = match self.conversations.pin_last_user_message(&conversation_id).await {
+            Ok(Some(pinned)) => pinned,
+            Ok(None) => return Ok("There's no message of yours to pin yet.".into()),
+            Err(ConversationError::TooManyPins(pinned)) => {
+                return Ok(format!(
+                    "You already have the most pinned messages allowed ({}). Use `!unpin` to release them first.",
+                    pinned
+                ));
+            }
+            Err(e) => return Err(e.into()),
+        };
+
+        info!("Pinned a message for {}", &conversation_id[..8.min(conversation_id.len())]);
+        let content = pinned.content.unwrap_or_default();
+        let excerpt = if content.chars().count() > MAX_EXCERPT_CHARS {
+            format!("{}...", content.chars().take(MAX_EXCERPT_CHARS).collect::<String>())
+        } else {
+            content
+        };
+        Ok(format!(
+            "Pinned: \"{}\"\n\nIt will be kept when older messages are trimmed. Use `!unpin` to release your pins.",
+            excerpt
+        ))
+    }
+}
+
+pub struct UnpinHandler {
+    conversations: Arc<ConversationStore>,
+    /// Whether histories are kept per receiving account (multi-persona mode).
+    per_account: bool,
+}
+
+impl UnpinHandler {
+    pub fn new(conversations: Arc<ConversationStore>) -> Self {
+        Self {
+            conversations,
+            per_account: false,
+        }
+    }
+
+    /// Unpin in the history kept for the receiving account only, matching a
+    /// `ChatHandler` configured with personas.
+    pub fn per_account(mut self) -> Self {
+        self.per_account = true;
+        self
+    }
+}
+
+#[async_trait]
+impl CommandHandler for UnpinHandler {
+    fn trigger(&self) -> Option<&str> {
+        Some("!unpin")
+    }
+
+    fn description(&self) -> Option<&str> {
+        Some("Release pinned messages")
+    }
+
+    async fn execute(&self, message: &BotMessage) -> AppResult<String> {
+        let conversation_id = conversation_key(message, self.per_account);
+        match self.conversations.unpin_all(&conversation_id).await? {
+            0 => Ok("No messages are pinned.".into()),
+            1 => Ok("Unpinned 1 message.".into()),
+            n => Ok(format!("Unpinned {} messages.", n)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::message;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_pin_and_unpin() {
+        let conversations = Arc::new(ConversationStore::new(100, Duration::from_secs(3600)));
+        let pin = PinHandler::new(conversations.clone());
+        let unpin = UnpinHandler::new(conversations.clone());
+
+        assert_eq!(
+            pin.execute(&message("!pin")).await.unwrap(),
+            "There's no message of yours to pin yet."
+        );
+
+        conversations.add_message("+14155551234", "user", "Call me Sam", None).await.unwrap();
+        conversations.add_message("+14155551234", "assistant", "Hi Sam!", None).await.unwrap();
+        let reply = pin.execute(&message("!pin")).await.unwrap();
+        assert!(reply.starts_with("Pinned: \"Call me Sam\""));
+        let conv = conversations.get("+14155551234").await.unwrap().unwrap();
+        assert!(conv.messages[0].pinned);
+
+        assert_eq!(unpin.execute(&message("!unpin")).await.unwrap(), "Unpinned 1 message.");
+        assert_eq!(unpin.execute(&message("!unpin")).await.unwrap(), "No messages are pinned.");
+    }
+
+    #[tokio::test]
+    async fn test_pin_refused_over_cap() {
+        let conversations = Arc::new(ConversationStore::new(2, Duration::from_secs(3600)));
+        let pin = PinHandler::new(conversations.clone());
+
+        conversations.add_message("+14155551234", "user", "Call me Sam", None).await.unwrap();
+        assert!(pin.execute(&message("!pin")).await.unwrap().starts_with("Pinned:"));
+        conversations.add_message("+14155551234", "user", "I'm vegetarian", None).await.unwrap();
+
+        assert_eq!(
+            pin.execute(&message("!pin")).await.unwrap(),
+            "You already have the most pinned messages allowed (1). Use `!unpin` to release them first."
+        );
+    }
+}
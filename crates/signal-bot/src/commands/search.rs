@@ -0,0 +1,113 @@
+//! Search command - finds earlier messages in the conversation.
+
+use crate::commands::{conversation_key, CommandHandler};
+use crate::error::AppResult;
+use async_trait::async_trait;
+use conversation_store::{ConversationStore, StoredMessage};
+use signal_client::BotMessage;
+use std::sync::Arc;
+
+/// Longest snippet shown for a single message, in characters.
+const MAX_SNIPPET_CHARS: usize = 200;
+
+pub struct SearchHandler {
+    conversations: Arc<ConversationStore>,
+    /// Whether histories are kept per receiving account (multi-persona mode).
+    per_account: bool,
+}
+
+impl SearchHandler {
+    pub fn new(conversations: Arc<ConversationStore>) -> Self {
+        Self {
+            conversations,
+            per_account: false,
+        }
+    }
+
+    /// Search the history kept for the receiving account only, matching a
+    /// `ChatHandler` configured with personas.
+    pub fn per_account(mut self) -> Self {
+        self.per_account = true;
+        self
+    }
+}
+
+fn format_match(message: &StoredMessage) -> String {
+    let content = message.content.as_deref().unwrap_or_default();
+    let snippet = if content.chars().count() > MAX_SNIPPET_CHARS {
+        format!("{}...", content.chars().take(MAX_SNIPPET_CHARS).collect::<String>())
+    } else {
+        content.to_string()
+    };
+    format!(
+        "- [{}] {}: {}",
+        message.timestamp.format("%Y-%m-%d %H:%M UTC"),
+        if message.role == "user" { "You" } else { "Bot" },
+        snippet
+    )
+}
+
+#[async_trait]
+impl CommandHandler for SearchHandler {
+    fn trigger(&self) -> Option<&str> {
+        Some("!search")
+    }
+
+    fn description(&self) -> Option<&str> {
+        Some("Find earlier messages in this conversation")
+    }
+
+    fn usage(&self) -> Option<&str> {
+        Some("<query>")
+    }
+
+    async fn execute(&self, message: &BotMessage) -> AppResult<String> {
+        let query = message.text.trim_start_matches("!search").trim();
+        if query.is_empty() {
+            return Ok("Usage: `!search <query>`".into());
+        }
+
+        let conversation_id = conversation_key(message, self.per_account);
+        let matches = self.conversations.search(&conversation_id, query).await;
+        if matches.is_empty() {
+            return Ok(format!("No messages in this conversation match \"{}\".", query));
+        }
+
+        let lines: Vec<String> = matches.iter().map(format_match).collect();
+        Ok(format!("**Messages matching \"{}\"**\n\n{}", query, lines.join("\n")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::message;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_search_hit_and_miss() {
+        let conversations = Arc::new(ConversationStore::new(100, Duration::from_secs(3600)));
+        for (role, text) in [
+            ("user", "I'm building a greenhouse controller"),
+            ("assistant", "Nice! Which sensors are you using?"),
+            ("user", "Humidity and soil moisture"),
+        ] {
+            conversations.add_message("+14155551234", role, text, None).await.unwrap();
+        }
+        let handler = SearchHandler::new(conversations);
+
+        let reply = handler.execute(&message("!search Greenhouse")).await.unwrap();
+        assert!(reply.starts_with("**Messages matching \"Greenhouse\"**"));
+        assert!(reply.contains("UTC] You: I'm building a greenhouse controller"));
+        assert!(!reply.contains("sensors"));
+
+        let reply = handler.execute(&message("!search sensors")).await.unwrap();
+        assert!(reply.contains("] Bot: Nice! Which sensors are you using?"));
+
+        let reply = handler.execute(&message("!search solar panels")).await.unwrap();
+        assert_eq!(reply, "No messages in this conversation match \"solar panels\".");
+
+        let reply = handler.execute(&message("!search")).await.unwrap();
+        assert_eq!(reply, "Usage: `!search <query>`");
+    }
+}
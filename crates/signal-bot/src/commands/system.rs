@@ -0,0 +1,222 @@
+//! System command - replaces the base system prompt for the conversation.
+
+use crate::commands::{command_args, conversation_key, CommandHandler, DEFAULT_MAX_PERSONA_LENGTH};
+use crate::error::AppResult;
+use crate::personas::PersonaRegistry;
+use async_trait::async_trait;
+use conversation_store::ConversationStore;
+use signal_client::{BotMessage, MessageKind};
+use std::sync::Arc;
+use tracing::info;
+
+pub struct SystemPromptHandler {
+    conversations: Arc<ConversationStore>,
+    /// Operator's prompt, shown while no replacement is set.
+    default_prompt: String,
+    max_length: usize,
+    /// Whether histories are kept per receiving account (multi-persona mode).
+    per_account: bool,
+    /// Per-account personas whose prompts replace `default_prompt`.
+    personas: Option<Arc<PersonaRegistry>>,
+}
+
+impl SystemPromptHandler {
+    pub fn new(conversations: Arc<ConversationStore>, default_prompt: impl Into<String>) -> Self {
+        Self {
+            conversations,
+            default_prompt: default_prompt.into(),
+            max_length: DEFAULT_MAX_PERSONA_LENGTH,
+            per_account: false,
+            personas: None,
+        }
+    }
+
+    /// Set the longest prompt accepted, in characters.
+    pub fn with_max_length(mut self, max_length: usize) -> Self {
+        self.max_length = max_length;
+        self
+    }
+
+    /// Keep prompts per receiving account, matching a `ChatHandler`
+    /// configured with personas.
+    pub fn per_account(mut self) -> Self {
+        self.per_account = true;
+        self
+    }
+
+    /// Show the receiving account's persona prompt as the default, matching
+    /// a `ChatHandler` configured with the same personas.
+    pub fn with_personas(mut self, personas: Arc<PersonaRegistry>) -> Self {
+        self.personas = Some(personas);
+        self
+    }
+
+    /// Prompt in effect for `message` while no replacement is set.
+    async fn default_prompt_for(&self, message: &BotMessage) -> String {
+        let persona = match self.personas {
+            Some(ref personas) => personas.get(&message.receiving_account).await,
+            None => None,
+        };
+        persona
+            .and_then(|p| p.system_prompt)
+            .unwrap_or_else(|| self.default_prompt.clone())
+    }
+}
+
+#[async_trait]
+impl CommandHandler for SystemPromptHandler {
+    fn trigger(&self) -> Option<&str> {
+        Some("!system")
+    }
+
+    fn description(&self) -> Option<&str> {
+        Some("Replace the system prompt for this chat (`reset` to clear)")
+    }
+
+    fn usage(&self) -> Option<&str> {
+        Some("<text>")
+    }
+
+    // `!system` mustn't also answer e.g. `!systemic`
+    fn matches(&self, message: &BotMessage) -> bool {
+        !message.is_edit()
+            && matches!(message.kind, MessageKind::Text)
+            && command_args(&message.text, "!system").is_some()
+    }
+
+    async fn execute(&self, message: &BotMessage) -> AppResult<String> {
+        // Group members can't be checked for admin rights, so groups keep
+        // the operator's prompt
+        if message.is_group {
+            return Ok("Custom system prompts are only available in direct messages.".into());
+        }
+
+        let conversation_id = &conversation_key(message, self.per_account);
+        let args = command_args(&message.text, "!system").unwrap_or_default();
+
+        match args {
+            "" => Ok(match self.conversations.system_prompt_override(conversation_id).await {
+                Some(prompt) => format!(
+                    "**Current system prompt:**\n{}\n\nUse `!system reset` to go back to the default.",
+                    prompt
+                ),
+                None => format!(
+                    "**Current system prompt (default):**\n{}\n\nUse `!system <prompt>` to replace it.",
+                    self.default_prompt_for(message).await
+                ),
+            }),
+            "reset" => {
+                if self.conversations.clear_system_prompt_override(conversation_id).await {
+                    info!("Cleared system prompt for {}", &conversation_id[..8.min(conversation_id.len())]);
+                    Ok("System prompt reset to the default.".into())
+                } else {
+                    Ok("Already using the default system prompt.".into())
+                }
+            }
+            prompt => {
+                let length = prompt.chars().count();
+                if length > self.max_length {
+                    return Ok(format!(
+                        "That system prompt is too long ({} characters). The limit is {}.",
+                        length, self.max_length
+                    ));
+                }
+                self.conversations.set_system_prompt_override(conversation_id, prompt).await;
+                Ok("System prompt set. It applies to this conversation until you use `!system reset`.".into())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::message;
+    use crate::personas::Persona;
+    use std::collections::HashMap;
+    use std::time::Duration;
+
+    fn handler() -> (SystemPromptHandler, Arc<ConversationStore>) {
+        let conversations = Arc::new(ConversationStore::new(100, Duration::from_secs(3600)));
+        let handler = SystemPromptHandler::new(conversations.clone(), "You are a helpful assistant.");
+        (handler, conversations)
+    }
+
+    #[tokio::test]
+    async fn test_set_and_show() {
+        let (handler, conversations) = handler();
+
+        let reply = handler.execute(&message("!system")).await.unwrap();
+        assert!(reply.contains("(default):**\nYou are a helpful assistant."));
+
+        let reply = handler.execute(&message("!system You only answer in haiku.")).await.unwrap();
+        assert!(reply.starts_with("System prompt set."));
+        assert_eq!(
+            conversations.system_prompt_override("+14155551234").await.as_deref(),
+            Some("You only answer in haiku.")
+        );
+
+        let reply = handler.execute(&message("!system")).await.unwrap();
+        assert!(reply.contains("**Current system prompt:**\nYou only answer in haiku."));
+    }
+
+    #[tokio::test]
+    async fn test_trigger_is_a_whole_word() {
+        let (handler, _) = handler();
+
+        assert!(handler.matches(&message("!system")));
+        assert!(handler.matches(&message("!system Be brief.")));
+        assert!(!handler.matches(&message("!systemic failure")));
+    }
+
+    #[tokio::test]
+    async fn test_shows_persona_prompt_as_default() {
+        let (handler, _) = handler();
+        let personas = PersonaRegistry::from_map(HashMap::from([(
+            "+15555555555".to_string(),
+            Persona {
+                model: None,
+                system_prompt: Some("You are a travel agent.".to_string()),
+            },
+        )]));
+        let handler = handler.per_account().with_personas(Arc::new(personas));
+
+        let reply = handler.execute(&message("!system")).await.unwrap();
+        assert!(reply.contains("(default):**\nYou are a travel agent."));
+
+        // Accounts without a persona prompt fall back to the operator's
+        let mut other = message("!system");
+        other.receiving_account = "+16666666666".into();
+        let reply = handler.execute(&other).await.unwrap();
+        assert!(reply.contains("(default):**\nYou are a helpful assistant."));
+    }
+
+    #[tokio::test]
+    async fn test_reset() {
+        let (handler, conversations) = handler();
+        handler.execute(&message("!system You only answer in haiku.")).await.unwrap();
+
+        let reply = handler.execute(&message("!system reset")).await.unwrap();
+        assert_eq!(reply, "System prompt reset to the default.");
+        assert!(conversations.system_prompt_override("+14155551234").await.is_none());
+
+        let reply = handler.execute(&message("!system reset")).await.unwrap();
+        assert_eq!(reply, "Already using the default system prompt.");
+    }
+
+    #[tokio::test]
+    async fn test_rejects_long_prompt_and_groups() {
+        let (handler, conversations) = handler();
+        let handler = handler.with_max_length(10);
+
+        let reply = handler.execute(&message("!system This is far too long")).await.unwrap();
+        assert!(reply.contains("too long"));
+
+        let mut group = message("!system Be brief.");
+        group.is_group = true;
+        group.group_id = Some("group-1".into());
+        let reply = handler.execute(&group).await.unwrap();
+        assert!(reply.contains("only available in direct messages"));
+        assert!(conversations.system_prompt_override("group-1").await.is_none());
+    }
+}
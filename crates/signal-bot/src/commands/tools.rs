@@ -0,0 +1,56 @@
+//! Tools command - lists the tools the AI can call.
+
+use crate::commands::CommandHandler;
+use crate::error::AppResult;
+use async_trait::async_trait;
+use signal_client::BotMessage;
+use std::sync::Arc;
+use tools::ToolRegistry;
+
+pub struct ToolsHandler {
+    registry: Arc<ToolRegistry>,
+}
+
+impl ToolsHandler {
+    pub fn new(registry: Arc<ToolRegistry>) -> Self {
+        Self { registry }
+    }
+}
+
+/// First sentence of a tool description, so each tool fits on one line.
+fn summary(description: &str) -> &str {
+    match description.find(". ") {
+        Some(end) => &description[..=end],
+        None => description,
+    }
+}
+
+#[async_trait]
+impl CommandHandler for ToolsHandler {
+    fn trigger(&self) -> Option<&str> {
+        Some("!tools")
+    }
+
+    fn description(&self) -> Option<&str> {
+        Some("List tools the AI can use")
+    }
+
+    async fn execute(&self, message: &BotMessage) -> AppResult<String> {
+        let mut definitions = self.registry.get_definitions_for(&message.source);
+        if definitions.is_empty() {
+            return Ok("No tools are enabled. I can still chat!".into());
+        }
+        definitions.sort_by(|a, b| a.function.name.cmp(&b.function.name));
+
+        let tool_list = definitions
+            .iter()
+            .map(|d| format!("- {} - {}", d.function.name, summary(&d.function.description)))
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        Ok(format!(
+            "**Available Tools:**\n{}\n\n_I use these automatically when they help answer you._",
+            tool_list
+        ))
+    }
+}
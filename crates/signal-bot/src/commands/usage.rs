@@ -0,0 +1,209 @@
+//! Usage command - shows token and credit consumption.
+
+use crate::commands::{conversation_key, CommandHandler};
+use crate::error::AppResult;
+use async_trait::async_trait;
+use conversation_store::ConversationStore;
+use signal_client::BotMessage;
+use std::sync::Arc;
+use tracing::info;
+use x402_payments::{CreditBalance, CreditStore, PricingCalculator, UsageRecord};
+
+pub struct UsageHandler {
+    conversations: Arc<ConversationStore>,
+    /// Credit store when payments are enabled.
+    credit_store: Option<Arc<CreditStore>>,
+    /// Whether histories are kept per receiving account (multi-persona mode).
+    per_account: bool,
+}
+
+impl UsageHandler {
+    pub fn new(conversations: Arc<ConversationStore>, credit_store: Option<Arc<CreditStore>>) -> Self {
+        Self {
+            conversations,
+            credit_store,
+            per_account: false,
+        }
+    }
+
+    /// Count messages per receiving account, matching a `ChatHandler`
+    /// configured with personas.
+    pub fn per_account(mut self) -> Self {
+        self.per_account = true;
+        self
+    }
+}
+
+/// Totals over a user's usage records.
+#[derive(Debug, Default, PartialEq, Eq)]
+struct UsageSummary {
+    /// Charged model exchanges.
+    messages: usize,
+    prompt_tokens: u64,
+    completion_tokens: u64,
+    credits_consumed: u64,
+    /// Credits an operator clawed back, kept apart from usage.
+    credits_refunded: u64,
+    /// Credits an operator moved to other users.
+    credits_transferred: u64,
+}
+
+impl UsageSummary {
+    fn from_records(records: &[UsageRecord]) -> Self {
+        records.iter().fold(Self::default(), |mut summary, record| {
+            if record.is_refund() {
+                summary.credits_refunded += record.credits_consumed;
+            } else if record.is_transfer() {
+                summary.credits_transferred += record.credits_consumed;
+            } else {
+                summary.messages += 1;
+                summary.prompt_tokens += u64::from(record.prompt_tokens);
+                summary.completion_tokens += u64::from(record.completion_tokens);
+                summary.credits_consumed += record.credits_consumed;
+            }
+            summary
+        })
+    }
+}
+
+fn format_usage(summary: &UsageSummary, balance: &CreditBalance, stored_messages: usize) -> String {
+    let mut response = String::from("**Your Usage**\n\n");
+
+    if summary.messages == 0 {
+        response.push_str("No paid messages yet.\n");
+    } else {
+        response.push_str(&format!(
+            "Messages: {}\n\
+             Prompt tokens: {}\n\
+             Completion tokens: {}\n\
+             Credits used: {} ({})\n\
+             Average per message: {}\n",
+            summary.messages,
+            summary.prompt_tokens,
+            summary.completion_tokens,
+            summary.credits_consumed,
+            PricingCalculator::format_usdc(summary.credits_consumed),
+            PricingCalculator::format_usdc(summary.credits_consumed / summary.messages as u64),
+        ));
+    }
+    if summary.credits_refunded > 0 {
+        response.push_str(&format!(
+            "Refunded: {}\n",
+            PricingCalculator::format_usdc(summary.credits_refunded)
+        ));
+    }
+    if summary.credits_transferred > 0 {
+        response.push_str(&format!(
+            "Transferred: {}\n",
+            PricingCalculator::format_usdc(summary.credits_transferred)
+        ));
+    }
+    if balance.total_received > 0 {
+        response.push_str(&format!(
+            "Received: {}\n",
+            PricingCalculator::format_usdc(balance.total_received)
+        ));
+    }
+
+    response.push_str(&format!(
+        "Remaining: {} ({})\n\n\
+         Messages in this conversation: {}",
+        balance.credits_remaining,
+        PricingCalculator::format_usdc(balance.credits_remaining),
+        stored_messages
+    ));
+    response
+}
+
+#[async_trait]
+impl CommandHandler for UsageHandler {
+    fn trigger(&self) -> Option<&str> {
+        Some("!usage")
+    }
+
+    fn description(&self) -> Option<&str> {
+        Some("Show your token and credit usage")
+    }
+
+    async fn execute(&self, message: &BotMessage) -> AppResult<String> {
+        let conversation_id = conversation_key(message, self.per_account);
+        let stored_messages = self.conversations.message_count(&conversation_id).await?;
+
+        let Some(ref credit_store) = self.credit_store else {
+            return Ok(format!(
+                "**Your Usage**\n\nMessages in this conversation: {}\n\n\
+                 _This bot is free to use, so no credits are tracked._",
+                stored_messages
+            ));
+        };
+
+        let user_id = &message.source;
+        let summary = UsageSummary::from_records(&credit_store.get_usage(user_id).await);
+        let balance = credit_store.get_balance(user_id).await;
+        info!(
+            "Usage check for {}: {} messages, {} credits",
+            user_id, summary.messages, summary.credits_consumed
+        );
+
+        Ok(format_usage(&summary, &balance, stored_messages))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(prompt_tokens: u32, completion_tokens: u32, credits: u64) -> UsageRecord {
+        UsageRecord::new("+14155551234".to_string(), "+14155551234".to_string(), prompt_tokens, completion_tokens, credits)
+    }
+
+    #[test]
+    fn test_summary_aggregates_records() {
+        let records = vec![
+            record(1_000, 200, 1_500),
+            record(2_500, 800, 3_300),
+            record(120, 40, 200),
+            UsageRecord::refund("+14155551234".to_string(), 10_000, "chargeback"),
+            UsageRecord::transfer("+14155551234".to_string(), "+14155559999".to_string(), 20_000),
+        ];
+
+        let summary = UsageSummary::from_records(&records);
+        assert_eq!(
+            summary,
+            UsageSummary {
+                messages: 3,
+                prompt_tokens: 3_620,
+                completion_tokens: 1_040,
+                credits_consumed: 5_000,
+                credits_refunded: 10_000,
+                credits_transferred: 20_000,
+            }
+        );
+
+        let balance = CreditBalance {
+            credits_remaining: 95_000,
+            total_received: 40_000,
+            ..CreditBalance::new("+14155551234".to_string())
+        };
+        let response = format_usage(&summary, &balance, 6);
+        assert!(response.contains("Messages: 3\n"));
+        assert!(response.contains("Credits used: 5000 ($0.005000)"));
+        assert!(response.contains("Average per message: $0.001666"));
+        assert!(response.contains("Refunded: $0.010000"));
+        assert!(response.contains("Transferred: $0.020000"));
+        assert!(response.contains("Received: $0.040000"));
+        assert!(response.contains("Remaining: 95000 ($0.095000)"));
+    }
+
+    #[test]
+    fn test_empty_history() {
+        let summary = UsageSummary::from_records(&[]);
+        assert_eq!(summary, UsageSummary::default());
+
+        let response = format_usage(&summary, &CreditBalance::new("+14155551234".to_string()), 0);
+        assert!(response.contains("No paid messages yet."));
+        assert!(!response.contains("Refunded"));
+        assert!(!response.contains("Received"));
+        assert!(response.contains("Remaining: 0 ($0.000000)"));
+    }
+}
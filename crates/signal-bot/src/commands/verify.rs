@@ -3,10 +3,12 @@
 use crate::commands::CommandHandler;
 use crate::error::AppResult;
 use async_trait::async_trait;
-use dstack_client::DstackClient;
+use base64::Engine;
+use dstack_client::{DstackApi, DstackClient};
+use serde::{Deserialize, Serialize};
 use signal_client::BotMessage;
 use std::sync::Arc;
-use tracing::info;
+use tracing::{debug, info};
 use sha2::{Sha256, Digest};
 use hex;
 
@@ -28,42 +30,62 @@ impl OperatorAddresses {
     }
 }
 
+/// Source repository shown in verification instructions by default.
+const DEFAULT_REPO_URL: &str = "https://github.com/zmanian/signal-bot-tee";
+
+/// Controls which sections appear in the attestation response.
+#[derive(Debug, Clone)]
+pub struct VerifyOptions {
+    /// Include the full base64 TDX quote.
+    pub include_quote: bool,
+    /// Include step-by-step verification instructions.
+    pub include_instructions: bool,
+    /// Repository users should compare the compose hash against.
+    pub repo_url: String,
+}
+
+impl Default for VerifyOptions {
+    fn default() -> Self {
+        Self {
+            include_quote: true,
+            include_instructions: true,
+            repo_url: DEFAULT_REPO_URL.to_string(),
+        }
+    }
+}
+
 pub struct VerifyHandler {
-    dstack: Arc<DstackClient>,
+    dstack: Arc<dyn DstackApi>,
     /// Optional operator addresses to display.
     operator_addresses: Option<OperatorAddresses>,
+    /// Output formatting options.
+    options: VerifyOptions,
 }
 
 impl VerifyHandler {
-    pub fn new(dstack: Arc<DstackClient>) -> Self {
+    pub fn new(dstack: Arc<dyn DstackApi>) -> Self {
+        Self::new_with_options(dstack, VerifyOptions::default())
+    }
+
+    /// Create handler with custom output options.
+    pub fn new_with_options(dstack: Arc<dyn DstackApi>, options: VerifyOptions) -> Self {
         Self {
             dstack,
             operator_addresses: None,
+            options,
         }
     }
 
-    /// Create handler with operator addresses to display.
-    pub fn with_operator_addresses(dstack: Arc<DstackClient>, addresses: OperatorAddresses) -> Self {
-        Self {
-            dstack,
-            operator_addresses: Some(addresses),
-        }
+    /// Also display operator addresses, keeping the handler's options.
+    pub fn with_operator_addresses(mut self, addresses: OperatorAddresses) -> Self {
+        self.operator_addresses = Some(addresses);
+        self
     }
 
     /// Parse the challenge nonce from the message text.
     /// Expected format: "!verify <nonce>" or just "!verify"
     fn parse_challenge(&self, text: &str) -> Option<String> {
-        let trimmed = text.trim();
-        if trimmed.starts_with("!verify") {
-            let rest = trimmed.strip_prefix("!verify").unwrap().trim();
-            if rest.is_empty() {
-                None
-            } else {
-                Some(rest.to_string())
-            }
-        } else {
-            None
-        }
+        command_argument(text, "!verify")
     }
 
     async fn generate_attestation(&self, challenge: Option<&str>) -> AttestationResult {
@@ -88,20 +110,34 @@ impl VerifyHandler {
             }
         };
 
-        // Prepare report_data - hash if challenge is too long
+        // RTMRs are informational; older guest agents may not serve them
+        let rtmrs = match self.dstack.get_tcb_info().await {
+            Ok(tcb) => tcb
+                .rtmrs()
+                .into_iter()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect(),
+            Err(e) => {
+                debug!("TCB info unavailable: {}", e);
+                vec![]
+            }
+        };
+
+        // Prepare report_data - decode hex/base64, or hash if too long
         let default_challenge = "no-challenge-provided";
-        let challenge_str = challenge.unwrap_or(default_challenge);
-        let challenge_bytes = challenge_str.as_bytes();
-
-        let (report_data, was_hashed) = if challenge_bytes.len() > 64 {
-            // Hash the challenge with SHA-256 (produces 32 bytes)
-            let mut hasher = Sha256::new();
-            hasher.update(challenge_bytes);
-            let hash = hasher.finalize();
-            (hash.to_vec(), true)
-        } else {
-            // Use challenge as-is
-            (challenge_bytes.to_vec(), false)
+        let ChallengeData {
+            report_data,
+            was_hashed,
+            was_decoded,
+        } = match challenge_report_data(challenge.unwrap_or(default_challenge)) {
+            Ok(data) => data,
+            Err(e) => {
+                return AttestationResult {
+                    in_tee: true,
+                    error: Some(format!("Invalid challenge: {}", e)),
+                    ..Default::default()
+                };
+            }
         };
 
         let report_data_hex = hex::encode(&report_data);
@@ -117,19 +153,37 @@ impl VerifyHandler {
                     error: Some(format!("Failed to generate quote: {}", e)),
                     report_data_hex: Some(report_data_hex),
                     was_hashed,
+                    was_decoded,
+                    rtmrs,
                     ..Default::default()
                 };
             }
         };
 
+        // Self-check that the quote carries the challenge; signatures are
+        // left to external verifiers
+        let report_data_matches = quote.as_ref().and_then(|q| {
+            let raw = base64::engine::general_purpose::STANDARD.decode(&q.quote).ok()?;
+            match DstackClient::verify_quote(&raw, &report_data) {
+                Ok(verdict) => Some(verdict.report_data_matches),
+                Err(e) => {
+                    debug!("Could not parse quote: {}", e);
+                    None
+                }
+            }
+        });
+
         AttestationResult {
             in_tee: true,
             compose_hash: app_info.compose_hash,
             app_id: app_info.app_id,
             quote: quote.map(|q| q.quote),
+            report_data_matches,
             challenge: challenge.map(String::from),
             report_data_hex: Some(report_data_hex),
             was_hashed,
+            was_decoded,
+            rtmrs,
             error: None,
             operator_addresses: self.operator_addresses.clone(),
         }
@@ -162,6 +216,7 @@ impl VerifyHandler {
         } else {
             lines.push("**Your Challenge:** (none provided)".into());
             lines.push("_Tip: Use `!verify <your-random-text>` for cryptographic proof_".into());
+            lines.push("_Exact bytes: `!verify hex:<hex>` or `!verify b64:<base64>`_".into());
         }
         lines.push(String::new());
 
@@ -171,9 +226,16 @@ impl VerifyHandler {
             lines.push(format!("```\n{}\n```", report_data_hex));
             if result.was_hashed {
                 lines.push("_This is the SHA-256 hash of your challenge._".into());
+            } else if result.was_decoded {
+                lines.push("_These are your decoded challenge bytes, used as-is._".into());
             } else {
                 lines.push("_This is your challenge encoded in hex._".into());
             }
+            match result.report_data_matches {
+                Some(true) => lines.push("✅ The quote's report_data matches your challenge.".into()),
+                Some(false) => lines.push("❌ The quote's report_data does NOT match your challenge.".into()),
+                None => {}
+            }
             lines.push(String::new());
         }
 
@@ -185,23 +247,39 @@ impl VerifyHandler {
         if let Some(id) = &result.app_id {
             lines.push(format!("- App ID: {}", id));
         }
+        for (name, value) in &result.rtmrs {
+            lines.push(format!("- {}: {}", name, value));
+        }
         lines.push(String::new());
 
         // Quote
         if let Some(quote) = &result.quote {
-            lines.push("**TDX Quote (base64):**".into());
-            lines.push("```".into());
-            // Split quote into chunks for readability (Signal might have message limits)
-            for chunk in quote.as_bytes().chunks(64) {
-                lines.push(String::from_utf8_lossy(chunk).to_string());
+            if self.options.include_quote {
+                lines.push("**TDX Quote (base64):**".into());
+                lines.push("```".into());
+                // Wrap the quote for readability; long replies are split into parts on send
+                for chunk in quote.as_bytes().chunks(64) {
+                    lines.push(String::from_utf8_lossy(chunk).to_string());
+                }
+                lines.push("```".into());
+            } else {
+                lines.push(format!(
+                    "**TDX Quote:** generated ({} bytes base64, omitted here)",
+                    quote.len()
+                ));
             }
-            lines.push("```".into());
             lines.push(String::new());
+        } else if let Some(err) = &result.error {
+            lines.push(format!("**Quote Error:** {}", err));
+        }
 
+        if result.quote.is_some() && self.options.include_instructions {
             lines.push("**How to Verify:**".into());
             lines.push("1. **Verify Report Data:** The report_data field in the quote should match the hex value above".into());
             if result.was_hashed {
                 lines.push("   - Since your challenge was >64 bytes, verify: `echo -n '<your-challenge>' | sha256sum`".into());
+            } else if result.was_decoded {
+                lines.push("   - Your challenge bytes are used directly, so this should be their hex encoding".into());
             } else {
                 lines.push("   - To verify: `echo -n '<your-challenge>' | xxd -p`".into());
             }
@@ -214,11 +292,9 @@ impl VerifyHandler {
             lines.push(String::new());
 
             lines.push("3. **Verify Docker Compose:** Check that compose_hash matches the expected configuration".into());
-            lines.push("   - Repository: https://github.com/zmanian/signal-bot-tee".into());
+            lines.push(format!("   - Repository: {}", self.options.repo_url));
             lines.push("   - Compare the compose_hash above with: `sha256sum docker-compose.yaml`".into());
             lines.push("   - This proves the bot is running the expected code".into());
-        } else if let Some(err) = &result.error {
-            lines.push(format!("**Quote Error:** {}", err));
         }
 
         // Operator addresses for fund sweeping
@@ -248,6 +324,183 @@ impl VerifyHandler {
     }
 }
 
+/// Text following `trigger` in a command message, if any.
+fn command_argument(text: &str, trigger: &str) -> Option<String> {
+    let rest = text.trim().strip_prefix(trigger)?.trim();
+    if rest.is_empty() {
+        None
+    } else {
+        Some(rest.to_string())
+    }
+}
+
+/// Most bytes a quote's report_data can hold.
+const MAX_REPORT_DATA_LEN: usize = 64;
+
+/// Report data derived from a challenge.
+#[derive(Debug, PartialEq)]
+struct ChallengeData {
+    report_data: Vec<u8>,
+    /// Text over 64 bytes, replaced by its SHA-256 hash.
+    was_hashed: bool,
+    /// Given as `hex:` or `b64:` and decoded to raw bytes.
+    was_decoded: bool,
+}
+
+/// Turn a challenge into report_data.
+///
+/// `hex:<hex>` and `b64:<base64>` decode to exact bytes (at most 64). Any
+/// other text is used as-is, or hashed with SHA-256 when over 64 bytes.
+fn challenge_report_data(challenge: &str) -> Result<ChallengeData, String> {
+    let decoded = if let Some(encoded) = challenge.strip_prefix("hex:") {
+        Some(hex::decode(encoded.trim()).map_err(|e| format!("malformed hex: {}", e))?)
+    } else if let Some(encoded) = challenge.strip_prefix("b64:") {
+        Some(
+            base64::engine::general_purpose::STANDARD
+                .decode(encoded.trim())
+                .map_err(|e| format!("malformed base64: {}", e))?,
+        )
+    } else {
+        None
+    };
+
+    if let Some(bytes) = decoded {
+        if bytes.is_empty() {
+            return Err("decoded challenge is empty".into());
+        }
+        if bytes.len() > MAX_REPORT_DATA_LEN {
+            return Err(format!(
+                "decoded challenge is {} bytes, report data holds at most {}",
+                bytes.len(),
+                MAX_REPORT_DATA_LEN
+            ));
+        }
+        return Ok(ChallengeData {
+            report_data: bytes,
+            was_hashed: false,
+            was_decoded: true,
+        });
+    }
+
+    let bytes = challenge.as_bytes();
+    if bytes.len() > MAX_REPORT_DATA_LEN {
+        // Hash the challenge with SHA-256 (produces 32 bytes)
+        Ok(ChallengeData {
+            report_data: Sha256::digest(bytes).to_vec(),
+            was_hashed: true,
+            was_decoded: false,
+        })
+    } else {
+        Ok(ChallengeData {
+            report_data: bytes.to_vec(),
+            was_hashed: false,
+            was_decoded: false,
+        })
+    }
+}
+
+/// Schema version of [`AttestationBundle`].
+pub const ATTESTATION_BUNDLE_VERSION: u32 = 1;
+
+/// Machine-readable attestation for offline verification.
+///
+/// Sent to users as base64-encoded JSON by `!attest`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AttestationBundle {
+    /// Bundle schema version.
+    pub version: u32,
+    /// Base64 TDX quote.
+    pub quote: String,
+    /// Hex report data embedded in the quote.
+    pub report_data: String,
+    pub app_id: Option<String>,
+    pub compose_hash: Option<String>,
+    /// Unix time the quote was generated.
+    pub timestamp: i64,
+    /// The user's challenge, if one was given.
+    pub nonce: Option<String>,
+}
+
+impl AttestationBundle {
+    /// Encode as base64 JSON.
+    pub fn encode(&self) -> String {
+        let json = serde_json::to_vec(self).unwrap_or_default();
+        base64::engine::general_purpose::STANDARD.encode(json)
+    }
+
+    /// Decode a bundle produced by [`AttestationBundle::encode`].
+    pub fn decode(encoded: &str) -> Option<Self> {
+        let json = base64::engine::general_purpose::STANDARD
+            .decode(encoded.trim())
+            .ok()?;
+        serde_json::from_slice(&json).ok()
+    }
+}
+
+/// `!attest` - attestation as a single copy-pasteable bundle.
+///
+/// Uses the same quote generation as `!verify`.
+pub struct AttestHandler {
+    verify: VerifyHandler,
+}
+
+impl AttestHandler {
+    pub fn new(dstack: Arc<dyn DstackApi>) -> Self {
+        Self {
+            verify: VerifyHandler::new(dstack),
+        }
+    }
+
+    async fn generate_bundle(&self, nonce: Option<&str>) -> Result<AttestationBundle, String> {
+        let result = self.verify.generate_attestation(nonce).await;
+
+        let (Some(quote), Some(report_data)) = (result.quote, result.report_data_hex) else {
+            return Err(result
+                .error
+                .unwrap_or_else(|| "Attestation unavailable".to_string()));
+        };
+
+        Ok(AttestationBundle {
+            version: ATTESTATION_BUNDLE_VERSION,
+            quote,
+            report_data,
+            app_id: result.app_id,
+            compose_hash: result.compose_hash,
+            timestamp: chrono::Utc::now().timestamp(),
+            nonce: result.challenge,
+        })
+    }
+}
+
+#[async_trait]
+impl CommandHandler for AttestHandler {
+    fn trigger(&self) -> Option<&str> {
+        Some("!attest")
+    }
+
+    fn description(&self) -> Option<&str> {
+        Some("Get attestation as a machine-readable bundle")
+    }
+
+    fn usage(&self) -> Option<&str> {
+        Some("<challenge>")
+    }
+
+    async fn execute(&self, message: &BotMessage) -> AppResult<String> {
+        let nonce = command_argument(&message.text, "!attest");
+        info!("Attestation bundle requested by {}", message.source);
+
+        match self.generate_bundle(nonce.as_deref()).await {
+            Ok(bundle) => Ok(format!(
+                "**Attestation Bundle** (v{})\n```\n{}\n```\n_Base64-encoded JSON. Save it and decode with `base64 -d` to verify offline._",
+                bundle.version,
+                bundle.encode()
+            )),
+            Err(e) => Ok(format!("**Attestation unavailable:** {}", e)),
+        }
+    }
+}
+
 #[derive(Default)]
 struct AttestationResult {
     in_tee: bool,
@@ -257,6 +510,12 @@ struct AttestationResult {
     challenge: Option<String>,
     report_data_hex: Option<String>,
     was_hashed: bool,
+    /// The challenge was given as hex or base64 bytes.
+    was_decoded: bool,
+    /// Whether the parsed quote embeds the report data, if it could be parsed.
+    report_data_matches: Option<bool>,
+    /// Runtime measurement registers as `(name, hex)`.
+    rtmrs: Vec<(String, String)>,
     error: Option<String>,
     operator_addresses: Option<OperatorAddresses>,
 }
@@ -267,9 +526,24 @@ impl CommandHandler for VerifyHandler {
         Some("!verify")
     }
 
+    fn description(&self) -> Option<&str> {
+        Some("Get TEE attestation with your challenge")
+    }
+
+    fn usage(&self) -> Option<&str> {
+        Some("<challenge> | hex:<hex> | b64:<base64>")
+    }
+
     async fn execute(&self, message: &BotMessage) -> AppResult<String> {
         let challenge = self.parse_challenge(&message.text);
 
+        if let Some(Err(e)) = challenge.as_deref().map(challenge_report_data) {
+            return Ok(format!(
+                "**Invalid challenge:** {}\n\nUse `!verify hex:<hex>` or `!verify b64:<base64>` for up to 64 raw bytes, or plain text.",
+                e
+            ));
+        }
+
         info!(
             "Attestation requested by {} with challenge: {:?}",
             message.source,
@@ -284,11 +558,13 @@ impl CommandHandler for VerifyHandler {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use dstack_client::{DstackClient, MockDstackClient};
 
     fn create_test_handler() -> VerifyHandler {
         VerifyHandler {
             dstack: Arc::new(DstackClient::new("/fake")),
             operator_addresses: None,
+            options: VerifyOptions::default(),
         }
     }
 
@@ -463,6 +739,7 @@ mod tests {
                 near: Some("operator.near".into()),
                 solana: None,
             }),
+            options: VerifyOptions::default(),
         };
 
         let result = AttestationResult {
@@ -483,4 +760,234 @@ mod tests {
         assert!(response.contains("operator.near"));
         assert!(response.contains("swept to these addresses"));
     }
+
+    #[tokio::test]
+    async fn test_generate_attestation_with_mock_tee() {
+        let handler = VerifyHandler::new(Arc::new(MockDstackClient::new()));
+
+        let result = handler.generate_attestation(Some("my-nonce")).await;
+
+        assert!(result.in_tee);
+        assert!(result.error.is_none());
+        assert_eq!(result.compose_hash.as_deref(), Some("mock-compose-hash"));
+        assert_eq!(result.app_id.as_deref(), Some("mock-app-id"));
+        assert_eq!(result.report_data_hex, Some(hex::encode("my-nonce")));
+        assert!(result.quote.is_some());
+        assert!(!result.was_hashed);
+        assert_eq!(
+            result.rtmrs.iter().map(|(name, _)| name.as_str()).collect::<Vec<_>>(),
+            ["RTMR0", "RTMR1", "RTMR2", "RTMR3"]
+        );
+
+        assert_eq!(result.report_data_matches, Some(true));
+
+        let response = handler.format_response(result);
+        assert!(response.contains("- RTMR3: "));
+        assert!(response.contains("report_data matches your challenge"));
+    }
+
+    #[tokio::test]
+    async fn test_generate_attestation_hashes_long_challenge() {
+        let handler = VerifyHandler::new(Arc::new(MockDstackClient::new()));
+        let long_challenge = "a".repeat(65);
+
+        let result = handler.generate_attestation(Some(&long_challenge)).await;
+
+        assert!(result.was_hashed);
+        assert_eq!(
+            result.report_data_hex,
+            Some(hex::encode(Sha256::digest(long_challenge.as_bytes())))
+        );
+    }
+
+    #[test]
+    fn test_challenge_hex() {
+        let challenge = format!("hex:{}", "ab".repeat(32));
+        let data = challenge_report_data(&challenge).unwrap();
+        assert_eq!(data.report_data, vec![0xab; 32]);
+        assert!(data.was_decoded);
+        assert!(!data.was_hashed);
+
+        assert_eq!(
+            challenge_report_data("hex:DEADbeef").unwrap().report_data,
+            [0xde, 0xad, 0xbe, 0xef]
+        );
+        assert!(challenge_report_data("hex:xyz1").unwrap_err().contains("malformed hex"));
+        assert!(challenge_report_data("hex:abc").unwrap_err().contains("malformed hex"));
+    }
+
+    #[test]
+    fn test_challenge_base64() {
+        let data = challenge_report_data("b64:AAECAw==").unwrap();
+        assert_eq!(data.report_data, [0, 1, 2, 3]);
+        assert!(data.was_decoded);
+
+        assert!(challenge_report_data("b64:not base64!")
+            .unwrap_err()
+            .contains("malformed base64"));
+        assert!(challenge_report_data("b64:").is_err());
+    }
+
+    #[test]
+    fn test_challenge_oversized_bytes_rejected() {
+        // Decoded bytes are never hashed, unlike long text
+        let err = challenge_report_data(&format!("hex:{}", "00".repeat(65))).unwrap_err();
+        assert!(err.contains("65 bytes"));
+
+        let encoded = base64::engine::general_purpose::STANDARD.encode([7u8; 65]);
+        assert!(challenge_report_data(&format!("b64:{}", encoded)).is_err());
+        assert!(challenge_report_data(&format!("hex:{}", "00".repeat(64))).is_ok());
+    }
+
+    #[test]
+    fn test_challenge_plain_text() {
+        let data = challenge_report_data("deadbeef").unwrap();
+        assert_eq!(data.report_data, b"deadbeef");
+        assert!(!data.was_decoded);
+        assert!(!data.was_hashed);
+
+        let long = "a".repeat(65);
+        let data = challenge_report_data(&long).unwrap();
+        assert_eq!(data.report_data, Sha256::digest(long.as_bytes()).to_vec());
+        assert!(data.was_hashed);
+    }
+
+    #[tokio::test]
+    async fn test_generate_attestation_with_hex_challenge() {
+        let handler = VerifyHandler::new(Arc::new(MockDstackClient::new()));
+        let challenge_hex = "0123456789abcdef".repeat(4);
+
+        let result = handler
+            .generate_attestation(Some(&format!("hex:{}", challenge_hex)))
+            .await;
+        assert!(result.was_decoded);
+        assert_eq!(result.report_data_hex, Some(challenge_hex));
+        assert_eq!(result.report_data_matches, Some(true));
+
+        let response = handler.format_response(result);
+        assert!(response.contains("decoded challenge bytes"));
+        assert!(!response.contains("xxd -p"));
+
+        let result = handler.generate_attestation(Some("hex:zz")).await;
+        assert!(result.quote.is_none());
+        assert!(result.error.unwrap().starts_with("Invalid challenge"));
+    }
+
+    #[tokio::test]
+    async fn test_attestation_bundle_round_trip() {
+        let handler = AttestHandler::new(Arc::new(MockDstackClient::new()));
+
+        let bundle = handler.generate_bundle(Some("my-nonce")).await.unwrap();
+        assert_eq!(bundle.version, ATTESTATION_BUNDLE_VERSION);
+        assert_eq!(bundle.report_data, hex::encode("my-nonce"));
+        assert_eq!(bundle.nonce.as_deref(), Some("my-nonce"));
+        assert_eq!(bundle.compose_hash.as_deref(), Some("mock-compose-hash"));
+
+        assert_eq!(AttestationBundle::decode(&bundle.encode()), Some(bundle));
+
+        let outside = AttestHandler::new(Arc::new(MockDstackClient::not_in_tee()));
+        assert!(outside.generate_bundle(None).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_generate_attestation_outside_tee() {
+        let handler = VerifyHandler::new(Arc::new(MockDstackClient::not_in_tee()));
+
+        let result = handler.generate_attestation(None).await;
+
+        assert!(!result.in_tee);
+        assert!(result.error.is_some());
+    }
+
+    fn quote_result() -> AttestationResult {
+        AttestationResult {
+            in_tee: true,
+            compose_hash: Some("abc123".into()),
+            app_id: Some("app-456".into()),
+            quote: Some("base64quote".into()),
+            challenge: Some("test".into()),
+            report_data_hex: Some(hex::encode("test".as_bytes())),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_format_response_rtmrs() {
+        let handler = create_test_handler();
+
+        let response = handler.format_response(AttestationResult {
+            rtmrs: vec![
+                ("RTMR0".into(), "aa00".into()),
+                ("RTMR3".into(), "dd33".into()),
+            ],
+            ..quote_result()
+        });
+        let tee_info = response.split("**TEE Info:**").nth(1).unwrap();
+        assert!(tee_info.contains("- RTMR0: aa00\n"));
+        assert!(tee_info.contains("- RTMR3: dd33\n"));
+
+        // Older guest agents without TCB info
+        let response = handler.format_response(quote_result());
+        assert!(response.contains("- App ID: app-456"));
+        assert!(!response.contains("RTMR"));
+    }
+
+    #[test]
+    fn test_options_hide_quote_and_instructions() {
+        let handler = VerifyHandler::new_with_options(
+            Arc::new(DstackClient::new("/fake")),
+            VerifyOptions {
+                include_quote: false,
+                include_instructions: false,
+                ..Default::default()
+            },
+        );
+
+        let response = handler.format_response(quote_result());
+        assert!(response.contains("TEE Attestation"));
+        assert!(response.contains("abc123"));
+        assert!(response.contains("omitted here"));
+        assert!(!response.contains("base64quote"));
+        assert!(!response.contains("How to Verify:"));
+    }
+
+    #[test]
+    fn test_operator_addresses_keep_options() {
+        let addresses = OperatorAddresses {
+            base: Some("0xABC123".into()),
+            ..Default::default()
+        };
+        let handler = VerifyHandler::new_with_options(
+            Arc::new(DstackClient::new("/fake")),
+            VerifyOptions {
+                include_quote: false,
+                ..Default::default()
+            },
+        )
+        .with_operator_addresses(addresses.clone());
+
+        let response = handler.format_response(AttestationResult {
+            operator_addresses: Some(addresses),
+            ..quote_result()
+        });
+        assert!(response.contains("0xABC123"));
+        assert!(response.contains("omitted here"));
+        assert!(!response.contains("base64quote"));
+    }
+
+    #[test]
+    fn test_options_custom_repo_url() {
+        let handler = VerifyHandler::new_with_options(
+            Arc::new(DstackClient::new("/fake")),
+            VerifyOptions {
+                repo_url: "https://github.com/example/fork".into(),
+                ..Default::default()
+            },
+        );
+
+        let response = handler.format_response(quote_result());
+        assert!(response.contains("base64quote"));
+        assert!(response.contains("https://github.com/example/fork"));
+        assert!(!response.contains("zmanian/signal-bot-tee"));
+    }
 }
@@ -1,7 +1,10 @@
 //! Application configuration loaded from environment variables.
 
-use anyhow::{Context, Result};
+use anyhow::{ensure, Context, Result};
+use near_ai_client::ChatParams;
+use secrecy::SecretString;
 use serde::Deserialize;
+use signal_client::{BotMessage, RetryPolicy};
 use std::time::Duration;
 
 /// Application configuration.
@@ -33,6 +36,22 @@ pub struct Config {
     /// Payment configuration
     #[serde(default)]
     pub payments: x402_payments::PaymentConfig,
+
+    /// OpenAI-compatible HTTP API configuration
+    #[serde(default)]
+    pub api: ApiConfig,
+
+    /// HTTP health probe configuration
+    #[serde(default)]
+    pub health: HealthConfig,
+
+    /// When to answer chat messages in groups
+    #[serde(default)]
+    pub groups: GroupPolicy,
+
+    /// Content moderation of incoming messages and replies
+    #[serde(default)]
+    pub moderation: ModerationConfig,
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -44,6 +63,58 @@ pub struct SignalConfig {
     /// Poll interval for messages
     #[serde(default = "default_poll_interval", with = "humantime_serde")]
     pub poll_interval: Duration,
+
+    /// How messages reach the bot
+    #[serde(default = "default_receive_mode")]
+    pub receive_mode: ReceiveMode,
+
+    /// HTTP port for the receive webhook (`webhook` mode)
+    #[serde(default = "default_webhook_port")]
+    pub webhook_port: u16,
+
+    /// Token Signal CLI must send in the `Authorization` header of webhook
+    /// calls. Without it the webhook only listens on loopback.
+    #[serde(default)]
+    pub webhook_token: Option<SecretString>,
+
+    /// Attempts per outgoing message when the API fails transiently (5xx, refused connection)
+    #[serde(default = "default_send_attempts")]
+    pub send_attempts: u32,
+
+    /// Delay before the first send retry (doubles per retry, capped at 5s)
+    #[serde(default = "default_send_backoff", with = "humantime_serde")]
+    pub send_backoff: Duration,
+
+    /// Consecutive failed sends before sending pauses (0 disables)
+    #[serde(default = "default_send_breaker_threshold")]
+    pub send_breaker_threshold: u32,
+
+    /// How long sending pauses once the threshold is reached
+    #[serde(default = "default_send_breaker_cooldown", with = "humantime_serde")]
+    pub send_breaker_cooldown: Duration,
+}
+
+impl SignalConfig {
+    /// Retry and circuit-breaker policy for outgoing messages.
+    pub fn retry_policy(&self) -> RetryPolicy {
+        RetryPolicy {
+            max_attempts: self.send_attempts.max(1),
+            initial_backoff: self.send_backoff,
+            breaker_threshold: self.send_breaker_threshold,
+            breaker_cooldown: self.send_breaker_cooldown,
+            ..RetryPolicy::default()
+        }
+    }
+}
+
+/// How the bot receives Signal messages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReceiveMode {
+    /// Poll Signal CLI's receive endpoint for every account
+    Poll,
+    /// Accept messages POSTed by Signal CLI to `/v1/receive`
+    Webhook,
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -59,9 +130,49 @@ pub struct NearAiConfig {
     #[serde(default = "default_model")]
     pub model: String,
 
+    /// Backup model used when the default is rate limited or unavailable
+    #[serde(default)]
+    pub fallback_model: Option<String>,
+
     /// Request timeout
     #[serde(default = "default_timeout", with = "humantime_serde")]
     pub timeout: Duration,
+
+    /// Timeout for requests that offer tools (tool selection is slower)
+    #[serde(default = "default_tool_timeout", with = "humantime_serde")]
+    pub tool_timeout: Duration,
+
+    /// Timeout for the startup health check and the readiness probe
+    #[serde(default = "default_health_check_timeout", with = "humantime_serde")]
+    pub health_check_timeout: Duration,
+
+    /// Sampling temperature
+    #[serde(default = "default_temperature")]
+    pub temperature: f32,
+
+    /// Nucleus sampling cutoff (API default when unset)
+    #[serde(default)]
+    pub top_p: Option<f32>,
+
+    /// Penalty for repeating frequent tokens (API default when unset)
+    #[serde(default)]
+    pub frequency_penalty: Option<f32>,
+
+    /// Penalty for repeating any seen token (API default when unset)
+    #[serde(default)]
+    pub presence_penalty: Option<f32>,
+}
+
+impl NearAiConfig {
+    /// Sampling parameters to send with chat completions.
+    pub fn chat_params(&self) -> ChatParams {
+        ChatParams {
+            temperature: Some(self.temperature),
+            top_p: self.top_p,
+            frequency_penalty: self.frequency_penalty,
+            presence_penalty: self.presence_penalty,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -73,6 +184,38 @@ pub struct ConversationConfig {
     /// Max messages per conversation (older messages are trimmed)
     #[serde(default = "default_max_messages")]
     pub max_messages: usize,
+
+    /// Hard cap on how long any message is kept, even in active conversations
+    #[serde(default, with = "humantime_serde")]
+    pub max_message_age: Option<Duration>,
+
+    /// Once a conversation holds more messages than this, the oldest are
+    /// summarized by the model instead of trimmed (unset disables)
+    #[serde(default)]
+    pub summarize_after: Option<usize>,
+
+    /// How many of the oldest messages each summary replaces
+    #[serde(default = "default_summarize_batch")]
+    pub summarize_batch: usize,
+}
+
+impl ConversationConfig {
+    /// Check that summaries happen before trimming and replace fewer
+    /// messages than it takes to trigger one, so each summary leaves recent
+    /// history in place.
+    pub fn validate(&self) -> Result<()> {
+        let Some(after) = self.summarize_after else {
+            return Ok(());
+        };
+        ensure!(
+            self.summarize_batch >= 1 && self.summarize_batch < after && after < self.max_messages,
+            "Summarization needs 1 <= CONVERSATION__SUMMARIZE_BATCH ({}) < CONVERSATION__SUMMARIZE_AFTER ({}) < CONVERSATION__MAX_MESSAGES ({})",
+            self.summarize_batch,
+            after,
+            self.max_messages
+        );
+        Ok(())
+    }
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -89,16 +232,142 @@ pub struct BotConfig {
     #[serde(default)]
     pub github_repo: Option<String>,
 
+    /// Registration proxy URL to load per-account personas from
+    /// (model and system prompt for each registered number)
+    #[serde(default)]
+    pub registry_url: Option<String>,
+
+    /// Comma-separated sender numbers the bot serves (`*` matches any run of
+    /// characters, e.g. `+1415*`). Unset serves everyone.
+    #[serde(default)]
+    pub allowed_senders: Option<String>,
+
+    /// Comma-separated sender numbers that are always ignored (same wildcards)
+    #[serde(default)]
+    pub blocked_senders: Option<String>,
+
+    /// Reply sent to senders missing from `allowed_senders` (silent if unset)
+    #[serde(default)]
+    pub unauthorized_message: Option<String>,
+
+    /// Stream answers by editing a placeholder message as tokens arrive
+    #[serde(default)]
+    pub stream_replies: bool,
+
+    /// Minimum time between edits of a streamed reply
+    #[serde(default = "default_stream_edit_interval", with = "humantime_serde")]
+    pub stream_edit_interval: Duration,
+
+    /// Signal number that receives operator alerts (failed sweeps, unhealthy
+    /// chain RPCs, fallback storage key). Unset disables alerts.
+    #[serde(default)]
+    pub admin_number: Option<String>,
+
+    /// Bot account alerts are sent from (defaults to the first registered one)
+    #[serde(default)]
+    pub admin_from_account: Option<String>,
+
+    /// Minimum time between repeats of the same alert
+    #[serde(default = "default_alert_interval", with = "humantime_serde")]
+    pub alert_interval: Duration,
+
+    /// Longest custom system prompt accepted by `!persona`, in characters
+    #[serde(default = "default_max_persona_length")]
+    pub max_persona_length: usize,
+
+    /// Include the full base64 TDX quote in `!verify` replies
+    #[serde(default = "default_true")]
+    pub verify_include_quote: bool,
+
+    /// Include step-by-step verification instructions in `!verify` replies
+    #[serde(default = "default_true")]
+    pub verify_include_instructions: bool,
+
+    /// Messages one sender may send per minute before being asked to slow
+    /// down (0 disables)
+    #[serde(default = "default_rate_limit_per_minute")]
+    pub rate_limit_per_minute: u32,
+
+    /// Time allowed at shutdown to finish the current message and any
+    /// already received, before exiting
+    #[serde(default = "default_shutdown_grace", with = "humantime_serde")]
+    pub shutdown_grace: Duration,
+
     /// Log level
     #[serde(default = "default_log_level")]
     pub log_level: String,
 }
 
+/// Whether a sender may talk to the bot.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SenderAccess {
+    Allowed,
+    /// Listed in `blocked_senders`.
+    Blocked,
+    /// An allowlist is set and doesn't include the sender.
+    NotAllowed,
+}
+
+impl BotConfig {
+    /// Check `sender` against the block and allow lists.
+    ///
+    /// The blocklist wins over the allowlist.
+    pub fn sender_access(&self, sender: &str) -> SenderAccess {
+        if sender_listed(self.blocked_senders.as_deref(), sender) {
+            return SenderAccess::Blocked;
+        }
+        match self.allowed_senders.as_deref() {
+            Some(list) if !list.trim().is_empty() && !sender_listed(Some(list), sender) => {
+                SenderAccess::NotAllowed
+            }
+            _ => SenderAccess::Allowed,
+        }
+    }
+}
+
+/// Whether `sender` matches any pattern in a comma-separated list.
+fn sender_listed(list: Option<&str>, sender: &str) -> bool {
+    list.unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .filter(|pattern| !pattern.is_empty())
+        .any(|pattern| wildcard_match(pattern, sender))
+}
+
+/// Match `value` against `pattern`, where `*` matches any run of characters.
+fn wildcard_match(pattern: &str, value: &str) -> bool {
+    let parts: Vec<&str> = pattern.split('*').collect();
+    let (first, last) = (parts[0], parts[parts.len() - 1]);
+    if parts.len() == 1 {
+        return pattern == value;
+    }
+    if value.len() < first.len() + last.len() || !value.starts_with(first) || !value.ends_with(last) {
+        return false;
+    }
+
+    let mut rest = &value[first.len()..value.len() - last.len()];
+    for part in &parts[1..parts.len() - 1] {
+        match rest.find(part) {
+            Some(i) => rest = &rest[i + part.len()..],
+            None => return false,
+        }
+    }
+    true
+}
+
 #[derive(Debug, Clone, Deserialize)]
 pub struct DstackConfig {
     /// Dstack guest agent socket path
     #[serde(default = "default_dstack_socket")]
     pub socket_path: String,
+
+    /// HTTP URL of a proxied guest agent; used instead of the socket when set
+    #[serde(default)]
+    pub url: Option<String>,
+
+    /// How long guest agent app info is reused (0 disables)
+    #[serde(default = "default_dstack_cache_ttl", with = "humantime_serde")]
+    pub cache_ttl: Duration,
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -111,6 +380,19 @@ pub struct ToolsConfig {
     #[serde(default = "default_max_tool_calls")]
     pub max_tool_calls: usize,
 
+    /// Longest tool result passed to the model, in characters
+    #[serde(default = "default_max_tool_result_length")]
+    pub max_result_length: usize,
+
+    /// Comma-separated tools for users without their own list (`*` or unset
+    /// allows every enabled tool)
+    #[serde(default)]
+    pub default_tools: Option<String>,
+
+    /// Per-user tool lists, e.g. `+15551234567=calculator,weather;+15557654321=*`
+    #[serde(default)]
+    pub user_tools: Option<String>,
+
     /// Web search configuration
     #[serde(default)]
     pub web_search: WebSearchConfig,
@@ -122,6 +404,53 @@ pub struct ToolsConfig {
     /// Calculator tool configuration
     #[serde(default)]
     pub calculator: CalculatorConfig,
+
+    /// Crypto price tool configuration
+    #[serde(default)]
+    pub crypto_price: CryptoPriceConfig,
+
+    /// Timezone-aware current time tool configuration
+    #[serde(default)]
+    pub datetime: DateTimeConfig,
+
+    /// Unit and currency conversion tool configuration
+    #[serde(default)]
+    pub convert: ConvertConfig,
+
+    /// Conversation history search configuration
+    #[serde(default)]
+    pub search_history: SearchHistoryConfig,
+
+    /// URL fetcher configuration
+    #[serde(default)]
+    pub fetch_url: FetchUrlConfig,
+}
+
+impl ToolsConfig {
+    /// Tools for users without their own list, if limited.
+    pub fn default_tool_list(&self) -> Option<Vec<String>> {
+        self.default_tools.as_deref().map(split_tool_names)
+    }
+
+    /// Each user's own tool list, from `user_tools`.
+    pub fn user_tool_lists(&self) -> Vec<(String, Vec<String>)> {
+        self.user_tools
+            .as_deref()
+            .unwrap_or_default()
+            .split(';')
+            .filter_map(|entry| entry.split_once('='))
+            .map(|(user, tools)| (user.trim().to_string(), split_tool_names(tools)))
+            .filter(|(user, _)| !user.is_empty())
+            .collect()
+    }
+}
+
+fn split_tool_names(list: &str) -> Vec<String> {
+    list.split(',')
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .map(String::from)
+        .collect()
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -145,12 +474,221 @@ pub struct CalculatorConfig {
     pub enabled: bool,
 }
 
+#[derive(Debug, Clone, Deserialize)]
+pub struct DateTimeConfig {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ConvertConfig {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// Frankfurter-compatible exchange rate API base URL
+    #[serde(default = "default_convert_rates_url")]
+    pub rates_url: String,
+    /// How long a fetched exchange rate is reused
+    #[serde(default = "default_convert_cache_ttl", with = "humantime_serde")]
+    pub cache_ttl: Duration,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct CryptoPriceConfig {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// CoinGecko-compatible API base URL
+    #[serde(default = "default_crypto_price_url")]
+    pub api_url: String,
+    /// How long a fetched price is reused, to stay within API rate limits
+    #[serde(default = "default_crypto_price_cache_ttl", with = "humantime_serde")]
+    pub cache_ttl: Duration,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct FetchUrlConfig {
+    /// Off by default: fetched pages can carry instructions aimed at the model
+    #[serde(default)]
+    pub enabled: bool,
+    /// Largest response body downloaded, in bytes; the rest is dropped
+    #[serde(default = "default_fetch_max_bytes")]
+    pub max_bytes: usize,
+    #[serde(default = "default_fetch_timeout", with = "humantime_serde")]
+    pub timeout: Duration,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SearchHistoryConfig {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// NEAR AI embedding model (the tool is skipped without one)
+    pub embedding_model: Option<String>,
+    #[serde(default = "default_history_results")]
+    pub max_results: usize,
+}
+
+/// How the bot decides whether to answer a group chat message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GroupMode {
+    /// Answer every message
+    Always,
+    /// Answer only when the bot is @-mentioned
+    Mention,
+    /// Answer only messages starting with the configured prefix
+    Prefix,
+    /// Answer every message, but only in allowlisted groups
+    Allowlist,
+}
+
+/// Group response policy. Direct messages are always answered.
+#[derive(Debug, Clone, Deserialize)]
+pub struct GroupPolicy {
+    #[serde(default = "default_group_mode")]
+    pub mode: GroupMode,
+
+    /// Prefix that addresses the bot in `prefix` mode
+    #[serde(default = "default_group_prefix")]
+    pub prefix: String,
+
+    /// Comma-separated group IDs answered in `allowlist` mode
+    #[serde(default)]
+    pub allowed_groups: Option<String>,
+
+    /// Comma-separated UUIDs (ACIs) of the bot's accounts. Clients that
+    /// don't share the bot's number mention it by UUID only, so in `mention`
+    /// mode these count as the bot too.
+    #[serde(default)]
+    pub account_uuids: Option<String>,
+
+    /// Bot's Signal username (from `BOT__SIGNAL_USERNAME`). In `mention`
+    /// mode, `@username` typed as plain text also counts as a mention.
+    #[serde(skip)]
+    pub username: Option<String>,
+}
+
+impl GroupPolicy {
+    /// Whether the bot should answer `message`.
+    pub fn allows(&self, message: &BotMessage) -> bool {
+        let Some(group_id) = message.group_id.as_deref() else {
+            return true;
+        };
+
+        match self.mode {
+            GroupMode::Always => true,
+            GroupMode::Mention => {
+                message.mentions(&message.receiving_account)
+                    || self
+                        .account_uuids
+                        .as_deref()
+                        .unwrap_or_default()
+                        .split(',')
+                        .map(str::trim)
+                        .any(|uuid| !uuid.is_empty() && message.mentions(uuid))
+                    || self
+                        .username
+                        .as_deref()
+                        .is_some_and(|username| names_username(&message.text, username))
+            }
+            GroupMode::Prefix => message.text.trim_start().starts_with(self.prefix.as_str()),
+            GroupMode::Allowlist => self
+                .allowed_groups
+                .as_deref()
+                .unwrap_or_default()
+                .split(',')
+                .any(|id| id.trim() == group_id),
+        }
+    }
+}
+
+/// Whether `text` contains `@username` as a whole word, ignoring case.
+fn names_username(text: &str, username: &str) -> bool {
+    let text = text.to_lowercase();
+    let handle = format!("@{}", username.trim_start_matches('@').to_lowercase());
+    text.match_indices(&handle).any(|(start, _)| {
+        text[start + handle.len()..]
+            .chars()
+            .next()
+            .is_none_or(|c| !(c.is_alphanumeric() || c == '_'))
+    })
+}
+
+/// Which moderator checks message content.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ModerationBackend {
+    /// No moderation
+    None,
+    /// Flag text containing any configured keyword
+    Keywords,
+    /// Ask a NEAR AI model to classify the text
+    Model,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ModerationConfig {
+    #[serde(default = "default_moderation_backend")]
+    pub backend: ModerationBackend,
+
+    /// Comma-separated words or phrases flagged by the `keywords` backend
+    #[serde(default)]
+    pub keywords: Option<String>,
+
+    /// Model used by the `model` backend (defaults to the chat model)
+    #[serde(default)]
+    pub model: Option<String>,
+
+    /// Check messages from users before handling them
+    #[serde(default)]
+    pub check_incoming: bool,
+
+    /// Check replies before sending them
+    #[serde(default = "default_true")]
+    pub check_outgoing: bool,
+
+    /// Sent in place of flagged text
+    #[serde(default = "default_moderation_message")]
+    pub blocked_message: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ApiConfig {
+    /// Serve the OpenAI-compatible chat completions API
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// HTTP port for the API
+    #[serde(default = "default_api_port")]
+    pub port: u16,
+
+    /// Bearer token clients must present (the API won't start without one)
+    #[serde(default)]
+    pub bearer_token: Option<SecretString>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct HealthConfig {
+    /// Serve the `/livez`, `/readyz` and `/health` probes
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// HTTP port for the probe
+    #[serde(default = "default_health_port")]
+    pub port: u16,
+}
+
 // Default implementations
 impl Default for SignalConfig {
     fn default() -> Self {
         Self {
             service_url: default_signal_service(),
             poll_interval: default_poll_interval(),
+            receive_mode: default_receive_mode(),
+            webhook_port: default_webhook_port(),
+            webhook_token: None,
+            send_attempts: default_send_attempts(),
+            send_backoff: default_send_backoff(),
+            send_breaker_threshold: default_send_breaker_threshold(),
+            send_breaker_cooldown: default_send_breaker_cooldown(),
         }
     }
 }
@@ -160,6 +698,9 @@ impl Default for ConversationConfig {
         Self {
             ttl: default_ttl(),
             max_messages: default_max_messages(),
+            max_message_age: None,
+            summarize_after: None,
+            summarize_batch: default_summarize_batch(),
         }
     }
 }
@@ -170,15 +711,75 @@ impl Default for BotConfig {
             system_prompt: default_system_prompt(),
             signal_username: None,
             github_repo: None,
+            registry_url: None,
+            allowed_senders: None,
+            blocked_senders: None,
+            unauthorized_message: None,
+            stream_replies: false,
+            stream_edit_interval: default_stream_edit_interval(),
+            admin_number: None,
+            admin_from_account: None,
+            alert_interval: default_alert_interval(),
+            max_persona_length: default_max_persona_length(),
+            verify_include_quote: true,
+            verify_include_instructions: true,
+            rate_limit_per_minute: default_rate_limit_per_minute(),
+            shutdown_grace: default_shutdown_grace(),
             log_level: default_log_level(),
         }
     }
 }
 
+impl Default for ApiConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            port: default_api_port(),
+            bearer_token: None,
+        }
+    }
+}
+
+impl Default for HealthConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            port: default_health_port(),
+        }
+    }
+}
+
+impl Default for ModerationConfig {
+    fn default() -> Self {
+        Self {
+            backend: default_moderation_backend(),
+            keywords: None,
+            model: None,
+            check_incoming: false,
+            check_outgoing: default_true(),
+            blocked_message: default_moderation_message(),
+        }
+    }
+}
+
+impl Default for GroupPolicy {
+    fn default() -> Self {
+        Self {
+            mode: default_group_mode(),
+            prefix: default_group_prefix(),
+            allowed_groups: None,
+            account_uuids: None,
+            username: None,
+        }
+    }
+}
+
 impl Default for DstackConfig {
     fn default() -> Self {
         Self {
             socket_path: default_dstack_socket(),
+            url: None,
+            cache_ttl: default_dstack_cache_ttl(),
         }
     }
 }
@@ -188,9 +789,17 @@ impl Default for ToolsConfig {
         Self {
             enabled: default_true(),
             max_tool_calls: default_max_tool_calls(),
+            max_result_length: default_max_tool_result_length(),
+            default_tools: None,
+            user_tools: None,
             web_search: WebSearchConfig::default(),
             weather: WeatherConfig::default(),
             calculator: CalculatorConfig::default(),
+            crypto_price: CryptoPriceConfig::default(),
+            datetime: DateTimeConfig::default(),
+            convert: ConvertConfig::default(),
+            search_history: SearchHistoryConfig::default(),
+            fetch_url: FetchUrlConfig::default(),
         }
     }
 }
@@ -213,6 +822,16 @@ impl Default for WeatherConfig {
     }
 }
 
+impl Default for SearchHistoryConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_true(),
+            embedding_model: None,
+            max_results: default_history_results(),
+        }
+    }
+}
+
 impl Default for CalculatorConfig {
     fn default() -> Self {
         Self {
@@ -221,6 +840,44 @@ impl Default for CalculatorConfig {
     }
 }
 
+impl Default for DateTimeConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_true(),
+        }
+    }
+}
+
+impl Default for ConvertConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_true(),
+            rates_url: default_convert_rates_url(),
+            cache_ttl: default_convert_cache_ttl(),
+        }
+    }
+}
+
+impl Default for CryptoPriceConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_true(),
+            api_url: default_crypto_price_url(),
+            cache_ttl: default_crypto_price_cache_ttl(),
+        }
+    }
+}
+
+impl Default for FetchUrlConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_bytes: default_fetch_max_bytes(),
+            timeout: default_fetch_timeout(),
+        }
+    }
+}
+
 // Default value functions
 fn default_signal_service() -> String {
     "http://signal-api:8080".into()
@@ -230,6 +887,30 @@ fn default_poll_interval() -> Duration {
     Duration::from_millis(200)
 }
 
+fn default_receive_mode() -> ReceiveMode {
+    ReceiveMode::Poll
+}
+
+fn default_webhook_port() -> u16 {
+    8084
+}
+
+fn default_send_attempts() -> u32 {
+    3
+}
+
+fn default_send_backoff() -> Duration {
+    Duration::from_millis(500)
+}
+
+fn default_send_breaker_threshold() -> u32 {
+    5
+}
+
+fn default_send_breaker_cooldown() -> Duration {
+    Duration::from_secs(30)
+}
+
 fn default_near_ai_url() -> String {
     "https://cloud-api.near.ai/v1".into()
 }
@@ -238,10 +919,42 @@ fn default_model() -> String {
     "deepseek-ai/DeepSeek-V3.1".into()
 }
 
+fn default_stream_edit_interval() -> Duration {
+    Duration::from_millis(1500)
+}
+
+fn default_alert_interval() -> Duration {
+    x402_payments::notify::DEFAULT_ALERT_INTERVAL
+}
+
+fn default_rate_limit_per_minute() -> u32 {
+    crate::rate_limit::DEFAULT_MESSAGES_PER_MINUTE
+}
+
+fn default_shutdown_grace() -> Duration {
+    crate::shutdown::DEFAULT_SHUTDOWN_GRACE
+}
+
+fn default_max_persona_length() -> usize {
+    crate::commands::DEFAULT_MAX_PERSONA_LENGTH
+}
+
 fn default_timeout() -> Duration {
     Duration::from_secs(10)
 }
 
+fn default_tool_timeout() -> Duration {
+    Duration::from_secs(30)
+}
+
+fn default_health_check_timeout() -> Duration {
+    Duration::from_secs(5)
+}
+
+fn default_temperature() -> f32 {
+    0.7
+}
+
 fn default_ttl() -> Duration {
     Duration::from_secs(24 * 60 * 60) // 24 hours
 }
@@ -250,6 +963,10 @@ fn default_max_messages() -> usize {
     50
 }
 
+fn default_summarize_batch() -> usize {
+    20
+}
+
 fn default_system_prompt() -> String {
     r#"You are an AI assistant accessible via Signal, running in a Trusted Execution Environment (TEE) for privacy protection.
 
@@ -306,10 +1023,22 @@ fn default_log_level() -> String {
     "info".into()
 }
 
+fn default_api_port() -> u16 {
+    8083
+}
+
+fn default_health_port() -> u16 {
+    8085
+}
+
 fn default_dstack_socket() -> String {
     "/var/run/dstack.sock".into()
 }
 
+fn default_dstack_cache_ttl() -> Duration {
+    Duration::from_secs(60)
+}
+
 fn default_true() -> bool {
     true
 }
@@ -322,6 +1051,54 @@ fn default_search_results() -> usize {
     5
 }
 
+fn default_max_tool_result_length() -> usize {
+    tools::DEFAULT_MAX_RESULT_LEN
+}
+
+fn default_crypto_price_url() -> String {
+    tools::builtin::DEFAULT_COINGECKO_URL.to_string()
+}
+
+fn default_crypto_price_cache_ttl() -> Duration {
+    tools::builtin::DEFAULT_PRICE_CACHE_TTL
+}
+
+fn default_convert_rates_url() -> String {
+    tools::builtin::DEFAULT_RATES_URL.to_string()
+}
+
+fn default_convert_cache_ttl() -> Duration {
+    tools::builtin::DEFAULT_RATE_CACHE_TTL
+}
+
+fn default_fetch_max_bytes() -> usize {
+    tools::builtin::DEFAULT_FETCH_MAX_BYTES
+}
+
+fn default_fetch_timeout() -> Duration {
+    tools::builtin::DEFAULT_FETCH_TIMEOUT
+}
+
+fn default_history_results() -> usize {
+    5
+}
+
+fn default_group_mode() -> GroupMode {
+    GroupMode::Mention
+}
+
+fn default_moderation_backend() -> ModerationBackend {
+    ModerationBackend::None
+}
+
+fn default_moderation_message() -> String {
+    "Sorry, I can't help with that.".to_string()
+}
+
+fn default_group_prefix() -> String {
+    "@bot".into()
+}
+
 impl Config {
     /// Load configuration from environment variables.
     pub fn load() -> Result<Self> {
@@ -339,8 +1116,34 @@ impl Config {
             .build()
             .context("Failed to build configuration")?;
 
-        config
+        let config: Self = config
             .try_deserialize()
-            .context("Failed to deserialize configuration")
+            .context("Failed to deserialize configuration")?;
+        config.conversation.validate()?;
+        Ok(config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_summarization_settings_validated() {
+        let conversation = |after, batch| ConversationConfig {
+            max_messages: 50,
+            summarize_after: after,
+            summarize_batch: batch,
+            ..Default::default()
+        };
+
+        assert!(conversation(None, 100).validate().is_ok());
+        assert!(conversation(Some(40), 20).validate().is_ok());
+        // A batch that reaches the trigger would summarize recent history
+        assert!(conversation(Some(20), 20).validate().is_err());
+        assert!(conversation(Some(10), 20).validate().is_err());
+        assert!(conversation(Some(40), 0).validate().is_err());
+        // Summaries must come before trimming
+        assert!(conversation(Some(50), 20).validate().is_err());
     }
 }
@@ -0,0 +1,107 @@
+//! HTTP health probe.
+//!
+//! Serves probes for orchestrators. `GET /livez` only proves the process
+//! answers and never touches a dependency. `GET /readyz` (also served as
+//! `GET /health`) reports whether NEAR AI answers, whether the conversation
+//! store is usable, and whether the bot runs in a TEE, and returns 503 when a
+//! dependency is down. NEAR AI is checked with an unpaid `GET /models`, so
+//! frequent probes cost no tokens.
+
+use crate::config::HealthConfig;
+use crate::error::AppResult;
+use anyhow::Context;
+use axum::{extract::State, http::StatusCode, routing::get, Json, Router};
+use conversation_store::ConversationStore;
+use near_ai_client::NearAiClient;
+use serde::{Deserialize, Serialize};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::TcpListener;
+use tracing::{error, info};
+
+/// Shared state for the health probe.
+pub struct HealthState {
+    pub near_ai: Arc<NearAiClient>,
+    pub conversations: Arc<ConversationStore>,
+    /// How long to wait for NEAR AI before reporting it not ready.
+    pub near_ai_timeout: Duration,
+    /// Whether the bot runs in a TEE, checked once at startup.
+    pub in_tee: bool,
+}
+
+/// `GET /readyz` response body.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct HealthResponse {
+    /// `ok` when every dependency is healthy, otherwise `degraded`.
+    pub status: String,
+    pub near_ai: bool,
+    pub conversation_store: bool,
+    pub in_tee: bool,
+}
+
+/// Create the health router.
+pub fn create_router(state: Arc<HealthState>) -> Router {
+    Router::new()
+        .route("/livez", get(live))
+        .route("/readyz", get(ready))
+        .route("/health", get(ready))
+        .with_state(state)
+}
+
+/// Bind the health server and run it as a background task.
+///
+/// Returns `None` when the probe is disabled.
+pub async fn spawn_health_server(
+    config: &HealthConfig,
+    state: HealthState,
+) -> AppResult<Option<tokio::task::JoinHandle<()>>> {
+    if !config.enabled {
+        return Ok(None);
+    }
+
+    let router = create_router(Arc::new(state));
+    let addr = SocketAddr::from(([0, 0, 0, 0], config.port));
+    let listener = TcpListener::bind(addr)
+        .await
+        .with_context(|| format!("Failed to bind health server to {}", addr))?;
+
+    info!("Health probe listening on {}", addr);
+
+    let handle = tokio::spawn(async move {
+        if let Err(e) = axum::serve(listener, router).await {
+            error!("Health server error: {}", e);
+        }
+    });
+
+    Ok(Some(handle))
+}
+
+/// `GET /livez`
+async fn live() -> &'static str {
+    "ok"
+}
+
+/// `GET /readyz` and `GET /health`
+async fn ready(State(state): State<Arc<HealthState>>) -> (StatusCode, Json<HealthResponse>) {
+    let (near_ai, conversation_store) = tokio::join!(
+        state.near_ai.ping(state.near_ai_timeout),
+        state.conversations.health_check()
+    );
+    let healthy = near_ai && conversation_store;
+
+    let status = if healthy {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    (
+        status,
+        Json(HealthResponse {
+            status: if healthy { "ok" } else { "degraded" }.to_string(),
+            near_ai,
+            conversation_store,
+            in_tee: state.in_tee,
+        }),
+    )
+}
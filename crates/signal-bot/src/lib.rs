@@ -1,3 +1,11 @@
+pub mod alerts;
+pub mod api;
 pub mod commands;
 pub mod config;
 pub mod error;
+pub mod health;
+pub mod moderation;
+pub mod personas;
+pub mod rate_limit;
+pub mod shutdown;
+pub mod webhook;
@@ -1,23 +1,36 @@
 //! Signal AI Proxy Bot - Main entry point.
 
+use signal_bot::alerts::SignalAlertChannel;
 use signal_bot::commands::*;
-use signal_bot::config::Config;
+use signal_bot::config::{BotConfig, Config, GroupPolicy, ReceiveMode, SenderAccess};
 use signal_bot::error::AppResult;
+use signal_bot::health::HealthState;
+use signal_bot::moderation::ContentFilter;
+use signal_bot::personas::PersonaRegistry;
+use signal_bot::rate_limit::{SenderRateLimiter, Throttle};
 use anyhow::Context;
 use conversation_store::ConversationStore;
-use dstack_client::DstackClient;
+use dstack_client::{DstackClient, DstackTransport};
 use near_ai_client::NearAiClient;
-use signal_client::{MessageReceiver, SignalClient};
+use signal_client::{BotMessage, MessageReceiver, SignalClient};
+use std::pin::Pin;
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::signal;
-use tokio_stream::StreamExt;
-use tools::{ToolRegistry, builtin::{CalculatorTool, WeatherTool, WebSearchTool}};
-use tracing::{error, info, warn};
+use tokio_stream::wrappers::ReceiverStream;
+use tokio_stream::{Stream, StreamExt};
+use tools::{ToolRegistry, builtin::{CalculatorTool, ConvertTool, CryptoPriceTool, DateTimeTool, FetchUrlTool, SearchHistoryTool, WeatherTool, WebSearchTool}};
+use tracing::{debug, error, info, info_span, warn, Instrument};
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter};
-use x402_payments::CreditStore;
+use uuid::Uuid;
+use x402_payments::{CreditStore, OperatorNotifier};
 
 /// Create and configure tool registry based on config.
-fn create_tool_registry(config: &signal_bot::config::ToolsConfig) -> ToolRegistry {
+fn create_tool_registry(
+    config: &signal_bot::config::ToolsConfig,
+    near_ai: &Arc<NearAiClient>,
+    conversations: &Arc<ConversationStore>,
+) -> ToolRegistry {
     let mut registry = ToolRegistry::new();
 
     if !config.enabled {
@@ -37,6 +50,30 @@ fn create_tool_registry(config: &signal_bot::config::ToolsConfig) -> ToolRegistr
         info!("Registered tool: get_weather");
     }
 
+    // Crypto prices - free CoinGecko API (no key needed)
+    if config.crypto_price.enabled {
+        let tool = CryptoPriceTool::new()
+            .with_base_url(&config.crypto_price.api_url)
+            .with_cache_ttl(config.crypto_price.cache_ttl);
+        registry.register(Arc::new(tool));
+        info!("Registered tool: get_crypto_price");
+    }
+
+    // Current time in any timezone - local, no API
+    if config.datetime.enabled {
+        registry.register(Arc::new(DateTimeTool::new()));
+        info!("Registered tool: get_current_time");
+    }
+
+    // Unit conversion is local; currency rates come from a free API
+    if config.convert.enabled {
+        let tool = ConvertTool::new()
+            .with_base_url(&config.convert.rates_url)
+            .with_cache_ttl(config.convert.cache_ttl);
+        registry.register(Arc::new(tool));
+        info!("Registered tool: convert");
+    }
+
     // Web search - requires API key
     if config.web_search.enabled {
         if let Some(api_key) = &config.web_search.api_key {
@@ -49,6 +86,35 @@ fn create_tool_registry(config: &signal_bot::config::ToolsConfig) -> ToolRegistr
         }
     }
 
+    // URL fetcher - opt-in
+    if config.fetch_url.enabled {
+        let tool = FetchUrlTool::new()
+            .with_max_bytes(config.fetch_url.max_bytes)
+            .with_timeout(config.fetch_url.timeout);
+        registry.register(Arc::new(tool));
+        info!("Registered tool: fetch_url (max_bytes: {})", config.fetch_url.max_bytes);
+    }
+
+    // History search - requires an embedding model
+    if config.search_history.enabled {
+        if let Some(model) = &config.search_history.embedding_model {
+            let tool = SearchHistoryTool::new(near_ai.clone(), conversations.clone(), model.clone())
+                .with_max_results(config.search_history.max_results);
+            registry.register(Arc::new(tool));
+            info!("Registered tool: search_history (model: {})", model);
+        } else {
+            warn!("History search tool enabled but TOOLS__SEARCH_HISTORY__EMBEDDING_MODEL not set - skipping");
+        }
+    }
+
+    if let Some(tools) = config.default_tool_list() {
+        info!("Default tools limited to: {}", tools.join(", "));
+        registry.set_default_tools(tools);
+    }
+    for (user, tools) in config.user_tool_lists() {
+        registry.set_user_tools(user, tools);
+    }
+
     let enabled_count = registry.list_enabled().len();
     info!("Tool registry ready with {} enabled tools", enabled_count);
 
@@ -58,7 +124,7 @@ fn create_tool_registry(config: &signal_bot::config::ToolsConfig) -> ToolRegistr
 #[tokio::main]
 async fn main() -> AppResult<()> {
     // Load configuration
-    let config = Config::load().context("Failed to load configuration")?;
+    let mut config = Config::load().context("Failed to load configuration")?;
 
     // Initialize logging
     init_logging(&config.bot.log_level);
@@ -76,40 +142,83 @@ async fn main() -> AppResult<()> {
         .context("Failed to create NEAR AI client")?,
     );
 
-    let conversations = Arc::new(ConversationStore::new(
-        config.conversation.max_messages,
-        config.conversation.ttl,
-    ));
-
-    let dstack = Arc::new(DstackClient::new(&config.dstack.socket_path));
+    let conversations = Arc::new(match config.conversation.max_message_age {
+        Some(hard_cap) => ConversationStore::new_with_retention(
+            config.conversation.max_messages,
+            config.conversation.ttl,
+            hard_cap,
+        ),
+        None => ConversationStore::new(config.conversation.max_messages, config.conversation.ttl),
+    });
+
+    let dstack = Arc::new(
+        DstackClient::new_with_transport(
+            DstackTransport::from_config(&config.dstack.socket_path, config.dstack.url.as_deref())
+                .context("Invalid DSTACK__URL")?,
+        )
+            .with_cache_ttl(config.dstack.cache_ttl),
+    );
 
     let signal = Arc::new(
-        SignalClient::new(&config.signal.service_url)
-            .context("Failed to create Signal client")?,
+        SignalClient::new_with_retry_policy(
+            &config.signal.service_url,
+            config.signal.retry_policy(),
+        )
+        .context("Failed to create Signal client")?,
     );
 
     // Create tool registry based on config
-    let tool_registry = Arc::new(create_tool_registry(&config.tools));
+    let tool_registry = Arc::new(create_tool_registry(&config.tools, &near_ai, &conversations));
+
+    // Operator alerts over Signal
+    let notifier = config.bot.admin_number.as_ref().map(|admin| {
+        let mut channel = SignalAlertChannel::new(signal.clone(), admin.clone());
+        if let Some(ref account) = config.bot.admin_from_account {
+            channel = channel.with_from_account(account.clone());
+        }
+        info!("Operator alerts go to {}", admin);
+        Arc::new(
+            OperatorNotifier::new(Arc::new(channel))
+                .with_min_interval(config.bot.alert_interval),
+        )
+    });
 
     // Initialize payment system
     let credit_store = if config.payments.enabled {
         info!("Initializing payment system...");
 
-        // Create separate DstackClient instances for payment system
-        let payment_dstack = DstackClient::new(&config.dstack.socket_path);
-        let server_dstack = DstackClient::new(&config.dstack.socket_path);
+        // Each account gets its own deposit wallets
+        if config.payments.accounts.is_none() {
+            match signal.list_accounts().await {
+                Ok(accounts) => config.payments.accounts = Some(accounts.join(",")),
+                Err(e) => warn!("Could not list Signal accounts, using shared deposit wallets: {}", e),
+            }
+        }
+
+        // Payment system gets its own handles; clones share the response cache
+        let payment_dstack = dstack.as_ref().clone();
+        let server_dstack = dstack.as_ref().clone();
 
-        let store = CreditStore::new(
+        let store = CreditStore::new_with_subject(
             payment_dstack,
             config.payments.storage_path.clone(),
+            config.payments.signing_subject.clone(),
         )
         .await
         .context("Failed to initialize credit store")?;
+        store.set_persist_retries(config.payments.persist_retries);
+        store.set_credit_ttl(config.payments.pricing.credit_ttl);
+        store.set_idempotency_key_ttl(config.payments.idempotency_key_ttl);
+        if let Some(ref notifier) = notifier {
+            store.set_notifier(notifier.clone()).await;
+        }
 
-        // Spawn payment HTTP server
+        // Spawn payment HTTP server sharing the bot's credit store
         if let Some(handle) = x402_payments::spawn_payment_server(
             config.payments.clone(),
             server_dstack,
+            Some(store.clone()),
+            notifier.clone(),
         )
         .await
         .context("Failed to start payment server")? {
@@ -129,10 +238,13 @@ async fn main() -> AppResult<()> {
     };
 
     // Health checks
-    if near_ai.health_check().await {
+    if near_ai.health_check(config.near_ai.health_check_timeout).await {
         info!("NEAR AI healthy - Model: {}", config.near_ai.model);
     } else {
-        warn!("NEAR AI health check failed - will retry on requests");
+        warn!(
+            "NEAR AI health check failed at {} - will retry on requests",
+            config.near_ai.base_url
+        );
     }
 
     info!(
@@ -140,7 +252,8 @@ async fn main() -> AppResult<()> {
         config.conversation.max_messages, config.conversation.ttl
     );
 
-    if dstack.is_in_tee().await {
+    let in_tee = dstack.is_in_tee().await;
+    if in_tee {
         if let Ok(info) = dstack.get_app_info().await {
             info!(
                 "Running in TEE - App ID: {}",
@@ -157,10 +270,18 @@ async fn main() -> AppResult<()> {
     }
     info!("Signal API healthy");
 
+    // Per-account personas from the registration proxy (multi-bot hosting)
+    let personas = config.bot.registry_url.as_ref().map(|url| {
+        let registry = Arc::new(PersonaRegistry::new(url.clone()));
+        registry.clone().spawn_refresh(Duration::from_secs(300));
+        info!("Loading bot personas from {}", url);
+        registry
+    });
+
     // Create command handlers
     // Create ChatHandler with or without payment integration
-    let chat_handler: Box<dyn CommandHandler> = if let Some(ref store) = credit_store {
-        Box::new(ChatHandler::with_payments(
+    let chat_handler = if let Some(ref store) = credit_store {
+        ChatHandler::with_payments(
             near_ai.clone(),
             conversations.clone(),
             signal.clone(),
@@ -171,9 +292,9 @@ async fn main() -> AppResult<()> {
             config.bot.github_repo.clone(),
             store.clone(),
             config.payments.pricing.clone(),
-        ))
+        )
     } else {
-        Box::new(ChatHandler::new(
+        ChatHandler::new(
             near_ai.clone(),
             conversations.clone(),
             signal.clone(),
@@ -182,68 +303,292 @@ async fn main() -> AppResult<()> {
             config.tools.max_tool_calls,
             config.bot.signal_username.clone(),
             config.bot.github_repo.clone(),
-        ))
+        )
+    };
+    let mut chat_handler = chat_handler
+        .with_tool_timeout(config.near_ai.tool_timeout)
+        .with_max_tool_result_len(config.tools.max_result_length)
+        .with_sampling(config.near_ai.chat_params())
+        .with_group_policy(GroupPolicy {
+            username: config.bot.signal_username.clone(),
+            ..config.groups.clone()
+        });
+    if let Some(ref model) = config.near_ai.fallback_model {
+        chat_handler = chat_handler.with_fallback_model(model.clone());
+    }
+    if let Some(after) = config.conversation.summarize_after {
+        chat_handler = chat_handler.with_summarization(after, config.conversation.summarize_batch);
+        info!(
+            "Summarizing the oldest {} messages of conversations longer than {}",
+            config.conversation.summarize_batch, after
+        );
+    }
+    let content_filter = ContentFilter::from_config(&config.moderation, &near_ai);
+    if config.bot.stream_replies && content_filter.checks_outgoing() {
+        // Streamed text would be on screen before it could be checked
+        warn!("Reply streaming disabled: replies are moderated before sending");
+    } else if config.bot.stream_replies {
+        chat_handler = chat_handler.with_streaming(config.bot.stream_edit_interval);
+        info!("Streaming replies (editing at most every {:?})", config.bot.stream_edit_interval);
+    }
+    let mut clear_handler = ClearHandler::new(conversations.clone());
+    let mut persona_handler = PersonaHandler::new(conversations.clone())
+        .with_max_length(config.bot.max_persona_length);
+    let mut system_handler = SystemPromptHandler::new(conversations.clone(), config.bot.system_prompt.clone())
+        .with_max_length(config.bot.max_persona_length);
+    let mut model_handler = ModelHandler::new(near_ai.clone(), conversations.clone());
+    let mut usage_handler = UsageHandler::new(conversations.clone(), credit_store.clone());
+    let mut search_handler = SearchHandler::new(conversations.clone());
+    let mut pin_handler = PinHandler::new(conversations.clone());
+    let mut unpin_handler = UnpinHandler::new(conversations.clone());
+    let chat_handler = match personas {
+        Some(personas) => {
+            clear_handler = clear_handler.per_account();
+            persona_handler = persona_handler.per_account();
+            system_handler = system_handler.per_account().with_personas(personas.clone());
+            model_handler = model_handler.per_account();
+            usage_handler = usage_handler.per_account();
+            search_handler = search_handler.per_account();
+            pin_handler = pin_handler.per_account();
+            unpin_handler = unpin_handler.per_account();
+            chat_handler.with_personas(personas)
+        }
+        None => chat_handler,
     };
 
+    // Point verification instructions at this deployment's source, if configured
+    let mut verify_options = VerifyOptions {
+        include_quote: config.bot.verify_include_quote,
+        include_instructions: config.bot.verify_include_instructions,
+        ..Default::default()
+    };
+    if let Some(ref repo) = config.bot.github_repo {
+        verify_options.repo_url = repo.clone();
+    }
+
     let mut handlers: Vec<Box<dyn CommandHandler>> = vec![
-        chat_handler,
-        Box::new(VerifyHandler::new(dstack.clone())),
-        Box::new(ClearHandler::new(conversations.clone())),
-        Box::new(HelpHandler::new()),
+        Box::new(chat_handler),
+        Box::new(VerifyHandler::new_with_options(dstack.clone(), verify_options)),
+        Box::new(AttestHandler::new(dstack.clone())),
+        Box::new(clear_handler),
+        Box::new(persona_handler),
+        Box::new(system_handler),
         Box::new(ModelsHandler::new(near_ai.clone())),
+        Box::new(model_handler),
+        Box::new(ToolsHandler::new(tool_registry.clone())),
+        Box::new(usage_handler),
+        Box::new(search_handler),
+        Box::new(pin_handler),
+        Box::new(unpin_handler),
+        Box::new(EphemeralHandler::new(signal.clone())),
     ];
 
     // Add payment handlers if enabled
     if let Some(ref store) = credit_store {
         handlers.push(Box::new(BalanceHandler::new(store.clone())));
         handlers.push(Box::new(DepositHandler::new(config.payments.clone())));
-        info!("Payment commands enabled: !balance, !deposit");
+        handlers.push(Box::new(CostHandler::new(config.payments.pricing.clone())));
+        info!("Payment commands enabled: !balance, !deposit, !cost");
+    }
+
+    // Help lists whatever was registered above
+    let help_handler = HelpHandler::new(&handlers);
+    handlers.push(Box::new(help_handler));
+
+    // OpenAI-compatible API (bearer-token gated, not charged credits)
+    if config.api.enabled {
+        let mut api_chat = ChatHandler::new(
+            near_ai.clone(),
+            conversations.clone(),
+            signal.clone(),
+            tool_registry.clone(),
+            config.bot.system_prompt.clone(),
+            config.tools.max_tool_calls,
+            config.bot.signal_username.clone(),
+            config.bot.github_repo.clone(),
+        )
+        .with_tool_timeout(config.near_ai.tool_timeout)
+        .with_max_tool_result_len(config.tools.max_result_length)
+        .with_sampling(config.near_ai.chat_params());
+        if let Some(ref model) = config.near_ai.fallback_model {
+            api_chat = api_chat.with_fallback_model(model.clone());
+        }
+        if let Some(after) = config.conversation.summarize_after {
+            api_chat = api_chat.with_summarization(after, config.conversation.summarize_batch);
+        }
+        signal_bot::api::spawn_api_server(
+            &config.api,
+            Arc::new(api_chat),
+            conversations.clone(),
+            config.near_ai.model.clone(),
+        )
+        .await?;
     }
 
+    signal_bot::health::spawn_health_server(
+        &config.health,
+        HealthState {
+            near_ai: near_ai.clone(),
+            conversations: conversations.clone(),
+            near_ai_timeout: config.near_ai.health_check_timeout,
+            in_tee,
+        },
+    )
+    .await?;
+
+    let rate_limiter = SenderRateLimiter::new(config.bot.rate_limit_per_minute);
+
     info!("Registered {} command handlers", handlers.len());
     info!("NEAR AI endpoint: {}", config.near_ai.base_url);
     info!("Listening for messages...");
 
     // Start message receiver
-    let receiver = MessageReceiver::new((*signal).clone(), config.signal.poll_interval);
-    let mut stream = Box::pin(receiver.stream());
+    let mut stream: Pin<Box<dyn Stream<Item = BotMessage> + Send>> = match config.signal.receive_mode {
+        ReceiveMode::Poll => {
+            let receiver = MessageReceiver::new((*signal).clone(), config.signal.poll_interval);
+            Box::pin(receiver.stream())
+        }
+        ReceiveMode::Webhook => {
+            let (messages, _server) = signal_bot::webhook::spawn_webhook_server(&config.signal).await?;
+            Box::pin(ReceiverStream::new(messages))
+        }
+    };
+
+    let process = |message| {
+        process_message(&handlers, &signal, &config.bot, &content_filter, &rate_limiter, message)
+    };
 
-    // Main message loop
-    loop {
+    // Main message loop. On shutdown, stop taking new messages but finish
+    // the current one, so a paid reply isn't charged without being sent.
+    let shutdown = signal::ctrl_c();
+    tokio::pin!(shutdown);
+    let deadline = loop {
         tokio::select! {
             Some(message) = stream.next() => {
-                // Find matching handler
-                let handler = handlers
-                    .iter()
-                    .find(|h| h.matches(&message));
-
-                if let Some(handler) = handler {
-                    match handler.execute(&message).await {
-                        Ok(response) => {
-                            if let Err(e) = signal.reply(&message, &response).await {
-                                error!("Failed to send reply: {}", e);
-                            }
-                        }
-                        Err(e) => {
-                            error!("Handler error: {}", e);
-                            let _ = signal
-                                .reply(&message, "Sorry, something went wrong.")
-                                .await;
+                let handling = process(message);
+                tokio::pin!(handling);
+                tokio::select! {
+                    _ = &mut handling => {}
+                    _ = &mut shutdown => {
+                        info!("Shutdown signal received, finishing the current message");
+                        let deadline = tokio::time::Instant::now() + config.bot.shutdown_grace;
+                        if tokio::time::timeout_at(deadline, &mut handling).await.is_err() {
+                            warn!("Shutdown grace period ran out while handling a message");
                         }
+                        break deadline;
                     }
                 }
             }
-            _ = signal::ctrl_c() => {
+            _ = &mut shutdown => {
                 info!("Shutdown signal received");
-                break;
+                break tokio::time::Instant::now() + config.bot.shutdown_grace;
             }
         }
-    }
+    };
+
+    // Messages already received would otherwise be lost
+    signal_bot::shutdown::drain(&mut stream, deadline, process).await;
 
     info!("Shutting down...");
     Ok(())
 }
 
+/// Handle one message, tagging every log line for it (handler, NEAR AI,
+/// tools, credits) with one correlation id.
+async fn process_message(
+    handlers: &[Box<dyn CommandHandler>],
+    signal: &SignalClient,
+    bot: &BotConfig,
+    content_filter: &ContentFilter,
+    rate_limiter: &SenderRateLimiter,
+    message: BotMessage,
+) {
+    let span = info_span!(
+        "message",
+        request_id = %Uuid::new_v4(),
+        message_timestamp = message.timestamp
+    );
+    handle_message(handlers, signal, bot, content_filter, rate_limiter, &message)
+        .instrument(span)
+        .await;
+}
+
+/// Run `message` through the first matching handler and send its reply.
+async fn handle_message(
+    handlers: &[Box<dyn CommandHandler>],
+    signal: &SignalClient,
+    bot: &BotConfig,
+    content_filter: &ContentFilter,
+    rate_limiter: &SenderRateLimiter,
+    message: &BotMessage,
+) {
+    match bot.sender_access(&message.source) {
+        SenderAccess::Allowed => {}
+        SenderAccess::Blocked => {
+            debug!(sender = %message.source, "Ignoring message from blocked sender");
+            return;
+        }
+        SenderAccess::NotAllowed => {
+            debug!(sender = %message.source, "Rejecting message from sender not in allowlist");
+            // Only answered in direct chats, so groups aren't spammed
+            let direct = !message.is_group && !message.is_edit() && message.is_text();
+            if let Some(reply) = bot.unauthorized_message.as_ref().filter(|_| direct) {
+                if let Err(e) = signal.reply(message, reply).await {
+                    error!("Failed to send reply: {}", e);
+                }
+            }
+            return;
+        }
+    }
+
+    let Some(handler) = handlers.iter().find(|h| h.matches(message)) else {
+        if !message.is_text() {
+            debug!(sender = %message.source, kind = ?message.kind, "No handler for non-text message");
+        }
+        return;
+    };
+
+    if let Throttle::Limited { retry_after, notify } = rate_limiter.check(&message.source) {
+        debug!(sender = %message.source, ?retry_after, "Rate limited sender");
+        // Told once per burst, and only in direct chats
+        if notify && !message.is_group && !message.is_edit() {
+            let reply = format!(
+                "You're sending messages too quickly. Please slow down and try again in {} seconds.",
+                retry_after.as_secs().max(1)
+            );
+            if let Err(e) = signal.reply(message, &reply).await {
+                error!("Failed to send reply: {}", e);
+            }
+        }
+        return;
+    }
+
+    if let Some(notice) = content_filter.screen_incoming(&message.text).await {
+        if !message.is_edit() {
+            if let Err(e) = signal.reply(message, notice).await {
+                error!("Failed to send reply: {}", e);
+            }
+        }
+        return;
+    }
+
+    match handler.execute(message).await {
+        Ok(response) if response.is_empty() => {}
+        Ok(response) => {
+            let response = content_filter.screen_outgoing(response).await;
+            if let Err(e) = signal.reply(message, &response).await {
+                error!("Failed to send reply: {}", e);
+            }
+        }
+        Err(e) => {
+            error!("Handler error: {}", e);
+            let _ = signal
+                .reply(message, "Sorry, something went wrong.")
+                .await;
+        }
+    }
+}
+
 fn init_logging(level: &str) {
     let filter = EnvFilter::try_from_default_env()
         .unwrap_or_else(|_| EnvFilter::new(level));
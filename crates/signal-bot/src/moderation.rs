@@ -0,0 +1,230 @@
+//! Content moderation for incoming and outgoing messages.
+//!
+//! A [`Moderator`] decides whether a piece of text is acceptable. The
+//! [`ContentFilter`] runs it on the directions the operator configured and
+//! swaps flagged text for a safe notice.
+
+use crate::config::{ModerationBackend, ModerationConfig};
+use async_trait::async_trait;
+use near_ai_client::{ChatParams, Message, NearAiClient, Role};
+use std::sync::Arc;
+use tracing::warn;
+
+/// Instructions for the model-based moderator.
+const MODERATION_PROMPT: &str = "You are a content moderator. Decide whether the user's text is \
+    safe to show in a chat. Reply with exactly SAFE, or UNSAFE: followed by a short reason. \
+    Text is unsafe if it contains hate, harassment, sexual content involving minors, \
+    instructions for serious harm, or self-harm encouragement.";
+
+/// Outcome of a moderation check.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Moderation {
+    pub allow: bool,
+    /// Why the text was flagged.
+    pub reason: Option<String>,
+}
+
+impl Moderation {
+    pub fn allowed() -> Self {
+        Self {
+            allow: true,
+            reason: None,
+        }
+    }
+
+    pub fn flagged(reason: impl Into<String>) -> Self {
+        Self {
+            allow: false,
+            reason: Some(reason.into()),
+        }
+    }
+}
+
+/// Decides whether text may be shown.
+#[async_trait]
+pub trait Moderator: Send + Sync {
+    async fn check(&self, text: &str) -> Moderation;
+}
+
+/// Allows everything.
+pub struct NoopModerator;
+
+#[async_trait]
+impl Moderator for NoopModerator {
+    async fn check(&self, _text: &str) -> Moderation {
+        Moderation::allowed()
+    }
+}
+
+/// Flags text containing any of a list of words or phrases (case-insensitive).
+pub struct KeywordModerator {
+    keywords: Vec<String>,
+}
+
+impl KeywordModerator {
+    pub fn new(keywords: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        Self {
+            keywords: keywords
+                .into_iter()
+                .map(|k| k.into().trim().to_lowercase())
+                .filter(|k| !k.is_empty())
+                .collect(),
+        }
+    }
+
+    /// Build from a comma-separated list.
+    pub fn from_list(list: &str) -> Self {
+        Self::new(list.split(','))
+    }
+}
+
+#[async_trait]
+impl Moderator for KeywordModerator {
+    async fn check(&self, text: &str) -> Moderation {
+        let text = text.to_lowercase();
+        match self.keywords.iter().find(|k| text.contains(k.as_str())) {
+            Some(keyword) => Moderation::flagged(format!("contains blocked keyword '{}'", keyword)),
+            None => Moderation::allowed(),
+        }
+    }
+}
+
+/// Asks a NEAR AI model to classify the text.
+///
+/// Fails open: if the model can't be reached, the text is allowed and a
+/// warning logged.
+pub struct NearAiModerator {
+    near_ai: NearAiClient,
+}
+
+impl NearAiModerator {
+    /// Classify with `near_ai`'s model.
+    pub fn new(near_ai: NearAiClient) -> Self {
+        Self { near_ai }
+    }
+}
+
+#[async_trait]
+impl Moderator for NearAiModerator {
+    async fn check(&self, text: &str) -> Moderation {
+        let messages = vec![
+            Message {
+                role: Role::System,
+                content: Some(MODERATION_PROMPT.to_string()),
+                tool_call_id: None,
+                tool_calls: None,
+            },
+            Message {
+                role: Role::User,
+                content: Some(text.to_string()),
+                tool_call_id: None,
+                tool_calls: None,
+            },
+        ];
+
+        match self.near_ai.chat(messages, ChatParams::default().with_temperature(0.0), Some(32), None).await {
+            Ok(verdict) => {
+                let verdict = verdict.trim();
+                if verdict.to_uppercase().starts_with("UNSAFE") {
+                    let reason = verdict["UNSAFE".len()..].trim_start_matches(':').trim();
+                    Moderation::flagged(if reason.is_empty() { "flagged by model" } else { reason })
+                } else {
+                    Moderation::allowed()
+                }
+            }
+            Err(e) => {
+                warn!("Moderation model unavailable, allowing message: {}", e);
+                Moderation::allowed()
+            }
+        }
+    }
+}
+
+/// Runs a [`Moderator`] on the configured message directions.
+pub struct ContentFilter {
+    moderator: Arc<dyn Moderator>,
+    check_incoming: bool,
+    check_outgoing: bool,
+    blocked_message: String,
+}
+
+impl ContentFilter {
+    pub fn new(
+        moderator: Arc<dyn Moderator>,
+        check_incoming: bool,
+        check_outgoing: bool,
+        blocked_message: impl Into<String>,
+    ) -> Self {
+        Self {
+            moderator,
+            check_incoming,
+            check_outgoing,
+            blocked_message: blocked_message.into(),
+        }
+    }
+
+    /// Build the filter described by `config`.
+    ///
+    /// The `model` backend classifies with `config.model`, or `near_ai`'s own
+    /// model when unset.
+    pub fn from_config(config: &ModerationConfig, near_ai: &NearAiClient) -> Self {
+        let moderator: Arc<dyn Moderator> = match config.backend {
+            ModerationBackend::None => Arc::new(NoopModerator),
+            ModerationBackend::Keywords => Arc::new(KeywordModerator::from_list(
+                config.keywords.as_deref().unwrap_or_default(),
+            )),
+            ModerationBackend::Model => Arc::new(NearAiModerator::new(match config.model {
+                Some(ref model) => near_ai.with_model(model),
+                None => near_ai.clone(),
+            })),
+        };
+        let enabled = config.backend != ModerationBackend::None;
+
+        Self::new(
+            moderator,
+            enabled && config.check_incoming,
+            enabled && config.check_outgoing,
+            config.blocked_message.clone(),
+        )
+    }
+
+    /// Whether replies are checked before they're sent.
+    pub fn checks_outgoing(&self) -> bool {
+        self.check_outgoing
+    }
+
+    /// Check an incoming message, returning the notice to answer with if it
+    /// was flagged.
+    pub async fn screen_incoming(&self, text: &str) -> Option<&str> {
+        if !self.check_incoming {
+            return None;
+        }
+        let moderation = self.moderator.check(text).await;
+        if moderation.allow {
+            return None;
+        }
+        warn!(
+            direction = "incoming",
+            reason = moderation.reason.as_deref().unwrap_or_default(),
+            "Moderation flagged message"
+        );
+        Some(&self.blocked_message)
+    }
+
+    /// Check a reply, replacing it with the safe notice if it was flagged.
+    pub async fn screen_outgoing(&self, reply: String) -> String {
+        if !self.check_outgoing {
+            return reply;
+        }
+        let moderation = self.moderator.check(&reply).await;
+        if moderation.allow {
+            return reply;
+        }
+        warn!(
+            direction = "outgoing",
+            reason = moderation.reason.as_deref().unwrap_or_default(),
+            "Moderation flagged reply"
+        );
+        self.blocked_message.clone()
+    }
+}
@@ -0,0 +1,108 @@
+//! Per-account bot personas.
+//!
+//! Each Signal number registered through the registration proxy can carry
+//! its own model and system prompt. The bot looks these up by
+//! `BotMessage::receiving_account` so one process can host many bots.
+
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::RwLock;
+use tracing::{debug, info, warn};
+
+/// Model and system prompt overrides for one bot account.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct Persona {
+    pub model: Option<String>,
+    pub system_prompt: Option<String>,
+}
+
+/// Bot entry as listed by the registration proxy's `GET /v1/bots`.
+#[derive(Debug, Deserialize)]
+struct RegistryBot {
+    phone_number: String,
+    #[serde(flatten)]
+    persona: Persona,
+}
+
+/// Persona lookup keyed by the bot's phone number.
+pub struct PersonaRegistry {
+    registry_url: Option<String>,
+    client: reqwest::Client,
+    personas: RwLock<HashMap<String, Persona>>,
+}
+
+impl PersonaRegistry {
+    /// Create a registry that loads personas from the registration proxy.
+    pub fn new(registry_url: impl Into<String>) -> Self {
+        Self {
+            registry_url: Some(registry_url.into().trim_end_matches('/').to_string()),
+            client: reqwest::Client::builder()
+                .timeout(Duration::from_secs(10))
+                .build()
+                .unwrap_or_default(),
+            personas: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Create a registry with a fixed set of personas.
+    pub fn from_map(personas: HashMap<String, Persona>) -> Self {
+        Self {
+            registry_url: None,
+            client: reqwest::Client::new(),
+            personas: RwLock::new(personas),
+        }
+    }
+
+    /// Get the persona for a bot account, if one is configured.
+    pub async fn get(&self, account: &str) -> Option<Persona> {
+        self.personas.read().await.get(account).cloned()
+    }
+
+    /// Reload personas from the registration proxy.
+    ///
+    /// Returns the number of personas loaded. A registry created with
+    /// [`PersonaRegistry::from_map`] keeps its personas unchanged.
+    pub async fn refresh(&self) -> Result<usize, reqwest::Error> {
+        let Some(ref url) = self.registry_url else {
+            return Ok(self.personas.read().await.len());
+        };
+
+        let bots: Vec<RegistryBot> = self
+            .client
+            .get(format!("{}/v1/bots", url))
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+
+        let personas: HashMap<String, Persona> = bots
+            .into_iter()
+            .map(|bot| (bot.phone_number, bot.persona))
+            .collect();
+        let count = personas.len();
+
+        let mut current = self.personas.write().await;
+        if *current != personas {
+            info!("Loaded {} bot personas from registry", count);
+        }
+        *current = personas;
+
+        Ok(count)
+    }
+
+    /// Refresh personas in the background at a fixed interval.
+    pub fn spawn_refresh(self: Arc<Self>, interval: Duration) -> tokio::task::JoinHandle<()> {
+        tokio::spawn(async move {
+            loop {
+                match self.refresh().await {
+                    Ok(count) => debug!("Persona refresh: {} personas", count),
+                    Err(e) => warn!("Failed to refresh personas from registry: {}", e),
+                }
+                tokio::time::sleep(interval).await;
+            }
+        })
+    }
+}
@@ -0,0 +1,141 @@
+//! Per-sender rate limiting for incoming messages.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Default messages per minute allowed from one sender.
+pub const DEFAULT_MESSAGES_PER_MINUTE: u32 = 20;
+
+/// Buckets tracked before idle ones are dropped.
+const MAX_TRACKED_SENDERS: usize = 10_000;
+
+/// Outcome of a rate limit check.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Throttle {
+    Allowed,
+    /// Over the limit. `notify` is set on the first rejected message of a
+    /// burst, so the sender is told to slow down once rather than per message.
+    Limited { retry_after: Duration, notify: bool },
+}
+
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+    notified: bool,
+}
+
+/// Token bucket per sender: up to `per_minute` messages in a burst, refilled
+/// at `per_minute` per minute.
+pub struct SenderRateLimiter {
+    per_minute: u32,
+    buckets: Mutex<HashMap<String, Bucket>>,
+}
+
+impl SenderRateLimiter {
+    /// Allow `per_minute` messages per sender per minute (zero disables).
+    pub fn new(per_minute: u32) -> Self {
+        Self {
+            per_minute,
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Take a token for `sender`.
+    pub fn check(&self, sender: &str) -> Throttle {
+        self.check_at(sender, Instant::now())
+    }
+
+    fn check_at(&self, sender: &str, now: Instant) -> Throttle {
+        if self.per_minute == 0 {
+            return Throttle::Allowed;
+        }
+        let capacity = f64::from(self.per_minute);
+        let per_sec = capacity / 60.0;
+
+        let mut buckets = self.buckets.lock().unwrap_or_else(|e| e.into_inner());
+        if buckets.len() >= MAX_TRACKED_SENDERS {
+            // A bucket idle for a minute is full again, same as a new one
+            buckets.retain(|_, b| now.duration_since(b.updated) < Duration::from_secs(60));
+        }
+
+        let bucket = buckets.entry(sender.to_string()).or_insert(Bucket {
+            tokens: capacity,
+            updated: now,
+            notified: false,
+        });
+        let elapsed = now.saturating_duration_since(bucket.updated).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * per_sec).min(capacity);
+        bucket.updated = now;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            bucket.notified = false;
+            Throttle::Allowed
+        } else {
+            let notify = !bucket.notified;
+            bucket.notified = true;
+            Throttle::Limited {
+                retry_after: Duration::from_secs_f64((1.0 - bucket.tokens) / per_sec),
+                notify,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bucket_refills_over_time() {
+        let limiter = SenderRateLimiter::new(6); // one token every 10s
+        let start = Instant::now();
+
+        for _ in 0..6 {
+            assert_eq!(limiter.check_at("alice", start), Throttle::Allowed);
+        }
+        assert_eq!(
+            limiter.check_at("alice", start),
+            Throttle::Limited { retry_after: Duration::from_secs(10), notify: true }
+        );
+        // Told once per burst
+        assert!(matches!(
+            limiter.check_at("alice", start + Duration::from_secs(5)),
+            Throttle::Limited { notify: false, .. }
+        ));
+
+        // 10s after the burst one token is back, and only one
+        assert_eq!(limiter.check_at("alice", start + Duration::from_secs(10)), Throttle::Allowed);
+        assert!(matches!(
+            limiter.check_at("alice", start + Duration::from_secs(10)),
+            Throttle::Limited { notify: true, .. }
+        ));
+
+        // Refill stops at capacity
+        let later = start + Duration::from_secs(3600);
+        for _ in 0..6 {
+            assert_eq!(limiter.check_at("alice", later), Throttle::Allowed);
+        }
+        assert!(matches!(limiter.check_at("alice", later), Throttle::Limited { .. }));
+    }
+
+    #[test]
+    fn test_senders_have_separate_buckets() {
+        let limiter = SenderRateLimiter::new(1);
+        let now = Instant::now();
+
+        assert_eq!(limiter.check_at("alice", now), Throttle::Allowed);
+        assert!(matches!(limiter.check_at("alice", now), Throttle::Limited { .. }));
+        assert_eq!(limiter.check_at("bob", now), Throttle::Allowed);
+    }
+
+    #[test]
+    fn test_zero_disables() {
+        let limiter = SenderRateLimiter::new(0);
+        for _ in 0..1000 {
+            assert_eq!(limiter.check("alice"), Throttle::Allowed);
+        }
+    }
+}
@@ -0,0 +1,81 @@
+//! Graceful shutdown of the message loop.
+
+use std::future::Future;
+use std::time::Duration;
+use tokio::time::{timeout, timeout_at, Instant};
+use tokio_stream::{Stream, StreamExt};
+use tracing::{info, warn};
+
+/// Default time allowed for in-flight and buffered messages at shutdown.
+pub const DEFAULT_SHUTDOWN_GRACE: Duration = Duration::from_secs(10);
+
+/// Handle the messages already buffered in `stream` until it has none
+/// ready or `deadline` passes. Doesn't wait for new messages to arrive.
+///
+/// Returns how many messages were handled to completion.
+pub async fn drain<S, F, Fut>(stream: &mut S, deadline: Instant, mut handle: F) -> usize
+where
+    S: Stream + Unpin,
+    F: FnMut(S::Item) -> Fut,
+    Fut: Future<Output = ()>,
+{
+    let mut handled = 0;
+    while Instant::now() < deadline {
+        // A zero timeout still polls once, so buffered items come through
+        let Ok(Some(item)) = timeout(Duration::ZERO, stream.next()).await else {
+            break;
+        };
+        if timeout_at(deadline, handle(item)).await.is_err() {
+            warn!("Shutdown grace period ran out while handling a message");
+            return handled;
+        }
+        handled += 1;
+    }
+    if handled > 0 {
+        info!("Drained {} buffered messages before shutdown", handled);
+    }
+    handled
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    #[tokio::test]
+    async fn test_drains_buffered_items_without_waiting_for_more() {
+        // Three ready items, then a stream that would wait forever
+        let mut stream = Box::pin(tokio_stream::iter(1..=3).chain(tokio_stream::pending()));
+        let seen = Arc::new(Mutex::new(Vec::new()));
+
+        let handled = drain(&mut stream, Instant::now() + Duration::from_secs(5), |item| {
+            let seen = seen.clone();
+            async move { seen.lock().unwrap().push(item) }
+        })
+        .await;
+
+        assert_eq!(handled, 3);
+        assert_eq!(*seen.lock().unwrap(), [1, 2, 3]);
+    }
+
+    #[tokio::test]
+    async fn test_stops_at_deadline() {
+        let mut stream = tokio_stream::iter(1..=3);
+        let started = std::time::Instant::now();
+
+        let handled = drain(&mut stream, Instant::now() + Duration::from_millis(50), |_| {
+            tokio::time::sleep(Duration::from_secs(5))
+        })
+        .await;
+
+        assert_eq!(handled, 0);
+        assert!(started.elapsed() < Duration::from_secs(1));
+    }
+
+    #[tokio::test]
+    async fn test_past_deadline_handles_nothing() {
+        let mut stream = tokio_stream::iter(1..=3);
+        let handled = drain(&mut stream, Instant::now(), |_| async {}).await;
+        assert_eq!(handled, 0);
+    }
+}
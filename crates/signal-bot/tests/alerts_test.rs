@@ -0,0 +1,74 @@
+//! Integration tests for operator alerts over Signal.
+
+use signal_bot::alerts::SignalAlertChannel;
+use signal_client::{RetryPolicy, SignalClient};
+use std::sync::Arc;
+use wiremock::matchers::{body_partial_json, method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+use x402_payments::OperatorNotifier;
+
+const ADMIN: &str = "+14155550000";
+
+async fn mock_send(server: &MockServer, from: &str) {
+    Mock::given(method("POST"))
+        .and(path("/v2/send"))
+        .and(body_partial_json(serde_json::json!({
+            "number": from,
+            "recipients": [ADMIN]
+        })))
+        .respond_with(ResponseTemplate::new(201))
+        .expect(1)
+        .mount(server)
+        .await;
+}
+
+#[tokio::test]
+async fn test_alert_sent_from_first_registered_account() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/v1/accounts"))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_json(serde_json::json!(["+15550001111", "+15550002222"])),
+        )
+        .mount(&server)
+        .await;
+    mock_send(&server, "+15550001111").await;
+
+    let signal = Arc::new(SignalClient::new(server.uri()).unwrap());
+    let notifier = OperatorNotifier::new(Arc::new(SignalAlertChannel::new(signal, ADMIN)));
+
+    assert!(notifier.notify("sweep:Base", "Base sweep failed").await);
+    // Repeats within the alert interval are dropped
+    assert!(!notifier.notify("sweep:Base", "Base sweep failed").await);
+}
+
+#[tokio::test]
+async fn test_alert_sent_from_configured_account() {
+    let server = MockServer::start().await;
+    mock_send(&server, "+15550002222").await;
+
+    let signal = Arc::new(SignalClient::new(server.uri()).unwrap());
+    let channel = SignalAlertChannel::new(signal, ADMIN).with_from_account("+15550002222");
+    let notifier = OperatorNotifier::new(Arc::new(channel));
+
+    assert!(notifier.notify("health:Near", "NEAR payments are degraded").await);
+}
+
+#[tokio::test]
+async fn test_undeliverable_alert_is_retried_next_time() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v2/send"))
+        .respond_with(ResponseTemplate::new(400))
+        .expect(2)
+        .mount(&server)
+        .await;
+
+    let signal =
+        Arc::new(SignalClient::new_with_retry_policy(server.uri(), RetryPolicy::none()).unwrap());
+    let channel = SignalAlertChannel::new(signal, ADMIN).with_from_account("+15550002222");
+    let notifier = OperatorNotifier::new(Arc::new(channel));
+
+    assert!(!notifier.notify("key-fallback", "AppInfo key").await);
+    assert!(!notifier.notify("key-fallback", "AppInfo key").await);
+}
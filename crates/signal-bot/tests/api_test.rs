@@ -0,0 +1,157 @@
+//! Integration tests for the OpenAI-compatible chat completions API.
+
+mod common;
+
+use common::{mock_near_ai_server, test_near_ai_client};
+use conversation_store::ConversationStore;
+use signal_bot::api::{create_router, ApiState, ChatCompletionResponse, ErrorResponse};
+use signal_bot::commands::ChatHandler;
+use signal_client::SignalClient;
+use std::sync::Arc;
+use std::time::Duration;
+use tools::ToolRegistry;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+const TOKEN: &str = "test-token";
+
+/// Serve the API on an ephemeral port, returning its base URL.
+async fn start_api(near_ai_server: &MockServer) -> (String, Arc<ConversationStore>) {
+    let near_ai = Arc::new(test_near_ai_client(near_ai_server));
+    let conversations = Arc::new(ConversationStore::new(50, Duration::from_secs(3600)));
+    // Never contacted: API requests don't send Signal progress messages
+    let signal = Arc::new(SignalClient::new("http://127.0.0.1:9").unwrap());
+
+    let chat = Arc::new(ChatHandler::new(
+        near_ai,
+        conversations.clone(),
+        signal,
+        Arc::new(ToolRegistry::new()),
+        "You are a helpful assistant.".to_string(),
+        5,
+        None,
+        None,
+    ));
+
+    let state = Arc::new(ApiState {
+        chat,
+        conversations: conversations.clone(),
+        model: "test-model".to_string(),
+        bearer_token: TOKEN.to_string().into(),
+    });
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, create_router(state)).await.unwrap();
+    });
+
+    (format!("http://{}", addr), conversations)
+}
+
+async fn mock_completion(server: &MockServer, content: &str) {
+    Mock::given(method("POST"))
+        .and(path("/chat/completions"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "id": "chatcmpl-123",
+            "object": "chat.completion",
+            "created": 1677652288,
+            "model": "test-model",
+            "choices": [{
+                "index": 0,
+                "message": { "role": "assistant", "content": content },
+                "finish_reason": "stop"
+            }],
+            "usage": { "prompt_tokens": 12, "completion_tokens": 8, "total_tokens": 20 }
+        })))
+        .mount(server)
+        .await;
+}
+
+#[tokio::test]
+async fn test_chat_completions_openai_shape() {
+    let near_ai_server = mock_near_ai_server().await;
+    mock_completion(&near_ai_server, "Hello from the TEE").await;
+    let (base_url, conversations) = start_api(&near_ai_server).await;
+
+    let response = reqwest::Client::new()
+        .post(format!("{}/v1/chat/completions", base_url))
+        .bearer_auth(TOKEN)
+        .json(&serde_json::json!({
+            "model": "anything",
+            "messages": [
+                { "role": "user", "content": "Hi" },
+                { "role": "assistant", "content": "Hello!" },
+                { "role": "user", "content": "Who are you?" }
+            ]
+        }))
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), 200);
+    let body: ChatCompletionResponse = response.json().await.unwrap();
+    assert_eq!(body.object, "chat.completion");
+    assert_eq!(body.model, "test-model");
+    assert_eq!(body.choices[0].message.role, "assistant");
+    assert_eq!(body.choices[0].message.content.as_deref(), Some("Hello from the TEE"));
+    assert_eq!(body.usage.total_tokens, 20);
+
+    // Stateless requests don't leave history behind
+    assert_eq!(conversations.conversation_count().await, 0);
+
+    // The request's history was forwarded to the model
+    let requests = near_ai_server.received_requests().await.unwrap();
+    let sent: serde_json::Value = requests[0].body_json().unwrap();
+    let roles: Vec<&str> = sent["messages"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|m| m["role"].as_str().unwrap())
+        .collect();
+    assert_eq!(roles, vec!["system", "user", "assistant", "user"]);
+}
+
+#[tokio::test]
+async fn test_chat_completions_keeps_history_per_user() {
+    let near_ai_server = mock_near_ai_server().await;
+    mock_completion(&near_ai_server, "Noted").await;
+    let (base_url, conversations) = start_api(&near_ai_server).await;
+
+    let response = reqwest::Client::new()
+        .post(format!("{}/v1/chat/completions", base_url))
+        .bearer_auth(TOKEN)
+        .json(&serde_json::json!({
+            "messages": [{ "role": "user", "content": "Remember 42" }],
+            "user": "alice"
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+
+    let history = conversations.get("api:alice").await.unwrap().unwrap();
+    assert_eq!(history.messages.len(), 2);
+    assert_eq!(history.messages[0].content.as_deref(), Some("Remember 42"));
+}
+
+#[tokio::test]
+async fn test_chat_completions_requires_bearer_token() {
+    let near_ai_server = mock_near_ai_server().await;
+    let (base_url, _) = start_api(&near_ai_server).await;
+
+    let response = reqwest::Client::new()
+        .post(format!("{}/v1/chat/completions", base_url))
+        .bearer_auth("wrong-token")
+        .json(&serde_json::json!({
+            "messages": [{ "role": "user", "content": "Hi" }]
+        }))
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), 401);
+    let body: ErrorResponse = response.json().await.unwrap();
+    assert_eq!(body.error.error_type, "invalid_request_error");
+    assert!(near_ai_server.received_requests().await.unwrap().is_empty());
+}
@@ -1,15 +1,19 @@
 //! Common test utilities for integration tests.
 
+use dstack_client::MockDstackClient;
 use near_ai_client::NearAiClient;
+use signal_client::{BotMessage, MessageKind};
 use std::time::Duration;
 use wiremock::MockServer;
 
 /// Start a mock NEAR AI server.
+#[allow(dead_code)]
 pub async fn mock_near_ai_server() -> MockServer {
     MockServer::start().await
 }
 
 /// Create a NEAR AI client configured for a mock server.
+#[allow(dead_code)]
 pub fn test_near_ai_client(mock_server: &MockServer) -> NearAiClient {
     NearAiClient::new(
         "test-api-key",
@@ -19,3 +23,25 @@ pub fn test_near_ai_client(mock_server: &MockServer) -> NearAiClient {
     )
     .unwrap()
 }
+
+/// Create a deterministic Dstack client that reports running in a TEE.
+#[allow(dead_code)]
+pub fn test_dstack_client() -> MockDstackClient {
+    MockDstackClient::new()
+}
+
+/// A direct text message from `+14155551234` to the bot.
+#[allow(dead_code)]
+pub fn message(text: &str) -> BotMessage {
+    BotMessage {
+        source: "+14155551234".to_string(),
+        text: text.to_string(),
+        timestamp: 1,
+        is_group: false,
+        group_id: None,
+        receiving_account: "+15555555555".to_string(),
+        edit_target: None,
+        mentions: vec![],
+        kind: MessageKind::Text,
+    }
+}
@@ -2,15 +2,15 @@
 
 mod common;
 
-use common::{mock_near_ai_server, test_near_ai_client};
+use common::{mock_near_ai_server, test_dstack_client, test_near_ai_client};
 use conversation_store::ConversationStore;
-use signal_client::{BotMessage, SignalClient};
+use signal_client::{BotMessage, MessageKind, Reaction, SignalClient, Sticker};
 use std::sync::Arc;
 use std::time::Duration;
 use tools::ToolRegistry;
 use wiremock::matchers::{method, path, body_json, body_string_contains};
 use wiremock::{Mock, MockServer, ResponseTemplate};
-use signal_bot::commands::{ChatHandler, CommandHandler};
+use signal_bot::commands::{ChatHandler, CommandHandler, VerifyHandler};
 
 #[tokio::test]
 async fn test_bot_chat_e2e() {
@@ -31,6 +31,8 @@ async fn test_bot_chat_e2e() {
         tool_registry.clone(),
         "You are a helpful assistant.".to_string(),
         5,
+        None,
+        None,
     );
 
     // 3. Mock NEAR AI Response
@@ -76,6 +78,9 @@ async fn test_bot_chat_e2e() {
         is_group: false,
         group_id: None,
         receiving_account: "+987654321".to_string(),
+        edit_target: None,
+        mentions: vec![],
+        kind: MessageKind::Text,
     };
 
     // 6. Execute Handler
@@ -113,6 +118,8 @@ async fn test_bot_tool_use_e2e() {
         tool_registry.clone(),
         "You are a helpful assistant.".to_string(),
         5,
+        None,
+        None,
     );
 
     // 3. Mock NEAR AI Response 1: Tool Call
@@ -205,6 +212,9 @@ async fn test_bot_tool_use_e2e() {
         is_group: false,
         group_id: None,
         receiving_account: "+987654321".to_string(),
+        edit_target: None,
+        mentions: vec![],
+        kind: MessageKind::Text,
     };
 
     let response = chat_handler.execute(&incoming).await.unwrap();
@@ -223,3 +233,294 @@ async fn test_bot_tool_use_e2e() {
     assert_eq!(history.messages[3].role, "assistant");
 }
 
+#[tokio::test]
+async fn test_bot_tool_loop_capped_for_model_that_always_calls_tools() {
+    let near_ai_server = mock_near_ai_server().await;
+    let signal_server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v2/send"))
+        .respond_with(ResponseTemplate::new(201))
+        .mount(&signal_server)
+        .await;
+
+    let mut tool_registry = ToolRegistry::new();
+    tool_registry.register(Arc::new(tools::builtin::CalculatorTool::new()));
+    let conversations = Arc::new(ConversationStore::new(50, Duration::from_secs(3600)));
+    let chat_handler = ChatHandler::new(
+        Arc::new(test_near_ai_client(&near_ai_server)),
+        conversations.clone(),
+        Arc::new(SignalClient::new(signal_server.uri()).unwrap()),
+        Arc::new(tool_registry),
+        "You are a helpful assistant.".to_string(),
+        2,
+        None,
+        None,
+    );
+
+    // Every response asks for another tool call and never answers
+    Mock::given(method("POST"))
+        .and(path("/chat/completions"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "id": "chatcmpl-loop",
+            "object": "chat.completion",
+            "created": 1677652288,
+            "model": "test-model",
+            "choices": [{
+                "index": 0,
+                "message": {
+                    "role": "assistant",
+                    "content": null,
+                    "tool_calls": [{
+                        "id": "call_loop",
+                        "type": "function",
+                        "function": { "name": "calculate", "arguments": "{\"expression\": \"1 + 1\"}" }
+                    }]
+                },
+                "finish_reason": "tool_calls"
+            }]
+        })))
+        // Two tool rounds plus the final tool-less call
+        .expect(3)
+        .mount(&near_ai_server)
+        .await;
+
+    let incoming = BotMessage {
+        source: "+123456789".to_string(),
+        text: "Keep calculating".to_string(),
+        timestamp: 1,
+        is_group: false,
+        group_id: None,
+        receiving_account: "+987654321".to_string(),
+        edit_target: None,
+        mentions: vec![],
+        kind: MessageKind::Text,
+    };
+
+    let response = chat_handler.execute(&incoming).await.unwrap();
+    assert!(response.contains("couldn't complete that after several tool attempts"));
+
+    // The final call's tool request isn't run; the fallback closes the turn
+    let history = conversations.get("+123456789").await.unwrap().unwrap();
+    let tool_results = history.messages.iter().filter(|m| m.role == "tool").count();
+    assert_eq!(tool_results, 2);
+    assert_eq!(history.messages.last().unwrap().role, "assistant");
+}
+
+#[tokio::test]
+async fn test_bot_verify_e2e() {
+    let verify_handler = VerifyHandler::new(Arc::new(test_dstack_client()));
+
+    let incoming = BotMessage {
+        source: "+123456789".to_string(),
+        text: "!verify my-nonce".to_string(),
+        timestamp: 123456789,
+        is_group: false,
+        group_id: None,
+        receiving_account: "+987654321".to_string(),
+        edit_target: None,
+        mentions: vec![],
+        kind: MessageKind::Text,
+    };
+
+    assert!(verify_handler.matches(&incoming));
+    let response = verify_handler.execute(&incoming).await.unwrap();
+
+    assert!(response.contains("TEE Attestation"));
+    assert!(response.contains("my-nonce"));
+    assert!(response.contains("mock-compose-hash"));
+    assert!(response.contains(&hex::encode("my-nonce")));
+    assert!(response.contains("TDX Quote"));
+}
+
+#[tokio::test]
+async fn test_bot_edit_updates_history_without_reply() {
+    let near_ai_server = mock_near_ai_server().await;
+    Mock::given(method("POST"))
+        .and(path("/chat/completions"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "id": "chatcmpl-123",
+            "object": "chat.completion",
+            "created": 1677652288,
+            "model": "test-model",
+            "choices": [{
+                "index": 0,
+                "message": { "role": "assistant", "content": "Paris is lovely." },
+                "finish_reason": "stop"
+            }]
+        })))
+        .expect(1)
+        .mount(&near_ai_server)
+        .await;
+
+    let conversations = Arc::new(ConversationStore::new(50, Duration::from_secs(3600)));
+    let chat_handler = ChatHandler::new(
+        Arc::new(test_near_ai_client(&near_ai_server)),
+        conversations.clone(),
+        Arc::new(SignalClient::new("http://127.0.0.1:9").unwrap()),
+        Arc::new(ToolRegistry::new()),
+        "You are a helpful assistant.".to_string(),
+        5,
+        None,
+        None,
+    );
+
+    let original = BotMessage {
+        source: "+123456789".to_string(),
+        text: "Tell me about Pars".to_string(),
+        timestamp: 1000,
+        is_group: false,
+        group_id: None,
+        receiving_account: "+987654321".to_string(),
+        edit_target: None,
+        mentions: vec![],
+        kind: MessageKind::Text,
+    };
+    let edit = BotMessage {
+        text: "Tell me about Paris".to_string(),
+        timestamp: 2000,
+        edit_target: Some(1000),
+        ..original.clone()
+    };
+
+    assert_eq!(chat_handler.execute(&original).await.unwrap(), "Paris is lovely.");
+
+    // The edit is routed to chat, but neither re-asks the model nor replies
+    assert!(chat_handler.matches(&edit));
+    assert_eq!(chat_handler.execute(&edit).await.unwrap(), "");
+
+    let history = conversations.get("+123456789").await.unwrap().unwrap();
+    assert_eq!(history.messages.len(), 2);
+    assert_eq!(history.messages[0].content.as_deref(), Some("Tell me about Paris"));
+
+    // Edited commands are ignored rather than run a second time
+    let verify_handler = VerifyHandler::new(Arc::new(test_dstack_client()));
+    let verify_edit = BotMessage {
+        text: "!verify again".to_string(),
+        ..edit
+    };
+    assert!(!verify_handler.matches(&verify_edit));
+}
+
+#[tokio::test]
+async fn test_bot_ignores_reactions_and_stickers() {
+    let near_ai_server = mock_near_ai_server().await;
+    let chat_handler = ChatHandler::new(
+        Arc::new(test_near_ai_client(&near_ai_server)),
+        Arc::new(ConversationStore::new(50, Duration::from_secs(3600))),
+        Arc::new(SignalClient::new("http://127.0.0.1:9").unwrap()),
+        Arc::new(ToolRegistry::new()),
+        "You are a helpful assistant.".to_string(),
+        5,
+        None,
+        None,
+    );
+
+    let reaction = BotMessage {
+        source: "+123456789".to_string(),
+        text: String::new(),
+        timestamp: 2000,
+        is_group: false,
+        group_id: None,
+        receiving_account: "+987654321".to_string(),
+        edit_target: None,
+        mentions: vec![],
+        kind: MessageKind::Reaction(Reaction {
+            emoji: "❤️".to_string(),
+            target_author: "+987654321".to_string(),
+            target_sent_timestamp: 1000,
+            is_remove: false,
+        }),
+    };
+    assert!(!chat_handler.matches(&reaction));
+
+    let sticker = BotMessage {
+        kind: MessageKind::Sticker(Sticker {
+            pack_id: "pack".to_string(),
+            sticker_id: 1,
+        }),
+        ..reaction
+    };
+    assert!(!chat_handler.matches(&sticker));
+}
+
+#[tokio::test]
+async fn test_bot_streaming_reply_edits_placeholder() {
+    let near_ai_server = mock_near_ai_server().await;
+    let signal_server = MockServer::start().await;
+    let conversations = Arc::new(ConversationStore::new(50, Duration::from_secs(3600)));
+
+    let chat_handler = ChatHandler::new(
+        Arc::new(test_near_ai_client(&near_ai_server)),
+        conversations.clone(),
+        Arc::new(SignalClient::new(signal_server.uri()).unwrap()),
+        Arc::new(ToolRegistry::new()),
+        "You are a helpful assistant.".to_string(),
+        5,
+        None,
+        None,
+    )
+    .with_streaming(Duration::ZERO);
+
+    let chunk = |content: &str| {
+        serde_json::json!({
+            "id": "chatcmpl-123",
+            "object": "chat.completion.chunk",
+            "created": 1677652288,
+            "model": "test-model",
+            "choices": [{ "index": 0, "delta": { "content": content }, "finish_reason": null }]
+        })
+    };
+    let sse = format!(
+        "data: {}\n\ndata: {}\n\ndata: [DONE]\n\n",
+        chunk("Hello "),
+        chunk("there!")
+    );
+    Mock::given(method("POST"))
+        .and(path("/chat/completions"))
+        .and(body_string_contains("\"stream\":true"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(sse, "text/event-stream"))
+        .expect(1)
+        .mount(&near_ai_server)
+        .await;
+
+    Mock::given(method("POST"))
+        .and(path("/v2/send"))
+        .respond_with(
+            ResponseTemplate::new(201).set_body_json(serde_json::json!({ "timestamp": "1700000000000" })),
+        )
+        .mount(&signal_server)
+        .await;
+
+    let incoming = BotMessage {
+        source: "+123456789".to_string(),
+        text: "Hi there!".to_string(),
+        timestamp: 123456789,
+        is_group: false,
+        group_id: None,
+        receiving_account: "+987654321".to_string(),
+        edit_target: None,
+        mentions: vec![],
+        kind: MessageKind::Text,
+    };
+
+    // The reply was already delivered by editing the placeholder
+    let response = chat_handler.execute(&incoming).await.unwrap();
+    assert_eq!(response, "");
+
+    let sent: Vec<serde_json::Value> = signal_server
+        .received_requests()
+        .await
+        .unwrap()
+        .iter()
+        .map(|r| r.body_json().unwrap())
+        .collect();
+    assert!(sent.len() >= 2);
+    assert_eq!(sent[0]["message"], "…");
+    assert!(sent[0].get("edit_timestamp").is_none());
+    let last = sent.last().unwrap();
+    assert_eq!(last["message"], "Hello there!");
+    assert_eq!(last["edit_timestamp"], 1700000000000i64);
+
+    let history = conversations.get("+123456789").await.unwrap().unwrap();
+    assert_eq!(history.messages[1].content.as_deref(), Some("Hello there!"));
+}
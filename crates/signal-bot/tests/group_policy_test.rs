@@ -0,0 +1,95 @@
+//! Integration tests for the group response policy.
+
+use signal_bot::config::{GroupMode, GroupPolicy};
+use signal_client::{BotMessage, MessageKind};
+
+const BOT: &str = "+15550001111";
+
+fn group_message(group_id: &str, text: &str, mentions: &[&str]) -> BotMessage {
+    BotMessage {
+        source: "+14155551234".to_string(),
+        text: text.to_string(),
+        timestamp: 1,
+        is_group: true,
+        group_id: Some(group_id.to_string()),
+        receiving_account: BOT.to_string(),
+        edit_target: None,
+        mentions: mentions.iter().map(|m| m.to_string()).collect(),
+        kind: MessageKind::Text,
+    }
+}
+
+fn policy(mode: GroupMode) -> GroupPolicy {
+    GroupPolicy {
+        mode,
+        ..GroupPolicy::default()
+    }
+}
+
+#[test]
+fn test_default_requires_mention_in_groups() {
+    let policy = GroupPolicy::default();
+    assert_eq!(policy.mode, GroupMode::Mention);
+
+    assert!(!policy.allows(&group_message("g1", "hello all", &[])));
+    assert!(!policy.allows(&group_message("g1", "hey you", &["+14155550000"])));
+    assert!(policy.allows(&group_message("g1", "\u{FFFC} hello", &[BOT])));
+
+    // Direct messages are always answered
+    let dm = BotMessage {
+        is_group: false,
+        group_id: None,
+        ..group_message("g1", "hello", &[])
+    };
+    assert!(policy.allows(&dm));
+}
+
+#[test]
+fn test_mention_mode_accepts_typed_username() {
+    let policy = GroupPolicy {
+        username: Some("askbot.01".to_string()),
+        ..GroupPolicy::default()
+    };
+    assert!(policy.allows(&group_message("g1", "@AskBot.01 what's the time?", &[])));
+    assert!(policy.allows(&group_message("g1", "ask @askbot.01.", &[])));
+    assert!(!policy.allows(&group_message("g1", "askbot.01 is quiet today", &[])));
+    assert!(!policy.allows(&group_message("g1", "@askbot.012 hello", &[])));
+
+    // Without a username only real mentions count
+    assert!(!GroupPolicy::default().allows(&group_message("g1", "@askbot.01 hi", &[])));
+}
+
+#[test]
+fn test_mention_mode_accepts_bot_uuid() {
+    let policy = GroupPolicy {
+        account_uuids: Some("other-uuid, bot-uuid".to_string()),
+        ..GroupPolicy::default()
+    };
+    assert!(policy.allows(&group_message("g1", "\u{FFFC} hi", &["bot-uuid"])));
+    assert!(!policy.allows(&group_message("g1", "\u{FFFC} hi", &["friend-uuid"])));
+
+    // Without configured UUIDs a UUID-only mention isn't recognised
+    assert!(!GroupPolicy::default().allows(&group_message("g1", "\u{FFFC} hi", &["bot-uuid"])));
+}
+
+#[test]
+fn test_always_mode() {
+    assert!(policy(GroupMode::Always).allows(&group_message("g1", "hello all", &[])));
+}
+
+#[test]
+fn test_prefix_mode() {
+    let policy = policy(GroupMode::Prefix);
+    assert!(policy.allows(&group_message("g1", "@bot what's the time?", &[])));
+    assert!(!policy.allows(&group_message("g1", "what's the time?", &[])));
+}
+
+#[test]
+fn test_allowlist_mode() {
+    let policy = GroupPolicy {
+        allowed_groups: Some("g1, g2".to_string()),
+        ..policy(GroupMode::Allowlist)
+    };
+    assert!(policy.allows(&group_message("g2", "hello", &[])));
+    assert!(!policy.allows(&group_message("g3", "hello", &[])));
+}
@@ -0,0 +1,119 @@
+//! Integration tests for the health probe.
+
+mod common;
+
+use common::{mock_near_ai_server, test_near_ai_client};
+use conversation_store::ConversationStore;
+use signal_bot::health::{create_router, HealthResponse, HealthState};
+use std::sync::Arc;
+use std::time::Duration;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+/// Serve the probes on an ephemeral port and return their address.
+async fn serve(near_ai_server: &MockServer, in_tee: bool) -> std::net::SocketAddr {
+    let state = Arc::new(HealthState {
+        near_ai: Arc::new(test_near_ai_client(near_ai_server)),
+        conversations: Arc::new(ConversationStore::new(50, Duration::from_secs(3600))),
+        near_ai_timeout: Duration::from_secs(2),
+        in_tee,
+    });
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, create_router(state)).await.unwrap();
+    });
+
+    addr
+}
+
+/// Fetch `/readyz` once.
+async fn probe(near_ai_server: &MockServer, in_tee: bool) -> (reqwest::StatusCode, HealthResponse) {
+    let addr = serve(near_ai_server, in_tee).await;
+    let response = reqwest::get(format!("http://{}/readyz", addr)).await.unwrap();
+    let status = response.status();
+    (status, response.json().await.unwrap())
+}
+
+/// Fail the test if the probe spends a completion.
+async fn forbid_completions(near_ai_server: &MockServer) {
+    Mock::given(method("POST"))
+        .and(path("/chat/completions"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(0)
+        .mount(near_ai_server)
+        .await;
+}
+
+#[tokio::test]
+async fn test_health_ok() {
+    let near_ai_server = mock_near_ai_server().await;
+    forbid_completions(&near_ai_server).await;
+    Mock::given(method("GET"))
+        .and(path("/models"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "object": "list",
+            "data": []
+        })))
+        .mount(&near_ai_server)
+        .await;
+
+    let (status, health) = probe(&near_ai_server, true).await;
+    assert_eq!(status, reqwest::StatusCode::OK);
+    assert_eq!(health.status, "ok");
+    assert!(health.near_ai);
+    assert!(health.conversation_store);
+    assert!(health.in_tee);
+}
+
+#[tokio::test]
+async fn test_health_degraded_when_near_ai_down() {
+    let near_ai_server = mock_near_ai_server().await;
+    forbid_completions(&near_ai_server).await;
+    Mock::given(method("GET"))
+        .and(path("/models"))
+        .respond_with(ResponseTemplate::new(401))
+        .mount(&near_ai_server)
+        .await;
+
+    let (status, health) = probe(&near_ai_server, false).await;
+    assert_eq!(status, reqwest::StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(health.status, "degraded");
+    assert!(!health.near_ai);
+    assert!(health.conversation_store);
+    assert!(!health.in_tee);
+}
+
+#[tokio::test]
+async fn test_live_without_dependencies() {
+    // Liveness never calls NEAR AI
+    let near_ai_server = mock_near_ai_server().await;
+    Mock::given(wiremock::matchers::any())
+        .respond_with(ResponseTemplate::new(500))
+        .expect(0)
+        .mount(&near_ai_server)
+        .await;
+    let addr = serve(&near_ai_server, false).await;
+
+    let response = reqwest::get(format!("http://{}/livez", addr)).await.unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::OK);
+    assert_eq!(response.text().await.unwrap(), "ok");
+}
+
+#[tokio::test]
+async fn test_health_alias_for_readiness() {
+    let near_ai_server = mock_near_ai_server().await;
+    forbid_completions(&near_ai_server).await;
+    Mock::given(method("GET"))
+        .and(path("/models"))
+        .respond_with(ResponseTemplate::new(503))
+        .mount(&near_ai_server)
+        .await;
+    let addr = serve(&near_ai_server, false).await;
+
+    let response = reqwest::get(format!("http://{}/health", addr)).await.unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::SERVICE_UNAVAILABLE);
+    let health: HealthResponse = response.json().await.unwrap();
+    assert!(!health.near_ai);
+}
@@ -0,0 +1,99 @@
+//! Integration tests for content moderation.
+
+mod common;
+
+use common::{mock_near_ai_server, test_near_ai_client};
+use signal_bot::config::{ModerationBackend, ModerationConfig};
+use signal_bot::moderation::{ContentFilter, KeywordModerator, Moderator, NearAiModerator};
+use std::sync::Arc;
+use wiremock::matchers::{body_string_contains, method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+async fn mock_verdict(server: &MockServer, trigger: &str, verdict: &str) {
+    Mock::given(method("POST"))
+        .and(path("/chat/completions"))
+        .and(body_string_contains(trigger))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "id": "chatcmpl-123",
+            "object": "chat.completion",
+            "created": 1677652288,
+            "model": "moderation-model",
+            "choices": [{
+                "index": 0,
+                "message": { "role": "assistant", "content": verdict },
+                "finish_reason": "stop"
+            }]
+        })))
+        .mount(server)
+        .await;
+}
+
+#[tokio::test]
+async fn test_keyword_moderator() {
+    let moderator = KeywordModerator::from_list("secret plan, Forbidden ,");
+
+    assert!(moderator.check("Here is the weather").await.allow);
+    let flagged = moderator.check("This is FORBIDDEN knowledge").await;
+    assert!(!flagged.allow);
+    assert!(flagged.reason.unwrap().contains("forbidden"));
+}
+
+#[tokio::test]
+async fn test_near_ai_moderator() {
+    let server = mock_near_ai_server().await;
+    mock_verdict(&server, "hello", "SAFE").await;
+    mock_verdict(&server, "nasty", "UNSAFE: harassment").await;
+    let moderator = NearAiModerator::new(test_near_ai_client(&server).with_model("moderation-model"));
+
+    assert!(moderator.check("hello there").await.allow);
+    let flagged = moderator.check("something nasty").await;
+    assert!(!flagged.allow);
+    assert_eq!(flagged.reason.as_deref(), Some("harassment"));
+
+    let requests = server.received_requests().await.unwrap();
+    let sent: serde_json::Value = requests[0].body_json().unwrap();
+    assert_eq!(sent["model"], "moderation-model");
+}
+
+#[tokio::test]
+async fn test_near_ai_moderator_fails_open() {
+    let server = mock_near_ai_server().await;
+    Mock::given(method("POST"))
+        .and(path("/chat/completions"))
+        .respond_with(ResponseTemplate::new(500))
+        .mount(&server)
+        .await;
+    let moderator = NearAiModerator::new(test_near_ai_client(&server));
+
+    assert!(moderator.check("anything").await.allow);
+}
+
+#[tokio::test]
+async fn test_filter_checks_configured_directions() {
+    let moderator = Arc::new(KeywordModerator::from_list("forbidden"));
+
+    let outgoing_only = ContentFilter::new(moderator.clone(), false, true, "Blocked.");
+    assert_eq!(outgoing_only.screen_incoming("forbidden question").await, None);
+    assert_eq!(outgoing_only.screen_outgoing("forbidden answer".to_string()).await, "Blocked.");
+    assert_eq!(outgoing_only.screen_outgoing("fine answer".to_string()).await, "fine answer");
+
+    let incoming_only = ContentFilter::new(moderator, true, false, "Blocked.");
+    assert_eq!(incoming_only.screen_incoming("forbidden question").await, Some("Blocked."));
+    assert_eq!(incoming_only.screen_outgoing("forbidden answer".to_string()).await, "forbidden answer");
+}
+
+#[tokio::test]
+async fn test_filter_disabled_by_default() {
+    let server = mock_near_ai_server().await;
+    let filter = ContentFilter::from_config(&ModerationConfig::default(), &test_near_ai_client(&server));
+    assert!(!filter.checks_outgoing());
+
+    let keywords = ModerationConfig {
+        backend: ModerationBackend::Keywords,
+        keywords: Some("forbidden".to_string()),
+        ..ModerationConfig::default()
+    };
+    let filter = ContentFilter::from_config(&keywords, &test_near_ai_client(&server));
+    assert!(filter.checks_outgoing());
+    assert_eq!(filter.screen_outgoing("forbidden".to_string()).await, "Sorry, I can't help with that.");
+}
@@ -3,7 +3,7 @@
 mod common;
 
 use common::{mock_near_ai_server, test_near_ai_client};
-use near_ai_client::Message;
+use near_ai_client::{ChatParams, Message};
 use wiremock::matchers::{header, method, path};
 use wiremock::{Mock, ResponseTemplate};
 
@@ -40,7 +40,7 @@ async fn test_near_ai_chat_integration() {
         .await;
 
     let messages = vec![Message::user("Hello from integration test")];
-    let result = client.chat(messages, None, None).await;
+    let result = client.chat(messages, ChatParams::default(), None, None).await;
 
     assert!(result.is_ok());
     assert_eq!(result.unwrap(), "Integration test response");
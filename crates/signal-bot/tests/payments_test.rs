@@ -0,0 +1,301 @@
+//! Integration tests for credit charging in chat.
+
+mod common;
+
+use common::{message, mock_near_ai_server, test_dstack_client, test_near_ai_client};
+use conversation_store::ConversationStore;
+use signal_bot::commands::{ChatHandler, CommandHandler};
+use signal_client::SignalClient;
+use std::sync::Arc;
+use std::time::Duration;
+use tempfile::TempDir;
+use tools::ToolRegistry;
+use wiremock::matchers::{body_string_contains, method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+use x402_payments::{calculate_credits, Chain, CreditStore, Deposit, PricingConfig, TokenUsage};
+
+const USER: &str = "+14155551234";
+
+/// Answer every completion as if it used far more tokens than the cap allows.
+async fn mock_expensive_completion(server: &MockServer) {
+    Mock::given(method("POST"))
+        .and(path("/chat/completions"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "id": "chatcmpl-123",
+            "object": "chat.completion",
+            "created": 1677652288,
+            "model": "test-model",
+            "choices": [{
+                "index": 0,
+                "message": { "role": "assistant", "content": "A very long answer" },
+                "finish_reason": "length"
+            }],
+            "usage": { "prompt_tokens": 100, "completion_tokens": 100000, "total_tokens": 100100 }
+        })))
+        .mount(server)
+        .await;
+}
+
+/// Chat handler charging a user holding `balance` credits, capped per message.
+async fn capped_chat(
+    near_ai_server: &MockServer,
+    balance: u64,
+    cap: u64,
+) -> (ChatHandler, Arc<CreditStore>, TempDir) {
+    let dir = TempDir::new().unwrap();
+    let store = CreditStore::new(test_dstack_client(), dir.path().join("credits.enc"))
+        .await
+        .unwrap();
+    let deposit = Deposit::new_pending(USER.to_string(), Chain::Base, "0xabc".to_string(), balance, balance);
+    store.add_credits(deposit).await.unwrap();
+
+    let pricing = PricingConfig {
+        max_credits_per_message: Some(cap),
+        ..Default::default()
+    };
+    let chat = ChatHandler::with_payments(
+        Arc::new(test_near_ai_client(near_ai_server)),
+        Arc::new(ConversationStore::new(50, Duration::from_secs(3600))),
+        Arc::new(SignalClient::new("http://127.0.0.1:9").unwrap()),
+        Arc::new(ToolRegistry::new()),
+        "You are a helpful assistant.".to_string(),
+        5,
+        None,
+        None,
+        store.clone(),
+        pricing,
+    );
+
+    (chat, store, dir)
+}
+
+#[tokio::test]
+async fn test_credit_cap_limits_tokens_and_charge() {
+    let near_ai_server = mock_near_ai_server().await;
+    mock_expensive_completion(&near_ai_server).await;
+    // Balance is below the cap, so the balance is the effective budget
+    let (chat, store, _dir) = capped_chat(&near_ai_server, 300, 1_000).await;
+
+    let response = chat.execute(&message("Tell me everything")).await.unwrap();
+    assert!(response.starts_with("A very long answer"));
+
+    // 300 credits buys at most 1000 completion tokens at the default price
+    let requests = near_ai_server.received_requests().await.unwrap();
+    let sent: serde_json::Value = requests[0].body_json().unwrap();
+    let max_tokens = sent["max_tokens"].as_u64().unwrap();
+    assert!(max_tokens > 0 && max_tokens < 1_000, "max_tokens was {}", max_tokens);
+
+    // The bill never exceeds the budget, whatever the model reports
+    let balance = store.get_balance(USER).await;
+    assert_eq!(balance.credits_remaining, 0);
+    assert_eq!(balance.total_consumed, 300);
+}
+
+#[tokio::test]
+async fn test_credit_cap_refuses_oversized_message() {
+    let near_ai_server = mock_near_ai_server().await;
+    mock_expensive_completion(&near_ai_server).await;
+    let (chat, store, _dir) = capped_chat(&near_ai_server, 1_000_000, 150).await;
+
+    let response = chat.execute(&message(&"word ".repeat(800))).await.unwrap();
+
+    assert!(response.contains("per-message limit"));
+    assert!(near_ai_server.received_requests().await.unwrap().is_empty());
+    assert_eq!(store.get_balance(USER).await.credits_remaining, 1_000_000);
+}
+
+#[tokio::test]
+async fn test_tool_turn_charged_once_for_summed_usage() {
+    let near_ai_server = mock_near_ai_server().await;
+    // Second call carries the tool result and answers
+    Mock::given(method("POST"))
+        .and(path("/chat/completions"))
+        .and(body_string_contains("\"role\":\"tool\""))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "id": "chatcmpl-2",
+            "object": "chat.completion",
+            "created": 1677652289,
+            "model": "test-model",
+            "choices": [{
+                "index": 0,
+                "message": { "role": "assistant", "content": "2 + 2 is 4." },
+                "finish_reason": "stop"
+            }],
+            "usage": { "prompt_tokens": 4000, "completion_tokens": 1000, "total_tokens": 5000 }
+        })))
+        .expect(1)
+        .mount(&near_ai_server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/chat/completions"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "id": "chatcmpl-1",
+            "object": "chat.completion",
+            "created": 1677652288,
+            "model": "test-model",
+            "choices": [{
+                "index": 0,
+                "message": {
+                    "role": "assistant",
+                    "content": null,
+                    "tool_calls": [{
+                        "id": "call_1",
+                        "type": "function",
+                        "function": { "name": "calculate", "arguments": "{\"expression\": \"2 + 2\"}" }
+                    }]
+                },
+                "finish_reason": "tool_calls"
+            }],
+            "usage": { "prompt_tokens": 3000, "completion_tokens": 500, "total_tokens": 3500 }
+        })))
+        .expect(1)
+        .mount(&near_ai_server)
+        .await;
+
+    // Accepts the "Using calculate..." progress message
+    let signal_server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v2/send"))
+        .respond_with(ResponseTemplate::new(201))
+        .mount(&signal_server)
+        .await;
+
+    let dir = TempDir::new().unwrap();
+    let store = CreditStore::new(test_dstack_client(), dir.path().join("credits.enc"))
+        .await
+        .unwrap();
+    let deposit = Deposit::new_pending(USER.to_string(), Chain::Base, "0xabc".to_string(), 1_000_000, 1_000_000);
+    store.add_credits(deposit).await.unwrap();
+
+    let mut tool_registry = ToolRegistry::new();
+    tool_registry.register(Arc::new(tools::builtin::CalculatorTool::new()));
+    let chat = ChatHandler::with_payments(
+        Arc::new(test_near_ai_client(&near_ai_server)),
+        Arc::new(ConversationStore::new(50, Duration::from_secs(3600))),
+        Arc::new(SignalClient::new(signal_server.uri()).unwrap()),
+        Arc::new(tool_registry),
+        "You are a helpful assistant.".to_string(),
+        5,
+        None,
+        None,
+        store.clone(),
+        PricingConfig::default(),
+    );
+
+    let response = chat.execute(&message("How much is 2+2?")).await.unwrap();
+    assert!(response.starts_with("2 + 2 is 4."));
+
+    // One record for the whole turn, with both calls' tokens
+    let usage = store.get_usage(USER).await;
+    assert_eq!(usage.len(), 1);
+    assert_eq!(usage[0].prompt_tokens, 7000);
+    assert_eq!(usage[0].completion_tokens, 1500);
+
+    let expected = calculate_credits(&TokenUsage::new(7000, 1500), &PricingConfig::default());
+    assert_eq!(usage[0].credits_consumed, expected);
+    let balance = store.get_balance(USER).await;
+    assert_eq!(balance.total_consumed, expected);
+    assert_eq!(balance.credits_remaining, 1_000_000 - expected);
+}
+
+#[tokio::test]
+async fn test_charged_at_selected_model_rates() {
+    let near_ai_server = mock_near_ai_server().await;
+    Mock::given(method("POST"))
+        .and(path("/chat/completions"))
+        .and(body_string_contains("\"model\":\"pricey-model\""))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "id": "chatcmpl-1",
+            "object": "chat.completion",
+            "created": 1677652288,
+            "model": "pricey-model",
+            "choices": [{
+                "index": 0,
+                "message": { "role": "assistant", "content": "Hello!" },
+                "finish_reason": "stop"
+            }],
+            "usage": { "prompt_tokens": 1000, "completion_tokens": 1000, "total_tokens": 2000 }
+        })))
+        .expect(1)
+        .mount(&near_ai_server)
+        .await;
+
+    let dir = TempDir::new().unwrap();
+    let store = CreditStore::new(test_dstack_client(), dir.path().join("credits.enc"))
+        .await
+        .unwrap();
+    let deposit = Deposit::new_pending(USER.to_string(), Chain::Base, "0xabc".to_string(), 1_000_000, 1_000_000);
+    store.add_credits(deposit).await.unwrap();
+
+    let conversations = Arc::new(ConversationStore::new(50, Duration::from_secs(3600)));
+    conversations.set_model_override(USER, "pricey-model").await;
+    let pricing = PricingConfig {
+        model_rates: Some("pricey-model=10000000:20000000".into()),
+        ..Default::default()
+    };
+    let chat = ChatHandler::with_payments(
+        Arc::new(test_near_ai_client(&near_ai_server)),
+        conversations,
+        Arc::new(SignalClient::new("http://127.0.0.1:9").unwrap()),
+        Arc::new(ToolRegistry::new()),
+        "You are a helpful assistant.".to_string(),
+        5,
+        None,
+        None,
+        store.clone(),
+        pricing,
+    );
+
+    let response = chat.execute(&message("Hi")).await.unwrap();
+    assert!(response.starts_with("Hello!"));
+
+    // 1000 tokens at 10 credits each plus 1000 at 20, not the default rates
+    assert_eq!(store.get_balance(USER).await.total_consumed, 30_000);
+}
+
+/// Whether any request so far asked the model for a history summary.
+async fn summary_requested(server: &MockServer) -> bool {
+    server
+        .received_requests()
+        .await
+        .unwrap()
+        .iter()
+        .any(|request| String::from_utf8_lossy(&request.body).contains("Summarize this excerpt"))
+}
+
+#[tokio::test]
+async fn test_history_summary_fits_credit_cap() {
+    // A 400-token summary costs ~130 credits at the default rates
+    for (cap, summarized) in [(100, false), (1_000, true)] {
+        let near_ai_server = mock_near_ai_server().await;
+        mock_expensive_completion(&near_ai_server).await;
+        let (chat, _store, _dir) = capped_chat(&near_ai_server, 1_000_000, cap).await;
+        let chat = chat.with_summarization(2, 2);
+
+        chat.execute(&message("first")).await.unwrap();
+        chat.execute(&message("second")).await.unwrap();
+
+        assert_eq!(summary_requested(&near_ai_server).await, summarized, "cap {}", cap);
+    }
+}
+
+#[tokio::test]
+async fn test_history_summary_leaves_newest_message() {
+    let near_ai_server = mock_near_ai_server().await;
+    mock_expensive_completion(&near_ai_server).await;
+    let (chat, _store, _dir) = capped_chat(&near_ai_server, 1_000_000, 1_000).await;
+    // A batch larger than the whole conversation
+    let chat = chat.with_summarization(2, 10);
+
+    chat.execute(&message("first")).await.unwrap();
+    chat.execute(&message("second")).await.unwrap();
+
+    let requests = near_ai_server.received_requests().await.unwrap();
+    let summary = requests
+        .iter()
+        .map(|request| String::from_utf8_lossy(&request.body).into_owned())
+        .find(|body| body.contains("Summarize this excerpt"))
+        .expect("history summarized");
+    assert!(summary.contains("user: first"));
+    assert!(!summary.contains("user: second"), "the question being answered isn't summarized");
+}
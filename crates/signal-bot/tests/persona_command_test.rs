@@ -0,0 +1,111 @@
+//! Integration tests for the `!persona` command.
+
+mod common;
+
+use common::{message, mock_near_ai_server, test_near_ai_client};
+use conversation_store::ConversationStore;
+use signal_bot::commands::{ChatHandler, CommandHandler, PersonaHandler};
+use signal_client::{BotMessage, SignalClient};
+use std::sync::Arc;
+use std::time::Duration;
+use tools::ToolRegistry;
+use wiremock::matchers::{body_string_contains, method, path};
+use wiremock::{Mock, ResponseTemplate};
+
+const USER: &str = "+14155551234";
+
+fn store() -> Arc<ConversationStore> {
+    Arc::new(ConversationStore::new(50, Duration::from_secs(3600)))
+}
+
+#[tokio::test]
+async fn test_persona_set_show_and_reset() {
+    let conversations = store();
+    let handler = PersonaHandler::new(conversations.clone());
+
+    let reply = handler.execute(&message("!persona")).await.unwrap();
+    assert!(reply.contains("No custom persona is set"));
+
+    let reply = handler.execute(&message("!persona Answer like a pirate.")).await.unwrap();
+    assert!(reply.contains("Persona set"));
+    assert_eq!(conversations.prompt_override(USER).await.as_deref(), Some("Answer like a pirate."));
+
+    let reply = handler.execute(&message("!persona")).await.unwrap();
+    assert!(reply.contains("Answer like a pirate."));
+
+    let reply = handler.execute(&message("!persona reset")).await.unwrap();
+    assert!(reply.contains("reset to the default"));
+    assert!(conversations.prompt_override(USER).await.is_none());
+}
+
+#[tokio::test]
+async fn test_persona_trigger_is_a_whole_word() {
+    let handler = PersonaHandler::new(store());
+
+    assert!(handler.matches(&message("!persona")));
+    assert!(handler.matches(&message("!persona Be brief")));
+    assert!(!handler.matches(&message("!personality")));
+    assert!(!handler.matches(&message("!persona!persona")));
+}
+
+#[tokio::test]
+async fn test_persona_length_limit() {
+    let conversations = store();
+    let handler = PersonaHandler::new(conversations.clone()).with_max_length(10);
+
+    let reply = handler.execute(&message("!persona Speak only in haiku")).await.unwrap();
+    assert!(reply.contains("too long (19 characters)"));
+    assert!(conversations.prompt_override(USER).await.is_none());
+}
+
+#[tokio::test]
+async fn test_persona_disabled_in_groups() {
+    let conversations = store();
+    let handler = PersonaHandler::new(conversations.clone());
+    let group_message = BotMessage {
+        is_group: true,
+        group_id: Some("group-1".to_string()),
+        ..message("!persona Be rude")
+    };
+
+    let reply = handler.execute(&group_message).await.unwrap();
+    assert!(reply.contains("only available in direct messages"));
+    assert!(conversations.prompt_override("group-1").await.is_none());
+}
+
+#[tokio::test]
+async fn test_chat_prepends_persona_to_system_prompt() {
+    let near_ai_server = mock_near_ai_server().await;
+    Mock::given(method("POST"))
+        .and(path("/chat/completions"))
+        .and(body_string_contains("Answer like a pirate.\\n\\nYou are a helpful assistant."))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "id": "chatcmpl-123",
+            "object": "chat.completion",
+            "created": 1677652288,
+            "model": "test-model",
+            "choices": [{
+                "index": 0,
+                "message": { "role": "assistant", "content": "Ahoy" },
+                "finish_reason": "stop"
+            }]
+        })))
+        .expect(1)
+        .mount(&near_ai_server)
+        .await;
+
+    let conversations = store();
+    conversations.set_prompt_override(USER, "Answer like a pirate.").await;
+    let chat_handler = ChatHandler::new(
+        Arc::new(test_near_ai_client(&near_ai_server)),
+        conversations,
+        Arc::new(SignalClient::new("http://127.0.0.1:9").unwrap()),
+        Arc::new(ToolRegistry::new()),
+        "You are a helpful assistant.".to_string(),
+        5,
+        None,
+        None,
+    );
+
+    assert_eq!(chat_handler.execute(&message("Hello")).await.unwrap(), "Ahoy");
+}
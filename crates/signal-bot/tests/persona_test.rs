@@ -0,0 +1,135 @@
+//! Integration tests for per-account bot personas.
+
+mod common;
+
+use common::{mock_near_ai_server, test_near_ai_client};
+use conversation_store::ConversationStore;
+use signal_bot::commands::{ChatHandler, ClearHandler, CommandHandler};
+use signal_bot::personas::{Persona, PersonaRegistry};
+use signal_client::{BotMessage, MessageKind, SignalClient};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tools::ToolRegistry;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+const PIRATE_BOT: &str = "+15550001111";
+const PLAIN_BOT: &str = "+15550002222";
+const USER: &str = "+14155551234";
+
+fn message_to(account: &str, text: &str) -> BotMessage {
+    BotMessage {
+        source: USER.to_string(),
+        text: text.to_string(),
+        timestamp: 1,
+        is_group: false,
+        group_id: None,
+        receiving_account: account.to_string(),
+        edit_target: None,
+        mentions: vec![],
+        kind: MessageKind::Text,
+    }
+}
+
+async fn mock_completion(server: &MockServer) {
+    Mock::given(method("POST"))
+        .and(path("/chat/completions"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "id": "chatcmpl-123",
+            "object": "chat.completion",
+            "created": 1677652288,
+            "model": "test-model",
+            "choices": [{
+                "index": 0,
+                "message": { "role": "assistant", "content": "Ahoy" },
+                "finish_reason": "stop"
+            }]
+        })))
+        .mount(server)
+        .await;
+}
+
+#[tokio::test]
+async fn test_registry_loads_personas_from_proxy() {
+    let proxy = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/v1/bots"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!([{
+            "username": "pirate.01",
+            "phone_number": PIRATE_BOT,
+            "signal_link": "https://signal.me/#eu/pirate.01",
+            "registered_at": "2025-01-01T00:00:00Z",
+            "model": "persona-model",
+            "description": "Talks like a pirate",
+            "system_prompt": "You are Pirate Bot.",
+            "identity_key": null
+        }])))
+        .mount(&proxy)
+        .await;
+
+    let registry = PersonaRegistry::new(proxy.uri());
+    assert_eq!(registry.refresh().await.unwrap(), 1);
+
+    let persona = registry.get(PIRATE_BOT).await.unwrap();
+    assert_eq!(persona.model.as_deref(), Some("persona-model"));
+    assert_eq!(persona.system_prompt.as_deref(), Some("You are Pirate Bot."));
+    assert!(registry.get(PLAIN_BOT).await.is_none());
+}
+
+#[tokio::test]
+async fn test_chat_answers_with_account_persona() {
+    let near_ai_server = mock_near_ai_server().await;
+    mock_completion(&near_ai_server).await;
+
+    let conversations = Arc::new(ConversationStore::new(50, Duration::from_secs(3600)));
+    let personas = Arc::new(PersonaRegistry::from_map(HashMap::from([(
+        PIRATE_BOT.to_string(),
+        Persona {
+            model: Some("persona-model".to_string()),
+            system_prompt: Some("You are Pirate Bot.".to_string()),
+        },
+    )])));
+
+    let chat_handler = ChatHandler::new(
+        Arc::new(test_near_ai_client(&near_ai_server)),
+        conversations.clone(),
+        Arc::new(SignalClient::new("http://127.0.0.1:9").unwrap()),
+        Arc::new(ToolRegistry::new()),
+        "You are a helpful assistant.".to_string(),
+        5,
+        None,
+        None,
+    )
+    .with_personas(personas);
+
+    chat_handler.execute(&message_to(PIRATE_BOT, "Hi")).await.unwrap();
+    chat_handler.execute(&message_to(PLAIN_BOT, "Hi")).await.unwrap();
+
+    let requests = near_ai_server.received_requests().await.unwrap();
+    let pirate: serde_json::Value = requests[0].body_json().unwrap();
+    let plain: serde_json::Value = requests[1].body_json().unwrap();
+
+    assert_eq!(pirate["model"], "persona-model");
+    assert!(pirate["messages"][0]["content"]
+        .as_str()
+        .unwrap()
+        .starts_with("You are Pirate Bot."));
+    assert_eq!(plain["model"], "test-model");
+    assert!(plain["messages"][0]["content"]
+        .as_str()
+        .unwrap()
+        .starts_with("You are a helpful assistant."));
+
+    // Each bot account keeps its own history with the user
+    let pirate_key = format!("{}:{}", PIRATE_BOT, USER);
+    let plain_key = format!("{}:{}", PLAIN_BOT, USER);
+    assert_eq!(conversations.message_count(&pirate_key).await.unwrap(), 2);
+    assert_eq!(conversations.message_count(&plain_key).await.unwrap(), 2);
+
+    // !clear only affects the account it was sent to
+    let clear_handler = ClearHandler::new(conversations.clone()).per_account();
+    clear_handler.execute(&message_to(PIRATE_BOT, "!clear")).await.unwrap();
+    assert_eq!(conversations.message_count(&pirate_key).await.unwrap(), 0);
+    assert_eq!(conversations.message_count(&plain_key).await.unwrap(), 2);
+}
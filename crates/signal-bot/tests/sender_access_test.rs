@@ -0,0 +1,42 @@
+//! Integration tests for the sender allow and block lists.
+
+use signal_bot::config::{BotConfig, SenderAccess};
+
+fn bot_config(allowed: Option<&str>, blocked: Option<&str>) -> BotConfig {
+    BotConfig {
+        allowed_senders: allowed.map(str::to_string),
+        blocked_senders: blocked.map(str::to_string),
+        ..BotConfig::default()
+    }
+}
+
+#[test]
+fn test_everyone_allowed_by_default() {
+    let config = BotConfig::default();
+    assert_eq!(config.sender_access("+14155551234"), SenderAccess::Allowed);
+}
+
+#[test]
+fn test_allowlist_with_wildcards() {
+    let config = bot_config(Some("+14155551234, +1628*"), None);
+
+    assert_eq!(config.sender_access("+14155551234"), SenderAccess::Allowed);
+    assert_eq!(config.sender_access("+16285550000"), SenderAccess::Allowed);
+    assert_eq!(config.sender_access("+14155559999"), SenderAccess::NotAllowed);
+    assert_eq!(config.sender_access("+1415555123"), SenderAccess::NotAllowed);
+}
+
+#[test]
+fn test_blocklist_wins_over_allowlist() {
+    let config = bot_config(Some("+1*"), Some("+1555*0000"));
+
+    assert_eq!(config.sender_access("+15551230000"), SenderAccess::Blocked);
+    assert_eq!(config.sender_access("+15551230001"), SenderAccess::Allowed);
+    assert_eq!(config.sender_access("+445551230000"), SenderAccess::NotAllowed);
+}
+
+#[test]
+fn test_empty_allowlist_serves_everyone() {
+    let config = bot_config(Some(""), Some(""));
+    assert_eq!(config.sender_access("+14155551234"), SenderAccess::Allowed);
+}
@@ -0,0 +1,46 @@
+//! Integration tests for the `!tools` command.
+
+mod common;
+
+use common::message;
+use signal_bot::commands::{CommandHandler, ToolsHandler};
+use std::sync::Arc;
+use tools::builtin::{CalculatorTool, WeatherTool};
+use tools::ToolRegistry;
+
+#[tokio::test]
+async fn test_tools_lists_enabled_tools_with_summaries() {
+    let mut registry = ToolRegistry::new();
+    registry.register(Arc::new(WeatherTool::new()));
+    registry.register(Arc::new(CalculatorTool::new()));
+    let handler = ToolsHandler::new(Arc::new(registry));
+
+    assert!(handler.matches(&message("!tools")));
+    let reply = handler.execute(&message("!tools")).await.unwrap();
+
+    // Sorted by name, one line each with the first sentence of the description
+    let calc = reply.find("- calculate - Evaluate mathematical expressions.\n").unwrap();
+    let weather = reply.find("- get_weather - Get current weather for a location.").unwrap();
+    assert!(calc < weather);
+    assert!(!reply.contains("Supports basic arithmetic"));
+}
+
+#[tokio::test]
+async fn test_tools_skips_disabled_tools() {
+    let mut registry = ToolRegistry::new();
+    registry.register(Arc::new(WeatherTool::new()));
+    registry.register(Arc::new(CalculatorTool::new()));
+    registry.disable("get_weather");
+    let handler = ToolsHandler::new(Arc::new(registry));
+
+    let reply = handler.execute(&message("!tools")).await.unwrap();
+    assert!(reply.contains("calculate"));
+    assert!(!reply.contains("get_weather"));
+}
+
+#[tokio::test]
+async fn test_tools_with_empty_registry() {
+    let handler = ToolsHandler::new(Arc::new(ToolRegistry::new()));
+    let reply = handler.execute(&message("!tools")).await.unwrap();
+    assert!(reply.contains("No tools are enabled"));
+}
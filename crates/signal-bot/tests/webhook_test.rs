@@ -0,0 +1,136 @@
+//! Integration tests for the Signal receive webhook.
+
+use signal_bot::config::SignalConfig;
+use signal_bot::webhook::{create_router, webhook_addr, WebhookState};
+use signal_client::BotMessage;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+/// Serve the webhook on an ephemeral port, returning its URL and message queue.
+async fn start_webhook(token: Option<&str>) -> (String, mpsc::Receiver<BotMessage>) {
+    let (tx, rx) = mpsc::channel(10);
+    let state = Arc::new(WebhookState {
+        messages: tx,
+        token: token.map(|t| t.to_string().into()),
+    });
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, create_router(state)).await.unwrap();
+    });
+
+    (format!("http://{}/v1/receive", addr), rx)
+}
+
+fn text_payload(text: &str) -> serde_json::Value {
+    serde_json::json!({
+        "envelope": {
+            "source": "+14155551234",
+            "sourceNumber": "+14155551234",
+            "timestamp": 1700000000000i64,
+            "dataMessage": {
+                "timestamp": 1700000000000i64,
+                "message": text
+            }
+        },
+        "account": "+15555555555"
+    })
+}
+
+#[tokio::test]
+async fn test_webhook_forwards_text_messages() {
+    let (url, mut messages) = start_webhook(None).await;
+
+    let response = reqwest::Client::new()
+        .post(&url)
+        .json(&text_payload("Hello via push"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+
+    let message = tokio::time::timeout(Duration::from_secs(1), messages.recv())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(message.text, "Hello via push");
+    assert_eq!(message.source, "+14155551234");
+    assert_eq!(message.receiving_account, "+15555555555");
+}
+
+#[tokio::test]
+async fn test_webhook_acknowledges_non_text_envelopes() {
+    let (url, mut messages) = start_webhook(None).await;
+
+    let receipt = serde_json::json!({
+        "envelope": {
+            "source": "+14155551234",
+            "timestamp": 1700000000000i64,
+            "receiptMessage": { "isDelivery": true, "timestamps": [1700000000000i64] }
+        },
+        "account": "+15555555555"
+    });
+    let response = reqwest::Client::new().post(&url).json(&receipt).send().await.unwrap();
+    assert_eq!(response.status(), 200);
+    assert!(messages.try_recv().is_err());
+}
+
+#[tokio::test]
+async fn test_webhook_requires_token_when_configured() {
+    let (url, mut messages) = start_webhook(Some("secret")).await;
+    let client = reqwest::Client::new();
+
+    let response = client.post(&url).json(&text_payload("Hi")).send().await.unwrap();
+    assert_eq!(response.status(), 401);
+
+    let response = client
+        .post(&url)
+        .bearer_auth("wrong")
+        .json(&text_payload("Hi"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 401);
+
+    // Tokens in the query string would end up in access logs; not accepted
+    let response = client
+        .post(format!("{}?token=secret", url))
+        .json(&text_payload("Hi"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 401);
+    assert!(messages.try_recv().is_err());
+
+    let response = client
+        .post(&url)
+        .bearer_auth("secret")
+        .json(&text_payload("Bearer"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+    assert_eq!(messages.recv().await.unwrap().text, "Bearer");
+
+    // Credentials embedded in the webhook URL arrive as Basic auth
+    let response = client
+        .post(&url)
+        .basic_auth("bot", Some("secret"))
+        .json(&text_payload("Basic"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+    assert_eq!(messages.recv().await.unwrap().text, "Basic");
+}
+
+#[test]
+fn test_webhook_listens_on_loopback_without_token() {
+    let mut config = SignalConfig::default();
+    assert!(webhook_addr(&config).ip().is_loopback());
+
+    config.webhook_token = Some("secret".to_string().into());
+    assert!(webhook_addr(&config).ip().is_unspecified());
+}
@@ -1,8 +1,11 @@
 //! Signal HTTP client.
 
 use crate::error::SignalError;
+use crate::retry::{CircuitBreaker, RetryPolicy};
+use crate::split::{split_for_signal, MAX_MESSAGE_LEN};
 use crate::types::*;
 use reqwest::Client;
+use std::sync::Arc;
 use std::time::Duration;
 use tracing::{debug, instrument, warn};
 use urlencoding::encode;
@@ -14,11 +17,32 @@ use urlencoding::encode;
 pub struct SignalClient {
     client: Client,
     base_url: String,
+    retry: RetryPolicy,
+    breaker: Arc<CircuitBreaker>,
+}
+
+/// Outcome of a single send attempt.
+enum SendAttempt {
+    Sent(Option<i64>),
+    /// Transient failure (5xx, refused connection) worth retrying
+    Retryable(SignalError),
+    /// Timed out: the API may still have sent the message, so retrying
+    /// could deliver it twice. Counts towards the circuit breaker.
+    TimedOut(SignalError),
+    Failed(SignalError),
 }
 
 impl SignalClient {
-    /// Create a new Signal client.
+    /// Create a new Signal client with the default [`RetryPolicy`].
     pub fn new(base_url: impl Into<String>) -> Result<Self, SignalError> {
+        Self::new_with_retry_policy(base_url, RetryPolicy::default())
+    }
+
+    /// Create a new Signal client that retries sends according to `retry`.
+    pub fn new_with_retry_policy(
+        base_url: impl Into<String>,
+        retry: RetryPolicy,
+    ) -> Result<Self, SignalError> {
         let client = Client::builder()
             .timeout(Duration::from_secs(30))
             .build()?;
@@ -26,9 +50,18 @@ impl SignalClient {
         Ok(Self {
             client,
             base_url: base_url.into(),
+            breaker: Arc::new(CircuitBreaker::new(&retry)),
+            retry,
         })
     }
 
+    /// Replace the HTTP client with one using a shorter request timeout.
+    #[cfg(test)]
+    pub(crate) fn with_request_timeout(mut self, timeout: Duration) -> Self {
+        self.client = Client::builder().timeout(timeout).build().unwrap();
+        self
+    }
+
     /// List all registered accounts.
     #[instrument(skip(self))]
     pub async fn list_accounts(&self) -> Result<Vec<String>, SignalError> {
@@ -77,6 +110,9 @@ impl SignalClient {
     }
 
     /// Receive pending messages for a specific phone number.
+    ///
+    /// An empty list means the poll succeeded with nothing pending; error
+    /// statuses and bodies that aren't a message list are returned as errors.
     #[instrument(skip(self))]
     pub async fn receive(&self, phone_number: &str) -> Result<Vec<IncomingMessage>, SignalError> {
         let encoded_number = encode(phone_number);
@@ -89,51 +125,315 @@ impl SignalClient {
             .send()
             .await?;
 
-        if !response.status().is_success() {
-            let msg = response.text().await.unwrap_or_default();
-            return Err(SignalError::Api(msg));
+        let status = response.status();
+        let body = response.text().await?;
+        if !status.is_success() {
+            return Err(SignalError::Api(format!("receive returned {}: {}", status, body.trim())));
         }
 
-        let messages: Vec<IncomingMessage> = response.json().await?;
+        // Nothing pending can come back as an empty body rather than `[]`
+        if body.trim().is_empty() {
+            return Ok(Vec::new());
+        }
+        // Parse envelopes one by one so a single unexpected shape doesn't
+        // drop the rest of the batch
+        let envelopes: Vec<serde_json::Value> = serde_json::from_str(&body)?;
+        let messages: Vec<IncomingMessage> = envelopes
+            .into_iter()
+            .filter_map(|envelope| match serde_json::from_value(envelope) {
+                Ok(message) => Some(message),
+                Err(e) => {
+                    warn!("Skipping unparseable envelope for {}: {}", phone_number, e);
+                    None
+                }
+            })
+            .collect();
         debug!("Received {} messages for {}", messages.len(), phone_number);
         Ok(messages)
     }
 
     /// Send a message from a specific account to a recipient.
+    ///
+    /// Returns the sent message's timestamp, which identifies it for later
+    /// edits, when the API reports one. Transient failures are retried per
+    /// the client's [`RetryPolicy`].
     #[instrument(skip(self, message))]
     pub async fn send(
         &self,
         from_number: &str,
         recipient: &str,
         message: &str,
-    ) -> Result<(), SignalError> {
+    ) -> Result<Option<i64>, SignalError> {
+        let sent = self.post_message(from_number, recipient, message, None).await?;
+        debug!("Sent message from {} to {}", from_number, recipient);
+        Ok(sent)
+    }
+
+    /// Replace the text of a message this account sent earlier.
+    ///
+    /// `target_timestamp` is the timestamp returned by [`SignalClient::send`].
+    #[instrument(skip(self, new_text))]
+    pub async fn edit_message(
+        &self,
+        from_number: &str,
+        recipient: &str,
+        target_timestamp: i64,
+        new_text: &str,
+    ) -> Result<Option<i64>, SignalError> {
+        let sent = self
+            .post_message(from_number, recipient, new_text, Some(target_timestamp))
+            .await?;
+        debug!("Edited message {} from {} to {}", target_timestamp, from_number, recipient);
+        Ok(sent)
+    }
+
+    async fn post_message(
+        &self,
+        from_number: &str,
+        recipient: &str,
+        message: &str,
+        edit_timestamp: Option<i64>,
+    ) -> Result<Option<i64>, SignalError> {
+        if !self.breaker.allow() {
+            return Err(SignalError::CircuitOpen);
+        }
+
         let request = SendMessageRequest {
             message: message.to_string(),
             number: Some(from_number.to_string()),
             recipients: Some(vec![recipient.to_string()]),
+            edit_timestamp,
         };
 
-        let response = self
+        let mut attempt = 1;
+        loop {
+            match self.try_post_message(&request).await {
+                SendAttempt::Sent(timestamp) => {
+                    self.breaker.record_success();
+                    return Ok(timestamp);
+                }
+                SendAttempt::Retryable(e) if attempt < self.retry.max_attempts => {
+                    let delay = self.retry.backoff(attempt);
+                    warn!(
+                        "Send attempt {}/{} failed, retrying in {:?}: {}",
+                        attempt, self.retry.max_attempts, delay, e
+                    );
+                    tokio::time::sleep(delay).await;
+                    attempt += 1;
+                }
+                SendAttempt::Retryable(e) | SendAttempt::TimedOut(e) => {
+                    if self.breaker.record_failure() {
+                        warn!(
+                            "Signal API failing repeatedly, pausing sends for {:?}",
+                            self.retry.breaker_cooldown
+                        );
+                    }
+                    return Err(e);
+                }
+                SendAttempt::Failed(e) => return Err(e),
+            }
+        }
+    }
+
+    async fn try_post_message(&self, request: &SendMessageRequest) -> SendAttempt {
+        let response = match self
             .client
             .post(format!("{}/v2/send", self.base_url))
+            .json(request)
+            .send()
+            .await
+        {
+            Ok(response) => response,
+            Err(e) if e.is_connect() => return SendAttempt::Retryable(e.into()),
+            Err(e) if e.is_timeout() => return SendAttempt::TimedOut(e.into()),
+            Err(e) => return SendAttempt::Failed(e.into()),
+        };
+
+        let status = response.status();
+        if !status.is_success() {
+            let msg = response.text().await.unwrap_or_default();
+            warn!("Send failed ({}): {}", status, msg);
+            let error = SignalError::SendFailed(msg);
+            return if status.is_server_error() {
+                SendAttempt::Retryable(error)
+            } else {
+                SendAttempt::Failed(error)
+            };
+        }
+
+        let body = response.text().await.unwrap_or_default();
+        SendAttempt::Sent(
+            serde_json::from_str::<SendMessageResponse>(&body)
+                .ok()
+                .and_then(|r| r.timestamp),
+        )
+    }
+
+    /// Create a group owned by `account` with the given members.
+    #[instrument(skip(self, members))]
+    pub async fn create_group(
+        &self,
+        account: &str,
+        name: &str,
+        members: &[String],
+    ) -> Result<GroupId, SignalError> {
+        let request = CreateGroupRequest {
+            name: name.to_string(),
+            members: members.to_vec(),
+        };
+        let response = self
+            .client
+            .post(format!("{}/v1/groups/{}", self.base_url, encode(account)))
             .json(&request)
             .send()
             .await?;
 
-        if !response.status().is_success() {
+        let response = Self::check_group_response("create group", response).await?;
+        let created: CreateGroupResponse = response.json().await?;
+        debug!("Created group {} with {} members", created.id, members.len());
+        Ok(created.id)
+    }
+
+    /// Add members to a group `account` administers.
+    #[instrument(skip(self, members))]
+    pub async fn add_members(
+        &self,
+        account: &str,
+        group: &GroupId,
+        members: &[String],
+    ) -> Result<(), SignalError> {
+        self.update_members(reqwest::Method::POST, account, group, members)
+            .await?;
+        debug!("Added {} members to group {}", members.len(), group);
+        Ok(())
+    }
+
+    /// Remove members from a group `account` administers.
+    #[instrument(skip(self, members))]
+    pub async fn remove_members(
+        &self,
+        account: &str,
+        group: &GroupId,
+        members: &[String],
+    ) -> Result<(), SignalError> {
+        self.update_members(reqwest::Method::DELETE, account, group, members)
+            .await?;
+        debug!("Removed {} members from group {}", members.len(), group);
+        Ok(())
+    }
+
+    async fn update_members(
+        &self,
+        method: reqwest::Method,
+        account: &str,
+        group: &GroupId,
+        members: &[String],
+    ) -> Result<(), SignalError> {
+        let request = GroupMembersRequest {
+            members: members.to_vec(),
+        };
+        let response = self
+            .client
+            .request(
+                method,
+                format!(
+                    "{}/v1/groups/{}/{}/members",
+                    self.base_url,
+                    encode(account),
+                    encode(group.as_str())
+                ),
+            )
+            .json(&request)
+            .send()
+            .await?;
+
+        Self::check_group_response("update group members", response).await?;
+        Ok(())
+    }
+
+    /// Turn an error status from a group endpoint into
+    /// [`SignalError::GroupOperationFailed`].
+    async fn check_group_response(
+        operation: &str,
+        response: reqwest::Response,
+    ) -> Result<reqwest::Response, SignalError> {
+        let status = response.status();
+        if status.is_success() {
+            return Ok(response);
+        }
+        let msg = response.text().await.unwrap_or_default();
+        warn!("Failed to {} ({}): {}", operation, status, msg);
+        Err(SignalError::GroupOperationFailed(format!(
+            "{} returned {}: {}",
+            operation,
+            status,
+            msg.trim()
+        )))
+    }
+
+    /// Set the disappearing-message timer of `account`'s conversation with
+    /// `recipient`. `seconds` of 0 turns disappearing messages off.
+    ///
+    /// Uses `PUT /v1/contacts/{account}` with `expiration_in_seconds`, which
+    /// needs signal-cli-rest-api 0.57 or newer; older servers without the
+    /// endpoint yield [`SignalError::Unsupported`].
+    #[instrument(skip(self))]
+    pub async fn set_expiration(
+        &self,
+        account: &str,
+        recipient: &str,
+        seconds: u32,
+    ) -> Result<(), SignalError> {
+        let request = UpdateContactRequest {
+            recipient: recipient.to_string(),
+            expiration_in_seconds: seconds,
+        };
+        let response = self
+            .client
+            .put(format!("{}/v1/contacts/{}", self.base_url, encode(account)))
+            .json(&request)
+            .send()
+            .await?;
+
+        let status = response.status();
+        if !status.is_success() {
             let msg = response.text().await.unwrap_or_default();
-            warn!("Send failed: {}", msg);
-            return Err(SignalError::SendFailed(msg));
+            warn!("Failed to set expiration ({}): {}", status, msg);
+            // Servers without the endpoint don't route it at all
+            return Err(match status {
+                reqwest::StatusCode::NOT_FOUND
+                | reqwest::StatusCode::METHOD_NOT_ALLOWED
+                | reqwest::StatusCode::NOT_IMPLEMENTED => SignalError::Unsupported(
+                    "disappearing messages need signal-cli-rest-api 0.57 or newer (PUT /v1/contacts/{number})".into(),
+                ),
+                _ => SignalError::Api(format!("set expiration returned {}: {}", status, msg.trim())),
+            });
         }
 
-        debug!("Sent message from {} to {}", from_number, recipient);
+        debug!("Set expiration for {} to {}s", recipient, seconds);
         Ok(())
     }
 
     /// Reply to a message (handles both direct and group messages).
     /// Uses the receiving account to send the reply.
-    pub async fn reply(&self, original: &BotMessage, message: &str) -> Result<(), SignalError> {
-        self.send(&original.receiving_account, original.reply_target(), message)
-            .await
+    ///
+    /// Replies longer than [`MAX_MESSAGE_LEN`] are sent as several messages
+    /// (see [`split_for_signal`]); the first part's timestamp is returned.
+    pub async fn reply(&self, original: &BotMessage, message: &str) -> Result<Option<i64>, SignalError> {
+        let parts = split_for_signal(message, MAX_MESSAGE_LEN);
+        if parts.len() > 1 {
+            debug!("Splitting reply into {} parts", parts.len());
+        }
+
+        let mut first = None;
+        for (i, part) in parts.iter().enumerate() {
+            let sent = self
+                .send(&original.receiving_account, original.reply_target(), part)
+                .await?;
+            if i == 0 {
+                first = sent;
+            }
+        }
+        Ok(first)
     }
 }
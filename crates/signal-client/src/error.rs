@@ -18,4 +18,13 @@ pub enum SignalError {
 
     #[error("Send failed: {0}")]
     SendFailed(String),
+
+    #[error("Signal API unavailable after repeated failures, not sending")]
+    CircuitOpen,
+
+    #[error("Group operation failed: {0}")]
+    GroupOperationFailed(String),
+
+    #[error("Not supported by this Signal API server: {0}")]
+    Unsupported(String),
 }
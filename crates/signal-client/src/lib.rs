@@ -3,23 +3,38 @@
 mod client;
 mod error;
 mod receiver;
+mod retry;
+mod split;
 mod types;
 
 pub use client::SignalClient;
 pub use error::SignalError;
 pub use receiver::MessageReceiver;
+pub use retry::RetryPolicy;
+pub use split::{split_for_signal, MAX_MESSAGE_LEN};
 pub use types::*;
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use wiremock::matchers::{method, path};
+    use std::time::Duration;
+    use wiremock::matchers::{body_partial_json, method, path};
     use wiremock::{Mock, MockServer, ResponseTemplate};
 
     async fn create_test_client(mock_server: &MockServer) -> SignalClient {
         SignalClient::new(mock_server.uri()).unwrap()
     }
 
+    fn fast_retry_policy() -> RetryPolicy {
+        RetryPolicy {
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(1),
+            max_backoff: Duration::from_millis(5),
+            breaker_threshold: 2,
+            breaker_cooldown: Duration::from_secs(60),
+        }
+    }
+
     #[tokio::test]
     async fn test_health_check_success() {
         let mock_server = MockServer::start().await;
@@ -106,6 +121,101 @@ mod tests {
         assert_eq!(msgs[0].envelope.source, "+14155551234");
     }
 
+    #[tokio::test]
+    async fn test_receive_empty_is_ok() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(path("/v1/receive/%2B15555555555"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("[]"))
+            .mount(&mock_server)
+            .await;
+
+        let client = create_test_client(&mock_server).await;
+        assert!(client.receive("+15555555555").await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_receive_server_error() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(path("/v1/receive/%2B15555555555"))
+            .respond_with(ResponseTemplate::new(500).set_body_string("account is being linked"))
+            .mount(&mock_server)
+            .await;
+
+        let client = create_test_client(&mock_server).await;
+        let result = client.receive("+15555555555").await;
+
+        assert!(
+            matches!(result, Err(SignalError::Api(ref msg)) if msg.contains("500") && msg.contains("being linked"))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_receive_malformed_body() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(path("/v1/receive/%2B15555555555"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("{\"error\": \"not a list\"}"))
+            .mount(&mock_server)
+            .await;
+
+        let client = create_test_client(&mock_server).await;
+        let result = client.receive("+15555555555").await;
+
+        assert!(matches!(result, Err(SignalError::Json(_))));
+    }
+
+    #[tokio::test]
+    async fn test_receive_skips_unparseable_envelopes() {
+        let mock_server = MockServer::start().await;
+
+        let messages = serde_json::json!([
+            { "envelope": { "timestamp": "not a number" }, "account": "+15555555555" },
+            {
+                "envelope": {
+                    "source": "+14155551234",
+                    "timestamp": 1677652289000i64,
+                    "dataMessage": {
+                        "timestamp": 1677652289000i64,
+                        "reaction": { "emoji": "👍", "targetAuthorUuid": "bot-uuid", "targetSentTimestamp": 1 }
+                    }
+                },
+                "account": "+15555555555"
+            },
+            {
+                "envelope": {
+                    "source": "+14155551234",
+                    "timestamp": 1677652290000i64,
+                    "dataMessage": { "message": "Still here", "timestamp": 1677652290000i64 }
+                },
+                "account": "+15555555555"
+            }
+        ]);
+
+        Mock::given(method("GET"))
+            .and(path("/v1/receive/%2B15555555555"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(&messages))
+            .mount(&mock_server)
+            .await;
+
+        let client = create_test_client(&mock_server).await;
+        let msgs = client.receive("+15555555555").await.unwrap();
+
+        // The broken envelope is dropped; a reaction missing `targetAuthor` still parses
+        assert_eq!(msgs.len(), 2);
+        let reaction = BotMessage::from_incoming(&msgs[0]).unwrap();
+        let MessageKind::Reaction(reaction) = reaction.kind else {
+            panic!("expected a reaction, got {:?}", reaction.kind);
+        };
+        assert_eq!(reaction.emoji, "👍");
+        assert_eq!(reaction.target_author, "");
+        assert_eq!(BotMessage::from_incoming(&msgs[1]).unwrap().text, "Still here");
+    }
+
     #[tokio::test]
     async fn test_send_message() {
         let mock_server = MockServer::start().await;
@@ -121,7 +231,32 @@ mod tests {
         let client = create_test_client(&mock_server).await;
         let result = client.send("+15555555555", "+14155551234", "Hello!").await;
 
-        assert!(result.is_ok());
+        assert_eq!(result.unwrap(), Some(1677652288000));
+    }
+
+    #[tokio::test]
+    async fn test_edit_message() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("POST"))
+            .and(path("/v2/send"))
+            .and(body_partial_json(serde_json::json!({
+                "message": "Hello, edited!",
+                "edit_timestamp": 1677652288000i64
+            })))
+            .respond_with(ResponseTemplate::new(201).set_body_json(serde_json::json!({
+                "timestamp": "1677652299000"
+            })))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let client = create_test_client(&mock_server).await;
+        let result = client
+            .edit_message("+15555555555", "+14155551234", 1677652288000, "Hello, edited!")
+            .await;
+
+        assert_eq!(result.unwrap(), Some(1677652299000));
     }
 
     #[tokio::test]
@@ -141,6 +276,265 @@ mod tests {
         assert!(matches!(result, Err(SignalError::SendFailed(_))));
     }
 
+    #[tokio::test]
+    async fn test_send_retries_server_errors() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("POST"))
+            .and(path("/v2/send"))
+            .respond_with(ResponseTemplate::new(503).set_body_string("Unavailable"))
+            .up_to_n_times(2)
+            .expect(2)
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/v2/send"))
+            .respond_with(ResponseTemplate::new(201).set_body_json(serde_json::json!({
+                "timestamp": 1677652288000i64
+            })))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let client =
+            SignalClient::new_with_retry_policy(mock_server.uri(), fast_retry_policy()).unwrap();
+        let result = client.send("+15555555555", "+14155551234", "Hello!").await;
+
+        assert_eq!(result.unwrap(), Some(1677652288000));
+    }
+
+    #[tokio::test]
+    async fn test_send_does_not_retry_client_errors() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("POST"))
+            .and(path("/v2/send"))
+            .respond_with(ResponseTemplate::new(400).set_body_string("Invalid recipient"))
+            .expect(3)
+            .mount(&mock_server)
+            .await;
+
+        // One attempt per send, and client errors never open the circuit
+        let client =
+            SignalClient::new_with_retry_policy(mock_server.uri(), fast_retry_policy()).unwrap();
+        for _ in 0..3 {
+            let result = client.send("+15555555555", "+14155551234", "Hello!").await;
+            assert!(matches!(result, Err(SignalError::SendFailed(_))));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_send_does_not_retry_timeouts() {
+        let mock_server = MockServer::start().await;
+
+        // The API may have sent the message before the response was lost
+        Mock::given(method("POST"))
+            .and(path("/v2/send"))
+            .respond_with(ResponseTemplate::new(201).set_delay(Duration::from_millis(500)))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let client = SignalClient::new_with_retry_policy(mock_server.uri(), fast_retry_policy())
+            .unwrap()
+            .with_request_timeout(Duration::from_millis(50));
+        let result = client.send("+15555555555", "+14155551234", "Hello!").await;
+
+        assert!(matches!(result, Err(SignalError::Http(_))));
+    }
+
+    #[tokio::test]
+    async fn test_send_circuit_opens_after_repeated_failures() {
+        let mock_server = MockServer::start().await;
+
+        // Two sends of three attempts each, then the circuit is open
+        Mock::given(method("POST"))
+            .and(path("/v2/send"))
+            .respond_with(ResponseTemplate::new(500))
+            .expect(6)
+            .mount(&mock_server)
+            .await;
+
+        let client =
+            SignalClient::new_with_retry_policy(mock_server.uri(), fast_retry_policy()).unwrap();
+        let original = BotMessage {
+            source: "+14155551234".into(),
+            text: "Hi".into(),
+            timestamp: 1,
+            is_group: false,
+            group_id: None,
+            receiving_account: "+15555555555".into(),
+            edit_target: None,
+            mentions: vec![],
+            kind: MessageKind::Text,
+        };
+
+        for _ in 0..2 {
+            let result = client.reply(&original, "Hello!").await;
+            assert!(matches!(result, Err(SignalError::SendFailed(_))));
+        }
+
+        // Clones share the breaker
+        let result = client.clone().reply(&original, "Hello!").await;
+        assert!(matches!(result, Err(SignalError::CircuitOpen)));
+    }
+
+    #[tokio::test]
+    async fn test_reply_splits_long_messages() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("POST"))
+            .and(path("/v2/send"))
+            .respond_with(ResponseTemplate::new(201).set_body_json(serde_json::json!({
+                "timestamp": 1677652288000i64
+            })))
+            .expect(2)
+            .mount(&mock_server)
+            .await;
+
+        let client = create_test_client(&mock_server).await;
+        let original = BotMessage {
+            source: "+14155551234".into(),
+            text: "Hi".into(),
+            timestamp: 1,
+            is_group: false,
+            group_id: None,
+            receiving_account: "+15555555555".into(),
+            edit_target: None,
+            mentions: vec![],
+            kind: MessageKind::Text,
+        };
+        let long = "word ".repeat(MAX_MESSAGE_LEN / 5 + 10);
+
+        let result = client.reply(&original, &long).await;
+        assert_eq!(result.unwrap(), Some(1677652288000));
+
+        let sent: Vec<String> = mock_server
+            .received_requests()
+            .await
+            .unwrap()
+            .iter()
+            .map(|r| serde_json::from_slice::<serde_json::Value>(&r.body).unwrap()["message"]
+                .as_str()
+                .unwrap()
+                .to_string())
+            .collect();
+        assert!(sent.iter().all(|m| m.chars().count() <= MAX_MESSAGE_LEN));
+        assert_eq!(sent.join(" "), long.trim_end());
+    }
+
+    #[tokio::test]
+    async fn test_create_group() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("POST"))
+            .and(path("/v1/groups/%2B15555555555"))
+            .and(body_partial_json(serde_json::json!({
+                "name": "Support",
+                "members": ["+14155551234"]
+            })))
+            .respond_with(ResponseTemplate::new(201).set_body_json(serde_json::json!({
+                "id": "group.c3VwcG9ydA=="
+            })))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let client = create_test_client(&mock_server).await;
+        let group = client
+            .create_group("+15555555555", "Support", &["+14155551234".to_string()])
+            .await
+            .unwrap();
+
+        assert_eq!(group, GroupId("group.c3VwcG9ydA==".into()));
+    }
+
+    #[tokio::test]
+    async fn test_add_and_remove_members() {
+        let mock_server = MockServer::start().await;
+
+        for verb in ["POST", "DELETE"] {
+            Mock::given(method(verb))
+                .and(path("/v1/groups/%2B15555555555/group.abc123/members"))
+                .and(body_partial_json(serde_json::json!({ "members": ["+14155551234"] })))
+                .respond_with(ResponseTemplate::new(204))
+                .expect(1)
+                .mount(&mock_server)
+                .await;
+        }
+
+        let client = create_test_client(&mock_server).await;
+        let group = GroupId("group.abc123".into());
+        let members = ["+14155551234".to_string()];
+
+        client.add_members("+15555555555", &group, &members).await.unwrap();
+        client.remove_members("+15555555555", &group, &members).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_group_operation_failure() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("POST"))
+            .and(path("/v1/groups/%2B15555555555/group.abc123/members"))
+            .respond_with(
+                ResponseTemplate::new(400).set_body_string("{\"error\":\"Group not found\"}"),
+            )
+            .mount(&mock_server)
+            .await;
+
+        let client = create_test_client(&mock_server).await;
+        let result = client
+            .add_members(
+                "+15555555555",
+                &GroupId("group.abc123".into()),
+                &["+14155551234".to_string()],
+            )
+            .await;
+
+        assert!(
+            matches!(result, Err(SignalError::GroupOperationFailed(ref msg)) if msg.contains("400") && msg.contains("Group not found"))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_set_expiration() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("PUT"))
+            .and(path("/v1/contacts/%2B15555555555"))
+            .and(body_partial_json(serde_json::json!({
+                "recipient": "+14155551234",
+                "expiration_in_seconds": 300
+            })))
+            .respond_with(ResponseTemplate::new(204))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let client = create_test_client(&mock_server).await;
+        client
+            .set_expiration("+15555555555", "+14155551234", 300)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_set_expiration_unsupported() {
+        let mock_server = MockServer::start().await;
+
+        Mock::given(method("PUT"))
+            .and(path("/v1/contacts/%2B15555555555"))
+            .respond_with(ResponseTemplate::new(404).set_body_string("404 page not found"))
+            .mount(&mock_server)
+            .await;
+
+        let client = create_test_client(&mock_server).await;
+        let result = client.set_expiration("+15555555555", "+14155551234", 0).await;
+
+        assert!(matches!(result, Err(SignalError::Unsupported(_))));
+    }
+
     #[tokio::test]
     async fn test_get_account() {
         let mock_server = MockServer::start().await;
@@ -179,7 +573,11 @@ mod tests {
                     message: Some("Hello bot!".into()),
                     timestamp: 1677652288000,
                     group_info: None,
+                    mentions: vec![],
+                    reaction: None,
+                    sticker: None,
                 }),
+                edit_message: None,
             },
             account: "+15555555555".into(),
         };
@@ -209,7 +607,11 @@ mod tests {
                     group_info: Some(GroupInfo {
                         group_id: "test-group-id".into(),
                     }),
+                    mentions: vec![],
+                    reaction: None,
+                    sticker: None,
                 }),
+                edit_message: None,
             },
             account: "+15555555555".into(),
         };
@@ -233,6 +635,7 @@ mod tests {
                 source_name: None,
                 timestamp: 1677652288000,
                 data_message: None,
+                edit_message: None,
             },
             account: "+15555555555".into(),
         };
@@ -240,4 +643,114 @@ mod tests {
         let bot_msg = BotMessage::from_incoming(&incoming);
         assert!(bot_msg.is_none());
     }
+
+    #[test]
+    fn test_bot_message_from_edit() {
+        let incoming: IncomingMessage = serde_json::from_value(serde_json::json!({
+            "envelope": {
+                "source": "+14155551234",
+                "timestamp": 1677652299000i64,
+                "editMessage": {
+                    "targetSentTimestamp": 1677652288000i64,
+                    "dataMessage": {
+                        "message": "Hello bot, fixed!",
+                        "timestamp": 1677652299000i64
+                    }
+                }
+            },
+            "account": "+15555555555"
+        }))
+        .unwrap();
+
+        let msg = BotMessage::from_incoming(&incoming).unwrap();
+        assert!(msg.is_edit());
+        assert_eq!(msg.edit_target, Some(1677652288000));
+        assert_eq!(msg.text, "Hello bot, fixed!");
+    }
+
+    #[test]
+    fn test_bot_message_mentions() {
+        let incoming: IncomingMessage = serde_json::from_value(serde_json::json!({
+            "envelope": {
+                "source": "+14155551234",
+                "timestamp": 1677652288000i64,
+                "dataMessage": {
+                    "message": "\u{FFFC} what's the weather?",
+                    "timestamp": 1677652288000i64,
+                    "groupInfo": { "groupId": "test-group-id" },
+                    "mentions": [
+                        { "name": "Bot", "number": "+15555555555", "uuid": "bot-uuid", "start": 0, "length": 1 },
+                        { "name": "Friend", "uuid": "friend-uuid", "start": 0, "length": 1 }
+                    ]
+                }
+            },
+            "account": "+15555555555"
+        }))
+        .unwrap();
+
+        let msg = BotMessage::from_incoming(&incoming).unwrap();
+        assert!(msg.mentions("+15555555555"));
+        assert!(msg.mentions("bot-uuid"));
+        assert!(msg.mentions("friend-uuid"));
+        assert!(!msg.mentions("+16666666666"));
+    }
+
+    #[test]
+    fn test_bot_message_from_reaction() {
+        let incoming: IncomingMessage = serde_json::from_value(serde_json::json!({
+            "envelope": {
+                "source": "+14155551234",
+                "timestamp": 1677652289000i64,
+                "dataMessage": {
+                    "timestamp": 1677652289000i64,
+                    "reaction": {
+                        "emoji": "👍",
+                        "targetAuthor": "+15555555555",
+                        "targetAuthorNumber": "+15555555555",
+                        "targetAuthorUuid": "bot-uuid",
+                        "targetSentTimestamp": 1677652288000i64,
+                        "isRemove": false
+                    }
+                }
+            },
+            "account": "+15555555555"
+        }))
+        .unwrap();
+
+        let msg = BotMessage::from_incoming(&incoming).unwrap();
+        assert!(!msg.is_text());
+        assert_eq!(msg.text, "");
+        let MessageKind::Reaction(reaction) = msg.kind else {
+            panic!("expected a reaction, got {:?}", msg.kind);
+        };
+        assert_eq!(reaction.emoji, "👍");
+        assert_eq!(reaction.target_author, "+15555555555");
+        assert_eq!(reaction.target_sent_timestamp, 1677652288000);
+        assert!(!reaction.is_remove);
+    }
+
+    #[test]
+    fn test_bot_message_from_sticker() {
+        let incoming: IncomingMessage = serde_json::from_value(serde_json::json!({
+            "envelope": {
+                "source": "+14155551234",
+                "timestamp": 1677652289000i64,
+                "dataMessage": {
+                    "timestamp": 1677652289000i64,
+                    "sticker": { "packId": "abc123", "stickerId": 4 }
+                }
+            },
+            "account": "+15555555555"
+        }))
+        .unwrap();
+
+        let msg = BotMessage::from_incoming(&incoming).unwrap();
+        assert_eq!(
+            msg.kind,
+            MessageKind::Sticker(Sticker {
+                pack_id: "abc123".into(),
+                sticker_id: 4,
+            })
+        );
+    }
 }
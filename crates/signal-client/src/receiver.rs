@@ -7,6 +7,19 @@ use tokio::time::sleep;
 use tokio_stream::Stream;
 use tracing::{debug, error, info, warn};
 
+/// Longest pause between polls while receiving keeps failing.
+const MAX_RECEIVE_BACKOFF: Duration = Duration::from_secs(30);
+
+/// Delay before the next poll after `failed_polls` consecutive failed rounds.
+fn poll_delay(poll_interval: Duration, failed_polls: u32) -> Duration {
+    if failed_polls == 0 {
+        return poll_interval;
+    }
+    let base = poll_interval.max(Duration::from_millis(500));
+    base.saturating_mul(2u32.saturating_pow(failed_polls - 1))
+        .min(MAX_RECEIVE_BACKOFF)
+}
+
 /// Message receiver that polls all registered accounts for new messages.
 pub struct MessageReceiver {
     client: SignalClient,
@@ -31,6 +44,8 @@ impl MessageReceiver {
         async_stream::stream! {
             let mut accounts: Vec<String> = Vec::new();
             let mut last_account_refresh = std::time::Instant::now();
+            // Consecutive rounds in which every account failed to receive
+            let mut failed_polls: u32 = 0;
 
             loop {
                 // Refresh account list periodically or on first run
@@ -64,13 +79,16 @@ impl MessageReceiver {
                 }
 
                 // Poll each account for messages
+                let mut any_ok = false;
                 for account in &accounts {
                     match self.client.receive(account).await {
                         Ok(messages) => {
+                            any_ok = true;
                             for msg in messages {
                                 if let Some(bot_msg) = BotMessage::from_incoming(&msg) {
                                     debug!(
-                                        "Received on {}: '{}' from {}",
+                                        "Received {}on {}: '{}' from {}",
+                                        if bot_msg.is_edit() { "edit " } else { "" },
                                         account,
                                         &bot_msg.text[..bot_msg.text.len().min(50)],
                                         bot_msg.source
@@ -86,8 +104,34 @@ impl MessageReceiver {
                     }
                 }
 
-                sleep(self.poll_interval).await;
+                if any_ok {
+                    if failed_polls > 0 {
+                        info!("Receiving recovered after {} failed polls", failed_polls);
+                    }
+                    failed_polls = 0;
+                } else {
+                    failed_polls = failed_polls.saturating_add(1);
+                    let delay = poll_delay(self.poll_interval, failed_polls);
+                    warn!("Receiving failed for all accounts, backing off for {:?}", delay);
+                }
+
+                sleep(poll_delay(self.poll_interval, failed_polls)).await;
             }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_poll_delay_backs_off_on_failures() {
+        let interval = Duration::from_millis(200);
+        assert_eq!(poll_delay(interval, 0), interval);
+        assert_eq!(poll_delay(interval, 1), Duration::from_millis(500));
+        assert_eq!(poll_delay(interval, 2), Duration::from_secs(1));
+        assert_eq!(poll_delay(interval, 3), Duration::from_secs(2));
+        assert_eq!(poll_delay(interval, 50), MAX_RECEIVE_BACKOFF);
+    }
+}
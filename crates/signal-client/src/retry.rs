@@ -0,0 +1,168 @@
+//! Retry and circuit-breaker policy for sending messages.
+
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// How sends are retried when the Signal API fails transiently.
+///
+/// Server errors (5xx) and refused connections are retried with exponential
+/// backoff. Client errors (4xx) fail immediately, and so do timeouts, since
+/// the message may have gone out before the response was lost. After
+/// `breaker_threshold` consecutive sends fail, further sends are rejected
+/// without contacting the API until `breaker_cooldown` has passed.
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    /// Total attempts per send, including the first (1 disables retries)
+    pub max_attempts: u32,
+    /// Delay before the first retry, doubled for each later one
+    pub initial_backoff: Duration,
+    /// Upper bound on the delay between attempts
+    pub max_backoff: Duration,
+    /// Consecutive failed sends that open the circuit (0 disables the breaker)
+    pub breaker_threshold: u32,
+    /// How long an open circuit rejects sends
+    pub breaker_cooldown: Duration,
+}
+
+impl RetryPolicy {
+    /// A policy that makes a single attempt and never opens the circuit.
+    pub fn none() -> Self {
+        Self {
+            max_attempts: 1,
+            breaker_threshold: 0,
+            ..Self::default()
+        }
+    }
+
+    /// Delay before retry number `retry` (starting at 1).
+    pub(crate) fn backoff(&self, retry: u32) -> Duration {
+        let factor = 2u32.saturating_pow(retry.saturating_sub(1));
+        self.initial_backoff
+            .saturating_mul(factor)
+            .min(self.max_backoff)
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(5),
+            breaker_threshold: 5,
+            breaker_cooldown: Duration::from_secs(30),
+        }
+    }
+}
+
+/// Tracks consecutive send failures, shared by all clones of a client.
+pub(crate) struct CircuitBreaker {
+    threshold: u32,
+    cooldown: Duration,
+    state: Mutex<BreakerState>,
+}
+
+#[derive(Default)]
+struct BreakerState {
+    failures: u32,
+    open_until: Option<Instant>,
+}
+
+impl CircuitBreaker {
+    pub(crate) fn new(policy: &RetryPolicy) -> Self {
+        Self {
+            threshold: policy.breaker_threshold,
+            cooldown: policy.breaker_cooldown,
+            state: Mutex::new(BreakerState::default()),
+        }
+    }
+
+    /// Whether a send may go out now.
+    ///
+    /// Once the cooldown passes sends are let through again: the first
+    /// success closes the circuit, a failure reopens it straight away.
+    pub(crate) fn allow(&self) -> bool {
+        let state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        !matches!(state.open_until, Some(until) if Instant::now() < until)
+    }
+
+    pub(crate) fn record_success(&self) {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        *state = BreakerState::default();
+    }
+
+    /// Record a send that failed after exhausting its retries.
+    ///
+    /// Returns true if this failure opened the circuit.
+    pub(crate) fn record_failure(&self) -> bool {
+        if self.threshold == 0 {
+            return false;
+        }
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        state.failures = state.failures.saturating_add(1);
+        if state.failures >= self.threshold {
+            state.open_until = Some(Instant::now() + self.cooldown);
+            true
+        } else {
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff_doubles_up_to_max() {
+        let policy = RetryPolicy {
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_millis(300),
+            ..RetryPolicy::default()
+        };
+        assert_eq!(policy.backoff(1), Duration::from_millis(100));
+        assert_eq!(policy.backoff(2), Duration::from_millis(200));
+        assert_eq!(policy.backoff(3), Duration::from_millis(300));
+        assert_eq!(policy.backoff(40), Duration::from_millis(300));
+    }
+
+    #[test]
+    fn test_breaker_opens_after_threshold_and_resets_on_success() {
+        let breaker = CircuitBreaker::new(&RetryPolicy {
+            breaker_threshold: 2,
+            breaker_cooldown: Duration::from_secs(60),
+            ..RetryPolicy::default()
+        });
+
+        assert!(!breaker.record_failure());
+        assert!(breaker.allow());
+        assert!(breaker.record_failure());
+        assert!(!breaker.allow());
+
+        breaker.record_success();
+        assert!(breaker.allow());
+    }
+
+    #[test]
+    fn test_breaker_probe_after_cooldown() {
+        let breaker = CircuitBreaker::new(&RetryPolicy {
+            breaker_threshold: 1,
+            breaker_cooldown: Duration::ZERO,
+            ..RetryPolicy::default()
+        });
+
+        assert!(breaker.record_failure());
+        // Cooldown elapsed: sends are allowed again, and a failure reopens at once
+        assert!(breaker.allow());
+        assert!(breaker.record_failure());
+    }
+
+    #[test]
+    fn test_disabled_breaker_never_opens() {
+        let breaker = CircuitBreaker::new(&RetryPolicy::none());
+        for _ in 0..10 {
+            assert!(!breaker.record_failure());
+        }
+        assert!(breaker.allow());
+    }
+}
@@ -0,0 +1,257 @@
+//! Splitting long messages into Signal-sized parts.
+
+/// Longest message, in characters, Signal delivers as plain text. Longer
+/// bodies are turned into attachments or rejected by the REST API.
+pub const MAX_MESSAGE_LEN: usize = 2000;
+
+/// Opening and closing marker of a markdown code block.
+const FENCE: &str = "```";
+
+/// Split `text` into ordered parts of at most `max_len` characters.
+///
+/// Parts break between lines where possible, then between words; a word is
+/// only cut when it alone is longer than `max_len`. A code block that has to
+/// be split is closed at the end of each part and reopened (with its
+/// language tag) at the start of the next, so every part renders on its own.
+pub fn split_for_signal(text: &str, max_len: usize) -> Vec<String> {
+    let max_len = max_len.max(1);
+    if char_len(text) <= max_len {
+        return vec![text.to_string()];
+    }
+
+    let mut splitter = Splitter {
+        max_len,
+        parts: Vec::new(),
+        current: String::new(),
+    };
+    let mut lines = text.split_inclusive('\n');
+    while let Some(line) = lines.next() {
+        if !is_fence(line) {
+            splitter.push_text(line);
+            continue;
+        }
+
+        let mut body = Vec::new();
+        let mut closing = None;
+        for line in lines.by_ref() {
+            if is_fence(line) {
+                closing = Some(line);
+                break;
+            }
+            body.push(line);
+        }
+        splitter.push_code_block(line, &body, closing);
+    }
+    splitter.flush();
+    splitter.parts
+}
+
+struct Splitter {
+    max_len: usize,
+    parts: Vec<String>,
+    current: String,
+}
+
+impl Splitter {
+    fn fits(&self, segment: &str) -> bool {
+        char_len(&self.current) + char_len(segment) <= self.max_len
+    }
+
+    /// Finish the current part, dropping trailing whitespace.
+    fn flush(&mut self) {
+        let part = self.current.trim_end();
+        if !part.is_empty() {
+            self.parts.push(part.to_string());
+        }
+        self.current.clear();
+    }
+
+    /// Append `segment`, starting a new part first if it doesn't fit.
+    fn push(&mut self, segment: &str) {
+        if !self.fits(segment) {
+            self.flush();
+        }
+        self.current.push_str(segment);
+    }
+
+    fn push_text(&mut self, line: &str) {
+        if self.fits(line) {
+            self.current.push_str(line);
+            return;
+        }
+        self.flush();
+        for piece in wrap(line, self.max_len) {
+            self.push(piece);
+        }
+    }
+
+    fn push_code_block(&mut self, opening: &str, body: &[&str], closing: Option<&str>) {
+        let block: String = std::iter::once(opening)
+            .chain(body.iter().copied())
+            .chain(closing)
+            .collect();
+        if self.fits(&block) {
+            self.current.push_str(&block);
+            return;
+        }
+        self.flush();
+        if self.fits(&block) {
+            self.current.push_str(&block);
+            return;
+        }
+
+        // Room left for code once a part carries its opening and closing fence
+        let overhead = char_len(opening) + char_len(FENCE) + 1;
+        if overhead >= self.max_len {
+            // Too narrow for fences; fall back to plain text
+            for line in std::iter::once(opening).chain(body.iter().copied()).chain(closing) {
+                self.push_text(line);
+            }
+            return;
+        }
+        let width = self.max_len - overhead;
+
+        self.current.push_str(opening);
+        for line in body {
+            for piece in wrap(line, width) {
+                if char_len(&self.current) + char_len(piece) > width + char_len(opening) {
+                    self.close_fence();
+                    self.flush();
+                    self.current.push_str(opening);
+                }
+                self.current.push_str(piece);
+            }
+        }
+        if let Some(closing) = closing {
+            if !self.current.ends_with('\n') {
+                self.current.push('\n');
+            }
+            self.current.push_str(closing);
+        }
+    }
+
+    fn close_fence(&mut self) {
+        if !self.current.ends_with('\n') {
+            self.current.push('\n');
+        }
+        self.current.push_str(FENCE);
+    }
+}
+
+fn is_fence(line: &str) -> bool {
+    line.trim_start().starts_with(FENCE)
+}
+
+fn char_len(text: &str) -> usize {
+    text.chars().count()
+}
+
+/// Break `line` into pieces of at most `width` characters, after whitespace
+/// where possible. Concatenated, the pieces are `line` again.
+fn wrap(line: &str, width: usize) -> Vec<&str> {
+    let mut pieces = Vec::new();
+    let mut start = 0;
+    let mut len = 0;
+    // Byte offset just past the last whitespace, and the piece length there
+    let mut last_break: Option<(usize, usize)> = None;
+
+    for (i, c) in line.char_indices() {
+        if len == width {
+            let cut = match last_break.take() {
+                Some((offset, break_len)) => {
+                    len -= break_len;
+                    offset
+                }
+                None => {
+                    len = 0;
+                    i
+                }
+            };
+            pieces.push(&line[start..cut]);
+            start = cut;
+        }
+        len += 1;
+        if c.is_whitespace() {
+            last_break = Some((i + c.len_utf8(), len));
+        }
+    }
+    if start < line.len() {
+        pieces.push(&line[start..]);
+    }
+    pieces
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_short_message_unchanged() {
+        assert_eq!(split_for_signal("Hello!", 100), vec!["Hello!"]);
+    }
+
+    #[test]
+    fn test_splits_on_word_boundaries() {
+        let text = "the quick brown fox jumps over the lazy dog";
+        let parts = split_for_signal(text, 12);
+
+        assert_eq!(parts, ["the quick", "brown fox", "jumps over", "the lazy dog"]);
+        assert!(parts.iter().all(|p| p.chars().count() <= 12));
+    }
+
+    #[test]
+    fn test_prefers_line_boundaries() {
+        let text = "first line\nsecond line\nthird line";
+        assert_eq!(
+            split_for_signal(text, 25),
+            ["first line\nsecond line", "third line"]
+        );
+    }
+
+    #[test]
+    fn test_overlong_word_is_cut() {
+        let parts = split_for_signal("aaaaaaaaaa bb", 4);
+        assert_eq!(parts, ["aaaa", "aaaa", "aa", "bb"]);
+    }
+
+    #[test]
+    fn test_counts_characters_not_bytes() {
+        let parts = split_for_signal("héllo wörld", 6);
+        assert_eq!(parts, ["héllo", "wörld"]);
+    }
+
+    #[test]
+    fn test_code_block_kept_whole_when_it_fits() {
+        let text = "Intro text here\n```rust\nfn main() {}\n```\nOutro";
+        let parts = split_for_signal(text, 30);
+
+        assert_eq!(parts, ["Intro text here", "```rust\nfn main() {}\n```\nOutro"]);
+    }
+
+    #[test]
+    fn test_code_block_across_boundary_is_reopened() {
+        let code: String = (0..6).map(|i| format!("let x{} = {};\n", i, i)).collect();
+        let text = format!("Here is the code:\n```rust\n{}```\nDone.", code);
+        let parts = split_for_signal(&text, 40);
+
+        assert!(parts.len() > 2);
+        assert_eq!(parts[0], "Here is the code:");
+        for part in &parts {
+            assert!(part.chars().count() <= 40, "too long: {:?}", part);
+            // Fences balanced within each part
+            assert_eq!(part.matches(FENCE).count() % 2, 0, "unbalanced: {:?}", part);
+        }
+        assert!(parts[1].starts_with("```rust\nlet x0 = 0;\n"));
+        assert!(parts[1].ends_with("\n```"));
+        assert_eq!(parts.last().unwrap(), "Done.");
+
+        // Every line of code survives, in order
+        let rejoined: String = parts
+            .iter()
+            .flat_map(|p| p.lines())
+            .filter(|l| l.starts_with("let"))
+            .map(|l| format!("{}\n", l))
+            .collect();
+        assert_eq!(rejoined, code);
+    }
+}
@@ -19,6 +19,17 @@ pub struct Envelope {
     pub timestamp: i64,
     #[serde(rename = "dataMessage")]
     pub data_message: Option<DataMessage>,
+    #[serde(rename = "editMessage")]
+    pub edit_message: Option<EditMessage>,
+}
+
+/// Edit of an earlier message, identified by the timestamp it was sent with.
+#[derive(Debug, Clone, Deserialize)]
+pub struct EditMessage {
+    #[serde(rename = "targetSentTimestamp")]
+    pub target_sent_timestamp: i64,
+    #[serde(rename = "dataMessage")]
+    pub data_message: DataMessage,
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -27,6 +38,45 @@ pub struct DataMessage {
     pub timestamp: i64,
     #[serde(rename = "groupInfo")]
     pub group_info: Option<GroupInfo>,
+    #[serde(default)]
+    pub mentions: Vec<Mention>,
+    pub reaction: Option<Reaction>,
+    pub sticker: Option<Sticker>,
+}
+
+/// An emoji reaction to an earlier message.
+///
+/// Signal CLI versions differ in which fields they fill in, so missing ones
+/// default to empty rather than failing the whole receive batch.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct Reaction {
+    #[serde(default)]
+    pub emoji: String,
+    /// Author of the message reacted to (number, or UUID when hidden).
+    #[serde(rename = "targetAuthor", default)]
+    pub target_author: String,
+    #[serde(rename = "targetSentTimestamp", default)]
+    pub target_sent_timestamp: i64,
+    /// Whether an earlier reaction is being taken back.
+    #[serde(rename = "isRemove", default)]
+    pub is_remove: bool,
+}
+
+/// A sticker sent in place of text.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct Sticker {
+    #[serde(rename = "packId", default)]
+    pub pack_id: String,
+    #[serde(rename = "stickerId", default)]
+    pub sticker_id: u32,
+}
+
+/// An @-mention of another Signal user within a message.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Mention {
+    pub number: Option<String>,
+    pub uuid: Option<String>,
+    pub name: Option<String>,
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -41,14 +91,80 @@ pub struct SendMessageRequest {
     pub message: String,
     pub number: Option<String>,
     pub recipients: Option<Vec<String>>,
+    /// Timestamp of an earlier message from this account to replace.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub edit_timestamp: Option<i64>,
 }
 
 /// Send message response.
 #[derive(Debug, Clone, Deserialize)]
 pub struct SendMessageResponse {
+    /// Sent timestamp (signal-cli-rest-api reports it as a string).
+    #[serde(default, deserialize_with = "deserialize_timestamp")]
     pub timestamp: Option<i64>,
 }
 
+fn deserialize_timestamp<'de, D>(deserializer: D) -> Result<Option<i64>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Timestamp {
+        Number(i64),
+        Text(String),
+    }
+
+    Ok(match Option::<Timestamp>::deserialize(deserializer)? {
+        Some(Timestamp::Number(ts)) => Some(ts),
+        Some(Timestamp::Text(ts)) => ts.parse().ok(),
+        None => None,
+    })
+}
+
+/// Identifier of a Signal group as the REST API reports it (`group.<base64>`).
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct GroupId(pub String);
+
+impl GroupId {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl std::fmt::Display for GroupId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// Request to create a group.
+#[derive(Debug, Clone, Serialize)]
+pub struct CreateGroupRequest {
+    pub name: String,
+    pub members: Vec<String>,
+}
+
+/// Create group response.
+#[derive(Debug, Clone, Deserialize)]
+pub struct CreateGroupResponse {
+    pub id: GroupId,
+}
+
+/// Members to add to or remove from a group.
+#[derive(Debug, Clone, Serialize)]
+pub struct GroupMembersRequest {
+    pub members: Vec<String>,
+}
+
+/// Contact update, used to set the disappearing-message timer.
+#[derive(Debug, Clone, Serialize)]
+pub struct UpdateContactRequest {
+    pub recipient: String,
+    pub expiration_in_seconds: u32,
+}
+
 /// Account information.
 #[derive(Debug, Clone, Deserialize)]
 pub struct Account {
@@ -57,6 +173,18 @@ pub struct Account {
     pub registered: bool,
 }
 
+/// What a [`BotMessage`] carries besides (or instead of) text.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum MessageKind {
+    /// A regular text message.
+    #[default]
+    Text,
+    /// A reaction to an earlier message; `text` is empty.
+    Reaction(Reaction),
+    /// A sticker; `text` is empty.
+    Sticker(Sticker),
+}
+
 /// Parsed message for bot processing.
 #[derive(Debug, Clone)]
 pub struct BotMessage {
@@ -72,13 +200,33 @@ pub struct BotMessage {
     pub group_id: Option<String>,
     /// The bot's phone number that received this message.
     pub receiving_account: String,
+    /// Timestamp of the original message when this is an edit of it.
+    pub edit_target: Option<i64>,
+    /// Phone numbers and UUIDs (ACIs) of everyone @-mentioned in the message.
+    /// A mention contributes whichever of the two Signal shared.
+    pub mentions: Vec<String>,
+    /// Text, reaction or sticker.
+    pub kind: MessageKind,
 }
 
 impl BotMessage {
     /// Extract bot message from incoming envelope.
+    ///
+    /// Edits carry the new text and the original message's timestamp in
+    /// `edit_target`. Reactions and stickers come through with empty text
+    /// and their details in `kind`; other envelopes yield `None`.
     pub fn from_incoming(msg: &IncomingMessage) -> Option<Self> {
-        let data = msg.envelope.data_message.as_ref()?;
-        let text = data.message.clone()?;
+        let edit = msg.envelope.edit_message.as_ref();
+        let data = match edit {
+            Some(edit) => &edit.data_message,
+            None => msg.envelope.data_message.as_ref()?,
+        };
+        let (text, kind) = match (&data.message, &data.reaction, &data.sticker) {
+            (_, Some(reaction), _) => (String::new(), MessageKind::Reaction(reaction.clone())),
+            (_, _, Some(sticker)) => (String::new(), MessageKind::Sticker(sticker.clone())),
+            (Some(text), _, _) => (text.clone(), MessageKind::Text),
+            _ => return None,
+        };
 
         Some(Self {
             source: msg.envelope.source.clone(),
@@ -87,9 +235,31 @@ impl BotMessage {
             is_group: data.group_info.is_some(),
             group_id: data.group_info.as_ref().map(|g| g.group_id.clone()),
             receiving_account: msg.account.clone(),
+            edit_target: edit.map(|e| e.target_sent_timestamp),
+            mentions: data
+                .mentions
+                .iter()
+                .flat_map(|m| m.number.iter().chain(m.uuid.iter()).cloned())
+                .collect(),
+            kind,
         })
     }
 
+    /// Whether this is a plain text message (not a reaction or sticker).
+    pub fn is_text(&self) -> bool {
+        self.kind == MessageKind::Text
+    }
+
+    /// Whether `account` (a number or UUID) is @-mentioned in the message.
+    pub fn mentions(&self, account: &str) -> bool {
+        self.mentions.iter().any(|m| m == account)
+    }
+
+    /// Whether this message edits an earlier one.
+    pub fn is_edit(&self) -> bool {
+        self.edit_target.is_some()
+    }
+
     /// Get the reply target (group ID or source number).
     pub fn reply_target(&self) -> &str {
         self.group_id.as_deref().unwrap_or(&self.source)
@@ -1,18 +1,24 @@
 //! HTTP request handlers.
 
 use super::types::{
-    AccountInfo, AccountsResponse, AdoptAccountRequest, BotConfigResponse, BotInfo,
-    DeleteUsernameRequest, HealthResponse, ProfileResponse, RegisterRequest, RegisterResponse,
+    AccountInfo, AccountsResponse, AdoptAccountRequest, AuditResponse, BotConfigResponse, BotInfo,
+    BulkStatusEntry, BulkStatusRequest, BulkStatusResponse, MAX_BULK_STATUS_NUMBERS,
+    DeleteUsernameRequest, HealthResponse, IdentityResponse, PageParams, ProfileResponse, RegisterRequest,
+    RegisterResponse, ResendRequest,
     SetUsernameRequest, StatusResponse, UnregisterRequest, UpdateBotConfigRequest,
     UpdateProfileRequest, UsernameResponse, VerifyRequest, VerifyResponse,
 };
 use super::AppState;
 use crate::error::ProxyError;
-use crate::registry::{normalize_phone_number, PhoneNumberRecord, RegistrationStatus};
+use crate::registry::{
+    normalize_phone_number, PhoneNumberRecord, RegistrationAction, RegistrationEvent,
+    RegistrationStatus,
+};
 use axum::{
-    extract::{Path, State},
+    extract::{Path, Query, State},
     Json,
 };
+use chrono::Utc;
 use tracing::{info, warn};
 
 /// Health check endpoint.
@@ -61,10 +67,8 @@ pub async fn register_number(
 
     // Proxy to Signal CLI REST API
     // If Signal says "already registered", try to unregister first and retry
-    let register_result = state
-        .signal_client
-        .register(&number, request.captcha.as_deref(), request.use_voice)
-        .await;
+    let register_result =
+        register_with_captcha(&state, &number, request.captcha.as_deref(), request.use_voice).await;
 
     if let Err(ProxyError::SignalApi(ref msg)) = register_result {
         if msg.contains("already registered") {
@@ -72,9 +76,7 @@ pub async fn register_number(
             // Try to unregister the stale registration
             if state.signal_client.unregister(&number).await.is_ok() {
                 // Retry registration
-                state
-                    .signal_client
-                    .register(&number, request.captcha.as_deref(), request.use_voice)
+                register_with_captcha(&state, &number, request.captcha.as_deref(), request.use_voice)
                     .await?;
             } else {
                 // Unregister failed, return original error
@@ -97,6 +99,11 @@ pub async fn register_number(
 
     let mut registry = state.registry.write().await;
     registry.insert(number.clone(), record);
+    registry.record_event(RegistrationEvent::new(
+        number.clone(),
+        RegistrationAction::Registered,
+        request.ownership_secret.is_some(),
+    ));
 
     // Persist to encrypted storage
     state.store.save(&registry).await?;
@@ -111,6 +118,75 @@ pub async fn register_number(
     }))
 }
 
+/// Resend the verification code for a pending registration.
+pub async fn resend_code(
+    State(state): State<AppState>,
+    Path(number): Path<String>,
+    Json(request): Json<ResendRequest>,
+) -> Result<Json<RegisterResponse>, ProxyError> {
+    let number = normalize_phone_number(&number).map_err(ProxyError::InvalidPhoneNumber)?;
+    info!(phone_number = %number, "Resend code request received");
+
+    // Only a registration awaiting verification has a code to resend
+    let registry = state.registry.read().await;
+    let record = registry.get(&number).ok_or(ProxyError::NotFound(number.clone()))?;
+
+    if record.status != RegistrationStatus::Pending {
+        return Err(ProxyError::NotFound(number));
+    }
+
+    if !record.verify_ownership(request.ownership_secret.as_deref()) {
+        return Err(ProxyError::OwnershipProofMismatch);
+    }
+    drop(registry);
+
+    register_with_captcha(&state, &number, request.captcha.as_deref(), request.use_voice).await?;
+
+    // A fresh code restarts the pending TTL, so pruning doesn't expire it early
+    let mut registry = state.registry.write().await;
+    if let Some(record) = registry.get_mut(&number) {
+        record.registered_at = Utc::now();
+    }
+    state.store.save(&registry).await?;
+    drop(registry);
+
+    info!(phone_number = %number, "Verification code resent");
+
+    Ok(Json(RegisterResponse {
+        phone_number: number,
+        status: "pending".to_string(),
+        message: "Verification code resent. Use /v1/register/{number}/verify/{code} to complete."
+            .to_string(),
+    }))
+}
+
+/// Register with Signal, solving a captcha and retrying once if Signal
+/// demands one and a captcha provider is configured.
+///
+/// Without a provider, or if solving fails, the captcha error is returned so
+/// the client can supply a token manually.
+async fn register_with_captcha(
+    state: &AppState,
+    number: &str,
+    captcha: Option<&str>,
+    use_voice: bool,
+) -> Result<(), ProxyError> {
+    let result = state.signal_client.register(number, captcha, use_voice).await;
+    let (Err(ProxyError::CaptchaRequired), Some(provider)) = (&result, &state.captcha) else {
+        return result;
+    };
+
+    info!(phone_number = %number, "Signal requires a captcha, solving automatically");
+    let token = match provider.solve().await {
+        Ok(token) => token,
+        Err(e) => {
+            warn!(phone_number = %number, error = %e, "Captcha solving failed");
+            return Err(ProxyError::CaptchaRequired);
+        }
+    };
+    state.signal_client.register(number, Some(&token), use_voice).await
+}
+
 /// Verify registration with code.
 pub async fn verify_registration(
     State(state): State<AppState>,
@@ -146,6 +222,11 @@ pub async fn verify_registration(
     if let Some(record) = registry.get_mut(&number) {
         record.mark_verified();
     }
+    registry.record_event(RegistrationEvent::new(
+        number.clone(),
+        RegistrationAction::Verified,
+        request.ownership_secret.is_some(),
+    ));
 
     // Persist
     state.store.save(&registry).await?;
@@ -176,11 +257,95 @@ pub async fn get_status(
     }))
 }
 
-/// List all registered accounts.
-pub async fn list_accounts(State(state): State<AppState>) -> Json<AccountsResponse> {
+/// Get an account's safety number and identity fingerprint.
+pub async fn get_identity(
+    State(state): State<AppState>,
+    Path(number): Path<String>,
+) -> Result<Json<IdentityResponse>, ProxyError> {
+    let number = normalize_phone_number(&number).map_err(ProxyError::InvalidPhoneNumber)?;
+
+    let identity = state
+        .signal_client
+        .get_identity(&number)
+        .await?
+        .ok_or_else(|| ProxyError::NotFound(number.clone()))?;
+
+    Ok(Json(IdentityResponse {
+        phone_number: number,
+        safety_number: identity.safety_number,
+        fingerprint: identity.fingerprint,
+    }))
+}
+
+/// Look up the status of several numbers at once.
+///
+/// Each number gets its own result; invalid or unknown numbers don't fail
+/// the request.
+pub async fn bulk_status(
+    State(state): State<AppState>,
+    Json(request): Json<BulkStatusRequest>,
+) -> Result<Json<BulkStatusResponse>, ProxyError> {
+    if request.numbers.len() > MAX_BULK_STATUS_NUMBERS {
+        return Err(ProxyError::InvalidRequest(format!(
+            "At most {} numbers per request",
+            MAX_BULK_STATUS_NUMBERS
+        )));
+    }
+
     let registry = state.registry.read().await;
-    let accounts: Vec<AccountInfo> = registry
+    let results = request
+        .numbers
+        .into_iter()
+        .map(|number| match normalize_phone_number(&number) {
+            Ok(normalized) => {
+                let record = registry.get(&normalized);
+                BulkStatusEntry {
+                    number,
+                    phone_number: Some(normalized),
+                    found: record.is_some(),
+                    status: record.map(|r| r.status.clone()),
+                    registered_at: record.map(|r| r.registered_at.to_rfc3339()),
+                    error: None,
+                }
+            }
+            Err(e) => BulkStatusEntry {
+                number,
+                phone_number: None,
+                found: false,
+                status: None,
+                registered_at: None,
+                error: Some(ProxyError::InvalidPhoneNumber(e).to_string()),
+            },
+        })
+        .collect();
+
+    Ok(Json(BulkStatusResponse { results }))
+}
+
+/// List registered accounts, oldest registration first.
+///
+/// Without `limit`, `offset` or `since` every account is returned, as before
+/// the endpoint was paginated.
+pub async fn list_accounts(
+    State(state): State<AppState>,
+    Query(page): Query<PageParams>,
+) -> Json<AccountsResponse> {
+    let registry = state.registry.read().await;
+    let mut records: Vec<_> = registry
         .list_all()
+        .into_iter()
+        .filter(|r| page.since.is_none_or(|since| r.registered_at >= since))
+        .collect();
+    records.sort_by(|a, b| {
+        a.registered_at
+            .cmp(&b.registered_at)
+            .then_with(|| a.phone_number.cmp(&b.phone_number))
+    });
+
+    let total = records.len();
+    let paginated = page.is_requested();
+    let records = if paginated { page.apply(records) } else { records };
+    let accounts: Vec<AccountInfo> = records
         .into_iter()
         .map(|r| AccountInfo {
             phone_number: r.phone_number.clone(),
@@ -192,8 +357,12 @@ pub async fn list_accounts(State(state): State<AppState>) -> Json<AccountsRespon
         })
         .collect();
 
-    let total = accounts.len();
-    Json(AccountsResponse { accounts, total })
+    Json(AccountsResponse {
+        accounts,
+        total,
+        offset: paginated.then_some(page.offset),
+        limit: paginated.then(|| page.limit()),
+    })
 }
 
 /// Unregister a phone number.
@@ -221,6 +390,11 @@ pub async fn unregister(
     // Remove from registry
     let mut registry = state.registry.write().await;
     registry.remove(&number);
+    registry.record_event(RegistrationEvent::new(
+        number.clone(),
+        RegistrationAction::Unregistered,
+        request.ownership_secret.is_some(),
+    ));
 
     // Persist
     state.store.save(&registry).await?;
@@ -234,6 +408,26 @@ pub async fn unregister(
     }))
 }
 
+/// Admin endpoint: Read the registration audit log, newest first.
+pub async fn list_audit_events(
+    State(state): State<AppState>,
+    Query(page): Query<PageParams>,
+) -> Json<AuditResponse> {
+    let registry = state.registry.read().await;
+    let events: Vec<_> = registry
+        .events()
+        .iter()
+        .filter(|e| page.since.is_none_or(|since| e.timestamp >= since))
+        .collect();
+
+    Json(AuditResponse {
+        total: events.len(),
+        events: page.apply(events.into_iter().rev().cloned()),
+        offset: page.offset,
+        limit: page.limit(),
+    })
+}
+
 /// Debug endpoint: List accounts registered in Signal CLI (not our registry).
 pub async fn debug_signal_accounts(
     State(state): State<AppState>,
@@ -257,7 +451,13 @@ pub async fn debug_force_unregister(
 
     // Also remove from our registry if present
     let mut registry = state.registry.write().await;
-    registry.remove(&number);
+    if registry.remove(&number).is_some() {
+        registry.record_event(RegistrationEvent::new(
+            number.clone(),
+            RegistrationAction::Unregistered,
+            false,
+        ));
+    }
     state.store.save(&registry).await?;
 
     Ok(Json(serde_json::json!({
@@ -1,14 +1,17 @@
 //! Rate limiting and other middleware.
 
 use crate::error::ProxyError;
+use crate::registry::normalize_phone_number;
+use api_common::constant_time_eq;
 use axum::{
     extract::{Request, State},
+    http::header,
     middleware::Next,
     response::Response,
 };
 use governor::{
-    clock::DefaultClock,
-    state::{InMemoryState, NotKeyed},
+    clock::{Clock, DefaultClock},
+    state::{keyed::DefaultKeyedStateStore, InMemoryState, NotKeyed},
     Quota, RateLimiter,
 };
 use std::{num::NonZeroU32, sync::Arc};
@@ -17,11 +20,27 @@ use tracing::{debug, warn};
 /// Global rate limiter (not keyed by IP).
 pub type GlobalLimiter = RateLimiter<NotKeyed, InMemoryState, DefaultClock>;
 
+/// Rate limiter keyed by phone number and registration step.
+pub type NumberLimiter =
+    RateLimiter<(String, RegistrationStep), DefaultKeyedStateStore<(String, RegistrationStep)>, DefaultClock>;
+
+/// Which part of a registration a request is, so each step of one
+/// registration has its own per-number budget.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RegistrationStep {
+    /// `POST /v1/register/:number`, which asks Signal for a code
+    Register,
+    /// Resending or verifying the code of a pending registration
+    Confirm,
+}
+
 /// Rate limiter state shared across requests.
 #[derive(Clone)]
 pub struct RateLimitState {
     /// Global rate limiter for all requests
     pub global: Arc<GlobalLimiter>,
+    /// Per-number limiter for the `/v1/register/:number` routes, keyed by step
+    pub per_number: Option<Arc<NumberLimiter>>,
 }
 
 impl RateLimitState {
@@ -33,6 +52,24 @@ impl RateLimitState {
 
         Self {
             global: Arc::new(RateLimiter::direct(quota)),
+            per_number: None,
+        }
+    }
+
+    /// Also limit registration attempts for each phone number (zero disables).
+    pub fn with_per_number_per_hour(mut self, requests_per_hour: u32) -> Self {
+        self.per_number = NonZeroU32::new(requests_per_hour)
+            .map(|n| Arc::new(RateLimiter::keyed(Quota::per_hour(n))));
+        self
+    }
+
+    /// Forget phone numbers whose limit has fully replenished, so the
+    /// per-number state doesn't grow with every number ever seen. Call
+    /// periodically.
+    pub fn retain_recent(&self) {
+        if let Some(ref limiter) = self.per_number {
+            limiter.retain_recent();
+            limiter.shrink_to_fit();
         }
     }
 
@@ -42,14 +79,44 @@ impl RateLimitState {
     }
 }
 
+/// Phone number a `/v1/register/:number` path is for, normalized so
+/// formatting variants share a limit, and the step it performs.
+fn registration_number(path: &str) -> Option<(String, RegistrationStep)> {
+    let mut segments = path.strip_prefix("/v1/register/")?.split('/');
+    let number = urlencoding::decode(segments.next()?).ok()?;
+    let step = match segments.next() {
+        None => RegistrationStep::Register,
+        Some(_) => RegistrationStep::Confirm,
+    };
+    Some((normalize_phone_number(&number).ok()?, step))
+}
+
 /// Rate limiting middleware.
 ///
-/// Checks the global rate limit and returns 429 Too Many Requests if exceeded.
+/// Checks the per-number limit on registration routes, then the global
+/// limit, and returns 429 Too Many Requests if either is exceeded. Asking
+/// for a code and resending or verifying it are limited separately, so one
+/// registration's follow-up requests don't use up its registration budget.
 pub async fn rate_limit_middleware(
     State(rate_limit): State<RateLimitState>,
     request: Request,
     next: Next,
 ) -> Result<Response, ProxyError> {
+    // Checked first so an abusive number doesn't use up the global budget
+    if let Some(ref limiter) = rate_limit.per_number {
+        if let Some(key) = registration_number(request.uri().path()) {
+            if let Err(not_until) = limiter.check_key(&key) {
+                let retry_after = not_until.wait_time_from(DefaultClock::default().now());
+                let (number, step) = key;
+                warn!(%number, ?step, ?retry_after, "Per-number rate limit exceeded");
+                return Err(ProxyError::NumberRateLimitExceeded {
+                    number,
+                    retry_after_secs: retry_after.as_secs().max(1),
+                });
+            }
+        }
+    }
+
     // Check global rate limit
     if rate_limit.global.check().is_err() {
         warn!("Global rate limit exceeded");
@@ -60,6 +127,32 @@ pub async fn rate_limit_middleware(
     Ok(next.run(request).await)
 }
 
+/// Admin authentication middleware for the debug routes.
+///
+/// Requires an `Authorization: Bearer <token>` header matching the
+/// configured admin token, and returns 401 Unauthorized otherwise.
+pub async fn admin_auth_middleware(
+    State(admin_token): State<Arc<str>>,
+    request: Request,
+    next: Next,
+) -> Result<Response, ProxyError> {
+    let presented = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+
+    match presented {
+        Some(token) if constant_time_eq(token.as_bytes(), admin_token.as_bytes()) => {
+            Ok(next.run(request).await)
+        }
+        _ => {
+            warn!(path = %request.uri().path(), "Rejected debug request without a valid admin token");
+            Err(ProxyError::Unauthorized)
+        }
+    }
+}
+
 /// Logging middleware for requests.
 pub async fn logging_middleware(request: Request, next: Next) -> Response {
     let method = request.method().clone();
@@ -105,6 +198,47 @@ mod tests {
         assert!(state.global.check().is_err());
     }
 
+    #[test]
+    fn test_registration_number() {
+        let number = "+14155551234".to_string();
+        assert_eq!(
+            registration_number("/v1/register/%2B14155551234"),
+            Some((number.clone(), RegistrationStep::Register))
+        );
+        assert_eq!(
+            registration_number("/v1/register/+1%20415%20555%201234/verify/123456"),
+            Some((number.clone(), RegistrationStep::Confirm))
+        );
+        assert_eq!(
+            registration_number("/v1/register/+14155551234/resend"),
+            Some((number, RegistrationStep::Confirm))
+        );
+        assert_eq!(registration_number("/v1/status/+14155551234"), None);
+        assert_eq!(registration_number("/v1/register/abc"), None);
+    }
+
+    #[test]
+    fn test_retain_recent_forgets_replenished_numbers() {
+        let limiter = Arc::new(RateLimiter::keyed(Quota::per_second(NonZeroU32::new(1000).unwrap())));
+        let state = RateLimitState {
+            per_number: Some(limiter.clone()),
+            ..RateLimitState::permissive()
+        };
+
+        assert!(limiter.check_key(&("+14155551234".to_string(), RegistrationStep::Register)).is_ok());
+        assert_eq!(limiter.len(), 1);
+        std::thread::sleep(std::time::Duration::from_millis(10));
+        state.retain_recent();
+        assert!(limiter.is_empty());
+
+        // A number still being limited is kept
+        let state = RateLimitState::permissive().with_per_number_per_hour(1);
+        let hourly = state.per_number.clone().unwrap();
+        assert!(hourly.check_key(&("+14155551234".to_string(), RegistrationStep::Register)).is_ok());
+        state.retain_recent();
+        assert_eq!(hourly.len(), 1);
+    }
+
     #[test]
     fn test_permissive_rate_limit() {
         let state = RateLimitState::permissive();
@@ -5,9 +5,13 @@ mod middleware;
 mod types;
 
 pub use handlers::*;
-pub use middleware::{logging_middleware, rate_limit_middleware, RateLimitState};
+pub use middleware::{
+    admin_auth_middleware, logging_middleware, rate_limit_middleware, RateLimitState,
+    RegistrationStep,
+};
 pub use types::*;
 
+use crate::captcha::CaptchaProvider;
 use crate::registry::{Registry, Store};
 use crate::signal::SignalRegistrationClient;
 use axum::{
@@ -30,6 +34,10 @@ pub struct AppState {
     pub store: Arc<Store>,
     /// Signal CLI client
     pub signal_client: Arc<SignalRegistrationClient>,
+    /// Solves registration captchas, if configured
+    pub captcha: Option<Arc<dyn CaptchaProvider>>,
+    /// Bearer token for the admin and debug endpoints; they are disabled without one
+    pub admin_token: Option<Arc<str>>,
 }
 
 impl AppState {
@@ -43,8 +51,22 @@ impl AppState {
             registry: Arc::new(RwLock::new(registry)),
             store: Arc::new(store),
             signal_client: Arc::new(signal_client),
+            captcha: None,
+            admin_token: None,
         }
     }
+
+    /// Solve captchas automatically when Signal demands one.
+    pub fn with_captcha_provider(mut self, provider: Arc<dyn CaptchaProvider>) -> Self {
+        self.captcha = Some(provider);
+        self
+    }
+
+    /// Enable the admin and debug endpoints behind the given bearer token.
+    pub fn with_admin_token(mut self, token: impl Into<Arc<str>>) -> Self {
+        self.admin_token = Some(token.into());
+        self
+    }
 }
 
 /// Create the API router with rate limiting.
@@ -54,17 +76,31 @@ pub fn create_router(state: AppState) -> Router {
 
 /// Create the API router with custom rate limiting.
 pub fn create_router_with_rate_limit(state: AppState, rate_limit: RateLimitState) -> Router {
+    // Admin and debug endpoints can unregister accounts, so they only exist
+    // when an admin token is configured
+    let admin = match state.admin_token.clone() {
+        Some(token) => Router::new()
+            .route("/v1/admin/audit", get(handlers::list_audit_events))
+            .route("/v1/debug/signal-accounts", get(handlers::debug_signal_accounts))
+            .route("/v1/debug/force-unregister/:number", post(handlers::debug_force_unregister))
+            .route_layer(axum_middleware::from_fn_with_state(token, admin_auth_middleware)),
+        None => Router::new(),
+    };
+
     Router::new()
         // Health check (no rate limiting)
         .route("/health", get(handlers::health))
         // Registration endpoints (with rate limiting)
         .route("/v1/register/:number", post(handlers::register_number))
+        .route("/v1/register/:number/resend", post(handlers::resend_code))
         .route(
             "/v1/register/:number/verify/:code",
             post(handlers::verify_registration),
         )
+        .route("/v1/status/bulk", post(handlers::bulk_status))
         .route("/v1/status/:number", get(handlers::get_status))
         .route("/v1/accounts", get(handlers::list_accounts))
+        .route("/v1/accounts/:number/identity", get(handlers::get_identity))
         .route("/v1/unregister/:number", delete(handlers::unregister))
         // Profile and username management (requires ownership_secret)
         .route("/v1/profiles/:number", put(handlers::update_profile))
@@ -76,9 +112,8 @@ pub fn create_router_with_rate_limit(state: AppState, rate_limit: RateLimitState
         .route("/v1/bots", get(handlers::list_bots))
         .route("/v1/bots/:number", get(handlers::get_bot_config))
         .route("/v1/bots/:number", put(handlers::update_bot_config))
-        // Debug endpoints
-        .route("/v1/debug/signal-accounts", get(handlers::debug_signal_accounts))
-        .route("/v1/debug/force-unregister/:number", post(handlers::debug_force_unregister))
+        // Admin and debug endpoints (require the admin token)
+        .merge(admin)
         .layer(axum_middleware::from_fn_with_state(
             rate_limit.clone(),
             rate_limit_middleware,
@@ -1,6 +1,6 @@
 //! API request and response types.
 
-use crate::registry::RegistrationStatus;
+use crate::registry::{RegistrationEvent, RegistrationStatus};
 use serde::{Deserialize, Serialize};
 
 /// Request to initiate phone number registration.
@@ -32,6 +32,20 @@ pub struct RegisterResponse {
     pub message: String,
 }
 
+/// Request to resend the verification code for a pending registration.
+#[derive(Debug, Deserialize)]
+pub struct ResendRequest {
+    /// Optional CAPTCHA token if required by Signal
+    pub captcha: Option<String>,
+
+    /// Use voice call instead of SMS for verification code
+    #[serde(default)]
+    pub use_voice: bool,
+
+    /// Ownership secret (must match what was provided during registration)
+    pub ownership_secret: Option<String>,
+}
+
 /// Request to verify registration with code.
 #[derive(Debug, Deserialize)]
 pub struct VerifyRequest {
@@ -58,13 +72,72 @@ pub struct StatusResponse {
     pub registered_at: Option<String>,
 }
 
+/// An account's Signal identity, for users to verify the safety number.
+#[derive(Debug, Serialize)]
+pub struct IdentityResponse {
+    pub phone_number: String,
+    /// Safety number to compare in the Signal app (e.g. "96616 40685 ...")
+    pub safety_number: String,
+    /// Identity key fingerprint in hex
+    pub fingerprint: String,
+}
+
+/// Largest number of entries accepted by a bulk status request.
+pub const MAX_BULK_STATUS_NUMBERS: usize = 500;
+
+/// Request for the status of several numbers at once.
+#[derive(Debug, Deserialize)]
+pub struct BulkStatusRequest {
+    pub numbers: Vec<String>,
+}
+
+/// Status of each requested number, in request order.
+#[derive(Debug, Serialize)]
+pub struct BulkStatusResponse {
+    pub results: Vec<BulkStatusEntry>,
+}
+
+/// Status of one number in a bulk request.
+#[derive(Debug, Serialize)]
+pub struct BulkStatusEntry {
+    /// The number as given in the request.
+    pub number: String,
+    /// Normalized number, if it was valid.
+    pub phone_number: Option<String>,
+    pub found: bool,
+    pub status: Option<RegistrationStatus>,
+    pub registered_at: Option<String>,
+    /// Why the number couldn't be looked up.
+    pub error: Option<String>,
+}
+
 /// List of registered accounts.
+///
+/// `offset` and `limit` are only present when the client asked for a page;
+/// a plain `GET /v1/accounts` still lists every account.
 #[derive(Debug, Serialize)]
 pub struct AccountsResponse {
     pub accounts: Vec<AccountInfo>,
+    /// Total number of accounts (registered since `since`, if given) across all pages.
+    pub total: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub offset: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<usize>,
+}
+
+/// Page of the registration audit log, newest first.
+#[derive(Debug, Serialize)]
+pub struct AuditResponse {
+    pub events: Vec<RegistrationEvent>,
+    /// Total number of events (since `since`, if given) across all pages.
     pub total: usize,
+    pub offset: usize,
+    pub limit: usize,
 }
 
+pub use api_common::{PageParams, DEFAULT_PAGE_SIZE, MAX_PAGE_SIZE};
+
 /// Account info for listing.
 #[derive(Debug, Serialize)]
 pub struct AccountInfo {
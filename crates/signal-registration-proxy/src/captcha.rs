@@ -0,0 +1,202 @@
+//! Automatic solving of Signal's registration captcha.
+//!
+//! Signal often demands an hCaptcha before it sends a verification code.
+//! Without a provider the client has to solve it at
+//! signalcaptchas.org and pass the token in the registration request; with
+//! one configured, the proxy fetches a token itself and retries.
+
+use crate::config::{CaptchaConfig, CaptchaProviderKind};
+use crate::error::ProxyError;
+use async_trait::async_trait;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{debug, info};
+
+/// hCaptcha site key of Signal's registration captcha.
+pub const SIGNAL_REGISTRATION_SITE_KEY: &str = "5fad97ac-7d06-4e44-b18a-b950b20148ff";
+
+/// Page the registration captcha is served from.
+pub const SIGNAL_CAPTCHA_PAGE: &str = "https://signalcaptchas.org/registration/generate.html";
+
+/// Obtains captcha tokens for registration.
+#[async_trait]
+pub trait CaptchaProvider: Send + Sync {
+    /// Solve a registration captcha, returning a token Signal accepts.
+    async fn solve(&self) -> Result<String, ProxyError>;
+}
+
+/// Build the provider selected in the configuration, if any.
+pub fn from_config(config: &CaptchaConfig) -> Result<Option<Arc<dyn CaptchaProvider>>, ProxyError> {
+    match config.provider {
+        CaptchaProviderKind::None => Ok(None),
+        CaptchaProviderKind::SolverService => {
+            let url = config.solver_url.as_deref().ok_or_else(|| {
+                ProxyError::Internal("CAPTCHA__SOLVER_URL is required for the solver_service provider".into())
+            })?;
+            let provider = SolverServiceProvider::new(
+                url,
+                config.api_key.clone(),
+                config.site_key.clone(),
+                Duration::from_secs(config.timeout_secs),
+            )?;
+            info!("Captcha solving enabled via {}", url);
+            Ok(Some(Arc::new(provider)))
+        }
+    }
+}
+
+/// Solves captchas through an HTTP solver service.
+///
+/// The service receives `POST {"site_key", "page_url"}` and answers with
+/// `{"token"}`, the hCaptcha response for that site.
+pub struct SolverServiceProvider {
+    client: Client,
+    url: String,
+    api_key: Option<String>,
+    site_key: String,
+}
+
+impl SolverServiceProvider {
+    pub fn new(
+        url: impl Into<String>,
+        api_key: Option<String>,
+        site_key: impl Into<String>,
+        timeout: Duration,
+    ) -> Result<Self, ProxyError> {
+        let client = Client::builder()
+            .timeout(timeout)
+            .build()
+            .map_err(|e| ProxyError::Internal(format!("Failed to create HTTP client: {}", e)))?;
+
+        Ok(Self {
+            client,
+            url: url.into(),
+            api_key,
+            site_key: site_key.into(),
+        })
+    }
+}
+
+#[derive(Serialize)]
+struct SolveRequest<'a> {
+    site_key: &'a str,
+    page_url: &'a str,
+}
+
+#[derive(Deserialize)]
+struct SolveResponse {
+    token: String,
+}
+
+#[async_trait]
+impl CaptchaProvider for SolverServiceProvider {
+    async fn solve(&self) -> Result<String, ProxyError> {
+        debug!(url = %self.url, "Requesting captcha solution");
+
+        let mut request = self.client.post(&self.url).json(&SolveRequest {
+            site_key: &self.site_key,
+            page_url: SIGNAL_CAPTCHA_PAGE,
+        });
+        if let Some(ref key) = self.api_key {
+            request = request.bearer_auth(key);
+        }
+
+        let response = request
+            .send()
+            .await
+            .map_err(|e| ProxyError::Internal(format!("Captcha solver unreachable: {}", e)))?;
+        if !response.status().is_success() {
+            return Err(ProxyError::Internal(format!(
+                "Captcha solver returned {}",
+                response.status()
+            )));
+        }
+        let solved: SolveResponse = response
+            .json()
+            .await
+            .map_err(|e| ProxyError::Internal(format!("Invalid captcha solver response: {}", e)))?;
+
+        Ok(signal_captcha_token(&self.site_key, &solved.token))
+    }
+}
+
+/// Wrap an hCaptcha response in the `signalcaptcha://` form Signal expects,
+/// unless the solver already returned it that way.
+fn signal_captcha_token(site_key: &str, token: &str) -> String {
+    if token.starts_with("signalcaptcha://") {
+        token.to_string()
+    } else {
+        format!("signalcaptcha://signal-hcaptcha.{}.registration.{}", site_key, token)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{body_json, header, method};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[test]
+    fn test_signal_captcha_token() {
+        assert_eq!(
+            signal_captcha_token("key", "P1_abc"),
+            "signalcaptcha://signal-hcaptcha.key.registration.P1_abc"
+        );
+        assert_eq!(
+            signal_captcha_token("key", "signalcaptcha://already"),
+            "signalcaptcha://already"
+        );
+    }
+
+    #[test]
+    fn test_from_config() {
+        assert!(from_config(&CaptchaConfig::default()).unwrap().is_none());
+
+        let missing_url = CaptchaConfig {
+            provider: CaptchaProviderKind::SolverService,
+            ..CaptchaConfig::default()
+        };
+        assert!(from_config(&missing_url).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_solver_service_provider() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(header("authorization", "Bearer solver-key"))
+            .and(body_json(serde_json::json!({
+                "site_key": SIGNAL_REGISTRATION_SITE_KEY,
+                "page_url": SIGNAL_CAPTCHA_PAGE,
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({ "token": "P1_abc" })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let provider = SolverServiceProvider::new(
+            server.uri(),
+            Some("solver-key".into()),
+            SIGNAL_REGISTRATION_SITE_KEY,
+            Duration::from_secs(5),
+        )
+        .unwrap();
+
+        let token = provider.solve().await.unwrap();
+        assert!(token.ends_with(".registration.P1_abc"));
+    }
+
+    #[tokio::test]
+    async fn test_solver_service_error() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(503))
+            .mount(&server)
+            .await;
+
+        let provider =
+            SolverServiceProvider::new(server.uri(), None, "key", Duration::from_secs(5)).unwrap();
+        assert!(matches!(provider.solve().await, Err(ProxyError::Internal(_))));
+    }
+}
@@ -27,6 +27,10 @@ pub struct Config {
     #[serde(default)]
     pub rate_limit: RateLimitConfig,
 
+    /// Automatic captcha solving
+    #[serde(default)]
+    pub captcha: CaptchaConfig,
+
     /// Logging configuration
     #[serde(default)]
     pub log: LogConfig,
@@ -48,6 +52,10 @@ pub struct RegistryConfig {
     /// Enable persistence (if false, registry is in-memory only)
     #[serde(default = "default_true")]
     pub persist: bool,
+
+    /// Seconds a registration may stay pending before it expires (0 = never)
+    #[serde(default = "default_pending_ttl_secs")]
+    pub pending_ttl_secs: u64,
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -59,6 +67,10 @@ pub struct ServerConfig {
     /// Server port
     #[serde(default = "default_port")]
     pub port: u16,
+
+    /// Bearer token required by the `/v1/admin/*` and `/v1/debug/*` routes (disabled if unset)
+    #[serde(default)]
+    pub admin_token: Option<String>,
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -66,6 +78,10 @@ pub struct DstackConfig {
     /// Dstack socket path
     #[serde(default = "default_dstack_socket")]
     pub socket_path: String,
+
+    /// HTTP URL of a proxied guest agent; used instead of the socket when set
+    #[serde(default)]
+    pub url: Option<String>,
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -79,6 +95,40 @@ pub struct RateLimitConfig {
     pub per_number_per_hour: u32,
 }
 
+/// Which captcha provider solves Signal's registration captcha.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CaptchaProviderKind {
+    /// No automatic solving; clients pass a token themselves
+    #[default]
+    None,
+    /// An HTTP solver service at `solver_url`
+    SolverService,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct CaptchaConfig {
+    /// Captcha provider to use when Signal demands a captcha
+    #[serde(default)]
+    pub provider: CaptchaProviderKind,
+
+    /// Solver service endpoint (for `solver_service`)
+    #[serde(default)]
+    pub solver_url: Option<String>,
+
+    /// API key sent to the solver service as a bearer token
+    #[serde(default)]
+    pub api_key: Option<String>,
+
+    /// hCaptcha site key of Signal's registration captcha
+    #[serde(default = "default_captcha_site_key")]
+    pub site_key: String,
+
+    /// How long to wait for a solution, in seconds
+    #[serde(default = "default_captcha_timeout_secs")]
+    pub timeout_secs: u64,
+}
+
 #[derive(Debug, Clone, Deserialize)]
 pub struct LogConfig {
     /// Log level
@@ -100,6 +150,7 @@ impl Default for RegistryConfig {
         Self {
             path: default_registry_path(),
             persist: true,
+            pending_ttl_secs: default_pending_ttl_secs(),
         }
     }
 }
@@ -109,6 +160,7 @@ impl Default for ServerConfig {
         Self {
             listen_addr: default_listen_addr(),
             port: default_port(),
+            admin_token: None,
         }
     }
 }
@@ -117,6 +169,7 @@ impl Default for DstackConfig {
     fn default() -> Self {
         Self {
             socket_path: default_dstack_socket(),
+            url: None,
         }
     }
 }
@@ -130,6 +183,18 @@ impl Default for RateLimitConfig {
     }
 }
 
+impl Default for CaptchaConfig {
+    fn default() -> Self {
+        Self {
+            provider: CaptchaProviderKind::None,
+            solver_url: None,
+            api_key: None,
+            site_key: default_captcha_site_key(),
+            timeout_secs: default_captcha_timeout_secs(),
+        }
+    }
+}
+
 impl Default for LogConfig {
     fn default() -> Self {
         Self {
@@ -147,6 +212,10 @@ fn default_registry_path() -> PathBuf {
     PathBuf::from("/data/registry.enc")
 }
 
+fn default_pending_ttl_secs() -> u64 {
+    3600
+}
+
 fn default_true() -> bool {
     true
 }
@@ -171,6 +240,14 @@ fn default_per_number_rph() -> u32 {
     3
 }
 
+fn default_captcha_site_key() -> String {
+    crate::captcha::SIGNAL_REGISTRATION_SITE_KEY.into()
+}
+
+fn default_captcha_timeout_secs() -> u64 {
+    120
+}
+
 fn default_log_level() -> String {
     "info".into()
 }
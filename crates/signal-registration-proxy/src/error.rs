@@ -1,6 +1,6 @@
 //! Error types for the registration proxy.
 
-use axum::http::StatusCode;
+use axum::http::{header, HeaderValue, StatusCode};
 use axum::response::{IntoResponse, Response};
 use axum::Json;
 use serde::Serialize;
@@ -18,6 +18,9 @@ pub enum ProxyError {
     #[error("Invalid phone number format: {0}")]
     InvalidPhoneNumber(String),
 
+    #[error("Invalid request: {0}")]
+    InvalidRequest(String),
+
     #[error("Ownership proof mismatch")]
     OwnershipProofMismatch,
 
@@ -27,6 +30,9 @@ pub enum ProxyError {
     #[error("Signal API error: {0}")]
     SignalApi(String),
 
+    #[error("CAPTCHA required. Please provide a captcha token.")]
+    CaptchaRequired,
+
     #[error("Storage error: {0}")]
     Storage(String),
 
@@ -36,9 +42,15 @@ pub enum ProxyError {
     #[error("TEE not available: {0}")]
     TeeNotAvailable(String),
 
+    #[error("Missing or invalid admin token")]
+    Unauthorized,
+
     #[error("Rate limit exceeded")]
     RateLimitExceeded,
 
+    #[error("Too many registration attempts for {number}, retry in {retry_after_secs}s")]
+    NumberRateLimitExceeded { number: String, retry_after_secs: u64 },
+
     #[error("Internal error: {0}")]
     Internal(String),
 }
@@ -56,24 +68,38 @@ impl IntoResponse for ProxyError {
             ProxyError::AlreadyRegistered(_) => (StatusCode::CONFLICT, "ALREADY_REGISTERED"),
             ProxyError::NotFound(_) => (StatusCode::NOT_FOUND, "NOT_FOUND"),
             ProxyError::InvalidPhoneNumber(_) => (StatusCode::BAD_REQUEST, "INVALID_PHONE_NUMBER"),
+            ProxyError::InvalidRequest(_) => (StatusCode::BAD_REQUEST, "INVALID_REQUEST"),
             ProxyError::OwnershipProofMismatch => (StatusCode::FORBIDDEN, "OWNERSHIP_MISMATCH"),
             ProxyError::PendingVerification => (StatusCode::CONFLICT, "PENDING_VERIFICATION"),
             ProxyError::SignalApi(_) => (StatusCode::BAD_GATEWAY, "SIGNAL_API_ERROR"),
+            ProxyError::CaptchaRequired => (StatusCode::BAD_REQUEST, "CAPTCHA_REQUIRED"),
             ProxyError::Storage(_) => (StatusCode::INTERNAL_SERVER_ERROR, "STORAGE_ERROR"),
             ProxyError::Encryption(_) => (StatusCode::INTERNAL_SERVER_ERROR, "ENCRYPTION_ERROR"),
             ProxyError::TeeNotAvailable(_) => {
                 (StatusCode::SERVICE_UNAVAILABLE, "TEE_NOT_AVAILABLE")
             }
+            ProxyError::Unauthorized => (StatusCode::UNAUTHORIZED, "UNAUTHORIZED"),
             ProxyError::RateLimitExceeded => (StatusCode::TOO_MANY_REQUESTS, "RATE_LIMIT_EXCEEDED"),
+            ProxyError::NumberRateLimitExceeded { .. } => {
+                (StatusCode::TOO_MANY_REQUESTS, "NUMBER_RATE_LIMIT_EXCEEDED")
+            }
             ProxyError::Internal(_) => (StatusCode::INTERNAL_SERVER_ERROR, "INTERNAL_ERROR"),
         };
 
+        let retry_after = match &self {
+            ProxyError::NumberRateLimitExceeded { retry_after_secs, .. } => Some(*retry_after_secs),
+            _ => None,
+        };
         let body = ErrorResponse {
             error: self.to_string(),
             code: code.to_string(),
         };
 
-        (status, Json(body)).into_response()
+        let mut response = (status, Json(body)).into_response();
+        if let Some(secs) = retry_after {
+            response.headers_mut().insert(header::RETRY_AFTER, HeaderValue::from(secs));
+        }
+        response
     }
 }
 
@@ -6,6 +6,7 @@
 //! - Persist registration state with TEE-encrypted storage
 
 pub mod api;
+pub mod captcha;
 pub mod config;
 pub mod error;
 pub mod registry;
@@ -13,5 +14,7 @@ pub mod signal;
 
 pub use config::Config;
 pub use error::ProxyError;
-pub use registry::{PhoneNumberRecord, Registry, RegistrationStatus, Store};
+pub use registry::{
+    PhoneNumberRecord, Registry, RegistrationAction, RegistrationEvent, RegistrationStatus, Store,
+};
 pub use signal::SignalRegistrationClient;
@@ -1,15 +1,17 @@
 //! Signal Registration Proxy - Entry point.
 
-use dstack_client::DstackClient;
+use dstack_client::{DstackClient, DstackTransport};
 use signal_registration_proxy::{
     api::{create_router_with_rate_limit, AppState, RateLimitState},
+    captcha,
     config::Config,
     registry::Store,
     signal::SignalRegistrationClient,
 };
 use std::net::SocketAddr;
+use std::time::Duration;
 use tokio::net::TcpListener;
-use tracing::{error, info};
+use tracing::{error, info, warn};
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter};
 
 #[tokio::main]
@@ -35,7 +37,15 @@ async fn main() {
     info!("Starting Signal Registration Proxy");
 
     // Initialize Dstack client for TEE operations
-    let dstack = DstackClient::new(&config.dstack.socket_path);
+    let transport =
+        DstackTransport::from_config(&config.dstack.socket_path, config.dstack.url.as_deref());
+    let dstack = match transport {
+        Ok(transport) => DstackClient::new_with_transport(transport),
+        Err(e) => {
+            error!("Invalid DSTACK__URL: {}", e);
+            std::process::exit(1);
+        }
+    };
 
     // Initialize storage
     let store = if config.registry.persist {
@@ -68,10 +78,56 @@ async fn main() {
     };
 
     // Create application state
-    let state = AppState::new(registry, store, signal_client);
+    let mut state = AppState::new(registry, store, signal_client);
+    match captcha::from_config(&config.captcha) {
+        Ok(Some(provider)) => state = state.with_captcha_provider(provider),
+        Ok(None) => {}
+        Err(e) => {
+            error!("Failed to configure captcha provider: {}", e);
+            std::process::exit(1);
+        }
+    }
+    match config.server.admin_token.as_deref().filter(|t| !t.is_empty()) {
+        Some(token) => state = state.with_admin_token(token),
+        None => info!("No admin token configured, admin and debug endpoints disabled"),
+    }
+
+    // Periodically expire registrations that were never verified
+    if config.registry.pending_ttl_secs > 0 {
+        let ttl = Duration::from_secs(config.registry.pending_ttl_secs);
+        let registry = state.registry.clone();
+        let store = state.store.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(ttl.min(Duration::from_secs(60)));
+            loop {
+                interval.tick().await;
+                let mut registry = registry.write().await;
+                let expired = registry.prune_pending(ttl);
+                if expired > 0 {
+                    info!("Expired {} stale pending registrations", expired);
+                    if let Err(e) = store.save(&registry).await {
+                        warn!("Failed to save registry after expiring registrations: {}", e);
+                    }
+                }
+            }
+        });
+    }
 
     // Create rate limiter from config
-    let rate_limit = RateLimitState::new(config.rate_limit.global_per_minute);
+    let rate_limit = RateLimitState::new(config.rate_limit.global_per_minute)
+        .with_per_number_per_hour(config.rate_limit.per_number_per_hour);
+
+    // Periodically forget numbers whose per-number limit has replenished
+    if rate_limit.per_number.is_some() {
+        let rate_limit = rate_limit.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_secs(600));
+            loop {
+                interval.tick().await;
+                rate_limit.retain_recent();
+            }
+        });
+    }
 
     // Create router with rate limiting
     let app = create_router_with_rate_limit(state, rate_limit);
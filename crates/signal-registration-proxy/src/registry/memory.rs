@@ -1,14 +1,23 @@
 //! In-memory registry implementation.
 
-use super::{PhoneNumberRecord, RegistrationStatus};
+use super::{PhoneNumberRecord, RegistrationEvent, RegistrationStatus};
+use chrono::Utc;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::time::Duration;
+
+/// Most audit events kept; older ones are dropped as new ones arrive.
+const MAX_AUDIT_EVENTS: usize = 10_000;
 
 /// In-memory phone number registry.
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Registry {
     /// Phone number records indexed by normalized phone number
     records: HashMap<String, PhoneNumberRecord>,
+
+    /// Audit log of the latest [`MAX_AUDIT_EVENTS`] lifecycle events, oldest first
+    #[serde(default)]
+    events: Vec<RegistrationEvent>,
 }
 
 impl Registry {
@@ -16,6 +25,7 @@ impl Registry {
     pub fn new() -> Self {
         Self {
             records: HashMap::new(),
+            events: Vec::new(),
         }
     }
 
@@ -80,11 +90,45 @@ impl Registry {
             .filter(|r| r.status == RegistrationStatus::Verified)
             .count()
     }
+
+    /// Append an event to the audit log, dropping the oldest past
+    /// [`MAX_AUDIT_EVENTS`].
+    pub fn record_event(&mut self, event: RegistrationEvent) {
+        self.events.push(event);
+        if self.events.len() > MAX_AUDIT_EVENTS {
+            let excess = self.events.len() - MAX_AUDIT_EVENTS;
+            self.events.drain(..excess);
+        }
+    }
+
+    /// All audit events, oldest first.
+    pub fn events(&self) -> &[RegistrationEvent] {
+        &self.events
+    }
+
+    /// Mark pending registrations started more than `ttl` ago as failed,
+    /// so the number can be registered again. Returns how many expired.
+    pub fn prune_pending(&mut self, ttl: Duration) -> usize {
+        let ttl = chrono::Duration::from_std(ttl).unwrap_or(chrono::Duration::MAX);
+        let Some(cutoff) = Utc::now().checked_sub_signed(ttl) else {
+            return 0;
+        };
+
+        let mut expired = 0;
+        for record in self.records.values_mut() {
+            if record.status == RegistrationStatus::Pending && record.registered_at < cutoff {
+                record.mark_failed();
+                expired += 1;
+            }
+        }
+        expired
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::registry::RegistrationAction;
 
     #[test]
     fn test_registry_insert_and_get() {
@@ -144,4 +188,64 @@ mod tests {
 
         assert!(deserialized.get("+14155551234").is_some());
     }
+
+    #[test]
+    fn test_registry_events_persist() {
+        let mut registry = Registry::new();
+        registry.record_event(RegistrationEvent::new(
+            "+14155551234".into(),
+            RegistrationAction::Registered,
+            true,
+        ));
+
+        let json = serde_json::to_string(&registry).unwrap();
+        let deserialized: Registry = serde_json::from_str(&json).unwrap();
+        assert_eq!(deserialized.events().len(), 1);
+        assert_eq!(deserialized.events()[0].action, RegistrationAction::Registered);
+
+        // Registries saved before the audit log existed still load
+        let legacy: Registry = serde_json::from_str(r#"{"records": {}}"#).unwrap();
+        assert!(legacy.events().is_empty());
+    }
+
+    #[test]
+    fn test_registry_events_capped() {
+        let mut registry = Registry::new();
+        for i in 0..MAX_AUDIT_EVENTS + 5 {
+            registry.record_event(RegistrationEvent::new(
+                format!("+1415555{:04}", i % 10_000),
+                RegistrationAction::Registered,
+                true,
+            ));
+        }
+
+        assert_eq!(registry.events().len(), MAX_AUDIT_EVENTS);
+        assert_eq!(registry.events()[0].phone_number, "+14155550005");
+    }
+
+    #[test]
+    fn test_registry_prune_pending() {
+        let mut registry = Registry::new();
+        registry.insert(
+            "+14155551234".into(),
+            PhoneNumberRecord::new_pending("+14155551234".into(), None, None, None),
+        );
+
+        let mut stale = PhoneNumberRecord::new_pending("+14155555678".into(), None, None, None);
+        stale.registered_at -= chrono::Duration::hours(2);
+        registry.insert("+14155555678".into(), stale);
+
+        let mut verified = PhoneNumberRecord::new_pending("+14155559999".into(), None, None, None);
+        verified.registered_at -= chrono::Duration::hours(2);
+        verified.mark_verified();
+        registry.insert("+14155559999".into(), verified);
+
+        assert_eq!(registry.prune_pending(Duration::from_secs(3600)), 1);
+        assert!(registry.is_pending("+14155551234"));
+        assert_eq!(registry.get("+14155555678").unwrap().status, RegistrationStatus::Failed);
+        assert!(registry.is_registered("+14155559999"));
+
+        // Already expired records aren't counted again
+        assert_eq!(registry.prune_pending(Duration::from_secs(3600)), 0);
+    }
 }
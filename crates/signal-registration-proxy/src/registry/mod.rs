@@ -21,13 +21,54 @@ pub enum RegistrationStatus {
     Failed,
 }
 
+/// A step in a number's registration lifecycle.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum RegistrationAction {
+    /// Registration initiated and a verification code requested
+    Registered,
+    /// Verification code accepted
+    Verified,
+    /// Number removed from Signal and the registry
+    Unregistered,
+}
+
+/// An entry in the registration audit log.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RegistrationEvent {
+    /// The phone number in E.164 format
+    pub phone_number: String,
+
+    /// What happened
+    pub action: RegistrationAction,
+
+    /// When it happened
+    pub timestamp: DateTime<Utc>,
+
+    /// Whether the caller supplied an ownership secret
+    pub ownership_provided: bool,
+}
+
+impl RegistrationEvent {
+    /// Create an event timestamped now.
+    pub fn new(phone_number: String, action: RegistrationAction, ownership_provided: bool) -> Self {
+        Self {
+            phone_number,
+            action,
+            timestamp: Utc::now(),
+            ownership_provided,
+        }
+    }
+}
+
 /// A registered phone number record.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PhoneNumberRecord {
     /// The phone number in E.164 format (e.g., "+14155551234")
     pub phone_number: String,
 
-    /// When the number was first registered
+    /// When the number was registered, or its verification code last
+    /// resent while pending
     pub registered_at: DateTime<Utc>,
 
     /// Registration status
@@ -67,10 +67,8 @@ impl SignalRegistrationClient {
             warn!(status = %status, body = %body, "Signal registration failed");
 
             // Parse specific error types
-            if body.contains("captcha") {
-                return Err(ProxyError::SignalApi(
-                    "CAPTCHA required. Please provide a captcha token.".to_string(),
-                ));
+            if body.to_lowercase().contains("captcha") {
+                return Err(ProxyError::CaptchaRequired);
             }
 
             return Err(ProxyError::SignalApi(format!(
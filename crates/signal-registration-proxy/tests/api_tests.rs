@@ -7,9 +7,14 @@ use axum::{
 use signal_registration_proxy::{
     api::{create_router_with_rate_limit, AppState, RateLimitState},
     registry::{Registry, Store},
-    SignalRegistrationClient,
+    PhoneNumberRecord, SignalRegistrationClient,
 };
+use signal_registration_proxy::captcha::SolverServiceProvider;
+use std::sync::Arc;
+use std::time::Duration;
 use tower::ServiceExt;
+use wiremock::matchers::{body_string_contains, method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
 
 /// Create a test app state with memory-only storage.
 fn create_test_state() -> AppState {
@@ -90,6 +95,129 @@ async fn test_list_accounts_empty() {
     assert!(json["accounts"].as_array().unwrap().is_empty());
 }
 
+#[tokio::test]
+async fn test_list_accounts_paginated() {
+    let mut registry = Registry::new();
+    for i in 0..5 {
+        let number = format!("+1415555000{}", i);
+        registry.insert(
+            number.clone(),
+            PhoneNumberRecord::new_pending(number, None, None, None),
+        );
+    }
+    let store = Store::memory();
+    let signal_client = SignalRegistrationClient::new("http://localhost:9999").unwrap();
+    let state = AppState::new(registry, store, signal_client);
+    let app = create_router_with_rate_limit(state, RateLimitState::permissive());
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .uri("/v1/accounts?limit=2&offset=3")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+
+    assert_eq!(json["total"], 5);
+    assert_eq!(json["offset"], 3);
+    assert_eq!(json["limit"], 2);
+    assert_eq!(json["accounts"].as_array().unwrap().len(), 2);
+}
+
+#[tokio::test]
+async fn test_list_accounts_unpaginated_returns_all() {
+    let mut registry = Registry::new();
+    for i in 0..60 {
+        let number = format!("+141555500{:02}", i);
+        registry.insert(
+            number.clone(),
+            PhoneNumberRecord::new_pending(number, None, None, None),
+        );
+    }
+    let store = Store::memory();
+    let signal_client = SignalRegistrationClient::new("http://localhost:9999").unwrap();
+    let state = AppState::new(registry, store, signal_client);
+    let app = create_router_with_rate_limit(state, RateLimitState::permissive());
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .uri("/v1/accounts")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+
+    // Same shape as before pagination: every account, no page fields
+    assert_eq!(json["total"], 60);
+    assert_eq!(json["accounts"].as_array().unwrap().len(), 60);
+    assert!(json.get("offset").is_none());
+    assert!(json.get("limit").is_none());
+}
+
+#[tokio::test]
+async fn test_bulk_status() {
+    let mut registry = Registry::new();
+    registry.insert(
+        "+14155550000".to_string(),
+        PhoneNumberRecord::new_pending("+14155550000".to_string(), None, None, None),
+    );
+    let signal_client = SignalRegistrationClient::new("http://localhost:9999").unwrap();
+    let state = AppState::new(registry, Store::memory(), signal_client);
+    let app = create_router_with_rate_limit(state, RateLimitState::permissive());
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/v1/status/bulk")
+                .header("content-type", "application/json")
+                .body(Body::from(
+                    r#"{"numbers": ["+1 (415) 555-0000", "+14155559999", "invalid"]}"#,
+                ))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    let results = json["results"].as_array().unwrap();
+    assert_eq!(results.len(), 3);
+
+    assert_eq!(results[0]["phone_number"], "+14155550000");
+    assert_eq!(results[0]["found"], true);
+    assert_eq!(results[0]["status"], "pending");
+
+    assert_eq!(results[1]["found"], false);
+    assert!(results[1]["error"].is_null());
+
+    assert_eq!(results[2]["number"], "invalid");
+    assert_eq!(results[2]["found"], false);
+    assert!(results[2]["error"].as_str().unwrap().contains("Invalid phone number"));
+}
+
 #[tokio::test]
 async fn test_invalid_phone_number() {
     let state = create_test_state();
@@ -141,3 +269,445 @@ async fn test_rate_limiting() {
         .unwrap();
     assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
 }
+
+#[tokio::test]
+async fn test_per_number_rate_limit() {
+    let state = create_test_state();
+    let rate_limit = RateLimitState::permissive().with_per_number_per_hour(2);
+    let app = create_router_with_rate_limit(state, rate_limit);
+
+    let register = |number: &str| {
+        Request::builder()
+            .method("POST")
+            .uri(format!("/v1/register/{}", number))
+            .header("content-type", "application/json")
+            .body(Body::from(r#"{"ownership_secret": "s3cret"}"#))
+            .unwrap()
+    };
+
+    // Signal isn't reachable, but the limiter lets both attempts through
+    for number in ["+14155551234", "%2B14155551234"] {
+        let response = app.clone().oneshot(register(number)).await.unwrap();
+        assert_ne!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    }
+
+    // A formatting variant of the same number is over the cap
+    let response = app.clone().oneshot(register("+1%20415%20555%201234")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    let retry_after: u64 = response.headers()["retry-after"].to_str().unwrap().parse().unwrap();
+    assert!(retry_after > 0 && retry_after <= 1800);
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(json["code"], "NUMBER_RATE_LIMIT_EXCEEDED");
+
+    // Other numbers and other routes are unaffected
+    let response = app.clone().oneshot(register("+14155550000")).await.unwrap();
+    assert_ne!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    let response = app
+        .oneshot(Request::builder().uri("/v1/status/+14155551234").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_per_number_rate_limit_covers_a_full_registration() {
+    let state = create_test_state();
+    let per_hour = signal_registration_proxy::config::RateLimitConfig::default().per_number_per_hour;
+    let rate_limit = RateLimitState::permissive().with_per_number_per_hour(per_hour);
+    let app = create_router_with_rate_limit(state, rate_limit);
+
+    let post = |uri: &str| {
+        Request::builder()
+            .method("POST")
+            .uri(uri)
+            .header("content-type", "application/json")
+            .body(Body::from(r#"{"ownership_secret": "s3cret"}"#))
+            .unwrap()
+    };
+
+    // Register, resend, verify under the default limit
+    for uri in [
+        "/v1/register/+14155551234",
+        "/v1/register/+14155551234/resend",
+        "/v1/register/+14155551234/verify/123456",
+    ] {
+        let response = app.clone().oneshot(post(uri)).await.unwrap();
+        assert_ne!(response.status(), StatusCode::TOO_MANY_REQUESTS, "{}", uri);
+    }
+
+    // The follow-up requests left the registration budget untouched
+    for _ in 1..per_hour {
+        let response = app.clone().oneshot(post("/v1/register/+14155551234")).await.unwrap();
+        assert_ne!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    }
+    let response = app.clone().oneshot(post("/v1/register/+14155551234")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+}
+
+/// Mock Signal API that only registers when a captcha token is supplied.
+async fn captcha_demanding_signal_api() -> MockServer {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v1/register/%2B14155551234"))
+        .and(body_string_contains("signalcaptcha://"))
+        .respond_with(ResponseTemplate::new(201))
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/v1/register/%2B14155551234"))
+        .respond_with(ResponseTemplate::new(400).set_body_string(r#"{"error":"Captcha required for verification"}"#))
+        .mount(&server)
+        .await;
+    server
+}
+
+fn register_request() -> Request<Body> {
+    Request::builder()
+        .method("POST")
+        .uri("/v1/register/+14155551234")
+        .header("content-type", "application/json")
+        .body(Body::from(r#"{"ownership_secret": "s3cret"}"#))
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_register_captcha_required_without_provider() {
+    let signal_api = captcha_demanding_signal_api().await;
+    let state = AppState::new(
+        Registry::new(),
+        Store::memory(),
+        SignalRegistrationClient::new(signal_api.uri()).unwrap(),
+    );
+    let app = create_router_with_rate_limit(state, RateLimitState::permissive());
+
+    let response = app.oneshot(register_request()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(json["code"], "CAPTCHA_REQUIRED");
+}
+
+#[tokio::test]
+async fn test_register_solves_captcha_and_retries() {
+    let signal_api = captcha_demanding_signal_api().await;
+    let solver = MockServer::start().await;
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({ "token": "P1_solved" })))
+        .expect(1)
+        .mount(&solver)
+        .await;
+
+    let provider =
+        SolverServiceProvider::new(solver.uri(), None, "site-key", Duration::from_secs(5)).unwrap();
+    let state = AppState::new(
+        Registry::new(),
+        Store::memory(),
+        SignalRegistrationClient::new(signal_api.uri()).unwrap(),
+    )
+    .with_captcha_provider(Arc::new(provider));
+    let registry = state.registry.clone();
+    let app = create_router_with_rate_limit(state, RateLimitState::permissive());
+
+    let response = app.oneshot(register_request()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(registry.read().await.get("+14155551234").is_some());
+}
+
+async fn get_identity(app: axum::Router, number: &str) -> (StatusCode, serde_json::Value) {
+    let response = app
+        .oneshot(
+            Request::builder()
+                .uri(format!("/v1/accounts/{}/identity", number))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let status = response.status();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    (status, serde_json::from_slice(&body).unwrap())
+}
+
+#[tokio::test]
+async fn test_get_identity() {
+    let signal_api = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/v1/identities/%2B14155551234"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!([
+            {
+                "number": "+15550000000",
+                "status": "TRUSTED_UNVERIFIED",
+                "fingerprint": "05 aa",
+                "safety_number": "11111 22222"
+            },
+            {
+                "number": "+14155551234",
+                "status": "TRUSTED_VERIFIED",
+                "fingerprint": "05 d1 6a 0a",
+                "safety_number": "96616 40685 12345",
+                "uuid": "3f2a"
+            }
+        ])))
+        .mount(&signal_api)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/v1/identities/%2B14155559999"))
+        .respond_with(ResponseTemplate::new(404))
+        .mount(&signal_api)
+        .await;
+
+    let state = AppState::new(
+        Registry::new(),
+        Store::memory(),
+        SignalRegistrationClient::new(signal_api.uri()).unwrap(),
+    );
+    let app = create_router_with_rate_limit(state, RateLimitState::permissive());
+
+    let (status, json) = get_identity(app.clone(), "+14155551234").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(json["phone_number"], "+14155551234");
+    assert_eq!(json["safety_number"], "96616 40685 12345");
+    assert_eq!(json["fingerprint"], "05 d1 6a 0a");
+
+    let (status, json) = get_identity(app, "+14155559999").await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(json["code"], "NOT_FOUND");
+}
+
+fn resend_request(number: &str, body: &str) -> Request<Body> {
+    Request::builder()
+        .method("POST")
+        .uri(format!("/v1/register/{}/resend", number))
+        .header("content-type", "application/json")
+        .body(Body::from(body.to_string()))
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_resend_code() {
+    let signal_api = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v1/register/%2B14155551234"))
+        .respond_with(ResponseTemplate::new(201))
+        .expect(1)
+        .mount(&signal_api)
+        .await;
+
+    let mut registry = Registry::new();
+    registry.insert(
+        "+14155551234".into(),
+        PhoneNumberRecord::new_pending("+14155551234".into(), Some("s3cret"), None, None),
+    );
+    let mut verified = PhoneNumberRecord::new_pending("+14155555678".into(), Some("s3cret"), None, None);
+    verified.mark_verified();
+    registry.insert("+14155555678".into(), verified);
+
+    let state = AppState::new(
+        registry,
+        Store::memory(),
+        SignalRegistrationClient::new(signal_api.uri()).unwrap(),
+    );
+    let registry = state.registry.clone();
+    let app = create_router_with_rate_limit(state, RateLimitState::permissive());
+
+    // Wrong secret is refused before Signal is called
+    let response = app
+        .clone()
+        .oneshot(resend_request("+14155551234", r#"{"ownership_secret": "wrong"}"#))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    let response = app
+        .clone()
+        .oneshot(resend_request("+14155551234", r#"{"ownership_secret": "s3cret"}"#))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(registry.read().await.count(), 2);
+    assert!(registry.read().await.is_pending("+14155551234"));
+
+    // Verified and unknown numbers have nothing to resend
+    for number in ["+14155555678", "+14155559999"] {
+        let response = app
+            .clone()
+            .oneshot(resend_request(number, r#"{"ownership_secret": "s3cret"}"#))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+}
+
+#[tokio::test]
+async fn test_resend_restarts_pending_ttl() {
+    let signal_api = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v1/register/%2B14155551234"))
+        .respond_with(ResponseTemplate::new(201))
+        .mount(&signal_api)
+        .await;
+
+    let mut registry = Registry::new();
+    let mut stale = PhoneNumberRecord::new_pending("+14155551234".into(), Some("s3cret"), None, None);
+    stale.registered_at -= chrono::Duration::hours(2);
+    registry.insert("+14155551234".into(), stale);
+
+    let state = AppState::new(
+        registry,
+        Store::memory(),
+        SignalRegistrationClient::new(signal_api.uri()).unwrap(),
+    );
+    let registry = state.registry.clone();
+    let app = create_router_with_rate_limit(state, RateLimitState::permissive());
+
+    let response = app
+        .oneshot(resend_request("+14155551234", r#"{"ownership_secret": "s3cret"}"#))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // The fresh code is still within an hour's TTL
+    let mut registry = registry.write().await;
+    assert_eq!(registry.prune_pending(Duration::from_secs(3600)), 0);
+    assert!(registry.is_pending("+14155551234"));
+}
+
+async fn debug_accounts_status(app: axum::Router, authorization: Option<&str>) -> StatusCode {
+    let mut request = Request::builder().uri("/v1/debug/signal-accounts");
+    if let Some(value) = authorization {
+        request = request.header("authorization", value);
+    }
+    app.oneshot(request.body(Body::empty()).unwrap())
+        .await
+        .unwrap()
+        .status()
+}
+
+#[tokio::test]
+async fn test_debug_endpoints_require_admin_token() {
+    let signal_api = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/v1/accounts"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!(["+14155551234"])))
+        .mount(&signal_api)
+        .await;
+
+    let state = AppState::new(
+        Registry::new(),
+        Store::memory(),
+        SignalRegistrationClient::new(signal_api.uri()).unwrap(),
+    )
+    .with_admin_token("t0ken");
+    let app = create_router_with_rate_limit(state, RateLimitState::permissive());
+
+    assert_eq!(debug_accounts_status(app.clone(), None).await, StatusCode::UNAUTHORIZED);
+    assert_eq!(
+        debug_accounts_status(app.clone(), Some("Bearer wrong")).await,
+        StatusCode::UNAUTHORIZED
+    );
+    assert_eq!(debug_accounts_status(app.clone(), Some("t0ken")).await, StatusCode::UNAUTHORIZED);
+    assert_eq!(debug_accounts_status(app.clone(), Some("Bearer t0ken")).await, StatusCode::OK);
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/v1/debug/force-unregister/+14155551234")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn test_debug_endpoints_disabled_without_admin_token() {
+    let app = create_router_with_rate_limit(create_test_state(), RateLimitState::permissive());
+    assert_eq!(
+        debug_accounts_status(app, Some("Bearer anything")).await,
+        StatusCode::NOT_FOUND
+    );
+}
+
+fn json_request(method: &str, uri: &str, body: &str) -> Request<Body> {
+    Request::builder()
+        .method(method)
+        .uri(uri)
+        .header("content-type", "application/json")
+        .body(Body::from(body.to_string()))
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_audit_log_records_lifecycle() {
+    let signal_api = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v1/register/%2B14155551234"))
+        .respond_with(ResponseTemplate::new(201))
+        .mount(&signal_api)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/v1/register/%2B14155551234/verify/123456"))
+        .respond_with(ResponseTemplate::new(201))
+        .mount(&signal_api)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/v1/unregister/%2B14155551234"))
+        .respond_with(ResponseTemplate::new(204))
+        .mount(&signal_api)
+        .await;
+
+    let state = AppState::new(
+        Registry::new(),
+        Store::memory(),
+        SignalRegistrationClient::new(signal_api.uri()).unwrap(),
+    )
+    .with_admin_token("t0ken");
+    let app = create_router_with_rate_limit(state, RateLimitState::permissive());
+
+    let secret = r#"{"ownership_secret": "s3cret"}"#;
+    for (method, uri) in [
+        ("POST", "/v1/register/+14155551234"),
+        ("POST", "/v1/register/+14155551234/verify/123456"),
+        ("DELETE", "/v1/unregister/+14155551234"),
+    ] {
+        let response = app.clone().oneshot(json_request(method, uri, secret)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK, "{} {}", method, uri);
+    }
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .uri("/v1/admin/audit")
+                .header("authorization", "Bearer t0ken")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+
+    assert_eq!(json["total"], 3);
+    let actions: Vec<&str> = json["events"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|e| e["action"].as_str().unwrap())
+        .collect();
+    assert_eq!(actions, ["unregistered", "verified", "registered"]);
+    for event in json["events"].as_array().unwrap() {
+        assert_eq!(event["phone_number"], "+14155551234");
+        assert_eq!(event["ownership_provided"], true);
+    }
+}
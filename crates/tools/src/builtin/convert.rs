@@ -0,0 +1,366 @@
+//! Unit and currency conversion tool.
+//!
+//! Length, mass and temperature are converted locally; currencies use live
+//! rates from the Frankfurter API (ECB reference rates, no key required).
+
+use crate::error::ToolError;
+use crate::types::{FunctionDefinition, Tool, ToolDefinition};
+use async_trait::async_trait;
+use reqwest::Client;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tracing::debug;
+
+/// Default Frankfurter API base URL.
+pub const DEFAULT_RATES_URL: &str = "https://api.frankfurter.app";
+
+/// Default time a fetched exchange rate is reused. The ECB publishes once a
+/// day, so there's little to gain from asking more often.
+pub const DEFAULT_RATE_CACHE_TTL: Duration = Duration::from_secs(3600);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Dimension {
+    Length,
+    Mass,
+}
+
+/// Units as (names, dimension, size in metres or kilograms).
+const UNITS: &[(&[&str], Dimension, f64)] = &[
+    (&["mm", "millimeter", "millimeters", "millimetre", "millimetres"], Dimension::Length, 0.001),
+    (&["cm", "centimeter", "centimeters", "centimetre", "centimetres"], Dimension::Length, 0.01),
+    (&["m", "meter", "meters", "metre", "metres"], Dimension::Length, 1.0),
+    (&["km", "kilometer", "kilometers", "kilometre", "kilometres"], Dimension::Length, 1000.0),
+    (&["in", "inch", "inches"], Dimension::Length, 0.0254),
+    (&["ft", "foot", "feet"], Dimension::Length, 0.3048),
+    (&["yd", "yard", "yards"], Dimension::Length, 0.9144),
+    (&["mi", "mile", "miles"], Dimension::Length, 1609.344),
+    (&["nmi", "nautical mile", "nautical miles"], Dimension::Length, 1852.0),
+    (&["mg", "milligram", "milligrams"], Dimension::Mass, 0.000001),
+    (&["g", "gram", "grams"], Dimension::Mass, 0.001),
+    (&["kg", "kilogram", "kilograms", "kilo", "kilos"], Dimension::Mass, 1.0),
+    (&["t", "tonne", "tonnes", "metric ton", "metric tons"], Dimension::Mass, 1000.0),
+    (&["oz", "ounce", "ounces"], Dimension::Mass, 0.028349523125),
+    (&["lb", "lbs", "pound", "pounds"], Dimension::Mass, 0.45359237),
+    (&["st", "stone", "stones"], Dimension::Mass, 6.35029318),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Temperature {
+    Celsius,
+    Fahrenheit,
+    Kelvin,
+}
+
+impl Temperature {
+    fn parse(unit: &str) -> Option<Self> {
+        match unit {
+            "c" | "°c" | "celsius" | "degc" => Some(Self::Celsius),
+            "f" | "°f" | "fahrenheit" | "degf" => Some(Self::Fahrenheit),
+            "k" | "kelvin" => Some(Self::Kelvin),
+            _ => None,
+        }
+    }
+
+    fn to_celsius(self, value: f64) -> f64 {
+        match self {
+            Self::Celsius => value,
+            Self::Fahrenheit => (value - 32.0) * 5.0 / 9.0,
+            Self::Kelvin => value - 273.15,
+        }
+    }
+
+    fn celsius_to(self, celsius: f64) -> f64 {
+        match self {
+            Self::Celsius => celsius,
+            Self::Fahrenheit => celsius * 9.0 / 5.0 + 32.0,
+            Self::Kelvin => celsius + 273.15,
+        }
+    }
+}
+
+/// Convert between units of length, mass, temperature and currency.
+pub struct ConvertTool {
+    client: Client,
+    base_url: String,
+    cache_ttl: Duration,
+    /// Rates keyed on (from, to) currency codes.
+    cache: Mutex<HashMap<(String, String), (Instant, RateQuote)>>,
+}
+
+#[derive(Deserialize)]
+struct ConvertArgs {
+    value: f64,
+    from: String,
+    to: String,
+}
+
+#[derive(Deserialize)]
+struct RatesResponse {
+    date: String,
+    rates: HashMap<String, f64>,
+}
+
+#[derive(Debug, Clone)]
+struct RateQuote {
+    rate: f64,
+    date: String,
+}
+
+impl ConvertTool {
+    pub fn new() -> Self {
+        Self {
+            client: Client::new(),
+            base_url: DEFAULT_RATES_URL.into(),
+            cache_ttl: DEFAULT_RATE_CACHE_TTL,
+            cache: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Use a different Frankfurter-compatible rates API.
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into().trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Set how long a fetched rate is reused (zero disables caching).
+    pub fn with_cache_ttl(mut self, ttl: Duration) -> Self {
+        self.cache_ttl = ttl;
+        self
+    }
+
+    /// Convert length, mass or temperature locally.
+    fn convert_unit(value: f64, from: &str, to: &str) -> Option<f64> {
+        if let (Some(from), Some(to)) = (Temperature::parse(from), Temperature::parse(to)) {
+            return Some(to.celsius_to(from.to_celsius(value)));
+        }
+
+        let lookup = |unit: &str| {
+            UNITS
+                .iter()
+                .find(|(names, _, _)| names.contains(&unit))
+                .map(|&(_, dimension, size)| (dimension, size))
+        };
+        match (lookup(from), lookup(to)) {
+            (Some((from_dim, from_size)), Some((to_dim, to_size))) if from_dim == to_dim => {
+                Some(value * from_size / to_size)
+            }
+            _ => None,
+        }
+    }
+
+    fn is_currency_code(code: &str) -> bool {
+        code.len() == 3 && code.chars().all(|c| c.is_ascii_alphabetic())
+    }
+
+    async fn rate(&self, from: &str, to: &str) -> Result<RateQuote, ToolError> {
+        let key = (from.to_string(), to.to_string());
+        {
+            let cache = self.cache.lock().unwrap_or_else(|e| e.into_inner());
+            if let Some((fetched, quote)) = cache.get(&key) {
+                if fetched.elapsed() < self.cache_ttl {
+                    debug!(from = %from, to = %to, "Using cached exchange rate");
+                    return Ok(quote.clone());
+                }
+            }
+        }
+
+        debug!(from = %from, to = %to, "Fetching exchange rate");
+        let response = self
+            .client
+            .get(format!("{}/latest", self.base_url))
+            .query(&[("from", from), ("to", to)])
+            .send()
+            .await?;
+        if response.status() == 404 || response.status() == 422 {
+            return Err(unsupported(from, to));
+        }
+        if !response.status().is_success() {
+            return Err(ToolError::ExternalService(format!(
+                "Exchange rate API error: {}",
+                response.status()
+            )));
+        }
+        let rates: RatesResponse = response.json().await?;
+        let quote = RateQuote {
+            rate: *rates.rates.get(to).ok_or_else(|| unsupported(from, to))?,
+            date: rates.date,
+        };
+
+        let mut cache = self.cache.lock().unwrap_or_else(|e| e.into_inner());
+        cache.retain(|_, (fetched, _)| fetched.elapsed() < self.cache_ttl);
+        cache.insert(key, (Instant::now(), quote.clone()));
+        Ok(quote)
+    }
+}
+
+impl Default for ConvertTool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn unsupported(from: &str, to: &str) -> ToolError {
+    ToolError::InvalidArguments(format!(
+        "Unsupported conversion from '{}' to '{}'. Supported: length (mm, cm, m, km, in, ft, yd, mi, nmi), \
+         mass (mg, g, kg, t, oz, lb, st), temperature (C, F, K) and currencies by ISO code (e.g. USD, EUR).",
+        from, to
+    ))
+}
+
+/// Round away floating point noise without hiding meaningful digits.
+fn round(value: f64) -> f64 {
+    (value * 1e6).round() / 1e6
+}
+
+#[async_trait]
+impl Tool for ConvertTool {
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            tool_type: "function".into(),
+            function: FunctionDefinition {
+                name: "convert".into(),
+                description: "Convert a value between units of length, mass or temperature, or between currencies at the latest exchange rate.".into(),
+                parameters: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "value": {
+                            "type": "number",
+                            "description": "The amount to convert"
+                        },
+                        "from": {
+                            "type": "string",
+                            "description": "Unit or ISO currency code to convert from (e.g., 'km', 'lb', 'F', 'USD')"
+                        },
+                        "to": {
+                            "type": "string",
+                            "description": "Unit or ISO currency code to convert to (e.g., 'mi', 'kg', 'C', 'EUR')"
+                        }
+                    },
+                    "required": ["value", "from", "to"]
+                }),
+            },
+        }
+    }
+
+    fn name(&self) -> &str {
+        "convert"
+    }
+
+    async fn execute(&self, arguments: &str) -> Result<String, ToolError> {
+        let args: ConvertArgs = serde_json::from_str(arguments)
+            .map_err(|e| ToolError::InvalidArguments(e.to_string()))?;
+        let from = args.from.trim().to_lowercase();
+        let to = args.to.trim().to_lowercase();
+
+        if let Some(result) = Self::convert_unit(args.value, &from, &to) {
+            return Ok(serde_json::json!({
+                "value": args.value,
+                "from": args.from.trim(),
+                "to": args.to.trim(),
+                "result": round(result),
+            })
+            .to_string());
+        }
+
+        if Self::is_currency_code(&from) && Self::is_currency_code(&to) {
+            let (from, to) = (from.to_uppercase(), to.to_uppercase());
+            let quote = if from == to {
+                RateQuote { rate: 1.0, date: String::new() }
+            } else {
+                self.rate(&from, &to).await?
+            };
+            return Ok(serde_json::json!({
+                "value": args.value,
+                "from": from,
+                "to": to,
+                "result": (args.value * quote.rate * 100.0).round() / 100.0,
+                "rate": quote.rate,
+                "rate_date": quote.date,
+            })
+            .to_string());
+        }
+
+        Err(unsupported(args.from.trim(), args.to.trim()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{method, path, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    async fn convert(tool: &ConvertTool, value: f64, from: &str, to: &str) -> Result<serde_json::Value, ToolError> {
+        let args = serde_json::json!({ "value": value, "from": from, "to": to }).to_string();
+        Ok(serde_json::from_str(&tool.execute(&args).await?).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_temperature() {
+        let tool = ConvertTool::new();
+        assert_eq!(convert(&tool, 100.0, "C", "F").await.unwrap()["result"], 212.0);
+        assert_eq!(convert(&tool, 98.6, "fahrenheit", "celsius").await.unwrap()["result"], 37.0);
+        assert_eq!(convert(&tool, -40.0, "°F", "°C").await.unwrap()["result"], -40.0);
+        assert_eq!(convert(&tool, 0.0, "K", "C").await.unwrap()["result"], -273.15);
+    }
+
+    #[tokio::test]
+    async fn test_length_and_mass() {
+        let tool = ConvertTool::new();
+        assert_eq!(convert(&tool, 10.0, "km", "mi").await.unwrap()["result"], 6.213712);
+        assert_eq!(convert(&tool, 6.0, "feet", "m").await.unwrap()["result"], 1.8288);
+        assert_eq!(convert(&tool, 1.0, "kg", "lb").await.unwrap()["result"], 2.204623);
+    }
+
+    #[tokio::test]
+    async fn test_unsupported_conversion() {
+        let tool = ConvertTool::new();
+        for (from, to) in [("kg", "km"), ("C", "m"), ("furlong", "m"), ("lightyear", "parsec")] {
+            let err = convert(&tool, 1.0, from, to).await.unwrap_err();
+            assert!(matches!(err, ToolError::InvalidArguments(ref m) if m.contains("Unsupported conversion")), "{} -> {}", from, to);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_currency_rate_is_fetched_once_and_cached() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/latest"))
+            .and(query_param("from", "USD"))
+            .and(query_param("to", "EUR"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "amount": 1.0,
+                "base": "USD",
+                "date": "2024-06-14",
+                "rates": { "EUR": 0.9342 }
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let tool = ConvertTool::new().with_base_url(server.uri());
+        let result = convert(&tool, 250.0, "usd", "EUR").await.unwrap();
+        assert_eq!(result["result"], 233.55);
+        assert_eq!(result["rate"], 0.9342);
+        assert_eq!(result["rate_date"], "2024-06-14");
+
+        let again = convert(&tool, 10.0, "USD", "EUR").await.unwrap();
+        assert_eq!(again["result"], 9.34);
+    }
+
+    #[tokio::test]
+    async fn test_unknown_currency() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/latest"))
+            .respond_with(ResponseTemplate::new(404).set_body_json(serde_json::json!({ "message": "not found" })))
+            .mount(&server)
+            .await;
+
+        let tool = ConvertTool::new().with_base_url(server.uri());
+        let err = convert(&tool, 1.0, "USD", "XYZ").await.unwrap_err();
+        assert!(matches!(err, ToolError::InvalidArguments(ref m) if m.contains("Unsupported conversion")));
+    }
+}
@@ -0,0 +1,344 @@
+//! Crypto price tool using the CoinGecko API.
+
+use crate::error::ToolError;
+use crate::types::{FunctionDefinition, Tool, ToolDefinition};
+use async_trait::async_trait;
+use reqwest::Client;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tracing::debug;
+
+/// Default CoinGecko API base URL (free tier, no key required).
+pub const DEFAULT_COINGECKO_URL: &str = "https://api.coingecko.com/api/v3";
+
+/// Default time a fetched price is reused before querying again.
+pub const DEFAULT_PRICE_CACHE_TTL: Duration = Duration::from_secs(60);
+
+/// Ticker symbols whose CoinGecko id is unambiguous, so lookups skip `/search`.
+const KNOWN_IDS: &[(&str, &str)] = &[
+    ("btc", "bitcoin"),
+    ("eth", "ethereum"),
+    ("sol", "solana"),
+    ("near", "near"),
+    ("usdc", "usd-coin"),
+    ("usdt", "tether"),
+    ("bnb", "binancecoin"),
+    ("xrp", "ripple"),
+    ("ada", "cardano"),
+    ("doge", "dogecoin"),
+    ("dot", "polkadot"),
+    ("atom", "cosmos"),
+    ("avax", "avalanche-2"),
+    ("matic", "matic-network"),
+    ("ltc", "litecoin"),
+];
+
+/// Crypto price tool backed by CoinGecko.
+pub struct CryptoPriceTool {
+    client: Client,
+    base_url: String,
+    cache_ttl: Duration,
+    /// Formatted results keyed on (coin id, quote currency).
+    cache: Mutex<HashMap<(String, String), (Instant, String)>>,
+}
+
+#[derive(Deserialize)]
+struct PriceArgs {
+    symbol: String,
+    #[serde(default = "default_currency")]
+    currency: String,
+}
+
+fn default_currency() -> String {
+    "usd".into()
+}
+
+#[derive(Deserialize)]
+struct SearchResponse {
+    #[serde(default)]
+    coins: Vec<SearchCoin>,
+}
+
+#[derive(Deserialize)]
+struct SearchCoin {
+    id: String,
+    symbol: String,
+}
+
+impl CryptoPriceTool {
+    /// Create a crypto price tool using the public CoinGecko API.
+    pub fn new() -> Self {
+        Self {
+            client: Client::new(),
+            base_url: DEFAULT_COINGECKO_URL.into(),
+            cache_ttl: DEFAULT_PRICE_CACHE_TTL,
+            cache: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Use a different CoinGecko-compatible API (e.g. the Pro endpoint).
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into().trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Set how long a fetched price is reused (zero disables caching).
+    pub fn with_cache_ttl(mut self, ttl: Duration) -> Self {
+        self.cache_ttl = ttl;
+        self
+    }
+
+    fn cached(&self, key: &(String, String)) -> Option<String> {
+        let cache = self.cache.lock().unwrap_or_else(|e| e.into_inner());
+        cache
+            .get(key)
+            .filter(|(fetched, _)| fetched.elapsed() < self.cache_ttl)
+            .map(|(_, result)| result.clone())
+    }
+
+    fn store(&self, key: (String, String), result: &str) {
+        let mut cache = self.cache.lock().unwrap_or_else(|e| e.into_inner());
+        cache.retain(|_, (fetched, _)| fetched.elapsed() < self.cache_ttl);
+        cache.insert(key, (Instant::now(), result.to_string()));
+    }
+
+    /// Resolve a ticker symbol (or CoinGecko id) to a CoinGecko id.
+    async fn resolve_id(&self, symbol: &str) -> Result<String, ToolError> {
+        if let Some((_, id)) = KNOWN_IDS.iter().find(|(s, _)| *s == symbol) {
+            return Ok(id.to_string());
+        }
+
+        let response = self
+            .client
+            .get(format!("{}/search", self.base_url))
+            .query(&[("query", symbol)])
+            .send()
+            .await?;
+        let search: SearchResponse = Self::check(response).await?.json().await?;
+
+        // Results are ranked by market cap, so the first exact match is the
+        // token people usually mean
+        search
+            .coins
+            .into_iter()
+            .find(|c| c.symbol.eq_ignore_ascii_case(symbol) || c.id == symbol)
+            .map(|c| c.id)
+            .ok_or_else(|| {
+                ToolError::InvalidArguments(format!(
+                    "Unknown token '{}'. Ask the user which token they mean (e.g. its full name).",
+                    symbol
+                ))
+            })
+    }
+
+    async fn check(response: reqwest::Response) -> Result<reqwest::Response, ToolError> {
+        if response.status() == 429 {
+            return Err(ToolError::RateLimit);
+        }
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(ToolError::ExternalService(format!(
+                "CoinGecko API error: {} - {}",
+                status, body
+            )));
+        }
+        Ok(response)
+    }
+}
+
+impl Default for CryptoPriceTool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl Tool for CryptoPriceTool {
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            tool_type: "function".into(),
+            function: FunctionDefinition {
+                name: "get_crypto_price".into(),
+                description: "Get the current price, 24h change and market cap of a cryptocurrency. Use whenever the user asks about token prices; your own knowledge of prices is out of date.".into(),
+                parameters: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "symbol": {
+                            "type": "string",
+                            "description": "Token ticker symbol or name (e.g., 'BTC', 'ETH', 'near')"
+                        },
+                        "currency": {
+                            "type": "string",
+                            "description": "Quote currency (e.g., 'usd', 'eur', 'btc'). Defaults to 'usd'."
+                        }
+                    },
+                    "required": ["symbol"]
+                }),
+            },
+        }
+    }
+
+    fn name(&self) -> &str {
+        "get_crypto_price"
+    }
+
+    async fn execute(&self, arguments: &str) -> Result<String, ToolError> {
+        let args: PriceArgs = serde_json::from_str(arguments)
+            .map_err(|e| ToolError::InvalidArguments(e.to_string()))?;
+
+        let symbol = args.symbol.trim().to_lowercase();
+        let currency = args.currency.trim().to_lowercase();
+        if symbol.is_empty() {
+            return Err(ToolError::InvalidArguments("Empty symbol".into()));
+        }
+
+        let id = self.resolve_id(&symbol).await?;
+        let key = (id.clone(), currency.clone());
+        if let Some(result) = self.cached(&key) {
+            debug!(id = %id, currency = %currency, "Using cached crypto price");
+            return Ok(result);
+        }
+
+        debug!(id = %id, currency = %currency, "Fetching crypto price");
+
+        let response = self
+            .client
+            .get(format!("{}/simple/price", self.base_url))
+            .query(&[
+                ("ids", id.as_str()),
+                ("vs_currencies", currency.as_str()),
+                ("include_24hr_change", "true"),
+                ("include_market_cap", "true"),
+            ])
+            .send()
+            .await?;
+        let prices: HashMap<String, HashMap<String, Option<f64>>> =
+            Self::check(response).await?.json().await?;
+
+        let quote = prices.get(&id).ok_or_else(|| {
+            ToolError::InvalidArguments(format!(
+                "No price available for '{}'. Ask the user which token they mean.",
+                args.symbol.trim()
+            ))
+        })?;
+        let price = quote.get(&currency).copied().flatten().ok_or_else(|| {
+            ToolError::InvalidArguments(format!("Unsupported quote currency '{}'", currency))
+        })?;
+
+        let result = serde_json::json!({
+            "symbol": symbol.to_uppercase(),
+            "id": id,
+            "currency": currency,
+            "price": price,
+            "change_24h_percent": quote.get(&format!("{}_24h_change", currency)).copied().flatten(),
+            "market_cap": quote.get(&format!("{}_market_cap", currency)).copied().flatten(),
+        })
+        .to_string();
+
+        self.store(key, &result);
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{method, path, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    async fn mock_price(server: &MockServer, id: &str) {
+        Mock::given(method("GET"))
+            .and(path("/simple/price"))
+            .and(query_param("ids", id))
+            .and(query_param("vs_currencies", "usd"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                id: {
+                    "usd": 3.25,
+                    "usd_24h_change": -1.5,
+                    "usd_market_cap": 3_900_000_000.0
+                }
+            })))
+            .expect(1)
+            .mount(server)
+            .await;
+    }
+
+    #[test]
+    fn test_definition() {
+        let tool = CryptoPriceTool::new();
+        let def = tool.definition();
+
+        assert_eq!(def.tool_type, "function");
+        assert_eq!(def.function.name, "get_crypto_price");
+    }
+
+    #[tokio::test]
+    async fn test_price_is_fetched_once_and_cached() {
+        let server = MockServer::start().await;
+        mock_price(&server, "near").await;
+
+        let tool = CryptoPriceTool::new().with_base_url(server.uri());
+        let first = tool.execute(r#"{"symbol": "NEAR"}"#).await.unwrap();
+        let second = tool.execute(r#"{"symbol": "near", "currency": "USD"}"#).await.unwrap();
+        assert_eq!(first, second);
+
+        let result: serde_json::Value = serde_json::from_str(&first).unwrap();
+        assert_eq!(result["symbol"], "NEAR");
+        assert_eq!(result["price"], 3.25);
+        assert_eq!(result["change_24h_percent"], -1.5);
+        assert_eq!(result["market_cap"], 3_900_000_000.0);
+    }
+
+    #[tokio::test]
+    async fn test_symbol_resolved_via_search() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/search"))
+            .and(query_param("query", "arb"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "coins": [
+                    { "id": "arbitrum", "symbol": "ARB", "name": "Arbitrum" },
+                    { "id": "arb-clone", "symbol": "ARB", "name": "Clone" }
+                ]
+            })))
+            .mount(&server)
+            .await;
+        mock_price(&server, "arbitrum").await;
+
+        let tool = CryptoPriceTool::new().with_base_url(server.uri());
+        let result: serde_json::Value =
+            serde_json::from_str(&tool.execute(r#"{"symbol": "ARB"}"#).await.unwrap()).unwrap();
+        assert_eq!(result["id"], "arbitrum");
+    }
+
+    #[tokio::test]
+    async fn test_unknown_symbol() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/search"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({ "coins": [] })))
+            .mount(&server)
+            .await;
+
+        let tool = CryptoPriceTool::new().with_base_url(server.uri());
+        let err = tool.execute(r#"{"symbol": "notacoin"}"#).await.unwrap_err();
+        assert!(matches!(err, ToolError::InvalidArguments(ref msg) if msg.contains("Unknown token")));
+    }
+
+    #[tokio::test]
+    async fn test_rate_limited() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/simple/price"))
+            .respond_with(ResponseTemplate::new(429))
+            .mount(&server)
+            .await;
+
+        let tool = CryptoPriceTool::new().with_base_url(server.uri());
+        let err = tool.execute(r#"{"symbol": "btc"}"#).await.unwrap_err();
+        assert!(matches!(err, ToolError::RateLimit));
+    }
+}
@@ -0,0 +1,142 @@
+//! Date/time tool - current local time in any IANA timezone.
+
+use crate::error::ToolError;
+use crate::types::{FunctionDefinition, Tool, ToolDefinition};
+use async_trait::async_trait;
+use chrono::{DateTime, Offset, Utc};
+use chrono_tz::Tz;
+use serde::Deserialize;
+
+/// Current time in a timezone, with its UTC offset.
+pub struct DateTimeTool;
+
+#[derive(Deserialize)]
+struct DateTimeArgs {
+    timezone: String,
+}
+
+impl DateTimeTool {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Describe `now` as seen in `tz`.
+    fn describe(now: DateTime<Utc>, tz: Tz) -> serde_json::Value {
+        let local = now.with_timezone(&tz);
+        let offset_secs = local.offset().fix().local_minus_utc();
+        let sign = if offset_secs < 0 { '-' } else { '+' };
+        let offset_secs = offset_secs.abs();
+
+        serde_json::json!({
+            "timezone": tz.name(),
+            "local_time": local.format("%Y-%m-%d %H:%M:%S").to_string(),
+            "weekday": local.format("%A").to_string(),
+            "abbreviation": local.format("%Z").to_string(),
+            "utc_offset": format!("{}{:02}:{:02}", sign, offset_secs / 3600, offset_secs % 3600 / 60),
+            "utc_time": now.format("%Y-%m-%d %H:%M:%S").to_string(),
+        })
+    }
+}
+
+impl Default for DateTimeTool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl Tool for DateTimeTool {
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            tool_type: "function".into(),
+            function: FunctionDefinition {
+                name: "get_current_time".into(),
+                description: "Get the current date and time in a timezone, with its UTC offset (daylight saving time included). Use for questions like 'what time is it in Tokyo?'.".into(),
+                parameters: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "timezone": {
+                            "type": "string",
+                            "description": "IANA timezone name (e.g., 'Asia/Tokyo', 'America/New_York', 'Europe/London', 'UTC')"
+                        }
+                    },
+                    "required": ["timezone"]
+                }),
+            },
+        }
+    }
+
+    fn name(&self) -> &str {
+        "get_current_time"
+    }
+
+    async fn execute(&self, arguments: &str) -> Result<String, ToolError> {
+        let args: DateTimeArgs = serde_json::from_str(arguments)
+            .map_err(|e| ToolError::InvalidArguments(e.to_string()))?;
+
+        let name = args.timezone.trim();
+        let tz: Tz = name.parse().map_err(|_| {
+            ToolError::InvalidArguments(format!(
+                "Unknown timezone '{}'. Use an IANA timezone name such as 'Asia/Tokyo', \
+                 'America/New_York' or 'Europe/Paris', not a city or abbreviation.",
+                name
+            ))
+        })?;
+
+        Ok(Self::describe(Utc::now(), tz).to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_definition() {
+        let def = DateTimeTool::new().definition();
+        assert_eq!(def.function.name, "get_current_time");
+    }
+
+    #[tokio::test]
+    async fn test_valid_timezone() {
+        let result = DateTimeTool::new()
+            .execute(r#"{"timezone": "Asia/Tokyo"}"#)
+            .await
+            .unwrap();
+        let result: serde_json::Value = serde_json::from_str(&result).unwrap();
+
+        assert_eq!(result["timezone"], "Asia/Tokyo");
+        assert_eq!(result["utc_offset"], "+09:00");
+        assert_eq!(result["abbreviation"], "JST");
+    }
+
+    #[tokio::test]
+    async fn test_invalid_timezone() {
+        let err = DateTimeTool::new()
+            .execute(r#"{"timezone": "Tokyo Time"}"#)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ToolError::InvalidArguments(ref m) if m.contains("IANA timezone name")));
+    }
+
+    #[test]
+    fn test_offset_follows_daylight_saving() {
+        let tz: Tz = "America/New_York".parse().unwrap();
+
+        let summer = DateTimeTool::describe(Utc.with_ymd_and_hms(2024, 7, 1, 16, 0, 0).unwrap(), tz);
+        assert_eq!(summer["utc_offset"], "-04:00");
+        assert_eq!(summer["abbreviation"], "EDT");
+        assert_eq!(summer["local_time"], "2024-07-01 12:00:00");
+        assert_eq!(summer["weekday"], "Monday");
+
+        let winter = DateTimeTool::describe(Utc.with_ymd_and_hms(2024, 1, 15, 16, 0, 0).unwrap(), tz);
+        assert_eq!(winter["utc_offset"], "-05:00");
+        assert_eq!(winter["abbreviation"], "EST");
+        assert_eq!(winter["local_time"], "2024-01-15 11:00:00");
+
+        // Half-hour zones keep their minutes
+        let india = DateTimeTool::describe(Utc.with_ymd_and_hms(2024, 1, 15, 0, 0, 0).unwrap(), "Asia/Kolkata".parse().unwrap());
+        assert_eq!(india["utc_offset"], "+05:30");
+    }
+}
@@ -0,0 +1,563 @@
+//! URL fetcher tool - reads the text of a web page.
+
+use crate::error::ToolError;
+use crate::types::{FunctionDefinition, Tool, ToolDefinition};
+use async_trait::async_trait;
+use reqwest::redirect::Policy;
+use reqwest::{header, Client, Url};
+use serde::Deserialize;
+use std::net::{IpAddr, SocketAddr};
+use std::time::Duration;
+use tracing::debug;
+
+/// Default cap on how much of a response body is downloaded.
+pub const DEFAULT_FETCH_MAX_BYTES: usize = 512 * 1024;
+
+/// Default time allowed for the whole request, body included.
+pub const DEFAULT_FETCH_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Redirects followed before giving up. Each hop is checked like the
+/// original URL, so a public page can't redirect into the private network.
+const MAX_REDIRECTS: usize = 5;
+
+/// Elements whose content is never readable text.
+const SKIPPED_ELEMENTS: &[&str] = &["script", "style", "noscript", "template", "svg", "head"];
+
+/// Elements that start a new line in the extracted text.
+const BLOCK_ELEMENTS: &[&str] = &[
+    "p", "div", "br", "li", "ul", "ol", "tr", "table", "section", "article", "header",
+    "footer", "nav", "aside", "main", "h1", "h2", "h3", "h4", "h5", "h6", "pre",
+    "blockquote", "hr", "dd", "dt", "figcaption",
+];
+
+/// Fetches http(s) URLs and returns their text content.
+///
+/// Only public addresses are contacted: hostnames are resolved up front,
+/// every address is checked, and the request is pinned to the checked
+/// address so DNS can't be swapped in between.
+pub struct FetchUrlTool {
+    max_bytes: usize,
+    timeout: Duration,
+    /// A private address that may be contacted anyway (tests against a
+    /// local server).
+    allowed_private: Option<IpAddr>,
+}
+
+#[derive(Deserialize)]
+struct FetchArgs {
+    url: String,
+}
+
+impl FetchUrlTool {
+    pub fn new() -> Self {
+        Self {
+            max_bytes: DEFAULT_FETCH_MAX_BYTES,
+            timeout: DEFAULT_FETCH_TIMEOUT,
+            allowed_private: None,
+        }
+    }
+
+    /// Set how many bytes of a response are downloaded; the rest is dropped.
+    pub fn with_max_bytes(mut self, max_bytes: usize) -> Self {
+        self.max_bytes = max_bytes;
+        self
+    }
+
+    /// Set the timeout for each request.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Allow the loopback address local test servers listen on.
+    #[cfg(test)]
+    fn allowing_local(mut self) -> Self {
+        self.allowed_private = Some(IpAddr::from([127, 0, 0, 1]));
+        self
+    }
+
+    /// Check the scheme and resolve the host to a public address.
+    async fn resolve(&self, url: &Url) -> Result<SocketAddr, ToolError> {
+        if !matches!(url.scheme(), "http" | "https") {
+            return Err(ToolError::InvalidArguments(format!(
+                "Only http and https URLs can be fetched, not '{}'",
+                url.scheme()
+            )));
+        }
+        let host = url
+            .host_str()
+            .ok_or_else(|| ToolError::InvalidArguments("URL has no host".into()))?;
+        let port = url.port_or_known_default().unwrap_or(80);
+
+        let addrs: Vec<SocketAddr> = match url.host() {
+            Some(url::Host::Ipv4(ip)) => vec![SocketAddr::new(ip.into(), port)],
+            Some(url::Host::Ipv6(ip)) => vec![SocketAddr::new(ip.into(), port)],
+            _ => tokio::net::lookup_host((host, port))
+                .await
+                .map_err(|e| ToolError::ExternalService(format!("Could not resolve {}: {}", host, e)))?
+                .collect(),
+        };
+
+        // Every address must be public, or a second lookup could pick a private one
+        let blocked = addrs
+            .iter()
+            .find(|a| !is_public(a.ip()) && Some(a.ip()) != self.allowed_private);
+        if let Some(addr) = blocked {
+            return Err(ToolError::InvalidArguments(format!(
+                "{} resolves to a private address ({}) and can't be fetched",
+                host,
+                addr.ip()
+            )));
+        }
+        addrs
+            .into_iter()
+            .next()
+            .ok_or_else(|| ToolError::ExternalService(format!("Could not resolve {}", host)))
+    }
+
+    async fn get(&self, url: &Url, addr: SocketAddr) -> Result<reqwest::Response, ToolError> {
+        // No proxy: it would connect on our behalf, bypassing the pinned address
+        let mut builder = Client::builder()
+            .timeout(self.timeout)
+            .redirect(Policy::none())
+            .no_proxy();
+        if let Some(url::Host::Domain(domain)) = url.host() {
+            builder = builder.resolve(domain, addr);
+        }
+        let client = builder
+            .build()
+            .map_err(|e| ToolError::ExternalService(format!("Failed to create HTTP client: {}", e)))?;
+        Ok(client.get(url.clone()).send().await?)
+    }
+
+    /// Read up to `max_bytes` of the body; returns whether it was cut short.
+    async fn read_capped(&self, mut response: reqwest::Response) -> Result<(Vec<u8>, bool), ToolError> {
+        let mut body = Vec::new();
+        while let Some(chunk) = response.chunk().await? {
+            let room = self.max_bytes - body.len();
+            if chunk.len() > room {
+                body.extend_from_slice(&chunk[..room]);
+                return Ok((body, true));
+            }
+            body.extend_from_slice(&chunk);
+        }
+        Ok((body, false))
+    }
+}
+
+impl Default for FetchUrlTool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl Tool for FetchUrlTool {
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            tool_type: "function".into(),
+            function: FunctionDefinition {
+                name: "fetch_url".into(),
+                description: "Fetch a web page and return its readable text. Use to read a link from web_search results or one the user shared.".into(),
+                parameters: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "url": {
+                            "type": "string",
+                            "description": "The http or https URL to fetch"
+                        }
+                    },
+                    "required": ["url"]
+                }),
+            },
+        }
+    }
+
+    fn name(&self) -> &str {
+        "fetch_url"
+    }
+
+    async fn execute(&self, arguments: &str) -> Result<String, ToolError> {
+        let args: FetchArgs = serde_json::from_str(arguments)
+            .map_err(|e| ToolError::InvalidArguments(e.to_string()))?;
+        let mut url = Url::parse(args.url.trim())
+            .map_err(|e| ToolError::InvalidArguments(format!("Invalid URL: {}", e)))?;
+
+        for _ in 0..=MAX_REDIRECTS {
+            let addr = self.resolve(&url).await?;
+            debug!(url = %url, "Fetching URL");
+            let response = self.get(&url, addr).await?;
+
+            if response.status().is_redirection() {
+                let location = response
+                    .headers()
+                    .get(header::LOCATION)
+                    .and_then(|l| l.to_str().ok())
+                    .ok_or_else(|| ToolError::ExternalService("Redirect without a location".into()))?;
+                url = url
+                    .join(location)
+                    .map_err(|e| ToolError::ExternalService(format!("Invalid redirect: {}", e)))?;
+                continue;
+            }
+
+            if !response.status().is_success() {
+                return Err(ToolError::ExternalService(format!(
+                    "{} returned {}",
+                    url,
+                    response.status()
+                )));
+            }
+
+            let content_type = response
+                .headers()
+                .get(header::CONTENT_TYPE)
+                .and_then(|c| c.to_str().ok())
+                .and_then(|c| c.split(';').next())
+                .map(|c| c.trim().to_ascii_lowercase())
+                .unwrap_or_default();
+            if !is_text(&content_type) {
+                return Err(ToolError::InvalidArguments(format!(
+                    "{} is not a text page (content type '{}')",
+                    url, content_type
+                )));
+            }
+
+            let (body, truncated) = self.read_capped(response).await?;
+            let body = String::from_utf8_lossy(&body);
+            let (title, content) = if content_type.contains("html") {
+                html_to_text(&body)
+            } else {
+                (None, body.trim().to_string())
+            };
+
+            return Ok(serde_json::json!({
+                "url": url.as_str(),
+                "content_type": content_type,
+                "title": title,
+                "content": content,
+                "truncated": truncated,
+            })
+            .to_string());
+        }
+
+        Err(ToolError::ExternalService(format!(
+            "Gave up after {} redirects",
+            MAX_REDIRECTS
+        )))
+    }
+}
+
+/// Whether a MIME type is something worth returning as text.
+fn is_text(content_type: &str) -> bool {
+    content_type.starts_with("text/")
+        || content_type.ends_with("+xml")
+        || content_type.ends_with("+json")
+        || matches!(content_type, "application/json" | "application/xml")
+}
+
+/// Whether an address is on the public internet.
+fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, c, _] = ip.octets();
+            !(ip.is_private()
+                || ip.is_loopback()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast()
+                || ip.is_multicast()
+                || ip.is_documentation()
+                || a == 0
+                || a >= 240
+                || (a == 100 && (64..128).contains(&b)) // carrier-grade NAT
+                || (a == 192 && b == 0 && c == 0)
+                || (a == 198 && (18..20).contains(&b)))
+        }
+        IpAddr::V6(ip) => {
+            // IPv4-mapped (::ffff:a.b.c.d) and IPv4-compatible (::a.b.c.d)
+            if let Some(v4) = ip.to_ipv4() {
+                return is_public(IpAddr::V4(v4));
+            }
+            let segments = ip.segments();
+            let embedded = |hi: u16, lo: u16| {
+                let [a, b] = hi.to_be_bytes();
+                let [c, d] = lo.to_be_bytes();
+                IpAddr::from([a, b, c, d])
+            };
+            // NAT64 (64:ff9b::/96) and 6to4 (2002::/16) reach the embedded IPv4 address
+            if segments[..6] == [0x64, 0xff9b, 0, 0, 0, 0] {
+                return is_public(embedded(segments[6], segments[7]));
+            }
+            if segments[0] == 0x2002 {
+                return is_public(embedded(segments[1], segments[2]));
+            }
+            let first = segments[0];
+            !(ip.is_loopback()
+                || ip.is_unspecified()
+                || ip.is_multicast()
+                || segments[..3] == [0x64, 0xff9b, 1] // local-use NAT64
+                || (first & 0xfe00) == 0xfc00 // unique local
+                || (first & 0xffc0) == 0xfe80) // link local
+        }
+    }
+}
+
+/// Reduce HTML to readable text, returning the page title separately.
+fn html_to_text(html: &str) -> (Option<String>, String) {
+    // ASCII lowercasing keeps byte offsets, so both can be indexed alike
+    let lower = html.to_ascii_lowercase();
+    let mut title = None;
+    let mut text = String::new();
+    let mut pos = 0;
+
+    while let Some(start) = lower[pos..].find('<').map(|i| pos + i) {
+        text.push_str(&html[pos..start]);
+
+        if lower[start..].starts_with("<!--") {
+            pos = lower[start..].find("-->").map_or(html.len(), |i| start + i + 3);
+            continue;
+        }
+        let Some(end) = lower[start..].find('>').map(|i| start + i) else {
+            pos = html.len();
+            break;
+        };
+        let tag = &lower[start + 1..end];
+        pos = end + 1;
+
+        let closing = tag.starts_with('/');
+        let name = tag
+            .trim_start_matches('/')
+            .split(|c: char| c.is_whitespace() || c == '/')
+            .next()
+            .unwrap_or("");
+
+        if !closing && (name == "title" || SKIPPED_ELEMENTS.contains(&name)) {
+            let close = format!("</{}", name);
+            let content_end = lower[pos..].find(&close).map_or(html.len(), |i| pos + i);
+            if name == "title" {
+                title = Some(collapse_whitespace(&decode_entities(&html[pos..content_end])));
+            } else if name == "head" {
+                // The title usually lives in the head
+                title = title.or_else(|| html_to_text(&html[pos..content_end]).0);
+            }
+            pos = lower[content_end..].find('>').map_or(html.len(), |i| content_end + i + 1);
+            continue;
+        }
+
+        if BLOCK_ELEMENTS.contains(&name) {
+            text.push('\n');
+        }
+    }
+    text.push_str(&html[pos.min(html.len())..]);
+
+    let text = decode_entities(&text);
+    let lines: Vec<String> = text
+        .lines()
+        .map(collapse_whitespace)
+        .filter(|line| !line.is_empty())
+        .collect();
+    (title.filter(|t| !t.is_empty()), lines.join("\n"))
+}
+
+fn collapse_whitespace(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Decode the named entities common in prose, and numeric ones.
+fn decode_entities(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(amp) = rest.find('&') {
+        out.push_str(&rest[..amp]);
+        rest = &rest[amp..];
+
+        let decoded = rest.find(';').filter(|&end| end <= 10).and_then(|end| {
+            let entity = &rest[1..end];
+            let c = match entity {
+                "amp" => Some('&'),
+                "lt" => Some('<'),
+                "gt" => Some('>'),
+                "quot" => Some('"'),
+                "apos" | "#39" => Some('\''),
+                "nbsp" => Some(' '),
+                _ => entity
+                    .strip_prefix("#x")
+                    .or_else(|| entity.strip_prefix("#X"))
+                    .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                    .or_else(|| entity.strip_prefix('#').and_then(|n| n.parse().ok()))
+                    .and_then(char::from_u32),
+            };
+            c.map(|c| (c, end))
+        });
+
+        match decoded {
+            Some((c, end)) => {
+                out.push(c);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    async fn fetch(tool: &FetchUrlTool, url: &str) -> Result<serde_json::Value, ToolError> {
+        let args = serde_json::json!({ "url": url }).to_string();
+        let result = tool.execute(&args).await?;
+        Ok(serde_json::from_str(&result).unwrap())
+    }
+
+    #[test]
+    fn test_definition() {
+        let tool = FetchUrlTool::new();
+        assert_eq!(tool.definition().function.name, "fetch_url");
+    }
+
+    #[test]
+    fn test_html_to_text() {
+        let html = r#"<html><head><title>Hello &amp; welcome</title>
+            <style>body { color: red }</style></head>
+            <body><h1>Heading</h1><!-- note --><p>First <b>bold</b>&nbsp;para.</p>
+            <script>alert("x")</script><p>Second&#33; &lt;tag&gt;</p></body></html>"#;
+
+        let (title, text) = html_to_text(html);
+        assert_eq!(title.as_deref(), Some("Hello & welcome"));
+        assert_eq!(text, "Heading\nFirst bold para.\nSecond! <tag>");
+    }
+
+    #[test]
+    fn test_is_public() {
+        for ip in ["10.1.2.3", "127.0.0.1", "169.254.169.254", "192.168.1.1", "100.64.0.1", "0.0.0.0", "::1", "fd00::1", "fe80::1", "::ffff:127.0.0.1", "::127.0.0.1", "::10.0.0.1", "64:ff9b::a9fe:a9fe", "64:ff9b::7f00:1", "64:ff9b:1::1", "2002:a00:1::1", "2002:7f00:1::"] {
+            assert!(!is_public(ip.parse().unwrap()), "{} should be private", ip);
+        }
+        for ip in ["1.1.1.1", "93.184.216.34", "2606:4700:4700::1111", "64:ff9b::101:101", "2002:101:101::1"] {
+            assert!(is_public(ip.parse().unwrap()), "{} should be public", ip);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_rejects_non_http_schemes() {
+        let tool = FetchUrlTool::new();
+        for url in ["file:///etc/passwd", "ftp://example.com/file", "gopher://example.com"] {
+            let err = fetch(&tool, url).await.unwrap_err();
+            assert!(matches!(err, ToolError::InvalidArguments(ref m) if m.contains("Only http")), "{}", url);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_blocks_private_addresses() {
+        let tool = FetchUrlTool::new();
+        for url in ["http://127.0.0.1:1/", "http://169.254.169.254/latest/meta-data", "http://[::1]/", "http://localhost/"] {
+            let err = fetch(&tool, url).await.unwrap_err();
+            assert!(matches!(err, ToolError::InvalidArguments(ref m) if m.contains("private address")), "{}", url);
+        }
+
+        // A local server is blocked too, even though it would answer
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("secret"))
+            .expect(0)
+            .mount(&server)
+            .await;
+        assert!(fetch(&tool, &server.uri()).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_fetches_html_as_text() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/page"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_raw("<title>Doc</title><p>Hello <i>world</i></p>", "text/html; charset=utf-8"),
+            )
+            .mount(&server)
+            .await;
+
+        let tool = FetchUrlTool::new().allowing_local();
+        let result = fetch(&tool, &format!("{}/page", server.uri())).await.unwrap();
+        assert_eq!(result["title"], "Doc");
+        assert_eq!(result["content"], "Hello world");
+        assert_eq!(result["truncated"], false);
+    }
+
+    #[tokio::test]
+    async fn test_caps_download_size() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("a".repeat(10_000)))
+            .mount(&server)
+            .await;
+
+        let tool = FetchUrlTool::new().allowing_local().with_max_bytes(100);
+        let result = fetch(&tool, &server.uri()).await.unwrap();
+        assert_eq!(result["content"].as_str().unwrap().len(), 100);
+        assert_eq!(result["truncated"], true);
+    }
+
+    #[tokio::test]
+    async fn test_refuses_non_text_content() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(200).set_body_raw(vec![0x89, b'P', b'N', b'G'], "image/png"))
+            .mount(&server)
+            .await;
+
+        let tool = FetchUrlTool::new().allowing_local();
+        let err = fetch(&tool, &server.uri()).await.unwrap_err();
+        assert!(matches!(err, ToolError::InvalidArguments(ref m) if m.contains("image/png")));
+    }
+
+    #[tokio::test]
+    async fn test_follows_redirects() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/old"))
+            .respond_with(ResponseTemplate::new(301).insert_header("location", "/new"))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/new"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("moved here"))
+            .mount(&server)
+            .await;
+
+        let tool = FetchUrlTool::new().allowing_local();
+        let result = fetch(&tool, &format!("{}/old", server.uri())).await.unwrap();
+        assert_eq!(result["content"], "moved here");
+        assert!(result["url"].as_str().unwrap().ends_with("/new"));
+    }
+
+    #[tokio::test]
+    async fn test_blocks_redirect_to_private_address() {
+        let server = MockServer::start().await;
+        for (from, to) in [
+            ("/metadata", "http://169.254.169.254/latest/meta-data"),
+            ("/nat64", "http://[64:ff9b::a9fe:a9fe]/latest/meta-data"),
+            ("/internal", "http://10.0.0.1/admin"),
+        ] {
+            Mock::given(method("GET"))
+                .and(path(from))
+                .respond_with(ResponseTemplate::new(302).insert_header("location", to))
+                .mount(&server)
+                .await;
+        }
+
+        let tool = FetchUrlTool::new().allowing_local();
+        for from in ["/metadata", "/nat64", "/internal"] {
+            let err = fetch(&tool, &format!("{}{}", server.uri(), from)).await.unwrap_err();
+            assert!(matches!(err, ToolError::InvalidArguments(ref m) if m.contains("private address")), "{}", from);
+        }
+    }
+}
@@ -1,9 +1,19 @@
 //! Built-in tools.
 
 mod calculator;
+mod convert;
+mod crypto_price;
+mod datetime;
+mod fetch_url;
+mod search_history;
 mod weather;
 mod web_search;
 
 pub use calculator::CalculatorTool;
-pub use weather::WeatherTool;
+pub use convert::{ConvertTool, DEFAULT_RATES_URL, DEFAULT_RATE_CACHE_TTL};
+pub use crypto_price::{CryptoPriceTool, DEFAULT_COINGECKO_URL, DEFAULT_PRICE_CACHE_TTL};
+pub use datetime::DateTimeTool;
+pub use fetch_url::{FetchUrlTool, DEFAULT_FETCH_MAX_BYTES, DEFAULT_FETCH_TIMEOUT};
+pub use search_history::SearchHistoryTool;
+pub use weather::{WeatherTool, DEFAULT_FORECAST_URL, DEFAULT_GEOCODING_URL};
 pub use web_search::WebSearchTool;
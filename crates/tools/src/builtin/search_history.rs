@@ -0,0 +1,287 @@
+//! Conversation history search using NEAR AI embeddings.
+
+use crate::error::ToolError;
+use crate::types::{FunctionDefinition, Tool, ToolContext, ToolDefinition};
+use async_trait::async_trait;
+use conversation_store::ConversationStore;
+use near_ai_client::NearAiClient;
+use serde::Deserialize;
+use std::sync::Arc;
+use tracing::debug;
+
+/// Longest snippet returned for a single message, in characters.
+const MAX_SNIPPET_CHARS: usize = 300;
+
+/// Finds the caller's past messages most relevant to a query.
+///
+/// Only the conversation named in the call's [`ToolContext`] is searched, so
+/// one user can never see another's history.
+pub struct SearchHistoryTool {
+    near_ai: Arc<NearAiClient>,
+    conversations: Arc<ConversationStore>,
+    embedding_model: String,
+    max_results: usize,
+}
+
+#[derive(Deserialize)]
+struct SearchHistoryArgs {
+    query: String,
+}
+
+impl SearchHistoryTool {
+    /// Create a history search tool embedding with `embedding_model`.
+    pub fn new(
+        near_ai: Arc<NearAiClient>,
+        conversations: Arc<ConversationStore>,
+        embedding_model: impl Into<String>,
+    ) -> Self {
+        Self {
+            near_ai,
+            conversations,
+            embedding_model: embedding_model.into(),
+            max_results: 5,
+        }
+    }
+
+    /// Set maximum number of snippets to return.
+    pub fn with_max_results(mut self, max: usize) -> Self {
+        self.max_results = max;
+        self
+    }
+}
+
+/// Cosine similarity of two vectors (0 when either is all zeros).
+fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm_a: f32 = a.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let norm_b: f32 = b.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm_a == 0.0 || norm_b == 0.0 {
+        0.0
+    } else {
+        dot / (norm_a * norm_b)
+    }
+}
+
+fn snippet(content: &str) -> String {
+    if content.chars().count() > MAX_SNIPPET_CHARS {
+        let truncated: String = content.chars().take(MAX_SNIPPET_CHARS).collect();
+        format!("{}...", truncated)
+    } else {
+        content.to_string()
+    }
+}
+
+#[async_trait]
+impl Tool for SearchHistoryTool {
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            tool_type: "function".into(),
+            function: FunctionDefinition {
+                name: "search_history".into(),
+                description: "Search earlier messages in this conversation. Use when the user refers to something they told you before (e.g., 'what did I say about my trip?').".into(),
+                parameters: serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "query": {
+                            "type": "string",
+                            "description": "What to look for (e.g., 'travel plans', 'favorite restaurant')"
+                        }
+                    },
+                    "required": ["query"]
+                }),
+            },
+        }
+    }
+
+    fn name(&self) -> &str {
+        "search_history"
+    }
+
+    async fn execute(&self, _arguments: &str) -> Result<String, ToolError> {
+        Err(ToolError::NotConfigured(
+            "History search needs the caller's conversation".into(),
+        ))
+    }
+
+    async fn execute_with_context(
+        &self,
+        arguments: &str,
+        context: &ToolContext,
+    ) -> Result<String, ToolError> {
+        let Some(conversation_id) = context.conversation_id.as_deref() else {
+            return self.execute(arguments).await;
+        };
+
+        let args: SearchHistoryArgs = serde_json::from_str(arguments)
+            .map_err(|e| ToolError::InvalidArguments(e.to_string()))?;
+
+        let query = args.query.trim();
+        if query.is_empty() {
+            return Err(ToolError::InvalidArguments("Empty query".into()));
+        }
+
+        let Some(conversation) = self
+            .conversations
+            .get(conversation_id)
+            .await
+            .map_err(|e| ToolError::ExternalService(e.to_string()))?
+        else {
+            return Ok("No earlier messages in this conversation.".into());
+        };
+
+        // The latest user message is the question being answered now
+        let current = conversation.messages.iter().rposition(|m| m.role == "user");
+        let candidates: Vec<_> = conversation
+            .messages
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| Some(*i) != current)
+            .filter(|(_, m)| m.role == "user" || m.role == "assistant")
+            .filter_map(|(_, m)| {
+                m.content
+                    .as_deref()
+                    .filter(|c| !c.trim().is_empty())
+                    .map(|c| (m, c))
+            })
+            .collect();
+
+        if candidates.is_empty() {
+            return Ok("No earlier messages in this conversation.".into());
+        }
+
+        debug!(query = %query, candidates = candidates.len(), "Searching conversation history");
+
+        let mut inputs = vec![query.to_string()];
+        inputs.extend(candidates.iter().map(|(_, content)| content.to_string()));
+        let embeddings = self
+            .near_ai
+            .embed(inputs, &self.embedding_model)
+            .await
+            .map_err(|e| ToolError::ExternalService(e.to_string()))?;
+
+        let (query_embedding, message_embeddings) = embeddings
+            .split_first()
+            .ok_or_else(|| ToolError::ExternalService("No embeddings returned".into()))?;
+
+        let mut ranked: Vec<_> = candidates
+            .iter()
+            .zip(message_embeddings)
+            .map(|((message, content), embedding)| {
+                (cosine_similarity(query_embedding, embedding), message, content)
+            })
+            .collect();
+        ranked.sort_by(|a, b| b.0.total_cmp(&a.0));
+        ranked.truncate(self.max_results);
+
+        let mut output = format!("Earlier messages matching '{}':\n\n", query);
+        for (i, (_, message, content)) in ranked.iter().enumerate() {
+            output.push_str(&format!(
+                "{}. [{}] {}: {}\n",
+                i + 1,
+                message.timestamp.format("%Y-%m-%d %H:%M UTC"),
+                message.role,
+                snippet(content)
+            ));
+        }
+
+        Ok(output)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, Request, ResponseTemplate};
+
+    /// Embed each input by which topic keywords it mentions.
+    fn keyword_embeddings(request: &Request) -> ResponseTemplate {
+        let body: serde_json::Value = request.body_json().unwrap();
+        let data: Vec<_> = body["input"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .enumerate()
+            .map(|(index, input)| {
+                let text = input.as_str().unwrap().to_lowercase();
+                let embedding = [
+                    if text.contains("cat") { 1.0 } else { 0.0 },
+                    if text.contains("paris") { 1.0 } else { 0.0 },
+                    0.1,
+                ];
+                serde_json::json!({ "object": "embedding", "index": index, "embedding": embedding })
+            })
+            .collect();
+        ResponseTemplate::new(200).set_body_json(serde_json::json!({ "object": "list", "data": data }))
+    }
+
+    async fn setup() -> (SearchHistoryTool, Arc<ConversationStore>, MockServer) {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/embeddings"))
+            .respond_with(keyword_embeddings)
+            .mount(&server)
+            .await;
+
+        let near_ai = NearAiClient::new("test-api-key", server.uri(), "test-model", Duration::from_secs(5))
+            .unwrap();
+        let conversations = Arc::new(ConversationStore::new(50, Duration::from_secs(3600)));
+        let tool = SearchHistoryTool::new(Arc::new(near_ai), conversations.clone(), "test-embedding-model")
+            .with_max_results(1);
+
+        (tool, conversations, server)
+    }
+
+    #[tokio::test]
+    async fn test_finds_relevant_message_in_own_conversation() {
+        let (tool, conversations, _server) = setup().await;
+        for (user, text) in [
+            ("+1111", "My cat is called Miso"),
+            ("+1111", "I'm flying to Paris next week"),
+            ("+2222", "My cat is called Pixel"),
+            ("+1111", "What's my cat's name?"),
+        ] {
+            conversations.add_message(user, "user", text, None).await.unwrap();
+        }
+
+        let result = tool
+            .execute_with_context(r#"{"query": "cat name"}"#, &ToolContext::for_conversation("+1111"))
+            .await
+            .unwrap();
+
+        assert!(result.contains("Miso"));
+        assert!(!result.contains("Paris"), "capped to one snippet");
+        assert!(!result.contains("Pixel"), "other users' history is never searched");
+        assert!(!result.contains("What's my cat's name?"));
+    }
+
+    #[tokio::test]
+    async fn test_requires_conversation_context() {
+        let (tool, _, server) = setup().await;
+
+        let result = tool.execute(r#"{"query": "cat"}"#).await;
+
+        assert!(matches!(result, Err(ToolError::NotConfigured(_))));
+        assert!(server.received_requests().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_empty_history() {
+        let (tool, _, _server) = setup().await;
+
+        let result = tool
+            .execute_with_context(r#"{"query": "cat"}"#, &ToolContext::for_conversation("+3333"))
+            .await
+            .unwrap();
+
+        assert!(result.contains("No earlier messages"));
+    }
+
+    #[test]
+    fn test_cosine_similarity() {
+        assert!((cosine_similarity(&[1.0, 0.0], &[1.0, 0.0]) - 1.0).abs() < 1e-6);
+        assert_eq!(cosine_similarity(&[1.0, 0.0], &[0.0, 1.0]), 0.0);
+        assert_eq!(cosine_similarity(&[0.0, 0.0], &[1.0, 0.0]), 0.0);
+    }
+}
@@ -4,12 +4,25 @@ use crate::error::ToolError;
 use crate::types::{FunctionDefinition, Tool, ToolDefinition};
 use async_trait::async_trait;
 use reqwest::Client;
+use serde::de::DeserializeOwned;
 use serde::Deserialize;
-use tracing::debug;
+use std::time::Duration;
+use tracing::{debug, warn};
+
+/// Default Open-Meteo geocoding API base URL.
+pub const DEFAULT_GEOCODING_URL: &str = "https://geocoding-api.open-meteo.com/v1";
+
+/// Default Open-Meteo forecast API base URL.
+pub const DEFAULT_FORECAST_URL: &str = "https://api.open-meteo.com/v1";
+
+/// Time allowed for each Open-Meteo request.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
 
 /// Weather tool using Open-Meteo API.
 pub struct WeatherTool {
     client: Client,
+    geocoding_url: String,
+    forecast_url: String,
 }
 
 #[derive(Deserialize)]
@@ -46,21 +59,108 @@ struct CurrentWeather {
 impl WeatherTool {
     pub fn new() -> Self {
         Self {
-            client: Client::new(),
+            client: Client::builder()
+                .timeout(REQUEST_TIMEOUT)
+                .build()
+                .unwrap_or_default(),
+            geocoding_url: DEFAULT_GEOCODING_URL.into(),
+            forecast_url: DEFAULT_FORECAST_URL.into(),
+        }
+    }
+
+    /// Use different Open-Meteo-compatible geocoding and forecast APIs.
+    pub fn with_base_urls(
+        mut self,
+        geocoding_url: impl Into<String>,
+        forecast_url: impl Into<String>,
+    ) -> Self {
+        self.geocoding_url = geocoding_url.into().trim_end_matches('/').to_string();
+        self.forecast_url = forecast_url.into().trim_end_matches('/').to_string();
+        self
+    }
+
+    /// GET a JSON response, treating non-2xx statuses as errors.
+    async fn get_json<T: DeserializeOwned>(
+        &self,
+        url: &str,
+        query: &[(&str, String)],
+    ) -> Result<T, ToolError> {
+        let response = self.client.get(url).query(query).send().await?;
+        if !response.status().is_success() {
+            return Err(ToolError::ExternalService(format!(
+                "Open-Meteo API error: {}",
+                response.status()
+            )));
         }
+        Ok(response.json().await?)
+    }
+
+    async fn current_weather(&self, location: &str) -> Result<String, ToolError> {
+        // Step 1: Geocode the location
+        debug!(location = %location, "Geocoding location");
+        let geo_response: GeocodingResponse = self
+            .get_json(
+                &format!("{}/search", self.geocoding_url),
+                &[
+                    ("name", location.to_string()),
+                    ("count", "1".into()),
+                    ("language", "en".into()),
+                    ("format", "json".into()),
+                ],
+            )
+            .await?;
+
+        let Some(geo) = geo_response.results.and_then(|r| r.into_iter().next()) else {
+            return Ok(format!(
+                "Location '{}' not found. Ask the user for a nearby city.",
+                location
+            ));
+        };
+
+        // Step 2: Get weather data
+        debug!(lat = geo.latitude, lon = geo.longitude, "Fetching weather");
+        let weather: WeatherResponse = self
+            .get_json(
+                &format!("{}/forecast", self.forecast_url),
+                &[
+                    ("latitude", geo.latitude.to_string()),
+                    ("longitude", geo.longitude.to_string()),
+                    ("current_weather", "true".into()),
+                ],
+            )
+            .await?;
+
+        // Format nice location name
+        let location_name = match (&geo.admin1, &geo.country) {
+            (Some(admin), Some(country)) => format!("{}, {}, {}", geo.name, admin, country),
+            (None, Some(country)) => format!("{}, {}", geo.name, country),
+            _ => geo.name,
+        };
+
+        let description = Self::weather_code_to_description(weather.current_weather.weathercode);
+        let temp_f = weather.current_weather.temperature * 9.0 / 5.0 + 32.0;
+
+        Ok(format!(
+            "Weather in {}: {:.1}°C ({:.1}°F), {}. Wind: {:.1} km/h",
+            location_name,
+            weather.current_weather.temperature,
+            temp_f,
+            description,
+            weather.current_weather.windspeed
+        ))
     }
 
     fn weather_code_to_description(code: i32) -> &'static str {
         match code {
             0 => "Clear sky",
-            1 | 2 | 3 => "Partly cloudy",
+            1..=3 => "Partly cloudy",
             45 | 48 => "Foggy",
             51 | 53 | 55 => "Drizzle",
             61 | 63 | 65 => "Rain",
             66 | 67 => "Freezing rain",
             71 | 73 | 75 => "Snow",
             77 => "Snow grains",
-            80 | 81 | 82 => "Rain showers",
+            80..=82 => "Rain showers",
             85 | 86 => "Snow showers",
             95 => "Thunderstorm",
             96 | 99 => "Thunderstorm with hail",
@@ -110,65 +210,26 @@ impl Tool for WeatherTool {
             return Err(ToolError::InvalidArguments("Empty location".into()));
         }
 
-        // Step 1: Geocode the location
-        debug!(location = %location, "Geocoding location");
-        let geocode_url = format!(
-            "https://geocoding-api.open-meteo.com/v1/search?name={}&count=1&language=en&format=json",
-            urlencoding::encode(location)
-        );
-
-        let geo_response: GeocodingResponse = self
-            .client
-            .get(&geocode_url)
-            .send()
-            .await?
-            .json()
-            .await?;
-
-        let geo = geo_response
-            .results
-            .and_then(|r| r.into_iter().next())
-            .ok_or_else(|| ToolError::ExternalService(format!("Location '{}' not found", location)))?;
-
-        // Step 2: Get weather data
-        debug!(lat = geo.latitude, lon = geo.longitude, "Fetching weather");
-        let weather_url = format!(
-            "https://api.open-meteo.com/v1/forecast?latitude={}&longitude={}&current_weather=true",
-            geo.latitude, geo.longitude
-        );
-
-        let weather: WeatherResponse = self
-            .client
-            .get(&weather_url)
-            .send()
-            .await?
-            .json()
-            .await?;
-
-        // Format nice location name
-        let location_name = match (&geo.admin1, &geo.country) {
-            (Some(admin), Some(country)) => format!("{}, {}, {}", geo.name, admin, country),
-            (None, Some(country)) => format!("{}, {}", geo.name, country),
-            _ => geo.name,
-        };
-
-        let description = Self::weather_code_to_description(weather.current_weather.weathercode);
-        let temp_f = weather.current_weather.temperature * 9.0 / 5.0 + 32.0;
-
-        Ok(format!(
-            "Weather in {}: {:.1}°C ({:.1}°F), {}. Wind: {:.1} km/h",
-            location_name,
-            weather.current_weather.temperature,
-            temp_f,
-            description,
-            weather.current_weather.windspeed
-        ))
+        // An unreachable provider shouldn't sink the whole answer; tell the
+        // model so it can say so instead
+        match self.current_weather(location).await {
+            Ok(summary) => Ok(summary),
+            Err(e) => {
+                warn!("Weather lookup failed: {}", e);
+                Ok(format!(
+                    "The weather service is unavailable right now, so current conditions for '{}' can't be checked. Tell the user to try again later.",
+                    location
+                ))
+            }
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use wiremock::matchers::{method, path, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
 
     #[test]
     fn test_weather_code_descriptions() {
@@ -186,6 +247,81 @@ mod tests {
         assert_eq!(def.function.name, "get_weather");
     }
 
+    async fn mock_open_meteo(server: &MockServer) {
+        Mock::given(method("GET"))
+            .and(path("/search"))
+            .and(query_param("name", "Tokyo"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "results": [{
+                    "name": "Tokyo",
+                    "latitude": 35.6895,
+                    "longitude": 139.69171,
+                    "country": "Japan",
+                    "admin1": "Tokyo"
+                }]
+            })))
+            .expect(1)
+            .mount(server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/forecast"))
+            .and(query_param("latitude", "35.6895"))
+            .and(query_param("longitude", "139.69171"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "current_weather": {
+                    "temperature": 20.0,
+                    "windspeed": 12.5,
+                    "weathercode": 61
+                }
+            })))
+            .expect(1)
+            .mount(server)
+            .await;
+    }
+
+    #[tokio::test]
+    async fn test_geocodes_then_fetches_current_weather() {
+        let server = MockServer::start().await;
+        mock_open_meteo(&server).await;
+
+        let tool = WeatherTool::new().with_base_urls(server.uri(), server.uri());
+        let result = tool.execute(r#"{"location": "Tokyo"}"#).await.unwrap();
+        assert_eq!(
+            result,
+            "Weather in Tokyo, Tokyo, Japan: 20.0°C (68.0°F), Rain. Wind: 12.5 km/h"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_unknown_location() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/search"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({})))
+            .mount(&server)
+            .await;
+
+        let tool = WeatherTool::new().with_base_urls(server.uri(), server.uri());
+        let result = tool.execute(r#"{"location": "Nowhere"}"#).await.unwrap();
+        assert!(result.contains("'Nowhere' not found"));
+    }
+
+    #[tokio::test]
+    async fn test_unreachable_api_falls_back() {
+        let tool = WeatherTool::new().with_base_urls("http://127.0.0.1:9", "http://127.0.0.1:9");
+        let result = tool.execute(r#"{"location": "Tokyo"}"#).await.unwrap();
+        assert!(result.contains("weather service is unavailable"));
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(503))
+            .mount(&server)
+            .await;
+        let tool = WeatherTool::new().with_base_urls(server.uri(), server.uri());
+        let result = tool.execute(r#"{"location": "Tokyo"}"#).await.unwrap();
+        assert!(result.contains("weather service is unavailable"));
+    }
+
     // Integration test - requires network
     #[tokio::test]
     #[ignore] // Run with: cargo test -p tools -- --ignored
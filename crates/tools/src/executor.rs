@@ -1,14 +1,19 @@
 //! Tool executor with timeout and error handling.
 
-use crate::registry::ToolRegistry;
-use crate::types::{ToolCall, ToolResult};
-#[cfg(test)]
 use crate::error::ToolError;
+use crate::registry::ToolRegistry;
+use crate::types::{ToolCall, ToolContext, ToolResult};
 use std::sync::Arc;
 use std::time::Duration;
 use tokio::time::timeout;
 use tracing::{error, info, warn};
 
+/// Default cap on a tool result, in characters.
+pub const DEFAULT_MAX_RESULT_LEN: usize = 4000;
+
+/// Appended to tool results that were cut short.
+const TRUNCATION_MARKER: &str = "…[truncated]";
+
 /// Executor for running tools with safety limits.
 pub struct ToolExecutor {
     registry: Arc<ToolRegistry>,
@@ -22,7 +27,7 @@ impl ToolExecutor {
         Self {
             registry,
             timeout_secs: 10,
-            max_response_len: 4000,
+            max_response_len: DEFAULT_MAX_RESULT_LEN,
         }
     }
 
@@ -32,7 +37,8 @@ impl ToolExecutor {
         self
     }
 
-    /// Set maximum response length.
+    /// Set maximum response length, in characters. Longer results are cut
+    /// before they reach the model.
     pub fn with_max_response_len(mut self, len: usize) -> Self {
         self.max_response_len = len;
         self
@@ -40,11 +46,20 @@ impl ToolExecutor {
 
     /// Execute a tool call.
     pub async fn execute(&self, tool_call: &ToolCall) -> ToolResult {
+        self.execute_with_context(tool_call, &ToolContext::default()).await
+    }
+
+    /// Execute a tool call on behalf of the caller described by `context`.
+    pub async fn execute_with_context(&self, tool_call: &ToolCall, context: &ToolContext) -> ToolResult {
         let tool_name = &tool_call.function.name;
         info!(tool = %tool_name, "Executing tool");
 
-        // Get the tool
-        let tool = match self.registry.get_tool(tool_name) {
+        // Get the tool, within the caller's allow list when known
+        let tool = match context.user_id.as_deref() {
+            Some(user_id) => self.registry.get_tool_for(user_id, tool_name),
+            None => self.registry.get_tool(tool_name),
+        };
+        let tool = match tool {
             Some(t) => t,
             None => {
                 warn!(tool = %tool_name, "Tool not found or disabled");
@@ -55,25 +70,23 @@ impl ToolExecutor {
             }
         };
 
+        // Reject malformed arguments with a message the model can act on
+        let arguments = &tool_call.function.arguments;
+        if let Err(e) = validate_arguments(&tool.definition().function.parameters, arguments) {
+            warn!(tool = %tool_name, error = %e, "Tool arguments failed validation");
+            return ToolResult::error(&tool_call.id, format!("Error: {}", e));
+        }
+
         // Execute with timeout
         let result = timeout(
             Duration::from_secs(self.timeout_secs),
-            tool.execute(&tool_call.function.arguments),
+            tool.execute_with_context(arguments, context),
         )
         .await;
 
         match result {
             Ok(Ok(content)) => {
-                // Truncate if needed
-                let content = if content.len() > self.max_response_len {
-                    format!(
-                        "{}... [truncated, {} chars total]",
-                        &content[..self.max_response_len],
-                        content.len()
-                    )
-                } else {
-                    content
-                };
+                let content = truncate_result(content, self.max_response_len);
                 info!(tool = %tool_name, len = content.len(), "Tool executed successfully");
                 ToolResult::success(&tool_call.id, content)
             }
@@ -92,6 +105,51 @@ impl ToolExecutor {
     }
 }
 
+/// Check tool call arguments against the tool's JSON schema.
+///
+/// Every violation is listed so the model can fix them in one retry. A
+/// schema that doesn't compile is a tool bug, so validation is skipped and
+/// the tool left to parse the arguments itself.
+fn validate_arguments(schema: &serde_json::Value, arguments: &str) -> Result<(), ToolError> {
+    // Some models send an empty string for tools without parameters
+    let arguments = if arguments.trim().is_empty() { "{}" } else { arguments };
+    let instance: serde_json::Value = serde_json::from_str(arguments)
+        .map_err(|e| ToolError::InvalidArguments(format!("arguments are not valid JSON: {}", e)))?;
+
+    let validator = match jsonschema::validator_for(schema) {
+        Ok(validator) => validator,
+        Err(e) => {
+            warn!(error = %e, "Tool has an invalid parameter schema");
+            return Ok(());
+        }
+    };
+
+    let problems: Vec<String> = validator
+        .iter_errors(&instance)
+        .map(|e| {
+            let path = e.instance_path.to_string();
+            if path.is_empty() {
+                e.to_string()
+            } else {
+                format!("{}: {}", path, e)
+            }
+        })
+        .collect();
+    if problems.is_empty() {
+        Ok(())
+    } else {
+        Err(ToolError::InvalidArguments(problems.join("; ")))
+    }
+}
+
+/// Cut `content` to at most `max_chars` characters, on a char boundary.
+fn truncate_result(content: String, max_chars: usize) -> String {
+    match content.char_indices().nth(max_chars) {
+        Some((cut, _)) => format!("{}{}", &content[..cut], TRUNCATION_MARKER),
+        None => content,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -167,6 +225,117 @@ mod tests {
         assert_eq!(result.content, "fast result");
     }
 
+    fn location_schema() -> serde_json::Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "location": { "type": "string" },
+                "days": { "type": "integer" }
+            },
+            "required": ["location"]
+        })
+    }
+
+    #[test]
+    fn test_validate_arguments() {
+        let schema = location_schema();
+        assert!(validate_arguments(&schema, r#"{"location": "Tokyo", "days": 3}"#).is_ok());
+
+        let missing = validate_arguments(&schema, r#"{"days": 3}"#).unwrap_err();
+        assert!(missing.to_string().contains(r#""location" is a required property"#), "{}", missing);
+
+        let wrong_type = validate_arguments(&schema, r#"{"location": 5, "days": "soon"}"#).unwrap_err();
+        let message = wrong_type.to_string();
+        assert!(message.contains(r#"/location: 5 is not of type "string""#), "{}", message);
+        assert!(message.contains(r#"/days: "soon" is not of type "integer""#), "{}", message);
+
+        let not_json = validate_arguments(&schema, "{location: Tokyo").unwrap_err();
+        assert!(not_json.to_string().contains("not valid JSON"));
+
+        // Tools without parameters accept an empty argument string
+        assert!(validate_arguments(&serde_json::json!({}), "").is_ok());
+    }
+
+    struct ForecastTool;
+
+    #[async_trait]
+    impl Tool for ForecastTool {
+        fn definition(&self) -> ToolDefinition {
+            ToolDefinition {
+                tool_type: "function".into(),
+                function: FunctionDefinition {
+                    name: "forecast".into(),
+                    description: "Forecast".into(),
+                    parameters: location_schema(),
+                },
+            }
+        }
+
+        fn name(&self) -> &str {
+            "forecast"
+        }
+
+        async fn execute(&self, _arguments: &str) -> Result<String, ToolError> {
+            panic!("called with arguments that failed validation");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_execute_rejects_invalid_arguments() {
+        let mut registry = ToolRegistry::new();
+        registry.register(Arc::new(ForecastTool));
+        let executor = ToolExecutor::new(Arc::new(registry));
+
+        let call = ToolCall {
+            id: "call-1".into(),
+            call_type: "function".into(),
+            function: crate::types::FunctionCall {
+                name: "forecast".into(),
+                arguments: r#"{"days": 2}"#.into(),
+            },
+        };
+
+        let result = executor.execute(&call).await;
+        assert!(!result.success);
+        assert!(result.content.starts_with("Error: Invalid arguments:"));
+        assert!(result.content.contains("\"location\" is a required property"));
+    }
+
+    #[test]
+    fn test_truncate_result() {
+        assert_eq!(truncate_result("hello".into(), 5), "hello");
+        assert_eq!(truncate_result("hello!".into(), 5), "hello…[truncated]");
+
+        // Multi-byte characters are counted whole and never split
+        assert_eq!(truncate_result("héllo wörld".into(), 11), "héllo wörld");
+        assert_eq!(truncate_result("日本語のテキスト".into(), 3), "日本語…[truncated]");
+        assert_eq!(truncate_result("🦀🦀".into(), 1), "🦀…[truncated]");
+    }
+
+    #[tokio::test]
+    async fn test_execute_truncates_oversized_result() {
+        let mut registry = ToolRegistry::new();
+        registry.register(Arc::new(FastTool));
+        let executor = ToolExecutor::new(Arc::new(registry)).with_max_response_len(4);
+
+        let call = ToolCall {
+            id: "call-1".into(),
+            call_type: "function".into(),
+            function: crate::types::FunctionCall {
+                name: "fast".into(),
+                arguments: "{}".into(),
+            },
+        };
+
+        let result = executor.execute(&call).await;
+        assert!(result.success);
+        assert_eq!(result.content, "fast…[truncated]");
+
+        // "fast result" is exactly 11 characters
+        let executor = ToolExecutor::new(executor.registry.clone()).with_max_response_len(11);
+        assert_eq!(executor.execute(&call).await.content, "fast result");
+    }
+
     #[tokio::test]
     async fn test_execute_timeout() {
         let mut registry = ToolRegistry::new();
@@ -205,4 +374,30 @@ mod tests {
         assert!(!result.success);
         assert!(result.content.contains("not available"));
     }
+
+    #[tokio::test]
+    async fn test_execute_respects_user_allow_list() {
+        let mut registry = ToolRegistry::new();
+        registry.register(Arc::new(FastTool));
+        registry.set_default_tools(Vec::<String>::new());
+        registry.set_user_tools("+15551111111", ["fast"]);
+        let executor = ToolExecutor::new(Arc::new(registry));
+
+        let call = ToolCall {
+            id: "call-1".into(),
+            call_type: "function".into(),
+            function: crate::types::FunctionCall {
+                name: "fast".into(),
+                arguments: "{}".into(),
+            },
+        };
+
+        let allowed = ToolContext::for_conversation("c").with_user("+15551111111");
+        assert!(executor.execute_with_context(&call, &allowed).await.success);
+
+        let denied = ToolContext::for_conversation("c").with_user("+15559999999");
+        let result = executor.execute_with_context(&call, &denied).await;
+        assert!(!result.success);
+        assert!(result.content.contains("not available"));
+    }
 }
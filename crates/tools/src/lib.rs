@@ -8,5 +8,5 @@ pub mod builtin;
 
 pub use error::ToolError;
 pub use types::*;
-pub use registry::ToolRegistry;
-pub use executor::ToolExecutor;
+pub use registry::{ToolRegistry, ALL_TOOLS};
+pub use executor::{ToolExecutor, DEFAULT_MAX_RESULT_LEN};
@@ -4,10 +4,19 @@ use crate::types::{Tool, ToolDefinition};
 use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 
+/// Tool list entry that allows every enabled tool.
+pub const ALL_TOOLS: &str = "*";
+
 /// Registry of available tools.
+///
+/// Besides the global enabled set, callers can be limited to a subset of
+/// tools: a user with their own list sees only those, everyone else sees the
+/// default list (all enabled tools when no default is set).
 pub struct ToolRegistry {
     tools: HashMap<String, Arc<dyn Tool>>,
     enabled: HashSet<String>,
+    default_allowed: Option<HashSet<String>>,
+    user_allowed: HashMap<String, HashSet<String>>,
 }
 
 impl ToolRegistry {
@@ -16,6 +25,8 @@ impl ToolRegistry {
         Self {
             tools: HashMap::new(),
             enabled: HashSet::new(),
+            default_allowed: None,
+            user_allowed: HashMap::new(),
         }
     }
 
@@ -43,6 +54,36 @@ impl ToolRegistry {
         self.enabled.contains(name)
     }
 
+    /// Limit users without their own list to `names` (`*` allows all).
+    pub fn set_default_tools<I, S>(&mut self, names: I)
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.default_allowed = Some(names.into_iter().map(Into::into).collect());
+    }
+
+    /// Limit `user_id` to `names` (`*` allows all), replacing the default list.
+    pub fn set_user_tools<I, S>(&mut self, user_id: impl Into<String>, names: I)
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.user_allowed
+            .insert(user_id.into(), names.into_iter().map(Into::into).collect());
+    }
+
+    /// Check if a tool is enabled and allowed for `user_id`.
+    pub fn is_allowed_for(&self, user_id: &str, name: &str) -> bool {
+        if !self.enabled.contains(name) {
+            return false;
+        }
+        match self.user_allowed.get(user_id).or(self.default_allowed.as_ref()) {
+            Some(allowed) => allowed.contains(name) || allowed.contains(ALL_TOOLS),
+            None => true,
+        }
+    }
+
     /// Get definitions for all enabled tools.
     pub fn get_definitions(&self) -> Vec<ToolDefinition> {
         self.tools
@@ -52,6 +93,24 @@ impl ToolRegistry {
             .collect()
     }
 
+    /// Get definitions for the enabled tools `user_id` may use.
+    pub fn get_definitions_for(&self, user_id: &str) -> Vec<ToolDefinition> {
+        self.tools
+            .iter()
+            .filter(|(name, _)| self.is_allowed_for(user_id, name))
+            .map(|(_, tool)| tool.definition())
+            .collect()
+    }
+
+    /// Get a tool by name (only if enabled and allowed for `user_id`).
+    pub fn get_tool_for(&self, user_id: &str, name: &str) -> Option<Arc<dyn Tool>> {
+        if self.is_allowed_for(user_id, name) {
+            self.tools.get(name).cloned()
+        } else {
+            None
+        }
+    }
+
     /// Get a tool by name (only if enabled).
     pub fn get_tool(&self, name: &str) -> Option<Arc<dyn Tool>> {
         if self.enabled.contains(name) {
@@ -143,4 +202,46 @@ mod tests {
         assert_eq!(defs.len(), 1);
         assert_eq!(defs[0].function.name, "tool1");
     }
+
+    fn names(defs: Vec<ToolDefinition>) -> Vec<String> {
+        let mut names: Vec<String> = defs.into_iter().map(|d| d.function.name).collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn test_get_definitions_for_user() {
+        let mut registry = ToolRegistry::new();
+        for name in ["calculator", "web_search", "weather"] {
+            registry.register(Arc::new(MockTool { name: name.into() }));
+        }
+        registry.set_default_tools(["calculator", "weather"]);
+        registry.set_user_tools("+15551111111", ["calculator", "web_search"]);
+        registry.set_user_tools("+15552222222", [ALL_TOOLS]);
+
+        assert_eq!(names(registry.get_definitions_for("+15551111111")), ["calculator", "web_search"]);
+        assert_eq!(names(registry.get_definitions_for("+15559999999")), ["calculator", "weather"]);
+        assert_eq!(
+            names(registry.get_definitions_for("+15552222222")),
+            ["calculator", "weather", "web_search"]
+        );
+
+        assert!(registry.get_tool_for("+15551111111", "web_search").is_some());
+        assert!(registry.get_tool_for("+15559999999", "web_search").is_none());
+
+        // Disabling a tool wins over any allow list
+        registry.disable("calculator");
+        assert_eq!(names(registry.get_definitions_for("+15551111111")), ["web_search"]);
+    }
+
+    #[test]
+    fn test_no_default_allows_all_enabled() {
+        let mut registry = ToolRegistry::new();
+        registry.register(Arc::new(MockTool { name: "tool1".into() }));
+        registry.register(Arc::new(MockTool { name: "tool2".into() }));
+        registry.set_user_tools("+15551111111", ["tool1"]);
+
+        assert_eq!(names(registry.get_definitions_for("+15551111111")), ["tool1"]);
+        assert_eq!(names(registry.get_definitions_for("+15559999999")), ["tool1", "tool2"]);
+    }
 }
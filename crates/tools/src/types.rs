@@ -77,6 +77,31 @@ impl ToolResult {
     }
 }
 
+/// Who a tool call is being made for.
+#[derive(Debug, Clone, Default)]
+pub struct ToolContext {
+    /// Conversation the call belongs to, for tools that read the caller's history.
+    pub conversation_id: Option<String>,
+    /// User making the call, for per-user tool allow lists.
+    pub user_id: Option<String>,
+}
+
+impl ToolContext {
+    /// Context for a call made within `conversation_id`.
+    pub fn for_conversation(conversation_id: impl Into<String>) -> Self {
+        Self {
+            conversation_id: Some(conversation_id.into()),
+            user_id: None,
+        }
+    }
+
+    /// Restrict the call to the tools `user_id` may use.
+    pub fn with_user(mut self, user_id: impl Into<String>) -> Self {
+        self.user_id = Some(user_id.into());
+        self
+    }
+}
+
 /// Trait for implementing tools.
 #[async_trait]
 pub trait Tool: Send + Sync {
@@ -88,4 +113,16 @@ pub trait Tool: Send + Sync {
 
     /// Execute the tool with JSON arguments.
     async fn execute(&self, arguments: &str) -> Result<String, ToolError>;
+
+    /// Execute the tool on behalf of a caller.
+    ///
+    /// Tools that don't need to know who is calling can rely on the default,
+    /// which ignores the context.
+    async fn execute_with_context(
+        &self,
+        arguments: &str,
+        _context: &ToolContext,
+    ) -> Result<String, ToolError> {
+        self.execute(arguments).await
+    }
 }
@@ -1,20 +1,31 @@
 //! HTTP API handlers.
 
+use super::ownership::{ownership_message, verify_ownership, AddressAction, ProofError, MAX_SIGNATURE_AGE};
+use super::qr;
 use super::types::*;
-use crate::chains::{BaseFacilitator, ChainFacilitator, NearFacilitator, SolanaFacilitator};
-use crate::config::PaymentConfig;
-use crate::credits::{CreditStore, PricingCalculator};
+use crate::chains::{BaseFacilitator, ChainFacilitator, DepositWallets, NearFacilitator, SolanaFacilitator};
+use crate::config::{PaymentConfig, PricingConfig};
+use crate::credits::{CreditStore, PricingCalculator, TokenUsage};
 use crate::error::PaymentError;
-use crate::types::{Chain, Deposit};
+use crate::sweeper::FundSweeper;
+use crate::types::{Chain, Deposit, SweepRecord, SweepStatus};
+use crate::webhook::{DepositEvent, DepositWebhook};
+use api_common::constant_time_eq;
 use axum::{
-    extract::{Path, State},
-    http::StatusCode,
+    extract::{Path, Query, State},
+    http::{
+        header::{AUTHORIZATION, CONTENT_TYPE},
+        HeaderMap, StatusCode,
+    },
     response::IntoResponse,
     routing::{get, post},
     Json, Router,
 };
+use chrono::Utc;
+use secrecy::ExposeSecret;
+use std::collections::HashMap;
 use std::sync::Arc;
-use tracing::{error, info};
+use tracing::{error, info, warn};
 
 /// Shared application state for handlers.
 pub struct AppState {
@@ -24,6 +35,12 @@ pub struct AppState {
     pub base: Option<Arc<BaseFacilitator>>,
     pub near: Option<Arc<NearFacilitator>>,
     pub solana: Option<Arc<SolanaFacilitator>>,
+    /// Deposit wallets of each bot account, used instead of the default
+    /// ones above when a request names the account.
+    pub accounts: HashMap<String, DepositWallets>,
+    pub sweeper: Option<Arc<FundSweeper>>,
+    /// Notified of each credited deposit, when configured.
+    pub deposit_webhook: Option<Arc<DepositWebhook>>,
 }
 
 impl AppState {
@@ -35,6 +52,10 @@ impl AppState {
         solana: Option<Arc<SolanaFacilitator>>,
     ) -> Self {
         let pricing = PricingCalculator::new(config.pricing.clone());
+        let deposit_webhook = config
+            .deposit_webhook_url
+            .as_deref()
+            .map(|url| Arc::new(DepositWebhook::new(url)));
         Self {
             credit_store,
             config,
@@ -42,8 +63,60 @@ impl AppState {
             base,
             near,
             solana,
+            accounts: HashMap::new(),
+            sweeper: None,
+            deposit_webhook,
         }
     }
+
+    /// Attach the running fund sweeper for the admin endpoints.
+    pub fn with_sweeper(mut self, sweeper: Arc<FundSweeper>) -> Self {
+        self.sweeper = Some(sweeper);
+        self
+    }
+
+    /// Attach each bot account's deposit wallets.
+    pub fn with_accounts(mut self, accounts: HashMap<String, DepositWallets>) -> Self {
+        self.accounts = accounts;
+        self
+    }
+
+    /// Facilitator for `chain`, from `account`'s wallets when one is named.
+    fn facilitator(
+        &self,
+        chain: Chain,
+        account: Option<&str>,
+    ) -> Result<Arc<dyn ChainFacilitator>, (StatusCode, Json<ErrorResponse>)> {
+        let facilitator = match account {
+            Some(account) => self
+                .accounts
+                .get(account)
+                .ok_or_else(|| {
+                    (
+                        StatusCode::NOT_FOUND,
+                        Json(ErrorResponse::new(
+                            format!("Unknown account: {}", account),
+                            "UNKNOWN_ACCOUNT",
+                        )),
+                    )
+                })?
+                .get(chain),
+            None => match chain {
+                Chain::Base => self.base.clone().map(|f| f as Arc<dyn ChainFacilitator>),
+                Chain::Near => self.near.clone().map(|f| f as Arc<dyn ChainFacilitator>),
+                Chain::Solana => self.solana.clone().map(|f| f as Arc<dyn ChainFacilitator>),
+            },
+        };
+        facilitator.ok_or_else(|| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse::new(
+                    format!("{} facilitator not initialized", chain),
+                    "CHAIN_NOT_AVAILABLE",
+                )),
+            )
+        })
+    }
 }
 
 /// Create the payment API router.
@@ -52,9 +125,22 @@ pub fn create_router(state: Arc<AppState>) -> Router {
         .route("/health", get(health_check))
         .route("/v1/balance/:user_id", get(get_balance))
         .route("/v1/deposits/:user_id", get(get_deposits))
+        .route("/v1/usage/:user_id", get(get_usage))
         .route("/v1/deposit", post(process_deposit))
+        .route("/v1/deposit-intent", post(create_deposit_intent))
+        .route("/v1/link-address", post(link_address).delete(unlink_address))
         .route("/v1/deposit-address/:chain", get(get_deposit_address))
+        .route("/v1/deposit-address/:chain/qr", get(get_deposit_address_qr))
         .route("/v1/pricing", get(get_pricing))
+        .route("/v1/quote", post(get_quote))
+        .route("/v1/estimate", get(get_estimate))
+        .route("/v1/sweeps/status", get(get_sweep_status))
+        .route("/v1/sweeps/run", post(run_sweep))
+        .route("/v1/admin/reconcile", post(reconcile_balances))
+        .route("/v1/admin/refund", post(refund_credits))
+        .route("/v1/admin/transfer", post(transfer_credits))
+        .route("/v1/admin/key-source", get(get_key_source))
+        .route("/v1/admin/reseal", post(reseal_store))
         .with_state(state)
 }
 
@@ -120,23 +206,70 @@ async fn get_balance(
         credits_remaining_usdc: PricingCalculator::format_usdc(balance.credits_remaining),
         total_deposited_usdc: PricingCalculator::format_usdc(balance.total_deposited),
         total_consumed_usdc: PricingCalculator::format_usdc(balance.total_consumed),
+        total_received_usdc: PricingCalculator::format_usdc(balance.total_received),
     }))
 }
 
-/// Get deposits for a user.
+/// Get a page of deposits for a user, oldest first.
 async fn get_deposits(
     State(state): State<Arc<AppState>>,
     Path(user_id): Path<String>,
-) -> Json<Vec<Deposit>> {
-    let deposits = state.credit_store.get_deposits(&user_id).await;
-    Json(deposits)
+    Query(page): Query<PageParams>,
+) -> Json<DepositsResponse> {
+    let (deposits, total) = state
+        .credit_store
+        .get_deposits_page(&user_id, page.since, page.offset, page.limit())
+        .await;
+
+    Json(DepositsResponse {
+        total,
+        deposits,
+        offset: page.offset,
+        limit: page.limit(),
+    })
+}
+
+/// Get a page of usage records for a user, oldest first.
+async fn get_usage(
+    State(state): State<Arc<AppState>>,
+    Path(user_id): Path<String>,
+    Query(page): Query<PageParams>,
+) -> Json<UsageResponse> {
+    let (usage, total) = state
+        .credit_store
+        .get_usage_page(&user_id, page.since, page.offset, page.limit())
+        .await;
+
+    Json(UsageResponse {
+        total,
+        usage,
+        offset: page.offset,
+        limit: page.limit(),
+    })
 }
 
+/// Header carrying a client-chosen key that makes deposit retries safe.
+const IDEMPOTENCY_KEY_HEADER: &str = "idempotency-key";
+
+/// Longest idempotency key accepted.
+const MAX_IDEMPOTENCY_KEY_LENGTH: usize = 255;
+
 /// Process a deposit.
+///
+/// A request carrying an `Idempotency-Key` that was already used for this
+/// deposit gets the original response back without being verified again.
 async fn process_deposit(
     State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
     Json(request): Json<DepositRequest>,
 ) -> Result<Json<DepositResponse>, (StatusCode, Json<ErrorResponse>)> {
+    let idempotency_key = idempotency_key(&headers)?;
+    if let Some(ref key) = idempotency_key {
+        if let Some(response) = replay_deposit(&state, key, &request).await {
+            return response;
+        }
+    }
+
     // Check if chain is enabled
     let chain_enabled = match request.chain {
         Chain::Base => state.config.base.as_ref().is_some_and(|c| c.enabled),
@@ -155,7 +288,11 @@ async fn process_deposit(
     }
 
     // Check for duplicate transaction
-    if state.credit_store.is_tx_processed(&request.tx_hash).await {
+    if state
+        .credit_store
+        .is_tx_processed(request.chain, &request.tx_hash)
+        .await
+    {
         return Err((
             StatusCode::CONFLICT,
             Json(ErrorResponse::new(
@@ -168,42 +305,23 @@ async fn process_deposit(
     // Verify payment on-chain using appropriate facilitator
     use crate::chains::PaymentPayload;
 
-    let payload = PaymentPayload::new(
+    // The user_id doubles as the expected NEAR memo, so a deposit can only
+    // be claimed by the account it was tagged for when enforcement is on.
+    let mut payload = PaymentPayload::new(
         request.chain,
         request.tx_hash.clone(),
         request.user_id.clone(),
     )
     .with_amount(request.amount);
 
-    let verification = match request.chain {
-        Chain::Base => {
-            let facilitator = state.base.as_ref().ok_or_else(|| {
-                (
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    Json(ErrorResponse::new("Base facilitator not initialized", "CHAIN_NOT_AVAILABLE")),
-                )
-            })?;
-            facilitator.verify_payment(&payload).await
-        }
-        Chain::Near => {
-            let facilitator = state.near.as_ref().ok_or_else(|| {
-                (
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    Json(ErrorResponse::new("NEAR facilitator not initialized", "CHAIN_NOT_AVAILABLE")),
-                )
-            })?;
-            facilitator.verify_payment(&payload).await
-        }
-        Chain::Solana => {
-            let facilitator = state.solana.as_ref().ok_or_else(|| {
-                (
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    Json(ErrorResponse::new("Solana facilitator not initialized", "CHAIN_NOT_AVAILABLE")),
-                )
-            })?;
-            facilitator.verify_payment(&payload).await
-        }
+    if let Some(ref from) = request.from {
+        payload = payload.with_from(from.clone());
     }
+
+    let verification = state
+        .facilitator(request.chain, request.account.as_deref())?
+        .verify_payment(&payload)
+        .await
     .map_err(|e| {
         error!("Payment verification failed: {}", e);
         (
@@ -224,6 +342,20 @@ async fn process_deposit(
 
     // Use verified amount from blockchain
     let verified_amount = verification.amount_usdc;
+    check_deposit_bounds(&state.config, verified_amount)?;
+    if !request.chain.supports_memo() && state.config.require_linked_sender {
+        let owner = match verification.from {
+            Some(ref from) => state.credit_store.linked_address_owner(request.chain, from).await,
+            None => None,
+        };
+        check_deposit_sender(&request, verification.from.as_deref(), owner.as_deref())?;
+    } else if !request.chain.supports_memo() {
+        let owner = state
+            .credit_store
+            .deposit_intent_owner(request.chain, verified_amount)
+            .await;
+        check_deposit_attribution(&state.config, &request, verified_amount, owner.as_deref())?;
+    }
     let credits = state.pricing.usdc_to_credits(verified_amount);
 
     let mut deposit = Deposit::new_pending(
@@ -232,36 +364,71 @@ async fn process_deposit(
         request.tx_hash.clone(),
         verified_amount,
         credits,
-    );
+    )
+    .with_token(verification.token.clone());
 
     // Mark as confirmed since verification succeeded
     deposit.confirm();
 
     let deposit_id = deposit.id.clone();
     let tx_hash = deposit.tx_hash.clone();
+    let token = deposit.token.clone();
 
-    match state.credit_store.add_credits(deposit).await {
+    match state
+        .credit_store
+        .add_credits_with_key(deposit, idempotency_key.as_deref())
+        .await
+    {
         Ok(balance) => {
             info!(
-                "Processed deposit for {}: {} USDC = {} credits",
-                request.user_id, verified_amount, credits
+                "Processed deposit for {}: {} {} = {} credits",
+                request.user_id, verified_amount, token, credits
             );
 
+            // Delivered in the background so a slow endpoint can't hold up the deposit
+            if let Some(ref webhook) = state.deposit_webhook {
+                let webhook = webhook.clone();
+                let event = DepositEvent {
+                    user_id: request.user_id.clone(),
+                    chain: request.chain,
+                    tx_hash: tx_hash.clone(),
+                    credits_granted: credits,
+                    new_balance: balance.credits_remaining,
+                };
+                tokio::spawn(async move {
+                    webhook.send(&event).await;
+                });
+            }
+
             Ok(Json(DepositResponse {
                 deposit_id,
+                amount_usdc: PricingCalculator::format_usdc(verified_amount),
                 credits_granted: credits,
+                credits_granted_usdc: PricingCalculator::format_usdc(credits),
                 new_balance: balance.credits_remaining,
                 tx_hash,
+                token,
                 status: crate::types::DepositStatus::Confirmed,
             }))
         }
-        Err(PaymentError::DuplicateTransaction(_)) => Err((
-            StatusCode::CONFLICT,
-            Json(ErrorResponse::new(
-                "Transaction already processed",
-                "DUPLICATE_TX",
-            )),
-        )),
+        Err(e @ (PaymentError::DuplicateTransaction(_) | PaymentError::IdempotencyKeyReused(_))) => {
+            // A concurrent retry with the same key may have won the race
+            if let Some(ref key) = idempotency_key {
+                if let Some(response) = replay_deposit(&state, key, &request).await {
+                    return response;
+                }
+            }
+            match e {
+                PaymentError::IdempotencyKeyReused(_) => Err(idempotency_key_reused()),
+                _ => Err((
+                    StatusCode::CONFLICT,
+                    Json(ErrorResponse::new(
+                        "Transaction already processed",
+                        "DUPLICATE_TX",
+                    )),
+                )),
+            }
+        }
         Err(e) => {
             error!("Failed to process deposit: {}", e);
             Err((
@@ -272,83 +439,111 @@ async fn process_deposit(
     }
 }
 
+/// The request's idempotency key, if it sent one.
+fn idempotency_key(headers: &HeaderMap) -> Result<Option<String>, (StatusCode, Json<ErrorResponse>)> {
+    let Some(value) = headers.get(IDEMPOTENCY_KEY_HEADER) else {
+        return Ok(None);
+    };
+    match value.to_str().map(str::trim) {
+        Ok(key) if !key.is_empty() && key.len() <= MAX_IDEMPOTENCY_KEY_LENGTH => Ok(Some(key.to_string())),
+        _ => Err((
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse::new(
+                format!(
+                    "Idempotency-Key must be 1 to {} printable characters",
+                    MAX_IDEMPOTENCY_KEY_LENGTH
+                ),
+                "INVALID_IDEMPOTENCY_KEY",
+            )),
+        )),
+    }
+}
+
+/// Answer a repeated idempotency key with the original deposit response.
+///
+/// `None` if the key hasn't been used; an error if it was used for a
+/// different deposit.
+async fn replay_deposit(
+    state: &AppState,
+    key: &str,
+    request: &DepositRequest,
+) -> Option<Result<Json<DepositResponse>, (StatusCode, Json<ErrorResponse>)>> {
+    let (deposit, new_balance) = state.credit_store.idempotent_deposit(&request.user_id, key).await?;
+
+    if deposit.chain != request.chain || deposit.tx_hash != request.tx_hash || deposit.user_id != request.user_id {
+        return Some(Err(idempotency_key_reused()));
+    }
+
+    info!("Replaying deposit {} for a repeated idempotency key", deposit.id);
+    Some(Ok(Json(DepositResponse {
+        deposit_id: deposit.id,
+        amount_usdc: PricingCalculator::format_usdc(deposit.amount_usdc),
+        credits_granted: deposit.credits_granted,
+        credits_granted_usdc: PricingCalculator::format_usdc(deposit.credits_granted),
+        new_balance,
+        tx_hash: deposit.tx_hash,
+        token: deposit.token,
+        status: deposit.status,
+    })))
+}
+
+fn idempotency_key_reused() -> (StatusCode, Json<ErrorResponse>) {
+    (
+        StatusCode::UNPROCESSABLE_ENTITY,
+        Json(ErrorResponse::new(
+            "Idempotency-Key was already used for a different deposit",
+            "IDEMPOTENCY_KEY_REUSED",
+        )),
+    )
+}
+
+/// Parse a chain name from a request path.
+fn parse_chain(chain: &str) -> Result<Chain, (StatusCode, Json<ErrorResponse>)> {
+    match chain.to_lowercase().as_str() {
+        "base" => Ok(Chain::Base),
+        "near" => Ok(Chain::Near),
+        "solana" => Ok(Chain::Solana),
+        _ => Err((
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse::new(
+                format!("Unknown chain: {}", chain),
+                "UNKNOWN_CHAIN",
+            )),
+        )),
+    }
+}
+
+/// Look up a chain's deposit address, in `account`'s wallets when one is
+/// named, and its USDC token contract.
+fn deposit_address_for(
+    state: &AppState,
+    chain: Chain,
+    account: Option<&str>,
+) -> Result<(String, String), (StatusCode, Json<ErrorResponse>)> {
+    let token_contract = match chain {
+        Chain::Base => state.config.base.as_ref().map(|c| c.usdc_contract.clone()),
+        Chain::Near => state.config.near.as_ref().map(|c| c.usdc_contract.clone()),
+        Chain::Solana => state.config.solana.as_ref().map(|c| c.usdc_mint.clone()),
+    }
+    .ok_or_else(|| {
+        (
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse::new(format!("{} not configured", chain), "CHAIN_DISABLED")),
+        )
+    })?;
+    let facilitator = state.facilitator(chain, account)?;
+
+    Ok((facilitator.deposit_address(), token_contract))
+}
+
 /// Get deposit address for a chain.
 async fn get_deposit_address(
     State(state): State<Arc<AppState>>,
     Path(chain): Path<String>,
+    Query(params): Query<DepositAddressParams>,
 ) -> Result<Json<DepositAddressResponse>, (StatusCode, Json<ErrorResponse>)> {
-    let chain = match chain.to_lowercase().as_str() {
-        "base" => Chain::Base,
-        "near" => Chain::Near,
-        "solana" => Chain::Solana,
-        _ => {
-            return Err((
-                StatusCode::BAD_REQUEST,
-                Json(ErrorResponse::new(
-                    format!("Unknown chain: {}", chain),
-                    "UNKNOWN_CHAIN",
-                )),
-            ));
-        }
-    };
-
-    // Get actual deposit addresses from facilitators
-    let (address, token_contract) = match chain {
-        Chain::Base => {
-            let config = state.config.base.as_ref().ok_or_else(|| {
-                (
-                    StatusCode::BAD_REQUEST,
-                    Json(ErrorResponse::new("Base not configured", "CHAIN_DISABLED")),
-                )
-            })?;
-            let facilitator = state.base.as_ref().ok_or_else(|| {
-                (
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    Json(ErrorResponse::new("Base facilitator not initialized", "CHAIN_NOT_AVAILABLE")),
-                )
-            })?;
-            (
-                facilitator.deposit_address(),
-                config.usdc_contract.clone(),
-            )
-        }
-        Chain::Near => {
-            let config = state.config.near.as_ref().ok_or_else(|| {
-                (
-                    StatusCode::BAD_REQUEST,
-                    Json(ErrorResponse::new("NEAR not configured", "CHAIN_DISABLED")),
-                )
-            })?;
-            let facilitator = state.near.as_ref().ok_or_else(|| {
-                (
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    Json(ErrorResponse::new("NEAR facilitator not initialized", "CHAIN_NOT_AVAILABLE")),
-                )
-            })?;
-            (
-                facilitator.deposit_address(),
-                config.usdc_contract.clone(),
-            )
-        }
-        Chain::Solana => {
-            let config = state.config.solana.as_ref().ok_or_else(|| {
-                (
-                    StatusCode::BAD_REQUEST,
-                    Json(ErrorResponse::new("Solana not configured", "CHAIN_DISABLED")),
-                )
-            })?;
-            let facilitator = state.solana.as_ref().ok_or_else(|| {
-                (
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    Json(ErrorResponse::new("Solana facilitator not initialized", "CHAIN_NOT_AVAILABLE")),
-                )
-            })?;
-            (
-                facilitator.deposit_address(),
-                config.usdc_mint.clone(),
-            )
-        }
-    };
+    let chain = parse_chain(&chain)?;
+    let (address, token_contract) = deposit_address_for(&state, chain, params.account.as_deref())?;
 
     Ok(Json(DepositAddressResponse {
         chain,
@@ -359,6 +554,42 @@ async fn get_deposit_address(
     }))
 }
 
+/// Get a PNG QR code of a chain's deposit address.
+///
+/// For NEAR with `?user_id=`, the QR also carries the memo the user has to
+/// enter, as text below the account id.
+async fn get_deposit_address_qr(
+    State(state): State<Arc<AppState>>,
+    Path(chain): Path<String>,
+    Query(params): Query<DepositQrParams>,
+) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+    let chain = parse_chain(&chain)?;
+    let (address, _) = deposit_address_for(&state, chain, params.account.as_deref())?;
+
+    let payload = deposit_qr_payload(chain, &address, params.user_id.as_deref());
+    let png = qr::render_png(&payload).ok_or_else(|| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse::new("Deposit address too long for a QR code", "QR_FAILED")),
+        )
+    })?;
+
+    Ok(([(CONTENT_TYPE, "image/png")], png))
+}
+
+/// Text encoded in a deposit QR code.
+///
+/// NEAR has no payment URI wallets agree on, so a memo is added as plain
+/// text on its own line for the user to copy.
+fn deposit_qr_payload(chain: Chain, address: &str, user_id: Option<&str>) -> String {
+    match (chain, user_id) {
+        (Chain::Near, Some(user_id)) if !user_id.is_empty() => {
+            format!("{}\nMemo: {}", address, user_id)
+        }
+        _ => address.to_string(),
+    }
+}
+
 /// Get pricing information.
 async fn get_pricing(State(state): State<Arc<AppState>>) -> Json<PricingResponse> {
     let config = &state.config.pricing;
@@ -392,6 +623,1417 @@ async fn get_pricing(State(state): State<Arc<AppState>>) -> Json<PricingResponse
             config.completion_credits_per_million,
         ),
         minimum_per_message: PricingCalculator::format_usdc(config.minimum_credits_per_message),
+        minimum_deposit: PricingCalculator::format_usdc(state.config.min_deposit_usdc),
+        maximum_deposit: state.config.max_deposit_usdc.map(PricingCalculator::format_usdc),
         supported_chains: chains,
     })
 }
+
+/// Message length, in characters, used to turn a quote into a message count.
+const QUOTE_MESSAGE_CHARS: usize = 500;
+
+/// Preview the credits a deposit would grant, without side effects.
+async fn get_quote(
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<QuoteRequest>,
+) -> Result<Json<QuoteResponse>, (StatusCode, Json<ErrorResponse>)> {
+    quote_deposit(&state.config, &state.pricing, &request).map(Json)
+}
+
+/// Quote a deposit with the same checks and conversion `process_deposit` applies.
+fn quote_deposit(
+    config: &PaymentConfig,
+    pricing: &PricingCalculator,
+    request: &QuoteRequest,
+) -> Result<QuoteResponse, (StatusCode, Json<ErrorResponse>)> {
+    if !config.enabled_chains().contains(&request.chain) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse::new(
+                format!("Chain {} is not enabled", request.chain),
+                "CHAIN_DISABLED",
+            )),
+        ));
+    }
+    check_deposit_bounds(config, request.amount)?;
+
+    let credits = pricing.usdc_to_credits(request.amount);
+    let per_message = pricing.estimate(QUOTE_MESSAGE_CHARS).max(1);
+    Ok(QuoteResponse {
+        chain: request.chain,
+        amount_usdc: request.amount,
+        amount_display: PricingCalculator::format_usdc(request.amount),
+        credits,
+        approx_messages: credits / per_message,
+    })
+}
+
+/// Estimate the cost of a hypothetical message.
+async fn get_estimate(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<EstimateParams>,
+) -> Json<EstimateResponse> {
+    Json(estimate_message(&state.config.pricing, params))
+}
+
+/// Price a prompt of `params.prompt_tokens` plus a typical response, at
+/// `params.model`'s rates when it has its own.
+fn estimate_message(config: &PricingConfig, params: EstimateParams) -> EstimateResponse {
+    let pricing = PricingCalculator::new(match params.model.as_deref() {
+        Some(model) => config.for_model(model),
+        None => config.clone(),
+    });
+    let usage = TokenUsage::estimated(params.prompt_tokens);
+    let credits = pricing.estimate_tokens(params.prompt_tokens);
+    let cost_usdc = pricing.credits_to_usdc(credits);
+    EstimateResponse {
+        model: params.model,
+        prompt_tokens: usage.prompt_tokens,
+        completion_tokens: usage.completion_tokens,
+        credits,
+        cost_usdc,
+        cost_display: PricingCalculator::format_usdc(cost_usdc),
+    }
+}
+
+/// Reserve a distinct deposit amount for a user.
+///
+/// Deposit addresses are shared, so on Base and Solana (no memo) the exact
+/// amount is what ties a transfer to the user who sent it. Only 9,999
+/// amounts exist per chain and size, so reservations are made by the bot
+/// with the admin token rather than by anonymous callers; each user holds at
+/// most one per chain.
+async fn create_deposit_intent(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(request): Json<DepositIntentRequest>,
+) -> Result<Json<DepositIntentResponse>, (StatusCode, Json<ErrorResponse>)> {
+    if !is_admin(&state.config, &headers) {
+        warn!(user_id = %request.user_id, "Rejected unauthorized deposit intent");
+        return Err((
+            StatusCode::UNAUTHORIZED,
+            Json(ErrorResponse::new("Admin token required", "UNAUTHORIZED")),
+        ));
+    }
+    if !state.config.enabled_chains().contains(&request.chain) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse::new(
+                format!("Chain {} is not enabled", request.chain),
+                "CHAIN_DISABLED",
+            )),
+        ));
+    }
+    check_deposit_bounds(&state.config, request.amount)?;
+
+    let intent = state
+        .credit_store
+        .create_deposit_intent(
+            &request.user_id,
+            request.chain,
+            request.amount,
+            state.config.deposit_intent_ttl,
+        )
+        .await
+        .map_err(|e| {
+            error!("Failed to reserve deposit amount: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ErrorResponse::new(e.to_string(), "INTENT_FAILED")),
+            )
+        })?;
+
+    Ok(Json(DepositIntentResponse {
+        chain: intent.chain,
+        amount_usdc: intent.amount_usdc,
+        amount_display: PricingCalculator::format_usdc(intent.amount_usdc),
+        expires_at: intent.expires_at,
+    }))
+}
+
+/// Link a sender address to a user.
+///
+/// With `require_linked_sender`, Base and Solana deposits are only credited
+/// to the user who linked the address they came from, so the caller must
+/// prove control of the address (or present the admin token).
+async fn link_address(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(request): Json<LinkAddressRequest>,
+) -> Result<Json<LinkAddressResponse>, (StatusCode, Json<ErrorResponse>)> {
+    authorize_address_change(&state, &headers, &request, AddressAction::Link)?;
+
+    let linked = state
+        .credit_store
+        .link_address(&request.user_id, request.chain, &request.address)
+        .await
+        .map_err(|e| match e {
+            PaymentError::AddressAlreadyLinked(_) => (
+                StatusCode::CONFLICT,
+                Json(ErrorResponse::new(e.to_string(), "ADDRESS_LINKED")),
+            ),
+            e => {
+                error!("Failed to link address: {}", e);
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(ErrorResponse::new(e.to_string(), "LINK_FAILED")),
+                )
+            }
+        })?;
+
+    Ok(Json(LinkAddressResponse {
+        chain: linked.chain,
+        address: linked.address,
+        linked_at: linked.linked_at,
+    }))
+}
+
+/// Unlink a sender address from the user who linked it.
+async fn unlink_address(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(request): Json<LinkAddressRequest>,
+) -> Result<Json<LinkAddressResponse>, (StatusCode, Json<ErrorResponse>)> {
+    authorize_address_change(&state, &headers, &request, AddressAction::Unlink)?;
+
+    let unlinked = state
+        .credit_store
+        .unlink_address(&request.user_id, request.chain, &request.address)
+        .await
+        .map_err(|e| match e {
+            PaymentError::AddressNotLinked(_) => (
+                StatusCode::NOT_FOUND,
+                Json(ErrorResponse::new(e.to_string(), "ADDRESS_NOT_LINKED")),
+            ),
+            e => {
+                error!("Failed to unlink address: {}", e);
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(ErrorResponse::new(e.to_string(), "UNLINK_FAILED")),
+                )
+            }
+        })?;
+
+    Ok(Json(LinkAddressResponse {
+        chain: unlinked.chain,
+        address: unlinked.address,
+        linked_at: unlinked.linked_at,
+    }))
+}
+
+/// Check that an address can be linked on the request's chain and that the
+/// caller controls it: either the admin token or the address's signature
+/// over the [`ownership_message`] for `action`.
+fn authorize_address_change(
+    state: &AppState,
+    headers: &HeaderMap,
+    request: &LinkAddressRequest,
+    action: AddressAction,
+) -> Result<(), (StatusCode, Json<ErrorResponse>)> {
+    if !state.config.enabled_chains().contains(&request.chain) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse::new(
+                format!("Chain {} is not enabled", request.chain),
+                "CHAIN_DISABLED",
+            )),
+        ));
+    }
+    if request.chain.supports_memo() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse::new(
+                format!("{} deposits are attributed by memo", request.chain),
+                "MEMO_CHAIN",
+            )),
+        ));
+    }
+    if request.address.trim().is_empty() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse::new("Address is required", "INVALID_ADDRESS")),
+        ));
+    }
+    if is_admin(&state.config, headers) {
+        return Ok(());
+    }
+
+    let (Some(signature), Some(timestamp)) = (request.signature.as_deref(), request.timestamp) else {
+        return Err((
+            StatusCode::UNAUTHORIZED,
+            Json(ErrorResponse::new(
+                "A signature by the address and its timestamp are required",
+                "SIGNATURE_REQUIRED",
+            )),
+        ));
+    };
+    let message = ownership_message(action, request.chain, &request.address, &request.user_id, timestamp);
+    verify_ownership(request.chain, &request.address, &message, signature, timestamp, Utc::now()).map_err(
+        |e| match e {
+            ProofError::Expired => (
+                StatusCode::UNAUTHORIZED,
+                Json(ErrorResponse::new(
+                    format!(
+                        "Signature timestamp must be within {} minutes of now",
+                        MAX_SIGNATURE_AGE.num_minutes()
+                    ),
+                    "SIGNATURE_EXPIRED",
+                )),
+            ),
+            ProofError::Malformed(reason) => (
+                StatusCode::BAD_REQUEST,
+                Json(ErrorResponse::new(reason, "INVALID_SIGNATURE")),
+            ),
+            ProofError::Mismatch => {
+                warn!(
+                    "Rejected {} address change for {}: signature not made by {}",
+                    request.chain, request.user_id, request.address
+                );
+                (
+                    StatusCode::FORBIDDEN,
+                    Json(ErrorResponse::new(
+                        "Signature was not made by the address",
+                        "SIGNATURE_MISMATCH",
+                    )),
+                )
+            }
+        },
+    )
+}
+
+/// Check that a memo-less deposit was sent from an address the claiming
+/// user linked.
+///
+/// `owner` is whoever linked the verified sender `from`.
+fn check_deposit_sender(
+    request: &DepositRequest,
+    from: Option<&str>,
+    owner: Option<&str>,
+) -> Result<(), (StatusCode, Json<ErrorResponse>)> {
+    let Some(from) = from else {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse::new(
+                "The transfer's sender could not be determined",
+                "SENDER_UNKNOWN",
+            )),
+        ));
+    };
+    match owner {
+        Some(owner) if owner == request.user_id => Ok(()),
+        Some(_) => {
+            warn!(
+                "Rejected {} deposit {}: sender {} is linked to another user",
+                request.chain, request.tx_hash, from
+            );
+            Err((
+                StatusCode::FORBIDDEN,
+                Json(ErrorResponse::new(
+                    "Deposit was sent from an address linked to another user",
+                    "SENDER_MISMATCH",
+                )),
+            ))
+        }
+        None => Err((
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse::new(
+                format!(
+                    "Sender {} is not linked to any user; link it with POST /v1/link-address before sending",
+                    from
+                ),
+                "SENDER_NOT_LINKED",
+            )),
+        )),
+    }
+}
+
+/// Check that a memo-less deposit belongs to the user claiming it.
+///
+/// `owner` is whoever reserved exactly this amount. A deposit matching
+/// someone else's reservation is always rejected; one matching nobody's is
+/// rejected only when `require_unique_amount` is set.
+fn check_deposit_attribution(
+    config: &PaymentConfig,
+    request: &DepositRequest,
+    amount: u64,
+    owner: Option<&str>,
+) -> Result<(), (StatusCode, Json<ErrorResponse>)> {
+    match owner {
+        Some(owner) if owner == request.user_id => Ok(()),
+        Some(_) => {
+            warn!(
+                "Rejected {} deposit {}: amount {} is reserved for another user",
+                request.chain, request.tx_hash, amount
+            );
+            Err((
+                StatusCode::FORBIDDEN,
+                Json(ErrorResponse::new(
+                    "Deposit amount is reserved for another user",
+                    "INTENT_MISMATCH",
+                )),
+            ))
+        }
+        None if config.require_unique_amount => Err((
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse::new(
+                "No deposit request matches this amount; reserve one with POST /v1/deposit-intent before sending",
+                "NO_DEPOSIT_INTENT",
+            )),
+        )),
+        None => {
+            warn!(
+                "Crediting {} deposit {} without a reserved amount; the sender can't be told apart on the shared address",
+                request.chain, request.tx_hash
+            );
+            Ok(())
+        }
+    }
+}
+
+/// Reject verified deposits outside the configured bounds.
+fn check_deposit_bounds(
+    config: &PaymentConfig,
+    amount: u64,
+) -> Result<(), (StatusCode, Json<ErrorResponse>)> {
+    if amount < config.min_deposit_usdc {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse::new(
+                format!(
+                    "Deposit of {} is below the minimum of {}",
+                    PricingCalculator::format_usdc(amount),
+                    PricingCalculator::format_usdc(config.min_deposit_usdc)
+                ),
+                "DEPOSIT_TOO_SMALL",
+            )),
+        ));
+    }
+
+    if let Some(max) = config.max_deposit_usdc.filter(|max| amount > *max) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse::new(
+                format!(
+                    "Deposit of {} is above the maximum of {}",
+                    PricingCalculator::format_usdc(amount),
+                    PricingCalculator::format_usdc(max)
+                ),
+                "DEPOSIT_TOO_LARGE",
+            )),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Check the request's bearer token against the configured admin token.
+///
+/// Always fails when no admin token is configured.
+fn is_admin(config: &PaymentConfig, headers: &HeaderMap) -> bool {
+    let Some(expected) = config.admin_token.as_ref() else {
+        return false;
+    };
+
+    let provided = headers
+        .get(AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "));
+
+    match provided {
+        Some(token) => constant_time_eq(token.as_bytes(), expected.expose_secret().as_bytes()),
+        None => false,
+    }
+}
+
+fn sweeper_or_404(
+    state: &AppState,
+) -> Result<&Arc<FundSweeper>, (StatusCode, Json<ErrorResponse>)> {
+    state.sweeper.as_ref().ok_or_else(|| {
+        (
+            StatusCode::NOT_FOUND,
+            Json(ErrorResponse::new(
+                "Fund sweeper is not running",
+                "SWEEPER_DISABLED",
+            )),
+        )
+    })
+}
+
+/// Get fund sweeper status (admin only, since it reveals deposit wallet
+/// balances).
+async fn get_sweep_status(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+) -> Result<Json<SweepStatus>, (StatusCode, Json<ErrorResponse>)> {
+    if !is_admin(&state.config, &headers) {
+        warn!("Rejected unauthorized sweep status request");
+        return Err((
+            StatusCode::UNAUTHORIZED,
+            Json(ErrorResponse::new("Admin token required", "UNAUTHORIZED")),
+        ));
+    }
+
+    let sweeper = sweeper_or_404(&state)?;
+    Ok(Json(sweeper.status().await))
+}
+
+/// Run a sweep cycle immediately (admin only).
+async fn run_sweep(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+) -> Result<Json<Vec<SweepRecord>>, (StatusCode, Json<ErrorResponse>)> {
+    if !is_admin(&state.config, &headers) {
+        warn!("Rejected unauthorized sweep trigger");
+        return Err((
+            StatusCode::UNAUTHORIZED,
+            Json(ErrorResponse::new("Admin token required", "UNAUTHORIZED")),
+        ));
+    }
+
+    let sweeper = sweeper_or_404(&state)?;
+
+    info!("Manual sweep triggered via admin API");
+    let records = sweeper.sweep_once().await;
+
+    Ok(Json(records))
+}
+
+/// Recompute balances from the deposit and usage logs (admin only).
+///
+/// Reports discrepancies without changing anything unless `?apply=true`.
+async fn reconcile_balances(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Query(params): Query<ReconcileParams>,
+) -> Result<Json<ReconcileResponse>, (StatusCode, Json<ErrorResponse>)> {
+    if !is_admin(&state.config, &headers) {
+        warn!("Rejected unauthorized balance reconciliation");
+        return Err((
+            StatusCode::UNAUTHORIZED,
+            Json(ErrorResponse::new("Admin token required", "UNAUTHORIZED")),
+        ));
+    }
+
+    info!(apply = params.apply, "Balance reconciliation triggered via admin API");
+    let discrepancies = state.credit_store.reconcile(params.apply).await.map_err(|e| {
+        error!("Balance reconciliation failed: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse::new(e.to_string(), "RECONCILE_FAILED")),
+        )
+    })?;
+
+    Ok(Json(ReconcileResponse {
+        applied: params.apply && !discrepancies.is_empty(),
+        discrepancies,
+    }))
+}
+
+/// Reverse credits granted by a mistaken or fraudulent deposit (admin only).
+async fn refund_credits(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(request): Json<RefundRequest>,
+) -> Result<Json<RefundResponse>, (StatusCode, Json<ErrorResponse>)> {
+    if !is_admin(&state.config, &headers) {
+        warn!("Rejected unauthorized refund");
+        return Err((
+            StatusCode::UNAUTHORIZED,
+            Json(ErrorResponse::new("Admin token required", "UNAUTHORIZED")),
+        ));
+    }
+
+    if request.credits == 0 || request.reason.trim().is_empty() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse::new(
+                "A refund needs a positive credit amount and a reason",
+                "INVALID_REFUND",
+            )),
+        ));
+    }
+
+    let balance = state
+        .credit_store
+        .refund_credits(&request.user_id, request.credits, request.reason.trim())
+        .await
+        .map_err(|e| match e {
+            PaymentError::InsufficientCredits { .. } => (
+                StatusCode::CONFLICT,
+                Json(ErrorResponse::new(e.to_string(), "INSUFFICIENT_CREDITS")),
+            ),
+            e => {
+                error!("Refund failed: {}", e);
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(ErrorResponse::new(e.to_string(), "INTERNAL_ERROR")),
+                )
+            }
+        })?;
+
+    Ok(Json(RefundResponse {
+        user_id: request.user_id,
+        credits_refunded: request.credits,
+        new_balance: balance.credits_remaining,
+    }))
+}
+
+/// Move credits from one user to another, e.g. as a gift (admin only).
+async fn transfer_credits(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(request): Json<TransferRequest>,
+) -> Result<Json<TransferResponse>, (StatusCode, Json<ErrorResponse>)> {
+    if !is_admin(&state.config, &headers) {
+        warn!("Rejected unauthorized credit transfer");
+        return Err((
+            StatusCode::UNAUTHORIZED,
+            Json(ErrorResponse::new("Admin token required", "UNAUTHORIZED")),
+        ));
+    }
+
+    if request.credits == 0 || request.from == request.to {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse::new(
+                "A transfer needs a positive credit amount and two different users",
+                "INVALID_TRANSFER",
+            )),
+        ));
+    }
+
+    let (from_balance, to_balance) = state
+        .credit_store
+        .transfer_credits(&request.from, &request.to, request.credits)
+        .await
+        .map_err(|e| match e {
+            PaymentError::InsufficientCredits { .. } => (
+                StatusCode::CONFLICT,
+                Json(ErrorResponse::new(e.to_string(), "INSUFFICIENT_CREDITS")),
+            ),
+            e => {
+                error!("Credit transfer failed: {}", e);
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(ErrorResponse::new(e.to_string(), "INTERNAL_ERROR")),
+                )
+            }
+        })?;
+
+    Ok(Json(TransferResponse {
+        from: request.from,
+        to: request.to,
+        credits_transferred: request.credits,
+        from_balance: from_balance.credits_remaining,
+        to_balance: to_balance.credits_remaining,
+    }))
+}
+
+/// Report where the credit store's encryption key came from (admin only).
+async fn get_key_source(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+) -> Result<Json<KeySourceResponse>, (StatusCode, Json<ErrorResponse>)> {
+    if !is_admin(&state.config, &headers) {
+        warn!("Rejected unauthorized key source request");
+        return Err((
+            StatusCode::UNAUTHORIZED,
+            Json(ErrorResponse::new("Admin token required", "UNAUTHORIZED")),
+        ));
+    }
+
+    Ok(Json(KeySourceResponse {
+        source: state.credit_store.key_source(),
+        fallback: state.credit_store.uses_fallback_key(),
+    }))
+}
+
+/// Re-encrypt the credit store under a freshly derived key (admin only).
+async fn reseal_store(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+) -> Result<Json<ResealResponse>, (StatusCode, Json<ErrorResponse>)> {
+    if !is_admin(&state.config, &headers) {
+        warn!("Rejected unauthorized credit store reseal");
+        return Err((
+            StatusCode::UNAUTHORIZED,
+            Json(ErrorResponse::new("Admin token required", "UNAUTHORIZED")),
+        ));
+    }
+
+    info!("Credit store reseal triggered via admin API");
+    let previous_source = state.credit_store.key_source();
+    let source = state.credit_store.reseal().await.map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse::new(e.to_string(), "RESEAL_FAILED")),
+        )
+    })?;
+
+    Ok(Json(ResealResponse {
+        source,
+        previous_source,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    fn config_with_token(token: Option<&str>) -> PaymentConfig {
+        PaymentConfig {
+            admin_token: token.map(|t| t.to_string().into()),
+            ..Default::default()
+        }
+    }
+
+    fn bearer(token: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            AUTHORIZATION,
+            HeaderValue::from_str(&format!("Bearer {}", token)).unwrap(),
+        );
+        headers
+    }
+
+    #[test]
+    fn test_admin_token_accepted() {
+        let config = config_with_token(Some("s3cret"));
+        assert!(is_admin(&config, &bearer("s3cret")));
+    }
+
+    #[test]
+    fn test_admin_token_rejected() {
+        let config = config_with_token(Some("s3cret"));
+        assert!(!is_admin(&config, &bearer("wrong")));
+        assert!(!is_admin(&config, &bearer("s3cre")));
+        assert!(!is_admin(&config, &HeaderMap::new()));
+    }
+
+    #[test]
+    fn test_deposit_bounds() {
+        let config = PaymentConfig {
+            min_deposit_usdc: 100_000,
+            max_deposit_usdc: Some(1_000_000_000),
+            ..Default::default()
+        };
+
+        let (status, Json(body)) = check_deposit_bounds(&config, 1).unwrap_err();
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body.code, "DEPOSIT_TOO_SMALL");
+
+        let (_, Json(body)) = check_deposit_bounds(&config, 1_000_000_001).unwrap_err();
+        assert_eq!(body.code, "DEPOSIT_TOO_LARGE");
+
+        assert!(check_deposit_bounds(&config, 100_000).is_ok());
+        assert!(check_deposit_bounds(&config, 1_000_000_000).is_ok());
+
+        // No ceiling unless one is configured
+        let uncapped = PaymentConfig::default();
+        assert!(check_deposit_bounds(&uncapped, u64::MAX).is_ok());
+    }
+
+    #[test]
+    fn test_quote_deposit() {
+        let config = PaymentConfig {
+            base: Some(serde_json::from_str("{}").unwrap()),
+            min_deposit_usdc: 100_000,
+            ..Default::default()
+        };
+        let pricing = PricingCalculator::new(config.pricing.clone());
+
+        let quote = quote_deposit(&config, &pricing, &QuoteRequest { chain: Chain::Base, amount: 5_000_000 })
+            .unwrap();
+        assert_eq!(quote.credits, pricing.usdc_to_credits(5_000_000));
+        assert_eq!(quote.amount_display, "$5.000000");
+        assert_eq!(quote.approx_messages, quote.credits / pricing.estimate(QUOTE_MESSAGE_CHARS));
+        assert!(quote.approx_messages > 0);
+
+        let (_, Json(body)) =
+            quote_deposit(&config, &pricing, &QuoteRequest { chain: Chain::Base, amount: 1 }).unwrap_err();
+        assert_eq!(body.code, "DEPOSIT_TOO_SMALL");
+
+        let (_, Json(body)) =
+            quote_deposit(&config, &pricing, &QuoteRequest { chain: Chain::Solana, amount: 5_000_000 })
+                .unwrap_err();
+        assert_eq!(body.code, "CHAIN_DISABLED");
+    }
+
+    /// A payment API with Base enabled against `server`, plus the store
+    /// behind it. Keep the `TempDir` alive for the store's lifetime.
+    async fn base_deposit_state(
+        server: &wiremock::MockServer,
+        tokens: Vec<crate::config::TokenConfig>,
+    ) -> (Arc<AppState>, Arc<CreditStore>, tempfile::TempDir) {
+        use dstack_client::MockDstackClient;
+
+        let mut base: crate::config::BaseChainConfig =
+            serde_json::from_value(serde_json::json!({ "rpc_url": server.uri() })).unwrap();
+        base.tokens = tokens;
+        let config = PaymentConfig {
+            base: Some(base.clone()),
+            ..Default::default()
+        };
+        let facilitator = BaseFacilitator::new(base, &MockDstackClient::new()).await.unwrap();
+
+        let dir = tempfile::TempDir::new().unwrap();
+        let store = CreditStore::new(MockDstackClient::new(), dir.path().join("credits.enc"))
+            .await
+            .unwrap();
+        let state = Arc::new(AppState::new(store.clone(), config, Some(Arc::new(facilitator)), None, None));
+        (state, store, dir)
+    }
+
+    /// Mount a receipt for one transfer of `amount` of the ERC-20 at
+    /// `contract` to the Base deposit wallet, which must be verified once.
+    async fn mock_base_transfer(server: &wiremock::MockServer, state: &AppState, contract: &str, amount: u64) {
+        use wiremock::matchers::{body_partial_json, method};
+        use wiremock::{Mock, ResponseTemplate};
+
+        let pad = |address: &str| format!("0x{:0>64}", address.trim_start_matches("0x").to_lowercase());
+        let deposit_address = state.base.as_ref().unwrap().deposit_address();
+
+        Mock::given(method("POST"))
+            .and(body_partial_json(serde_json::json!({ "method": "eth_getTransactionReceipt" })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "result": {
+                    "status": "0x1",
+                    "blockNumber": "0xa",
+                    "logs": [{
+                        "address": contract,
+                        "topics": [
+                            crate::chains::base::TRANSFER_EVENT_SIGNATURE,
+                            pad("0x1111111111111111111111111111111111111111"),
+                            pad(&deposit_address),
+                        ],
+                        "data": format!("0x{:064x}", amount)
+                    }]
+                }
+            })))
+            .expect(1)
+            .mount(server)
+            .await;
+        Mock::given(method("POST"))
+            .and(body_partial_json(serde_json::json!({ "method": "eth_blockNumber" })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "result": "0x10"
+            })))
+            .mount(server)
+            .await;
+    }
+
+    #[tokio::test]
+    async fn test_deposit_address_per_account() {
+        use dstack_client::MockDstackClient;
+
+        let server = wiremock::MockServer::start().await;
+        let (state, store, _dir) = base_deposit_state(&server, vec![]).await;
+        let account = "+15550001111";
+        let subject = state.config.account_subject(account);
+        let base = state.config.base.clone().unwrap();
+        let facilitator = BaseFacilitator::new_with_subject(base, &MockDstackClient::new(), Some(&subject))
+            .await
+            .unwrap();
+        let wallets = DepositWallets {
+            base: Some(Arc::new(facilitator)),
+            ..Default::default()
+        };
+        let state = Arc::new(
+            AppState::new(store, state.config.clone(), state.base.clone(), None, None)
+                .with_accounts(HashMap::from([(account.to_string(), wallets)])),
+        );
+        let address = |account: Option<&str>| {
+            let params = DepositAddressParams {
+                account: account.map(String::from),
+            };
+            get_deposit_address(State(state.clone()), Path("base".to_string()), Query(params))
+        };
+
+        let Json(shared) = address(None).await.unwrap();
+        let Json(own) = address(Some(account)).await.unwrap();
+        assert_eq!(shared.address, state.base.as_ref().unwrap().deposit_address());
+        assert_ne!(own.address, shared.address);
+
+        let (status, Json(body)) = address(Some("+15559999999")).await.unwrap_err();
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(body.code, "UNKNOWN_ACCOUNT");
+    }
+
+    #[tokio::test]
+    async fn test_deposit_idempotency_key() {
+        let server = wiremock::MockServer::start().await;
+        let (state, store, _dir) = base_deposit_state(&server, vec![]).await;
+        let usdc = state.config.base.as_ref().unwrap().usdc_contract.clone();
+        mock_base_transfer(&server, &state, &usdc, 2_000_000).await;
+
+        let request = || DepositRequest {
+            chain: Chain::Base,
+            tx_hash: "0xabc".to_string(),
+            user_id: "+14155551234".to_string(),
+            amount: 2_000_000,
+            from: None,
+            account: None,
+        };
+        let mut headers = HeaderMap::new();
+        headers.insert(IDEMPOTENCY_KEY_HEADER, HeaderValue::from_static("retry-1"));
+
+        let Json(first) = process_deposit(State(state.clone()), headers.clone(), Json(request()))
+            .await
+            .unwrap();
+        let Json(second) = process_deposit(State(state.clone()), headers.clone(), Json(request()))
+            .await
+            .unwrap();
+        assert_eq!(serde_json::to_value(&first).unwrap(), serde_json::to_value(&second).unwrap());
+        assert_eq!(store.get_balance("+14155551234").await.credits_remaining, first.credits_granted);
+        assert_eq!(store.get_deposits("+14155551234").await.len(), 1);
+
+        // Without the key the retry is a duplicate
+        let (status, Json(body)) = process_deposit(State(state.clone()), HeaderMap::new(), Json(request()))
+            .await
+            .unwrap_err();
+        assert_eq!(status, StatusCode::CONFLICT);
+        assert_eq!(body.code, "DUPLICATE_TX");
+
+        // The key can't be reused for another deposit
+        let other = DepositRequest {
+            tx_hash: "0xdef".to_string(),
+            ..request()
+        };
+        let (status, Json(body)) = process_deposit(State(state), headers, Json(other)).await.unwrap_err();
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(body.code, "IDEMPOTENCY_KEY_REUSED");
+    }
+
+    #[tokio::test]
+    async fn test_deposit_in_usdt() {
+        const USDT: &str = "0xfde4c96c8593536e31f229ea8f37b2ada2699bb2";
+        let server = wiremock::MockServer::start().await;
+        let usdt = crate::config::TokenConfig {
+            symbol: "USDT".to_string(),
+            contract: USDT.to_string(),
+            decimals: 6,
+        };
+        let (state, store, _dir) = base_deposit_state(&server, vec![usdt]).await;
+        mock_base_transfer(&server, &state, USDT, 5_000_000).await;
+
+        let request = DepositRequest {
+            chain: Chain::Base,
+            tx_hash: "0xabc".to_string(),
+            user_id: "+14155551234".to_string(),
+            amount: 5_000_000,
+            from: None,
+            account: None,
+        };
+        let Json(response) = process_deposit(State(state.clone()), HeaderMap::new(), Json(request))
+            .await
+            .unwrap();
+
+        assert_eq!(response.token, "USDT");
+        assert_eq!(response.credits_granted, state.pricing.usdc_to_credits(5_000_000));
+        assert_eq!(response.new_balance, 5_000_000);
+        assert_eq!(store.get_deposits("+14155551234").await[0].token, "USDT");
+    }
+
+    #[tokio::test]
+    async fn test_deposit_response_formats_usdc() {
+        let server = wiremock::MockServer::start().await;
+        let (state, _store, _dir) = base_deposit_state(&server, vec![]).await;
+        let usdc = state.config.base.as_ref().unwrap().usdc_contract.clone();
+        mock_base_transfer(&server, &state, &usdc, 2_500_000).await;
+
+        let request = DepositRequest {
+            chain: Chain::Base,
+            tx_hash: "0xabc".to_string(),
+            user_id: "+14155551234".to_string(),
+            amount: 2_500_000,
+            from: None,
+            account: None,
+        };
+        let Json(response) = process_deposit(State(state), HeaderMap::new(), Json(request))
+            .await
+            .unwrap();
+
+        assert_eq!(response.amount_usdc, "$2.500000");
+        assert_eq!(response.credits_granted, 2_500_000);
+        assert_eq!(response.credits_granted_usdc, "$2.500000");
+    }
+
+    /// Deposit 2 USDC sent from 0x1111... as `+14155551234` with
+    /// `require_linked_sender` on, after `linked_by` (if anyone) linked that
+    /// sender.
+    async fn deposit_from_linked_sender(
+        linked_by: Option<&str>,
+    ) -> (Result<Json<DepositResponse>, (StatusCode, Json<ErrorResponse>)>, Arc<CreditStore>, tempfile::TempDir) {
+        let server = wiremock::MockServer::start().await;
+        let (state, store, dir) = base_deposit_state(&server, vec![]).await;
+        let config = PaymentConfig {
+            require_linked_sender: true,
+            admin_token: Some("s3cret".to_string().into()),
+            ..state.config.clone()
+        };
+        let state = Arc::new(AppState::new(store.clone(), config, state.base.clone(), None, None));
+        let usdc = state.config.base.as_ref().unwrap().usdc_contract.clone();
+        mock_base_transfer(&server, &state, &usdc, 2_000_000).await;
+
+        if let Some(user_id) = linked_by {
+            let request = LinkAddressRequest {
+                chain: Chain::Base,
+                user_id: user_id.to_string(),
+                address: "0x1111111111111111111111111111111111111111".to_string(),
+                timestamp: None,
+                signature: None,
+            };
+            let _ = link_address(State(state.clone()), bearer("s3cret"), Json(request)).await.unwrap();
+        }
+
+        let request = DepositRequest {
+            chain: Chain::Base,
+            tx_hash: "0xabc".to_string(),
+            user_id: "+14155551234".to_string(),
+            amount: 2_000_000,
+            from: None,
+            account: None,
+        };
+        let result = process_deposit(State(state), HeaderMap::new(), Json(request)).await;
+        (result, store, dir)
+    }
+
+    #[tokio::test]
+    async fn test_deposit_from_own_linked_sender() {
+        let (result, store, _dir) = deposit_from_linked_sender(Some("+14155551234")).await;
+        let Json(response) = result.unwrap();
+        assert_eq!(response.credits_granted, 2_000_000);
+        assert_eq!(store.get_balance("+14155551234").await.credits_remaining, 2_000_000);
+    }
+
+    #[tokio::test]
+    async fn test_deposit_from_another_users_sender_is_rejected() {
+        let (result, store, _dir) = deposit_from_linked_sender(Some("+14155559999")).await;
+        let (status, Json(body)) = result.unwrap_err();
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        assert_eq!(body.code, "SENDER_MISMATCH");
+        assert_eq!(store.get_balance("+14155551234").await.credits_remaining, 0);
+        assert_eq!(store.get_balance("+14155559999").await.credits_remaining, 0);
+    }
+
+    #[tokio::test]
+    async fn test_deposit_from_unlinked_sender_is_rejected() {
+        let (result, store, _dir) = deposit_from_linked_sender(None).await;
+        let (status, Json(body)) = result.unwrap_err();
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body.code, "SENDER_NOT_LINKED");
+        assert_eq!(store.get_balance("+14155551234").await.credits_remaining, 0);
+    }
+
+    /// A link request for `signer`'s address, signed for `action` by
+    /// `signed_by` (or unsigned).
+    fn signed_link_request(
+        signer: &alloy::signers::local::PrivateKeySigner,
+        user_id: &str,
+        action: AddressAction,
+        signed_by: Option<&alloy::signers::local::PrivateKeySigner>,
+    ) -> LinkAddressRequest {
+        use alloy::signers::SignerSync;
+
+        let address = signer.address().to_string();
+        let timestamp = Utc::now().timestamp();
+        let message = ownership_message(action, Chain::Base, &address, user_id, timestamp);
+        LinkAddressRequest {
+            chain: Chain::Base,
+            user_id: user_id.to_string(),
+            address,
+            timestamp: Some(timestamp),
+            signature: signed_by.map(|key| key.sign_message_sync(message.as_bytes()).unwrap().to_string()),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_link_address_conflict() {
+        let server = wiremock::MockServer::start().await;
+        let (state, _store, _dir) = base_deposit_state(&server, vec![]).await;
+        let signer = alloy::signers::local::PrivateKeySigner::random();
+        let request = |user_id: &str| signed_link_request(&signer, user_id, AddressAction::Link, Some(&signer));
+
+        let Json(linked) = link_address(State(state.clone()), HeaderMap::new(), Json(request("+1111")))
+            .await
+            .unwrap();
+        assert_eq!(linked.address, signer.address().to_string().to_lowercase());
+
+        let (status, Json(body)) = link_address(State(state.clone()), HeaderMap::new(), Json(request("+2222")))
+            .await
+            .unwrap_err();
+        assert_eq!(status, StatusCode::CONFLICT);
+        assert_eq!(body.code, "ADDRESS_LINKED");
+
+        let near = LinkAddressRequest { chain: Chain::Near, ..request("+1111") };
+        let (_, Json(body)) = link_address(State(state), HeaderMap::new(), Json(near)).await.unwrap_err();
+        assert_eq!(body.code, "CHAIN_DISABLED");
+    }
+
+    #[tokio::test]
+    async fn test_link_address_requires_proof_of_ownership() {
+        let server = wiremock::MockServer::start().await;
+        let (state, store, _dir) = base_deposit_state(&server, vec![]).await;
+        let victim = alloy::signers::local::PrivateKeySigner::random();
+        let attacker = alloy::signers::local::PrivateKeySigner::random();
+
+        // Unsigned
+        let request = signed_link_request(&victim, "+6666", AddressAction::Link, None);
+        let (status, Json(body)) = link_address(State(state.clone()), HeaderMap::new(), Json(request))
+            .await
+            .unwrap_err();
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert_eq!(body.code, "SIGNATURE_REQUIRED");
+
+        // Signed by someone else's key
+        let request = signed_link_request(&victim, "+6666", AddressAction::Link, Some(&attacker));
+        let (status, Json(body)) = link_address(State(state.clone()), HeaderMap::new(), Json(request))
+            .await
+            .unwrap_err();
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        assert_eq!(body.code, "SIGNATURE_MISMATCH");
+
+        // Signed long ago
+        let mut request = signed_link_request(&victim, "+6666", AddressAction::Link, Some(&victim));
+        request.timestamp = Some(request.timestamp.unwrap() - 3600);
+        let (status, Json(body)) = link_address(State(state.clone()), HeaderMap::new(), Json(request))
+            .await
+            .unwrap_err();
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert_eq!(body.code, "SIGNATURE_EXPIRED");
+
+        let victim_address = victim.address().to_string();
+        assert_eq!(store.linked_address_owner(Chain::Base, &victim_address).await, None);
+    }
+
+    #[tokio::test]
+    async fn test_unlink_address() {
+        let server = wiremock::MockServer::start().await;
+        let (state, store, _dir) = base_deposit_state(&server, vec![]).await;
+        let signer = alloy::signers::local::PrivateKeySigner::random();
+        let address = signer.address().to_string();
+
+        let request = signed_link_request(&signer, "+1111", AddressAction::Link, Some(&signer));
+        let _ = link_address(State(state.clone()), HeaderMap::new(), Json(request)).await.unwrap();
+
+        // A link signature doesn't authorize an unlink
+        let request = signed_link_request(&signer, "+1111", AddressAction::Link, Some(&signer));
+        let (_, Json(body)) = unlink_address(State(state.clone()), HeaderMap::new(), Json(request))
+            .await
+            .unwrap_err();
+        assert_eq!(body.code, "SIGNATURE_MISMATCH");
+
+        // Nor is another user's address theirs to unlink
+        let request = signed_link_request(&signer, "+2222", AddressAction::Unlink, Some(&signer));
+        let (status, Json(body)) = unlink_address(State(state.clone()), HeaderMap::new(), Json(request))
+            .await
+            .unwrap_err();
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(body.code, "ADDRESS_NOT_LINKED");
+
+        let request = signed_link_request(&signer, "+1111", AddressAction::Unlink, Some(&signer));
+        let Json(unlinked) = unlink_address(State(state), HeaderMap::new(), Json(request)).await.unwrap();
+        assert_eq!(unlinked.address, address.to_lowercase());
+        assert_eq!(store.linked_address_owner(Chain::Base, &address).await, None);
+    }
+
+    #[tokio::test]
+    async fn test_deposit_webhook() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, ResponseTemplate};
+
+        let server = wiremock::MockServer::start().await;
+        let hook = wiremock::MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/deposits"))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&hook)
+            .await;
+
+        let (state, store, _dir) = base_deposit_state(&server, vec![]).await;
+        let config = PaymentConfig {
+            deposit_webhook_url: Some(format!("{}/deposits", hook.uri())),
+            ..state.config.clone()
+        };
+        let state = Arc::new(AppState::new(store, config, state.base.clone(), None, None));
+        let usdc = state.config.base.as_ref().unwrap().usdc_contract.clone();
+        mock_base_transfer(&server, &state, &usdc, 2_000_000).await;
+
+        let request = || DepositRequest {
+            chain: Chain::Base,
+            tx_hash: "0xabc".to_string(),
+            user_id: "+14155551234".to_string(),
+            amount: 2_000_000,
+            from: None,
+            account: None,
+        };
+        let Json(deposit) =
+            process_deposit(State(state.clone()), HeaderMap::new(), Json(request()))
+                .await
+                .unwrap();
+        assert_eq!(deposit.credits_granted, 2_000_000);
+
+        // Delivered in the background
+        let mut received = Vec::new();
+        for _ in 0..100 {
+            received = hook.received_requests().await.unwrap();
+            if !received.is_empty() {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        }
+        assert_eq!(received.len(), 1);
+        let event: DepositEvent = serde_json::from_slice(&received[0].body).unwrap();
+        assert_eq!(
+            event,
+            DepositEvent {
+                user_id: "+14155551234".to_string(),
+                chain: Chain::Base,
+                tx_hash: "0xabc".to_string(),
+                credits_granted: 2_000_000,
+                new_balance: 2_000_000,
+            }
+        );
+
+        // A duplicate isn't credited, so nothing is sent
+        let (_, Json(body)) = process_deposit(State(state), HeaderMap::new(), Json(request()))
+            .await
+            .unwrap_err();
+        assert_eq!(body.code, "DUPLICATE_TX");
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+        assert_eq!(hook.received_requests().await.unwrap().len(), 1);
+    }
+
+    /// A payment API with no chains, an admin token of `s3cret`, and
+    /// `credits` already deposited for `+14155551234`.
+    async fn admin_state(credits: u64) -> (Arc<AppState>, tempfile::TempDir) {
+        let dir = tempfile::TempDir::new().unwrap();
+        let store = CreditStore::new(dstack_client::MockDstackClient::new(), dir.path().join("credits.enc"))
+            .await
+            .unwrap();
+        let deposit = Deposit::new_pending("+14155551234".to_string(), Chain::Base, "0xabc".to_string(), credits, credits);
+        store.add_credits(deposit).await.unwrap();
+        let state = Arc::new(AppState::new(store, config_with_token(Some("s3cret")), None, None, None));
+        (state, dir)
+    }
+
+    #[tokio::test]
+    async fn test_deposit_intent_requires_admin_token() {
+        let (state, _dir) = admin_state(0).await;
+        let request = || DepositIntentRequest {
+            chain: Chain::Base,
+            user_id: "+14155551234".to_string(),
+            amount: 5_000_000,
+        };
+
+        let (status, _) = create_deposit_intent(State(state.clone()), HeaderMap::new(), Json(request()))
+            .await
+            .unwrap_err();
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+        // Past authentication, Base isn't enabled in this config
+        let (status, Json(body)) = create_deposit_intent(State(state), bearer("s3cret"), Json(request()))
+            .await
+            .unwrap_err();
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body.code, "CHAIN_DISABLED");
+    }
+
+    fn refund_request(credits: u64) -> RefundRequest {
+        RefundRequest {
+            user_id: "+14155551234".to_string(),
+            credits,
+            reason: "fraudulent deposit".to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_refund_credits() {
+        let (state, _dir) = admin_state(1_000_000).await;
+
+        let Json(response) = refund_credits(State(state.clone()), bearer("s3cret"), Json(refund_request(400_000)))
+            .await
+            .unwrap();
+        assert_eq!(response.credits_refunded, 400_000);
+        assert_eq!(response.new_balance, 600_000);
+
+        let usage = state.credit_store.get_usage("+14155551234").await;
+        assert_eq!(usage.len(), 1);
+        assert!(usage[0].is_refund());
+        assert_eq!(usage[0].refund_reason.as_deref(), Some("fraudulent deposit"));
+        assert!(state.credit_store.reconcile(false).await.unwrap().is_empty());
+
+        let (status, _) = refund_credits(State(state), bearer("wrong"), Json(refund_request(1)))
+            .await
+            .unwrap_err();
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn test_refund_insufficient_credits() {
+        let (state, _dir) = admin_state(100_000).await;
+
+        let (status, Json(body)) = refund_credits(State(state.clone()), bearer("s3cret"), Json(refund_request(400_000)))
+            .await
+            .unwrap_err();
+        assert_eq!(status, StatusCode::CONFLICT);
+        assert_eq!(body.code, "INSUFFICIENT_CREDITS");
+        assert_eq!(state.credit_store.get_balance("+14155551234").await.credits_remaining, 100_000);
+        assert!(state.credit_store.get_usage("+14155551234").await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_sweep_status_requires_admin_token() {
+        let (state, _dir) = admin_state(0).await;
+
+        let (status, Json(body)) = get_sweep_status(State(state.clone()), HeaderMap::new()).await.unwrap_err();
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert_eq!(body.code, "UNAUTHORIZED");
+
+        // Authorized, but no sweeper is running
+        let (status, Json(body)) = get_sweep_status(State(state), bearer("s3cret")).await.unwrap_err();
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(body.code, "SWEEPER_DISABLED");
+    }
+
+    fn transfer_request(credits: u64) -> TransferRequest {
+        TransferRequest {
+            from: "+14155551234".to_string(),
+            to: "+14155559999".to_string(),
+            credits,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_transfer_credits() {
+        let (state, _dir) = admin_state(1_000_000).await;
+
+        let Json(response) = transfer_credits(State(state.clone()), bearer("s3cret"), Json(transfer_request(300_000)))
+            .await
+            .unwrap();
+        assert_eq!(response.from_balance, 700_000);
+        assert_eq!(response.to_balance, 300_000);
+        let recipient = state.credit_store.get_balance("+14155559999").await;
+        assert_eq!(recipient.credits_remaining, 300_000);
+        assert_eq!(recipient.total_received, 300_000);
+        assert_eq!(recipient.total_deposited, 0);
+
+        let usage = state.credit_store.get_usage("+14155551234").await;
+        assert_eq!(usage.len(), 1);
+        assert_eq!(usage[0].transfer_to.as_deref(), Some("+14155559999"));
+        // The transfer record accounts for both sides
+        assert!(state.credit_store.reconcile(false).await.unwrap().is_empty());
+
+        let (status, _) = transfer_credits(State(state), bearer("wrong"), Json(transfer_request(1)))
+            .await
+            .unwrap_err();
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn test_transfer_overdraw() {
+        let (state, _dir) = admin_state(100_000).await;
+
+        let (status, Json(body)) = transfer_credits(State(state.clone()), bearer("s3cret"), Json(transfer_request(400_000)))
+            .await
+            .unwrap_err();
+        assert_eq!(status, StatusCode::CONFLICT);
+        assert_eq!(body.code, "INSUFFICIENT_CREDITS");
+        assert_eq!(state.credit_store.get_balance("+14155551234").await.credits_remaining, 100_000);
+        assert_eq!(state.credit_store.get_balance("+14155559999").await.credits_remaining, 0);
+        assert!(state.credit_store.get_usage("+14155551234").await.is_empty());
+    }
+
+    #[test]
+    fn test_estimate_message() {
+        let pricing = PricingConfig {
+            model_rates: Some("big-model=1000000:1000000".into()),
+            ..Default::default()
+        };
+
+        // 10k prompt tokens at $0.10/M plus 20k completion tokens at $0.30/M
+        let estimate = estimate_message(
+            &pricing,
+            EstimateParams { prompt_tokens: 10_000, model: Some("test-model".into()) },
+        );
+        assert_eq!(estimate.completion_tokens, 20_000);
+        assert_eq!(estimate.credits, 1_000 + 6_000);
+        assert_eq!(estimate.cost_usdc, 7_000);
+        assert_eq!(estimate.cost_display, "$0.007000");
+        assert_eq!(estimate.model.as_deref(), Some("test-model"));
+
+        // Short prompts pay the per-message minimum
+        let estimate = estimate_message(&pricing, EstimateParams { prompt_tokens: 10, model: None });
+        assert_eq!(estimate.credits, 100);
+
+        // Models with their own rates are priced at them
+        let estimate = estimate_message(
+            &pricing,
+            EstimateParams { prompt_tokens: 10_000, model: Some("big-model".into()) },
+        );
+        assert_eq!(estimate.credits, 30_000);
+    }
+
+    #[test]
+    fn test_deposit_attribution() {
+        let request = DepositRequest {
+            chain: Chain::Base,
+            tx_hash: "0xabc".to_string(),
+            user_id: "+14155551234".to_string(),
+            amount: 1_000_001,
+            from: None,
+            account: None,
+        };
+        let lenient = PaymentConfig::default();
+        let strict = PaymentConfig {
+            require_unique_amount: true,
+            ..Default::default()
+        };
+
+        assert!(check_deposit_attribution(&strict, &request, 1_000_001, Some("+14155551234")).is_ok());
+
+        // Someone else's reserved amount is never credited to the claimant
+        let (status, Json(body)) =
+            check_deposit_attribution(&lenient, &request, 1_000_001, Some("+16505550000")).unwrap_err();
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        assert_eq!(body.code, "INTENT_MISMATCH");
+
+        // Unreserved amounts are only accepted when not required
+        assert!(check_deposit_attribution(&lenient, &request, 1_000_000, None).is_ok());
+        let (_, Json(body)) = check_deposit_attribution(&strict, &request, 1_000_000, None).unwrap_err();
+        assert_eq!(body.code, "NO_DEPOSIT_INTENT");
+    }
+
+    #[test]
+    fn test_admin_disabled_without_token() {
+        let config = config_with_token(None);
+        assert!(!is_admin(&config, &bearer("")));
+        assert!(!is_admin(&config, &bearer("anything")));
+    }
+
+    #[test]
+    fn test_deposit_qr_payload() {
+        // Memo-less chains and NEAR without a user encode the bare address
+        assert_eq!(deposit_qr_payload(Chain::Base, "0xabc", Some("+14155551234")), "0xabc");
+        assert_eq!(deposit_qr_payload(Chain::Near, "bot.near", None), "bot.near");
+        assert_eq!(deposit_qr_payload(Chain::Near, "bot.near", Some("")), "bot.near");
+
+        assert_eq!(
+            deposit_qr_payload(Chain::Near, "bot.near", Some("+14155551234")),
+            "bot.near\nMemo: +14155551234"
+        );
+    }
+
+    #[test]
+    fn test_parse_chain() {
+        assert_eq!(parse_chain("Solana").unwrap(), Chain::Solana);
+        let (status, Json(body)) = parse_chain("dogecoin").unwrap_err();
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body.code, "UNKNOWN_CHAIN");
+    }
+}
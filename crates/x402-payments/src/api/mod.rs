@@ -1,6 +1,8 @@
 //! HTTP API for payment operations.
 
 mod handlers;
+mod ownership;
+mod qr;
 mod types;
 
 pub use handlers::{create_router, AppState};
@@ -0,0 +1,195 @@
+//! Proof that a caller controls a sender address.
+//!
+//! Linking or unlinking an address needs a signature over
+//! [`ownership_message`] from the address itself: an EIP-191 personal
+//! message signature on Base, an ed25519 signature by the account key on
+//! Solana. The message names the user and a timestamp, so a signature can't
+//! be replayed for another user or, after [`MAX_SIGNATURE_AGE`], at all.
+
+use crate::types::Chain;
+use alloy::primitives::{Address, Signature as EvmSignature};
+use chrono::{DateTime, Duration, Utc};
+use solana_sdk::{pubkey::Pubkey, signature::Signature as SolanaSignature};
+use std::str::FromStr;
+
+/// How far a signed timestamp may be from now, in either direction.
+pub(crate) const MAX_SIGNATURE_AGE: Duration = Duration::minutes(10);
+
+/// What a signature authorizes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum AddressAction {
+    Link,
+    Unlink,
+}
+
+/// Why an ownership proof was rejected.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum ProofError {
+    /// The signed timestamp is too old or too far in the future.
+    Expired,
+    /// The address or signature doesn't parse.
+    Malformed(String),
+    /// The signature wasn't made by the address.
+    Mismatch,
+}
+
+/// The text the address owner signs to link or unlink it.
+pub(crate) fn ownership_message(
+    action: AddressAction,
+    chain: Chain,
+    address: &str,
+    user_id: &str,
+    timestamp: i64,
+) -> String {
+    let action = match action {
+        AddressAction::Link => "Link",
+        AddressAction::Unlink => "Unlink",
+    };
+    format!(
+        "signal-bot-tee: {} {} address {} for user {} at {}",
+        action,
+        chain,
+        address.trim(),
+        user_id,
+        timestamp
+    )
+}
+
+/// Check that `signature` over `message`, signed at `timestamp` (Unix
+/// seconds), was made by `address` on `chain`.
+pub(crate) fn verify_ownership(
+    chain: Chain,
+    address: &str,
+    message: &str,
+    signature: &str,
+    timestamp: i64,
+    now: DateTime<Utc>,
+) -> Result<(), ProofError> {
+    let signed_at = DateTime::from_timestamp(timestamp, 0).ok_or(ProofError::Expired)?;
+    if (now - signed_at).abs() > MAX_SIGNATURE_AGE {
+        return Err(ProofError::Expired);
+    }
+
+    match chain {
+        Chain::Base => {
+            let address = Address::from_str(address.trim())
+                .map_err(|e| ProofError::Malformed(format!("Invalid address: {}", e)))?;
+            let signature = EvmSignature::from_str(signature.trim())
+                .map_err(|e| ProofError::Malformed(format!("Invalid signature: {}", e)))?;
+            match signature.recover_address_from_msg(message) {
+                Ok(signer) if signer == address => Ok(()),
+                _ => Err(ProofError::Mismatch),
+            }
+        }
+        Chain::Solana => {
+            let pubkey = Pubkey::from_str(address.trim())
+                .map_err(|e| ProofError::Malformed(format!("Invalid address: {}", e)))?;
+            let signature = SolanaSignature::from_str(signature.trim())
+                .map_err(|e| ProofError::Malformed(format!("Invalid signature: {}", e)))?;
+            if signature.verify(pubkey.as_ref(), message.as_bytes()) {
+                Ok(())
+            } else {
+                Err(ProofError::Mismatch)
+            }
+        }
+        Chain::Near => Err(ProofError::Malformed(
+            "NEAR deposits are attributed by memo".to_string(),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy::signers::{local::PrivateKeySigner, SignerSync};
+    use solana_sdk::signature::{Keypair, Signer};
+
+    const TIMESTAMP: i64 = 1_700_000_000;
+
+    fn at(seconds: i64) -> DateTime<Utc> {
+        DateTime::from_timestamp(seconds, 0).unwrap()
+    }
+
+    #[test]
+    fn test_base_signature() {
+        let signer = PrivateKeySigner::random();
+        let address = signer.address().to_string();
+        let message = ownership_message(AddressAction::Link, Chain::Base, &address, "+1111", TIMESTAMP);
+        let signature = signer.sign_message_sync(message.as_bytes()).unwrap().to_string();
+
+        assert_eq!(
+            verify_ownership(Chain::Base, &address, &message, &signature, TIMESTAMP, at(TIMESTAMP)),
+            Ok(())
+        );
+        // Addresses compare regardless of checksum case
+        assert_eq!(
+            verify_ownership(Chain::Base, &address.to_lowercase(), &message, &signature, TIMESTAMP, at(TIMESTAMP)),
+            Ok(())
+        );
+
+        // Signed for another user
+        let other = ownership_message(AddressAction::Link, Chain::Base, &address, "+2222", TIMESTAMP);
+        assert_eq!(
+            verify_ownership(Chain::Base, &address, &other, &signature, TIMESTAMP, at(TIMESTAMP)),
+            Err(ProofError::Mismatch)
+        );
+
+        // Signed by another key
+        let impostor = PrivateKeySigner::random().address().to_string();
+        assert_eq!(
+            verify_ownership(Chain::Base, &impostor, &message, &signature, TIMESTAMP, at(TIMESTAMP)),
+            Err(ProofError::Mismatch)
+        );
+
+        assert!(matches!(
+            verify_ownership(Chain::Base, &address, &message, "0x1234", TIMESTAMP, at(TIMESTAMP)),
+            Err(ProofError::Malformed(_))
+        ));
+    }
+
+    #[test]
+    fn test_solana_signature() {
+        let keypair = Keypair::new();
+        let address = keypair.pubkey().to_string();
+        let message = ownership_message(AddressAction::Unlink, Chain::Solana, &address, "+1111", TIMESTAMP);
+        let signature = keypair.sign_message(message.as_bytes()).to_string();
+
+        assert_eq!(
+            verify_ownership(Chain::Solana, &address, &message, &signature, TIMESTAMP, at(TIMESTAMP)),
+            Ok(())
+        );
+
+        // A link signature doesn't authorize an unlink
+        let link = ownership_message(AddressAction::Link, Chain::Solana, &address, "+1111", TIMESTAMP);
+        assert_eq!(
+            verify_ownership(Chain::Solana, &address, &link, &signature, TIMESTAMP, at(TIMESTAMP)),
+            Err(ProofError::Mismatch)
+        );
+
+        let impostor = Keypair::new().pubkey().to_string();
+        assert_eq!(
+            verify_ownership(Chain::Solana, &impostor, &message, &signature, TIMESTAMP, at(TIMESTAMP)),
+            Err(ProofError::Mismatch)
+        );
+    }
+
+    #[test]
+    fn test_stale_signature_rejected() {
+        let keypair = Keypair::new();
+        let address = keypair.pubkey().to_string();
+        let message = ownership_message(AddressAction::Link, Chain::Solana, &address, "+1111", TIMESTAMP);
+        let signature = keypair.sign_message(message.as_bytes()).to_string();
+        let max_age = MAX_SIGNATURE_AGE.num_seconds();
+
+        for now in [TIMESTAMP + max_age + 1, TIMESTAMP - max_age - 1] {
+            assert_eq!(
+                verify_ownership(Chain::Solana, &address, &message, &signature, TIMESTAMP, at(now)),
+                Err(ProofError::Expired)
+            );
+        }
+        assert_eq!(
+            verify_ownership(Chain::Solana, &address, &message, &signature, TIMESTAMP, at(TIMESTAMP + max_age)),
+            Ok(())
+        );
+    }
+}
@@ -0,0 +1,124 @@
+//! QR code rendering for deposit addresses.
+//!
+//! Symbols come from the `qrcode` crate (error correction level M); this
+//! module only rasterizes them into an 8-bit grayscale PNG.
+
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+use qrcode::{Color, EcLevel, QrCode};
+use std::io::Write;
+
+/// Pixels per QR module in rendered PNGs.
+const MODULE_SCALE: usize = 8;
+
+/// Light modules around the symbol, as the QR spec requires.
+const QUIET_ZONE: usize = 4;
+
+/// Render `text` as a PNG QR code, or `None` if it's too long to encode.
+pub(crate) fn render_png(text: &str) -> Option<Vec<u8>> {
+    let qr = QrCode::with_error_correction_level(text, EcLevel::M).ok()?;
+    Some(to_png(qr.width(), &qr.to_colors()))
+}
+
+/// Render a `size` x `size` module grid as an 8-bit grayscale PNG with a
+/// quiet zone.
+fn to_png(size: usize, modules: &[Color]) -> Vec<u8> {
+    let width = (size + 2 * QUIET_ZONE) * MODULE_SCALE;
+
+    let mut raw = Vec::with_capacity((width + 1) * width);
+    for py in 0..width {
+        raw.push(0); // filter: none
+        for px in 0..width {
+            let (mx, my) = (px / MODULE_SCALE, py / MODULE_SCALE);
+            let dark = (QUIET_ZONE..QUIET_ZONE + size).contains(&mx)
+                && (QUIET_ZONE..QUIET_ZONE + size).contains(&my)
+                && modules[(my - QUIET_ZONE) * size + mx - QUIET_ZONE] == Color::Dark;
+            raw.push(if dark { 0 } else { 255 });
+        }
+    }
+
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+    // Writing to a Vec can't fail
+    encoder.write_all(&raw).expect("in-memory write");
+    let compressed = encoder.finish().expect("in-memory write");
+
+    let mut ihdr = Vec::with_capacity(13);
+    ihdr.extend_from_slice(&(width as u32).to_be_bytes());
+    ihdr.extend_from_slice(&(width as u32).to_be_bytes());
+    // 8-bit grayscale, default compression/filter, no interlace
+    ihdr.extend_from_slice(&[8, 0, 0, 0, 0]);
+
+    let mut png = b"\x89PNG\r\n\x1a\n".to_vec();
+    write_chunk(&mut png, b"IHDR", &ihdr);
+    write_chunk(&mut png, b"IDAT", &compressed);
+    write_chunk(&mut png, b"IEND", &[]);
+    png
+}
+
+fn write_chunk(png: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    png.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    png.extend_from_slice(kind);
+    png.extend_from_slice(data);
+    let mut hasher = crc32fast::Hasher::new();
+    hasher.update(kind);
+    hasher.update(data);
+    png.extend_from_slice(&hasher.finalize().to_be_bytes());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::read::ZlibDecoder;
+    use std::io::Read;
+
+    /// Decode a PNG written by `to_png` back into its grayscale rows.
+    fn decode_rows(png: &[u8]) -> Vec<Vec<u8>> {
+        let width = u32::from_be_bytes(png[16..20].try_into().unwrap()) as usize;
+        let idat_len = u32::from_be_bytes(png[33..37].try_into().unwrap()) as usize;
+        assert_eq!(&png[37..41], b"IDAT");
+
+        let mut raw = Vec::new();
+        ZlibDecoder::new(&png[41..41 + idat_len]).read_to_end(&mut raw).unwrap();
+        raw.chunks(width + 1)
+            .map(|row| {
+                assert_eq!(row[0], 0);
+                row[1..].to_vec()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_render_png() {
+        let png = render_png("hello").unwrap();
+        assert!(png.starts_with(b"\x89PNG\r\n\x1a\n"));
+        assert_eq!(&png[12..16], b"IHDR");
+        let width = u32::from_be_bytes(png[16..20].try_into().unwrap()) as usize;
+        assert_eq!(width, (21 + 2 * QUIET_ZONE) * MODULE_SCALE);
+        assert!(png.ends_with(&[0xAE, 0x42, 0x60, 0x82]));
+
+        assert!(render_png(&"x".repeat(3000)).is_none());
+    }
+
+    #[test]
+    fn test_png_round_trips_modules() {
+        let text = "0x3f5CE5FBFe3E9af3971dD833D26bA9b5C936f0bE";
+        let qr = QrCode::with_error_correction_level(text, EcLevel::M).unwrap();
+        let size = qr.width();
+        let rows = decode_rows(&render_png(text).unwrap());
+
+        // Sample each module's center and compare with the symbol
+        let sampled: Vec<Color> = (0..size * size)
+            .map(|i| {
+                let (x, y) = (i % size, i / size);
+                let pixel = rows[(y + QUIET_ZONE) * MODULE_SCALE + MODULE_SCALE / 2]
+                    [(x + QUIET_ZONE) * MODULE_SCALE + MODULE_SCALE / 2];
+                if pixel == 0 { Color::Dark } else { Color::Light }
+            })
+            .collect();
+        assert_eq!(sampled, qr.to_colors());
+
+        // Quiet zone is blank
+        assert!(rows[0].iter().all(|&p| p == 255));
+        assert!(rows.iter().all(|row| row[0] == 255));
+    }
+}
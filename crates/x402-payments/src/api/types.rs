@@ -1,6 +1,8 @@
 //! API request/response types.
 
-use crate::types::{Chain, DepositStatus};
+use crate::credits::KeySource;
+use crate::types::{BalanceDiscrepancy, Chain, Deposit, DepositStatus, UsageRecord};
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
 /// Balance response.
@@ -12,6 +14,8 @@ pub struct BalanceResponse {
     pub credits_remaining_usdc: String,
     pub total_deposited_usdc: String,
     pub total_consumed_usdc: String,
+    /// Credits other users transferred in.
+    pub total_received_usdc: String,
 }
 
 /// Deposit request.
@@ -25,18 +29,52 @@ pub struct DepositRequest {
     pub user_id: String,
     /// Amount claimed in micro-USDC.
     pub amount: u64,
+    /// Sender address or account (required for NEAR, optional elsewhere).
+    #[serde(default)]
+    pub from: Option<String>,
+    /// Bot account whose deposit wallet was paid (the default wallets when unset).
+    #[serde(default)]
+    pub account: Option<String>,
 }
 
 /// Deposit response.
 #[derive(Debug, Serialize, Deserialize)]
 pub struct DepositResponse {
     pub deposit_id: String,
+    /// Verified on-chain amount, as a human-readable USDC amount.
+    pub amount_usdc: String,
     pub credits_granted: u64,
+    /// Human-readable USDC value of the credits granted.
+    pub credits_granted_usdc: String,
     pub new_balance: u64,
     pub tx_hash: String,
+    /// Token detected in the transfer (e.g. `USDC`, `USDT`).
+    pub token: String,
     pub status: DepositStatus,
 }
 
+pub use api_common::{PageParams, DEFAULT_PAGE_SIZE, MAX_PAGE_SIZE};
+
+/// One page of a user's deposits.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DepositsResponse {
+    pub deposits: Vec<Deposit>,
+    /// Total number of deposits (since `since`, if given) across all pages.
+    pub total: usize,
+    pub offset: usize,
+    pub limit: usize,
+}
+
+/// One page of a user's usage records.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UsageResponse {
+    pub usage: Vec<UsageRecord>,
+    /// Total number of usage records (since `since`, if given) across all pages.
+    pub total: usize,
+    pub offset: usize,
+    pub limit: usize,
+}
+
 /// Deposit address response.
 #[derive(Debug, Serialize, Deserialize)]
 pub struct DepositAddressResponse {
@@ -54,9 +92,60 @@ pub struct PricingResponse {
     pub prompt_cost_per_million_tokens: String,
     pub completion_cost_per_million_tokens: String,
     pub minimum_per_message: String,
+    /// Smallest accepted deposit.
+    pub minimum_deposit: String,
+    /// Largest accepted deposit, if capped.
+    pub maximum_deposit: Option<String>,
     pub supported_chains: Vec<ChainInfo>,
 }
 
+/// Deposit quote request.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct QuoteRequest {
+    pub chain: Chain,
+    /// Amount the user plans to deposit, in micro-USDC.
+    pub amount: u64,
+}
+
+/// Credits a deposit would grant.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct QuoteResponse {
+    pub chain: Chain,
+    /// Quoted amount in micro-USDC.
+    pub amount_usdc: u64,
+    /// Human-readable USDC amount.
+    pub amount_display: String,
+    /// Credits the deposit would grant.
+    pub credits: u64,
+    /// Rough number of typical messages those credits cover.
+    pub approx_messages: u64,
+}
+
+/// Cost estimate query (`GET /v1/estimate?prompt_tokens=&model=`).
+#[derive(Debug, Deserialize)]
+pub struct EstimateParams {
+    /// Tokens in the hypothetical prompt.
+    pub prompt_tokens: u32,
+    /// Model the message would go to, priced at its own rates if it has
+    /// any (`pricing.model_rates`). Echoed back.
+    #[serde(default)]
+    pub model: Option<String>,
+}
+
+/// Cost estimate for a hypothetical message.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EstimateResponse {
+    pub model: Option<String>,
+    pub prompt_tokens: u32,
+    /// Assumed response length.
+    pub completion_tokens: u32,
+    pub credits: u64,
+    /// Cost in micro-USDC.
+    pub cost_usdc: u64,
+    /// Human-readable USDC cost.
+    pub cost_display: String,
+}
+
 /// Chain information.
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ChainInfo {
@@ -82,6 +171,152 @@ pub struct ChainHealth {
     pub healthy: bool,
 }
 
+/// Request to reserve a distinct deposit amount.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DepositIntentRequest {
+    pub chain: Chain,
+    /// User's phone number (E.164 format).
+    pub user_id: String,
+    /// Amount the user wants to deposit, in micro-USDC.
+    pub amount: u64,
+}
+
+/// A reserved deposit amount.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DepositIntentResponse {
+    pub chain: Chain,
+    /// Exact amount to send in micro-USDC.
+    pub amount_usdc: u64,
+    /// Human-readable USDC amount.
+    pub amount_display: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// Request to link a sender address to a user, or to unlink it.
+///
+/// Unless the caller presents the admin token, `signature` must be the
+/// address's signature over
+/// `signal-bot-tee: <Link|Unlink> <chain> address <address> for user <user_id> at <timestamp>`:
+/// EIP-191 (`personal_sign`, hex) on Base, ed25519 over the UTF-8 bytes
+/// (base58) on Solana.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LinkAddressRequest {
+    pub chain: Chain,
+    /// User's phone number (E.164 format).
+    pub user_id: String,
+    /// Address the user will send deposits from.
+    pub address: String,
+    /// Unix seconds the signature was made at.
+    #[serde(default)]
+    pub timestamp: Option<i64>,
+    /// The address's signature over the link or unlink message.
+    #[serde(default)]
+    pub signature: Option<String>,
+}
+
+/// A linked (or just unlinked) sender address.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LinkAddressResponse {
+    pub chain: Chain,
+    /// Normalized address deposits must come from.
+    pub address: String,
+    pub linked_at: DateTime<Utc>,
+}
+
+/// Deposit address QR query parameters.
+#[derive(Debug, Default, Deserialize)]
+pub struct DepositQrParams {
+    /// User whose memo a NEAR QR code should show.
+    #[serde(default)]
+    pub user_id: Option<String>,
+    /// Bot account whose deposit wallet to show (the default wallets when unset).
+    #[serde(default)]
+    pub account: Option<String>,
+}
+
+/// Deposit address query parameters.
+#[derive(Debug, Default, Deserialize)]
+pub struct DepositAddressParams {
+    /// Bot account whose deposit wallet to show (the default wallets when unset).
+    #[serde(default)]
+    pub account: Option<String>,
+}
+
+/// Balance reconciliation query parameters (`?apply=true`).
+#[derive(Debug, Default, Deserialize)]
+pub struct ReconcileParams {
+    /// Write the recomputed balances instead of only reporting them.
+    #[serde(default)]
+    pub apply: bool,
+}
+
+/// Balance reconciliation result.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ReconcileResponse {
+    /// Whether the corrections were written.
+    pub applied: bool,
+    pub discrepancies: Vec<BalanceDiscrepancy>,
+}
+
+/// Refund request (`POST /v1/admin/refund`).
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RefundRequest {
+    /// User whose credits are clawed back.
+    pub user_id: String,
+    /// Credits to remove.
+    pub credits: u64,
+    /// Why, for the audit log.
+    pub reason: String,
+}
+
+/// Refund result.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RefundResponse {
+    pub user_id: String,
+    pub credits_refunded: u64,
+    pub new_balance: u64,
+}
+
+/// Credit transfer request (`POST /v1/admin/transfer`).
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TransferRequest {
+    /// User the credits are taken from.
+    pub from: String,
+    /// User the credits are given to.
+    pub to: String,
+    /// Credits to move.
+    pub credits: u64,
+}
+
+/// Credit transfer result.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TransferResponse {
+    pub from: String,
+    pub to: String,
+    pub credits_transferred: u64,
+    /// Sender's balance after the transfer.
+    pub from_balance: u64,
+    /// Recipient's balance after the transfer.
+    pub to_balance: u64,
+}
+
+/// Credit store key source (`GET /v1/admin/key-source`).
+#[derive(Debug, Serialize, Deserialize)]
+pub struct KeySourceResponse {
+    /// `None` until the store has needed its key.
+    pub source: Option<KeySource>,
+    /// Whether the key is the weaker AppInfo fallback.
+    pub fallback: bool,
+}
+
+/// Result of `POST /v1/admin/reseal`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ResealResponse {
+    /// Key source the data is now encrypted under.
+    pub source: KeySource,
+    pub previous_source: Option<KeySource>,
+}
+
 /// Error response.
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ErrorResponse {
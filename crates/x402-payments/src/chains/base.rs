@@ -1,9 +1,10 @@
 //! Base (EVM) chain facilitator using Alloy.
 //!
-//! Verifies USDC transfers on Base L2 and manages deposit wallet.
+//! Verifies USDC (and other configured stablecoin) transfers on Base L2 and
+//! manages deposit wallet.
 
 use super::{ChainFacilitator, PaymentPayload, PaymentVerification, TxResult};
-use crate::config::BaseChainConfig;
+use crate::config::{BaseChainConfig, TokenConfig};
 use crate::error::PaymentError;
 use crate::types::{Chain, SettlementResult, TxStatus};
 use alloy::network::EthereumWallet;
@@ -12,11 +13,12 @@ use alloy::providers::ProviderBuilder;
 use alloy::signers::local::PrivateKeySigner;
 use alloy::sol;
 use async_trait::async_trait;
+use dstack_client::DstackApi;
 use serde::{Deserialize, Serialize};
 use tracing::{debug, info, warn};
 
 /// ERC20 Transfer event signature: keccak256("Transfer(address,address,uint256)")
-const TRANSFER_EVENT_SIGNATURE: &str =
+pub(crate) const TRANSFER_EVENT_SIGNATURE: &str =
     "0xddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef";
 
 // Define ERC20 interface using alloy's sol! macro
@@ -83,9 +85,19 @@ impl BaseFacilitator {
     /// Create a new Base facilitator.
     pub async fn new(
         config: BaseChainConfig,
-        dstack: &dstack_client::DstackClient,
+        dstack: &dyn DstackApi,
     ) -> Result<Self, PaymentError> {
-        let (signer, wallet_address) = Self::derive_wallet(dstack).await?;
+        Self::new_with_subject(config, dstack, None).await
+    }
+
+    /// Create a facilitator whose deposit wallet is derived for `subject`
+    /// (e.g. the bot's number), so each subject gets its own wallet.
+    pub async fn new_with_subject(
+        config: BaseChainConfig,
+        dstack: &dyn DstackApi,
+        subject: Option<&str>,
+    ) -> Result<Self, PaymentError> {
+        let (signer, wallet_address) = Self::derive_wallet(dstack, subject).await?;
 
         info!(
             "Initializing Base facilitator: rpc={}, usdc={}, wallet={}",
@@ -107,13 +119,15 @@ impl BaseFacilitator {
 
     /// Derive wallet from TEE key.
     ///
-    /// Derives a secp256k1 private key from TEE-derived entropy.
+    /// Derives a secp256k1 private key from TEE-derived entropy, separate
+    /// per `subject` if one is given.
     async fn derive_wallet(
-        dstack: &dstack_client::DstackClient,
+        dstack: &dyn DstackApi,
+        subject: Option<&str>,
     ) -> Result<(PrivateKeySigner, Address), PaymentError> {
         // Derive 32-byte key from TEE
         let key_bytes = dstack
-            .derive_key("x402-payments/base-deposit-wallet", None)
+            .derive_key("x402-payments/base-deposit-wallet", subject)
             .await
             .map_err(|e| PaymentError::Internal(format!("Failed to derive Base key: {}", e)))?;
 
@@ -187,8 +201,8 @@ impl BaseFacilitator {
         Ok(result)
     }
 
-    /// Call ERC20 balanceOf.
-    async fn get_erc20_balance(&self, token: &str, address: &str) -> Result<u64, PaymentError> {
+    /// Call ERC20 balanceOf, returning the raw amount.
+    async fn get_erc20_balance(&self, token: &str, address: &str) -> Result<u128, PaymentError> {
         // balanceOf(address) function selector = 0x70a08231
         // Pad address to 32 bytes
         let padded_address = format!("{:0>64}", address.trim_start_matches("0x"));
@@ -203,11 +217,12 @@ impl BaseFacilitator {
         ]);
 
         let result: String = self.rpc_call("eth_call", params).await?;
-        parse_hex_u64(&result)
+        parse_hex_u128(&result)
     }
 
-    /// Verify a USDC transfer transaction.
-    async fn verify_usdc_transfer(
+    /// Verify a transfer of any accepted token, converting the amount to
+    /// micro-units with the token's decimals.
+    async fn verify_token_transfer(
         &self,
         tx_hash: &str,
         expected_from: Option<&str>,
@@ -224,17 +239,21 @@ impl BaseFacilitator {
             return Err(PaymentError::TxFailed(tx_hash.to_string()));
         }
 
-        let usdc_address = self.config.usdc_contract.to_lowercase();
+        let tokens = self.config.accepted_tokens();
         let deposit_address = format!("{:?}", self.wallet_address).to_lowercase();
 
         let mut verified_amount: u64 = 0;
         let mut verified_from: Option<String> = None;
+        let mut verified_token: Option<&str> = None;
 
-        // Look for USDC Transfer event to our deposit address
+        // Look for an accepted token's Transfer event to our deposit address
         for log in &receipt.logs {
-            if log.address.to_lowercase() != usdc_address {
+            let Some(token) = tokens
+                .iter()
+                .find(|token| token.contract.eq_ignore_ascii_case(&log.address))
+            else {
                 continue;
-            }
+            };
 
             if log.topics.len() < 3 {
                 continue;
@@ -255,23 +274,27 @@ impl BaseFacilitator {
                 let from_topic = &log.topics[1];
                 verified_from = Some(format!("0x{}", &from_topic[from_topic.len() - 40..]));
 
-                // data = amount (U256 as hex)
-                verified_amount = parse_hex_u64(&log.data)?;
+                // data = amount (U256 as hex), in the token's own decimals
+                let raw_amount = parse_hex_u128(&log.data)?;
+                verified_amount = token.to_micro_units(raw_amount).ok_or_else(|| {
+                    PaymentError::InvalidPayload(format!("{} amount {} is too large", token.symbol, raw_amount))
+                })?;
+                verified_token = Some(&token.symbol);
 
                 debug!(
-                    "Found USDC transfer: from={:?}, to={}, amount={}",
-                    verified_from, to_address, verified_amount
+                    "Found {} transfer: from={:?}, to={}, amount={}",
+                    token.symbol, verified_from, to_address, verified_amount
                 );
                 break;
             }
         }
 
-        if verified_amount == 0 {
+        let Some(token) = verified_token.filter(|_| verified_amount > 0) else {
             return Err(PaymentError::NoTransferFound(format!(
-                "No USDC transfer to {} found in tx {}",
+                "No accepted token transfer to {} found in tx {}",
                 self.wallet_address, tx_hash
             )));
-        }
+        };
 
         // Verify sender if expected
         if let Some(expected) = expected_from {
@@ -309,6 +332,7 @@ impl BaseFacilitator {
         Ok(PaymentVerification {
             tx_hash: tx_hash.to_string(),
             amount_usdc: verified_amount,
+            token: token.to_string(),
             from: verified_from,
             to: format!("{:?}", self.wallet_address),
             confirmations,
@@ -319,18 +343,22 @@ impl BaseFacilitator {
 
 /// Parse a hex string (0x prefixed or not) to u64.
 fn parse_hex_u64(hex_str: &str) -> Result<u64, PaymentError> {
+    // For large hex values, parse as u128 first then try to fit in u64
+    parse_hex_u128(hex_str).and_then(|v| {
+        v.try_into()
+            .map_err(|_| PaymentError::Internal("Value overflow".to_string()))
+    })
+}
+
+/// Parse a hex string (0x prefixed or not) to u128.
+fn parse_hex_u128(hex_str: &str) -> Result<u128, PaymentError> {
     let clean = hex_str.trim_start_matches("0x");
     if clean.is_empty() || clean == "0" {
         return Ok(0);
     }
 
-    // For large hex values, parse as u128 first then try to fit in u64
     u128::from_str_radix(clean, 16)
         .map_err(|e| PaymentError::Internal(format!("Invalid hex: {}", e)))
-        .and_then(|v| {
-            v.try_into()
-                .map_err(|_| PaymentError::Internal("Value overflow".to_string()))
-        })
 }
 
 #[async_trait]
@@ -347,7 +375,7 @@ impl ChainFacilitator for BaseFacilitator {
         &self,
         payload: &PaymentPayload,
     ) -> Result<PaymentVerification, PaymentError> {
-        self.verify_usdc_transfer(&payload.tx_hash, payload.from.as_deref(), payload.amount)
+        self.verify_token_transfer(&payload.tx_hash, payload.from.as_deref(), payload.amount)
             .await
     }
 
@@ -361,24 +389,49 @@ impl ChainFacilitator for BaseFacilitator {
         ))
     }
 
-    async fn get_deposit_wallet_balance(&self) -> Result<u64, PaymentError> {
+    fn accepted_tokens(&self) -> Vec<TokenConfig> {
+        self.config.accepted_tokens()
+    }
+
+    async fn get_deposit_wallet_balance(&self, token: &TokenConfig) -> Result<u64, PaymentError> {
+        let wallet_addr = format!("{:?}", self.wallet_address);
+        let raw = self.get_erc20_balance(&token.contract, &wallet_addr).await?;
+        let balance = token
+            .to_micro_units(raw)
+            .ok_or_else(|| PaymentError::Internal(format!("{} balance overflow", token.symbol)))?;
+
+        debug!("Base deposit wallet balance: {} {} (micro)", balance, token.symbol);
+        Ok(balance)
+    }
+
+    async fn get_native_gas_balance(&self) -> Result<u128, PaymentError> {
         let wallet_addr = format!("{:?}", self.wallet_address);
-        let balance = self
-            .get_erc20_balance(&self.config.usdc_contract, &wallet_addr)
+        let result: String = self
+            .rpc_call("eth_getBalance", [wallet_addr.as_str(), "latest"])
             .await?;
+        let balance = parse_hex_u128(&result)?;
 
-        debug!("Base deposit wallet balance: {} USDC (micro)", balance);
+        debug!("Base deposit wallet gas balance: {} wei", balance);
         Ok(balance)
     }
 
+    fn min_gas_balance(&self) -> u128 {
+        self.config.min_gas_balance
+    }
+
+    fn sweep_reserve(&self) -> Option<u64> {
+        self.config.sweep_reserve_usdc
+    }
+
     async fn transfer_to(
         &self,
         destination: &str,
+        token: &TokenConfig,
         amount: u64,
     ) -> Result<TxResult, PaymentError> {
         info!(
-            "Transferring {} micro-USDC from {} to {} on Base",
-            amount, self.wallet_address, destination
+            "Transferring {} micro-{} from {} to {} on Base",
+            amount, token.symbol, self.wallet_address, destination
         );
 
         // Parse destination address
@@ -386,15 +439,16 @@ impl ChainFacilitator for BaseFacilitator {
             .parse()
             .map_err(|e| PaymentError::Internal(format!("Invalid destination address: {}", e)))?;
 
-        // Parse USDC contract address
-        let usdc_address: Address = self
-            .config
-            .usdc_contract
-            .parse()
-            .map_err(|e| PaymentError::Internal(format!("Invalid USDC contract address: {}", e)))?;
+        // Parse token contract address
+        let token_address: Address = token.contract.parse().map_err(|e| {
+            PaymentError::Internal(format!("Invalid {} contract address: {}", token.symbol, e))
+        })?;
 
-        // Convert amount to U256
-        let amount_u256 = U256::from(amount);
+        // Convert amount to the token's raw units
+        let raw_amount = token
+            .from_micro_units(amount)
+            .ok_or_else(|| PaymentError::Internal(format!("{} amount overflow", token.symbol)))?;
+        let amount_u256 = U256::from(raw_amount);
 
         // Create provider with wallet
         let wallet = EthereumWallet::from(self.signer.clone());
@@ -410,7 +464,7 @@ impl ChainFacilitator for BaseFacilitator {
             .connect_http(rpc_url);
 
         // Create contract instance
-        let contract = IERC20::new(usdc_address, provider);
+        let contract = IERC20::new(token_address, provider);
 
         // Call transfer function and send transaction
         let receipt = contract
@@ -486,6 +540,10 @@ impl ChainFacilitator for BaseFacilitator {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::config::TokenConfig;
+    use dstack_client::MockDstackClient;
+    use wiremock::matchers::{body_partial_json, method};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
 
     #[test]
     fn test_parse_hex_u64() {
@@ -503,10 +561,206 @@ mod tests {
         assert_eq!(parse_hex_u64("0x3b9aca00").unwrap(), 1_000_000_000);
     }
 
+    const USDC_CONTRACT: &str = "0x833589fcd6edb6e08f4c7c32d4f71b54bda02913";
+    const USDT_CONTRACT: &str = "0xfde4c96c8593536e31f229ea8f37b2ada2699bb2";
+    const SENDER: &str = "0x1111111111111111111111111111111111111111";
+
+    fn test_config(rpc_url: String) -> BaseChainConfig {
+        BaseChainConfig {
+            enabled: true,
+            rpc_url,
+            usdc_contract: USDC_CONTRACT.to_string(),
+            operator_address: None,
+            sweep_reserve_usdc: None,
+            min_gas_balance: 20_000_000_000_000,
+            tokens: vec![],
+        }
+    }
+
+    fn pad_topic(address: &str) -> String {
+        format!("0x{:0>64}", address.trim_start_matches("0x").to_lowercase())
+    }
+
+    /// Mount receipt and block number responses for a single USDC transfer.
+    async fn mock_transfer(server: &MockServer, to: &str, amount: u64) {
+        mock_token_transfer(server, USDC_CONTRACT, to, u128::from(amount)).await;
+    }
+
+    /// Mount receipt and block number responses for a single transfer of
+    /// the ERC-20 at `contract`.
+    async fn mock_token_transfer(server: &MockServer, contract: &str, to: &str, amount: u128) {
+        let receipt = serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "result": {
+                "status": "0x1",
+                "blockNumber": "0xa",
+                "logs": [{
+                    "address": contract,
+                    "topics": [TRANSFER_EVENT_SIGNATURE, pad_topic(SENDER), pad_topic(to)],
+                    "data": format!("0x{:064x}", amount)
+                }]
+            }
+        });
+
+        Mock::given(method("POST"))
+            .and(body_partial_json(serde_json::json!({ "method": "eth_getTransactionReceipt" })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(receipt))
+            .mount(server)
+            .await;
+
+        Mock::given(method("POST"))
+            .and(body_partial_json(serde_json::json!({ "method": "eth_blockNumber" })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "result": "0x10"
+            })))
+            .mount(server)
+            .await;
+    }
+
     #[tokio::test]
-    #[ignore] // Requires dstack client - run only in TEE environment
     async fn test_facilitator_creation() {
-        // This test requires a real dstack client, so we skip it in unit tests
-        // It should be tested in integration tests within a TEE environment
+        let dstack = MockDstackClient::new();
+
+        let first = BaseFacilitator::new(test_config("http://localhost".into()), &dstack)
+            .await
+            .unwrap();
+        let second = BaseFacilitator::new(test_config("http://localhost".into()), &dstack)
+            .await
+            .unwrap();
+
+        assert_eq!(first.chain(), Chain::Base);
+        assert!(first.deposit_address().starts_with("0x"));
+        assert_eq!(first.deposit_address(), second.deposit_address());
+    }
+
+    #[tokio::test]
+    async fn test_verify_payment_success() {
+        let server = MockServer::start().await;
+        let facilitator = BaseFacilitator::new(test_config(server.uri()), &MockDstackClient::new())
+            .await
+            .unwrap();
+        mock_transfer(&server, &facilitator.deposit_address(), 2_500_000).await;
+
+        let payload = PaymentPayload::new(Chain::Base, "0xabc".into(), "+14155551234".into())
+            .with_amount(2_500_000);
+        let verification = facilitator.verify_payment(&payload).await.unwrap();
+
+        assert!(verification.verified);
+        assert_eq!(verification.amount_usdc, 2_500_000);
+        assert_eq!(verification.from.as_deref(), Some(SENDER));
+        assert_eq!(verification.confirmations, 6);
+    }
+
+    #[tokio::test]
+    async fn test_verify_payment_in_usdt() {
+        let server = MockServer::start().await;
+        let config = BaseChainConfig {
+            tokens: vec![TokenConfig {
+                symbol: "USDT".into(),
+                contract: USDT_CONTRACT.into(),
+                decimals: 6,
+            }],
+            ..test_config(server.uri())
+        };
+        let facilitator = BaseFacilitator::new(config, &MockDstackClient::new()).await.unwrap();
+        mock_token_transfer(&server, USDT_CONTRACT, &facilitator.deposit_address(), 2_500_000).await;
+
+        let payload = PaymentPayload::new(Chain::Base, "0xabc".into(), "+14155551234".into())
+            .with_amount(2_500_000);
+        let verification = facilitator.verify_payment(&payload).await.unwrap();
+
+        assert_eq!(verification.token, "USDT");
+        assert_eq!(verification.amount_usdc, 2_500_000);
+    }
+
+    #[tokio::test]
+    async fn test_verify_payment_scales_token_decimals() {
+        let server = MockServer::start().await;
+        let config = BaseChainConfig {
+            tokens: vec![TokenConfig {
+                symbol: "DAI".into(),
+                contract: USDT_CONTRACT.into(),
+                decimals: 18,
+            }],
+            ..test_config(server.uri())
+        };
+        let facilitator = BaseFacilitator::new(config, &MockDstackClient::new()).await.unwrap();
+        // 2.5 tokens at 18 decimals
+        mock_token_transfer(&server, USDT_CONTRACT, &facilitator.deposit_address(), 2_500_000_000_000_000_000).await;
+
+        let payload = PaymentPayload::new(Chain::Base, "0xabc".into(), "+14155551234".into());
+        let verification = facilitator.verify_payment(&payload).await.unwrap();
+
+        assert_eq!(verification.token, "DAI");
+        assert_eq!(verification.amount_usdc, 2_500_000);
+    }
+
+    #[tokio::test]
+    async fn test_verify_payment_ignores_unaccepted_token() {
+        let server = MockServer::start().await;
+        let facilitator = BaseFacilitator::new(test_config(server.uri()), &MockDstackClient::new())
+            .await
+            .unwrap();
+        mock_token_transfer(&server, USDT_CONTRACT, &facilitator.deposit_address(), 2_500_000).await;
+
+        let payload = PaymentPayload::new(Chain::Base, "0xabc".into(), "+14155551234".into());
+        let result = facilitator.verify_payment(&payload).await;
+
+        assert!(matches!(result, Err(PaymentError::NoTransferFound(_))));
+    }
+
+    #[tokio::test]
+    async fn test_verify_payment_to_other_address() {
+        let server = MockServer::start().await;
+        let facilitator = BaseFacilitator::new(test_config(server.uri()), &MockDstackClient::new())
+            .await
+            .unwrap();
+        mock_transfer(&server, "0x2222222222222222222222222222222222222222", 2_500_000).await;
+
+        let payload = PaymentPayload::new(Chain::Base, "0xabc".into(), "+14155551234".into());
+        let result = facilitator.verify_payment(&payload).await;
+
+        assert!(matches!(result, Err(PaymentError::NoTransferFound(_))));
+    }
+
+    #[tokio::test]
+    async fn test_verify_payment_sender_mismatch() {
+        let server = MockServer::start().await;
+        let facilitator = BaseFacilitator::new(test_config(server.uri()), &MockDstackClient::new())
+            .await
+            .unwrap();
+        mock_transfer(&server, &facilitator.deposit_address(), 2_500_000).await;
+
+        let payload = PaymentPayload::new(Chain::Base, "0xabc".into(), "+14155551234".into())
+            .with_from("0x3333333333333333333333333333333333333333".into());
+        let result = facilitator.verify_payment(&payload).await;
+
+        assert!(matches!(result, Err(PaymentError::SenderMismatch { .. })));
+    }
+
+    #[tokio::test]
+    async fn test_native_gas_balance() {
+        let server = MockServer::start().await;
+        let facilitator = BaseFacilitator::new(test_config(server.uri()), &MockDstackClient::new())
+            .await
+            .unwrap();
+
+        Mock::given(method("POST"))
+            .and(body_partial_json(serde_json::json!({ "method": "eth_getBalance" })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "result": "0x38d7ea4c68000" // 0.001 ETH
+            })))
+            .mount(&server)
+            .await;
+
+        let balance = facilitator.get_native_gas_balance().await.unwrap();
+
+        assert_eq!(balance, 1_000_000_000_000_000);
+        assert!(balance >= facilitator.min_gas_balance());
     }
 }
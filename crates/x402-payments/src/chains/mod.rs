@@ -1,8 +1,10 @@
 //! Multi-chain payment verification and settlement.
 
+use crate::config::TokenConfig;
 use crate::error::PaymentError;
 use crate::types::{Chain, SettlementResult, TxStatus};
 use async_trait::async_trait;
+use std::sync::Arc;
 
 /// Payment payload for deposit verification.
 ///
@@ -53,6 +55,8 @@ pub struct PaymentVerification {
     pub tx_hash: String,
     /// Amount in micro-USDC (1e-6).
     pub amount_usdc: u64,
+    /// Symbol of the token transferred (e.g. `USDC`, `USDT`).
+    pub token: String,
     /// Sender address (if available).
     pub from: Option<String>,
     /// Recipient address (our deposit address).
@@ -117,15 +121,43 @@ pub trait ChainFacilitator: Send + Sync {
         payload: &PaymentPayload,
     ) -> Result<SettlementResult, PaymentError>;
 
-    /// Get the current USDC balance of the deposit wallet.
+    /// Tokens accepted for deposits on this chain, USDC first.
+    ///
+    /// FundSweeper sweeps each of them.
+    fn accepted_tokens(&self) -> Vec<TokenConfig>;
+
+    /// Get the deposit wallet's balance of `token`, in micro-units.
     ///
     /// Used by FundSweeper to know how much to sweep.
-    async fn get_deposit_wallet_balance(&self) -> Result<u64, PaymentError>;
+    async fn get_deposit_wallet_balance(&self, token: &TokenConfig) -> Result<u64, PaymentError>;
 
-    /// Transfer USDC from deposit wallet to destination.
+    /// Get the deposit wallet's balance of the chain's native gas token.
+    ///
+    /// Denominated in the smallest native unit (wei, yoctoNEAR, lamports).
+    /// Used by FundSweeper to skip transfers the wallet can't pay gas for.
+    async fn get_native_gas_balance(&self) -> Result<u128, PaymentError>;
+
+    /// Minimum native gas balance required before attempting a sweep transfer.
+    fn min_gas_balance(&self) -> u128 {
+        0
+    }
+
+    /// Micro-units of each token to leave behind when sweeping, if this
+    /// chain overrides the sweeper's default reserve.
+    fn sweep_reserve(&self) -> Option<u64> {
+        None
+    }
+
+    /// Transfer `amount` micro-units of `token` from the deposit wallet to
+    /// destination.
     ///
     /// Used by FundSweeper to send funds to operator.
-    async fn transfer_to(&self, destination: &str, amount: u64) -> Result<TxResult, PaymentError>;
+    async fn transfer_to(
+        &self,
+        destination: &str,
+        token: &TokenConfig,
+        amount: u64,
+    ) -> Result<TxResult, PaymentError>;
 
     /// Get the status of a transaction.
     async fn get_tx_status(&self, tx_hash: &str) -> Result<TxStatus, PaymentError>;
@@ -144,5 +176,32 @@ pub mod solana;
 
 // Re-exports
 pub use base::BaseFacilitator;
-pub use near::NearFacilitator;
+pub use near::{FundingStatus, NearFacilitator};
 pub use solana::SolanaFacilitator;
+
+/// One set of deposit wallets: a running facilitator per enabled chain.
+#[derive(Clone, Default)]
+pub struct DepositWallets {
+    pub base: Option<Arc<BaseFacilitator>>,
+    pub near: Option<Arc<NearFacilitator>>,
+    pub solana: Option<Arc<SolanaFacilitator>>,
+}
+
+impl DepositWallets {
+    /// Facilitator for `chain`, if it's running.
+    pub fn get(&self, chain: Chain) -> Option<Arc<dyn ChainFacilitator>> {
+        match chain {
+            Chain::Base => self.base.clone().map(|f| f as Arc<dyn ChainFacilitator>),
+            Chain::Near => self.near.clone().map(|f| f as Arc<dyn ChainFacilitator>),
+            Chain::Solana => self.solana.clone().map(|f| f as Arc<dyn ChainFacilitator>),
+        }
+    }
+
+    /// Every running facilitator.
+    pub fn facilitators(&self) -> Vec<Arc<dyn ChainFacilitator>> {
+        [Chain::Base, Chain::Near, Chain::Solana]
+            .into_iter()
+            .filter_map(|chain| self.get(chain))
+            .collect()
+    }
+}
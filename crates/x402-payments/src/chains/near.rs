@@ -3,12 +3,14 @@
 //! Uses NEAR RPC to verify USDC (NEP-141) transfers and manage deposits.
 
 use super::{ChainFacilitator, PaymentPayload, PaymentVerification, TxResult};
-use crate::config::NearChainConfig;
+use crate::config::{Commitment, NearChainConfig, TokenConfig};
 use crate::error::PaymentError;
 use crate::types::{Chain, SettlementResult, TxStatus};
 use async_trait::async_trait;
+use dstack_client::DstackApi;
+use secrecy::{ExposeSecret, SecretString};
 use serde::{Deserialize, Serialize};
-use tracing::{debug, info, warn};
+use tracing::{debug, error, info, warn};
 
 // NEAR crypto for ed25519 signing
 use near_crypto::{InMemorySigner, SecretKey};
@@ -16,7 +18,9 @@ use near_crypto::{InMemorySigner, SecretKey};
 use ed25519_dalek;
 // NEAR primitives for transaction types
 use near_primitives::{
-    transaction::{Action, FunctionCallAction, SignedTransaction, Transaction, TransactionV0},
+    transaction::{
+        Action, FunctionCallAction, SignedTransaction, Transaction, TransactionV0, TransferAction,
+    },
     types::{AccountId, BlockReference, Finality},
     views::AccessKeyView,
 };
@@ -37,6 +41,83 @@ pub struct NearFacilitator {
     rpc_client: JsonRpcClient,
     /// HTTP client for legacy RPC calls
     client: reqwest::Client,
+    /// Funding state seen at startup (`None` if the check itself failed)
+    startup_funding: Option<FundingStatus>,
+}
+
+/// Whether the implicit deposit account holds enough NEAR for gas.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FundingStatus {
+    /// Holds at least the configured `min_gas_balance`.
+    Funded { balance: u128 },
+    /// Exists but holds less than `required` yoctoNEAR.
+    Underfunded {
+        account_id: String,
+        balance: u128,
+        required: u128,
+    },
+    /// Has never received NEAR, so it doesn't exist on-chain yet.
+    Missing { account_id: String, required: u128 },
+}
+
+impl FundingStatus {
+    /// Whether transfers from the deposit account can pay for gas.
+    pub fn is_funded(&self) -> bool {
+        matches!(self, FundingStatus::Funded { .. })
+    }
+
+    /// yoctoNEAR still needed to reach the required balance.
+    pub fn shortfall(&self) -> u128 {
+        match self {
+            FundingStatus::Funded { .. } => 0,
+            FundingStatus::Underfunded { balance, required, .. } => required.saturating_sub(*balance),
+            FundingStatus::Missing { required, .. } => *required,
+        }
+    }
+
+    /// Operator instructions for funding the account, if it needs funding.
+    pub fn instructions(&self) -> Option<String> {
+        let (account_id, state) = match self {
+            FundingStatus::Funded { .. } => return None,
+            FundingStatus::Underfunded { account_id, balance, .. } => {
+                (account_id, format!("holds only {} NEAR", format_near(*balance)))
+            }
+            FundingStatus::Missing { account_id, .. } => (account_id, "does not exist yet".to_string()),
+        };
+
+        Some(format!(
+            "NEAR deposit account {} {}. Send at least {} NEAR to it \
+            (e.g. `near send <your-account> {} {}`) or set \
+            PAYMENTS__NEAR__FUNDER_ACCOUNT and PAYMENTS__NEAR__FUNDER_PRIVATE_KEY. \
+            Sweeping from NEAR fails until then.",
+            account_id,
+            state,
+            format_near(self.shortfall()),
+            account_id,
+            format_near(self.shortfall()),
+        ))
+    }
+}
+
+/// Format a yoctoNEAR amount as NEAR.
+fn format_near(yocto: u128) -> String {
+    const YOCTO_PER_NEAR: u128 = 1_000_000_000_000_000_000_000_000;
+    let whole = yocto / YOCTO_PER_NEAR;
+    let frac = format!("{:024}", yocto % YOCTO_PER_NEAR);
+    let frac = frac.trim_end_matches('0');
+    if frac.is_empty() {
+        whole.to_string()
+    } else {
+        format!("{}.{}", whole, frac)
+    }
+}
+
+/// `tx` RPC `wait_until` level for a configured commitment.
+fn wait_until(commitment: Commitment) -> &'static str {
+    match commitment {
+        Commitment::Confirmed => "EXECUTED_OPTIMISTIC",
+        Commitment::Finalized => "FINAL",
+    }
 }
 
 /// NEAR JSON-RPC request structure.
@@ -58,6 +139,14 @@ struct JsonRpcResponse<T> {
 #[derive(Debug, Deserialize)]
 struct JsonRpcError {
     message: String,
+    /// Structured cause, e.g. `UNKNOWN_ACCOUNT`.
+    #[serde(default)]
+    cause: Option<JsonRpcErrorCause>,
+}
+
+#[derive(Debug, Deserialize)]
+struct JsonRpcErrorCause {
+    name: String,
 }
 
 // RPC response structures - some fields unused but required for deserialization
@@ -139,6 +228,13 @@ mod rpc_types {
         pub result: Vec<u8>,
         pub block_height: u64,
     }
+
+    /// Query result for view_account.
+    #[derive(Debug, Deserialize)]
+    pub struct AccountBalanceView {
+        /// Liquid balance in yoctoNEAR.
+        pub amount: String,
+    }
 }
 
 use rpc_types::*;
@@ -147,10 +243,20 @@ impl NearFacilitator {
     /// Create a new NEAR facilitator with TEE-derived wallet.
     pub async fn new(
         config: NearChainConfig,
-        dstack: &dstack_client::DstackClient,
+        dstack: &dyn DstackApi,
+    ) -> Result<Self, PaymentError> {
+        Self::new_with_subject(config, dstack, None).await
+    }
+
+    /// Create a facilitator whose deposit wallet is derived for `subject`
+    /// (e.g. the bot's number), so each subject gets its own wallet.
+    pub async fn new_with_subject(
+        config: NearChainConfig,
+        dstack: &dyn DstackApi,
+        subject: Option<&str>,
     ) -> Result<Self, PaymentError> {
         // Derive wallet from TEE
-        let (signer, deposit_account) = Self::derive_wallet(dstack).await?;
+        let (signer, deposit_account) = Self::derive_wallet(dstack, subject).await?;
 
         info!(
             "Initializing NEAR facilitator: rpc={}, usdc={}, deposit={}",
@@ -166,21 +272,24 @@ impl NearFacilitator {
             .build()
             .map_err(|e| PaymentError::Internal(format!("Failed to create HTTP client: {}", e)))?;
 
-        let facilitator = Self {
+        let mut facilitator = Self {
             config,
             signer,
             deposit_account,
             rpc_client,
             client,
+            startup_funding: None,
         };
 
-        // Check if account is funded (warning only, don't fail construction)
-        if let Err(e) = facilitator.ensure_account_funded().await {
-            warn!(
-                "NEAR deposit account may not be funded yet: {}. \
-                Transfers will fail until the account is funded with at least 0.001 NEAR.",
-                e
-            );
+        // Record the funding state (don't fail construction; see `ensure_operational`)
+        match facilitator.check_funding().await {
+            Ok(status) => {
+                if let Some(instructions) = status.instructions() {
+                    warn!("{}", instructions);
+                }
+                facilitator.startup_funding = Some(status);
+            }
+            Err(e) => warn!("Could not check NEAR deposit account funding: {}", e),
         }
 
         Ok(facilitator)
@@ -188,13 +297,15 @@ impl NearFacilitator {
 
     /// Derive wallet (signer + account) from TEE key.
     ///
-    /// NEAR uses implicit accounts (64-char hex of ed25519 pubkey).
+    /// NEAR uses implicit accounts (64-char hex of ed25519 pubkey). A
+    /// `subject` yields a separate wallet per subject.
     pub async fn derive_wallet(
-        dstack: &dstack_client::DstackClient,
+        dstack: &dyn DstackApi,
+        subject: Option<&str>,
     ) -> Result<(InMemorySigner, AccountId), PaymentError> {
         // Derive 32-byte key from TEE
         let key_bytes = dstack
-            .derive_key("x402-payments/near-deposit-wallet", None)
+            .derive_key("x402-payments/near-deposit-wallet", subject)
             .await
             .map_err(|e| PaymentError::Internal(format!("Failed to derive NEAR key: {}", e)))?;
 
@@ -257,7 +368,10 @@ impl NearFacilitator {
             .map_err(|e| PaymentError::RpcError(format!("Failed to parse NEAR RPC response: {}", e)))?;
 
         if let Some(error) = json_response.error {
-            return Err(PaymentError::RpcError(error.message));
+            return Err(PaymentError::RpcError(match error.cause {
+                Some(cause) => format!("{} ({})", error.message, cause.name),
+                None => error.message,
+            }));
         }
 
         json_response
@@ -270,7 +384,7 @@ impl NearFacilitator {
         let params = serde_json::json!({
             "tx_hash": tx_hash,
             "sender_account_id": sender_id,
-            "wait_until": "EXECUTED"
+            "wait_until": wait_until(self.config.commitment)
         });
 
         self.rpc_call("tx", params).await
@@ -278,11 +392,16 @@ impl NearFacilitator {
 
     /// Get access key for transaction signing.
     async fn get_access_key(&self) -> Result<AccessKeyView, PaymentError> {
+        self.get_access_key_for(&self.signer).await
+    }
+
+    /// Get the access key `signer` signs with.
+    async fn get_access_key_for(&self, signer: &InMemorySigner) -> Result<AccessKeyView, PaymentError> {
         let request = methods::query::RpcQueryRequest {
             block_reference: BlockReference::Finality(Finality::Final),
             request: near_primitives::views::QueryRequest::ViewAccessKey {
-                account_id: self.deposit_account.clone(),
-                public_key: self.signer.public_key(),
+                account_id: signer.account_id.clone(),
+                public_key: signer.public_key(),
             },
         };
 
@@ -315,50 +434,139 @@ impl NearFacilitator {
         Ok(response)
     }
 
-    /// Check if the deposit account is funded.
+    /// Get the deposit account's liquid NEAR balance in yoctoNEAR.
+    async fn get_account_balance(&self) -> Result<u128, PaymentError> {
+        let params = serde_json::json!({
+            "request_type": "view_account",
+            "finality": "final",
+            "account_id": self.deposit_account.as_str()
+        });
+
+        let view: AccountBalanceView = self.rpc_call("query", params).await?;
+
+        view.amount
+            .parse::<u128>()
+            .map_err(|e| PaymentError::Internal(format!("Invalid account balance: {}", e)))
+    }
+
+    /// Funding state of the deposit account when the facilitator was created.
     ///
-    /// Implicit accounts on NEAR must be funded before they can perform transactions.
-    /// This method queries the account and checks if it has sufficient NEAR balance.
-    async fn ensure_account_funded(&self) -> Result<(), PaymentError> {
-        let request = methods::query::RpcQueryRequest {
-            block_reference: BlockReference::Finality(Finality::Final),
-            request: near_primitives::views::QueryRequest::ViewAccount {
-                account_id: self.deposit_account.clone(),
-            },
-        };
+    /// `None` if the RPC check failed.
+    pub fn funding_status(&self) -> Option<&FundingStatus> {
+        self.startup_funding.as_ref()
+    }
 
-        match self.rpc_client.call(request).await {
-            Ok(response) => {
-                match response.kind {
-                    QueryResponseKind::ViewAccount(account_view) => {
-                        // Check if account has at least 0.001 NEAR for gas (1e21 yoctoNEAR)
-                        const MIN_BALANCE: u128 = 1_000_000_000_000_000_000_000; // 0.001 NEAR
-
-                        if account_view.amount < MIN_BALANCE {
-                            return Err(PaymentError::Internal(format!(
-                                "Deposit account {} has insufficient NEAR balance: {} yoctoNEAR (need at least {} for gas)",
-                                self.deposit_account, account_view.amount, MIN_BALANCE
-                            )));
-                        }
-
-                        debug!(
-                            "Deposit account {} is funded with {} yoctoNEAR",
-                            self.deposit_account, account_view.amount
-                        );
-                        Ok(())
-                    }
-                    _ => Err(PaymentError::RpcError(
-                        "Unexpected response type for account query".to_string(),
-                    )),
-                }
+    /// Check whether the deposit account holds at least `min_gas_balance`.
+    ///
+    /// Implicit accounts on NEAR only exist once they've received NEAR, so an
+    /// unknown account is reported as [`FundingStatus::Missing`].
+    pub async fn check_funding(&self) -> Result<FundingStatus, PaymentError> {
+        let required = self.config.min_gas_balance;
+        let account_id = self.deposit_account.to_string();
+
+        let balance = match self.get_account_balance().await {
+            Ok(balance) => balance,
+            Err(PaymentError::RpcError(e)) if e.contains("UNKNOWN_ACCOUNT") => {
+                return Ok(FundingStatus::Missing { account_id, required });
             }
-            Err(e) => {
-                // Account doesn't exist
-                Err(PaymentError::Internal(format!(
-                    "Deposit account {} does not exist. Please fund the implicit account with at least 0.001 NEAR before use. Error: {}",
-                    self.deposit_account, e
-                )))
+            Err(e) => return Err(e),
+        };
+
+        if balance < required {
+            Ok(FundingStatus::Underfunded {
+                account_id,
+                balance,
+                required,
+            })
+        } else {
+            Ok(FundingStatus::Funded { balance })
+        }
+    }
+
+    /// Make sure the deposit account can pay for sweep gas.
+    ///
+    /// Tops the account up from `funder_account` when one is configured;
+    /// otherwise fails with instructions naming the account to fund.
+    pub async fn ensure_operational(&self) -> Result<FundingStatus, PaymentError> {
+        let status = self.check_funding().await?;
+        if status.is_funded() {
+            return Ok(status);
+        }
+
+        let (Some(funder_account), Some(funder_key)) =
+            (&self.config.funder_account, &self.config.funder_private_key)
+        else {
+            let instructions = status.instructions().unwrap_or_default();
+            error!("{}", instructions);
+            return Err(PaymentError::Config(instructions));
+        };
+
+        let amount = status.shortfall();
+        info!(
+            "Funding NEAR deposit account {} with {} NEAR from {}",
+            self.deposit_account,
+            format_near(amount),
+            funder_account
+        );
+        self.fund_from(funder_account, funder_key, amount).await?;
+
+        let status = self.check_funding().await?;
+        match status.instructions() {
+            None => Ok(status),
+            Some(instructions) => Err(PaymentError::Config(instructions)),
+        }
+    }
+
+    /// Transfer `amount` yoctoNEAR from the funder account to the deposit account.
+    async fn fund_from(
+        &self,
+        funder_account: &str,
+        funder_key: &SecretString,
+        amount: u128,
+    ) -> Result<TxResult, PaymentError> {
+        let account_id: AccountId = funder_account
+            .parse()
+            .map_err(|e| PaymentError::Config(format!("Invalid NEAR funder account: {}", e)))?;
+        let secret_key: SecretKey = funder_key
+            .expose_secret()
+            .parse()
+            .map_err(|_| PaymentError::Config("Invalid NEAR funder private key".to_string()))?;
+        let funder = InMemorySigner::from_secret_key(account_id, secret_key);
+
+        let access_key = self.get_access_key_for(&funder).await?;
+        let block = self.get_latest_block().await?;
+
+        let transaction = Transaction::V0(TransactionV0 {
+            signer_id: funder.account_id.clone(),
+            public_key: funder.public_key(),
+            nonce: access_key.nonce + 1,
+            receiver_id: self.deposit_account.clone(),
+            block_hash: block.header.hash,
+            actions: vec![Action::Transfer(TransferAction { deposit: amount })],
+        });
+
+        let signed_tx = SignedTransaction::new(
+            funder.sign(transaction.get_hash_and_size().0.as_ref()),
+            transaction,
+        );
+
+        let tx_result = self.broadcast_tx_commit(signed_tx).await?;
+        info!("NEAR deposit account funded: tx_hash={}", tx_result.tx_hash);
+
+        Ok(tx_result)
+    }
+
+    /// Fail unless the deposit account is funded.
+    async fn ensure_account_funded(&self) -> Result<(), PaymentError> {
+        match self.check_funding().await? {
+            FundingStatus::Funded { balance } => {
+                debug!(
+                    "Deposit account {} is funded with {} yoctoNEAR",
+                    self.deposit_account, balance
+                );
+                Ok(())
             }
+            status => Err(PaymentError::Internal(status.instructions().unwrap_or_default())),
         }
     }
 
@@ -501,11 +709,17 @@ impl NearFacilitator {
         if !expected_memo.is_empty() {
             let memo = args.memo.as_deref().unwrap_or("");
             if memo != expected_memo {
+                if self.config.enforce_memo {
+                    return Err(PaymentError::VerificationFailed(format!(
+                        "Memo mismatch: expected '{}', got '{}'",
+                        expected_memo, memo
+                    )));
+                }
                 warn!(
                     "Memo mismatch: expected '{}', got '{}'",
                     expected_memo, memo
                 );
-                // Note: We warn but don't fail - memo is optional verification
+                // Note: We warn but don't fail unless enforce_memo is set
             }
         }
 
@@ -517,6 +731,7 @@ impl NearFacilitator {
         Ok(PaymentVerification {
             tx_hash: tx_hash.to_string(),
             amount_usdc: amount,
+            token: "USDC".to_string(),
             from: Some(sender.to_string()),
             to: self.deposit_account.to_string(),
             confirmations: 1, // NEAR finality is immediate
@@ -562,23 +777,43 @@ impl ChainFacilitator for NearFacilitator {
         ))
     }
 
-    async fn get_deposit_wallet_balance(&self) -> Result<u64, PaymentError> {
+    fn accepted_tokens(&self) -> Vec<TokenConfig> {
+        vec![TokenConfig::usdc(&self.config.usdc_contract)]
+    }
+
+    async fn get_deposit_wallet_balance(&self, token: &TokenConfig) -> Result<u64, PaymentError> {
         let balance = self
-            .get_ft_balance(&self.config.usdc_contract, self.deposit_account.as_ref())
+            .get_ft_balance(&token.contract, self.deposit_account.as_ref())
             .await?;
 
-        debug!("NEAR deposit wallet balance: {} USDC (micro)", balance);
+        debug!("NEAR deposit wallet balance: {} {} (micro)", balance, token.symbol);
+        Ok(balance)
+    }
+
+    async fn get_native_gas_balance(&self) -> Result<u128, PaymentError> {
+        let balance = self.get_account_balance().await?;
+
+        debug!("NEAR deposit account gas balance: {} yoctoNEAR", balance);
         Ok(balance)
     }
 
+    fn min_gas_balance(&self) -> u128 {
+        self.config.min_gas_balance
+    }
+
+    fn sweep_reserve(&self) -> Option<u64> {
+        self.config.sweep_reserve_usdc
+    }
+
     async fn transfer_to(
         &self,
         destination: &str,
+        token: &TokenConfig,
         amount: u64,
     ) -> Result<TxResult, PaymentError> {
         info!(
-            "Transferring {} USDC from {} to {} on NEAR",
-            amount, self.deposit_account, destination
+            "Transferring {} micro-{} from {} to {} on NEAR",
+            amount, token.symbol, self.deposit_account, destination
         );
 
         // Ensure the deposit account is funded before attempting transfer
@@ -603,10 +838,10 @@ impl ChainFacilitator for NearFacilitator {
         let args_json = serde_json::to_string(&ft_transfer_args)
             .map_err(|e| PaymentError::Internal(format!("Failed to serialize args: {}", e)))?;
 
-        // Parse USDC contract as AccountId
-        let usdc_contract: AccountId = self.config.usdc_contract
+        // Parse token contract as AccountId
+        let token_contract: AccountId = token.contract
             .parse()
-            .map_err(|e| PaymentError::Internal(format!("Invalid USDC contract: {}", e)))?;
+            .map_err(|e| PaymentError::Internal(format!("Invalid {} contract: {}", token.symbol, e)))?;
 
         // Create ft_transfer action
         let action = Action::FunctionCall(Box::new(FunctionCallAction {
@@ -621,7 +856,7 @@ impl ChainFacilitator for NearFacilitator {
             signer_id: self.deposit_account.clone(),
             public_key: self.signer.public_key(),
             nonce: access_key.nonce + 1,
-            receiver_id: usdc_contract.clone(),
+            receiver_id: token_contract.clone(),
             block_hash: block.header.hash,
             actions: vec![action],
         };
@@ -675,14 +910,346 @@ impl ChainFacilitator for NearFacilitator {
 
 #[cfg(test)]
 mod tests {
-    // Note: Integration tests for NearFacilitator require a real DstackClient
-    // to derive the ed25519 wallet keypair from TEE-derived entropy.
-    //
-    // Unit tests here would need to mock DstackClient, which is complex.
-    // Instead, we rely on:
-    // 1. Integration tests that run in a real TEE environment (marked #[ignore])
-    // 2. The comprehensive tests in credits::store that validate the overall flow
-    // 3. Manual testing with real NEAR testnet/mainnet transactions
-    //
-    // Future work: Create a mock DstackClient for unit testing chain facilitators
+    use super::*;
+    use dstack_client::MockDstackClient;
+    use wiremock::matchers::{body_partial_json, method};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    const USDC_CONTRACT: &str = "usdc.test.near";
+    const SENDER: &str = "alice.near";
+
+    fn test_config(rpc_url: String) -> NearChainConfig {
+        NearChainConfig {
+            enabled: true,
+            rpc_url,
+            usdc_contract: USDC_CONTRACT.to_string(),
+            operator_account: None,
+            enforce_memo: false,
+            sweep_reserve_usdc: None,
+            min_gas_balance: 1_000_000_000_000_000_000_000,
+            funder_account: None,
+            funder_private_key: None,
+            commitment: Commitment::Finalized,
+        }
+    }
+
+    /// Build a `tx` RPC response containing a single ft_transfer call.
+    fn ft_transfer_response(receiver_id: &str, amount: &str, memo: Option<&str>) -> serde_json::Value {
+        let args = serde_json::json!({
+            "receiver_id": receiver_id,
+            "amount": amount,
+            "memo": memo,
+        });
+        let args_base64 = base64::Engine::encode(
+            &base64::engine::general_purpose::STANDARD,
+            serde_json::to_vec(&args).unwrap(),
+        );
+
+        serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": "dontcare",
+            "result": {
+                "status": { "SuccessValue": "" },
+                "transaction": {
+                    "signer_id": SENDER,
+                    "receiver_id": USDC_CONTRACT,
+                    "actions": [{
+                        "type": "FunctionCall",
+                        "method_name": "ft_transfer",
+                        "args": args_base64
+                    }]
+                },
+                "receipts_outcome": []
+            }
+        })
+    }
+
+    async fn setup(response: serde_json::Value) -> (NearFacilitator, MockServer) {
+        setup_with_config(response, |_| {}).await
+    }
+
+    async fn setup_with_config(
+        response: serde_json::Value,
+        configure: impl FnOnce(&mut NearChainConfig),
+    ) -> (NearFacilitator, MockServer) {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(body_partial_json(serde_json::json!({ "method": "tx" })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(response))
+            .mount(&server)
+            .await;
+
+        let mut config = test_config(server.uri());
+        configure(&mut config);
+        let facilitator = NearFacilitator::new(config, &MockDstackClient::new())
+            .await
+            .unwrap();
+
+        (facilitator, server)
+    }
+
+    async fn deposit_account() -> String {
+        let (_, account) = NearFacilitator::derive_wallet(&MockDstackClient::new(), None)
+            .await
+            .unwrap();
+        account.to_string()
+    }
+
+    #[tokio::test]
+    async fn test_derive_wallet_is_deterministic_implicit_account() {
+        let dstack = MockDstackClient::new();
+
+        let (_, first) = NearFacilitator::derive_wallet(&dstack, None).await.unwrap();
+        let (_, second) = NearFacilitator::derive_wallet(&dstack, None).await.unwrap();
+
+        assert_eq!(first, second);
+        assert_eq!(first.as_str().len(), 64);
+        assert!(first.as_str().chars().all(|c| c.is_ascii_hexdigit()));
+
+        let (_, other) = NearFacilitator::derive_wallet(&MockDstackClient::new().with_seed("other"), None)
+            .await
+            .unwrap();
+        assert_ne!(first, other);
+    }
+
+    #[tokio::test]
+    async fn test_derive_wallet_per_subject() {
+        let dstack = MockDstackClient::new();
+
+        let (_, shared) = NearFacilitator::derive_wallet(&dstack, None).await.unwrap();
+        let (_, bot_a) = NearFacilitator::derive_wallet(&dstack, Some("+15550001111")).await.unwrap();
+        let (_, bot_b) = NearFacilitator::derive_wallet(&dstack, Some("+15550002222")).await.unwrap();
+
+        assert_ne!(shared, bot_a);
+        assert_ne!(bot_a, bot_b);
+        assert_eq!(
+            bot_a,
+            NearFacilitator::derive_wallet(&dstack, Some("+15550001111")).await.unwrap().1
+        );
+    }
+
+    #[tokio::test]
+    async fn test_derive_wallet_outside_tee_fails() {
+        let result = NearFacilitator::derive_wallet(&MockDstackClient::not_in_tee(), None).await;
+        assert!(matches!(result, Err(PaymentError::Internal(_))));
+    }
+
+    #[tokio::test]
+    async fn test_verify_payment_success() {
+        let (_, deposit_account) = NearFacilitator::derive_wallet(&MockDstackClient::new(), None)
+            .await
+            .unwrap();
+        let (facilitator, _server) =
+            setup(ft_transfer_response(deposit_account.as_str(), "5000000", Some("+14155551234"))).await;
+
+        let payload = PaymentPayload::new(Chain::Near, "txhash".into(), "+14155551234".into())
+            .with_from(SENDER.into())
+            .with_amount(5_000_000);
+        let verification = facilitator.verify_payment(&payload).await.unwrap();
+
+        assert!(verification.verified);
+        assert_eq!(verification.amount_usdc, 5_000_000);
+        assert_eq!(verification.from.as_deref(), Some(SENDER));
+        assert_eq!(verification.to, facilitator.deposit_address());
+    }
+
+    #[tokio::test]
+    async fn test_verify_payment_wrong_receiver() {
+        let (facilitator, _server) =
+            setup(ft_transfer_response("someone-else.near", "5000000", None)).await;
+
+        let payload = PaymentPayload::new(Chain::Near, "txhash".into(), "+14155551234".into())
+            .with_from(SENDER.into());
+        let result = facilitator.verify_payment(&payload).await;
+
+        assert!(matches!(result, Err(PaymentError::NoTransferFound(_))));
+    }
+
+    #[tokio::test]
+    async fn test_verify_payment_amount_mismatch() {
+        let (_, deposit_account) = NearFacilitator::derive_wallet(&MockDstackClient::new(), None)
+            .await
+            .unwrap();
+        let (facilitator, _server) =
+            setup(ft_transfer_response(deposit_account.as_str(), "1000000", None)).await;
+
+        let payload = PaymentPayload::new(Chain::Near, "txhash".into(), "+14155551234".into())
+            .with_from(SENDER.into())
+            .with_amount(5_000_000);
+        let result = facilitator.verify_payment(&payload).await;
+
+        assert!(matches!(
+            result,
+            Err(PaymentError::AmountMismatch {
+                expected: 5_000_000,
+                actual: 1_000_000
+            })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_verify_payment_requires_sender() {
+        let (facilitator, _server) = setup(serde_json::json!({})).await;
+
+        let payload = PaymentPayload::new(Chain::Near, "txhash".into(), "+14155551234".into());
+        let result = facilitator.verify_payment(&payload).await;
+
+        assert!(matches!(result, Err(PaymentError::InvalidPayload(_))));
+    }
+
+    #[tokio::test]
+    async fn test_memo_mismatch_warns_by_default() {
+        let (facilitator, _server) =
+            setup(ft_transfer_response(&deposit_account().await, "5000000", Some("+19995550000"))).await;
+
+        let payload = PaymentPayload::new(Chain::Near, "txhash".into(), "+14155551234".into())
+            .with_from(SENDER.into());
+
+        assert!(facilitator.verify_payment(&payload).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_memo_mismatch_rejected_when_enforced() {
+        let (facilitator, _server) = setup_with_config(
+            ft_transfer_response(&deposit_account().await, "5000000", Some("+19995550000")),
+            |config| config.enforce_memo = true,
+        )
+        .await;
+
+        let payload = PaymentPayload::new(Chain::Near, "txhash".into(), "+14155551234".into())
+            .with_from(SENDER.into());
+        let result = facilitator.verify_payment(&payload).await;
+
+        assert!(matches!(result, Err(PaymentError::VerificationFailed(_))));
+    }
+
+    #[tokio::test]
+    async fn test_missing_memo_rejected_when_enforced() {
+        let (facilitator, _server) = setup_with_config(
+            ft_transfer_response(&deposit_account().await, "5000000", None),
+            |config| config.enforce_memo = true,
+        )
+        .await;
+
+        let payload = PaymentPayload::new(Chain::Near, "txhash".into(), "+14155551234".into())
+            .with_from(SENDER.into());
+        let result = facilitator.verify_payment(&payload).await;
+
+        assert!(matches!(result, Err(PaymentError::VerificationFailed(_))));
+    }
+
+    #[tokio::test]
+    async fn test_matching_memo_accepted_when_enforced() {
+        let (facilitator, _server) = setup_with_config(
+            ft_transfer_response(&deposit_account().await, "5000000", Some("+14155551234")),
+            |config| config.enforce_memo = true,
+        )
+        .await;
+
+        let payload = PaymentPayload::new(Chain::Near, "txhash".into(), "+14155551234".into())
+            .with_from(SENDER.into());
+
+        assert!(facilitator.verify_payment(&payload).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_native_gas_balance() {
+        let (facilitator, server) = setup(serde_json::json!({})).await;
+
+        Mock::given(method("POST"))
+            .and(body_partial_json(serde_json::json!({
+                "method": "query",
+                "params": { "request_type": "view_account" }
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "jsonrpc": "2.0",
+                "id": "dontcare",
+                "result": {
+                    "amount": "500000000000000000000",
+                    "locked": "0",
+                    "code_hash": "11111111111111111111111111111111",
+                    "storage_usage": 182,
+                    "block_height": 1,
+                    "block_hash": "11111111111111111111111111111111"
+                }
+            })))
+            .mount(&server)
+            .await;
+
+        let balance = facilitator.get_native_gas_balance().await.unwrap();
+
+        assert_eq!(balance, 500_000_000_000_000_000_000);
+        assert!(balance < facilitator.min_gas_balance());
+        assert!(facilitator.ensure_account_funded().await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_unknown_deposit_account_reported_missing() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(body_partial_json(serde_json::json!({
+                "method": "query",
+                "params": { "request_type": "view_account" }
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "jsonrpc": "2.0",
+                "id": "dontcare",
+                "error": {
+                    "name": "HANDLER_ERROR",
+                    "cause": { "name": "UNKNOWN_ACCOUNT", "info": {} },
+                    "code": -32000,
+                    "message": "Server error"
+                }
+            })))
+            .mount(&server)
+            .await;
+
+        let facilitator = NearFacilitator::new(test_config(server.uri()), &MockDstackClient::new())
+            .await
+            .unwrap();
+        let account_id = deposit_account().await;
+
+        let expected = FundingStatus::Missing {
+            account_id: account_id.clone(),
+            required: 1_000_000_000_000_000_000_000,
+        };
+        assert_eq!(facilitator.funding_status(), Some(&expected));
+
+        // Without a funder configured, the error names the account to fund
+        match facilitator.ensure_operational().await {
+            Err(PaymentError::Config(message)) => {
+                assert!(message.contains(&account_id));
+                assert!(message.contains("0.001 NEAR"));
+            }
+            other => panic!("expected a config error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_format_near() {
+        assert_eq!(format_near(1_000_000_000_000_000_000_000), "0.001");
+        assert_eq!(format_near(2_500_000_000_000_000_000_000_000), "2.5");
+        assert_eq!(format_near(0), "0");
+    }
+
+    #[tokio::test]
+    async fn test_verify_payment_waits_for_configured_commitment() {
+        let account = deposit_account().await;
+        let response = ft_transfer_response(&account, "5000000", None);
+        let payload = PaymentPayload::new(Chain::Near, "txhash".into(), "+14155551234".into())
+            .with_from(SENDER.into());
+
+        for (commitment, expected) in [
+            (Commitment::Finalized, "FINAL"),
+            (Commitment::Confirmed, "EXECUTED_OPTIMISTIC"),
+        ] {
+            let (facilitator, server) =
+                setup_with_config(response.clone(), |config| config.commitment = commitment).await;
+            facilitator.verify_payment(&payload).await.unwrap();
+
+            let requests = server.received_requests().await.unwrap();
+            let tx_request: serde_json::Value = requests.last().unwrap().body_json().unwrap();
+            assert_eq!(tx_request["params"]["wait_until"], expected);
+        }
+    }
 }
@@ -1,24 +1,28 @@
 //! Solana chain facilitator with wallet support.
 //!
-//! Verifies SPL token (USDC) transfers and supports sweeping to operator.
+//! Verifies SPL token (USDC and other configured stablecoin) transfers and
+//! supports sweeping to operator.
 
 use super::{ChainFacilitator, PaymentPayload, PaymentVerification, TxResult};
-use crate::config::SolanaChainConfig;
+use crate::config::{Commitment, SolanaChainConfig, TokenConfig};
 use crate::error::PaymentError;
 use crate::types::{Chain, SettlementResult, TxStatus};
 use async_trait::async_trait;
+use dstack_client::DstackApi;
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 use tracing::{debug, info, warn};
 
 // Solana SDK imports
-use solana_client::rpc_client::RpcClient;
+use solana_client::{client_error::ClientError, rpc_client::RpcClient};
+use solana_compute_budget_interface::ComputeBudgetInstruction;
 use solana_sdk::{
     commitment_config::CommitmentConfig,
+    instruction::Instruction,
     pubkey::Pubkey,
     signature::{Keypair, Signer},
     signer::SeedDerivable,
-    transaction::Transaction,
+    transaction::{Transaction, TransactionError},
 };
 use spl_associated_token_account::{
     get_associated_token_address, instruction::create_associated_token_account,
@@ -143,18 +147,74 @@ mod rpc_types {
     pub struct SignatureStatusResult {
         pub value: Vec<Option<SignatureStatus>>,
     }
+
+    /// getBalance result.
+    #[derive(Debug, Deserialize)]
+    pub struct BalanceResult {
+        /// Balance in lamports.
+        pub value: u64,
+    }
 }
 
 use rpc_types::*;
 
+/// Solana RPC commitment name for a configured level.
+fn commitment_name(commitment: Commitment) -> &'static str {
+    match commitment {
+        Commitment::Confirmed => "confirmed",
+        Commitment::Finalized => "finalized",
+    }
+}
+
+/// Prefix `instructions` with a compute-unit price when a priority fee is set.
+fn with_priority_fee(priority_fee_micro_lamports: u64, instructions: Vec<Instruction>) -> Vec<Instruction> {
+    if priority_fee_micro_lamports == 0 {
+        return instructions;
+    }
+
+    let mut with_fee = Vec::with_capacity(instructions.len() + 1);
+    with_fee.push(ComputeBudgetInstruction::set_compute_unit_price(priority_fee_micro_lamports));
+    with_fee.extend(instructions);
+    with_fee
+}
+
+/// Whether a send failed because its blockhash expired, so re-signing with a
+/// fresh one may succeed.
+fn is_blockhash_expired(error: &ClientError) -> bool {
+    if error.get_transaction_error() == Some(TransactionError::BlockhashNotFound) {
+        return true;
+    }
+    let message = error.to_string().to_lowercase();
+    message.contains("blockhash not found")
+        || message.contains("block height exceeded")
+        || message.contains("transaction expiration")
+}
+
+fn commitment_config(commitment: Commitment) -> CommitmentConfig {
+    match commitment {
+        Commitment::Confirmed => CommitmentConfig::confirmed(),
+        Commitment::Finalized => CommitmentConfig::finalized(),
+    }
+}
+
 impl SolanaFacilitator {
     /// Create a new Solana facilitator.
     pub async fn new(
         config: SolanaChainConfig,
-        dstack: &dstack_client::DstackClient,
+        dstack: &dyn DstackApi,
+    ) -> Result<Self, PaymentError> {
+        Self::new_with_subject(config, dstack, None).await
+    }
+
+    /// Create a facilitator whose deposit wallet is derived for `subject`
+    /// (e.g. the bot's number), so each subject gets its own wallet.
+    pub async fn new_with_subject(
+        config: SolanaChainConfig,
+        dstack: &dyn DstackApi,
+        subject: Option<&str>,
     ) -> Result<Self, PaymentError> {
         // Derive wallet keypair
-        let (wallet_keypair, wallet_pubkey) = Self::derive_wallet(dstack).await?;
+        let (wallet_keypair, wallet_pubkey) = Self::derive_wallet(dstack, subject).await?;
 
         info!(
             "Initializing Solana facilitator: rpc={}, usdc_mint={}, deposit={}",
@@ -166,10 +226,10 @@ impl SolanaFacilitator {
             .build()
             .map_err(|e| PaymentError::Internal(format!("Failed to create HTTP client: {}", e)))?;
 
-        // Create RPC client with confirmed commitment
+        // Create RPC client at the configured commitment
         let rpc_client = RpcClient::new_with_commitment(
             config.rpc_url.clone(),
-            CommitmentConfig::confirmed(),
+            commitment_config(config.commitment),
         );
 
         Ok(Self {
@@ -183,13 +243,15 @@ impl SolanaFacilitator {
 
     /// Derive deposit wallet from TEE key.
     ///
-    /// Returns (Keypair, Pubkey) for the TEE-derived wallet.
+    /// Returns (Keypair, Pubkey) for the TEE-derived wallet, separate per
+    /// `subject` if one is given.
     pub async fn derive_wallet(
-        dstack: &dstack_client::DstackClient,
+        dstack: &dyn DstackApi,
+        subject: Option<&str>,
     ) -> Result<(Keypair, Pubkey), PaymentError> {
         // Derive 32-byte key from TEE
         let key_bytes = dstack
-            .derive_key("x402-payments/solana-deposit-wallet", None)
+            .derive_key("x402-payments/solana-deposit-wallet", subject)
             .await
             .map_err(|e| PaymentError::Internal(format!("Failed to derive Solana key: {}", e)))?;
 
@@ -256,6 +318,7 @@ impl SolanaFacilitator {
             signature,
             {
                 "encoding": "json",
+                "commitment": commitment_name(self.config.commitment),
                 "maxSupportedTransactionVersion": 0
             }
         ]);
@@ -269,8 +332,9 @@ impl SolanaFacilitator {
         self.rpc_call("getSignatureStatuses", params).await
     }
 
-    /// Verify a USDC transfer transaction.
-    async fn verify_usdc_transfer(
+    /// Verify a transfer of any accepted token, converting the amount to
+    /// micro-units with the token's decimals.
+    async fn verify_token_transfer(
         &self,
         signature: &str,
         expected_from: Option<&str>,
@@ -294,15 +358,17 @@ impl SolanaFacilitator {
         let pre_balances = tx.meta.pre_token_balances.unwrap_or_default();
         let post_balances = tx.meta.post_token_balances.unwrap_or_default();
 
+        let tokens = self.config.accepted_tokens();
         let mut verified_amount: u64 = 0;
         let mut verified_from: Option<String> = None;
+        let mut verified_token: Option<&str> = None;
 
-        // Find USDC balance changes to our deposit address
+        // Find accepted token balance changes to our deposit address
         for post in &post_balances {
-            // Check if this is USDC
-            if post.mint != self.config.usdc_mint {
+            // Check if this is an accepted mint
+            let Some(token) = tokens.iter().find(|token| token.contract == post.mint) else {
                 continue;
-            }
+            };
 
             // Check if this is our deposit address
             let owner = post.owner.as_deref().unwrap_or("");
@@ -311,26 +377,30 @@ impl SolanaFacilitator {
             }
 
             // Find corresponding pre-balance
-            let pre_amount: u64 = pre_balances
+            let pre_amount: u128 = pre_balances
                 .iter()
                 .find(|p| p.account_index == post.account_index)
                 .map(|p| p.ui_token_amount.amount.parse().unwrap_or(0))
                 .unwrap_or(0);
 
-            let post_amount: u64 = post.ui_token_amount.amount.parse().unwrap_or(0);
+            let post_amount: u128 = post.ui_token_amount.amount.parse().unwrap_or(0);
 
             if post_amount > pre_amount {
-                verified_amount = post_amount - pre_amount;
+                let raw_amount = post_amount - pre_amount;
+                verified_amount = token.to_micro_units(raw_amount).ok_or_else(|| {
+                    PaymentError::InvalidPayload(format!("{} amount {} is too large", token.symbol, raw_amount))
+                })?;
+                verified_token = Some(&token.symbol);
 
                 // Try to find the sender
                 for pre in &pre_balances {
-                    if pre.mint == self.config.usdc_mint {
-                        let pre_bal: u64 = pre.ui_token_amount.amount.parse().unwrap_or(0);
+                    if pre.mint == post.mint {
+                        let pre_bal: u128 = pre.ui_token_amount.amount.parse().unwrap_or(0);
                         if let Some(post_entry) = post_balances
                             .iter()
                             .find(|p| p.account_index == pre.account_index)
                         {
-                            let post_bal: u64 = post_entry.ui_token_amount.amount.parse().unwrap_or(0);
+                            let post_bal: u128 = post_entry.ui_token_amount.amount.parse().unwrap_or(0);
                             if pre_bal > post_bal {
                                 verified_from = pre.owner.clone();
                                 break;
@@ -342,12 +412,12 @@ impl SolanaFacilitator {
             }
         }
 
-        if verified_amount == 0 {
+        let Some(token) = verified_token.filter(|_| verified_amount > 0) else {
             return Err(PaymentError::NoTransferFound(format!(
-                "No USDC transfer to {} found in tx {}",
+                "No accepted token transfer to {} found in tx {}",
                 self.wallet_pubkey, signature
             )));
-        }
+        };
 
         // Verify sender if expected
         if let Some(expected) = expected_from {
@@ -372,13 +442,14 @@ impl SolanaFacilitator {
         }
 
         debug!(
-            "Verified Solana USDC transfer: from={:?}, amount={}, signature={}",
-            verified_from, verified_amount, signature
+            "Verified Solana {} transfer: from={:?}, amount={}, signature={}",
+            token, verified_from, verified_amount, signature
         );
 
         Ok(PaymentVerification {
             tx_hash: signature.to_string(),
             amount_usdc: verified_amount,
+            token: token.to_string(),
             from: verified_from,
             to: self.wallet_pubkey.to_string(),
             confirmations: 1, // Solana finality is fast
@@ -408,7 +479,7 @@ impl ChainFacilitator for SolanaFacilitator {
         payload: &PaymentPayload,
     ) -> Result<PaymentVerification, PaymentError> {
         // tx_hash contains the Solana transaction signature
-        self.verify_usdc_transfer(&payload.tx_hash, payload.from.as_deref(), payload.amount)
+        self.verify_token_transfer(&payload.tx_hash, payload.from.as_deref(), payload.amount)
             .await
     }
 
@@ -422,27 +493,34 @@ impl ChainFacilitator for SolanaFacilitator {
         ))
     }
 
-    async fn get_deposit_wallet_balance(&self) -> Result<u64, PaymentError> {
-        // Parse USDC mint
-        let usdc_mint = Self::parse_pubkey(&self.config.usdc_mint)?;
+    fn accepted_tokens(&self) -> Vec<TokenConfig> {
+        self.config.accepted_tokens()
+    }
+
+    async fn get_deposit_wallet_balance(&self, token: &TokenConfig) -> Result<u64, PaymentError> {
+        // Parse token mint
+        let mint = Self::parse_pubkey(&token.contract)?;
 
         // Derive the associated token account (ATA) for our wallet
-        let ata = get_associated_token_address(&self.wallet_pubkey, &usdc_mint);
+        let ata = get_associated_token_address(&self.wallet_pubkey, &mint);
 
         debug!(
-            "Getting USDC balance for wallet {} at ATA {}",
-            self.wallet_pubkey, ata
+            "Getting {} balance for wallet {} at ATA {}",
+            token.symbol, self.wallet_pubkey, ata
         );
 
         // Get token account balance
         match self.rpc_client.get_token_account_balance(&ata) {
             Ok(balance) => {
-                let amount = balance
+                let raw = balance
                     .amount
-                    .parse::<u64>()
+                    .parse::<u128>()
                     .map_err(|e| PaymentError::Internal(format!("Invalid balance amount: {}", e)))?;
+                let amount = token.to_micro_units(raw).ok_or_else(|| {
+                    PaymentError::Internal(format!("{} balance overflow", token.symbol))
+                })?;
 
-                debug!("Solana wallet balance: {} USDC (raw amount)", amount);
+                debug!("Solana wallet balance: {} {} (micro)", amount, token.symbol);
                 Ok(amount)
             }
             Err(e) => {
@@ -460,23 +538,46 @@ impl ChainFacilitator for SolanaFacilitator {
         }
     }
 
+    async fn get_native_gas_balance(&self) -> Result<u128, PaymentError> {
+        let params = serde_json::json!([self.wallet_pubkey.to_string()]);
+        let result: BalanceResult = self.rpc_call("getBalance", params).await?;
+
+        debug!("Solana wallet gas balance: {} lamports", result.value);
+        Ok(u128::from(result.value))
+    }
+
+    fn min_gas_balance(&self) -> u128 {
+        self.config.min_gas_balance
+    }
+
+    fn sweep_reserve(&self) -> Option<u64> {
+        self.config.sweep_reserve_usdc
+    }
+
     async fn transfer_to(
         &self,
         destination: &str,
+        token: &TokenConfig,
         amount: u64,
     ) -> Result<TxResult, PaymentError> {
         info!(
-            "Transferring {} USDC from {} to {}",
-            amount, self.wallet_pubkey, destination
+            "Transferring {} micro-{} from {} to {}",
+            amount, token.symbol, self.wallet_pubkey, destination
         );
 
+        // Convert amount to the token's raw units
+        let raw_amount = token
+            .from_micro_units(amount)
+            .and_then(|raw| u64::try_from(raw).ok())
+            .ok_or_else(|| PaymentError::Internal(format!("{} amount overflow", token.symbol)))?;
+
         // Parse addresses
-        let usdc_mint = Self::parse_pubkey(&self.config.usdc_mint)?;
+        let mint = Self::parse_pubkey(&token.contract)?;
         let destination_pubkey = Self::parse_pubkey(destination)?;
 
         // Derive ATAs
-        let source_ata = get_associated_token_address(&self.wallet_pubkey, &usdc_mint);
-        let dest_ata = get_associated_token_address(&destination_pubkey, &usdc_mint);
+        let source_ata = get_associated_token_address(&self.wallet_pubkey, &mint);
+        let dest_ata = get_associated_token_address(&destination_pubkey, &mint);
 
         debug!(
             "Transfer from ATA {} to ATA {}",
@@ -492,7 +593,7 @@ impl ChainFacilitator for SolanaFacilitator {
                 let create_ata_ix = create_associated_token_account(
                     &self.wallet_pubkey,  // payer
                     &destination_pubkey,  // wallet owner
-                    &usdc_mint,           // mint
+                    &mint,           // mint
                     &spl_token::id(),     // token program
                 );
                 instructions.push(create_ata_ix);
@@ -502,43 +603,53 @@ impl ChainFacilitator for SolanaFacilitator {
             }
         }
 
-        // Create transfer_checked instruction (USDC has 6 decimals)
+        // Create transfer_checked instruction
         let transfer_ix = transfer_checked(
             &spl_token::id(),
             &source_ata,
-            &usdc_mint,
+            &mint,
             &dest_ata,
             &self.wallet_pubkey,
             &[],
-            amount,
-            6, // USDC decimals
+            raw_amount,
+            token.decimals,
         )
         .map_err(|e| PaymentError::Internal(format!("Failed to create transfer instruction: {}", e)))?;
         instructions.push(transfer_ix);
-
-        // Get recent blockhash
-        let recent_blockhash = self
-            .rpc_client
-            .get_latest_blockhash()
-            .map_err(|e| PaymentError::RpcError(format!("Failed to get recent blockhash: {}", e)))?;
-
-        // Create and sign transaction
-        let transaction = Transaction::new_signed_with_payer(
-            &instructions,
-            Some(&self.wallet_pubkey),
-            &[&self.wallet_keypair],
-            recent_blockhash,
-        );
-
-        // Send transaction
-        let signature = self
-            .rpc_client
-            .send_and_confirm_transaction(&transaction)
-            .map_err(|e| PaymentError::TxFailed(format!("Transfer failed: {}", e)))?;
+        let instructions = with_priority_fee(self.config.priority_fee_micro_lamports, instructions);
+
+        // Sign with a fresh blockhash on each attempt; retry only when the
+        // previous blockhash expired before the transfer landed
+        let mut attempt = 0;
+        let signature = loop {
+            let recent_blockhash = self
+                .rpc_client
+                .get_latest_blockhash()
+                .map_err(|e| PaymentError::RpcError(format!("Failed to get recent blockhash: {}", e)))?;
+
+            let transaction = Transaction::new_signed_with_payer(
+                &instructions,
+                Some(&self.wallet_pubkey),
+                &[&self.wallet_keypair],
+                recent_blockhash,
+            );
+
+            match self.rpc_client.send_and_confirm_transaction(&transaction) {
+                Ok(signature) => break signature,
+                Err(e) if is_blockhash_expired(&e) && attempt < self.config.transfer_retries => {
+                    attempt += 1;
+                    warn!(
+                        "Solana transfer blockhash expired, retrying ({}/{}): {}",
+                        attempt, self.config.transfer_retries, e
+                    );
+                }
+                Err(e) => return Err(PaymentError::TxFailed(format!("Transfer failed: {}", e))),
+            }
+        };
 
         info!(
-            "Successfully transferred {} USDC to {}, signature: {}",
-            amount, destination, signature
+            "Successfully transferred {} micro-{} to {}, signature: {}",
+            amount, token.symbol, destination, signature
         );
 
         Ok(TxResult {
@@ -557,7 +668,7 @@ impl ChainFacilitator for SolanaFacilitator {
                     Ok(TxStatus::Failed {
                         reason: format!("{:?}", status.err),
                     })
-                } else if status.confirmation_status.as_deref() == Some("finalized") {
+                } else if self.config.commitment.is_met_by(status.confirmation_status.as_deref()) {
                     Ok(TxStatus::Confirmed {
                         confirmations: status.confirmations.unwrap_or(1),
                     })
@@ -592,9 +703,170 @@ impl ChainFacilitator for SolanaFacilitator {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::config::TokenConfig;
+    use dstack_client::MockDstackClient;
+    use wiremock::matchers::{body_partial_json, method};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    const USDC_MINT: &str = "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v";
+    const USDT_MINT: &str = "Es9vMFrzaCERmJfrF4H2FYD4KCoNkY11McCe8BenwNYB";
+    const SENDER: &str = "9WzDXwBbmkg8ZTbNMqUxvQRAyrZzDsGYdLVL9zYtAWWM";
+
+    fn test_config(rpc_url: String) -> SolanaChainConfig {
+        SolanaChainConfig {
+            enabled: true,
+            rpc_url,
+            usdc_mint: USDC_MINT.to_string(),
+            operator_address: None,
+            sweep_reserve_usdc: None,
+            min_gas_balance: 2_100_000,
+            commitment: Commitment::Confirmed,
+            priority_fee_micro_lamports: 0,
+            transfer_retries: 3,
+            tokens: vec![],
+        }
+    }
+
+    fn token_balance(index: u8, mint: &str, owner: &str, amount: u64) -> serde_json::Value {
+        serde_json::json!({
+            "accountIndex": index,
+            "mint": mint,
+            "owner": owner,
+            "uiTokenAmount": { "amount": amount.to_string(), "decimals": 6, "uiAmount": null }
+        })
+    }
+
+    /// Mount a getTransaction response moving `amount` USDC from SENDER to `to`.
+    async fn mock_transfer(server: &MockServer, to: &str, amount: u64) {
+        mock_token_transfer(server, USDC_MINT, to, amount).await;
+    }
+
+    /// Mount a getTransaction response moving `amount` of `mint` from SENDER to `to`.
+    async fn mock_token_transfer(server: &MockServer, mint: &str, to: &str, amount: u64) {
+        let response = serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "result": {
+                "slot": 1,
+                "blockTime": null,
+                "transaction": {
+                    "message": { "accountKeys": [], "instructions": [] },
+                    "signatures": ["sig"]
+                },
+                "meta": {
+                    "err": null,
+                    "fee": 5000,
+                    "preTokenBalances": [
+                        token_balance(1, mint, to, 0),
+                        token_balance(2, mint, SENDER, 10_000_000)
+                    ],
+                    "postTokenBalances": [
+                        token_balance(1, mint, to, amount),
+                        token_balance(2, mint, SENDER, 10_000_000 - amount)
+                    ]
+                }
+            }
+        });
+
+        Mock::given(method("POST"))
+            .and(body_partial_json(serde_json::json!({ "method": "getTransaction" })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(response))
+            .mount(server)
+            .await;
+    }
 
-    // Note: Tests requiring DstackClient are integration tests that run in TEE
-    // Unit tests here validate parsing and type conversions
+    async fn test_facilitator(server: &MockServer) -> SolanaFacilitator {
+        SolanaFacilitator::new(test_config(server.uri()), &MockDstackClient::new())
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_derive_wallet_is_deterministic() {
+        let dstack = MockDstackClient::new();
+
+        let (_, first) = SolanaFacilitator::derive_wallet(&dstack, None).await.unwrap();
+        let (_, second) = SolanaFacilitator::derive_wallet(&dstack, None).await.unwrap();
+
+        assert_eq!(first, second);
+    }
+
+    #[tokio::test]
+    async fn test_verify_payment_success() {
+        let server = MockServer::start().await;
+        let facilitator = test_facilitator(&server).await;
+        mock_transfer(&server, &facilitator.deposit_address(), 3_000_000).await;
+
+        let payload = PaymentPayload::new(Chain::Solana, "sig".into(), "+14155551234".into())
+            .with_from(SENDER.into())
+            .with_amount(3_000_000);
+        let verification = facilitator.verify_payment(&payload).await.unwrap();
+
+        assert!(verification.verified);
+        assert_eq!(verification.amount_usdc, 3_000_000);
+        assert_eq!(verification.token, "USDC");
+        assert_eq!(verification.from.as_deref(), Some(SENDER));
+    }
+
+    #[tokio::test]
+    async fn test_verify_payment_in_usdt() {
+        let server = MockServer::start().await;
+        let config = SolanaChainConfig {
+            tokens: vec![TokenConfig {
+                symbol: "USDT".into(),
+                contract: USDT_MINT.into(),
+                decimals: 6,
+            }],
+            ..test_config(server.uri())
+        };
+        let facilitator = SolanaFacilitator::new(config, &MockDstackClient::new()).await.unwrap();
+        mock_token_transfer(&server, USDT_MINT, &facilitator.deposit_address(), 3_000_000).await;
+
+        let payload = PaymentPayload::new(Chain::Solana, "sig".into(), "+14155551234".into())
+            .with_amount(3_000_000);
+        let verification = facilitator.verify_payment(&payload).await.unwrap();
+
+        assert_eq!(verification.token, "USDT");
+        assert_eq!(verification.amount_usdc, 3_000_000);
+        assert_eq!(verification.from.as_deref(), Some(SENDER));
+    }
+
+    #[tokio::test]
+    async fn test_verify_payment_ignores_unaccepted_mint() {
+        let server = MockServer::start().await;
+        let facilitator = test_facilitator(&server).await;
+        mock_token_transfer(&server, USDT_MINT, &facilitator.deposit_address(), 3_000_000).await;
+
+        let payload = PaymentPayload::new(Chain::Solana, "sig".into(), "+14155551234".into());
+        let result = facilitator.verify_payment(&payload).await;
+
+        assert!(matches!(result, Err(PaymentError::NoTransferFound(_))));
+    }
+
+    #[tokio::test]
+    async fn test_verify_payment_to_other_wallet() {
+        let server = MockServer::start().await;
+        let facilitator = test_facilitator(&server).await;
+        mock_transfer(&server, SENDER, 3_000_000).await;
+
+        let payload = PaymentPayload::new(Chain::Solana, "sig".into(), "+14155551234".into());
+        let result = facilitator.verify_payment(&payload).await;
+
+        assert!(matches!(result, Err(PaymentError::NoTransferFound(_))));
+    }
+
+    #[tokio::test]
+    async fn test_verify_payment_amount_mismatch() {
+        let server = MockServer::start().await;
+        let facilitator = test_facilitator(&server).await;
+        mock_transfer(&server, &facilitator.deposit_address(), 3_000_000).await;
+
+        let payload = PaymentPayload::new(Chain::Solana, "sig".into(), "+14155551234".into())
+            .with_amount(1_000_000);
+        let result = facilitator.verify_payment(&payload).await;
+
+        assert!(matches!(result, Err(PaymentError::AmountMismatch { .. })));
+    }
 
     #[test]
     fn test_parse_pubkey() {
@@ -606,4 +878,87 @@ mod tests {
         let result = SolanaFacilitator::parse_pubkey("invalid");
         assert!(result.is_err());
     }
+
+    #[tokio::test]
+    async fn test_native_gas_balance() {
+        let server = MockServer::start().await;
+        let facilitator = SolanaFacilitator::new(test_config(server.uri()), &MockDstackClient::new())
+            .await
+            .unwrap();
+
+        Mock::given(method("POST"))
+            .and(body_partial_json(serde_json::json!({
+                "method": "getBalance",
+                "params": [facilitator.deposit_address()]
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "result": { "context": { "slot": 1 }, "value": 5_000_000 }
+            })))
+            .mount(&server)
+            .await;
+
+        let balance = facilitator.get_native_gas_balance().await.unwrap();
+
+        assert_eq!(balance, 5_000_000);
+        assert!(balance >= facilitator.min_gas_balance());
+    }
+
+    #[tokio::test]
+    async fn test_tx_status_honors_commitment() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(body_partial_json(serde_json::json!({ "method": "getSignatureStatuses" })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "result": {
+                    "context": { "slot": 10 },
+                    "value": [{ "slot": 9, "confirmations": 3, "err": null, "confirmationStatus": "confirmed" }]
+                }
+            })))
+            .mount(&server)
+            .await;
+
+        let confirmed = test_facilitator(&server).await;
+        assert!(matches!(
+            confirmed.get_tx_status("sig").await.unwrap(),
+            TxStatus::Confirmed { confirmations: 3 }
+        ));
+
+        let mut config = test_config(server.uri());
+        config.commitment = Commitment::Finalized;
+        let finalized = SolanaFacilitator::new(config, &MockDstackClient::new()).await.unwrap();
+        assert!(matches!(finalized.get_tx_status("sig").await.unwrap(), TxStatus::Pending));
+
+        // Transaction lookups ask the RPC for the same commitment
+        mock_transfer(&server, &finalized.deposit_address(), 1_000_000).await;
+        let payload = PaymentPayload::new(Chain::Solana, "sig".into(), "+14155551234".into());
+        finalized.verify_payment(&payload).await.unwrap();
+
+        let requests = server.received_requests().await.unwrap();
+        let lookup: serde_json::Value = requests.last().unwrap().body_json().unwrap();
+        assert_eq!(lookup["params"][1]["commitment"], "finalized");
+    }
+
+    #[test]
+    fn test_priority_fee_instruction() {
+        let transfer = Instruction::new_with_bytes(spl_token::id(), &[1, 2, 3], vec![]);
+
+        let instructions = with_priority_fee(0, vec![transfer.clone()]);
+        assert_eq!(instructions, vec![transfer.clone()]);
+
+        let instructions = with_priority_fee(25_000, vec![transfer.clone()]);
+        assert_eq!(instructions.len(), 2);
+        assert_eq!(instructions[0], ComputeBudgetInstruction::set_compute_unit_price(25_000));
+        assert_eq!(instructions[0].program_id, solana_compute_budget_interface::id());
+        assert_eq!(instructions[1], transfer);
+    }
+
+    #[test]
+    fn test_blockhash_expiry_is_retryable() {
+        assert!(is_blockhash_expired(&ClientError::from(TransactionError::BlockhashNotFound)));
+        assert!(!is_blockhash_expired(&ClientError::from(TransactionError::InsufficientFundsForFee)));
+    }
 }
@@ -1,6 +1,7 @@
 //! Payment configuration.
 
 use crate::types::{Chain, OperatorAddresses};
+use secrecy::SecretString;
 use serde::Deserialize;
 use std::path::PathBuf;
 use std::time::Duration;
@@ -24,6 +25,25 @@ pub struct PaymentConfig {
     #[serde(default = "default_storage_path")]
     pub storage_path: PathBuf,
 
+    /// Retries after a failed credit store write before the change is rolled back.
+    #[serde(default = "default_persist_retries")]
+    pub persist_retries: u32,
+
+    /// Prefix of the subject mixed into every TEE key derivation, giving
+    /// this deployment its own keys. Each of `accounts` derives its deposit
+    /// wallets for `{prefix}:{account}` (the bare account without a prefix);
+    /// the default wallets and the credit store, shared by every account,
+    /// use the prefix alone. Changing it changes all of them, so set it
+    /// before the first deposit.
+    #[serde(default)]
+    pub signing_subject: Option<String>,
+
+    /// Comma-separated Signal accounts served by this deployment, each
+    /// given its own deposit wallets. The bot fills it in from Signal CLI
+    /// when unset.
+    #[serde(default)]
+    pub accounts: Option<String>,
+
     /// Base (EVM) chain configuration.
     pub base: Option<BaseChainConfig>,
 
@@ -36,6 +56,45 @@ pub struct PaymentConfig {
     /// Sweep configuration.
     #[serde(default)]
     pub sweep: SweepConfig,
+
+    /// Bearer token for admin endpoints (disabled when unset).
+    #[serde(default)]
+    pub admin_token: Option<SecretString>,
+
+    /// Smallest accepted deposit in micro-USDC.
+    /// Default: 100,000 (= $0.10), below which sweeping costs more than it moves.
+    #[serde(default = "default_min_deposit")]
+    pub min_deposit_usdc: u64,
+
+    /// Largest accepted deposit in micro-USDC (unbounded when unset).
+    #[serde(default)]
+    pub max_deposit_usdc: Option<u64>,
+
+    /// Only credit Base/Solana deposits whose exact amount was reserved by
+    /// the claiming user (`POST /v1/deposit-intent`).
+    #[serde(default)]
+    pub require_unique_amount: bool,
+
+    /// Only credit Base/Solana deposits sent from an address the claiming
+    /// user linked (`POST /v1/link-address`).
+    #[serde(default)]
+    pub require_linked_sender: bool,
+
+    /// URL POSTed a JSON event for every credited deposit (best-effort).
+    #[serde(default)]
+    pub deposit_webhook_url: Option<String>,
+
+    /// How long a deposit's `Idempotency-Key` is remembered.
+    #[serde(default = "default_idempotency_key_ttl", with = "humantime_serde")]
+    pub idempotency_key_ttl: Duration,
+
+    /// How long a reserved deposit amount stays valid.
+    #[serde(default = "default_deposit_intent_ttl", with = "humantime_serde")]
+    pub deposit_intent_ttl: Duration,
+
+    /// How often chain RPCs are health-checked when operator alerts are on.
+    #[serde(default = "default_health_check_interval", with = "humantime_serde")]
+    pub health_check_interval: Duration,
 }
 
 fn default_enabled() -> bool {
@@ -50,6 +109,26 @@ fn default_storage_path() -> PathBuf {
     PathBuf::from("/data/credits.enc")
 }
 
+fn default_persist_retries() -> u32 {
+    crate::credits::DEFAULT_PERSIST_RETRIES
+}
+
+fn default_min_deposit() -> u64 {
+    100_000
+}
+
+fn default_idempotency_key_ttl() -> Duration {
+    crate::credits::DEFAULT_IDEMPOTENCY_KEY_TTL
+}
+
+fn default_deposit_intent_ttl() -> Duration {
+    Duration::from_secs(60 * 60)
+}
+
+fn default_health_check_interval() -> Duration {
+    Duration::from_secs(5 * 60)
+}
+
 impl Default for PaymentConfig {
     fn default() -> Self {
         Self {
@@ -57,10 +136,22 @@ impl Default for PaymentConfig {
             server_port: 8082,
             pricing: PricingConfig::default(),
             storage_path: default_storage_path(),
+            persist_retries: default_persist_retries(),
+            signing_subject: None,
+            accounts: None,
             base: None,
             near: None,
             solana: None,
             sweep: SweepConfig::default(),
+            admin_token: None,
+            min_deposit_usdc: default_min_deposit(),
+            max_deposit_usdc: None,
+            require_unique_amount: false,
+            require_linked_sender: false,
+            deposit_webhook_url: None,
+            idempotency_key_ttl: default_idempotency_key_ttl(),
+            deposit_intent_ttl: default_deposit_intent_ttl(),
+            health_check_interval: default_health_check_interval(),
         }
     }
 }
@@ -75,6 +166,26 @@ impl PaymentConfig {
         }
     }
 
+    /// Accounts listed in `accounts`.
+    pub fn account_list(&self) -> Vec<String> {
+        self.accounts
+            .as_deref()
+            .unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|account| !account.is_empty())
+            .map(String::from)
+            .collect()
+    }
+
+    /// Key derivation subject for `account`'s deposit wallets.
+    pub fn account_subject(&self, account: &str) -> String {
+        match self.signing_subject.as_deref() {
+            Some(prefix) if !prefix.is_empty() => format!("{}:{}", prefix, account),
+            _ => account.to_string(),
+        }
+    }
+
     /// Get enabled chains.
     pub fn enabled_chains(&self) -> Vec<Chain> {
         let mut chains = Vec::new();
@@ -104,15 +215,42 @@ pub struct PricingConfig {
     #[serde(default = "default_completion_credits")]
     pub completion_credits_per_million: u64,
 
+    /// Rates for specific models, overriding the two above, as a
+    /// comma-separated list of `model=prompt:completion` credits per 1M
+    /// tokens (e.g. `deepseek-ai/DeepSeek-V3.1=200000:600000`). Entries
+    /// that don't parse are ignored. Default: none
+    #[serde(default)]
+    pub model_rates: Option<String>,
+
     /// Minimum credits per message (floor).
     /// Default: 100 (= $0.0001)
     #[serde(default = "default_minimum_credits")]
     pub minimum_credits_per_message: u64,
 
+    /// Round fractional credits up to the next whole credit; when false
+    /// they are truncated. Default: true
+    #[serde(default = "default_round_up")]
+    pub round_up: bool,
+
+    /// Most credits a single message may cost, including tool calls.
+    /// Completions are cut short to stay within it. Default: no cap.
+    #[serde(default)]
+    pub max_credits_per_message: Option<u64>,
+
     /// USDC to credits ratio.
     /// Default: 1,000,000 (1 USDC = 1M credits)
     #[serde(default = "default_usdc_ratio")]
     pub usdc_to_credits_ratio: u64,
+
+    /// How long credits last after the user's most recent deposit. Unused
+    /// credits older than this are forfeited. Default: never expire.
+    #[serde(default, with = "humantime_serde::option")]
+    pub credit_ttl: Option<Duration>,
+
+    /// Warn a user once their balance covers fewer than this many messages
+    /// like the one just charged. 0 disables the warning. Default: 5
+    #[serde(default = "default_low_balance_messages")]
+    pub low_balance_messages: u64,
 }
 
 fn default_prompt_credits() -> u64 {
@@ -127,18 +265,53 @@ fn default_minimum_credits() -> u64 {
     100
 }
 
+fn default_round_up() -> bool {
+    true
+}
+
 fn default_usdc_ratio() -> u64 {
     1_000_000
 }
 
+fn default_low_balance_messages() -> u64 {
+    5
+}
+
 impl Default for PricingConfig {
     fn default() -> Self {
         Self {
             prompt_credits_per_million: default_prompt_credits(),
             completion_credits_per_million: default_completion_credits(),
+            model_rates: None,
             minimum_credits_per_message: default_minimum_credits(),
+            round_up: default_round_up(),
+            max_credits_per_message: None,
             usdc_to_credits_ratio: default_usdc_ratio(),
+            credit_ttl: None,
+            low_balance_messages: default_low_balance_messages(),
+        }
+    }
+}
+
+impl PricingConfig {
+    /// This config with `model`'s rates from `model_rates`, if it has any.
+    pub fn for_model(&self, model: &str) -> PricingConfig {
+        let rates = self.model_rates.as_deref().and_then(|list| {
+            list.split(',').find_map(|entry| {
+                let (name, rates) = entry.split_once('=')?;
+                if name.trim() != model {
+                    return None;
+                }
+                let (prompt, completion) = rates.split_once(':')?;
+                Some((prompt.trim().parse().ok()?, completion.trim().parse().ok()?))
+            })
+        });
+        let mut config = self.clone();
+        if let Some((prompt, completion)) = rates {
+            config.prompt_credits_per_million = prompt;
+            config.completion_credits_per_million = completion;
         }
+        config
     }
 }
 
@@ -159,12 +332,35 @@ pub struct BaseChainConfig {
 
     /// Operator's withdrawal address.
     pub operator_address: Option<String>,
+
+    /// USDC (micro) to leave in the deposit wallet when sweeping.
+    /// Overrides `sweep.reserve_for_gas` for this chain.
+    pub sweep_reserve_usdc: Option<u64>,
+
+    /// Minimum ETH balance (wei) the deposit wallet needs before a sweep is attempted.
+    #[serde(default = "default_base_min_gas_balance")]
+    pub min_gas_balance: u128,
+
+    /// ERC-20 stablecoins accepted besides USDC, credited 1:1 with USDC.
+    #[serde(default, deserialize_with = "deserialize_tokens")]
+    pub tokens: Vec<TokenConfig>,
+}
+
+impl BaseChainConfig {
+    /// Every token a deposit may be made in, USDC first.
+    pub fn accepted_tokens(&self) -> Vec<TokenConfig> {
+        accepted_tokens(&self.usdc_contract, &self.tokens)
+    }
 }
 
 fn default_chain_enabled() -> bool {
     true
 }
 
+fn default_base_min_gas_balance() -> u128 {
+    20_000_000_000_000 // 0.00002 ETH
+}
+
 fn default_base_rpc() -> String {
     "https://mainnet.base.org".to_string()
 }
@@ -174,6 +370,136 @@ fn default_base_usdc() -> String {
     "0x833589fCD6eDb6E08f4c7C32D4f71b54bdA02913".to_string()
 }
 
+/// A token accepted for deposits.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct TokenConfig {
+    /// Display symbol, e.g. `USDT`.
+    pub symbol: String,
+    /// Contract address (Base) or mint (Solana).
+    pub contract: String,
+    /// Decimal places of the token's smallest unit.
+    #[serde(default = "default_token_decimals")]
+    pub decimals: u8,
+}
+
+fn default_token_decimals() -> u8 {
+    USDC_DECIMALS
+}
+
+/// Decimal places of USDC, and of the micro-units credits are priced in.
+pub const USDC_DECIMALS: u8 = 6;
+
+impl TokenConfig {
+    /// USDC at `contract`.
+    pub fn usdc(contract: &str) -> Self {
+        Self {
+            symbol: "USDC".to_string(),
+            contract: contract.to_string(),
+            decimals: USDC_DECIMALS,
+        }
+    }
+
+    /// Convert a raw on-chain amount to micro-units (6 decimals), rounding
+    /// down. `None` if the result doesn't fit in a `u64`.
+    pub fn to_micro_units(&self, raw: u128) -> Option<u64> {
+        let micro = if self.decimals >= USDC_DECIMALS {
+            raw / 10u128.checked_pow(u32::from(self.decimals - USDC_DECIMALS))?
+        } else {
+            raw.checked_mul(10u128.pow(u32::from(USDC_DECIMALS - self.decimals)))?
+        };
+        u64::try_from(micro).ok()
+    }
+
+    /// Convert micro-units (6 decimals) to a raw on-chain amount, rounding
+    /// down. `None` on overflow.
+    pub fn from_micro_units(&self, micro: u64) -> Option<u128> {
+        let micro = u128::from(micro);
+        if self.decimals >= USDC_DECIMALS {
+            micro.checked_mul(10u128.checked_pow(u32::from(self.decimals - USDC_DECIMALS))?)
+        } else {
+            Some(micro / 10u128.pow(u32::from(USDC_DECIMALS - self.decimals)))
+        }
+    }
+}
+
+/// USDC followed by `extra`, skipping any that repeat the USDC contract.
+fn accepted_tokens(usdc_contract: &str, extra: &[TokenConfig]) -> Vec<TokenConfig> {
+    let mut tokens = vec![TokenConfig::usdc(usdc_contract)];
+    tokens.extend(
+        extra
+            .iter()
+            .filter(|token| !token.contract.eq_ignore_ascii_case(usdc_contract))
+            .cloned(),
+    );
+    tokens
+}
+
+/// Accept tokens as a list of `{symbol, contract, decimals}` tables, or, as
+/// environment variables only carry strings, as comma-separated
+/// `SYMBOL:contract[:decimals]` entries.
+fn deserialize_tokens<'de, D>(deserializer: D) -> Result<Vec<TokenConfig>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Tokens {
+        List(Vec<TokenConfig>),
+        Compact(String),
+    }
+
+    match Tokens::deserialize(deserializer)? {
+        Tokens::List(tokens) => Ok(tokens),
+        Tokens::Compact(list) => list
+            .split(',')
+            .map(str::trim)
+            .filter(|entry| !entry.is_empty())
+            .map(|entry| {
+                let mut parts = entry.split(':').map(str::trim);
+                let (Some(symbol), Some(contract)) = (parts.next(), parts.next()) else {
+                    return Err(serde::de::Error::custom(format!(
+                        "token `{}` must be SYMBOL:contract[:decimals]",
+                        entry
+                    )));
+                };
+                let decimals = match parts.next() {
+                    Some(decimals) => decimals.parse().map_err(|_| {
+                        serde::de::Error::custom(format!("invalid decimals in token `{}`", entry))
+                    })?,
+                    None => USDC_DECIMALS,
+                };
+                Ok(TokenConfig {
+                    symbol: symbol.to_string(),
+                    contract: contract.to_string(),
+                    decimals,
+                })
+            })
+            .collect(),
+    }
+}
+
+/// Commitment level a transaction must reach before it counts as confirmed.
+///
+/// Lower levels credit deposits sooner; higher levels rule out rollbacks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Commitment {
+    /// Voted on by a supermajority (Solana) or optimistically executed (NEAR).
+    Confirmed,
+    /// Finalized and irreversible.
+    Finalized,
+}
+
+impl Commitment {
+    /// Whether a Solana `confirmationStatus` meets this level.
+    pub fn is_met_by(self, confirmation_status: Option<&str>) -> bool {
+        match self {
+            Commitment::Confirmed => matches!(confirmation_status, Some("confirmed" | "finalized")),
+            Commitment::Finalized => confirmation_status == Some("finalized"),
+        }
+    }
+}
+
 /// NEAR Protocol configuration.
 #[derive(Debug, Clone, Deserialize)]
 pub struct NearChainConfig {
@@ -191,6 +517,51 @@ pub struct NearChainConfig {
 
     /// Operator's withdrawal account.
     pub operator_account: Option<String>,
+
+    /// Reject deposits whose `ft_transfer` memo is not the claiming user's
+    /// phone number.
+    ///
+    /// Without this anyone who sees a deposit tx hash can claim it for their
+    /// own account. With it, users must remember to set the memo, and a
+    /// deposit sent without one cannot be credited automatically. Off by
+    /// default so existing deposit flows keep working.
+    #[serde(default = "default_enforce_memo")]
+    pub enforce_memo: bool,
+
+    /// USDC (micro) to leave in the deposit account when sweeping.
+    /// Overrides `sweep.reserve_for_gas` for this chain.
+    pub sweep_reserve_usdc: Option<u64>,
+
+    /// Minimum NEAR balance (yoctoNEAR) the deposit account needs before a
+    /// sweep is attempted.
+    #[serde(default = "default_near_min_gas_balance")]
+    pub min_gas_balance: u128,
+
+    /// Account that tops up the deposit account's NEAR at startup.
+    pub funder_account: Option<String>,
+
+    /// Private key (`ed25519:...`) for `funder_account`.
+    #[serde(default)]
+    pub funder_private_key: Option<SecretString>,
+
+    /// How final a deposit transaction must be before it is credited.
+    ///
+    /// `confirmed` accepts optimistic execution; `finalized` waits until
+    /// every block the transfer touched is final.
+    #[serde(default = "default_near_commitment")]
+    pub commitment: Commitment,
+}
+
+fn default_near_commitment() -> Commitment {
+    Commitment::Finalized
+}
+
+fn default_enforce_memo() -> bool {
+    false
+}
+
+fn default_near_min_gas_balance() -> u128 {
+    1_000_000_000_000_000_000_000 // 0.001 NEAR
 }
 
 fn default_near_rpc() -> String {
@@ -219,6 +590,53 @@ pub struct SolanaChainConfig {
 
     /// Operator's withdrawal address.
     pub operator_address: Option<String>,
+
+    /// USDC (micro) to leave in the deposit wallet when sweeping.
+    /// Overrides `sweep.reserve_for_gas` for this chain.
+    pub sweep_reserve_usdc: Option<u64>,
+
+    /// Minimum SOL balance (lamports) the deposit wallet needs before a sweep
+    /// is attempted.
+    #[serde(default = "default_solana_min_gas_balance")]
+    pub min_gas_balance: u128,
+
+    /// Commitment a deposit transaction must reach before it is credited.
+    #[serde(default = "default_solana_commitment")]
+    pub commitment: Commitment,
+
+    /// Compute-unit price (micro-lamports) paid as a priority fee on sweep
+    /// transfers. 0 sends without a priority fee.
+    #[serde(default)]
+    pub priority_fee_micro_lamports: u64,
+
+    /// Times a sweep transfer is re-signed with a fresh blockhash after the
+    /// previous one expired.
+    #[serde(default = "default_solana_transfer_retries")]
+    pub transfer_retries: u32,
+
+    /// SPL stablecoins accepted besides USDC, credited 1:1 with USDC.
+    #[serde(default, deserialize_with = "deserialize_tokens")]
+    pub tokens: Vec<TokenConfig>,
+}
+
+impl SolanaChainConfig {
+    /// Every token a deposit may be made in, USDC first.
+    pub fn accepted_tokens(&self) -> Vec<TokenConfig> {
+        accepted_tokens(&self.usdc_mint, &self.tokens)
+    }
+}
+
+fn default_solana_transfer_retries() -> u32 {
+    3
+}
+
+fn default_solana_commitment() -> Commitment {
+    Commitment::Confirmed
+}
+
+fn default_solana_min_gas_balance() -> u128 {
+    // Fee plus rent for creating the operator's token account on first sweep
+    2_100_000 // 0.0021 SOL
 }
 
 fn default_solana_rpc() -> String {
@@ -241,9 +659,22 @@ pub struct SweepConfig {
     #[serde(default = "default_min_sweep_amount")]
     pub min_amount_usdc: u64,
 
-    /// Reserve to keep for gas fees (in micro-USDC).
+    /// USDC to leave in deposit wallets when sweeping (in micro-USDC).
+    ///
+    /// Gas itself is paid in each chain's native token and checked separately
+    /// against the chain's `min_gas_balance`. Chains can override this with
+    /// `sweep_reserve_usdc`.
     #[serde(default = "default_reserve_for_gas")]
     pub reserve_for_gas: u64,
+
+    /// Delay before retrying a chain whose sweep failed, doubled on each
+    /// further failure (capped at `interval`).
+    #[serde(default = "default_sweep_retry_delay", with = "humantime_serde")]
+    pub retry_delay: Duration,
+
+    /// Retries made after a failed sweep before waiting for the next interval.
+    #[serde(default = "default_sweep_max_retries")]
+    pub max_retries: u32,
 }
 
 fn default_sweep_interval() -> Duration {
@@ -258,12 +689,95 @@ fn default_reserve_for_gas() -> u64 {
     10_000 // $0.01 for gas
 }
 
+fn default_sweep_retry_delay() -> Duration {
+    Duration::from_secs(5 * 60) // 5 minutes
+}
+
+fn default_sweep_max_retries() -> u32 {
+    3
+}
+
 impl Default for SweepConfig {
     fn default() -> Self {
         Self {
             interval: default_sweep_interval(),
             min_amount_usdc: default_min_sweep_amount(),
             reserve_for_gas: default_reserve_for_gas(),
+            retry_delay: default_sweep_retry_delay(),
+            max_retries: default_sweep_max_retries(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_token_micro_units() {
+        let token = |decimals| TokenConfig {
+            symbol: "T".into(),
+            contract: "0x1".into(),
+            decimals,
+        };
+        assert_eq!(token(6).to_micro_units(2_500_000), Some(2_500_000));
+        assert_eq!(token(18).to_micro_units(2_500_000_000_000_000_000), Some(2_500_000));
+        assert_eq!(token(2).to_micro_units(250), Some(2_500_000));
+        assert_eq!(token(6).to_micro_units(u128::MAX), None);
+
+        assert_eq!(token(6).from_micro_units(2_500_000), Some(2_500_000));
+        assert_eq!(token(18).from_micro_units(2_500_000), Some(2_500_000_000_000_000_000));
+        assert_eq!(token(2).from_micro_units(2_509_999), Some(250));
+    }
+
+    #[test]
+    fn test_account_subject() {
+        let mut config = PaymentConfig {
+            accounts: Some(" +15550001111,,+15550002222 ".into()),
+            ..Default::default()
+        };
+        assert_eq!(config.account_list(), ["+15550001111", "+15550002222"]);
+        assert_eq!(config.account_subject("+15550001111"), "+15550001111");
+
+        config.signing_subject = Some("prod".into());
+        assert_eq!(config.account_subject("+15550001111"), "prod:+15550001111");
+    }
+
+    #[test]
+    fn test_pricing_for_model() {
+        let config = PricingConfig {
+            model_rates: Some("cheap-model=1000:2000, bad-model=oops, big/model-v2 = 500000:1500000".into()),
+            ..Default::default()
+        };
+
+        let cheap = config.for_model("cheap-model");
+        assert_eq!(cheap.prompt_credits_per_million, 1000);
+        assert_eq!(cheap.completion_credits_per_million, 2000);
+        assert_eq!(config.for_model("big/model-v2").completion_credits_per_million, 1_500_000);
+
+        // Unlisted or malformed entries keep the default rates
+        for model in ["other-model", "bad-model"] {
+            let priced = config.for_model(model);
+            assert_eq!(priced.prompt_credits_per_million, default_prompt_credits());
+            assert_eq!(priced.completion_credits_per_million, default_completion_credits());
         }
     }
+
+    #[test]
+    fn test_compact_token_list() {
+        let config: BaseChainConfig = serde_json::from_value(serde_json::json!({
+            "tokens": "USDT:0xfde4C96c8593536E31F229EA8f37b2ADa2699bb2, DAI:0x50c5725949A6F0c72E6C4a641F24049A917DB0Cb:18"
+        }))
+        .unwrap();
+
+        let tokens = config.accepted_tokens();
+        assert_eq!(tokens.len(), 3);
+        assert_eq!(tokens[0], TokenConfig::usdc(&default_base_usdc()));
+        assert_eq!(tokens[1].symbol, "USDT");
+        assert_eq!(tokens[1].decimals, 6);
+        assert_eq!(tokens[2].decimals, 18);
+
+        let invalid = serde_json::from_value::<BaseChainConfig>(serde_json::json!({ "tokens": "USDT" }));
+        assert!(invalid.is_err());
+    }
 }
@@ -3,5 +3,9 @@
 mod pricing;
 mod store;
 
-pub use pricing::{calculate_credits, estimate_credits, PricingCalculator, TokenUsage};
-pub use store::{CreditStore, CreditStoreData};
+pub use pricing::{
+    calculate_credits, estimate_credits, max_completion_tokens, PricingCalculator, TokenUsage,
+};
+pub use store::{
+    CreditStore, CreditStoreData, KeySource, DEFAULT_IDEMPOTENCY_KEY_TTL, DEFAULT_PERSIST_RETRIES,
+};
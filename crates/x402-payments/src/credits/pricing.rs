@@ -20,22 +20,37 @@ impl TokenUsage {
     pub fn total_tokens(&self) -> u32 {
         self.prompt_tokens + self.completion_tokens
     }
+
+    /// Usage expected for a prompt of `prompt_tokens`, assuming the response
+    /// is ~2x the prompt (very rough).
+    pub fn estimated(prompt_tokens: u32) -> Self {
+        Self::new(prompt_tokens, prompt_tokens.saturating_mul(2))
+    }
 }
 
 /// Calculate credits required for given token usage.
 ///
 /// Formula:
-/// - prompt_cost = (prompt_tokens * prompt_credits_per_million) / 1_000_000
-/// - completion_cost = (completion_tokens * completion_credits_per_million) / 1_000_000
-/// - total = max(prompt_cost + completion_cost, minimum_credits_per_message)
+/// - cost = prompt_tokens * prompt_credits_per_million
+///   + completion_tokens * completion_credits_per_million
+/// - credits = cost / 1_000_000, rounded up to a whole credit when `round_up`
+///   is set and truncated otherwise
+/// - total = max(credits, minimum_credits_per_message)
+///
+/// Rounding happens once on the sum, so splitting usage between prompt and
+/// completion never adds a credit.
 pub fn calculate_credits(usage: &TokenUsage, config: &PricingConfig) -> u64 {
-    let prompt_cost =
-        (usage.prompt_tokens as u64 * config.prompt_credits_per_million) / 1_000_000;
-    let completion_cost =
-        (usage.completion_tokens as u64 * config.completion_credits_per_million) / 1_000_000;
-
-    let total = prompt_cost + completion_cost;
-    total.max(config.minimum_credits_per_message)
+    let cost = usage.prompt_tokens as u128 * config.prompt_credits_per_million as u128
+        + usage.completion_tokens as u128 * config.completion_credits_per_million as u128;
+
+    let credits = if config.round_up {
+        cost.div_ceil(1_000_000)
+    } else {
+        cost / 1_000_000
+    };
+    u64::try_from(credits)
+        .unwrap_or(u64::MAX)
+        .max(config.minimum_credits_per_message)
 }
 
 /// Estimate credits for a message based on character count.
@@ -44,12 +59,27 @@ pub fn calculate_credits(usage: &TokenUsage, config: &PricingConfig) -> u64 {
 /// This is used for pre-flight checks before processing a message.
 pub fn estimate_credits(message_chars: usize, config: &PricingConfig) -> u64 {
     // Rough estimate: 4 chars per token
-    let estimated_prompt_tokens = (message_chars / 4).max(1) as u32;
-    // Assume response is ~2x the prompt (very rough)
-    let estimated_completion_tokens = estimated_prompt_tokens * 2;
+    let estimated_prompt_tokens = u32::try_from(message_chars / 4).unwrap_or(u32::MAX).max(1);
+    calculate_credits(&TokenUsage::estimated(estimated_prompt_tokens), config)
+}
 
-    let usage = TokenUsage::new(estimated_prompt_tokens, estimated_completion_tokens);
-    calculate_credits(&usage, config)
+/// Largest completion, in tokens, that keeps a request within `budget` credits.
+///
+/// Returns `None` when the budget can't cover the prompt plus at least one
+/// completion token.
+pub fn max_completion_tokens(prompt_tokens: u32, budget: u64, config: &PricingConfig) -> Option<u32> {
+    // Worked in millionths of a credit so the result fits the budget
+    // whichever way `calculate_credits` rounds
+    let prompt_cost = prompt_tokens as u128 * config.prompt_credits_per_million as u128;
+    let remaining = (budget as u128 * 1_000_000).checked_sub(prompt_cost)?;
+    if config.completion_credits_per_million == 0 {
+        return Some(u32::MAX);
+    }
+    let tokens = remaining / config.completion_credits_per_million as u128;
+    match u32::try_from(tokens).unwrap_or(u32::MAX) {
+        0 => None,
+        tokens => Some(tokens),
+    }
 }
 
 /// Pricing calculator with cached config.
@@ -72,6 +102,11 @@ impl PricingCalculator {
         estimate_credits(message_chars, &self.config)
     }
 
+    /// Estimate credits for a prompt of `prompt_tokens` and a typical response.
+    pub fn estimate_tokens(&self, prompt_tokens: u32) -> u64 {
+        calculate_credits(&TokenUsage::estimated(prompt_tokens), &self.config)
+    }
+
     /// Convert USDC amount to credits.
     pub fn usdc_to_credits(&self, usdc_micro: u64) -> u64 {
         // 1 USDC = usdc_to_credits_ratio credits
@@ -138,6 +173,37 @@ mod tests {
         assert_eq!(credits, config.minimum_credits_per_message);
     }
 
+    #[test]
+    fn test_calculate_credits_below_minimum() {
+        let config = default_config();
+        // 10 * 0.1 + 10 * 0.3 = 4 credits, well under the 100 minimum
+        assert_eq!(calculate_credits(&TokenUsage::new(10, 10), &config), 100);
+    }
+
+    #[test]
+    fn test_calculate_credits_exact_boundary() {
+        let config = default_config();
+        // 400 * 0.1 + 200 * 0.3 = exactly the 100 credit minimum
+        assert_eq!(calculate_credits(&TokenUsage::new(400, 200), &config), 100);
+        // One more completion token tips it over: 100.3 rounds up to 101
+        assert_eq!(calculate_credits(&TokenUsage::new(400, 201), &config), 101);
+        // Whole results aren't rounded
+        assert_eq!(calculate_credits(&TokenUsage::new(10_000, 0), &config), 1_000);
+    }
+
+    #[test]
+    fn test_calculate_credits_rounds_fractional_up() {
+        let config = default_config();
+        // 1001 * 0.1 + 501 * 0.3 = 100.1 + 150.3 = 250.4
+        assert_eq!(calculate_credits(&TokenUsage::new(1001, 501), &config), 251);
+
+        let truncating = PricingConfig {
+            round_up: false,
+            ..default_config()
+        };
+        assert_eq!(calculate_credits(&TokenUsage::new(1001, 501), &truncating), 250);
+    }
+
     #[test]
     fn test_estimate_credits() {
         let config = default_config();
@@ -175,6 +241,21 @@ mod tests {
         assert_eq!(usdc, 1_000_000);
     }
 
+    #[test]
+    fn test_max_completion_tokens() {
+        let config = default_config();
+
+        // 1000 prompt tokens cost 100, leaving 150 credits = 500 completion tokens
+        assert_eq!(max_completion_tokens(1000, 250, &config), Some(500));
+        // Prompt alone uses the whole budget
+        assert_eq!(max_completion_tokens(1000, 100, &config), None);
+        assert_eq!(max_completion_tokens(1000, 50, &config), None);
+        // The largest completion still fits once rounded up
+        let tokens = max_completion_tokens(1001, 251, &config).unwrap();
+        assert!(calculate_credits(&TokenUsage::new(1001, tokens), &config) <= 251);
+        assert!(calculate_credits(&TokenUsage::new(1001, tokens + 1), &config) > 251);
+    }
+
     #[test]
     fn test_format_usdc() {
         assert_eq!(PricingCalculator::format_usdc(1_000_000), "$1.000000");
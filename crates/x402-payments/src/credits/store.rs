@@ -1,21 +1,29 @@
 //! TEE-encrypted persistent credit store.
 
 use crate::error::PaymentError;
-use crate::types::{CreditBalance, Deposit, UsageRecord, UserId};
+use crate::notify::OperatorNotifier;
+use crate::types::{
+    BalanceDiscrepancy, BalanceTotals, Chain, CreditBalance, Deposit, DepositIntent,
+    DepositStatus, IdempotentDeposit, LinkedAddress, UsageRecord, UserId,
+};
 use aes_gcm::{
     aead::{Aead, KeyInit},
     Aes256Gcm, Key, Nonce,
 };
-use dstack_client::DstackClient;
+use chrono::{DateTime, Utc};
+use dstack_client::DstackApi;
 use rand::RngCore;
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 use std::collections::{HashMap, HashSet};
-use std::path::PathBuf;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU32, Ordering};
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::fs;
 use tokio::sync::RwLock;
-use tracing::{debug, info, warn};
+use tracing::{debug, error, info, instrument, warn};
 
 /// Key derivation path for credit store encryption.
 const KEY_DERIVATION_PATH: &str = "x402-payments/credit-store";
@@ -23,8 +31,103 @@ const KEY_DERIVATION_PATH: &str = "x402-payments/credit-store";
 /// Nonce size for AES-GCM (96 bits = 12 bytes).
 const NONCE_SIZE: usize = 12;
 
+/// Default number of retries when a write to disk fails.
+pub const DEFAULT_PERSIST_RETRIES: u32 = 2;
+
+/// Default time a deposit idempotency key is remembered.
+pub const DEFAULT_IDEMPOTENCY_KEY_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Delay before the first persist retry (doubles on each retry).
+const PERSIST_RETRY_BACKOFF: Duration = Duration::from_millis(50);
+
+/// Where the credit store's encryption key came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KeySource {
+    /// Dstack's DeriveKey endpoint (bound to the TEE's root of trust).
+    DeriveKey,
+    /// Hash of the public AppInfo, used when DeriveKey is unavailable.
+    AppInfo,
+    /// Passed in by the caller ([`CreditStore::with_key`]).
+    Provided,
+}
+
 /// Data version for schema migrations.
-const DATA_VERSION: u32 = 1;
+///
+/// - v1: processed tx hashes stored as bare strings
+/// - v2: processed tx hashes keyed on (chain, tx_hash)
+/// - v3: usage records carry the originating Signal message timestamp
+/// - v4: reserved deposit amounts (deposit intents)
+/// - v5: deposit idempotency keys scoped to their user, with creation times
+/// - v6: deposits record the token they were paid in (USDC when missing)
+/// - v7: sender addresses linked to users (none when missing)
+/// - v8: credits received by transfer tracked apart from deposits
+const DATA_VERSION: u32 = 8;
+
+/// Reserved deposit amounts add up to this many micro-USDC (just under one
+/// cent) to the requested amount, giving each user a distinct amount.
+const MAX_INTENT_OFFSET: u64 = 9_999;
+
+/// Exclusive advisory lock on a credit store's storage path.
+///
+/// The lock lives on a `<storage_path>.lock` sidecar rather than the data
+/// file itself, since saves replace the data file by renaming over it. The
+/// lock is released when the file handle is dropped.
+#[derive(Debug)]
+struct StorageLock {
+    _file: File,
+}
+
+impl StorageLock {
+    fn acquire(storage_path: &Path) -> Result<Self, PaymentError> {
+        if let Some(parent) = storage_path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        let mut lock_path = storage_path.as_os_str().to_owned();
+        lock_path.push(".lock");
+        let file = std::fs::OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(&lock_path)?;
+
+        Self::lock(&file).map_err(|e| {
+            if e.kind() == std::io::ErrorKind::WouldBlock {
+                PaymentError::Storage(format!(
+                    "Credit store {:?} is already in use by another writer; \
+                     only one CreditStore may open a storage path at a time",
+                    storage_path
+                ))
+            } else {
+                PaymentError::Storage(format!(
+                    "Failed to lock credit store {:?}: {}",
+                    storage_path, e
+                ))
+            }
+        })?;
+
+        Ok(Self { _file: file })
+    }
+
+    #[cfg(unix)]
+    fn lock(file: &File) -> std::io::Result<()> {
+        use std::os::unix::io::AsRawFd;
+
+        // SAFETY: the descriptor is owned by `file` and stays open for the call.
+        let rc = unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX | libc::LOCK_NB) };
+        if rc == 0 {
+            Ok(())
+        } else {
+            Err(std::io::Error::last_os_error())
+        }
+    }
+
+    #[cfg(not(unix))]
+    fn lock(_file: &File) -> std::io::Result<()> {
+        Ok(())
+    }
+}
 
 /// Persistent data structure for the credit store.
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -37,8 +140,18 @@ pub struct CreditStoreData {
     pub deposits: Vec<Deposit>,
     /// Usage log (for auditing).
     pub usage_log: Vec<UsageRecord>,
-    /// Processed transaction hashes (for double-spend prevention).
-    pub processed_tx_hashes: HashSet<String>,
+    /// Processed transactions keyed on (chain, tx_hash) (for double-spend prevention).
+    pub processed_tx_hashes: HashSet<(Chain, String)>,
+    /// Deposit amounts reserved for a user, awaiting their transfer.
+    #[serde(default)]
+    pub deposit_intents: Vec<DepositIntent>,
+    /// Client idempotency keys of processed deposits, keyed by
+    /// [`idempotency_scope`].
+    #[serde(default)]
+    pub idempotency_keys: HashMap<String, IdempotentDeposit>,
+    /// Sender addresses users have linked to their account.
+    #[serde(default)]
+    pub linked_addresses: Vec<LinkedAddress>,
 }
 
 impl Default for CreditStoreData {
@@ -49,27 +162,163 @@ impl Default for CreditStoreData {
             deposits: Vec::new(),
             usage_log: Vec::new(),
             processed_tx_hashes: HashSet::new(),
+            deposit_intents: Vec::new(),
+            idempotency_keys: HashMap::new(),
+            linked_addresses: Vec::new(),
+        }
+    }
+}
+
+impl CreditStoreData {
+    /// Re-key idempotency keys stored before v5, which were shared by all
+    /// users, under the user of the deposit they were used for.
+    fn scope_idempotency_keys(&mut self) {
+        let keys = std::mem::take(&mut self.idempotency_keys);
+        for (key, record) in keys {
+            if let Some(deposit) = self.deposits.iter().find(|d| d.id == record.deposit_id) {
+                self.idempotency_keys
+                    .insert(idempotency_scope(&deposit.user_id, &key), record);
+            }
+        }
+    }
+
+    /// Move credits received by transfer before v8, which were counted as
+    /// deposits, into `total_received`.
+    fn separate_received_transfers(&mut self) {
+        for usage in &self.usage_log {
+            let Some(ref to) = usage.transfer_to else {
+                continue;
+            };
+            if let Some(balance) = self.balances.get_mut(to) {
+                let moved = usage.credits_consumed.min(balance.total_deposited);
+                balance.total_deposited -= moved;
+                balance.total_received = balance.total_received.saturating_add(moved);
+            }
+        }
+    }
+}
+
+/// Where `user_id`'s idempotency `key` is stored. Keys are per user, so one
+/// user's key never collides with another's. Header values can't contain a
+/// newline, so the pair is unambiguous.
+fn idempotency_scope(user_id: &str, key: &str) -> String {
+    format!("{}\n{}", user_id, key)
+}
+
+/// Version 1 layout, before processed hashes were tagged with their chain.
+#[derive(Debug, Deserialize)]
+struct CreditStoreDataV1 {
+    balances: HashMap<UserId, CreditBalance>,
+    deposits: Vec<Deposit>,
+    usage_log: Vec<UsageRecord>,
+    processed_tx_hashes: HashSet<String>,
+}
+
+impl CreditStoreDataV1 {
+    /// Tag each processed hash with the chain recorded in the deposit log.
+    ///
+    /// A hash with no matching deposit is kept for every chain so that it
+    /// still can't be credited twice.
+    fn migrate(self) -> CreditStoreData {
+        let mut processed_tx_hashes = HashSet::new();
+
+        for tx_hash in self.processed_tx_hashes {
+            let chains: Vec<Chain> = self
+                .deposits
+                .iter()
+                .filter(|d| d.tx_hash == tx_hash)
+                .map(|d| d.chain)
+                .collect();
+
+            if chains.is_empty() {
+                warn!(
+                    "Processed tx {} has no deposit record, blocking it on all chains",
+                    tx_hash
+                );
+                for chain in [Chain::Base, Chain::Near, Chain::Solana] {
+                    processed_tx_hashes.insert((chain, tx_hash.clone()));
+                }
+            } else {
+                for chain in chains {
+                    processed_tx_hashes.insert((chain, tx_hash.clone()));
+                }
+            }
+        }
+
+        CreditStoreData {
+            version: DATA_VERSION,
+            balances: self.balances,
+            deposits: self.deposits,
+            usage_log: self.usage_log,
+            processed_tx_hashes,
+            deposit_intents: Vec::new(),
+            idempotency_keys: HashMap::new(),
+            linked_addresses: Vec::new(),
         }
     }
 }
 
+/// Just enough of the stored data to pick a migration path.
+#[derive(Debug, Deserialize)]
+struct DataVersion {
+    version: u32,
+}
+
 /// TEE-encrypted credit store.
 pub struct CreditStore {
     data: RwLock<CreditStoreData>,
-    dstack: DstackClient,
+    dstack: Box<dyn DstackApi>,
     storage_path: PathBuf,
     /// Cached encryption key.
     cached_key: RwLock<Option<[u8; 32]>>,
+    /// Retries after a failed write before a mutation is rolled back.
+    persist_retries: AtomicU32,
+    /// Held for the lifetime of the store so only one writer uses the path.
+    _lock: StorageLock,
+    /// Where the cached key came from (`None` until one is derived).
+    key_source: std::sync::RwLock<Option<KeySource>>,
+    /// Alerts the operator when the key falls back to AppInfo.
+    notifier: std::sync::RwLock<Option<Arc<OperatorNotifier>>>,
+    /// Subject mixed into key derivation, isolating this store's key.
+    key_subject: Option<String>,
+    /// How long credits last after a user's last deposit (`None` = forever).
+    credit_ttl: std::sync::RwLock<Option<Duration>>,
+    /// How long a deposit idempotency key is remembered.
+    idempotency_key_ttl: std::sync::RwLock<Duration>,
 }
 
 impl CreditStore {
     /// Create a new credit store and load existing data if available.
-    pub async fn new(dstack: DstackClient, storage_path: PathBuf) -> Result<Arc<Self>, PaymentError> {
+    ///
+    /// Fails if another `CreditStore` (in this or any other process) already
+    /// holds the storage path.
+    pub async fn new(
+        dstack: impl DstackApi + 'static,
+        storage_path: PathBuf,
+    ) -> Result<Arc<Self>, PaymentError> {
+        Self::new_with_subject(dstack, storage_path, None).await
+    }
+
+    /// Create a credit store encrypted with a key derived for `subject`
+    /// (e.g. the bot's number), so each subject's data has its own key.
+    pub async fn new_with_subject(
+        dstack: impl DstackApi + 'static,
+        storage_path: PathBuf,
+        subject: Option<String>,
+    ) -> Result<Arc<Self>, PaymentError> {
+        let lock = StorageLock::acquire(&storage_path)?;
         let store = Arc::new(Self {
             data: RwLock::new(CreditStoreData::default()),
-            dstack,
+            dstack: Box::new(dstack),
             storage_path,
             cached_key: RwLock::new(None),
+            persist_retries: AtomicU32::new(DEFAULT_PERSIST_RETRIES),
+            _lock: lock,
+            key_source: std::sync::RwLock::new(None),
+            notifier: std::sync::RwLock::new(None),
+            key_subject: subject,
+            credit_ttl: std::sync::RwLock::new(None),
+            idempotency_key_ttl: std::sync::RwLock::new(DEFAULT_IDEMPOTENCY_KEY_TTL),
         });
 
         // Load existing data if available
@@ -80,15 +329,23 @@ impl CreditStore {
 
     /// Create a credit store with a pre-derived key (for testing).
     pub async fn with_key(
-        dstack: DstackClient,
+        dstack: impl DstackApi + 'static,
         storage_path: PathBuf,
         key: [u8; 32],
     ) -> Result<Arc<Self>, PaymentError> {
+        let lock = StorageLock::acquire(&storage_path)?;
         let store = Arc::new(Self {
             data: RwLock::new(CreditStoreData::default()),
-            dstack,
+            dstack: Box::new(dstack),
             storage_path,
             cached_key: RwLock::new(Some(key)),
+            persist_retries: AtomicU32::new(DEFAULT_PERSIST_RETRIES),
+            _lock: lock,
+            key_source: std::sync::RwLock::new(Some(KeySource::Provided)),
+            notifier: std::sync::RwLock::new(None),
+            key_subject: None,
+            credit_ttl: std::sync::RwLock::new(None),
+            idempotency_key_ttl: std::sync::RwLock::new(DEFAULT_IDEMPOTENCY_KEY_TTL),
         });
 
         store.load().await?;
@@ -107,7 +364,7 @@ impl CreditStore {
         }
 
         // Try DeriveKey endpoint first
-        match self.dstack.derive_key(KEY_DERIVATION_PATH, None).await {
+        match self.dstack.derive_key(KEY_DERIVATION_PATH, self.key_subject.as_deref()).await {
             Ok(key_bytes) => {
                 if key_bytes.len() < 32 {
                     return Err(PaymentError::Encryption(format!(
@@ -120,6 +377,7 @@ impl CreditStore {
 
                 // Cache the key
                 *self.cached_key.write().await = Some(key);
+                self.set_key_source(Some(KeySource::DeriveKey));
 
                 info!("Using DeriveKey endpoint for credit store encryption");
                 return Ok(key);
@@ -144,6 +402,10 @@ impl CreditStore {
         hasher.update(compose_hash.as_bytes());
         hasher.update(app_id.as_bytes());
         hasher.update(KEY_DERIVATION_PATH.as_bytes());
+        if let Some(ref subject) = self.key_subject {
+            hasher.update(b"/");
+            hasher.update(subject.as_bytes());
+        }
         let hash = hasher.finalize();
 
         let mut key = [0u8; 32];
@@ -156,12 +418,176 @@ impl CreditStore {
             "Using AppInfo-derived key (compose_hash: {}, app_id: {})",
             compose_hash, app_id
         );
+        self.set_key_source(Some(KeySource::AppInfo));
+        self.alert_fallback_key().await;
 
         Ok(key)
     }
 
+    fn set_key_source(&self, source: Option<KeySource>) {
+        *self.key_source.write().unwrap_or_else(|e| e.into_inner()) = source;
+    }
+
+    /// Where the encryption key came from, or `None` if no key has been
+    /// needed yet (a fresh store derives it on first write).
+    pub fn key_source(&self) -> Option<KeySource> {
+        *self.key_source.read().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Whether the encryption key was derived from AppInfo because the
+    /// DeriveKey endpoint was unavailable.
+    pub fn uses_fallback_key(&self) -> bool {
+        self.key_source() == Some(KeySource::AppInfo)
+    }
+
+    /// Re-encrypt the current data under a freshly derived key.
+    ///
+    /// The existing file is first copied to `<storage_path>.pre-reseal` so
+    /// it can still be opened with the old key. Writes are blocked while
+    /// resealing; if it fails, the previous key stays in use.
+    pub async fn reseal(&self) -> Result<KeySource, PaymentError> {
+        let data = self.data.write().await;
+
+        if self.storage_path.exists() {
+            let mut backup_path = self.storage_path.as_os_str().to_owned();
+            backup_path.push(".pre-reseal");
+            fs::copy(&self.storage_path, &backup_path).await?;
+        }
+
+        let previous_key = self.cached_key.write().await.take();
+        let previous_source = self.key_source();
+        self.set_key_source(None);
+
+        if let Err(e) = self.persist_with_retry(&data).await {
+            error!("Reseal failed, keeping the previous key: {}", e);
+            *self.cached_key.write().await = previous_key;
+            self.set_key_source(previous_source);
+            return Err(e);
+        }
+
+        let source = self.key_source().unwrap_or(KeySource::DeriveKey);
+        info!(
+            "Resealed credit store under a {:?} key (was {:?})",
+            source, previous_source
+        );
+        Ok(source)
+    }
+
+    /// Alert the operator through `notifier` if the encryption key falls
+    /// back to AppInfo, including when that already happened on load.
+    pub async fn set_notifier(&self, notifier: Arc<OperatorNotifier>) {
+        *self.notifier.write().unwrap_or_else(|e| e.into_inner()) = Some(notifier);
+        if self.uses_fallback_key() {
+            self.alert_fallback_key().await;
+        }
+    }
+
+    async fn alert_fallback_key(&self) {
+        let notifier = self.notifier.read().unwrap_or_else(|e| e.into_inner()).clone();
+        if let Some(notifier) = notifier {
+            notifier
+                .notify(
+                    "key-fallback",
+                    "Credit store key was derived from AppInfo because DeriveKey is unavailable. \
+                    The key is predictable from public app metadata; check the dstack guest agent.",
+                )
+                .await;
+        }
+    }
+
+    /// Set how many times a failed write is retried before the change is
+    /// rolled back.
+    pub fn set_persist_retries(&self, retries: u32) {
+        self.persist_retries.store(retries, Ordering::Relaxed);
+    }
+
+    /// Set how long a deposit idempotency key is remembered. Older keys are
+    /// forgotten, so a retry after that is processed as a new request.
+    pub fn set_idempotency_key_ttl(&self, ttl: Duration) {
+        *self.idempotency_key_ttl.write().unwrap_or_else(|e| e.into_inner()) = ttl;
+    }
+
+    /// Earliest creation time of an idempotency key still remembered.
+    fn idempotency_cutoff(&self) -> DateTime<Utc> {
+        let ttl = *self.idempotency_key_ttl.read().unwrap_or_else(|e| e.into_inner());
+        chrono::Duration::from_std(ttl)
+            .ok()
+            .and_then(|ttl| Utc::now().checked_sub_signed(ttl))
+            .unwrap_or(DateTime::<Utc>::MIN_UTC)
+    }
+
+    /// Set how long credits last after a user's last deposit. Expired
+    /// balances read as zero and are reset the next time they're touched.
+    pub fn set_credit_ttl(&self, ttl: Option<Duration>) {
+        *self.credit_ttl.write().unwrap_or_else(|e| e.into_inner()) = ttl;
+    }
+
+    /// Persist balances reset by [`Self::expire_stale`] on a path that
+    /// otherwise wouldn't write. On failure the balances are put back so the
+    /// reset is retried on the next read.
+    async fn persist_expiry(&self, data: &mut CreditStoreData, previous: &[(&str, Option<CreditBalance>)]) {
+        if let Err(e) = self.persist_with_retry(data).await {
+            warn!("Failed to persist credit expiry: {}", e);
+            for (user_id, balance) in previous {
+                restore_balance(data, user_id.to_string(), balance.clone());
+            }
+        }
+    }
+
+    /// Forfeit `user_id`'s credits if they have outlived the credit TTL.
+    /// Returns whether the balance was reset.
+    fn expire_stale(&self, data: &mut CreditStoreData, user_id: &str) -> bool {
+        let Some(ttl) = *self.credit_ttl.read().unwrap_or_else(|e| e.into_inner()) else {
+            return false;
+        };
+        match data.balances.get_mut(user_id) {
+            Some(balance) if balance.is_expired(ttl) => {
+                info!(
+                    "Expired {} unused credits for {}",
+                    balance.credits_remaining,
+                    &user_id[..user_id.len().min(8)]
+                );
+                balance.expire();
+                true
+            }
+            _ => false,
+        }
+    }
+
     /// Save data to encrypted storage.
     pub async fn persist(&self) -> Result<(), PaymentError> {
+        let data = self.data.read().await;
+        self.write_data(&data).await
+    }
+
+    /// Save `data`, retrying transient failures with backoff.
+    async fn persist_with_retry(&self, data: &CreditStoreData) -> Result<(), PaymentError> {
+        let retries = self.persist_retries.load(Ordering::Relaxed);
+        let mut backoff = PERSIST_RETRY_BACKOFF;
+
+        let mut attempt = 0;
+
+        loop {
+            match self.write_data(data).await {
+                Ok(()) => return Ok(()),
+                Err(e) if attempt < retries => {
+                    attempt += 1;
+                    warn!(
+                        "Failed to persist credit store (attempt {}/{}): {}",
+                        attempt,
+                        retries + 1,
+                        e
+                    );
+                    tokio::time::sleep(backoff).await;
+                    backoff *= 2;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// Encrypt and atomically write `data` to disk.
+    async fn write_data(&self, data: &CreditStoreData) -> Result<(), PaymentError> {
         let key = self.derive_key().await?;
         let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key));
 
@@ -171,8 +597,7 @@ impl CreditStore {
         let nonce = Nonce::from_slice(&nonce_bytes);
 
         // Serialize data
-        let data = self.data.read().await;
-        let plaintext = serde_json::to_vec(&*data)?;
+        let plaintext = serde_json::to_vec(data)?;
 
         // Encrypt
         let ciphertext = cipher.encrypt(nonce, plaintext.as_ref())?;
@@ -229,7 +654,32 @@ impl CreditStore {
             )
         })?;
 
-        let data: CreditStoreData = serde_json::from_slice(&plaintext)?;
+        let stored_version = serde_json::from_slice::<DataVersion>(&plaintext)?.version;
+        let data = if stored_version < 2 {
+            let data = serde_json::from_slice::<CreditStoreDataV1>(&plaintext)?.migrate();
+            info!(
+                "Migrated credit store from v{} to v{}",
+                stored_version, DATA_VERSION
+            );
+            data
+        } else {
+            // Later versions only add fields that default when missing
+            let mut data = serde_json::from_slice::<CreditStoreData>(&plaintext)?;
+            if stored_version < 5 {
+                data.scope_idempotency_keys();
+            }
+            if stored_version < 8 {
+                data.separate_received_transfers();
+            }
+            if stored_version < DATA_VERSION {
+                info!(
+                    "Migrated credit store from v{} to v{}",
+                    stored_version, DATA_VERSION
+                );
+                data.version = DATA_VERSION;
+            }
+            data
+        };
 
         info!(
             "Loaded credit store: {} balances, {} deposits",
@@ -243,12 +693,36 @@ impl CreditStore {
     }
 
     /// Get credit balance for a user.
+    ///
+    /// Credits past the credit TTL read as zero, and the balance is reset.
     pub async fn get_balance(&self, user_id: &str) -> CreditBalance {
-        let data = self.data.read().await;
-        data.balances
-            .get(user_id)
-            .cloned()
-            .unwrap_or_else(|| CreditBalance::new(user_id.to_string()))
+        let ttl = *self.credit_ttl.read().unwrap_or_else(|e| e.into_inner());
+        {
+            let data = self.data.read().await;
+            let balance = data.balances.get(user_id);
+            if !matches!((balance, ttl), (Some(balance), Some(ttl)) if balance.is_expired(ttl)) {
+                return balance
+                    .cloned()
+                    .unwrap_or_else(|| CreditBalance::new(user_id.to_string()));
+            }
+        }
+
+        let mut data = self.data.write().await;
+        let previous_balance = data.balances.get(user_id).cloned();
+        if !self.expire_stale(&mut data, user_id) {
+            // Another caller got here first
+            return previous_balance.unwrap_or_else(|| CreditBalance::new(user_id.to_string()));
+        }
+        let balance = data.balances[user_id].clone();
+
+        // The credits are gone either way; a failed write just means the
+        // reset is retried on the next read
+        if let Err(e) = self.persist_with_retry(&data).await {
+            warn!("Failed to persist credit expiry for {}: {}", &user_id[..user_id.len().min(8)], e);
+            restore_balance(&mut data, user_id.to_string(), previous_balance);
+        }
+
+        balance
     }
 
     /// Check if user has sufficient credits.
@@ -258,83 +732,390 @@ impl CreditStore {
     }
 
     /// Add credits from a deposit.
+    ///
+    /// If the change can't be written to disk, it is rolled back and an
+    /// error returned, so the deposit can be retried.
     pub async fn add_credits(
         &self,
         deposit: Deposit,
     ) -> Result<CreditBalance, PaymentError> {
-        let balance_clone = {
-            let mut data = self.data.write().await;
+        self.add_credits_with_key(deposit, None).await
+    }
+
+    /// Add credits from a deposit, recording the client's idempotency key
+    /// with it so a retried request can be answered with the original result.
+    pub async fn add_credits_with_key(
+        &self,
+        deposit: Deposit,
+        idempotency_key: Option<&str>,
+    ) -> Result<CreditBalance, PaymentError> {
+        // Hold the lock through persisting so a failed write can be undone
+        let mut data = self.data.write().await;
 
-            // Check for double-spend
-            if data.processed_tx_hashes.contains(&deposit.tx_hash) {
-                return Err(PaymentError::DuplicateTransaction(deposit.tx_hash.clone()));
+        // Check for double-spend
+        let key = (deposit.chain, deposit.tx_hash.clone());
+        if data.processed_tx_hashes.contains(&key) {
+            return Err(PaymentError::DuplicateTransaction(deposit.tx_hash.clone()));
+        }
+        let scoped_key = idempotency_key.map(|key| idempotency_scope(&deposit.user_id, key));
+        if let Some(ref scoped_key) = scoped_key {
+            let cutoff = self.idempotency_cutoff();
+            data.idempotency_keys.retain(|_, record| record.created_at >= cutoff);
+            if data.idempotency_keys.contains_key(scoped_key) {
+                return Err(PaymentError::IdempotencyKeyReused(
+                    idempotency_key.unwrap_or_default().to_string(),
+                ));
             }
+        }
 
-            // Record deposit first to avoid borrow issues
-            data.processed_tx_hashes.insert(deposit.tx_hash.clone());
-            let credits_granted = deposit.credits_granted;
-            let user_id = deposit.user_id.clone();
-            data.deposits.push(deposit);
+        let user_id = deposit.user_id.clone();
+        let previous_balance = data.balances.get(&user_id).cloned();
+        let previous_intents = data.deposit_intents.clone();
 
-            // Get or create balance and add credits
-            let balance = data
-                .balances
-                .entry(user_id.clone())
-                .or_insert_with(|| CreditBalance::new(user_id));
+        // A top-up doesn't revive credits that have already expired
+        self.expire_stale(&mut data, &user_id);
 
-            balance.add_credits(credits_granted);
-            balance.clone()
-        };
+        // The deposit fulfils the user's reservation of this amount
+        data.deposit_intents.retain(|intent| {
+            !(intent.user_id == user_id
+                && intent.chain == deposit.chain
+                && intent.amount_usdc == deposit.amount_usdc)
+        });
+
+        // Record deposit first to avoid borrow issues
+        data.processed_tx_hashes.insert(key.clone());
+        let credits_granted = deposit.credits_granted;
+        let deposit_id = deposit.id.clone();
+        data.deposits.push(deposit);
+
+        // Get or create balance and add credits
+        let balance = data
+            .balances
+            .entry(user_id.clone())
+            .or_insert_with(|| CreditBalance::new(user_id.clone()));
+
+        balance.add_credits(credits_granted);
+        let balance_clone = balance.clone();
+
+        if let Some(ref scoped_key) = scoped_key {
+            data.idempotency_keys.insert(
+                scoped_key.clone(),
+                IdempotentDeposit {
+                    deposit_id,
+                    new_balance: balance_clone.credits_remaining,
+                    created_at: Utc::now(),
+                },
+            );
+        }
 
-        // Persist (lock is released)
-        self.persist().await?;
+        if let Err(e) = self.persist_with_retry(&data).await {
+            error!("Rolling back deposit {} after persist failure: {}", key.1, e);
+            if let Some(ref scoped_key) = scoped_key {
+                data.idempotency_keys.remove(scoped_key);
+            }
+            data.processed_tx_hashes.remove(&key);
+            data.deposits.pop();
+            data.deposit_intents = previous_intents;
+            restore_balance(&mut data, user_id, previous_balance);
+            return Err(e);
+        }
 
         Ok(balance_clone)
     }
 
+    /// Reserve a distinct deposit amount on `chain` for `user_id`.
+    ///
+    /// The amount is `amount_usdc` plus the smallest offset (1 to 9,999
+    /// micro-USDC) not already reserved by someone else on that chain, so a
+    /// transfer of exactly that amount identifies the depositor. Replaces the
+    /// user's earlier reservation on the chain.
+    pub async fn create_deposit_intent(
+        &self,
+        user_id: &str,
+        chain: Chain,
+        amount_usdc: u64,
+        ttl: Duration,
+    ) -> Result<DepositIntent, PaymentError> {
+        let mut data = self.data.write().await;
+        let previous_intents = data.deposit_intents.clone();
+
+        data.deposit_intents
+            .retain(|intent| !intent.is_expired() && !(intent.user_id == user_id && intent.chain == chain));
+
+        let taken: HashSet<u64> = data
+            .deposit_intents
+            .iter()
+            .filter(|intent| intent.chain == chain)
+            .map(|intent| intent.amount_usdc)
+            .collect();
+        let amount = (1..=MAX_INTENT_OFFSET)
+            .filter_map(|offset| amount_usdc.checked_add(offset))
+            .find(|amount| !taken.contains(amount))
+            .ok_or_else(|| {
+                PaymentError::Internal(format!("No unreserved {} deposit amount near {}", chain, amount_usdc))
+            })?;
+
+        let now = Utc::now();
+        let intent = DepositIntent {
+            user_id: user_id.to_string(),
+            chain,
+            amount_usdc: amount,
+            created_at: now,
+            expires_at: chrono::Duration::from_std(ttl)
+                .ok()
+                .and_then(|ttl| now.checked_add_signed(ttl))
+                .unwrap_or(DateTime::<Utc>::MAX_UTC),
+        };
+        data.deposit_intents.push(intent.clone());
+
+        if let Err(e) = self.persist_with_retry(&data).await {
+            error!("Rolling back deposit intent after persist failure: {}", e);
+            data.deposit_intents = previous_intents;
+            return Err(e);
+        }
+
+        debug!("Reserved {} deposit amount {} for {}", chain, amount, &user_id[..user_id.len().min(8)]);
+        Ok(intent)
+    }
+
+    /// The user who reserved exactly `amount_usdc` on `chain`, if anyone has.
+    pub async fn deposit_intent_owner(&self, chain: Chain, amount_usdc: u64) -> Option<UserId> {
+        let data = self.data.read().await;
+        data.deposit_intents
+            .iter()
+            .find(|intent| intent.chain == chain && intent.amount_usdc == amount_usdc && !intent.is_expired())
+            .map(|intent| intent.user_id.clone())
+    }
+
+    /// Link a sender address on `chain` to `user_id`.
+    ///
+    /// An address belongs to at most one user; linking one already linked to
+    /// someone else fails. Linking it again for the same user is a no-op.
+    pub async fn link_address(
+        &self,
+        user_id: &str,
+        chain: Chain,
+        address: &str,
+    ) -> Result<LinkedAddress, PaymentError> {
+        let address = LinkedAddress::normalize(chain, address);
+        let mut data = self.data.write().await;
+
+        if let Some(existing) = data
+            .linked_addresses
+            .iter()
+            .find(|linked| linked.chain == chain && linked.address == address)
+        {
+            if existing.user_id != user_id {
+                return Err(PaymentError::AddressAlreadyLinked(address));
+            }
+            return Ok(existing.clone());
+        }
+
+        let linked = LinkedAddress {
+            user_id: user_id.to_string(),
+            chain,
+            address,
+            linked_at: Utc::now(),
+        };
+        data.linked_addresses.push(linked.clone());
+
+        if let Err(e) = self.persist_with_retry(&data).await {
+            error!("Rolling back linked address after persist failure: {}", e);
+            data.linked_addresses.pop();
+            return Err(e);
+        }
+
+        debug!("Linked {} address {} to {}", chain, linked.address, &user_id[..user_id.len().min(8)]);
+        Ok(linked)
+    }
+
+    /// Unlink a sender address on `chain` from `user_id`.
+    ///
+    /// Fails unless `user_id` linked the address.
+    pub async fn unlink_address(
+        &self,
+        user_id: &str,
+        chain: Chain,
+        address: &str,
+    ) -> Result<LinkedAddress, PaymentError> {
+        let address = LinkedAddress::normalize(chain, address);
+        let mut data = self.data.write().await;
+
+        let Some(index) = data.linked_addresses.iter().position(|linked| {
+            linked.chain == chain && linked.address == address && linked.user_id == user_id
+        }) else {
+            return Err(PaymentError::AddressNotLinked(address));
+        };
+        let unlinked = data.linked_addresses.remove(index);
+
+        if let Err(e) = self.persist_with_retry(&data).await {
+            error!("Rolling back unlinked address after persist failure: {}", e);
+            data.linked_addresses.insert(index, unlinked);
+            return Err(e);
+        }
+
+        debug!("Unlinked {} address {} from {}", chain, unlinked.address, &user_id[..user_id.len().min(8)]);
+        Ok(unlinked)
+    }
+
+    /// The user who linked `address` on `chain`, if anyone has.
+    pub async fn linked_address_owner(&self, chain: Chain, address: &str) -> Option<UserId> {
+        let address = LinkedAddress::normalize(chain, address);
+        let data = self.data.read().await;
+        data.linked_addresses
+            .iter()
+            .find(|linked| linked.chain == chain && linked.address == address)
+            .map(|linked| linked.user_id.clone())
+    }
+
     /// Deduct credits for usage.
+    #[instrument(skip(self, user_id, usage))]
     pub async fn deduct_credits(
         &self,
         user_id: &str,
         credits: u64,
         usage: UsageRecord,
     ) -> Result<CreditBalance, PaymentError> {
-        let balance_clone = {
-            let mut data = self.data.write().await;
+        // Hold the lock through persisting so a failed write can be undone
+        let mut data = self.data.write().await;
+        let previous_balance = data.balances.get(user_id).cloned();
+
+        // First check if user exists and has enough unexpired credits
+        let expired = self.expire_stale(&mut data, user_id);
+        let available = data
+            .balances
+            .get(user_id)
+            .map(|b| b.credits_remaining)
+            .unwrap_or(0);
 
-            // First check if user exists and has enough credits
-            let available = data
-                .balances
-                .get(user_id)
-                .map(|b| b.credits_remaining)
-                .unwrap_or(0);
-
-            if available < credits {
-                return Err(PaymentError::InsufficientCredits {
-                    required: credits,
-                    available,
-                });
+        if available < credits {
+            if expired {
+                self.persist_expiry(&mut data, &[(user_id, previous_balance)]).await;
             }
+            return Err(PaymentError::InsufficientCredits {
+                required: credits,
+                available,
+            });
+        }
 
-            // Now get mutable reference, record usage first
-            data.usage_log.push(usage);
-
-            // Then deduct credits
-            let balance = data
-                .balances
-                .get_mut(user_id)
-                .ok_or_else(|| PaymentError::UserNotFound(user_id.to_string()))?;
+        let balance = data
+            .balances
+            .get_mut(user_id)
+            .ok_or_else(|| PaymentError::UserNotFound(user_id.to_string()))?;
 
-            balance.deduct_credits(credits);
-            balance.clone()
-        };
+        balance.deduct_credits(credits);
+        let balance_clone = balance.clone();
+        data.usage_log.push(usage);
 
-        // Persist (lock is released)
-        self.persist().await?;
+        if let Err(e) = self.persist_with_retry(&data).await {
+            error!("Rolling back charge for {} after persist failure: {}", user_id, e);
+            data.usage_log.pop();
+            restore_balance(&mut data, user_id.to_string(), previous_balance);
+            return Err(e);
+        }
 
         Ok(balance_clone)
     }
 
+    /// Claw back `credits` granted to `user_id` by mistake, logging `reason`
+    /// with the refund in the usage log.
+    ///
+    /// Fails with `InsufficientCredits` if the user no longer has them.
+    pub async fn refund_credits(
+        &self,
+        user_id: &str,
+        credits: u64,
+        reason: &str,
+    ) -> Result<CreditBalance, PaymentError> {
+        let balance = self
+            .deduct_credits(user_id, credits, UsageRecord::refund(user_id.to_string(), credits, reason))
+            .await?;
+        warn!("Refunded {} credits from {}: {}", credits, &user_id[..user_id.len().min(8)], reason);
+        Ok(balance)
+    }
+
+    /// Move `credits` from one user's balance to another's.
+    ///
+    /// Both balances change under one write lock and are persisted together
+    /// with a transfer record in the sender's usage log. Fails with
+    /// `InsufficientCredits` if the sender can't cover it. Returns the
+    /// sender's and recipient's new balances.
+    pub async fn transfer_credits(
+        &self,
+        from: &str,
+        to: &str,
+        credits: u64,
+    ) -> Result<(CreditBalance, CreditBalance), PaymentError> {
+        if from == to {
+            return Err(PaymentError::InvalidPayload("Can't transfer credits to the same user".to_string()));
+        }
+
+        let mut data = self.data.write().await;
+        let previous_from = data.balances.get(from).cloned();
+        let previous_to = data.balances.get(to).cloned();
+
+        let from_expired = self.expire_stale(&mut data, from);
+        let to_expired = self.expire_stale(&mut data, to);
+        let available = data.balances.get(from).map(|b| b.credits_remaining).unwrap_or(0);
+        if available < credits {
+            if from_expired || to_expired {
+                self.persist_expiry(&mut data, &[(from, previous_from), (to, previous_to)]).await;
+            }
+            return Err(PaymentError::InsufficientCredits {
+                required: credits,
+                available,
+            });
+        }
+
+        let sender = data
+            .balances
+            .get_mut(from)
+            .ok_or_else(|| PaymentError::UserNotFound(from.to_string()))?;
+        sender.deduct_credits(credits);
+        let sender = sender.clone();
+
+        let recipient = data
+            .balances
+            .entry(to.to_string())
+            .or_insert_with(|| CreditBalance::new(to.to_string()));
+        recipient.receive_credits(credits);
+        let recipient = recipient.clone();
+
+        data.usage_log
+            .push(UsageRecord::transfer(from.to_string(), to.to_string(), credits));
+
+        if let Err(e) = self.persist_with_retry(&data).await {
+            error!("Rolling back credit transfer after persist failure: {}", e);
+            data.usage_log.pop();
+            restore_balance(&mut data, from.to_string(), previous_from);
+            restore_balance(&mut data, to.to_string(), previous_to);
+            return Err(e);
+        }
+
+        info!(
+            "Transferred {} credits from {} to {}",
+            credits,
+            &from[..from.len().min(8)],
+            &to[..to.len().min(8)]
+        );
+        Ok((sender, recipient))
+    }
+
+    /// The deposit `user_id` first made with `idempotency_key` and the
+    /// balance it left, if they used the key within the idempotency TTL.
+    pub async fn idempotent_deposit(&self, user_id: &str, idempotency_key: &str) -> Option<(Deposit, u64)> {
+        let cutoff = self.idempotency_cutoff();
+        let data = self.data.read().await;
+        let record = data
+            .idempotency_keys
+            .get(&idempotency_scope(user_id, idempotency_key))
+            .filter(|record| record.created_at >= cutoff)?;
+        data.deposits
+            .iter()
+            .find(|d| d.id == record.deposit_id)
+            .map(|d| (d.clone(), record.new_balance))
+    }
+
     /// Get deposits for a user.
     pub async fn get_deposits(&self, user_id: &str) -> Vec<Deposit> {
         let data = self.data.read().await;
@@ -355,10 +1136,125 @@ impl CreditStore {
             .collect()
     }
 
-    /// Check if a transaction has been processed.
-    pub async fn is_tx_processed(&self, tx_hash: &str) -> bool {
+    /// One page of a user's deposits made at or after `since`, along with
+    /// how many there are in total. Only the page is cloned.
+    pub async fn get_deposits_page(
+        &self,
+        user_id: &str,
+        since: Option<DateTime<Utc>>,
+        offset: usize,
+        limit: usize,
+    ) -> (Vec<Deposit>, usize) {
+        let data = self.data.read().await;
+        page_of(
+            data.deposits
+                .iter()
+                .filter(|d| d.user_id == user_id && since.is_none_or(|since| d.created_at >= since)),
+            offset,
+            limit,
+        )
+    }
+
+    /// One page of a user's usage records from `since` on, along with how
+    /// many there are in total. Only the page is cloned.
+    pub async fn get_usage_page(
+        &self,
+        user_id: &str,
+        since: Option<DateTime<Utc>>,
+        offset: usize,
+        limit: usize,
+    ) -> (Vec<UsageRecord>, usize) {
+        let data = self.data.read().await;
+        page_of(
+            data.usage_log
+                .iter()
+                .filter(|u| u.user_id == user_id && since.is_none_or(|since| u.timestamp >= since)),
+            offset,
+            limit,
+        )
+    }
+
+    /// Check if a transaction has been processed on the given chain.
+    pub async fn is_tx_processed(&self, chain: Chain, tx_hash: &str) -> bool {
         let data = self.data.read().await;
-        data.processed_tx_hashes.contains(tx_hash)
+        data.processed_tx_hashes.contains(&(chain, tx_hash.to_string()))
+    }
+
+    /// Compare every balance against the deposit and usage logs.
+    ///
+    /// The logs are the source of truth: each user's totals are recomputed
+    /// from their non-failed deposits, usage records and transfers received,
+    /// less any credits recorded as expired. With `apply`, the recomputed
+    /// totals replace the stored ones and are persisted (rolled back if the
+    /// write fails). Returns the users whose balances differed.
+    pub async fn reconcile(&self, apply: bool) -> Result<Vec<BalanceDiscrepancy>, PaymentError> {
+        let mut data = self.data.write().await;
+
+        let mut expected: HashMap<UserId, BalanceTotals> = data
+            .balances
+            .keys()
+            .map(|user_id| (user_id.clone(), BalanceTotals::default()))
+            .collect();
+        for deposit in data.deposits.iter().filter(|d| d.status != DepositStatus::Failed) {
+            let totals = expected.entry(deposit.user_id.clone()).or_default();
+            totals.total_deposited = totals.total_deposited.saturating_add(deposit.credits_granted);
+        }
+        for usage in &data.usage_log {
+            let totals = expected.entry(usage.user_id.clone()).or_default();
+            totals.total_consumed = totals.total_consumed.saturating_add(usage.credits_consumed);
+            if let Some(ref to) = usage.transfer_to {
+                let totals = expected.entry(to.clone()).or_default();
+                totals.total_received = totals.total_received.saturating_add(usage.credits_consumed);
+            }
+        }
+
+        let mut discrepancies: Vec<BalanceDiscrepancy> = expected
+            .into_iter()
+            .filter_map(|(user_id, mut totals)| {
+                let expired = data.balances.get(&user_id).map_or(0, |b| b.total_expired);
+                totals.credits_remaining = totals
+                    .total_deposited
+                    .saturating_add(totals.total_received)
+                    .saturating_sub(totals.total_consumed)
+                    .saturating_sub(expired);
+                let stored = data
+                    .balances
+                    .get(&user_id)
+                    .map(BalanceTotals::from)
+                    .unwrap_or_default();
+                (stored != totals).then_some(BalanceDiscrepancy {
+                    user_id,
+                    stored,
+                    expected: totals,
+                })
+            })
+            .collect();
+        discrepancies.sort_by(|a, b| a.user_id.cmp(&b.user_id));
+
+        if !apply || discrepancies.is_empty() {
+            return Ok(discrepancies);
+        }
+
+        let previous_balances = data.balances.clone();
+        for discrepancy in &discrepancies {
+            let balance = data
+                .balances
+                .entry(discrepancy.user_id.clone())
+                .or_insert_with(|| CreditBalance::new(discrepancy.user_id.clone()));
+            balance.credits_remaining = discrepancy.expected.credits_remaining;
+            balance.total_deposited = discrepancy.expected.total_deposited;
+            balance.total_consumed = discrepancy.expected.total_consumed;
+            balance.total_received = discrepancy.expected.total_received;
+        }
+
+        if let Err(e) = self.persist_with_retry(&data).await {
+            error!("Rolling back balance reconciliation after persist failure: {}", e);
+            data.balances = previous_balances;
+            return Err(e);
+        }
+
+        warn!("Reconciled {} balances from the deposit and usage logs", discrepancies.len());
+        Ok(discrepancies)
     }
 
     /// Get summary statistics.
@@ -384,10 +1280,36 @@ pub struct CreditStoreStats {
     pub total_credits_consumed: u64,
 }
 
+/// Clone `limit` items after skipping `offset`, and count all of them.
+fn page_of<'a, T: Clone + 'a>(items: impl Iterator<Item = &'a T>, offset: usize, limit: usize) -> (Vec<T>, usize) {
+    let mut page = Vec::new();
+    let mut total = 0;
+    for item in items {
+        if total >= offset && page.len() < limit {
+            page.push(item.clone());
+        }
+        total += 1;
+    }
+    (page, total)
+}
+
+/// Put a user's balance back to what it was before a rolled-back change.
+fn restore_balance(data: &mut CreditStoreData, user_id: UserId, previous: Option<CreditBalance>) {
+    match previous {
+        Some(balance) => {
+            data.balances.insert(user_id, balance);
+        }
+        None => {
+            data.balances.remove(&user_id);
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::types::Chain;
+    use crate::notify::tests::RecordingChannel;
+    use dstack_client::{AppInfo, DstackError, MockDstackClient, Quote, TcbInfo};
     use tempfile::TempDir;
 
     fn create_test_key() -> [u8; 32] {
@@ -397,10 +1319,7 @@ mod tests {
     async fn create_test_store() -> (Arc<CreditStore>, TempDir) {
         let temp_dir = TempDir::new().unwrap();
         let storage_path = temp_dir.path().join("credits.enc");
-        // Use a non-existent socket path for testing (won't be used with pre-derived key)
-        let dstack = DstackClient::new("/var/run/dstack.sock");
-
-        let store = CreditStore::with_key(dstack, storage_path, create_test_key())
+        let store = CreditStore::new(MockDstackClient::new(), storage_path)
             .await
             .unwrap();
 
@@ -465,25 +1384,124 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn test_insufficient_credits() {
+    async fn test_reconcile_balances() {
         let (store, _dir) = create_test_store().await;
+        let user = "+14155551234";
 
-        // Add some credits
         let deposit = Deposit::new_pending(
-            "+14155551234".to_string(),
+            user.to_string(),
             Chain::Base,
             "0x123abc".to_string(),
-            100,
-            100,
+            1_000_000,
+            1_000_000,
         );
         store.add_credits(deposit).await.unwrap();
+        let usage = UsageRecord::new(user.to_string(), user.to_string(), 1000, 500, 500);
+        store.deduct_credits(user, 500, usage).await.unwrap();
 
-        // Try to deduct more than available
-        let usage = UsageRecord::new(
-            "+14155551234".to_string(),
-            "+14155551234".to_string(),
-            1000,
-            500,
+        assert!(store.reconcile(false).await.unwrap().is_empty());
+
+        // Drift the stored balance away from the logs
+        store.data.write().await.balances.get_mut(user).unwrap().credits_remaining = 42;
+
+        let report = store.reconcile(false).await.unwrap();
+        assert_eq!(report.len(), 1);
+        assert_eq!(report[0].user_id, user);
+        assert_eq!(report[0].stored.credits_remaining, 42);
+        assert_eq!(report[0].expected.credits_remaining, 999_500);
+        assert_eq!(store.get_balance(user).await.credits_remaining, 42, "dry run changes nothing");
+
+        assert_eq!(store.reconcile(true).await.unwrap().len(), 1);
+        assert_eq!(store.get_balance(user).await.credits_remaining, 999_500);
+        assert!(store.reconcile(false).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_deposit_intents_reserve_distinct_amounts() {
+        let (store, _dir) = create_test_store().await;
+        let ttl = Duration::from_secs(3600);
+
+        let alice = store.create_deposit_intent("+1111", Chain::Base, 5_000_000, ttl).await.unwrap();
+        let bob = store.create_deposit_intent("+2222", Chain::Base, 5_000_000, ttl).await.unwrap();
+        let other_chain = store.create_deposit_intent("+2222", Chain::Solana, 5_000_000, ttl).await.unwrap();
+
+        assert_eq!(alice.amount_usdc, 5_000_001);
+        assert_eq!(bob.amount_usdc, 5_000_002);
+        assert_eq!(other_chain.amount_usdc, 5_000_001);
+        assert_eq!(store.deposit_intent_owner(Chain::Base, 5_000_001).await.as_deref(), Some("+1111"));
+        assert_eq!(store.deposit_intent_owner(Chain::Base, 5_000_000).await, None);
+
+        // The matching deposit fulfils the reservation
+        let deposit = Deposit::new_pending("+1111".to_string(), Chain::Base, "0xabc".to_string(), 5_000_001, 5_000_001);
+        store.add_credits(deposit).await.unwrap();
+        assert_eq!(store.deposit_intent_owner(Chain::Base, 5_000_001).await, None);
+        assert_eq!(store.deposit_intent_owner(Chain::Base, 5_000_002).await.as_deref(), Some("+2222"));
+
+        // Expired reservations don't count and free their amount
+        let expired = store.create_deposit_intent("+3333", Chain::Base, 5_000_000, Duration::ZERO).await.unwrap();
+        assert_eq!(store.deposit_intent_owner(Chain::Base, expired.amount_usdc).await, None);
+    }
+
+    #[tokio::test]
+    async fn test_deposit_intent_replaces_users_previous_one() {
+        let (store, _dir) = create_test_store().await;
+        let ttl = Duration::from_secs(3600);
+
+        for _ in 0..5 {
+            store.create_deposit_intent("+1111", Chain::Base, 5_000_000, ttl).await.unwrap();
+        }
+        let latest = store.create_deposit_intent("+1111", Chain::Base, 7_000_000, ttl).await.unwrap();
+
+        let data = store.data.read().await;
+        assert_eq!(data.deposit_intents.len(), 1);
+        assert_eq!(data.deposit_intents[0].amount_usdc, latest.amount_usdc);
+    }
+
+    #[tokio::test]
+    async fn test_linked_addresses() {
+        let (store, _dir) = create_test_store().await;
+        let address = "0xAbCd000000000000000000000000000000000001";
+
+        let linked = store.link_address("+1111", Chain::Base, address).await.unwrap();
+        assert_eq!(linked.address, address.to_lowercase());
+        // Relinking is a no-op, and Base addresses match regardless of case
+        store.link_address("+1111", Chain::Base, &address.to_lowercase()).await.unwrap();
+        assert_eq!(store.linked_address_owner(Chain::Base, address).await.as_deref(), Some("+1111"));
+        assert_eq!(store.linked_address_owner(Chain::Solana, address).await, None);
+
+        let err = store.link_address("+2222", Chain::Base, address).await.unwrap_err();
+        assert!(matches!(err, PaymentError::AddressAlreadyLinked(_)));
+        assert_eq!(store.linked_address_owner(Chain::Base, address).await.as_deref(), Some("+1111"));
+
+        // Only the owner can unlink, after which anyone may link it
+        let err = store.unlink_address("+2222", Chain::Base, address).await.unwrap_err();
+        assert!(matches!(err, PaymentError::AddressNotLinked(_)));
+        store.unlink_address("+1111", Chain::Base, address).await.unwrap();
+        assert_eq!(store.linked_address_owner(Chain::Base, address).await, None);
+        store.link_address("+2222", Chain::Base, address).await.unwrap();
+        assert_eq!(store.linked_address_owner(Chain::Base, address).await.as_deref(), Some("+2222"));
+    }
+
+    #[tokio::test]
+    async fn test_insufficient_credits() {
+        let (store, _dir) = create_test_store().await;
+
+        // Add some credits
+        let deposit = Deposit::new_pending(
+            "+14155551234".to_string(),
+            Chain::Base,
+            "0x123abc".to_string(),
+            100,
+            100,
+        );
+        store.add_credits(deposit).await.unwrap();
+
+        // Try to deduct more than available
+        let usage = UsageRecord::new(
+            "+14155551234".to_string(),
+            "+14155551234".to_string(),
+            1000,
+            500,
             200,
         );
 
@@ -495,6 +1513,55 @@ mod tests {
         ));
     }
 
+    #[tokio::test]
+    async fn test_persist_failure_rolls_back() {
+        let temp_dir = TempDir::new().unwrap();
+        let data_dir = temp_dir.path().join("data");
+        let store = CreditStore::new(MockDstackClient::new(), data_dir.join("credits.enc"))
+            .await
+            .unwrap();
+        store.set_persist_retries(1);
+
+        let deposit = Deposit::new_pending(
+            "+14155551234".to_string(),
+            Chain::Base,
+            "0x123abc".to_string(),
+            1_000_000,
+            1_000_000,
+        );
+        store.add_credits(deposit).await.unwrap();
+
+        // Replace the data directory with a file so every write fails
+        std::fs::remove_dir_all(&data_dir).unwrap();
+        std::fs::write(&data_dir, b"not a directory").unwrap();
+
+        let usage = UsageRecord::new(
+            "+14155551234".to_string(),
+            "+14155551234".to_string(),
+            1000,
+            500,
+            500,
+        );
+        assert!(store.deduct_credits("+14155551234", 500, usage).await.is_err());
+
+        let deposit = Deposit::new_pending(
+            "+14155551234".to_string(),
+            Chain::Base,
+            "0x456def".to_string(),
+            2_000_000,
+            2_000_000,
+        );
+        assert!(store.add_credits(deposit).await.is_err());
+
+        // Neither change stuck, so both can be retried
+        let balance = store.get_balance("+14155551234").await;
+        assert_eq!(balance.credits_remaining, 1_000_000);
+        assert_eq!(balance.total_consumed, 0);
+        assert!(store.get_usage("+14155551234").await.is_empty());
+        assert_eq!(store.get_deposits("+14155551234").await.len(), 1);
+        assert!(!store.is_tx_processed(Chain::Base, "0x456def").await);
+    }
+
     #[tokio::test]
     async fn test_double_spend_prevention() {
         let (store, _dir) = create_test_store().await;
@@ -531,12 +1598,10 @@ mod tests {
     async fn test_persistence() {
         let temp_dir = TempDir::new().unwrap();
         let storage_path = temp_dir.path().join("credits.enc");
-        let key = create_test_key();
 
         // Create store and add data
         {
-            let dstack = DstackClient::new("/var/run/dstack.sock");
-            let store = CreditStore::with_key(dstack, storage_path.clone(), key)
+            let store = CreditStore::new(MockDstackClient::new(), storage_path.clone())
                 .await
                 .unwrap();
 
@@ -552,8 +1617,7 @@ mod tests {
 
         // Create new store instance and verify data loaded
         {
-            let dstack = DstackClient::new("/var/run/dstack.sock");
-            let store = CreditStore::with_key(dstack, storage_path, key)
+            let store = CreditStore::new(MockDstackClient::new(), storage_path)
                 .await
                 .unwrap();
 
@@ -562,6 +1626,113 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn test_signing_subject_isolates_store_key() {
+        let temp_dir = TempDir::new().unwrap();
+        let storage_path = temp_dir.path().join("credits.enc");
+        let subject = Some("+15550001111".to_string());
+
+        {
+            let store =
+                CreditStore::new_with_subject(MockDstackClient::new(), storage_path.clone(), subject.clone())
+                    .await
+                    .unwrap();
+            let deposit = Deposit::new_pending(
+                "+14155551234".to_string(),
+                Chain::Base,
+                "0x123abc".to_string(),
+                1_000_000,
+                1_000_000,
+            );
+            store.add_credits(deposit).await.unwrap();
+        }
+
+        // The shared key can't read a subject's data
+        assert!(CreditStore::new(MockDstackClient::new(), storage_path.clone()).await.is_err());
+
+        let store = CreditStore::new_with_subject(MockDstackClient::new(), storage_path, subject)
+            .await
+            .unwrap();
+        assert_eq!(store.get_balance("+14155551234").await.credits_remaining, 1_000_000);
+    }
+
+    #[tokio::test]
+    async fn test_storage_path_is_locked() {
+        let temp_dir = TempDir::new().unwrap();
+        let storage_path = temp_dir.path().join("credits.enc");
+
+        let store = CreditStore::new(MockDstackClient::new(), storage_path.clone())
+            .await
+            .unwrap();
+
+        let err = CreditStore::new(MockDstackClient::new(), storage_path.clone())
+            .await
+            .err()
+            .expect("second writer should be rejected");
+        assert!(matches!(err, PaymentError::Storage(ref msg) if msg.contains("already in use")));
+
+        // Dropping the store releases the lock.
+        drop(store);
+        CreditStore::new(MockDstackClient::new(), storage_path)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_persistence_with_pre_derived_key() {
+        let temp_dir = TempDir::new().unwrap();
+        let storage_path = temp_dir.path().join("credits.enc");
+        let key = create_test_key();
+
+        {
+            let store =
+                CreditStore::with_key(MockDstackClient::not_in_tee(), storage_path.clone(), key)
+                    .await
+                    .unwrap();
+
+            let deposit = Deposit::new_pending(
+                "+14155551234".to_string(),
+                Chain::Base,
+                "0x123abc".to_string(),
+                1_000_000,
+                1_000_000,
+            );
+            store.add_credits(deposit).await.unwrap();
+        }
+
+        let store = CreditStore::with_key(MockDstackClient::not_in_tee(), storage_path, key)
+            .await
+            .unwrap();
+        assert_eq!(store.get_balance("+14155551234").await.credits_remaining, 1_000_000);
+    }
+
+    #[tokio::test]
+    async fn test_load_fails_with_different_tee_key() {
+        let temp_dir = TempDir::new().unwrap();
+        let storage_path = temp_dir.path().join("credits.enc");
+
+        {
+            let store = CreditStore::new(MockDstackClient::new(), storage_path.clone())
+                .await
+                .unwrap();
+
+            let deposit = Deposit::new_pending(
+                "+14155551234".to_string(),
+                Chain::Base,
+                "0x123abc".to_string(),
+                1_000_000,
+                1_000_000,
+            );
+            store.add_credits(deposit).await.unwrap();
+        }
+
+        // A different deployment derives a different key and cannot decrypt
+        let result =
+            CreditStore::new(MockDstackClient::new().with_seed("other-deployment"), storage_path)
+                .await;
+        assert!(matches!(result, Err(PaymentError::Encryption(_))));
+    }
+
     #[tokio::test]
     async fn test_has_credits() {
         let (store, _dir) = create_test_store().await;
@@ -584,4 +1755,546 @@ mod tests {
         assert!(store.has_credits("+14155551234", 1_000_000).await);
         assert!(!store.has_credits("+14155551234", 1_000_001).await);
     }
+
+    #[tokio::test]
+    async fn test_deposits_and_usage_pages() {
+        let (store, _dir) = create_test_store().await;
+        let user = "+14155551234";
+
+        for i in 0..5 {
+            let deposit = Deposit::new_pending(user.to_string(), Chain::Base, format!("0x{}", i), 1_000, 1_000);
+            store.add_credits(deposit).await.unwrap();
+        }
+        let other = Deposit::new_pending("+14155559999".to_string(), Chain::Base, "0xother".to_string(), 1_000, 1_000);
+        store.add_credits(other).await.unwrap();
+
+        let (page, total) = store.get_deposits_page(user, None, 0, 2).await;
+        assert_eq!(total, 5);
+        assert_eq!(page.iter().map(|d| d.tx_hash.as_str()).collect::<Vec<_>>(), ["0x0", "0x1"]);
+
+        // The last page is short, and pages past the end are empty
+        let (page, total) = store.get_deposits_page(user, None, 4, 2).await;
+        assert_eq!((page.len(), total), (1, 5));
+        assert_eq!(page[0].tx_hash, "0x4");
+        let (page, total) = store.get_deposits_page(user, None, 5, 2).await;
+        assert_eq!((page.len(), total), (0, 5));
+        let (page, _) = store.get_deposits_page(user, None, 0, 0).await;
+        assert!(page.is_empty());
+
+        // `since` narrows both the page and the total
+        let cutoff = Utc::now();
+        store.data.write().await.deposits[3..5]
+            .iter_mut()
+            .for_each(|d| d.created_at = cutoff + chrono::Duration::seconds(1));
+        let (page, total) = store.get_deposits_page(user, Some(cutoff), 0, 10).await;
+        assert_eq!(total, 2);
+        assert_eq!(page[0].tx_hash, "0x3");
+
+        for _ in 0..3 {
+            let usage = UsageRecord::new(user.to_string(), user.to_string(), 10, 10, 100);
+            store.deduct_credits(user, 100, usage).await.unwrap();
+        }
+        let (page, total) = store.get_usage_page(user, None, 1, 10).await;
+        assert_eq!((page.len(), total), (2, 3));
+        let (page, total) = store.get_usage_page(user, Some(Utc::now()), 0, 10).await;
+        assert_eq!((page.len(), total), (0, 0));
+    }
+
+    /// Deposit `credits` for `user`, then backdate the last deposit by `age`.
+    async fn deposit_aged(store: &CreditStore, user: &str, tx_hash: &str, credits: u64, age: Duration) {
+        let deposit = Deposit::new_pending(user.to_string(), Chain::Base, tx_hash.to_string(), credits, credits);
+        store.add_credits(deposit).await.unwrap();
+        let mut data = store.data.write().await;
+        let balance = data.balances.get_mut(user).unwrap();
+        balance.last_deposit_at = Some(Utc::now() - chrono::Duration::from_std(age).unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_credits_within_ttl_stay_active() {
+        let (store, _dir) = create_test_store().await;
+        let user = "+14155551234";
+        store.set_credit_ttl(Some(Duration::from_secs(30 * 86400)));
+
+        deposit_aged(&store, user, "0xabc", 1_000_000, Duration::from_secs(86400)).await;
+
+        assert!(store.has_credits(user, 1_000_000).await);
+        let usage = UsageRecord::new(user.to_string(), user.to_string(), 1000, 500, 500);
+        let balance = store.deduct_credits(user, 500, usage).await.unwrap();
+        assert_eq!(balance.credits_remaining, 999_500);
+        assert_eq!(balance.total_expired, 0);
+    }
+
+    #[tokio::test]
+    async fn test_credits_expire_after_ttl() {
+        let (store, _dir) = create_test_store().await;
+        let user = "+14155551234";
+        store.set_credit_ttl(Some(Duration::from_secs(30 * 86400)));
+
+        deposit_aged(&store, user, "0xabc", 1_000_000, Duration::from_secs(31 * 86400)).await;
+
+        assert!(!store.has_credits(user, 100).await);
+        let usage = UsageRecord::new(user.to_string(), user.to_string(), 1000, 500, 500);
+        assert!(matches!(
+            store.deduct_credits(user, 500, usage).await,
+            Err(PaymentError::InsufficientCredits { available: 0, .. })
+        ));
+
+        let balance = store.get_balance(user).await;
+        assert_eq!(balance.credits_remaining, 0);
+        assert_eq!(balance.total_deposited, 1_000_000);
+        assert_eq!(balance.total_expired, 1_000_000);
+        assert!(store.reconcile(false).await.unwrap().is_empty(), "expiry keeps the ledger consistent");
+
+        // The reset is persisted
+        store.load().await.unwrap();
+        assert_eq!(store.data.read().await.balances[user].credits_remaining, 0);
+    }
+
+    #[tokio::test]
+    async fn test_refused_charge_persists_expiry() {
+        let (store, _dir) = create_test_store().await;
+        let user = "+14155551234";
+        let recipient = "+14155555678";
+        store.set_credit_ttl(Some(Duration::from_secs(30 * 86400)));
+
+        // The backdating is only in memory, so a reload would bring the
+        // credits back unless the refused charge wrote the reset
+        deposit_aged(&store, user, "0xabc", 1_000_000, Duration::from_secs(31 * 86400)).await;
+        let usage = UsageRecord::new(user.to_string(), user.to_string(), 1000, 500, 500);
+        assert!(matches!(
+            store.deduct_credits(user, 500, usage).await,
+            Err(PaymentError::InsufficientCredits { available: 0, .. })
+        ));
+        store.load().await.unwrap();
+        assert_eq!(store.data.read().await.balances[user].total_expired, 1_000_000);
+
+        deposit_aged(&store, recipient, "0xdef", 1_000_000, Duration::from_secs(31 * 86400)).await;
+        assert!(matches!(
+            store.transfer_credits(user, recipient, 500).await,
+            Err(PaymentError::InsufficientCredits { available: 0, .. })
+        ));
+        store.load().await.unwrap();
+        assert_eq!(store.data.read().await.balances[recipient].total_expired, 1_000_000);
+    }
+
+    #[tokio::test]
+    async fn test_credits_never_expire_without_ttl() {
+        let (store, _dir) = create_test_store().await;
+        let user = "+14155551234";
+
+        deposit_aged(&store, user, "0xabc", 1_000_000, Duration::from_secs(365 * 86400)).await;
+
+        assert!(store.has_credits(user, 1_000_000).await);
+    }
+
+    #[tokio::test]
+    async fn test_new_deposit_refreshes_credit_ttl() {
+        let (store, _dir) = create_test_store().await;
+        let user = "+14155551234";
+        store.set_credit_ttl(Some(Duration::from_secs(30 * 86400)));
+
+        // Topping up before the TTL runs out keeps the older credits
+        deposit_aged(&store, user, "0xabc", 1_000_000, Duration::from_secs(29 * 86400)).await;
+        deposit_aged(&store, user, "0xdef", 500_000, Duration::ZERO).await;
+        assert_eq!(store.get_balance(user).await.credits_remaining, 1_500_000);
+
+        // Topping up after it runs out only brings the new deposit
+        deposit_aged(&store, user, "0x123", 1_000_000, Duration::from_secs(31 * 86400)).await;
+        let deposit = Deposit::new_pending(user.to_string(), Chain::Base, "0x456".to_string(), 250_000, 250_000);
+        let balance = store.add_credits(deposit).await.unwrap();
+        assert_eq!(balance.credits_remaining, 250_000);
+        assert_eq!(balance.total_expired, 2_500_000);
+        assert!(store.has_credits(user, 250_000).await);
+    }
+
+    #[tokio::test]
+    async fn test_same_hash_on_different_chains() {
+        let (store, _dir) = create_test_store().await;
+
+        let base = Deposit::new_pending(
+            "+14155551234".to_string(),
+            Chain::Base,
+            "shared-id".to_string(),
+            1_000_000,
+            1_000_000,
+        );
+        store.add_credits(base).await.unwrap();
+
+        assert!(store.is_tx_processed(Chain::Base, "shared-id").await);
+        assert!(!store.is_tx_processed(Chain::Solana, "shared-id").await);
+
+        let solana = Deposit::new_pending(
+            "+14155551234".to_string(),
+            Chain::Solana,
+            "shared-id".to_string(),
+            1_000_000,
+            1_000_000,
+        );
+        store.add_credits(solana).await.unwrap();
+
+        assert_eq!(store.get_balance("+14155551234").await.credits_remaining, 2_000_000);
+    }
+
+    #[tokio::test]
+    async fn test_migrate_v1_processed_hashes() {
+        let temp_dir = TempDir::new().unwrap();
+        let storage_path = temp_dir.path().join("credits.enc");
+        let key = create_test_key();
+
+        let deposit = Deposit::new_pending(
+            "+14155551234".to_string(),
+            Chain::Near,
+            "near-tx".to_string(),
+            1_000_000,
+            1_000_000,
+        );
+        let v1 = serde_json::json!({
+            "version": 1,
+            "balances": {},
+            "deposits": [deposit],
+            "usage_log": [],
+            "processed_tx_hashes": ["near-tx", "orphan-tx"]
+        });
+
+        write_encrypted(&storage_path, &key, &v1);
+
+        let store = CreditStore::with_key(MockDstackClient::new(), storage_path.clone(), key)
+            .await
+            .unwrap();
+
+        // Known hash is mapped to its deposit's chain only
+        assert!(store.is_tx_processed(Chain::Near, "near-tx").await);
+        assert!(!store.is_tx_processed(Chain::Base, "near-tx").await);
+
+        // Unknown hash stays blocked everywhere
+        assert!(store.is_tx_processed(Chain::Base, "orphan-tx").await);
+        assert!(store.is_tx_processed(Chain::Near, "orphan-tx").await);
+        assert!(store.is_tx_processed(Chain::Solana, "orphan-tx").await);
+
+        // Migrated data round-trips as the current version
+        store.persist().await.unwrap();
+        drop(store);
+        let reloaded = CreditStore::with_key(MockDstackClient::new(), storage_path, key)
+            .await
+            .unwrap();
+        assert!(reloaded.is_tx_processed(Chain::Near, "near-tx").await);
+        assert_eq!(reloaded.data.read().await.version, DATA_VERSION);
+    }
+
+    #[tokio::test]
+    async fn test_migrate_v2_usage_records() {
+        let temp_dir = TempDir::new().unwrap();
+        let storage_path = temp_dir.path().join("credits.enc");
+        let key = create_test_key();
+
+        let v2 = serde_json::json!({
+            "version": 2,
+            "balances": {},
+            "deposits": [],
+            "usage_log": [{
+                "user_id": "+14155551234",
+                "conversation_id": "+14155551234",
+                "prompt_tokens": 100,
+                "completion_tokens": 50,
+                "total_tokens": 150,
+                "credits_consumed": 10,
+                "timestamp": "2025-01-01T00:00:00Z"
+            }],
+            "processed_tx_hashes": []
+        });
+
+        write_encrypted(&storage_path, &key, &v2);
+
+        let store = CreditStore::with_key(MockDstackClient::new(), storage_path, key)
+            .await
+            .unwrap();
+
+        let usage = store.get_usage("+14155551234").await;
+        assert_eq!(usage.len(), 1);
+        assert_eq!(usage[0].message_timestamp, None);
+        assert_eq!(store.data.read().await.version, DATA_VERSION);
+    }
+
+    /// Write `data` to `storage_path` encrypted the way `persist()` does.
+    fn write_encrypted(storage_path: &Path, key: &[u8; 32], data: &serde_json::Value) {
+        let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key));
+        let nonce_bytes = [7u8; NONCE_SIZE];
+        let ciphertext = cipher
+            .encrypt(
+                Nonce::from_slice(&nonce_bytes),
+                serde_json::to_vec(data).unwrap().as_ref(),
+            )
+            .unwrap();
+        let mut encrypted = nonce_bytes.to_vec();
+        encrypted.extend(ciphertext);
+        std::fs::write(storage_path, encrypted).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_migrate_v4_idempotency_keys() {
+        let temp_dir = TempDir::new().unwrap();
+        let storage_path = temp_dir.path().join("credits.enc");
+        let key = create_test_key();
+
+        let deposit = Deposit::new_pending(
+            "+14155551234".to_string(),
+            Chain::Base,
+            "0xabc".to_string(),
+            1_000_000,
+            1_000_000,
+        );
+        let v4 = serde_json::json!({
+            "version": 4,
+            "balances": {},
+            "deposits": [deposit],
+            "usage_log": [],
+            "processed_tx_hashes": [],
+            "idempotency_keys": {
+                "retry-1": { "deposit_id": deposit.id, "new_balance": 1_000_000 },
+                "orphan": { "deposit_id": "missing", "new_balance": 5 }
+            }
+        });
+        write_encrypted(&storage_path, &key, &v4);
+
+        let store = CreditStore::with_key(MockDstackClient::new(), storage_path, key)
+            .await
+            .unwrap();
+
+        // The key now belongs to the user whose deposit it was used for
+        let (replayed, _) = store.idempotent_deposit("+14155551234", "retry-1").await.unwrap();
+        assert_eq!(replayed.id, deposit.id);
+        assert!(store.idempotent_deposit("+14155559999", "retry-1").await.is_none());
+        assert_eq!(store.data.read().await.idempotency_keys.len(), 1);
+        assert_eq!(store.data.read().await.version, DATA_VERSION);
+    }
+
+    #[tokio::test]
+    async fn test_migrate_v7_received_transfers() {
+        let temp_dir = TempDir::new().unwrap();
+        let storage_path = temp_dir.path().join("credits.enc");
+        let key = create_test_key();
+
+        let deposit = |user: &str, tx: &str| {
+            Deposit::new_pending(user.to_string(), Chain::Base, tx.to_string(), 1_000_000, 1_000_000)
+        };
+        // Before v8 the recipient's transfer was counted as a deposit
+        let sender = CreditBalance {
+            credits_remaining: 700_000,
+            total_deposited: 1_000_000,
+            total_consumed: 300_000,
+            ..CreditBalance::new("+14155551234".to_string())
+        };
+        let recipient = CreditBalance {
+            credits_remaining: 1_300_000,
+            total_deposited: 1_300_000,
+            ..CreditBalance::new("+14155559999".to_string())
+        };
+        let v7 = serde_json::json!({
+            "version": 7,
+            "balances": { "+14155551234": sender, "+14155559999": recipient },
+            "deposits": [deposit("+14155551234", "0x1"), deposit("+14155559999", "0x2")],
+            "usage_log": [UsageRecord::transfer("+14155551234".to_string(), "+14155559999".to_string(), 300_000)],
+            "processed_tx_hashes": []
+        });
+        write_encrypted(&storage_path, &key, &v7);
+
+        let store = CreditStore::with_key(MockDstackClient::new(), storage_path, key)
+            .await
+            .unwrap();
+
+        let balance = store.get_balance("+14155559999").await;
+        assert_eq!(balance.total_deposited, 1_000_000);
+        assert_eq!(balance.total_received, 300_000);
+        assert_eq!(balance.credits_remaining, 1_300_000);
+        assert!(store.reconcile(false).await.unwrap().is_empty());
+        assert_eq!(store.data.read().await.version, DATA_VERSION);
+    }
+
+    #[tokio::test]
+    async fn test_idempotency_keys_scoped_per_user() {
+        let (store, _dir) = create_test_store().await;
+        let deposit = |user: &str, tx: &str| {
+            Deposit::new_pending(user.to_string(), Chain::Base, tx.to_string(), 1_000_000, 1_000_000)
+        };
+
+        // Two users may pick the same key
+        store
+            .add_credits_with_key(deposit("+14155551234", "0x1"), Some("retry-1"))
+            .await
+            .unwrap();
+        store
+            .add_credits_with_key(deposit("+14155559999", "0x2"), Some("retry-1"))
+            .await
+            .unwrap();
+        let (first, _) = store.idempotent_deposit("+14155551234", "retry-1").await.unwrap();
+        let (second, _) = store.idempotent_deposit("+14155559999", "retry-1").await.unwrap();
+        assert_eq!(first.tx_hash, "0x1");
+        assert_eq!(second.tx_hash, "0x2");
+
+        // But one user can't reuse theirs
+        let reused = store
+            .add_credits_with_key(deposit("+14155551234", "0x3"), Some("retry-1"))
+            .await;
+        assert!(matches!(reused, Err(PaymentError::IdempotencyKeyReused(_))));
+    }
+
+    #[tokio::test]
+    async fn test_idempotency_keys_expire() {
+        let (store, _dir) = create_test_store().await;
+        store.set_idempotency_key_ttl(Duration::from_millis(50));
+        let deposit = |tx: &str| {
+            Deposit::new_pending("+14155551234".to_string(), Chain::Base, tx.to_string(), 1_000_000, 1_000_000)
+        };
+
+        store.add_credits_with_key(deposit("0x1"), Some("retry-1")).await.unwrap();
+        assert!(store.idempotent_deposit("+14155551234", "retry-1").await.is_some());
+        tokio::time::sleep(Duration::from_millis(80)).await;
+
+        // Forgotten, and free to be used again
+        assert!(store.idempotent_deposit("+14155551234", "retry-1").await.is_none());
+        store.add_credits_with_key(deposit("0x2"), Some("retry-1")).await.unwrap();
+        assert_eq!(store.data.read().await.idempotency_keys.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_usage_links_signal_message() {
+        let (store, _dir) = create_test_store().await;
+
+        let deposit = Deposit::new_pending(
+            "+14155551234".to_string(),
+            Chain::Base,
+            "0x123abc".to_string(),
+            1_000_000,
+            1_000_000,
+        );
+        store.add_credits(deposit).await.unwrap();
+
+        let usage = UsageRecord::new(
+            "+14155551234".to_string(),
+            "group-abc".to_string(),
+            1000,
+            500,
+            500,
+        )
+        .with_message_timestamp(1_700_000_000_123);
+        store.deduct_credits("+14155551234", 500, usage).await.unwrap();
+
+        let usage = store.get_usage("+14155551234").await;
+        assert_eq!(usage[0].conversation_id, "group-abc");
+        assert_eq!(usage[0].message_timestamp, Some(1_700_000_000_123));
+    }
+
+    /// Guest agent whose DeriveKey endpoint is missing (older dstack).
+    struct NoDeriveKey(MockDstackClient);
+
+    #[async_trait::async_trait]
+    impl DstackApi for NoDeriveKey {
+        async fn is_in_tee(&self) -> bool {
+            self.0.is_in_tee().await
+        }
+
+        async fn get_app_info(&self) -> Result<AppInfo, DstackError> {
+            self.0.get_app_info().await
+        }
+
+        async fn get_tcb_info(&self) -> Result<TcbInfo, DstackError> {
+            self.0.get_tcb_info().await
+        }
+
+        async fn get_quote(&self, report_data: &[u8]) -> Result<Quote, DstackError> {
+            self.0.get_quote(report_data).await
+        }
+
+        async fn derive_key(
+            &self,
+            _path: &str,
+            _subject: Option<&str>,
+        ) -> Result<Vec<u8>, DstackError> {
+            Err(DstackError::KeyDerivation("404 Not Found".into()))
+        }
+
+        async fn get_ra_tls_cert(&self) -> Result<Vec<u8>, DstackError> {
+            self.0.get_ra_tls_cert().await
+        }
+    }
+
+    #[tokio::test]
+    async fn test_fallback_key_alerts_operator() {
+        let temp_dir = TempDir::new().unwrap();
+        let store = CreditStore::new(
+            NoDeriveKey(MockDstackClient::new()),
+            temp_dir.path().join("credits.enc"),
+        )
+        .await
+        .unwrap();
+        let channel = Arc::new(RecordingChannel::default());
+        store
+            .set_notifier(Arc::new(OperatorNotifier::new(channel.clone())))
+            .await;
+
+        // The key is derived on first write
+        assert!(!store.uses_fallback_key());
+        store.persist().await.unwrap();
+        assert!(store.uses_fallback_key());
+
+        let sent = channel.sent.lock().unwrap();
+        assert_eq!(sent.len(), 1);
+        assert!(sent[0].contains("AppInfo"));
+    }
+
+    #[tokio::test]
+    async fn test_reseal_moves_to_derived_key() {
+        let temp_dir = TempDir::new().unwrap();
+        let storage_path = temp_dir.path().join("credits.enc");
+
+        // Written under a stale key, e.g. from before a key-path change
+        {
+            let store = CreditStore::with_key(
+                MockDstackClient::new(),
+                storage_path.clone(),
+                create_test_key(),
+            )
+            .await
+            .unwrap();
+            assert_eq!(store.key_source(), Some(KeySource::Provided));
+            let deposit = Deposit::new_pending(
+                "+14155551234".to_string(),
+                Chain::Base,
+                "0xabc".to_string(),
+                1_000_000,
+                1_000_000,
+            );
+            store.add_credits(deposit).await.unwrap();
+
+            assert_eq!(store.reseal().await.unwrap(), KeySource::DeriveKey);
+            assert_eq!(store.key_source(), Some(KeySource::DeriveKey));
+        }
+
+        let mut backup_path = storage_path.as_os_str().to_owned();
+        backup_path.push(".pre-reseal");
+        assert!(std::path::Path::new(&backup_path).exists());
+
+        // Reopening with the derived key sees the data
+        let store = CreditStore::new(MockDstackClient::new(), storage_path)
+            .await
+            .unwrap();
+        assert_eq!(store.get_balance("+14155551234").await.credits_remaining, 1_000_000);
+    }
+
+    #[tokio::test]
+    async fn test_failed_reseal_keeps_previous_key() {
+        let temp_dir = TempDir::new().unwrap();
+        let store = CreditStore::with_key(
+            MockDstackClient::not_in_tee(),
+            temp_dir.path().join("credits.enc"),
+            create_test_key(),
+        )
+        .await
+        .unwrap();
+        store.set_persist_retries(0);
+
+        assert!(store.reseal().await.is_err());
+        assert_eq!(store.key_source(), Some(KeySource::Provided));
+        store.persist().await.unwrap();
+    }
 }
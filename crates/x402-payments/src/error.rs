@@ -29,6 +29,18 @@ pub enum PaymentError {
     #[error("Transaction already processed: {0}")]
     DuplicateTransaction(String),
 
+    /// Idempotency key already used for a different deposit.
+    #[error("Idempotency key already used: {0}")]
+    IdempotencyKeyReused(String),
+
+    /// Sender address already linked to another user.
+    #[error("Address already linked to another user: {0}")]
+    AddressAlreadyLinked(String),
+
+    /// Sender address not linked to the user.
+    #[error("Address not linked to this user: {0}")]
+    AddressNotLinked(String),
+
     /// Chain not supported or not enabled.
     #[error("Chain not supported: {0}")]
     UnsupportedChain(String),
@@ -17,6 +17,7 @@
 //! - [`credits`] - Credit balance management and pricing
 //! - [`chains`] - Multi-chain payment verification (Base, NEAR, Solana)
 //! - [`api`] - HTTP API for deposit and balance operations
+//! - [`webhook`] - Deposit notifications for external systems
 //!
 //! # Security
 //!
@@ -28,20 +29,30 @@ pub mod chains;
 pub mod config;
 pub mod credits;
 pub mod error;
+pub mod notify;
 pub mod sweeper;
 pub mod types;
+pub mod webhook;
 
 // Re-exports for convenience
 pub use config::PaymentConfig;
 pub use config::PricingConfig;
-pub use credits::{calculate_credits, estimate_credits, CreditStore, PricingCalculator, TokenUsage};
+pub use credits::{
+    calculate_credits, estimate_credits, max_completion_tokens, CreditStore, PricingCalculator,
+    TokenUsage,
+};
 pub use error::PaymentError;
-pub use sweeper::{spawn_sweeper, FundSweeper};
-pub use types::{Chain, CreditBalance, Deposit, DepositStatus, OperatorAddresses, SweepRecord, UsageRecord};
+pub use notify::{NotificationChannel, OperatorNotifier};
+pub use sweeper::{spawn_shared_sweeper, spawn_sweeper, FundSweeper};
+pub use types::{
+    Chain, CreditBalance, Deposit, DepositIntent, DepositStatus, IdempotentDeposit,
+    LinkedAddress, OperatorAddresses, SweepRecord, SweepStatus, UsageRecord,
+};
 
 use api::AppState;
-use chains::{BaseFacilitator, ChainFacilitator, NearFacilitator, SolanaFacilitator};
-use dstack_client::DstackClient;
+use chains::{BaseFacilitator, ChainFacilitator, DepositWallets, NearFacilitator, SolanaFacilitator};
+use dstack_client::{DstackApi, DstackClient};
+use std::collections::HashMap;
 use std::net::SocketAddr;
 use std::sync::Arc;
 use tokio::net::TcpListener;
@@ -60,98 +71,54 @@ pub async fn start_payment_server(
         return Ok(());
     }
 
-    // Initialize chain facilitators (before credit store, since they only need &dstack)
-    let base_facilitator = if let Some(base_config) = &config.base {
-        if base_config.enabled {
-            match BaseFacilitator::new(base_config.clone(), &dstack).await {
-                Ok(f) => {
-                    info!("Base facilitator initialized");
-                    Some(Arc::new(f))
-                }
-                Err(e) => {
-                    warn!("Failed to initialize Base facilitator: {}", e);
-                    None
-                }
-            }
-        } else {
-            None
-        }
-    } else {
-        None
-    };
-
-    let near_facilitator = if let Some(near_config) = &config.near {
-        if near_config.enabled {
-            match NearFacilitator::new(near_config.clone(), &dstack).await {
-                Ok(f) => {
-                    info!("NEAR facilitator initialized");
-                    Some(Arc::new(f))
-                }
-                Err(e) => {
-                    warn!("Failed to initialize NEAR facilitator: {}", e);
-                    None
-                }
-            }
-        } else {
-            None
-        }
-    } else {
-        None
-    };
-
-    let solana_facilitator = if let Some(solana_config) = &config.solana {
-        if solana_config.enabled {
-            match SolanaFacilitator::new(solana_config.clone(), &dstack).await {
-                Ok(f) => {
-                    info!("Solana facilitator initialized");
-                    Some(Arc::new(f))
-                }
-                Err(e) => {
-                    warn!("Failed to initialize Solana facilitator: {}", e);
-                    None
-                }
-            }
-        } else {
-            None
-        }
-    } else {
-        None
-    };
+    // Initialize chain facilitators (before credit store, since they only need &dstack);
+    // there's no operator to alert here
+    let mut startup_alerts = Vec::new();
+    let wallets = init_wallets(&config, &dstack, None, &mut startup_alerts).await;
+    let accounts = init_account_wallets(&config, &dstack, &mut startup_alerts).await;
 
     // Collect enabled facilitators for sweeper
-    let mut facilitators: Vec<Arc<dyn ChainFacilitator>> = Vec::new();
-    if let Some(ref f) = base_facilitator {
-        facilitators.push(f.clone());
-    }
-    if let Some(ref f) = near_facilitator {
-        facilitators.push(f.clone());
-    }
-    if let Some(ref f) = solana_facilitator {
-        facilitators.push(f.clone());
-    }
+    let facilitators = all_facilitators(&wallets, &accounts);
 
     // Spawn fund sweeper if we have any operator addresses configured
     let operator_addresses = config.operator_addresses();
-    if !facilitators.is_empty() && operator_addresses.has_any() {
-        info!("Starting fund sweeper with {} chains", facilitators.len());
-        spawn_sweeper(
+    let sweeper = if !facilitators.is_empty() && operator_addresses.has_any() {
+        info!("Starting fund sweeper with {} wallets", facilitators.len());
+        let sweeper = Arc::new(FundSweeper::new(
             facilitators,
             operator_addresses,
             config.sweep.clone(),
-        );
-    }
+        ));
+        spawn_shared_sweeper(sweeper.clone());
+        Some(sweeper)
+    } else {
+        None
+    };
 
     // Create credit store (takes ownership of dstack)
-    let credit_store = CreditStore::new(dstack, config.storage_path.clone()).await?;
+    let credit_store = CreditStore::new_with_subject(
+        dstack,
+        config.storage_path.clone(),
+        config.signing_subject.clone(),
+    )
+    .await?;
+    credit_store.set_persist_retries(config.persist_retries);
+    credit_store.set_credit_ttl(config.pricing.credit_ttl);
+    credit_store.set_idempotency_key_ttl(config.idempotency_key_ttl);
 
     // Create app state
-    let state = Arc::new(AppState::new(
+    let mut state = AppState::new(
         credit_store,
         config.clone(),
-        base_facilitator,
-        near_facilitator,
-        solana_facilitator,
-    ));
+        wallets.base,
+        wallets.near,
+        wallets.solana,
+    )
+    .with_accounts(accounts);
+    if let Some(sweeper) = sweeper {
+        state = state.with_sweeper(sweeper);
+    }
+    let state = Arc::new(state);
 
     // Create router
     let router = api::create_router(state);
@@ -174,107 +141,94 @@ pub async fn start_payment_server(
 
 /// Create and run the payment server as a background task.
 ///
+/// Pass the credit store the caller already uses (e.g. the bot's) as
+/// `credit_store` so deposits and deductions share one in-memory view; when
+/// `None`, a store is opened at `config.storage_path`. With a `notifier`, the
+/// operator is alerted about facilitators that fail to start or turn
+/// unhealthy, failed sweeps and a fallback storage key.
+///
 /// Returns a JoinHandle for the server task.
 pub async fn spawn_payment_server(
     config: PaymentConfig,
     dstack: DstackClient,
+    credit_store: Option<Arc<CreditStore>>,
+    notifier: Option<Arc<OperatorNotifier>>,
 ) -> Result<Option<tokio::task::JoinHandle<Result<(), PaymentError>>>, PaymentError> {
     if !config.enabled {
         info!("Payments disabled");
         return Ok(None);
     }
 
+    // Facilitator problems to report to the operator once everything is up
+    let mut startup_alerts: Vec<(String, String)> = Vec::new();
+
     // Initialize chain facilitators (before credit store, since they only need &dstack)
-    let base_facilitator = if let Some(base_config) = &config.base {
-        if base_config.enabled {
-            match BaseFacilitator::new(base_config.clone(), &dstack).await {
-                Ok(f) => {
-                    info!("Base facilitator initialized");
-                    Some(Arc::new(f))
-                }
-                Err(e) => {
-                    warn!("Failed to initialize Base facilitator: {}", e);
-                    None
-                }
-            }
-        } else {
-            None
+    let wallets = init_wallets(&config, &dstack, None, &mut startup_alerts).await;
+    let accounts = init_account_wallets(&config, &dstack, &mut startup_alerts).await;
+
+    // Collect enabled facilitators for sweeper
+    let facilitators = all_facilitators(&wallets, &accounts);
+
+    // Spawn fund sweeper if we have any operator addresses configured
+    if let Some(ref notifier) = notifier {
+        for (key, message) in &startup_alerts {
+            notifier.notify(key, message).await;
         }
-    } else {
-        None
-    };
+        if !facilitators.is_empty() {
+            notify::spawn_health_monitor(
+                facilitators.clone(),
+                notifier.clone(),
+                config.health_check_interval,
+            );
+        }
+    }
 
-    let near_facilitator = if let Some(near_config) = &config.near {
-        if near_config.enabled {
-            match NearFacilitator::new(near_config.clone(), &dstack).await {
-                Ok(f) => {
-                    info!("NEAR facilitator initialized");
-                    Some(Arc::new(f))
-                }
-                Err(e) => {
-                    warn!("Failed to initialize NEAR facilitator: {}", e);
-                    None
-                }
-            }
-        } else {
-            None
+    let operator_addresses = config.operator_addresses();
+    let sweeper = if !facilitators.is_empty() && operator_addresses.has_any() {
+        info!("Starting fund sweeper with {} wallets", facilitators.len());
+        let mut sweeper = FundSweeper::new(facilitators, operator_addresses, config.sweep.clone());
+        if let Some(ref notifier) = notifier {
+            sweeper = sweeper.with_notifier(notifier.clone());
         }
+        let sweeper = Arc::new(sweeper);
+        spawn_shared_sweeper(sweeper.clone());
+        Some(sweeper)
     } else {
         None
     };
 
-    let solana_facilitator = if let Some(solana_config) = &config.solana {
-        if solana_config.enabled {
-            match SolanaFacilitator::new(solana_config.clone(), &dstack).await {
-                Ok(f) => {
-                    info!("Solana facilitator initialized");
-                    Some(Arc::new(f))
-                }
-                Err(e) => {
-                    warn!("Failed to initialize Solana facilitator: {}", e);
-                    None
-                }
+    // Reuse the caller's credit store, or open one (takes ownership of dstack)
+    let credit_store = match credit_store {
+        Some(store) => store,
+        None => {
+            let store = CreditStore::new_with_subject(
+                dstack,
+                config.storage_path.clone(),
+                config.signing_subject.clone(),
+            )
+            .await?;
+            store.set_persist_retries(config.persist_retries);
+            store.set_credit_ttl(config.pricing.credit_ttl);
+            store.set_idempotency_key_ttl(config.idempotency_key_ttl);
+            if let Some(ref notifier) = notifier {
+                store.set_notifier(notifier.clone()).await;
             }
-        } else {
-            None
+            store
         }
-    } else {
-        None
     };
 
-    // Collect enabled facilitators for sweeper
-    let mut facilitators: Vec<Arc<dyn ChainFacilitator>> = Vec::new();
-    if let Some(ref f) = base_facilitator {
-        facilitators.push(f.clone());
-    }
-    if let Some(ref f) = near_facilitator {
-        facilitators.push(f.clone());
-    }
-    if let Some(ref f) = solana_facilitator {
-        facilitators.push(f.clone());
-    }
-
-    // Spawn fund sweeper if we have any operator addresses configured
-    let operator_addresses = config.operator_addresses();
-    if !facilitators.is_empty() && operator_addresses.has_any() {
-        info!("Starting fund sweeper with {} chains", facilitators.len());
-        spawn_sweeper(
-            facilitators,
-            operator_addresses,
-            config.sweep.clone(),
-        );
-    }
-
-    // Create credit store (takes ownership of dstack)
-    let credit_store = CreditStore::new(dstack, config.storage_path.clone()).await?;
-
-    let state = Arc::new(AppState::new(
+    let mut state = AppState::new(
         credit_store,
         config.clone(),
-        base_facilitator,
-        near_facilitator,
-        solana_facilitator,
-    ));
+        wallets.base,
+        wallets.near,
+        wallets.solana,
+    )
+    .with_accounts(accounts);
+    if let Some(sweeper) = sweeper {
+        state = state.with_sweeper(sweeper);
+    }
+    let state = Arc::new(state);
     let router = api::create_router(state);
 
     let addr = SocketAddr::from(([0, 0, 0, 0], config.server_port));
@@ -293,6 +247,111 @@ pub async fn spawn_payment_server(
     Ok(Some(handle))
 }
 
+/// Start one set of deposit wallets: `account`'s when given, otherwise the
+/// deployment's default wallets.
+///
+/// Chains whose facilitator fails to start are left out, with an operator
+/// alert queued in `alerts`.
+async fn init_wallets(
+    config: &PaymentConfig,
+    dstack: &dyn DstackApi,
+    account: Option<&str>,
+    alerts: &mut Vec<(String, String)>,
+) -> DepositWallets {
+    let subject = match account {
+        Some(account) => Some(config.account_subject(account)),
+        None => config.signing_subject.clone(),
+    };
+    let subject = subject.as_deref();
+    // Tells an account's facilitators apart in logs and alerts
+    let label = account.map(|a| format!(" for {}", a)).unwrap_or_default();
+    let key = account.map(|a| format!(":{}", a)).unwrap_or_default();
+    let mut wallets = DepositWallets::default();
+
+    if let Some(base_config) = config.base.as_ref().filter(|c| c.enabled) {
+        match BaseFacilitator::new_with_subject(base_config.clone(), dstack, subject).await {
+            Ok(f) => {
+                info!("Base facilitator initialized{}", label);
+                wallets.base = Some(Arc::new(f));
+            }
+            Err(e) => {
+                warn!("Failed to initialize Base facilitator{}: {}", label, e);
+                alerts.push((
+                    format!("init:Base{}", key),
+                    format!("Base payments{} are disabled: facilitator failed to start: {}", label, e),
+                ));
+            }
+        }
+    }
+
+    if let Some(near_config) = config.near.as_ref().filter(|c| c.enabled) {
+        match NearFacilitator::new_with_subject(near_config.clone(), dstack, subject).await {
+            Ok(f) => {
+                info!("NEAR facilitator initialized{}", label);
+                // Logs funding instructions itself when no funder is configured
+                if let Err(e) = f.ensure_operational().await {
+                    warn!("NEAR deposit account{} is not ready for sweeping: {}", label, e);
+                    alerts.push((
+                        format!("gas:Near{}", key),
+                        format!("NEAR deposit account{} is not ready for sweeping: {}", label, e),
+                    ));
+                }
+                wallets.near = Some(Arc::new(f));
+            }
+            Err(e) => {
+                warn!("Failed to initialize NEAR facilitator{}: {}", label, e);
+                alerts.push((
+                    format!("init:Near{}", key),
+                    format!("NEAR payments{} are disabled: facilitator failed to start: {}", label, e),
+                ));
+            }
+        }
+    }
+
+    if let Some(solana_config) = config.solana.as_ref().filter(|c| c.enabled) {
+        match SolanaFacilitator::new_with_subject(solana_config.clone(), dstack, subject).await {
+            Ok(f) => {
+                info!("Solana facilitator initialized{}", label);
+                wallets.solana = Some(Arc::new(f));
+            }
+            Err(e) => {
+                warn!("Failed to initialize Solana facilitator{}: {}", label, e);
+                alerts.push((
+                    format!("init:Solana{}", key),
+                    format!("Solana payments{} are disabled: facilitator failed to start: {}", label, e),
+                ));
+            }
+        }
+    }
+
+    wallets
+}
+
+/// Start the deposit wallets of every account in `config.accounts`.
+async fn init_account_wallets(
+    config: &PaymentConfig,
+    dstack: &dyn DstackApi,
+    alerts: &mut Vec<(String, String)>,
+) -> HashMap<String, DepositWallets> {
+    let mut accounts = HashMap::new();
+    for account in config.account_list() {
+        let wallets = init_wallets(config, dstack, Some(&account), alerts).await;
+        accounts.insert(account, wallets);
+    }
+    accounts
+}
+
+/// Every running facilitator, default wallets first.
+fn all_facilitators(
+    wallets: &DepositWallets,
+    accounts: &HashMap<String, DepositWallets>,
+) -> Vec<Arc<dyn ChainFacilitator>> {
+    std::iter::once(wallets)
+        .chain(accounts.values())
+        .flat_map(DepositWallets::facilitators)
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -300,7 +359,38 @@ mod tests {
     #[test]
     fn test_default_config() {
         let config = PaymentConfig::default();
-        assert!(config.enabled);
+        assert!(!config.enabled);
         assert_eq!(config.server_port, 8082);
     }
+
+    #[tokio::test]
+    async fn test_spawn_payment_server_shares_credit_store() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let config = PaymentConfig {
+            enabled: true,
+            server_port: 0,
+            storage_path: temp_dir.path().join("credits.enc"),
+            ..Default::default()
+        };
+
+        // The caller's store holds the storage lock, so the server must reuse
+        // it rather than open a second one.
+        let store = CreditStore::new(
+            dstack_client::MockDstackClient::new(),
+            config.storage_path.clone(),
+        )
+        .await
+        .unwrap();
+
+        let handle = spawn_payment_server(
+            config,
+            DstackClient::new("/nonexistent/dstack.sock"),
+            Some(store.clone()),
+            None,
+        )
+        .await
+        .unwrap()
+        .expect("payments are enabled");
+        handle.abort();
+    }
 }
@@ -0,0 +1,175 @@
+//! Operator notifications.
+//!
+//! Failures that need an operator's attention (a failing sweep, an unhealthy
+//! chain RPC, a weaker storage key) are otherwise only visible in the logs.
+//! [`OperatorNotifier`] forwards them to a [`NotificationChannel`] (the bot
+//! messages an admin Signal number), sending each kind of alert at most once
+//! per interval so a flapping RPC doesn't flood the operator.
+
+use crate::chains::ChainFacilitator;
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::{debug, warn};
+
+/// Default minimum time between two alerts with the same key.
+pub const DEFAULT_ALERT_INTERVAL: Duration = Duration::from_secs(3600);
+
+/// Where operator alerts are delivered.
+#[async_trait]
+pub trait NotificationChannel: Send + Sync {
+    /// Deliver one alert message.
+    async fn send(&self, message: &str) -> anyhow::Result<()>;
+}
+
+/// Sends debounced alerts to the operator.
+pub struct OperatorNotifier {
+    channel: Arc<dyn NotificationChannel>,
+    min_interval: Duration,
+    /// When each alert key was last delivered.
+    last_sent: Mutex<HashMap<String, Instant>>,
+}
+
+impl OperatorNotifier {
+    /// Create a notifier that delivers alerts through `channel`.
+    pub fn new(channel: Arc<dyn NotificationChannel>) -> Self {
+        Self {
+            channel,
+            min_interval: DEFAULT_ALERT_INTERVAL,
+            last_sent: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Set the minimum time between two alerts with the same key.
+    pub fn with_min_interval(mut self, interval: Duration) -> Self {
+        self.min_interval = interval;
+        self
+    }
+
+    /// Alert the operator, unless an alert with the same `key` went out
+    /// within the minimum interval.
+    ///
+    /// `key` identifies the kind of problem (e.g. `sweep:Base`). Returns
+    /// whether the alert was delivered.
+    pub async fn notify(&self, key: &str, message: &str) -> bool {
+        {
+            let mut last_sent = self.last_sent.lock().unwrap_or_else(|e| e.into_inner());
+            if let Some(sent) = last_sent.get(key) {
+                if sent.elapsed() < self.min_interval {
+                    debug!("Suppressing repeated operator alert '{}'", key);
+                    return false;
+                }
+            }
+            last_sent.insert(key.to_string(), Instant::now());
+        }
+
+        match self.channel.send(&format!("**Operator alert:** {}", message)).await {
+            Ok(()) => true,
+            Err(e) => {
+                warn!("Failed to deliver operator alert '{}': {}", key, e);
+                // Let the next occurrence try again
+                self.last_sent
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .remove(key);
+                false
+            }
+        }
+    }
+}
+
+/// Health-check each chain facilitator, alerting about unhealthy ones.
+pub async fn check_chain_health(
+    chains: &[Arc<dyn ChainFacilitator>],
+    notifier: &OperatorNotifier,
+) {
+    for chain in chains {
+        let problem = match chain.health_check().await {
+            Ok(true) => continue,
+            Ok(false) => "RPC is not responding".to_string(),
+            Err(e) => e.to_string(),
+        };
+        warn!("{} facilitator unhealthy: {}", chain.chain(), problem);
+        notifier
+            .notify(
+                &format!("health:{}", chain.chain()),
+                &format!("{} payments are degraded: {}", chain.chain(), problem),
+            )
+            .await;
+    }
+}
+
+/// Run [`check_chain_health`] every `interval` as a background task.
+pub fn spawn_health_monitor(
+    chains: Vec<Arc<dyn ChainFacilitator>>,
+    notifier: Arc<OperatorNotifier>,
+    interval: Duration,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            check_chain_health(&chains, &notifier).await;
+        }
+    })
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// Channel that records delivered alerts.
+    #[derive(Default)]
+    pub(crate) struct RecordingChannel {
+        pub(crate) sent: Mutex<Vec<String>>,
+        pub(crate) fail: bool,
+    }
+
+    #[async_trait]
+    impl NotificationChannel for RecordingChannel {
+        async fn send(&self, message: &str) -> anyhow::Result<()> {
+            if self.fail {
+                anyhow::bail!("channel down");
+            }
+            self.sent.lock().unwrap().push(message.to_string());
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_repeated_alerts_are_debounced_per_key() {
+        let channel = Arc::new(RecordingChannel::default());
+        let notifier = OperatorNotifier::new(channel.clone());
+
+        assert!(notifier.notify("sweep:Base", "Base sweep failed").await);
+        assert!(!notifier.notify("sweep:Base", "Base sweep failed again").await);
+        assert!(notifier.notify("sweep:Near", "NEAR sweep failed").await);
+
+        let sent = channel.sent.lock().unwrap();
+        assert_eq!(sent.len(), 2);
+        assert_eq!(sent[0], "**Operator alert:** Base sweep failed");
+    }
+
+    #[tokio::test]
+    async fn test_alert_repeats_after_interval() {
+        let channel = Arc::new(RecordingChannel::default());
+        let notifier = OperatorNotifier::new(channel.clone()).with_min_interval(Duration::ZERO);
+
+        assert!(notifier.notify("rpc:Base", "down").await);
+        assert!(notifier.notify("rpc:Base", "still down").await);
+        assert_eq!(channel.sent.lock().unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_failed_delivery_is_not_debounced() {
+        let channel = Arc::new(RecordingChannel {
+            fail: true,
+            ..Default::default()
+        });
+        let notifier = OperatorNotifier::new(channel);
+
+        assert!(!notifier.notify("rpc:Base", "down").await);
+        assert!(notifier.last_sent.lock().unwrap().is_empty());
+    }
+}
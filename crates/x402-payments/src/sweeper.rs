@@ -1,19 +1,42 @@
 //! Fund sweeper for automatic deposit-to-operator transfers.
 //!
-//! Periodically checks deposit wallet balances and transfers accumulated
-//! funds to the operator's withdrawal address.
+//! Periodically checks deposit wallet balances of every accepted token and
+//! transfers accumulated funds to the operator's withdrawal address. A chain whose sweep fails is
+//! retried with backoff, up to `max_retries` times, before the next interval.
 
 use crate::chains::ChainFacilitator;
-use crate::config::SweepConfig;
+use crate::config::{SweepConfig, TokenConfig};
 use crate::error::PaymentError;
-use crate::types::{Chain, OperatorAddresses, SweepRecord};
+use crate::notify::OperatorNotifier;
+use crate::types::{Chain, OperatorAddresses, SweepRecord, SweepStatus};
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
 use std::sync::Arc;
 use std::time::Duration;
 use tracing::{debug, error, info, warn};
 
+/// A deposit wallet: its chain and address.
+type Wallet = (Chain, String);
+
+/// Fold per-wallet values into one per chain.
+fn per_chain<T: Copy>(
+    values: impl IntoIterator<Item = (Chain, T)>,
+    fold: impl Fn(T, T) -> T,
+) -> HashMap<Chain, T> {
+    let mut chains = HashMap::new();
+    for (chain, value) in values {
+        chains
+            .entry(chain)
+            .and_modify(|total| *total = fold(*total, value))
+            .or_insert(value);
+    }
+    chains
+}
+
 /// Fund sweeper that periodically transfers deposits to operator wallets.
 pub struct FundSweeper {
-    /// Chain facilitators for each enabled chain.
+    /// Facilitator of every deposit wallet; a chain may have several (one
+    /// per bot account).
     chains: Vec<Arc<dyn ChainFacilitator>>,
     /// Operator addresses for each chain.
     operator_addresses: OperatorAddresses,
@@ -21,6 +44,18 @@ pub struct FundSweeper {
     config: SweepConfig,
     /// Sweep history (in-memory for now).
     sweep_history: tokio::sync::RwLock<Vec<SweepRecord>>,
+    /// When the last sweep cycle finished.
+    last_run: tokio::sync::RwLock<Option<DateTime<Utc>>>,
+    /// When the next scheduled cycle will start.
+    next_run: tokio::sync::RwLock<Option<DateTime<Utc>>>,
+    /// Last observed balance per deposit wallet and token symbol.
+    last_balances: tokio::sync::RwLock<HashMap<(Wallet, String), u64>>,
+    /// Consecutive failed sweeps per deposit wallet since the last success.
+    failed_attempts: tokio::sync::RwLock<HashMap<Wallet, u32>>,
+    /// Serializes sweep cycles so a manual trigger can't race the scheduler.
+    sweep_lock: tokio::sync::Mutex<()>,
+    /// Alerts the operator when a sweep fails or can't proceed.
+    notifier: Option<Arc<OperatorNotifier>>,
 }
 
 impl FundSweeper {
@@ -35,6 +70,24 @@ impl FundSweeper {
             operator_addresses,
             config,
             sweep_history: tokio::sync::RwLock::new(Vec::new()),
+            last_run: tokio::sync::RwLock::new(None),
+            next_run: tokio::sync::RwLock::new(None),
+            last_balances: tokio::sync::RwLock::new(HashMap::new()),
+            failed_attempts: tokio::sync::RwLock::new(HashMap::new()),
+            sweep_lock: tokio::sync::Mutex::new(()),
+            notifier: None,
+        }
+    }
+
+    /// Alert the operator about failed or stuck sweeps.
+    pub fn with_notifier(mut self, notifier: Arc<OperatorNotifier>) -> Self {
+        self.notifier = Some(notifier);
+        self
+    }
+
+    async fn alert(&self, key: &str, message: &str) {
+        if let Some(notifier) = &self.notifier {
+            notifier.notify(key, message).await;
         }
     }
 
@@ -49,18 +102,76 @@ impl FundSweeper {
 
     /// Run a single sweep cycle across all chains.
     pub async fn sweep_once(&self) -> Vec<SweepRecord> {
+        self.sweep_chains(false).await
+    }
+
+    /// Retry the chains whose last sweep failed and that have retries left.
+    pub async fn retry_failed(&self) -> Vec<SweepRecord> {
+        self.sweep_chains(true).await
+    }
+
+    /// Sweep every chain, or with `retry` only those awaiting a retry.
+    async fn sweep_chains(&self, retry: bool) -> Vec<SweepRecord> {
+        let _guard = self.sweep_lock.lock().await;
         let mut records = Vec::new();
 
         for chain in &self.chains {
-            match self.sweep_chain(chain.as_ref()).await {
-                Ok(Some(record)) => {
-                    records.push(record);
+            let chain_id = chain.chain();
+            let wallet = (chain_id, chain.deposit_address());
+            let failures = self
+                .failed_attempts
+                .read()
+                .await
+                .get(&wallet)
+                .copied()
+                .unwrap_or(0);
+            if retry && !self.retry_pending(failures) {
+                continue;
+            }
+
+            // Sweep every token, failing the wallet if any token failed
+            let mut failed = None;
+            for token in chain.accepted_tokens() {
+                match self.sweep_token(chain.as_ref(), &token).await {
+                    Ok(Some(record)) => records.push(record),
+                    Ok(None) => debug!("No {} sweep needed for {:?}", token.symbol, chain_id),
+                    Err(e) => {
+                        failed.get_or_insert(e);
+                    }
                 }
-                Ok(None) => {
-                    debug!("No sweep needed for {:?}", chain.chain());
+            }
+
+            match failed {
+                None => {
+                    self.failed_attempts.write().await.remove(&wallet);
                 }
-                Err(e) => {
-                    error!("Sweep failed for {:?}: {}", chain.chain(), e);
+                Some(e) => {
+                    // A scheduled cycle starts a fresh round of retries
+                    let failures = if retry { failures + 1 } else { 1 };
+                    self.failed_attempts.write().await.insert(wallet, failures);
+
+                    if self.retry_pending(failures) {
+                        warn!(
+                            "Sweep failed for {:?} (retry {} of {} in {:?}): {}",
+                            chain_id,
+                            failures,
+                            self.config.max_retries,
+                            self.retry_delay(failures),
+                            e
+                        );
+                    } else {
+                        error!(
+                            "Sweep failed for {:?} after {} retries, waiting for the next interval: {}",
+                            chain_id,
+                            failures - 1,
+                            e
+                        );
+                    }
+                    self.alert(
+                        &format!("sweep:{}", chain_id),
+                        &format!("{} sweep failed: {}", chain_id, e),
+                    )
+                    .await;
                 }
             }
         }
@@ -76,13 +187,18 @@ impl FundSweeper {
             }
         }
 
+        if !retry {
+            *self.last_run.write().await = Some(Utc::now());
+        }
+
         records
     }
 
-    /// Sweep funds from a single chain's deposit wallet.
-    async fn sweep_chain(
+    /// Sweep one token from a single chain's deposit wallet.
+    async fn sweep_token(
         &self,
         chain: &dyn ChainFacilitator,
+        token: &TokenConfig,
     ) -> Result<Option<SweepRecord>, PaymentError> {
         let chain_id = chain.chain();
         let deposit_address = chain.deposit_address();
@@ -97,42 +213,72 @@ impl FundSweeper {
         };
 
         // Get deposit wallet balance
-        let balance = chain.get_deposit_wallet_balance().await?;
+        let balance = chain.get_deposit_wallet_balance(token).await?;
+        self.last_balances
+            .write()
+            .await
+            .insert(((chain_id, deposit_address.clone()), token.symbol.clone()), balance);
 
         debug!(
-            "{:?} deposit wallet ({}) balance: {} micro-USDC",
-            chain_id, deposit_address, balance
+            "{:?} deposit wallet ({}) balance: {} micro-{}",
+            chain_id, deposit_address, balance, token.symbol
         );
 
         // Check if balance exceeds sweep threshold
         if balance < self.config.min_amount_usdc {
             debug!(
-                "{:?} balance ({}) below threshold ({}), skipping sweep",
-                chain_id, balance, self.config.min_amount_usdc
+                "{:?} {} balance ({}) below threshold ({}), skipping sweep",
+                chain_id, token.symbol, balance, self.config.min_amount_usdc
             );
             return Ok(None);
         }
 
-        // Calculate amount to sweep (leave reserve for gas)
-        let sweep_amount = balance.saturating_sub(self.config.reserve_for_gas);
+        // Calculate amount to sweep (leave the chain's reserve behind)
+        let reserve = chain.sweep_reserve().unwrap_or(self.config.reserve_for_gas);
+        let sweep_amount = balance.saturating_sub(reserve);
 
         if sweep_amount == 0 {
-            debug!("{:?} sweep amount is zero after gas reserve", chain_id);
+            debug!("{:?} sweep amount is zero after reserve", chain_id);
             return Ok(None);
         }
 
+        // Gas is paid in the native token, not USDC, so make sure the wallet
+        // can afford it rather than sending a transfer that will revert.
+        let min_gas = chain.min_gas_balance();
+        if min_gas > 0 {
+            let gas_balance = chain.get_native_gas_balance().await?;
+            if gas_balance < min_gas {
+                warn!(
+                    "Skipping {:?} sweep: deposit wallet {} has {} native gas units, needs at least {}. \
+                    Fund it with the chain's native token to resume sweeps.",
+                    chain_id, deposit_address, gas_balance, min_gas
+                );
+                self.alert(
+                    &format!("gas:{}", chain_id),
+                    &format!(
+                        "{} sweeps are paused: deposit wallet {} needs native gas ({} of {} units). \
+                        Fund it with the chain's native token.",
+                        chain_id, deposit_address, gas_balance, min_gas
+                    ),
+                )
+                .await;
+                return Ok(None);
+            }
+        }
+
         info!(
-            "Sweeping {} micro-USDC from {:?} ({}) to operator ({})",
-            sweep_amount, chain_id, deposit_address, operator_addr
+            "Sweeping {} micro-{} from {:?} ({}) to operator ({})",
+            sweep_amount, token.symbol, chain_id, deposit_address, operator_addr
         );
 
         // Execute transfer
-        let tx_result = chain.transfer_to(operator_addr, sweep_amount).await?;
+        let tx_result = chain.transfer_to(operator_addr, token, sweep_amount).await?;
 
         let record = SweepRecord {
             chain: chain_id,
             from: deposit_address,
             to: operator_addr.to_string(),
+            token: token.symbol.clone(),
             amount: sweep_amount,
             tx_hash: tx_result.tx_hash.clone(),
             success: tx_result.success,
@@ -141,31 +287,79 @@ impl FundSweeper {
 
         if tx_result.success {
             info!(
-                "Sweep successful: {:?} {} micro-USDC, tx: {}",
-                chain_id, sweep_amount, tx_result.tx_hash
+                "Sweep successful: {:?} {} micro-{}, tx: {}",
+                chain_id, sweep_amount, token.symbol, tx_result.tx_hash
             );
         } else {
             warn!(
                 "Sweep may have failed: {:?} tx: {}",
                 chain_id, tx_result.tx_hash
             );
+            self.alert(
+                &format!("sweep:{}", chain_id),
+                &format!("{} sweep transaction may have failed: {}", chain_id, tx_result.tx_hash),
+            )
+            .await;
         }
 
         Ok(Some(record))
     }
 
+    /// Whether a chain with `failures` consecutive failures gets another retry.
+    fn retry_pending(&self, failures: u32) -> bool {
+        failures > 0 && failures <= self.config.max_retries
+    }
+
+    /// Delay before the retry following the `failures`-th consecutive failure.
+    fn retry_delay(&self, failures: u32) -> Duration {
+        let backoff = 1u32.checked_shl(failures.saturating_sub(1)).unwrap_or(u32::MAX);
+        self.config
+            .retry_delay
+            .saturating_mul(backoff)
+            .min(self.config.interval)
+    }
+
+    /// Delay before the soonest pending retry, if any chain is awaiting one.
+    async fn next_retry_delay(&self) -> Option<Duration> {
+        self.failed_attempts
+            .read()
+            .await
+            .values()
+            .filter(|&&failures| self.retry_pending(failures))
+            .map(|&failures| self.retry_delay(failures))
+            .min()
+    }
+
     /// Run the sweeper as a background task.
     ///
-    /// This will run indefinitely, sleeping between sweep cycles.
+    /// This will run indefinitely, sleeping between sweep cycles and
+    /// retrying failed chains in between.
     pub async fn run(&self) {
         info!(
             "Starting fund sweeper, interval: {:?}, min_amount: {} micro-USDC",
             self.config.interval, self.config.min_amount_usdc
         );
 
+        let mut next_cycle = tokio::time::Instant::now() + self.config.interval;
         loop {
-            // Wait for the configured interval
-            tokio::time::sleep(self.config.interval).await;
+            // Wait for the configured interval, or a pending retry if sooner
+            let until_cycle = next_cycle.saturating_duration_since(tokio::time::Instant::now());
+            *self.next_run.write().await = chrono::Duration::from_std(until_cycle)
+                .ok()
+                .map(|remaining| Utc::now() + remaining);
+            match self.next_retry_delay().await {
+                Some(delay) if delay < until_cycle => {
+                    tokio::time::sleep(delay).await;
+                    info!("Retrying failed sweeps...");
+                    let records = self.retry_failed().await;
+                    if !records.is_empty() {
+                        info!("Sweep retry complete: {} transfers", records.len());
+                    }
+                    continue;
+                }
+                _ => tokio::time::sleep(until_cycle).await,
+            }
+            next_cycle = tokio::time::Instant::now() + self.config.interval;
 
             info!("Running sweep cycle...");
             let records = self.sweep_once().await;
@@ -183,6 +377,24 @@ impl FundSweeper {
         self.sweep_history.read().await.clone()
     }
 
+    /// Get the sweeper's schedule and last observed balances.
+    pub async fn status(&self) -> SweepStatus {
+        SweepStatus {
+            last_run: *self.last_run.read().await,
+            next_run: *self.next_run.read().await,
+            interval_secs: self.config.interval.as_secs(),
+            last_balances: per_chain(
+                self.last_balances.read().await.iter().map(|(((chain, _), _), &b)| (*chain, b)),
+                u64::saturating_add,
+            ),
+            failed_attempts: per_chain(
+                self.failed_attempts.read().await.iter().map(|((chain, _), &f)| (*chain, f)),
+                u32::max,
+            ),
+            history_len: self.sweep_history.read().await.len(),
+        }
+    }
+
     /// Get configured sweep interval.
     pub fn interval(&self) -> Duration {
         self.config.interval
@@ -203,7 +415,13 @@ pub fn spawn_sweeper(
     config: SweepConfig,
 ) -> tokio::task::JoinHandle<()> {
     let sweeper = Arc::new(FundSweeper::new(chains, operator_addresses, config));
+    spawn_shared_sweeper(sweeper)
+}
 
+/// Spawn an existing fund sweeper as a background task.
+///
+/// Callers keep their `Arc` to query status or trigger sweeps on demand.
+pub fn spawn_shared_sweeper(sweeper: Arc<FundSweeper>) -> tokio::task::JoinHandle<()> {
     tokio::spawn(async move {
         sweeper.run().await;
     })
@@ -214,15 +432,22 @@ mod tests {
     use super::*;
     use crate::chains::{PaymentPayload, PaymentVerification, TxResult};
     use crate::types::{SettlementResult, TxStatus};
+    use crate::notify::tests::RecordingChannel;
     use async_trait::async_trait;
-    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::sync::atomic::{AtomicU32, Ordering};
 
     /// Mock chain facilitator for testing.
     struct MockFacilitator {
         chain: Chain,
         deposit_address: String,
-        balance: AtomicU64,
+        /// Balance per token symbol; USDC first.
+        balances: std::sync::Mutex<Vec<(String, u64)>>,
         transfer_success: bool,
+        /// Transfers that fail before `transfer_success` applies.
+        failures_left: AtomicU32,
+        gas_balance: u128,
+        min_gas_balance: u128,
+        sweep_reserve: Option<u64>,
     }
 
     impl MockFacilitator {
@@ -230,10 +455,40 @@ mod tests {
             Self {
                 chain,
                 deposit_address: format!("deposit-{:?}", chain),
-                balance: AtomicU64::new(balance),
+                balances: std::sync::Mutex::new(vec![("USDC".to_string(), balance)]),
                 transfer_success,
+                failures_left: AtomicU32::new(0),
+                gas_balance: 1_000,
+                min_gas_balance: 100,
+                sweep_reserve: None,
             }
         }
+
+        fn with_gas(mut self, gas_balance: u128, min_gas_balance: u128) -> Self {
+            self.gas_balance = gas_balance;
+            self.min_gas_balance = min_gas_balance;
+            self
+        }
+
+        fn with_sweep_reserve(mut self, reserve: u64) -> Self {
+            self.sweep_reserve = Some(reserve);
+            self
+        }
+
+        fn with_token(self, symbol: &str, balance: u64) -> Self {
+            self.balances.lock().unwrap().push((symbol.to_string(), balance));
+            self
+        }
+
+        fn balance(&self, symbol: &str) -> u64 {
+            let balances = self.balances.lock().unwrap();
+            balances.iter().find(|(s, _)| s == symbol).map_or(0, |&(_, b)| b)
+        }
+
+        fn failing_times(self, failures: u32) -> Self {
+            self.failures_left.store(failures, Ordering::SeqCst);
+            self
+        }
     }
 
     #[async_trait]
@@ -260,18 +515,50 @@ mod tests {
             unimplemented!()
         }
 
-        async fn get_deposit_wallet_balance(&self) -> Result<u64, PaymentError> {
-            Ok(self.balance.load(Ordering::SeqCst))
+        fn accepted_tokens(&self) -> Vec<TokenConfig> {
+            let balances = self.balances.lock().unwrap();
+            balances
+                .iter()
+                .map(|(symbol, _)| TokenConfig {
+                    symbol: symbol.clone(),
+                    contract: symbol.to_lowercase(),
+                    decimals: 6,
+                })
+                .collect()
+        }
+
+        async fn get_deposit_wallet_balance(&self, token: &TokenConfig) -> Result<u64, PaymentError> {
+            Ok(self.balance(&token.symbol))
+        }
+
+        async fn get_native_gas_balance(&self) -> Result<u128, PaymentError> {
+            Ok(self.gas_balance)
+        }
+
+        fn min_gas_balance(&self) -> u128 {
+            self.min_gas_balance
+        }
+
+        fn sweep_reserve(&self) -> Option<u64> {
+            self.sweep_reserve
         }
 
         async fn transfer_to(
             &self,
             _destination: &str,
+            token: &TokenConfig,
             amount: u64,
         ) -> Result<TxResult, PaymentError> {
-            if self.transfer_success {
+            let failures_left = self.failures_left.load(Ordering::SeqCst);
+            if failures_left > 0 {
+                self.failures_left.store(failures_left - 1, Ordering::SeqCst);
+                Err(PaymentError::RpcError("Mock RPC timeout".to_string()))
+            } else if self.transfer_success {
                 // Deduct the transferred amount
-                self.balance.fetch_sub(amount, Ordering::SeqCst);
+                let mut balances = self.balances.lock().unwrap();
+                if let Some((_, balance)) = balances.iter_mut().find(|(s, _)| *s == token.symbol) {
+                    *balance -= amount;
+                }
                 Ok(TxResult {
                     tx_hash: "mock-tx-hash".to_string(),
                     block_number: Some(12345),
@@ -309,6 +596,7 @@ mod tests {
             interval: Duration::from_secs(1),
             min_amount_usdc: 10_000_000, // 10 USDC threshold
             reserve_for_gas: 10_000,     // 0.01 USDC reserve
+            ..SweepConfig::default()
         };
 
         let sweeper = FundSweeper::new(vec![chain.clone()], operator_addresses, config);
@@ -321,6 +609,37 @@ mod tests {
         assert!(records[0].success);
     }
 
+    #[tokio::test]
+    async fn test_sweep_every_accepted_token() {
+        let chain: Arc<dyn ChainFacilitator> = Arc::new(
+            MockFacilitator::new(Chain::Base, 20_000_000, true).with_token("USDT", 15_000_000),
+        );
+
+        let operator_addresses = OperatorAddresses {
+            base: Some("0xoperator".to_string()),
+            near: None,
+            solana: None,
+        };
+
+        let config = SweepConfig {
+            min_amount_usdc: 10_000_000,
+            reserve_for_gas: 0,
+            ..SweepConfig::default()
+        };
+
+        let sweeper = FundSweeper::new(vec![chain], operator_addresses, config);
+
+        let records = sweeper.sweep_once().await;
+
+        assert_eq!(records.len(), 2);
+        assert_eq!((records[0].token.as_str(), records[0].amount), ("USDC", 20_000_000));
+        assert_eq!((records[1].token.as_str(), records[1].amount), ("USDT", 15_000_000));
+
+        // Balances are summed across tokens
+        let status = sweeper.status().await;
+        assert_eq!(status.last_balances.get(&Chain::Base), Some(&35_000_000));
+    }
+
     #[tokio::test]
     async fn test_sweep_below_threshold() {
         let chain: Arc<dyn ChainFacilitator> = Arc::new(MockFacilitator::new(
@@ -339,6 +658,7 @@ mod tests {
             interval: Duration::from_secs(1),
             min_amount_usdc: 10_000_000, // 10 USDC threshold
             reserve_for_gas: 10_000,
+            ..SweepConfig::default()
         };
 
         let sweeper = FundSweeper::new(vec![chain], operator_addresses, config);
@@ -370,4 +690,161 @@ mod tests {
 
         assert!(records.is_empty()); // No sweep because no operator
     }
+
+    #[tokio::test]
+    async fn test_sweep_skipped_without_native_gas() {
+        let chain: Arc<dyn ChainFacilitator> = Arc::new(
+            MockFacilitator::new(Chain::Near, 20_000_000, true).with_gas(10, 1_000),
+        );
+
+        let operator_addresses = OperatorAddresses {
+            base: None,
+            near: Some("operator.near".to_string()),
+            solana: None,
+        };
+
+        let sweeper = FundSweeper::new(vec![chain.clone()], operator_addresses, SweepConfig::default());
+
+        let records = sweeper.sweep_once().await;
+
+        assert!(records.is_empty()); // Can't pay gas, so no transfer attempted
+        let usdc = &chain.accepted_tokens()[0];
+        assert_eq!(chain.get_deposit_wallet_balance(usdc).await.unwrap(), 20_000_000);
+    }
+
+    #[tokio::test]
+    async fn test_failed_sweep_alerts_operator_once() {
+        let chain: Arc<dyn ChainFacilitator> =
+            Arc::new(MockFacilitator::new(Chain::Base, 20_000_000, false));
+
+        let operator_addresses = OperatorAddresses {
+            base: Some("0xoperator".to_string()),
+            near: None,
+            solana: None,
+        };
+
+        let channel = Arc::new(RecordingChannel::default());
+        let sweeper = FundSweeper::new(vec![chain], operator_addresses, SweepConfig::default())
+            .with_notifier(Arc::new(OperatorNotifier::new(channel.clone())));
+
+        assert!(sweeper.sweep_once().await.is_empty());
+        assert!(sweeper.sweep_once().await.is_empty());
+
+        // The second failure falls within the alert interval
+        let sent = channel.sent.lock().unwrap();
+        assert_eq!(sent.len(), 1);
+        assert!(sent[0].contains("Base sweep failed"));
+    }
+
+    #[tokio::test]
+    async fn test_sweep_uses_chain_reserve_override() {
+        let chain: Arc<dyn ChainFacilitator> = Arc::new(
+            MockFacilitator::new(Chain::Solana, 20_000_000, true).with_sweep_reserve(0),
+        );
+
+        let operator_addresses = OperatorAddresses {
+            base: None,
+            near: None,
+            solana: Some("operator-solana".to_string()),
+        };
+
+        let sweeper = FundSweeper::new(vec![chain], operator_addresses, SweepConfig::default());
+
+        let records = sweeper.sweep_once().await;
+
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].amount, 20_000_000); // No USDC held back
+    }
+
+    #[tokio::test]
+    async fn test_status_tracks_last_run_and_balances() {
+        let chain: Arc<dyn ChainFacilitator> = Arc::new(MockFacilitator::new(
+            Chain::Base,
+            5_000_000, // below threshold, still observed
+            true,
+        ));
+
+        let operator_addresses = OperatorAddresses {
+            base: Some("0xoperator".to_string()),
+            near: None,
+            solana: None,
+        };
+
+        let sweeper = FundSweeper::new(vec![chain], operator_addresses, SweepConfig::default());
+
+        let status = sweeper.status().await;
+        assert!(status.last_run.is_none());
+        assert!(status.last_balances.is_empty());
+        assert_eq!(status.interval_secs, 24 * 60 * 60);
+
+        sweeper.sweep_once().await;
+
+        let status = sweeper.status().await;
+        assert!(status.last_run.is_some());
+        assert_eq!(status.last_balances.get(&Chain::Base), Some(&5_000_000));
+        assert_eq!(status.history_len, 0);
+    }
+
+    #[tokio::test]
+    async fn test_failed_sweep_retried_until_success() {
+        let chain: Arc<dyn ChainFacilitator> = Arc::new(
+            MockFacilitator::new(Chain::Base, 20_000_000, true).failing_times(2),
+        );
+
+        let operator_addresses = OperatorAddresses {
+            base: Some("0xoperator".to_string()),
+            near: None,
+            solana: None,
+        };
+
+        let config = SweepConfig {
+            retry_delay: Duration::from_secs(60),
+            max_retries: 3,
+            ..SweepConfig::default()
+        };
+        let sweeper = FundSweeper::new(vec![chain], operator_addresses, config);
+
+        assert!(sweeper.sweep_once().await.is_empty());
+        assert_eq!(sweeper.next_retry_delay().await, Some(Duration::from_secs(60)));
+
+        assert!(sweeper.retry_failed().await.is_empty());
+        // Backs off after the second failure
+        assert_eq!(sweeper.next_retry_delay().await, Some(Duration::from_secs(120)));
+        assert_eq!(sweeper.status().await.failed_attempts.get(&Chain::Base), Some(&2));
+
+        let records = sweeper.retry_failed().await;
+        assert_eq!(records.len(), 1);
+        assert!(records[0].success);
+        assert_eq!(sweeper.next_retry_delay().await, None);
+        assert!(sweeper.status().await.failed_attempts.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_failed_sweep_gives_up_after_max_retries() {
+        let chain: Arc<dyn ChainFacilitator> =
+            Arc::new(MockFacilitator::new(Chain::Base, 20_000_000, false));
+
+        let operator_addresses = OperatorAddresses {
+            base: Some("0xoperator".to_string()),
+            near: None,
+            solana: None,
+        };
+
+        let config = SweepConfig {
+            max_retries: 1,
+            ..SweepConfig::default()
+        };
+        let sweeper = FundSweeper::new(vec![chain], operator_addresses, config);
+
+        assert!(sweeper.sweep_once().await.is_empty());
+        assert!(sweeper.next_retry_delay().await.is_some());
+        assert!(sweeper.retry_failed().await.is_empty());
+        assert_eq!(sweeper.next_retry_delay().await, None);
+
+        // Nothing left to retry until the next scheduled cycle
+        assert!(sweeper.retry_failed().await.is_empty());
+        assert_eq!(sweeper.status().await.failed_attempts.get(&Chain::Base), Some(&2));
+        sweeper.sweep_once().await;
+        assert_eq!(sweeper.status().await.failed_attempts.get(&Chain::Base), Some(&1));
+    }
 }
@@ -2,6 +2,8 @@
 
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::Duration;
 
 /// Unique identifier for a user (phone number in E.164 format).
 pub type UserId = String;
@@ -28,6 +30,16 @@ impl std::fmt::Display for Chain {
     }
 }
 
+impl Chain {
+    /// Whether transfers on this chain carry a memo naming the depositing user.
+    ///
+    /// Without one, a transfer to the shared deposit address can only be
+    /// attributed by its exact amount.
+    pub fn supports_memo(&self) -> bool {
+        matches!(self, Chain::Near)
+    }
+}
+
 /// Credit balance for a user.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CreditBalance {
@@ -39,6 +51,12 @@ pub struct CreditBalance {
     pub total_deposited: u64,
     /// Total lifetime consumption in micro-USDC.
     pub total_consumed: u64,
+    /// Total credits other users transferred in, kept apart from deposits.
+    #[serde(default)]
+    pub total_received: u64,
+    /// Total credits forfeited because they went unused past the credit TTL.
+    #[serde(default)]
+    pub total_expired: u64,
     /// Timestamp of last deposit.
     pub last_deposit_at: Option<DateTime<Utc>>,
     /// Timestamp of last usage.
@@ -55,6 +73,8 @@ impl CreditBalance {
             credits_remaining: 0,
             total_deposited: 0,
             total_consumed: 0,
+            total_received: 0,
+            total_expired: 0,
             last_deposit_at: None,
             last_usage_at: None,
             created_at: Utc::now(),
@@ -68,6 +88,14 @@ impl CreditBalance {
         self.last_deposit_at = Some(Utc::now());
     }
 
+    /// Add credits transferred from another user. Like a deposit, this
+    /// restarts the expiry clock.
+    pub fn receive_credits(&mut self, amount: u64) {
+        self.credits_remaining = self.credits_remaining.saturating_add(amount);
+        self.total_received = self.total_received.saturating_add(amount);
+        self.last_deposit_at = Some(Utc::now());
+    }
+
     /// Deduct credits for usage. Returns true if successful.
     pub fn deduct_credits(&mut self, amount: u64) -> bool {
         if self.credits_remaining >= amount {
@@ -85,12 +113,61 @@ impl CreditBalance {
         self.credits_remaining >= amount
     }
 
+    /// Whether the remaining credits have outlived `ttl` since the last
+    /// deposit. Balances with no recorded deposit never expire.
+    pub fn is_expired(&self, ttl: Duration) -> bool {
+        self.credits_remaining > 0
+            && self.last_deposit_at.is_some_and(|at| {
+                chrono::Duration::from_std(ttl)
+                    .ok()
+                    .and_then(|ttl| at.checked_add_signed(ttl))
+                    .is_some_and(|expires_at| expires_at <= Utc::now())
+            })
+    }
+
+    /// Forfeit the remaining credits.
+    pub fn expire(&mut self) {
+        self.total_expired = self.total_expired.saturating_add(self.credits_remaining);
+        self.credits_remaining = 0;
+    }
+
     /// Convert credits to USDC (as f64 for display).
     pub fn credits_to_usdc(credits: u64) -> f64 {
         credits as f64 / 1_000_000.0
     }
 }
 
+/// The ledger totals of a balance.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BalanceTotals {
+    pub credits_remaining: u64,
+    pub total_deposited: u64,
+    pub total_consumed: u64,
+    #[serde(default)]
+    pub total_received: u64,
+}
+
+impl From<&CreditBalance> for BalanceTotals {
+    fn from(balance: &CreditBalance) -> Self {
+        Self {
+            credits_remaining: balance.credits_remaining,
+            total_deposited: balance.total_deposited,
+            total_consumed: balance.total_consumed,
+            total_received: balance.total_received,
+        }
+    }
+}
+
+/// A user whose stored balance disagrees with the deposit and usage logs.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BalanceDiscrepancy {
+    pub user_id: UserId,
+    /// Totals currently in the balance map.
+    pub stored: BalanceTotals,
+    /// Totals recomputed from the logs.
+    pub expected: BalanceTotals,
+}
+
 /// Status of a deposit.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
@@ -118,6 +195,9 @@ pub struct Deposit {
     pub amount_usdc: u64,
     /// Credits granted for this deposit.
     pub credits_granted: u64,
+    /// Symbol of the token deposited.
+    #[serde(default = "default_deposit_token")]
+    pub token: String,
     /// Current status.
     pub status: DepositStatus,
     /// When the deposit was initiated.
@@ -142,12 +222,19 @@ impl Deposit {
             tx_hash,
             amount_usdc,
             credits_granted,
+            token: default_deposit_token(),
             status: DepositStatus::Pending,
             created_at: Utc::now(),
             confirmed_at: None,
         }
     }
 
+    /// Record the token deposited (USDC unless set).
+    pub fn with_token(mut self, token: impl Into<String>) -> Self {
+        self.token = token.into();
+        self
+    }
+
     /// Mark the deposit as confirmed.
     pub fn confirm(&mut self) {
         self.status = DepositStatus::Confirmed;
@@ -160,6 +247,67 @@ impl Deposit {
     }
 }
 
+fn default_deposit_token() -> String {
+    "USDC".to_string()
+}
+
+/// The deposit an idempotency key was first used for.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IdempotentDeposit {
+    /// ID of the deposit credited for the key.
+    pub deposit_id: String,
+    /// The user's balance right after it was credited.
+    pub new_balance: u64,
+    /// When the key was first used. Keys stored before this was recorded
+    /// count from when they were loaded.
+    #[serde(default = "Utc::now")]
+    pub created_at: DateTime<Utc>,
+}
+
+/// A deposit amount reserved for one user.
+///
+/// Deposit addresses are shared, so on chains without memos a transfer of
+/// exactly this amount is how the deposit is tied back to `user_id`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DepositIntent {
+    pub user_id: UserId,
+    pub chain: Chain,
+    /// Exact amount to send in micro-USDC.
+    pub amount_usdc: u64,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+}
+
+impl DepositIntent {
+    /// Whether the reservation has lapsed.
+    pub fn is_expired(&self) -> bool {
+        Utc::now() >= self.expires_at
+    }
+}
+
+/// A sender address a user has claimed as their own.
+///
+/// With `require_linked_sender`, memo-less deposits are credited to the user
+/// who linked the address they were sent from.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LinkedAddress {
+    pub user_id: UserId,
+    pub chain: Chain,
+    /// Normalized sender address (lowercase on Base).
+    pub address: String,
+    pub linked_at: DateTime<Utc>,
+}
+
+impl LinkedAddress {
+    /// Normalize `address` the way the chain's verifier reports senders.
+    pub fn normalize(chain: Chain, address: &str) -> String {
+        match chain {
+            Chain::Base => address.trim().to_lowercase(),
+            Chain::Near | Chain::Solana => address.trim().to_string(),
+        }
+    }
+}
+
 /// Usage record for auditing and metering.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct UsageRecord {
@@ -177,6 +325,16 @@ pub struct UsageRecord {
     pub credits_consumed: u64,
     /// When this usage occurred.
     pub timestamp: DateTime<Utc>,
+    /// Timestamp of the Signal message that triggered this usage, if any.
+    #[serde(default)]
+    pub message_timestamp: Option<i64>,
+    /// Why an operator clawed these credits back; `None` for normal usage.
+    #[serde(default)]
+    pub refund_reason: Option<String>,
+    /// Recipient of these credits when an operator moved them to another
+    /// user; `None` for normal usage.
+    #[serde(default)]
+    pub transfer_to: Option<UserId>,
 }
 
 impl UsageRecord {
@@ -196,8 +354,44 @@ impl UsageRecord {
             total_tokens: prompt_tokens + completion_tokens,
             credits_consumed,
             timestamp: Utc::now(),
+            message_timestamp: None,
+            refund_reason: None,
+            transfer_to: None,
         }
     }
+
+    /// Record an operator reversing `credits` granted to `user_id`.
+    pub fn refund(user_id: UserId, credits: u64, reason: impl Into<String>) -> Self {
+        Self {
+            refund_reason: Some(reason.into()),
+            ..Self::new(user_id, "refund".to_string(), 0, 0, credits)
+        }
+    }
+
+    /// Whether this record is an operator refund rather than usage.
+    pub fn is_refund(&self) -> bool {
+        self.refund_reason.is_some()
+    }
+
+    /// Record `credits` moved from `user_id` to `to`. The recipient's side
+    /// is credited from this same record when balances are reconciled.
+    pub fn transfer(user_id: UserId, to: UserId, credits: u64) -> Self {
+        Self {
+            transfer_to: Some(to),
+            ..Self::new(user_id, "transfer".to_string(), 0, 0, credits)
+        }
+    }
+
+    /// Whether this record is a transfer to another user rather than usage.
+    pub fn is_transfer(&self) -> bool {
+        self.transfer_to.is_some()
+    }
+
+    /// Link this usage to the Signal message that triggered it.
+    pub fn with_message_timestamp(mut self, message_timestamp: i64) -> Self {
+        self.message_timestamp = Some(message_timestamp);
+        self
+    }
 }
 
 /// Transaction status for on-chain monitoring.
@@ -258,7 +452,10 @@ pub struct SweepRecord {
     pub from: String,
     /// Operator address (destination).
     pub to: String,
-    /// Amount swept in micro-USDC.
+    /// Symbol of the token swept.
+    #[serde(default = "default_deposit_token")]
+    pub token: String,
+    /// Amount swept in micro-units of `token`.
     pub amount: u64,
     /// Transaction hash.
     pub tx_hash: String,
@@ -267,3 +464,23 @@ pub struct SweepRecord {
     /// When the sweep occurred.
     pub timestamp: DateTime<Utc>,
 }
+
+/// Snapshot of the fund sweeper's schedule and last observations.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SweepStatus {
+    /// When the last sweep cycle finished.
+    pub last_run: Option<DateTime<Utc>>,
+    /// When the next scheduled sweep will start.
+    pub next_run: Option<DateTime<Utc>>,
+    /// Interval between scheduled sweeps, in seconds.
+    pub interval_secs: u64,
+    /// Deposit wallet balance (micro-units) seen on the last check, summed
+    /// over each chain's wallets and accepted tokens.
+    pub last_balances: HashMap<Chain, u64>,
+    /// Consecutive failed sweeps per chain (its worst wallet); absent once
+    /// every wallet's sweep succeeds.
+    #[serde(default)]
+    pub failed_attempts: HashMap<Chain, u32>,
+    /// Number of sweep records kept in history.
+    pub history_len: usize,
+}
@@ -0,0 +1,137 @@
+//! Deposit webhook.
+//!
+//! Pushes each credited deposit to an operator-configured URL (a Slack
+//! relay, a dashboard) so external systems don't have to poll. Delivery is
+//! best-effort: a few short attempts, then the failure is logged. A deposit
+//! is never failed or delayed because its notification couldn't be sent.
+
+use crate::types::{Chain, UserId};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tracing::{debug, warn};
+
+/// Timeout for each delivery attempt.
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Attempts made before a notification is given up on.
+const WEBHOOK_ATTEMPTS: u32 = 3;
+
+/// Delay before the first retry, doubled for each one after.
+const WEBHOOK_RETRY_DELAY: Duration = Duration::from_millis(500);
+
+/// Body POSTed for a credited deposit.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DepositEvent {
+    pub user_id: UserId,
+    pub chain: Chain,
+    pub tx_hash: String,
+    pub credits_granted: u64,
+    /// The user's balance right after the deposit.
+    pub new_balance: u64,
+}
+
+/// Delivers [`DepositEvent`]s to a URL.
+pub struct DepositWebhook {
+    client: reqwest::Client,
+    url: String,
+    retry_delay: Duration,
+}
+
+impl DepositWebhook {
+    /// Create a webhook posting to `url`.
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            client: reqwest::Client::builder()
+                .timeout(WEBHOOK_TIMEOUT)
+                .build()
+                .unwrap_or_default(),
+            url: url.into(),
+            retry_delay: WEBHOOK_RETRY_DELAY,
+        }
+    }
+
+    /// Set the delay before the first retry.
+    pub fn with_retry_delay(mut self, delay: Duration) -> Self {
+        self.retry_delay = delay;
+        self
+    }
+
+    /// POST `event`, retrying failed attempts. Returns whether it was
+    /// accepted (2xx); failures are logged.
+    pub async fn send(&self, event: &DepositEvent) -> bool {
+        let mut delay = self.retry_delay;
+        for attempt in 1..=WEBHOOK_ATTEMPTS {
+            match self.client.post(&self.url).json(event).send().await {
+                Ok(response) if response.status().is_success() => {
+                    debug!("Deposit webhook delivered for {}", event.tx_hash);
+                    return true;
+                }
+                Ok(response) => warn!(
+                    "Deposit webhook for {} got {} (attempt {}/{})",
+                    event.tx_hash,
+                    response.status(),
+                    attempt,
+                    WEBHOOK_ATTEMPTS
+                ),
+                Err(e) => warn!(
+                    "Deposit webhook for {} failed (attempt {}/{}): {}",
+                    event.tx_hash, attempt, WEBHOOK_ATTEMPTS, e
+                ),
+            }
+            if attempt < WEBHOOK_ATTEMPTS {
+                tokio::time::sleep(delay).await;
+                delay *= 2;
+            }
+        }
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::method;
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn event() -> DepositEvent {
+        DepositEvent {
+            user_id: "+14155551234".to_string(),
+            chain: Chain::Base,
+            tx_hash: "0xabc".to_string(),
+            credits_granted: 2_000_000,
+            new_balance: 2_000_000,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_retries_until_accepted() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(503))
+            .up_to_n_times(2)
+            .expect(2)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let webhook = DepositWebhook::new(server.uri()).with_retry_delay(Duration::ZERO);
+        assert!(webhook.send(&event()).await);
+    }
+
+    #[tokio::test]
+    async fn test_gives_up_after_attempts() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(500))
+            .expect(u64::from(WEBHOOK_ATTEMPTS))
+            .mount(&server)
+            .await;
+
+        let webhook = DepositWebhook::new(server.uri()).with_retry_delay(Duration::ZERO);
+        assert!(!webhook.send(&event()).await);
+    }
+}